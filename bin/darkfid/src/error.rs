@@ -34,11 +34,17 @@ pub enum RpcError {
     // Transaction-related errors
     TxSimulationFail = -32110,
     TxBroadcastFail = -32111,
+    TxComposeFail = -32112,
 
     // State-related errors,
     NotSynced = -32120,
     UnknownSlot = -32121,
 
+    // Wallet-related errors
+    WalletTableNotAllowed = -32130,
+    WalletRawSqlDisabled = -32131,
+    WalletQueryFailed = -32132,
+
     // Parsing errors
     ParseError = -32190,
 
@@ -61,9 +67,14 @@ fn to_tuple(e: RpcError) -> (i32, String) {
         // Transaction-related errors
         RpcError::TxSimulationFail => "Failed simulating transaction state change",
         RpcError::TxBroadcastFail => "Failed broadcasting transaction",
+        RpcError::TxComposeFail => "Failed composing transaction",
         // State-related errors
         RpcError::NotSynced => "Blockchain is not synced",
         RpcError::UnknownSlot => "Did not find slot",
+        // Wallet-related errors
+        RpcError::WalletTableNotAllowed => "Wallet table is not accessible",
+        RpcError::WalletRawSqlDisabled => "Raw SQL wallet methods are disabled",
+        RpcError::WalletQueryFailed => "Wallet query failed",
         // Parsing errors
         RpcError::ParseError => "Parse error",
         // Contract-related errors
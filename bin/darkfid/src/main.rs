@@ -16,7 +16,7 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::{path::Path, str::FromStr, sync::Arc};
+use std::{path::Path, str::FromStr, sync::Arc, time::Duration};
 
 use async_trait::async_trait;
 use darkfi_sdk::crypto::PublicKey;
@@ -29,9 +29,10 @@ use darkfi::{
     async_daemonize, cli_desc,
     consensus::{
         constants::{
-            MAINNET_BOOTSTRAP_TIMESTAMP, MAINNET_GENESIS_HASH_BYTES, MAINNET_GENESIS_TIMESTAMP,
-            MAINNET_INITIAL_DISTRIBUTION, TESTNET_BOOTSTRAP_TIMESTAMP, TESTNET_GENESIS_HASH_BYTES,
-            TESTNET_GENESIS_TIMESTAMP, TESTNET_INITIAL_DISTRIBUTION,
+            MAINNET_BOOTSTRAP_TIMESTAMP, MAINNET_CHECKPOINTS, MAINNET_GENESIS_HASH_BYTES,
+            MAINNET_GENESIS_TIMESTAMP, MAINNET_INITIAL_DISTRIBUTION, TESTNET_BOOTSTRAP_TIMESTAMP,
+            TESTNET_CHECKPOINTS, TESTNET_GENESIS_HASH_BYTES, TESTNET_GENESIS_TIMESTAMP,
+            TESTNET_INITIAL_DISTRIBUTION,
         },
         proto::{ProtocolProposal, ProtocolSync, ProtocolSyncConsensus, ProtocolTx},
         task::{block_sync_task, proposal_task},
@@ -169,6 +170,14 @@ struct Args {
     /// Verify system clock is correct
     clock_sync: bool,
 
+    #[structopt(long)]
+    /// Allow the deprecated raw SQL wallet methods, for trusted callers only
+    wallet_raw_sql: bool,
+
+    #[structopt(long, default_value = "200")]
+    /// Duration in milliseconds above which wallet SQL and sled operations are logged as slow
+    slow_op_threshold: u64,
+
     #[structopt(short, long)]
     /// Set log file to ouput into
     log: Option<String>,
@@ -182,12 +191,15 @@ pub struct Darkfid {
     synced: Mutex<bool>, // AtomicBool is weird in Arc
     consensus_p2p: Option<P2pPtr>,
     sync_p2p: Option<P2pPtr>,
-    _wallet: WalletPtr,
+    wallet: WalletPtr,
+    /// Whether the deprecated raw SQL wallet methods are allowed
+    wallet_raw_sql: bool,
     validator_state: ValidatorStatePtr,
 }
 
 // JSON-RPC methods
 mod rpc_blockchain;
+mod rpc_compose;
 mod rpc_misc;
 mod rpc_tx;
 mod rpc_wallet;
@@ -208,6 +220,7 @@ impl RequestHandler for Darkfid {
             "consensus_dnet_switch" => {
                 return self.misc_consensus_dnet_switch(req.id, req.params).await
             }
+            "slow_ops" => return self.misc_slow_ops(req.id, req.params).await,
 
             // ==================
             // Blockchain methods
@@ -226,12 +239,16 @@ impl RequestHandler for Darkfid {
             "blockchain.lookup_zkas" => {
                 return self.blockchain_lookup_zkas(req.id, req.params).await
             }
+            "blockchain.latest_coin_root" => {
+                return self.blockchain_latest_coin_root(req.id, req.params).await
+            }
 
             // ===================
             // Transaction methods
             // ===================
             "tx.simulate" => return self.tx_simulate(req.id, req.params).await,
             "tx.broadcast" => return self.tx_broadcast(req.id, req.params).await,
+            "tx.compose" => return self.tx_compose(req.id, req.params).await,
 
             // ==============
             // Wallet methods
@@ -243,6 +260,15 @@ impl RequestHandler for Darkfid {
             "wallet.query_row_multi" => {
                 return self.wallet_query_row_multi(req.id, req.params).await
             }
+            "wallet.insert" => return self.wallet_insert(req.id, req.params).await,
+            "wallet.select" => return self.wallet_select(req.id, req.params).await,
+            "wallet.recover" => return self.wallet_recover(req.id, req.params).await,
+            "wallet.export_view_keys" => {
+                return self.wallet_export_view_keys(req.id, req.params).await
+            }
+            "wallet.import_view_keys" => {
+                return self.wallet_import_view_keys(req.id, req.params).await
+            }
 
             // ==============
             // Invalid method
@@ -257,9 +283,17 @@ impl Darkfid {
         validator_state: ValidatorStatePtr,
         consensus_p2p: Option<P2pPtr>,
         sync_p2p: Option<P2pPtr>,
-        _wallet: WalletPtr,
+        wallet: WalletPtr,
+        wallet_raw_sql: bool,
     ) -> Self {
-        Self { synced: Mutex::new(false), consensus_p2p, sync_p2p, _wallet, validator_state }
+        Self {
+            synced: Mutex::new(false),
+            consensus_p2p,
+            sync_p2p,
+            wallet,
+            wallet_raw_sql,
+            validator_state,
+        }
     }
 }
 
@@ -287,6 +321,8 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
 
     // Initialize or load wallet
     let wallet = WalletDb::new(Some(expand_path(&args.wallet_path)?), Some(&args.wallet_pass))?;
+    let slow_op_threshold = Duration::from_millis(args.slow_op_threshold);
+    wallet.slow_log.set_threshold(slow_op_threshold);
 
     // Initialize or open sled database
     let db_path =
@@ -294,24 +330,27 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
     let sled_db = sled::open(&db_path)?;
 
     // Initialize validator state
-    let (bootstrap_ts, genesis_ts, genesis_data, initial_distribution) = match args.chain.as_str() {
-        "mainnet" => (
-            *MAINNET_BOOTSTRAP_TIMESTAMP,
-            *MAINNET_GENESIS_TIMESTAMP,
-            *MAINNET_GENESIS_HASH_BYTES,
-            *MAINNET_INITIAL_DISTRIBUTION,
-        ),
-        "testnet" => (
-            *TESTNET_BOOTSTRAP_TIMESTAMP,
-            *TESTNET_GENESIS_TIMESTAMP,
-            *TESTNET_GENESIS_HASH_BYTES,
-            *TESTNET_INITIAL_DISTRIBUTION,
-        ),
-        x => {
-            error!("Unsupported chain `{}`", x);
-            return Err(Error::UnsupportedChain)
-        }
-    };
+    let (bootstrap_ts, genesis_ts, genesis_data, initial_distribution, checkpoints) =
+        match args.chain.as_str() {
+            "mainnet" => (
+                *MAINNET_BOOTSTRAP_TIMESTAMP,
+                *MAINNET_GENESIS_TIMESTAMP,
+                *MAINNET_GENESIS_HASH_BYTES,
+                *MAINNET_INITIAL_DISTRIBUTION,
+                &*MAINNET_CHECKPOINTS,
+            ),
+            "testnet" => (
+                *TESTNET_BOOTSTRAP_TIMESTAMP,
+                *TESTNET_GENESIS_TIMESTAMP,
+                *TESTNET_GENESIS_HASH_BYTES,
+                *TESTNET_INITIAL_DISTRIBUTION,
+                &*TESTNET_CHECKPOINTS,
+            ),
+            x => {
+                error!("Unsupported chain `{}`", x);
+                return Err(Error::UnsupportedChain)
+            }
+        };
     // Parse faucet addresses
     let mut faucet_pubkeys = vec![];
 
@@ -342,6 +381,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
         args.single_node,
     )
     .await?;
+    state.read().await.blockchain.slow_log.set_threshold(slow_op_threshold);
 
     let sync_p2p = {
         info!("Registering block sync P2P protocols...");
@@ -422,8 +462,14 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
     };
 
     // Initialize program state
-    let darkfid =
-        Darkfid::new(state.clone(), consensus_p2p.clone(), sync_p2p.clone(), wallet.clone()).await;
+    let darkfid = Darkfid::new(
+        state.clone(),
+        consensus_p2p.clone(),
+        sync_p2p.clone(),
+        wallet.clone(),
+        args.wallet_raw_sql,
+    )
+    .await;
     let darkfid = Arc::new(darkfid);
 
     // JSON-RPC server
@@ -459,7 +505,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
     //info!("Waiting for sync P2P outbound connections");
     //sync_p2p.clone().unwrap().wait_for_outbound(ex.clone()).await?;
 
-    match block_sync_task(sync_p2p.clone().unwrap(), state.clone()).await {
+    match block_sync_task(sync_p2p.clone().unwrap(), state.clone(), checkpoints).await {
         Ok(()) => *darkfid.synced.lock().await = true,
         Err(e) => error!("Failed syncing blockchain: {}", e),
     }
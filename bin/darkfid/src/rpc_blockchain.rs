@@ -18,7 +18,8 @@
 
 use std::str::FromStr;
 
-use darkfi_sdk::crypto::ContractId;
+use darkfi_money_contract::{MONEY_CONTRACT_INFO_TREE, MONEY_CONTRACT_LATEST_COIN_ROOT};
+use darkfi_sdk::crypto::{ContractId, MONEY_CONTRACT_ID};
 use darkfi_serial::{deserialize, serialize};
 use log::{debug, error};
 use tinyjson::JsonValue;
@@ -250,4 +251,46 @@ impl Darkfid {
 
         JsonResponse::new(JsonValue::Array(ret), id).into()
     }
+
+    // RPCAPI:
+    // Returns the latest root of the Money contract's Merkle tree of coins.
+    // Clients compare it against their own tree's root, to tell whether the
+    // inclusion proofs they create are against the newest state.
+    //
+    // **Returns:**
+    // * Serialized and base58-encoded `MerkleNode`
+    //
+    // --> {"jsonrpc": "2.0", "method": "blockchain.latest_coin_root", "params": [], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": "ABCD...", "id": 1}
+    pub async fn blockchain_latest_coin_root(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if !params.is_empty() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let blockchain = { self.validator_state.read().await.blockchain.clone() };
+
+        let Ok(info_db) = blockchain.contracts.lookup(
+            &blockchain.sled_db,
+            &MONEY_CONTRACT_ID,
+            MONEY_CONTRACT_INFO_TREE,
+        ) else {
+            error!("[RPC] blockchain.latest_coin_root: Did not find Money info db");
+            return JsonError::new(InternalError, None, id).into()
+        };
+
+        let root = match info_db.get(serialize(&MONEY_CONTRACT_LATEST_COIN_ROOT)) {
+            Ok(Some(v)) => v,
+            Ok(None) => {
+                error!("[RPC] blockchain.latest_coin_root: Latest coin root not found");
+                return JsonError::new(InternalError, None, id).into()
+            }
+            Err(e) => {
+                error!("[RPC] blockchain.latest_coin_root: Failed reading info db: {}", e);
+                return JsonError::new(InternalError, None, id).into()
+            }
+        };
+
+        JsonResponse::new(JsonValue::String(bs58::encode(&root).into_string()), id).into()
+    }
 }
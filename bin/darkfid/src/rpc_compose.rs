@@ -0,0 +1,519 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{collections::HashMap, str::FromStr};
+
+use darkfi::{
+    rpc::jsonrpc::{ErrorCode::InvalidParams, JsonError, JsonResponse, JsonResult},
+    tx::Transaction,
+    util::encoding::base64,
+    wallet::walletdb::SqlType,
+    zk::{empty_witnesses, ProvingKey, ZkCircuit},
+    zkas::ZkBinary,
+    Error, Result,
+};
+use darkfi_money_contract::{
+    client::{
+        amount::{parse_amount, DEFAULT_DECIMALS},
+        token_freeze_v1::TokenFreezeCallBuilder,
+        token_mint_v1::TokenMintCallBuilder,
+        transfer_v1::TransferCallBuilder,
+        tx_builder::TransactionBuilder,
+        MoneyNote, OwnCoin, MONEY_COINS_COL_COIN, MONEY_COINS_COL_IS_SPENT,
+        MONEY_COINS_COL_LEAF_POSITION, MONEY_COINS_COL_MEMO, MONEY_COINS_COL_NULLIFIER,
+        MONEY_COINS_COL_SECRET, MONEY_COINS_COL_SERIAL, MONEY_COINS_COL_SPEND_HOOK,
+        MONEY_COINS_COL_TOKEN_BLIND, MONEY_COINS_COL_TOKEN_ID, MONEY_COINS_COL_USER_DATA,
+        MONEY_COINS_COL_VALUE, MONEY_COINS_COL_VALUE_BLIND, MONEY_COINS_TABLE,
+        MONEY_KEYS_COL_IS_DEFAULT, MONEY_KEYS_COL_KEY_ID, MONEY_KEYS_COL_SECRET, MONEY_KEYS_TABLE,
+        MONEY_TOKENS_COL_IS_FROZEN, MONEY_TOKENS_COL_MINT_AUTHORITY, MONEY_TOKENS_COL_TOKEN_ID,
+        MONEY_TOKENS_TABLE, MONEY_TREE_COL_TREE, MONEY_TREE_TABLE,
+    },
+    MoneyFunction, MONEY_CONTRACT_ZKAS_BURN_NS_V1, MONEY_CONTRACT_ZKAS_MINT_NS_V1,
+    MONEY_CONTRACT_ZKAS_TOKEN_FRZ_NS_V1, MONEY_CONTRACT_ZKAS_TOKEN_MINT_NS_V1,
+};
+use darkfi_sdk::{
+    crypto::{
+        pasta_prelude::Field, ContractId, Keypair, MerkleTree, PublicKey, SecretKey, TokenId,
+        MONEY_CONTRACT_ID,
+    },
+    pasta::pallas,
+    ContractCall,
+};
+use darkfi_serial::{deserialize, serialize, Decodable, Encodable};
+use log::{debug, error, info};
+use rand::rngs::OsRng;
+use tinyjson::JsonValue;
+
+use super::Darkfid;
+use crate::{server_error, RpcError};
+
+/// Prefix of key references, naming a key held in the wallet instead of
+/// giving it directly, e.g. `wallet:default` or `wallet:<key_id>`.
+const WALLET_KEY_PREFIX: &str = "wallet:";
+
+/// A contract call requested in a `tx.compose` description, with its key
+/// references resolved against the wallet.
+enum ComposeCall {
+    /// `Money::TransferV1` paying `amount` of `token_id` to `recipient`,
+    /// returning any change to `change`
+    Transfer { recipient: PublicKey, amount: u64, token_id: TokenId, change: Keypair },
+    /// `Money::TokenMintV1` minting `amount` of the authority's token
+    TokenMint {
+        mint_authority: Keypair,
+        recipient: PublicKey,
+        amount: u64,
+        max_supply: Option<u64>,
+    },
+    /// `Money::TokenFreezeV1` revoking the authority's mint
+    TokenFreeze { mint_authority: Keypair },
+}
+
+/// Everything needed to assemble a composed transaction, gathered from the
+/// wallet and the blockchain before proving.
+struct ComposeInputs {
+    calls: Vec<ComposeCall>,
+    coins: Vec<OwnCoin>,
+    tree: MerkleTree,
+    zkas: HashMap<&'static str, ZkBinary>,
+}
+
+/// Read a field of a call description
+fn field<'a>(obj: &'a HashMap<String, JsonValue>, name: &str) -> Result<&'a JsonValue> {
+    obj.get(name).ok_or_else(|| Error::Custom(format!("Missing field \"{}\"", name)))
+}
+
+/// Read a string field of a call description
+fn str_field<'a>(obj: &'a HashMap<String, JsonValue>, name: &str) -> Result<&'a str> {
+    match field(obj, name)?.get::<String>() {
+        Some(v) => Ok(v.as_str()),
+        None => Err(Error::Custom(format!("Field \"{}\" is not a string", name))),
+    }
+}
+
+/// Read an optional string field of a call description
+fn opt_str_field<'a>(obj: &'a HashMap<String, JsonValue>, name: &str) -> Result<Option<&'a str>> {
+    match obj.get(name) {
+        None | Some(JsonValue::Null) => Ok(None),
+        Some(_) => Ok(Some(str_field(obj, name)?)),
+    }
+}
+
+/// Read a nonzero decimal amount field of a call description
+fn amount_field(obj: &HashMap<String, JsonValue>, name: &str) -> Result<u64> {
+    match parse_amount(str_field(obj, name)?, DEFAULT_DECIMALS)? {
+        0 => Err(Error::Custom(format!("Field \"{}\" is zero", name))),
+        v => Ok(v),
+    }
+}
+
+/// Decode a serialized wallet blob
+fn blob<T: Decodable>(value: &SqlType) -> Result<T> {
+    let SqlType::Blob(bytes) = value else {
+        return Err(Error::Custom("Unexpected wallet column type".to_string()))
+    };
+
+    Ok(deserialize(bytes)?)
+}
+
+impl Darkfid {
+    // RPCAPI:
+    // Assemble, prove and sign a transaction from a JSON description of its
+    // calls, using the keys, coins and mint authorities held in the wallet.
+    // Calls are executed in the given order, and a coin is never spent by
+    // more than one of them. Amounts are decimal strings. Wherever a key is
+    // expected, a key reference `wallet:default` or `wallet:<key_id>` can be
+    // given to use a key of the wallet instead.
+    //
+    // Supported calls of the Money contract, with their params:
+    // * `TransferV1`: `recipient`, `amount`, `token`, and optionally `change`,
+    //   the key receiving the change (defaults to `wallet:default`)
+    // * `TokenMintV1`: `token`, `amount`, `recipient`, and optionally `max_supply`
+    // * `TokenFreezeV1`: `token`
+    //
+    // Returns the base64-encoded transaction, ready for `tx.simulate` and
+    // `tx.broadcast`. The wallet is left untouched, so spent coins are only
+    // marked once the transaction is seen on-chain.
+    //
+    // --> {"jsonrpc": "2.0", "method": "tx.compose", "params": [{"calls": [{"contract": "BZHKGQ26bzmBithTQYTJtjo2QdCqpkR9tjSBopT4yf4o", "function": "TransferV1", "params": {"recipient": "wallet:2", "amount": "4.2", "token": "241vANigf1Cy3ytjM1KHXiVECxgxdK4yApddL8KcLssb"}}]}], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": "base64encodedTX", "id": 1}
+    pub async fn tx_compose(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 1 {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let Some(calls) = params[0]
+            .get::<HashMap<String, JsonValue>>()
+            .and_then(|x| x.get("calls"))
+            .and_then(|x| x.get::<Vec<JsonValue>>())
+        else {
+            return JsonError::new(InvalidParams, None, id).into()
+        };
+
+        if calls.is_empty() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let inputs = match self.compose_inputs(calls).await {
+            Ok(v) => v,
+            Err(e) => {
+                error!("[RPC] tx.compose: Failed resolving transaction description: {}", e);
+                return server_error(RpcError::TxComposeFail, id, Some(&e.to_string()))
+            }
+        };
+
+        // Proving is expensive, so keep it off the RPC executor
+        let tx = match smol::unblock(move || compose_tx(inputs)).await {
+            Ok(v) => v,
+            Err(e) => {
+                error!("[RPC] tx.compose: Failed building transaction: {}", e);
+                return server_error(RpcError::TxComposeFail, id, Some(&e.to_string()))
+            }
+        };
+
+        info!("[RPC] tx.compose: Composed transaction {} with {} calls", tx.hash(), tx.calls.len());
+        JsonResponse::new(JsonValue::String(base64::encode(&serialize(&tx))), id).into()
+    }
+
+    /// Resolve the described calls against the wallet, and gather the coins,
+    /// Merkle tree and zkas circuits needed to build them.
+    async fn compose_inputs(&self, calls: &[JsonValue]) -> Result<ComposeInputs> {
+        let mut resolved = Vec::with_capacity(calls.len());
+        for (i, call) in calls.iter().enumerate() {
+            let call = self
+                .resolve_call(call)
+                .await
+                .map_err(|e| Error::Custom(format!("Call {}: {}", i, e)))?;
+            resolved.push(call);
+        }
+
+        let mut namespaces = vec![];
+        for call in &resolved {
+            match call {
+                ComposeCall::Transfer { .. } => {
+                    namespaces.push(MONEY_CONTRACT_ZKAS_MINT_NS_V1);
+                    namespaces.push(MONEY_CONTRACT_ZKAS_BURN_NS_V1);
+                }
+                ComposeCall::TokenMint { .. } => {
+                    namespaces.push(MONEY_CONTRACT_ZKAS_TOKEN_MINT_NS_V1)
+                }
+                ComposeCall::TokenFreeze { .. } => {
+                    namespaces.push(MONEY_CONTRACT_ZKAS_TOKEN_FRZ_NS_V1)
+                }
+            }
+        }
+
+        let blockchain = { self.validator_state.read().await.blockchain.clone() };
+        let mut zkas = HashMap::new();
+        for ns in namespaces {
+            if !zkas.contains_key(ns) {
+                let (zkbin, _) =
+                    blockchain.contracts.get_zkas(&blockchain.sled_db, &MONEY_CONTRACT_ID, ns)?;
+                zkas.insert(ns, zkbin);
+            }
+        }
+
+        // Coins and the Merkle tree are only needed to spend
+        let (coins, tree) = if resolved.iter().any(|c| matches!(c, ComposeCall::Transfer { .. })) {
+            (self.wallet_coins().await?, self.wallet_money_tree().await?)
+        } else {
+            (vec![], MerkleTree::new(100))
+        };
+
+        Ok(ComposeInputs { calls: resolved, coins, tree, zkas })
+    }
+
+    /// Parse a call description, resolving its key references
+    async fn resolve_call(&self, call: &JsonValue) -> Result<ComposeCall> {
+        let Some(call) = call.get::<HashMap<String, JsonValue>>() else {
+            return Err(Error::Custom("Call description is not an object".to_string()))
+        };
+
+        let contract_id = ContractId::from_str(str_field(call, "contract")?)?;
+        if contract_id != *MONEY_CONTRACT_ID {
+            return Err(Error::Custom(format!("Unsupported contract {}", contract_id)))
+        }
+
+        let Some(params) = field(call, "params")?.get::<HashMap<String, JsonValue>>() else {
+            return Err(Error::Custom("Field \"params\" is not an object".to_string()))
+        };
+
+        let function = str_field(call, "function")?;
+        debug!("[RPC] tx.compose: Resolving Money::{}", function);
+        match function {
+            "TransferV1" => {
+                let change = opt_str_field(params, "change")?.unwrap_or("wallet:default");
+                Ok(ComposeCall::Transfer {
+                    recipient: self.resolve_public(str_field(params, "recipient")?).await?,
+                    amount: amount_field(params, "amount")?,
+                    token_id: TokenId::from_str(str_field(params, "token")?)?,
+                    change: Keypair::new(self.resolve_secret(change).await?),
+                })
+            }
+
+            "TokenMintV1" => {
+                let token_id = TokenId::from_str(str_field(params, "token")?)?;
+                let max_supply = match params.get("max_supply") {
+                    None | Some(JsonValue::Null) => None,
+                    Some(_) => Some(amount_field(params, "max_supply")?),
+                };
+
+                Ok(ComposeCall::TokenMint {
+                    mint_authority: self.wallet_mint_authority(&token_id).await?,
+                    recipient: self.resolve_public(str_field(params, "recipient")?).await?,
+                    amount: amount_field(params, "amount")?,
+                    max_supply,
+                })
+            }
+
+            "TokenFreezeV1" => {
+                let token_id = TokenId::from_str(str_field(params, "token")?)?;
+                Ok(ComposeCall::TokenFreeze {
+                    mint_authority: self.wallet_mint_authority(&token_id).await?,
+                })
+            }
+
+            _ => Err(Error::Custom(format!("Unsupported function Money::{}", function))),
+        }
+    }
+
+    /// Resolve a public key, or a key reference to a wallet key
+    async fn resolve_public(&self, key: &str) -> Result<PublicKey> {
+        if key.starts_with(WALLET_KEY_PREFIX) {
+            return Ok(PublicKey::from_secret(self.resolve_secret(key).await?))
+        }
+
+        Ok(PublicKey::from_str(key)?)
+    }
+
+    /// Resolve a key reference to the secret key it names in the wallet
+    async fn resolve_secret(&self, key: &str) -> Result<SecretKey> {
+        let Some(reference) = key.strip_prefix(WALLET_KEY_PREFIX) else {
+            return Err(Error::Custom(format!("Invalid key reference \"{}\"", key)))
+        };
+
+        let where_query = match reference {
+            "default" => (MONEY_KEYS_COL_IS_DEFAULT, SqlType::Integer(1)),
+            _ => match reference.parse::<i64>() {
+                Ok(key_id) => (MONEY_KEYS_COL_KEY_ID, SqlType::Integer(key_id)),
+                Err(_) => return Err(Error::Custom(format!("Invalid key reference \"{}\"", key))),
+            },
+        };
+
+        let row = self
+            .wallet
+            .query_single(MONEY_KEYS_TABLE, vec![MONEY_KEYS_COL_SECRET], Some(vec![where_query]))
+            .await?;
+
+        match row.first() {
+            Some(secret) => blob(secret),
+            None => Err(Error::Custom(format!("Key \"{}\" not found in wallet", key))),
+        }
+    }
+
+    /// Fetch the wallet's mint authority for the given token
+    async fn wallet_mint_authority(&self, token_id: &TokenId) -> Result<Keypair> {
+        let row = self
+            .wallet
+            .query_single(
+                MONEY_TOKENS_TABLE,
+                vec![MONEY_TOKENS_COL_MINT_AUTHORITY, MONEY_TOKENS_COL_IS_FROZEN],
+                Some(vec![(MONEY_TOKENS_COL_TOKEN_ID, SqlType::Blob(serialize(token_id)))]),
+            )
+            .await?;
+
+        let [mint_authority, is_frozen] = &row[..] else {
+            return Err(Error::Custom(format!(
+                "Mint authority for {} not found in wallet",
+                token_id
+            )))
+        };
+
+        if matches!(is_frozen, SqlType::Integer(v) if *v != 0) {
+            return Err(Error::Custom(format!("Token mint for {} is frozen", token_id)))
+        }
+
+        Ok(Keypair::new(blob(mint_authority)?))
+    }
+
+    /// Fetch the wallet's unspent coins
+    async fn wallet_coins(&self) -> Result<Vec<OwnCoin>> {
+        let rows = self
+            .wallet
+            .query_multiple(
+                MONEY_COINS_TABLE,
+                vec![
+                    MONEY_COINS_COL_COIN,
+                    MONEY_COINS_COL_SERIAL,
+                    MONEY_COINS_COL_VALUE,
+                    MONEY_COINS_COL_TOKEN_ID,
+                    MONEY_COINS_COL_SPEND_HOOK,
+                    MONEY_COINS_COL_USER_DATA,
+                    MONEY_COINS_COL_VALUE_BLIND,
+                    MONEY_COINS_COL_TOKEN_BLIND,
+                    MONEY_COINS_COL_SECRET,
+                    MONEY_COINS_COL_NULLIFIER,
+                    MONEY_COINS_COL_LEAF_POSITION,
+                    MONEY_COINS_COL_MEMO,
+                ],
+                Some(vec![(MONEY_COINS_COL_IS_SPENT, SqlType::Integer(0))]),
+            )
+            .await?;
+
+        let mut coins = Vec::with_capacity(rows.len());
+        for row in rows {
+            let SqlType::Blob(memo) = &row[11] else {
+                return Err(Error::Custom("Unexpected wallet column type".to_string()))
+            };
+
+            let note = MoneyNote {
+                serial: blob(&row[1])?,
+                value: blob(&row[2])?,
+                token_id: blob(&row[3])?,
+                spend_hook: blob(&row[4])?,
+                user_data: blob(&row[5])?,
+                value_blind: blob(&row[6])?,
+                token_blind: blob(&row[7])?,
+                memo: memo.clone(),
+            };
+
+            coins.push(OwnCoin {
+                coin: blob(&row[0])?,
+                note,
+                secret: blob(&row[8])?,
+                nullifier: blob(&row[9])?,
+                leaf_position: blob(&row[10])?,
+            });
+        }
+
+        Ok(coins)
+    }
+
+    /// Fetch the wallet's Merkle tree of coins
+    async fn wallet_money_tree(&self) -> Result<MerkleTree> {
+        let row =
+            self.wallet.query_single(MONEY_TREE_TABLE, vec![MONEY_TREE_COL_TREE], None).await?;
+
+        match row.first() {
+            Some(tree) => blob(tree),
+            None => Err(Error::Custom("Money Merkle tree not found in wallet".to_string())),
+        }
+    }
+}
+
+/// Build a proving key for the given circuit
+fn proving_key(zkbin: &ZkBinary) -> Result<ProvingKey> {
+    let circuit = ZkCircuit::new(empty_witnesses(zkbin)?, zkbin);
+    Ok(ProvingKey::build(zkbin.k, &circuit))
+}
+
+/// Build, prove and sign the composed transaction
+fn compose_tx(inputs: ComposeInputs) -> Result<Transaction> {
+    let mut proving_keys = HashMap::new();
+    for (ns, zkbin) in &inputs.zkas {
+        debug!("[RPC] tx.compose: Building {} proving key", ns);
+        proving_keys.insert(*ns, proving_key(zkbin)?);
+    }
+
+    let zkbin = |ns: &str| inputs.zkas[ns].clone();
+    let pk = |ns: &str| proving_keys[ns].clone();
+
+    let mut builder = TransactionBuilder::new();
+    let mut spent = vec![];
+
+    for call in inputs.calls {
+        match call {
+            ComposeCall::Transfer { recipient, amount, token_id, change } => {
+                // Spendable coins of the token not used by a previous call
+                let coins: Vec<OwnCoin> = inputs
+                    .coins
+                    .iter()
+                    .filter(|c| c.note.token_id == token_id)
+                    .filter(|c| c.note.spend_hook == pallas::Base::ZERO)
+                    .filter(|c| !spent.contains(&c.nullifier))
+                    .cloned()
+                    .collect();
+
+                if coins.is_empty() {
+                    return Err(Error::Custom(format!("No spendable coins for {}", token_id)))
+                }
+
+                let debris = TransferCallBuilder {
+                    keypair: change,
+                    recipient,
+                    value: amount,
+                    token_id,
+                    rcpt_spend_hook: pallas::Base::ZERO,
+                    rcpt_user_data: pallas::Base::ZERO,
+                    rcpt_user_data_blind: pallas::Base::random(&mut OsRng),
+                    rcpt_view_public: None,
+                    change_spend_hook: pallas::Base::ZERO,
+                    change_user_data: pallas::Base::ZERO,
+                    change_user_data_blind: pallas::Base::random(&mut OsRng),
+                    change_view_public: None,
+                    coins,
+                    tree: inputs.tree.clone(),
+                    mint_zkbin: zkbin(MONEY_CONTRACT_ZKAS_MINT_NS_V1),
+                    mint_pk: pk(MONEY_CONTRACT_ZKAS_MINT_NS_V1),
+                    burn_zkbin: zkbin(MONEY_CONTRACT_ZKAS_BURN_NS_V1),
+                    burn_pk: pk(MONEY_CONTRACT_ZKAS_BURN_NS_V1),
+                    clear_input: false,
+                }
+                .build()?;
+
+                spent.extend(debris.spent_coins.iter().map(|c| c.nullifier));
+                builder.add_transfer(debris)?;
+            }
+
+            ComposeCall::TokenMint { mint_authority, recipient, amount, max_supply } => {
+                let debris = TokenMintCallBuilder {
+                    mint_authority,
+                    recipient,
+                    amount,
+                    max_supply,
+                    spend_hook: pallas::Base::ZERO,
+                    user_data: pallas::Base::ZERO,
+                    token_mint_zkbin: zkbin(MONEY_CONTRACT_ZKAS_TOKEN_MINT_NS_V1),
+                    token_mint_pk: pk(MONEY_CONTRACT_ZKAS_TOKEN_MINT_NS_V1),
+                }
+                .build()?;
+
+                let mut data = vec![MoneyFunction::TokenMintV1 as u8];
+                debris.params.encode(&mut data)?;
+                let call = ContractCall { contract_id: *MONEY_CONTRACT_ID, data };
+                builder.add_call(call, debris.proofs, vec![mint_authority.secret], &[])?;
+            }
+
+            ComposeCall::TokenFreeze { mint_authority } => {
+                let debris = TokenFreezeCallBuilder {
+                    mint_authority,
+                    token_freeze_zkbin: zkbin(MONEY_CONTRACT_ZKAS_TOKEN_FRZ_NS_V1),
+                    token_freeze_pk: pk(MONEY_CONTRACT_ZKAS_TOKEN_FRZ_NS_V1),
+                }
+                .build()?;
+
+                let mut data = vec![MoneyFunction::TokenFreezeV1 as u8];
+                debris.params.encode(&mut data)?;
+                let call = ContractCall { contract_id: *MONEY_CONTRACT_ID, data };
+                builder.add_call(call, debris.proofs, vec![mint_authority.secret], &[])?;
+            }
+        }
+    }
+
+    builder.build()
+}
@@ -16,11 +16,13 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use std::collections::HashMap;
+
 use tinyjson::JsonValue;
 
 use darkfi::{
     rpc::jsonrpc::{ErrorCode, JsonError, JsonResponse, JsonResult},
-    util::time::Timestamp,
+    util::{slow_log::SlowLog, time::Timestamp},
 };
 
 use super::Darkfid;
@@ -82,4 +84,55 @@ impl Darkfid {
 
         JsonResponse::new(JsonValue::Boolean(true), id).into()
     }
+
+    // RPCAPI:
+    // Returns the slow operation logs of the wallet SQL database and the sled
+    // blockchain database. For each of them, the number of timed operations,
+    // how many exceeded the configured threshold, and up to `n` of the slowest
+    // ones are returned. `n` is optional and defaults to 10.
+    //
+    // --> {"jsonrpc": "2.0", "method": "slow_ops", "params": [10], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": {"wallet": {"total": 42, "slow": 1, "worst": [{"label": "SELECT ...", "duration_ms": 230, "timestamp": 1234}]}, "sled": {...}}, "id": 1}
+    pub async fn misc_slow_ops(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() > 1 || (params.len() == 1 && !params[0].is_number()) {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let n = match params.first() {
+            Some(n) => *n.get::<f64>().unwrap() as usize,
+            None => 10,
+        };
+
+        let blockchain = { self.validator_state.read().await.blockchain.clone() };
+        let logs = HashMap::from([
+            ("wallet".to_string(), slow_log_json(&self.wallet.slow_log, n)),
+            ("sled".to_string(), slow_log_json(&blockchain.slow_log, n)),
+        ]);
+
+        JsonResponse::new(JsonValue::Object(logs), id).into()
+    }
+}
+
+/// Auxiliary function to encode the counters and the `n` slowest
+/// operations of a [`SlowLog`] as JSON.
+fn slow_log_json(log: &SlowLog, n: usize) -> JsonValue {
+    let metrics = log.metrics();
+    let worst = log
+        .worst(n)
+        .into_iter()
+        .map(|op| {
+            JsonValue::Object(HashMap::from([
+                ("label".to_string(), JsonValue::String(op.label)),
+                ("duration_ms".to_string(), JsonValue::Number(op.duration.as_millis() as f64)),
+                ("timestamp".to_string(), JsonValue::Number(op.timestamp.0 as f64)),
+            ]))
+        })
+        .collect();
+
+    JsonValue::Object(HashMap::from([
+        ("total".to_string(), JsonValue::Number(metrics.total as f64)),
+        ("slow".to_string(), JsonValue::Number(metrics.slow as f64)),
+        ("worst".to_string(), JsonValue::Array(worst)),
+    ]))
 }
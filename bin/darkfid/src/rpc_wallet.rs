@@ -30,10 +30,109 @@ use darkfi::{
 
 use super::{error::RpcError, server_error, Darkfid};
 */
-use darkfi::rpc::jsonrpc::JsonResult;
+use std::str::FromStr;
+
+use darkfi::{
+    rpc::jsonrpc::{ErrorCode, JsonError, JsonResponse, JsonResult},
+    wallet::walletdb::{QueryType, SqlType},
+};
+use darkfi_money_contract::client::{
+    view_key::ViewKey, MONEY_KEYS_COL_SECRET, MONEY_KEYS_TABLE, MONEY_VIEW_KEYS_COL_PUBLIC,
+    MONEY_VIEW_KEYS_COL_SECRET, MONEY_VIEW_KEYS_TABLE,
+};
+use darkfi_sdk::crypto::SecretKey;
+use darkfi_serial::{deserialize, serialize};
+use log::error;
 use tinyjson::JsonValue;
 
 use super::Darkfid;
+use crate::{server_error, RpcError};
+
+/// Wallet tables accessible through the structured wallet methods
+const WALLET_TABLES: &[&str] = &[
+    "consensus_coin",
+    "dao_daos",
+    "dao_proposals",
+    "dao_trees",
+    "dao_votes",
+    "money_aliases",
+    "money_coins",
+    "money_info",
+    "money_keys",
+    "money_tokens",
+    "money_tree",
+    "money_view_keys",
+    "transactions_history",
+    "transactions_labels",
+];
+
+/// Parse a JSON value into an SQL value of the given `QueryType`.
+/// Blobs are given as arrays of bytes.
+fn parse_sql_value(typ: QueryType, value: &JsonValue) -> Option<SqlType> {
+    match (typ, value) {
+        (
+            QueryType::OptionInteger | QueryType::OptionBlob | QueryType::OptionText,
+            JsonValue::Null,
+        ) => Some(SqlType::Null),
+        (QueryType::Integer | QueryType::OptionInteger, JsonValue::Number(n)) => {
+            if n.fract() != 0.0 {
+                return None
+            }
+            Some(SqlType::Integer(*n as i64))
+        }
+        (QueryType::Float, JsonValue::Number(n)) => Some(SqlType::Real(*n)),
+        (QueryType::Blob | QueryType::OptionBlob, JsonValue::Array(bytes)) => {
+            let mut blob = Vec::with_capacity(bytes.len());
+            for byte in bytes {
+                let JsonValue::Number(b) = byte else { return None };
+                if b.fract() != 0.0 || *b < 0.0 || *b > 255.0 {
+                    return None
+                }
+                blob.push(*b as u8);
+            }
+            Some(SqlType::Blob(blob))
+        }
+        (QueryType::Text | QueryType::OptionText, JsonValue::String(t)) => {
+            Some(SqlType::Text(t.clone()))
+        }
+        _ => None,
+    }
+}
+
+/// Parse `[["column", column_type, value], ...]` triplets.
+fn parse_sql_values(params: &JsonValue) -> Option<Vec<(String, SqlType)>> {
+    let JsonValue::Array(triplets) = params else { return None };
+
+    let mut values = Vec::with_capacity(triplets.len());
+    for triplet in triplets {
+        let JsonValue::Array(triplet) = triplet else { return None };
+        if triplet.len() != 3 {
+            return None
+        }
+        let (JsonValue::String(col), JsonValue::Number(typ)) = (&triplet[0], &triplet[1]) else {
+            return None
+        };
+        if typ.fract() != 0.0 || *typ < 0.0 || *typ >= QueryType::Last as u8 as f64 {
+            return None
+        }
+
+        values.push((col.clone(), parse_sql_value((*typ as u8).into(), &triplet[2])?));
+    }
+
+    Some(values)
+}
+
+fn sql_value_to_json(value: SqlType) -> JsonValue {
+    match value {
+        SqlType::Integer(i) => JsonValue::Number(i as f64),
+        SqlType::Real(r) => JsonValue::Number(r),
+        SqlType::Text(t) => JsonValue::String(t),
+        SqlType::Blob(b) => {
+            JsonValue::Array(b.into_iter().map(|x| JsonValue::Number(x as f64)).collect())
+        }
+        SqlType::Null => JsonValue::Null,
+    }
+}
 
 impl Darkfid {
     // RPCAPI:
@@ -51,11 +150,16 @@ impl Darkfid {
     // This function will fetch the first row it finds, if any. The `column_type` field
     // is a type available in the `WalletDb` API as an enum called `QueryType`. If a row
     // is not found, the returned result will be a JSON-RPC error.
-    // NOTE: This is obviously vulnerable to SQL injection. Open to interesting solutions.
+    // DEPRECATED: This is vulnerable to SQL injection, so it is disabled unless
+    // darkfid runs with `--wallet-raw-sql`. Use `wallet.select` instead.
     //
     // --> {"jsonrpc": "2.0", "method": "wallet.query_row_single", "params": [...], "id": 1}
     // <-- {"jsonrpc": "2.0", "result": ["va", "lu", "es", ...], "id": 1}
-    pub async fn wallet_query_row_single(&self, _id: u16, _params: JsonValue) -> JsonResult {
+    pub async fn wallet_query_row_single(&self, id: u16, _params: JsonValue) -> JsonResult {
+        if !self.wallet_raw_sql {
+            return server_error(RpcError::WalletRawSqlDisabled, id, None)
+        }
+
         todo!();
         /* TODO: This will be abstracted away
         // We need at least 3 params for something we want to fetch, and we want them in pairs.
@@ -205,10 +309,15 @@ impl Darkfid {
     // They're the same as above in `wallet.query_row_single`.
     // If there are any values found, they will be returned in a paired array. If not, an
     // empty array will be returned.
+    // DEPRECATED: Disabled unless darkfid runs with `--wallet-raw-sql`. Use `wallet.select`.
     //
     // --> {"jsonrpc": "2.0", "method": "wallet.query_row_multi", "params": [...], "id": 1}
     // <-- {"jsonrpc": "2.0", "result": [["va", "lu"], ["es", "es"], ...], "id": 1}
-    pub async fn wallet_query_row_multi(&self, _id: u16, _params: JsonValue) -> JsonResult {
+    pub async fn wallet_query_row_multi(&self, id: u16, _params: JsonValue) -> JsonResult {
+        if !self.wallet_raw_sql {
+            return server_error(RpcError::WalletRawSqlDisabled, id, None)
+        }
+
         todo!();
         /* TODO: This will be abstracted away
         // We need at least 3 params for something we want to fetch, and we want them in pairs.
@@ -329,10 +438,15 @@ impl Darkfid {
     // RPCAPI:
     // Executes an arbitrary SQL query on the wallet, and returns `true` on success.
     // `params[1..]` can optionally be provided in pairs like in `wallet.query_row_single`.
+    // DEPRECATED: Disabled unless darkfid runs with `--wallet-raw-sql`. Use `wallet.insert`.
     //
     // --> {"jsonrpc": "2.0", "method": "wallet.exec_sql", "params": ["CREATE TABLE ..."], "id": 1}
     // <-- {"jsonrpc": "2.0", "result": true, "id": 1}
-    pub async fn wallet_exec_sql(&self, _id: u16, _params: JsonValue) -> JsonResult {
+    pub async fn wallet_exec_sql(&self, id: u16, _params: JsonValue) -> JsonResult {
+        if !self.wallet_raw_sql {
+            return server_error(RpcError::WalletRawSqlDisabled, id, None)
+        }
+
         todo!();
         /* TODO: This will be abstracted away
         if params.is_empty() || !params[0].is_string() {
@@ -438,4 +552,206 @@ impl Darkfid {
         JsonResponse::new(json!(true), id).into()
         */
     }
+
+    // RPCAPI:
+    // Inserts a row into one of the whitelisted wallet tables, using a prepared
+    // statement. Values are given as `["column", column_type, value]` triplets,
+    // where `column_type` is a `QueryType`, and blobs are arrays of bytes.
+    // Returns `true` on success.
+    //
+    // --> {"jsonrpc": "2.0", "method": "wallet.insert", "params": ["money_aliases", [["alias", 4, "DRK"], ["token_id", 1, [42, ...]]]], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": true, "id": 1}
+    pub async fn wallet_insert(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 2 || !params[0].is_string() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let table = params[0].get::<String>().unwrap();
+        if !WALLET_TABLES.contains(&table.as_str()) {
+            return server_error(RpcError::WalletTableNotAllowed, id, Some(table.as_str()))
+        }
+
+        let Some(values) = parse_sql_values(&params[1]) else {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        };
+        if values.is_empty() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let (cols, values): (Vec<String>, Vec<SqlType>) = values.into_iter().unzip();
+        let values = cols.iter().map(String::as_str).zip(values).collect();
+        if let Err(e) = self.wallet.insert(table, values).await {
+            error!("[RPC] wallet.insert: Failed inserting into {}: {}", table, e);
+            return server_error(RpcError::WalletQueryFailed, id, None)
+        }
+
+        JsonResponse::new(JsonValue::Boolean(true), id).into()
+    }
+
+    // RPCAPI:
+    // Selects rows from one of the whitelisted wallet tables, using a prepared
+    // statement. The columns to return are given as an array of names, and an
+    // optional filter as `["column", column_type, value]` triplets that must all
+    // match. Returns an array of rows, each one an array of column values.
+    //
+    // --> {"jsonrpc": "2.0", "method": "wallet.select", "params": ["money_coins", ["coin", "value"], [["is_spent", 0, 0]]], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": [[[42, ...], 100], ...], "id": 1}
+    pub async fn wallet_select(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() < 2 || params.len() > 3 || !params[0].is_string() || !params[1].is_array() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let table = params[0].get::<String>().unwrap();
+        if !WALLET_TABLES.contains(&table.as_str()) {
+            return server_error(RpcError::WalletTableNotAllowed, id, Some(table.as_str()))
+        }
+
+        let mut col_names = vec![];
+        for col in params[1].get::<Vec<JsonValue>>().unwrap() {
+            let JsonValue::String(col) = col else {
+                return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+            };
+            col_names.push(col.as_str());
+        }
+        if col_names.is_empty() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let (where_cols, where_values): (Vec<String>, Vec<SqlType>) = match params.get(2) {
+            Some(filter) => match parse_sql_values(filter) {
+                Some(values) => values.into_iter().unzip(),
+                None => return JsonError::new(ErrorCode::InvalidParams, None, id).into(),
+            },
+            None => (vec![], vec![]),
+        };
+        let where_queries = match where_cols.is_empty() {
+            true => None,
+            false => Some(where_cols.iter().map(String::as_str).zip(where_values).collect()),
+        };
+
+        let rows = match self.wallet.query_multiple(table, col_names, where_queries).await {
+            Ok(v) => v,
+            Err(e) => {
+                error!("[RPC] wallet.select: Failed querying {}: {}", table, e);
+                return server_error(RpcError::WalletQueryFailed, id, None)
+            }
+        };
+
+        let rows = rows
+            .into_iter()
+            .map(|row| JsonValue::Array(row.into_iter().map(sql_value_to_json).collect()))
+            .collect();
+        JsonResponse::new(JsonValue::Array(rows), id).into()
+    }
+
+    // RPCAPI:
+    // Drops all the tables in the wallet, so that it can be rebuilt from
+    // scratch by a client recovering it from a seed. Returns `true` on success.
+    //
+    // --> {"jsonrpc": "2.0", "method": "wallet.recover", "params": [], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": true, "id": 1}
+    pub async fn wallet_recover(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if !params.is_empty() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        if let Err(e) = self.wallet.reset().await {
+            error!("[RPC] wallet.recover: Failed to reset wallet: {}", e);
+            return JsonError::new(ErrorCode::InternalError, None, id).into()
+        }
+
+        JsonResponse::new(JsonValue::Boolean(true), id).into()
+    }
+
+    // RPCAPI:
+    // Returns the view keys derived from the wallet's spend keys, encoded
+    // with base58. A view key can find the coins sent to its view address,
+    // but can't spend them, so it can be imported into a watch-only wallet,
+    // e.g. one run by an auditor, with `wallet.import_view_keys`.
+    //
+    // --> {"jsonrpc": "2.0", "method": "wallet.export_view_keys", "params": [], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": ["ViewKey", ...], "id": 1}
+    pub async fn wallet_export_view_keys(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if !params.is_empty() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let rows = match self
+            .wallet
+            .query_multiple(MONEY_KEYS_TABLE, vec![MONEY_KEYS_COL_SECRET], None)
+            .await
+        {
+            Ok(v) => v,
+            Err(e) => {
+                error!("[RPC] wallet.export_view_keys: Failed querying keys: {}", e);
+                return server_error(RpcError::WalletQueryFailed, id, None)
+            }
+        };
+
+        let mut view_keys = Vec::with_capacity(rows.len());
+        for row in rows {
+            let Some(SqlType::Blob(secret)) = row.first() else {
+                return server_error(RpcError::WalletQueryFailed, id, None)
+            };
+            let secret: SecretKey = match deserialize(secret) {
+                Ok(v) => v,
+                Err(e) => {
+                    error!("[RPC] wallet.export_view_keys: Failed decoding secret key: {}", e);
+                    return server_error(RpcError::WalletQueryFailed, id, None)
+                }
+            };
+
+            let view_key = ViewKey::derive(&secret).secret();
+            view_keys.push(JsonValue::String(bs58::encode(serialize(&view_key)).into_string()));
+        }
+
+        JsonResponse::new(JsonValue::Array(view_keys), id).into()
+    }
+
+    // RPCAPI:
+    // Imports the given base58 encoded view keys into the wallet, making it
+    // find the coins sent to their view addresses when scanning. A wallet
+    // holding view keys but no spend keys is watch-only.
+    // Returns the view addresses of the imported keys.
+    //
+    // --> {"jsonrpc": "2.0", "method": "wallet.import_view_keys", "params": ["ViewKey", ...], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": ["ViewAddress", ...], "id": 1}
+    pub async fn wallet_import_view_keys(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.is_empty() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let mut view_keys = Vec::with_capacity(params.len());
+        for param in params {
+            let JsonValue::String(key) = param else {
+                return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+            };
+            let Ok(key) = SecretKey::from_str(key) else {
+                return server_error(RpcError::ParseError, id, Some("Invalid view key"))
+            };
+            view_keys.push(ViewKey::from(key));
+        }
+
+        let mut addresses = Vec::with_capacity(view_keys.len());
+        for view_key in view_keys {
+            let values = vec![
+                (MONEY_VIEW_KEYS_COL_PUBLIC, SqlType::Blob(serialize(&view_key.public()))),
+                (MONEY_VIEW_KEYS_COL_SECRET, SqlType::Blob(serialize(&view_key.secret()))),
+            ];
+
+            if let Err(e) = self.wallet.insert(MONEY_VIEW_KEYS_TABLE, values).await {
+                error!("[RPC] wallet.import_view_keys: Failed inserting view key: {}", e);
+                return server_error(RpcError::WalletQueryFailed, id, None)
+            }
+
+            addresses.push(JsonValue::String(view_key.public().to_string()));
+        }
+
+        JsonResponse::new(JsonValue::Array(addresses), id).into()
+    }
 }
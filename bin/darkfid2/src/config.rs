@@ -0,0 +1,391 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{path::Path, str::FromStr};
+
+use log::{error, warn};
+use url::Url;
+
+use darkfi::{net::settings::SettingsOpt, util::path::expand_path, Error, Result};
+use darkfi_sdk::crypto::{PublicKey, SecretKey};
+
+use crate::{
+    utils::{parse_native_contract_hashes, parse_policy_rules, parse_rate_limits},
+    Args,
+};
+
+/// Schemes the P2P and JSON-RPC listeners can bind to
+const LISTEN_SCHEMES: [&str; 3] = ["tcp", "tcp+tls", "unix"];
+
+/// Schemes the P2P network can dial
+const DIAL_SCHEMES: [&str; 7] = ["tcp", "tcp+tls", "tor", "tor+tls", "nym", "nym+tls", "unix"];
+
+/// A problem found in a setting
+pub struct ConfigIssue {
+    /// Name of the setting, as used in the config file
+    pub setting: String,
+    /// What is wrong with the setting and how to fix it
+    pub message: String,
+}
+
+/// Aggregated result of validating the node configuration
+#[derive(Default)]
+pub struct ConfigReport {
+    /// Problems preventing the node from starting
+    pub errors: Vec<ConfigIssue>,
+    /// Suspicious settings the node can still run with
+    pub warnings: Vec<ConfigIssue>,
+}
+
+impl ConfigReport {
+    fn error(&mut self, setting: &str, message: impl Into<String>) {
+        self.errors.push(ConfigIssue { setting: setting.to_string(), message: message.into() });
+    }
+
+    fn warn(&mut self, setting: &str, message: impl Into<String>) {
+        self.warnings.push(ConfigIssue { setting: setting.to_string(), message: message.into() });
+    }
+
+    /// Log every issue found, failing if any of them is an error.
+    pub fn check(&self) -> Result<()> {
+        for issue in &self.warnings {
+            warn!(target: "darkfid", "Config warning in `{}`: {}", issue.setting, issue.message);
+        }
+
+        if self.errors.is_empty() {
+            return Ok(())
+        }
+
+        for issue in &self.errors {
+            error!(target: "darkfid", "Config error in `{}`: {}", issue.setting, issue.message);
+        }
+        error!(
+            target: "darkfid",
+            "Found {} configuration error(s), please fix them before starting the node",
+            self.errors.len()
+        );
+
+        Err(Error::ConfigInvalid)
+    }
+}
+
+/// Validate all node settings before any subsystem starts, collecting
+/// every problem found instead of stopping at the first one.
+pub fn validate_config(args: &Args) -> ConfigReport {
+    let mut report = ConfigReport::default();
+
+    check_modes(args, &mut report);
+    check_keys(args, &mut report);
+    check_limits(args, &mut report);
+    check_network("sync_net", &args.sync_net, &mut report);
+    if args.consensus {
+        check_network("consensus_net", &args.consensus_net, &mut report);
+    }
+    check_listeners(args, &mut report);
+    check_paths(args, &mut report);
+
+    report
+}
+
+/// Check the node's operating modes can be combined.
+fn check_modes(args: &Args, report: &mut ConfigReport) {
+    if args.observer && args.consensus {
+        report.error("observer", "Observer mode can't be combined with `consensus`");
+    }
+
+    if args.block_producer_key.is_some() && !args.consensus {
+        report.error("block_producer_key", "External block production requires `consensus`");
+    }
+
+    if args.block_producer_key.is_some() && args.observer {
+        report.error("block_producer_key", "Observer nodes can't produce blocks");
+    }
+
+    if args.consensus && args.skip_sync && !args.testing_mode {
+        report.warn(
+            "skip_sync",
+            "Participating in consensus without syncing first builds on a stale chain",
+        );
+    }
+
+    if args.checkpoint_authorities.is_empty() {
+        if args.checkpoint_key.is_some() {
+            report.error("checkpoint_key", "Checkpoint signing requires `checkpoint_authorities`");
+        }
+        if args.checkpoint_threshold.is_some() {
+            report.warn("checkpoint_threshold", "Has no effect without `checkpoint_authorities`");
+        }
+        if args.checkpoint_finality_hints {
+            report.warn(
+                "checkpoint_finality_hints",
+                "Has no effect without `checkpoint_authorities`",
+            );
+        }
+    } else if let Some(threshold) = args.checkpoint_threshold {
+        let authorities = args.checkpoint_authorities.len();
+        if threshold == 0 || threshold > authorities {
+            report.error(
+                "checkpoint_threshold",
+                format!("Must be between 1 and the {} configured authorities", authorities),
+            );
+        }
+    }
+}
+
+/// Check all configured keys and hashes parse.
+fn check_keys(args: &Args, report: &mut ConfigReport) {
+    if let Some(key) = &args.block_producer_key {
+        if SecretKey::from_str(key).is_err() {
+            report.error("block_producer_key", "Not a valid base58-encoded secret key");
+        }
+    }
+
+    if let Some(key) = &args.checkpoint_key {
+        if SecretKey::from_str(key).is_err() {
+            report.error("checkpoint_key", "Not a valid base58-encoded secret key");
+        }
+    }
+
+    for authority in &args.checkpoint_authorities {
+        if PublicKey::from_str(authority).is_err() {
+            report.error(
+                "checkpoint_authorities",
+                format!("`{}` is not a valid base58-encoded public key", authority),
+            );
+        }
+    }
+
+    if let Err(e) = parse_native_contract_hashes(&args.native_contract_hashes) {
+        report.error(
+            "native_contract_hashes",
+            format!("{}, entries must be \"contract_id:code_hash\"", e),
+        );
+    }
+
+    if args.strict_native_contracts && args.native_contract_hashes.is_empty() {
+        report.warn(
+            "strict_native_contracts",
+            "No `native_contract_hashes` given, only build metadata presence is enforced",
+        );
+    }
+}
+
+/// Check mempool, verification and rate limits.
+fn check_limits(args: &Args, report: &mut ConfigReport) {
+    if args.proof_verification_limit == Some(0) {
+        report.error("proof_verification_limit", "Must be at least 1");
+    }
+
+    if args.mempool_max_tx_size == Some(0) {
+        report.error("mempool_max_tx_size", "Must be at least 1, or unset for no limit");
+    }
+
+    if args.mempool_max_calls == Some(0) {
+        report.error("mempool_max_calls", "Must be at least 1, or unset for no limit");
+    }
+
+    if args.mempool_min_fee_multiplier == Some(0) {
+        report.error("mempool_min_fee_multiplier", "Must be at least 1, or unset for no floor");
+    }
+
+    if let Err(e) = parse_policy_rules(
+        &args.mempool_blocked_contracts,
+        args.mempool_max_tx_size,
+        args.mempool_max_calls,
+        &args.mempool_contract_call_limits,
+        args.mempool_conflict_policy.as_deref(),
+    ) {
+        report.error(
+            "mempool",
+            format!(
+                "{}, check `mempool_blocked_contracts`, `mempool_contract_call_limits` and `mempool_conflict_policy`",
+                e
+            ),
+        );
+    }
+
+    if let Err(e) = parse_rate_limits(
+        args.rpc_connection_rate_limit.as_deref(),
+        args.rpc_cheap_rate_limit.as_deref(),
+        args.rpc_expensive_rate_limit.as_deref(),
+    ) {
+        report.error("rpc_*_rate_limit", format!("{}, limits must be \"burst:per_second\"", e));
+    }
+}
+
+/// Check the settings of a P2P network.
+fn check_network(name: &str, net: &SettingsOpt, report: &mut ConfigReport) {
+    for url in &net.inbound {
+        if !LISTEN_SCHEMES.contains(&url.scheme()) {
+            report.error(
+                &format!("{}.inbound", name),
+                format!("Can't listen on `{}`, supported schemes: {:?}", url, LISTEN_SCHEMES),
+            );
+        }
+    }
+
+    for (field, urls) in
+        [("external_addrs", &net.external_addrs), ("peers", &net.peers), ("seeds", &net.seeds)]
+    {
+        for url in urls {
+            if !DIAL_SCHEMES.contains(&url.scheme()) {
+                report.error(
+                    &format!("{}.{}", name, field),
+                    format!("Unsupported transport in `{}`", url),
+                );
+            } else if url.scheme() != "unix" && (url.host_str().is_none() || url.port().is_none()) {
+                report.error(
+                    &format!("{}.{}", name, field),
+                    format!("`{}` must contain a host and a port", url),
+                );
+            }
+        }
+    }
+
+    for transport in &net.allowed_transports {
+        if !DIAL_SCHEMES.contains(&transport.as_str()) {
+            report.error(
+                &format!("{}.allowed_transports", name),
+                format!("Unknown transport `{}`, supported: {:?}", transport, DIAL_SCHEMES),
+            );
+        }
+    }
+
+    for transport in net.transport_weights.keys().chain(net.transport_limits.keys()) {
+        if !net.allowed_transports.is_empty() && !net.allowed_transports.contains(transport) {
+            report.warn(
+                &format!("{}.allowed_transports", name),
+                format!("`{}` has a weight or limit but isn't allowed", transport),
+            );
+        }
+    }
+
+    if net.outbound_connections.unwrap_or(0) == 0 && net.peers.is_empty() && net.inbound.is_empty()
+    {
+        report.warn(
+            &format!("{}.outbound_connections", name),
+            "No outbound slots, peers or inbound addresses, the node won't connect to anyone",
+        );
+    } else if net.outbound_connections.unwrap_or(0) > 0 &&
+        net.seeds.is_empty() &&
+        net.peers.is_empty()
+    {
+        report.warn(
+            &format!("{}.seeds", name),
+            "Outbound slots are configured but there are no seeds or peers to discover hosts from",
+        );
+    }
+
+    for (field, value) in [
+        ("outbound_connect_timeout", net.outbound_connect_timeout),
+        ("channel_handshake_timeout", net.channel_handshake_timeout),
+        ("channel_heartbeat_interval", net.channel_heartbeat_interval),
+        ("channel_pong_timeout", net.channel_pong_timeout),
+    ] {
+        if value == Some(0) {
+            report.error(&format!("{}.{}", name, field), "Must be at least 1 second");
+        }
+    }
+
+    if net.channel_missed_pongs == Some(0) {
+        report.error(&format!("{}.channel_missed_pongs", name), "Must be at least 1");
+    }
+
+    if net.message_queue_size == Some(0) || net.message_queue_sizes.values().any(|size| *size == 0)
+    {
+        report.warn(
+            &format!("{}.message_queue_size", name),
+            "Unbounded message queues let a flooding peer exhaust memory",
+        );
+    }
+}
+
+/// Check no two listeners bind to the same address.
+fn check_listeners(args: &Args, report: &mut ConfigReport) {
+    let mut listeners: Vec<(&str, &Url)> = vec![("rpc_listen", &args.rpc_listen)];
+    listeners.extend(args.sync_net.inbound.iter().map(|url| ("sync_net.inbound", url)));
+    if args.consensus {
+        listeners
+            .extend(args.consensus_net.inbound.iter().map(|url| ("consensus_net.inbound", url)));
+    }
+
+    if !LISTEN_SCHEMES.contains(&args.rpc_listen.scheme()) {
+        report.error(
+            "rpc_listen",
+            format!(
+                "Can't listen on `{}`, supported schemes: {:?}",
+                args.rpc_listen, LISTEN_SCHEMES
+            ),
+        );
+    }
+
+    for (i, (setting, url)) in listeners.iter().enumerate() {
+        for (other_setting, other) in &listeners[i + 1..] {
+            if listeners_collide(url, other) {
+                report.error(
+                    setting,
+                    format!("`{}` collides with `{}` from `{}`", url, other, other_setting),
+                );
+            }
+        }
+    }
+}
+
+/// Two listeners collide when they bind the same unix socket, or the same
+/// port on overlapping hosts.
+fn listeners_collide(a: &Url, b: &Url) -> bool {
+    if a.scheme() == "unix" || b.scheme() == "unix" {
+        return a.scheme() == b.scheme() && a.path() == b.path()
+    }
+
+    let unspecified = |url: &Url| matches!(url.host_str(), Some("0.0.0.0") | Some("[::]"));
+
+    a.port_or_known_default() == b.port_or_known_default() &&
+        (a.host_str() == b.host_str() || unspecified(a) || unspecified(b))
+}
+
+/// Check the directories of configured files exist.
+fn check_paths(args: &Args, report: &mut ConfigReport) {
+    let mut paths = vec![];
+    if let Some(log) = &args.log {
+        paths.push(("log", log));
+    }
+    if let Some(identity) = &args.sync_net.identity_path {
+        paths.push(("sync_net.identity_path", identity));
+    }
+    if let Some(identity) = &args.consensus_net.identity_path {
+        if args.consensus {
+            paths.push(("consensus_net.identity_path", identity));
+        }
+    }
+
+    for (setting, path) in paths {
+        let Ok(path) = expand_path(path) else {
+            report.error(setting, format!("Unable to expand path `{}`", path));
+            continue
+        };
+
+        let parent = path.parent().unwrap_or(Path::new("."));
+        if !parent.as_os_str().is_empty() && !parent.is_dir() {
+            report.error(
+                setting,
+                format!("Directory `{}` does not exist, please create it", parent.display()),
+            );
+        }
+    }
+}
@@ -0,0 +1,430 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::collections::HashMap;
+
+use tinyjson::JsonValue;
+
+use darkfi::{blockchain::BlockInfo, tx::Transaction};
+use darkfi_consensus_contract::{
+    model::{
+        ConsensusGenesisStakeParamsV1, ConsensusProposalParamsV1, ConsensusSlashEvidenceV1,
+        ConsensusSlashParamsV1,
+    },
+    ConsensusFunction,
+};
+use darkfi_money_contract::{
+    model::{
+        ClearInput, ConsensusInput, ConsensusOutput, ConsensusStakeParamsV1,
+        ConsensusUnstakeParamsV1, ConsensusUnstakeReqParamsV1, Input, MoneyFeeParamsV1,
+        MoneyStakeParamsV1, MoneyTokenFreezeParamsV1, MoneyTokenMetadataParamsV1,
+        MoneyTokenMintParamsV1, MoneyTransferParamsV1, MoneyUnstakeParamsV1, Output, TokenMetadata,
+        TokenSupply,
+    },
+    MoneyFunction,
+};
+use darkfi_sdk::{
+    crypto::{pasta_prelude::PrimeField, ContractId, CONSENSUS_CONTRACT_ID, MONEY_CONTRACT_ID},
+    pasta::{group::GroupEncoding, pallas},
+    tx::ContractCall,
+};
+use darkfi_serial::{deserialize, serialize, Decodable, Encodable};
+
+/// Decodes a contract call's data into its function name and its
+/// parameters as JSON. Returns `None` if the data can't be decoded.
+pub type CallDecoder = fn(&[u8]) -> Option<(&'static str, JsonValue)>;
+
+/// Registry of contract call decoders, used to turn transactions into
+/// human-readable JSON for explorers and wallets.
+pub struct DecoderRegistry {
+    decoders: HashMap<ContractId, CallDecoder>,
+}
+
+impl DecoderRegistry {
+    /// Create a registry holding the decoders of all native contracts.
+    pub fn native() -> Self {
+        let mut registry = Self { decoders: HashMap::new() };
+        registry.register(*MONEY_CONTRACT_ID, decode_money_call);
+        registry.register(*CONSENSUS_CONTRACT_ID, decode_consensus_call);
+        registry
+    }
+
+    /// Register the decoder for the given contract's calls, replacing
+    /// any existing one.
+    pub fn register(&mut self, contract_id: ContractId, decoder: CallDecoder) {
+        self.decoders.insert(contract_id, decoder);
+    }
+
+    /// Decode a single contract call. Calls of unknown contracts, or that fail
+    /// to decode, have a `null` function and params, along with their raw data.
+    pub fn decode_call(&self, call: &ContractCall) -> JsonValue {
+        let decoded = self.decoders.get(&call.contract_id).and_then(|decode| decode(&call.data));
+
+        let (function, params) = match decoded {
+            Some((function, params)) => (JsonValue::String(function.to_string()), params),
+            None => (JsonValue::Null, JsonValue::Null),
+        };
+
+        object([
+            ("contract_id", JsonValue::String(call.contract_id.to_string())),
+            ("function", function),
+            ("params", params),
+            ("data", JsonValue::String(hex(&call.data))),
+        ])
+    }
+
+    /// Summarize the calls of the given transaction, decoding only the
+    /// contract ID and function name of each of them.
+    pub fn summarize_tx(&self, tx: &Transaction) -> JsonValue {
+        let calls = tx
+            .calls
+            .iter()
+            .map(|call| {
+                let decoded =
+                    self.decoders.get(&call.contract_id).and_then(|decode| decode(&call.data));
+                let function = match decoded {
+                    Some((function, _)) => JsonValue::String(function.to_string()),
+                    None => JsonValue::Null,
+                };
+                object([
+                    ("contract_id", JsonValue::String(call.contract_id.to_string())),
+                    ("function", function),
+                ])
+            })
+            .collect();
+
+        JsonValue::Array(calls)
+    }
+
+    /// Describe the given block at given height: its header, along with the
+    /// hash and call summary of each of its transactions.
+    pub fn decode_block(&self, block: &BlockInfo, height: u64) -> JsonValue {
+        let header = &block.header;
+        let txs = block
+            .txs
+            .iter()
+            .map(|tx| {
+                object([
+                    ("hash", JsonValue::String(tx.hash().to_string())),
+                    ("calls", self.summarize_tx(tx)),
+                ])
+            })
+            .collect();
+        let slots = block.slots.iter().map(|slot| number(slot.id)).collect();
+
+        object([
+            ("hash", JsonValue::String(block.blockhash().to_string())),
+            ("height", number(height)),
+            (
+                "header",
+                object([
+                    ("version", number(header.version as u64)),
+                    ("previous", JsonValue::String(header.previous.to_string())),
+                    ("epoch", number(header.epoch)),
+                    ("slot", number(header.slot)),
+                    ("timestamp", number(header.timestamp.0)),
+                    ("root", JsonValue::String(header.root.to_string())),
+                ]),
+            ),
+            ("txs", JsonValue::Array(txs)),
+            ("slots", JsonValue::Array(slots)),
+        ])
+    }
+
+    /// Decode all calls of the given transaction, along with the number
+    /// of proofs and signatures attached to each of them.
+    pub fn decode_tx(&self, tx: &Transaction) -> JsonValue {
+        let calls = tx.calls.iter().map(|call| self.decode_call(call)).collect();
+        let proofs = tx.proofs.iter().map(|p| number(p.len() as u64)).collect();
+        let signatures = tx.signatures.iter().map(|s| number(s.len() as u64)).collect();
+
+        object([
+            ("hash", JsonValue::String(tx.hash().to_string())),
+            ("calls", JsonValue::Array(calls)),
+            ("proofs", JsonValue::Array(proofs)),
+            ("signatures", JsonValue::Array(signatures)),
+        ])
+    }
+}
+
+/// Decode the params of a `Money` contract call.
+fn decode_money_call(data: &[u8]) -> Option<(&'static str, JsonValue)> {
+    let (function, params) = data.split_first()?;
+    let decoded = match MoneyFunction::try_from(*function).ok()? {
+        MoneyFunction::FeeV1 => {
+            let params: MoneyFeeParamsV1 = decode(params)?;
+            let params = object([
+                ("input", input(&params.input)),
+                ("output", output(&params.output)),
+                ("fee_value", number(params.fee_value)),
+                (
+                    "fee_value_blind",
+                    JsonValue::String(hex(params.fee_value_blind.to_repr().as_ref())),
+                ),
+                ("token_blind", base(&params.token_blind)),
+            ]);
+            ("FeeV1", params)
+        }
+        MoneyFunction::GenesisMintV1 => {
+            ("GenesisMintV1", token_mint_params(&decode::<MoneyTokenMintParamsV1>(params)?))
+        }
+        MoneyFunction::TransferV1 => {
+            ("TransferV1", transfer_params(&decode::<MoneyTransferParamsV1>(params)?))
+        }
+        MoneyFunction::OtcSwapV1 => {
+            ("OtcSwapV1", transfer_params(&decode::<MoneyTransferParamsV1>(params)?))
+        }
+        MoneyFunction::TokenMintV1 => {
+            ("TokenMintV1", token_mint_params(&decode::<MoneyTokenMintParamsV1>(params)?))
+        }
+        MoneyFunction::TokenFreezeV1 => {
+            let params: MoneyTokenFreezeParamsV1 = decode(params)?;
+            let signature_public = JsonValue::String(params.signature_public.to_string());
+            ("TokenFreezeV1", object([("signature_public", signature_public)]))
+        }
+        MoneyFunction::StakeV1 => {
+            let params: MoneyStakeParamsV1 = decode(params)?;
+            let params = object([
+                ("token_blind", base(&params.token_blind)),
+                ("input", input(&params.input)),
+            ]);
+            ("StakeV1", params)
+        }
+        MoneyFunction::UnstakeV1 => {
+            let params: MoneyUnstakeParamsV1 = decode(params)?;
+            let params = object([
+                ("input", consensus_input(&params.input)),
+                ("output", output(&params.output)),
+            ]);
+            ("UnstakeV1", params)
+        }
+        MoneyFunction::TokenMetadataV1 => {
+            let params: MoneyTokenMetadataParamsV1 = decode(params)?;
+            let params = object([
+                ("mint_public", JsonValue::String(params.mint_public.to_string())),
+                ("metadata", token_metadata(&params.metadata)),
+            ]);
+            ("TokenMetadataV1", params)
+        }
+    };
+
+    Some(decoded)
+}
+
+/// Decode the params of a `Consensus` contract call.
+fn decode_consensus_call(data: &[u8]) -> Option<(&'static str, JsonValue)> {
+    let (function, params) = data.split_first()?;
+    let decoded = match ConsensusFunction::try_from(*function).ok()? {
+        ConsensusFunction::GenesisStakeV1 => {
+            let params: ConsensusGenesisStakeParamsV1 = decode(params)?;
+            let params = object([
+                ("input", clear_input(&params.input)),
+                ("output", consensus_output(&params.output)),
+            ]);
+            ("GenesisStakeV1", params)
+        }
+        ConsensusFunction::StakeV1 => {
+            let params: ConsensusStakeParamsV1 = decode(params)?;
+            let params = object([
+                ("input", input(&params.input)),
+                ("output", consensus_output(&params.output)),
+            ]);
+            ("StakeV1", params)
+        }
+        ConsensusFunction::ProposalV1 => {
+            let params: ConsensusProposalParamsV1 = decode(params)?;
+            ("ProposalV1", proposal_params(&params))
+        }
+        ConsensusFunction::UnstakeRequestV1 => {
+            let params: ConsensusUnstakeReqParamsV1 = decode(params)?;
+            let params = object([
+                ("input", consensus_input(&params.input)),
+                ("output", consensus_output(&params.output)),
+            ]);
+            ("UnstakeRequestV1", params)
+        }
+        ConsensusFunction::UnstakeV1 => {
+            let params: ConsensusUnstakeParamsV1 = decode(params)?;
+            ("UnstakeV1", object([("input", consensus_input(&params.input))]))
+        }
+        ConsensusFunction::SlashV1 => {
+            let params: ConsensusSlashParamsV1 = decode(params)?;
+            let params = object([
+                ("first", slash_evidence(&params.first)),
+                ("second", slash_evidence(&params.second)),
+            ]);
+            ("SlashV1", params)
+        }
+    };
+
+    Some(decoded)
+}
+
+fn proposal_params(params: &ConsensusProposalParamsV1) -> JsonValue {
+    object([
+        ("input", consensus_input(&params.input)),
+        ("output", consensus_output(&params.output)),
+        ("reward", number(params.reward)),
+        ("reward_blind", JsonValue::String(hex(params.reward_blind.to_repr().as_ref()))),
+        ("fork_hash", JsonValue::String(params.fork_hash.to_string())),
+        ("fork_previous_hash", JsonValue::String(params.fork_previous_hash.to_string())),
+        ("vrf_proof", encoded(&params.vrf_proof)),
+        ("y", base(&params.y)),
+        ("rho", base(&params.rho)),
+    ])
+}
+
+fn slash_evidence(evidence: &ConsensusSlashEvidenceV1) -> JsonValue {
+    let header = &evidence.header;
+    let header = object([
+        ("hash", JsonValue::String(header.headerhash().to_string())),
+        ("version", number(header.version as u64)),
+        ("previous", JsonValue::String(header.previous.to_string())),
+        ("epoch", number(header.epoch)),
+        ("slot", number(header.slot)),
+        ("timestamp", number(header.timestamp)),
+        ("root", JsonValue::String(header.root.to_string())),
+    ]);
+    object([
+        ("header", header),
+        ("signature", encoded(&evidence.signature)),
+        ("proposal", proposal_params(&evidence.proposal)),
+    ])
+}
+
+fn transfer_params(params: &MoneyTransferParamsV1) -> JsonValue {
+    object([
+        ("clear_inputs", JsonValue::Array(params.clear_inputs.iter().map(clear_input).collect())),
+        ("inputs", JsonValue::Array(params.inputs.iter().map(input).collect())),
+        ("outputs", JsonValue::Array(params.outputs.iter().map(output).collect())),
+    ])
+}
+
+fn token_mint_params(params: &MoneyTokenMintParamsV1) -> JsonValue {
+    let max_supply = match params.max_supply {
+        Some(v) => number(v),
+        None => JsonValue::Null,
+    };
+
+    object([
+        ("input", clear_input(&params.input)),
+        ("output", output(&params.output)),
+        ("max_supply", max_supply),
+    ])
+}
+
+fn clear_input(input: &ClearInput) -> JsonValue {
+    object([
+        ("value", number(input.value)),
+        ("token_id", JsonValue::String(input.token_id.to_string())),
+        ("value_blind", JsonValue::String(hex(input.value_blind.to_repr().as_ref()))),
+        ("token_blind", base(&input.token_blind)),
+        ("signature_public", JsonValue::String(input.signature_public.to_string())),
+    ])
+}
+
+fn input(input: &Input) -> JsonValue {
+    object([
+        ("value_commit", point(&input.value_commit)),
+        ("token_commit", base(&input.token_commit)),
+        ("nullifier", base(&input.nullifier.inner())),
+        ("merkle_root", base(&input.merkle_root.inner())),
+        ("spend_hook", base(&input.spend_hook)),
+        ("user_data_enc", base(&input.user_data_enc)),
+        ("signature_public", JsonValue::String(input.signature_public.to_string())),
+    ])
+}
+
+fn consensus_input(input: &ConsensusInput) -> JsonValue {
+    object([
+        ("epoch", number(input.epoch)),
+        ("value_commit", point(&input.value_commit)),
+        ("nullifier", base(&input.nullifier.inner())),
+        ("merkle_root", base(&input.merkle_root.inner())),
+        ("signature_public", JsonValue::String(input.signature_public.to_string())),
+    ])
+}
+
+fn output(output: &Output) -> JsonValue {
+    object([
+        ("value_commit", point(&output.value_commit)),
+        ("token_commit", base(&output.token_commit)),
+        ("coin", base(&output.coin.inner())),
+        ("note", encoded(&output.note)),
+    ])
+}
+
+fn consensus_output(output: &ConsensusOutput) -> JsonValue {
+    object([
+        ("value_commit", point(&output.value_commit)),
+        ("coin", base(&output.coin.inner())),
+        ("note", encoded(&output.note)),
+    ])
+}
+
+/// Deserialize the given bytes, requiring all of them to be consumed.
+fn decode<T: Decodable>(bytes: &[u8]) -> Option<T> {
+    deserialize(bytes).ok()
+}
+
+pub(crate) fn object<const N: usize>(fields: [(&str, JsonValue); N]) -> JsonValue {
+    JsonValue::Object(fields.into_iter().map(|(k, v)| (k.to_string(), v)).collect())
+}
+
+pub(crate) fn number(value: u64) -> JsonValue {
+    JsonValue::Number(value as f64)
+}
+
+pub(crate) fn token_metadata(metadata: &TokenMetadata) -> JsonValue {
+    object([
+        ("issuer", JsonValue::String(metadata.issuer.to_string())),
+        ("symbol_nonce", number(metadata.symbol_nonce)),
+        ("name", JsonValue::String(metadata.name.clone())),
+        ("symbol", JsonValue::String(metadata.symbol.clone())),
+        ("decimals", number(metadata.decimals as u64)),
+        ("uri", JsonValue::String(metadata.uri.clone())),
+    ])
+}
+
+pub(crate) fn token_supply(supply: &TokenSupply) -> JsonValue {
+    let opening = match &supply.opening {
+        Some(opening) => object([
+            ("value", number(opening.value)),
+            ("blind", JsonValue::String(hex(opening.blind.to_repr().as_ref()))),
+        ]),
+        None => JsonValue::Null,
+    };
+
+    object([("value_commit", point(&supply.value_commit)), ("opening", opening)])
+}
+
+fn base(value: &pallas::Base) -> JsonValue {
+    JsonValue::String(hex(value.to_repr().as_ref()))
+}
+
+fn point(value: &pallas::Point) -> JsonValue {
+    JsonValue::String(hex(value.to_bytes().as_ref()))
+}
+
+fn encoded<T: Encodable>(value: &T) -> JsonValue {
+    JsonValue::String(hex(&serialize(value)))
+}
+
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -16,7 +16,14 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use darkfi::rpc::jsonrpc::{ErrorCode::ServerError, JsonError, JsonResult};
+use std::collections::HashMap;
+
+use tinyjson::JsonValue;
+
+use darkfi::{
+    rpc::jsonrpc::{ErrorCode::ServerError, JsonError, JsonResult},
+    Error,
+};
 
 /// Custom RPC errors available for darkfid.
 /// Please sort them sensefully.
@@ -24,10 +31,27 @@ pub enum RpcError {
     // Transaction-related errors
     TxSimulationFail = -32110,
     TxBroadcastFail = -32111,
+    TracingDisabled = -32112,
+    TooManyPendingFilters = -32113,
 
     // State-related errors,
     NotSynced = -32120,
     UnknownSlot = -32121,
+    MmrOutOfRange = -32122,
+    UnknownTx = -32123,
+    UnknownBlock = -32124,
+
+    // Consensus-related errors
+    NotParticipating = -32130,
+    BlockProductionDisabled = -32131,
+    ProposalRejected = -32132,
+    CheckpointsDisabled = -32133,
+    CheckpointSigningDisabled = -32134,
+    CheckpointRejected = -32135,
+
+    // Node-related errors
+    ObserverMode = -32140,
+    AuditLogDisabled = -32141,
 
     // Parsing errors
     ParseError = -32190,
@@ -41,9 +65,24 @@ fn to_tuple(e: RpcError) -> (i32, String) {
         // Transaction-related errors
         RpcError::TxSimulationFail => "Failed simulating transaction state change",
         RpcError::TxBroadcastFail => "Failed broadcasting transaction",
+        RpcError::TracingDisabled => "Contract tracing is only available in testing mode",
+        RpcError::TooManyPendingFilters => "Node reached its pending transaction filters limit",
         // State-related errors
         RpcError::NotSynced => "Blockchain is not synced",
         RpcError::UnknownSlot => "Did not find slot",
+        RpcError::MmrOutOfRange => "Requested MMR leaf or size is out of range",
+        RpcError::UnknownTx => "Did not find transaction",
+        RpcError::UnknownBlock => "Did not find block",
+        // Consensus-related errors
+        RpcError::NotParticipating => "Node is not participating in consensus",
+        RpcError::BlockProductionDisabled => "External block production is not enabled",
+        RpcError::ProposalRejected => "Block proposal rejected",
+        RpcError::CheckpointsDisabled => "Node has no checkpoint authorities configured",
+        RpcError::CheckpointSigningDisabled => "Checkpoint signing is not enabled",
+        RpcError::CheckpointRejected => "Checkpoint rejected",
+        // Node-related errors
+        RpcError::ObserverMode => "Method is disabled on read-only observer nodes",
+        RpcError::AuditLogDisabled => "Audit log is not enabled",
         // Parsing errors
         RpcError::ParseError => "Parse error",
         // Contract-related errors
@@ -62,3 +101,26 @@ pub fn server_error(e: RpcError, id: u16, msg: Option<&str>) -> JsonResult {
 
     JsonError::new(ServerError(code), Some(default_msg), id).into()
 }
+
+/// Build the server error for a transaction that failed verification. The
+/// verification failure is appended to the message, and if it can be pinned
+/// to a single contract call, the call's index, contract ID and failing phase
+/// are sent in the error's `data` field.
+pub fn tx_verify_error(e: RpcError, id: u16, err: &Error) -> JsonResult {
+    let (code, default_msg) = to_tuple(e);
+
+    let Error::TxVerifyFailed(err) = err else {
+        return JsonError::new(ServerError(code), Some(default_msg), id).into()
+    };
+
+    let error = JsonError::new(ServerError(code), Some(format!("{}: {}", default_msg, err)), id);
+    let Some((call, contract_id, phase)) = err.call_context() else { return error.into() };
+
+    let data = JsonValue::Object(HashMap::from([
+        ("call".to_string(), JsonValue::Number(call as f64)),
+        ("contract_id".to_string(), JsonValue::String(contract_id.to_string())),
+        ("phase".to_string(), JsonValue::String(phase.to_string())),
+    ]));
+
+    error.with_data(data).into()
+}
@@ -0,0 +1,259 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::collections::HashMap;
+
+use darkfi_serial::serialize;
+use log::error;
+use tinyjson::JsonValue;
+
+use darkfi::{
+    blockchain::BlockInfo,
+    rpc::jsonrpc::{
+        ErrorCode::{InternalError, InvalidParams, ParseError},
+        JsonError, JsonResponse, JsonResult,
+    },
+    util::encoding::base64,
+};
+
+use crate::{
+    decode::{hex, number, object, DecoderRegistry},
+    server_error, Darkfid, RpcError,
+};
+
+/// Version of the gateway schemas. Bumped on any incompatible change, so
+/// integrators can rely on the layout of a given version staying stable.
+pub const SCHEMA_VERSION: u64 = 1;
+
+/// Wrap gateway data in its versioned envelope.
+fn envelope(kind: &str, data: JsonValue) -> JsonValue {
+    object([
+        ("schema", number(SCHEMA_VERSION)),
+        ("kind", JsonValue::String(kind.to_string())),
+        ("data", data),
+    ])
+}
+
+/// Stable schema of a block, with its transactions fully decoded.
+pub fn block_schema(block: &BlockInfo, decoders: &DecoderRegistry) -> JsonValue {
+    let header = &block.header;
+    object([
+        ("hash", JsonValue::String(block.blockhash().to_string())),
+        ("version", number(header.version as u64)),
+        ("previous", JsonValue::String(header.previous.to_string())),
+        ("epoch", number(header.epoch)),
+        ("slot", number(header.slot)),
+        ("timestamp", number(header.timestamp.0)),
+        ("root", JsonValue::String(hex(&serialize(&header.root)))),
+        ("txs", JsonValue::Array(block.txs.iter().map(|tx| decoders.decode_tx(tx)).collect())),
+    ])
+}
+
+/// Encode a JSON value as deterministic CBOR (RFC 8949, section 4.2).
+/// Integral numbers are encoded as integers, other numbers as 64-bit
+/// floats, and map keys are sorted by their encoding.
+pub fn to_cbor(value: &JsonValue) -> Vec<u8> {
+    let mut buf = vec![];
+    write_cbor(value, &mut buf);
+    buf
+}
+
+fn write_cbor(value: &JsonValue, buf: &mut Vec<u8>) {
+    match value {
+        JsonValue::Null => buf.push(0xf6),
+        JsonValue::Boolean(false) => buf.push(0xf4),
+        JsonValue::Boolean(true) => buf.push(0xf5),
+        JsonValue::Number(n) => write_number(*n, buf),
+        JsonValue::String(s) => {
+            write_head(3, s.len() as u64, buf);
+            buf.extend_from_slice(s.as_bytes());
+        }
+        JsonValue::Array(items) => {
+            write_head(4, items.len() as u64, buf);
+            for item in items {
+                write_cbor(item, buf);
+            }
+        }
+        JsonValue::Object(fields) => write_map(fields, buf),
+    }
+}
+
+fn write_map(fields: &HashMap<String, JsonValue>, buf: &mut Vec<u8>) {
+    let mut entries: Vec<(Vec<u8>, &JsonValue)> =
+        fields.iter().map(|(k, v)| (to_cbor(&JsonValue::String(k.clone())), v)).collect();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+    write_head(5, entries.len() as u64, buf);
+    for (key, value) in entries {
+        buf.extend_from_slice(&key);
+        write_cbor(value, buf);
+    }
+}
+
+fn write_number(n: f64, buf: &mut Vec<u8>) {
+    // Integers above 2^53 can't be represented exactly in JSON numbers
+    const MAX_SAFE_INTEGER: f64 = 9007199254740991.0;
+
+    if n.fract() == 0.0 && n.abs() <= MAX_SAFE_INTEGER {
+        match n >= 0.0 {
+            true => write_head(0, n as u64, buf),
+            false => write_head(1, (-1 - n as i64) as u64, buf),
+        }
+        return
+    }
+
+    buf.push(0xfb);
+    buf.extend_from_slice(&n.to_be_bytes());
+}
+
+fn write_head(major: u8, value: u64, buf: &mut Vec<u8>) {
+    let major = major << 5;
+    match value {
+        0..=23 => buf.push(major | value as u8),
+        24..=0xff => buf.extend_from_slice(&[major | 24, value as u8]),
+        0x100..=0xffff => {
+            buf.push(major | 25);
+            buf.extend_from_slice(&(value as u16).to_be_bytes());
+        }
+        0x10000..=0xffffffff => {
+            buf.push(major | 26);
+            buf.extend_from_slice(&(value as u32).to_be_bytes());
+        }
+        _ => {
+            buf.push(major | 27);
+            buf.extend_from_slice(&value.to_be_bytes());
+        }
+    }
+}
+
+/// Encode gateway data as base64-encoded CBOR, wrapped in its envelope.
+fn cbor_response(kind: &str, data: JsonValue, id: u16) -> JsonResult {
+    let encoded = base64::encode(&to_cbor(&envelope(kind, data)));
+    JsonResponse::new(JsonValue::String(encoded), id).into()
+}
+
+impl Darkfid {
+    // RPCAPI:
+    // Gateway for integrators that can't decode `darkfi_serial`. Queries the
+    // blockchain database for the block in the given slot, and returns it with
+    // its transactions decoded, following the gateway block schema.
+    //
+    // **Params:**
+    // * `array[0]`: `u64` slot ID (as string)
+    //
+    // **Returns:**
+    // * Base64-encoded CBOR of `{"schema": 1, "kind": "block", "data": {...}}`
+    //
+    // --> {"jsonrpc": "2.0", "method": "gateway.get_block", "params": ["0"], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": "o2RkYXRh...", "id": 1}
+    pub async fn gateway_get_block(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 1 || !params[0].is_string() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let Ok(slot) = params[0].get::<String>().unwrap().parse::<u64>() else {
+            return JsonError::new(ParseError, None, id).into()
+        };
+
+        let blocks = match self.validator.read().await.blockchain.get_blocks_by_slot(&[slot]) {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "darkfid::gateway::get_block", "Failed fetching block by slot: {}", e);
+                return JsonError::new(InternalError, None, id).into()
+            }
+        };
+
+        if blocks.is_empty() {
+            return server_error(RpcError::UnknownSlot, id, None)
+        }
+
+        cbor_response("block", block_schema(&blocks[0], &self.decoders), id)
+    }
+
+    // RPCAPI:
+    // Gateway for integrators that can't decode `darkfi_serial`. Queries the
+    // blockchain database for the given transaction, and returns it decoded,
+    // following the gateway transaction schema.
+    //
+    // **Params:**
+    // * `array[0]`: Hex-encoded transaction hash string
+    //
+    // **Returns:**
+    // * Base64-encoded CBOR of `{"schema": 1, "kind": "tx", "data": {...}}`
+    //
+    // --> {"jsonrpc": "2.0", "method": "gateway.get_tx", "params": ["TxHash"], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": "o2RkYXRh...", "id": 1}
+    pub async fn gateway_get_tx(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 1 || !params[0].is_string() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let Ok(tx_hash) = blake3::Hash::from_hex(params[0].get::<String>().unwrap()) else {
+            return JsonError::new(ParseError, None, id).into()
+        };
+
+        let txs = match self.validator.read().await.blockchain.transactions.get(&[tx_hash], false) {
+            Ok(txs) => txs,
+            Err(e) => {
+                error!(target: "darkfid::gateway::get_tx", "Failed fetching tx by hash: {}", e);
+                return JsonError::new(InternalError, None, id).into()
+            }
+        };
+
+        let Some(tx) = &txs[0] else { return server_error(RpcError::UnknownTx, id, None) };
+
+        cbor_response("tx", self.decoders.decode_tx(tx), id)
+    }
+
+    // RPCAPI:
+    // Gateway for integrators that can't decode `darkfi_serial`. Returns the
+    // node's view of the chain state, following the gateway state schema.
+    //
+    // **Params:**
+    // * `None`
+    //
+    // **Returns:**
+    // * Base64-encoded CBOR of `{"schema": 1, "kind": "state", "data": {...}}`,
+    //   with `last_slot`, `last_hash` and `pending_txs` data fields
+    //
+    // --> {"jsonrpc": "2.0", "method": "gateway.get_state", "params": [], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": "o2RkYXRh...", "id": 1}
+    pub async fn gateway_get_state(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if !params.is_empty() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let blockchain = { self.validator.read().await.blockchain.clone() };
+        let (Ok((last_slot, last_hash)), Ok(pending_txs)) =
+            (blockchain.last(), blockchain.get_pending_txs())
+        else {
+            return JsonError::new(InternalError, None, id).into()
+        };
+
+        let state = object([
+            ("last_slot", number(last_slot)),
+            ("last_hash", JsonValue::String(last_hash.to_string())),
+            ("pending_txs", number(pending_txs.len() as u64)),
+        ]);
+
+        cbor_response("state", state, id)
+    }
+}
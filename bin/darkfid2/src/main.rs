@@ -16,10 +16,10 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, str::FromStr, sync::Arc};
 
 use log::{error, info};
-use smol::stream::StreamExt;
+use smol::{lock::Mutex, stream::StreamExt};
 use structopt_toml::{serde::Deserialize, structopt::StructOpt, StructOptToml};
 use url::Url;
 
@@ -28,13 +28,16 @@ use darkfi::{
     blockchain::BlockInfo,
     cli_desc,
     net::{settings::SettingsOpt, P2pPtr},
-    rpc::{jsonrpc::JsonSubscriber, server::listen_and_serve},
+    rpc::{jsonrpc::JsonSubscriber, server::listen_and_serve_with_limits},
     system::StoppableTask,
     util::time::TimeKeeper,
-    validator::{Validator, ValidatorConfig, ValidatorPtr},
+    validator::{
+        policy::RulePolicy, utils::check_native_contracts, Validator, ValidatorConfig, ValidatorPtr,
+    },
     Error, Result,
 };
 use darkfi_contract_test_harness::vks;
+use darkfi_sdk::crypto::SecretKey;
 
 #[cfg(test)]
 mod tests;
@@ -42,21 +45,40 @@ mod tests;
 mod error;
 use error::{server_error, RpcError};
 
+/// Startup configuration validation
+mod config;
+use config::validate_config;
+
 /// JSON-RPC requests handler and methods
 mod rpc;
 mod rpc_blockchain;
+mod rpc_consensus;
 mod rpc_tx;
 
+/// Transaction call data decoding
+mod decode;
+use decode::DecoderRegistry;
+
+/// CBOR gateway for external integrators
+#[cfg(feature = "gateway")]
+mod gateway;
+
 /// Validator async tasks
 mod task;
-use task::sync::sync_task;
+use task::{
+    conflicts_task, pending_txs_task, rebroadcast_task, sync_task, PendingTxFiltersPtr,
+    Rebroadcaster, RebroadcasterPtr,
+};
 
 /// P2P net protocols
 mod proto;
 
 /// Utility functions
 mod utils;
-use utils::{genesis_txs_total, spawn_consensus_p2p, spawn_sync_p2p};
+use utils::{
+    genesis_txs_total, parse_checkpoint_config, parse_native_contract_hashes, parse_policy_rules,
+    parse_rate_limits, spawn_consensus_p2p, spawn_sync_p2p,
+};
 
 const CONFIG_FILE: &str = "darkfid_config.toml";
 const CONFIG_FILE_CONTENTS: &str = include_str!("../darkfid_config.toml");
@@ -73,10 +95,50 @@ struct Args {
     /// JSON-RPC listen URL
     rpc_listen: Url,
 
+    #[structopt(long)]
+    /// Per-connection JSON-RPC rate limit, given as "burst:per_second"
+    rpc_connection_rate_limit: Option<String>,
+
+    #[structopt(long)]
+    /// Per-connection rate limit of cheap JSON-RPC methods, given as "burst:per_second"
+    rpc_cheap_rate_limit: Option<String>,
+
+    #[structopt(long)]
+    /// Per-connection rate limit of expensive JSON-RPC methods (simulation,
+    /// broadcast), given as "burst:per_second"
+    rpc_expensive_rate_limit: Option<String>,
+
     #[structopt(long)]
     /// Participate in the consensus protocol
     consensus: bool,
 
+    #[structopt(long)]
+    /// Run as a read-only observer: sync, verify and serve the chain, but
+    /// never participate in consensus or accept locally submitted transactions
+    observer: bool,
+
+    #[structopt(long)]
+    /// Secret key used to sign blocks assembled by an external block
+    /// producer, enabling the `consensus.propose_block` RPC method
+    block_producer_key: Option<String>,
+
+    #[structopt(long)]
+    /// Public keys of the authorities whose signed checkpoints are accepted
+    checkpoint_authorities: Vec<String>,
+
+    #[structopt(long)]
+    /// Number of authority signatures a checkpoint requires (default: majority)
+    checkpoint_threshold: Option<usize>,
+
+    #[structopt(long)]
+    /// Use accepted checkpoints as hints when finalizing forks
+    checkpoint_finality_hints: bool,
+
+    #[structopt(long)]
+    /// Secret key of this node's checkpoint authority, enabling the
+    /// `consensus.sign_checkpoint` RPC method
+    checkpoint_key: Option<String>,
+
     #[structopt(long)]
     /// Skip syncing process and start node right away
     skip_sync: bool,
@@ -93,6 +155,63 @@ struct Args {
     /// Enable testing mode for local testing
     testing_mode: bool,
 
+    #[structopt(long)]
+    /// Reject transactions whose paid fee doesn't cover their verification cost
+    verify_fees: bool,
+
+    #[structopt(long)]
+    /// Record block, proposal and transaction decisions in an append-only audit log
+    audit_log: bool,
+
+    #[structopt(long)]
+    /// Maximum number of ZK proof verifications running at once, bounding memory use
+    proof_verification_limit: Option<usize>,
+
+    #[structopt(long)]
+    /// Expected code hashes of the embedded native contracts, given as
+    /// "contract_id:code_hash"
+    native_contract_hashes: Vec<String>,
+
+    #[structopt(long)]
+    /// Refuse to start if the embedded native contracts lack build metadata
+    /// or don't match their expected code hashes
+    strict_native_contracts: bool,
+
+    #[structopt(long)]
+    /// Refuse transactions calling any of these contract IDs
+    mempool_blocked_contracts: Vec<String>,
+
+    #[structopt(long)]
+    /// Refuse transactions larger than this many bytes
+    mempool_max_tx_size: Option<usize>,
+
+    #[structopt(long)]
+    /// Refuse transactions with more than this many contract calls
+    mempool_max_calls: Option<usize>,
+
+    #[structopt(long)]
+    /// Per-contract call limits, given as "contract_id:max_calls"
+    mempool_contract_call_limits: Vec<String>,
+
+    #[structopt(long)]
+    /// Refuse transactions paying a lower fee than this
+    mempool_min_fee: Option<u64>,
+
+    #[structopt(long)]
+    /// Refuse transactions paying a lower fee than this percentage of the
+    /// minimum fee for their gas use
+    mempool_min_fee_multiplier: Option<u64>,
+
+    #[structopt(long)]
+    /// How transactions conflicting with pending ones are handled:
+    /// "first-seen" or "fee-replacement"
+    mempool_conflict_policy: Option<String>,
+
+    #[structopt(long)]
+    /// Refuse contract deployments flagged by static analysis,
+    /// instead of only logging warnings about them
+    strict_contract_analysis: bool,
+
     #[structopt(short, long)]
     /// Set log file to ouput into
     log: Option<String>,
@@ -112,6 +231,18 @@ pub struct Darkfid {
     validator: ValidatorPtr,
     /// A map of various subscribers exporting live info from the blockchain
     subscribers: HashMap<&'static str, JsonSubscriber>,
+    /// Optional key signing blocks proposed by an external block producer
+    block_producer_key: Option<SecretKey>,
+    /// Contract call decoders used for transaction introspection
+    decoders: DecoderRegistry,
+    /// Flag indicating the node runs in read-only observer mode
+    observer: bool,
+    /// Optional key signing checkpoints as a member of the authority set
+    checkpoint_key: Option<SecretKey>,
+    /// Subscribers of pending transactions matching server-side filters
+    pending_filters: PendingTxFiltersPtr,
+    /// Tracker of locally submitted transactions to rebroadcast
+    rebroadcaster: RebroadcasterPtr,
 }
 
 impl Darkfid {
@@ -120,8 +251,25 @@ impl Darkfid {
         consensus_p2p: Option<P2pPtr>,
         validator: ValidatorPtr,
         subscribers: HashMap<&'static str, JsonSubscriber>,
+        block_producer_key: Option<SecretKey>,
+        observer: bool,
+        checkpoint_key: Option<SecretKey>,
     ) -> Self {
-        Self { sync_p2p, consensus_p2p, validator, subscribers }
+        let decoders = DecoderRegistry::native();
+        let pending_filters = Arc::new(Mutex::new(HashMap::new()));
+        let rebroadcaster = Rebroadcaster::new();
+        Self {
+            sync_p2p,
+            consensus_p2p,
+            validator,
+            subscribers,
+            block_producer_key,
+            decoders,
+            observer,
+            checkpoint_key,
+            pending_filters,
+            rebroadcaster,
+        }
     }
 }
 
@@ -129,10 +277,21 @@ async_daemonize!(realmain);
 async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
     info!(target: "darkfid", "Initializing DarkFi node...");
 
+    // Validate all settings before starting any subsystem
+    validate_config(&args).check()?;
+
     if args.testing_mode {
         info!(target: "darkfid", "Node is configured to run in testing mode!");
     }
 
+    if args.observer {
+        info!(target: "darkfid", "Node is configured to run in read-only observer mode!");
+    }
+
+    // Check the embedded native contracts against their expected builds
+    let native_contract_hashes = parse_native_contract_hashes(&args.native_contract_hashes)?;
+    check_native_contracts(&native_contract_hashes, args.strict_native_contracts)?;
+
     // NOTE: everything is dummy for now
     // FIXME: The VKS should only ever have to be generated on initial run.
     //        Do not use the precompiles for actual production code.
@@ -145,12 +304,32 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
     let genesis_block = BlockInfo::default();
     let genesis_txs_total = genesis_txs_total(&genesis_block.txs)?;
     let time_keeper = TimeKeeper::new(genesis_block.header.timestamp, 10, 90, 0);
+    let mut tx_policy = parse_policy_rules(
+        &args.mempool_blocked_contracts,
+        args.mempool_max_tx_size,
+        args.mempool_max_calls,
+        &args.mempool_contract_call_limits,
+        args.mempool_conflict_policy.as_deref(),
+    )?;
+    tx_policy.strict_contract_analysis = args.strict_contract_analysis;
+    tx_policy.min_fee = args.mempool_min_fee;
+    tx_policy.min_fee_multiplier = args.mempool_min_fee_multiplier;
+    let checkpoint_config = parse_checkpoint_config(
+        &args.checkpoint_authorities,
+        args.checkpoint_threshold,
+        args.checkpoint_finality_hints,
+    )?;
     let config = ValidatorConfig::new(
         time_keeper,
         genesis_block,
         genesis_txs_total,
         vec![],
         args.testing_mode,
+        args.verify_fees,
+        args.proof_verification_limit,
+        checkpoint_config,
+        Arc::new(RulePolicy::new(tx_policy)),
+        args.audit_log,
     );
 
     // Initialize validator
@@ -160,6 +339,9 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
     let mut subscribers = HashMap::new();
     subscribers.insert("blocks", JsonSubscriber::new("blockchain.subscribe_blocks"));
     subscribers.insert("txs", JsonSubscriber::new("blockchain.subscribe_txs"));
+    subscribers.insert("conflicts", JsonSubscriber::new("tx.subscribe_conflicts"));
+    subscribers.insert("pending_txs", JsonSubscriber::new("tx.subscribe_pending"));
+    subscribers.insert("checkpoints", JsonSubscriber::new("blockchain.subscribe_checkpoints"));
     if args.consensus {
         subscribers.insert("proposals", JsonSubscriber::new("blockchain.subscribe_proposals"));
     }
@@ -178,21 +360,51 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
         None
     };
 
+    // Parse the external block producer signing key, if configured
+    let block_producer_key = match args.block_producer_key {
+        Some(key) => Some(SecretKey::from_str(&key)?),
+        None => None,
+    };
+
+    // Parse the checkpoint authority signing key, if configured
+    let checkpoint_key = match args.checkpoint_key {
+        Some(key) => Some(SecretKey::from_str(&key)?),
+        None => None,
+    };
+
     // Initialize node
-    let darkfid =
-        Darkfid::new(sync_p2p.clone(), consensus_p2p.clone(), validator.clone(), subscribers).await;
+    let darkfid = Darkfid::new(
+        sync_p2p.clone(),
+        consensus_p2p.clone(),
+        validator.clone(),
+        subscribers,
+        block_producer_key,
+        args.observer,
+        checkpoint_key,
+    )
+    .await;
     let darkfid = Arc::new(darkfid);
     info!(target: "darkfid", "Node initialized successfully!");
 
     // JSON-RPC server
     info!(target: "darkfid", "Starting JSON-RPC server");
+    let rpc_limits = parse_rate_limits(
+        args.rpc_connection_rate_limit.as_deref(),
+        args.rpc_cheap_rate_limit.as_deref(),
+        args.rpc_expensive_rate_limit.as_deref(),
+    )?;
     // Here we create a task variable so we can manually close the
     // task later. P2P tasks don't need this since it has its own
     // stop() function to shut down, also terminating the task we
     // created for it.
     let rpc_task = StoppableTask::new();
     rpc_task.clone().start(
-        listen_and_serve(args.rpc_listen, darkfid.clone(), ex.clone()),
+        listen_and_serve_with_limits(
+            args.rpc_listen,
+            darkfid.clone(),
+            Some(rpc_limits),
+            ex.clone(),
+        ),
         |res| async {
             match res {
                 Ok(()) | Err(Error::RPCServerStopped) => { /* Do nothing */ }
@@ -203,6 +415,38 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
         ex.clone(),
     );
 
+    info!(target: "darkfid", "Starting mempool conflicts notifications task");
+    let conflicts_task_ = StoppableTask::new();
+    conflicts_task_.clone().start(
+        conflicts_task(validator.clone(), darkfid.subscribers.get("conflicts").unwrap().clone()),
+        |res| async {
+            match res {
+                Ok(()) | Err(Error::DetachedTaskStopped) => { /* Do nothing */ }
+                Err(e) => error!(target: "darkfid", "Failed starting conflicts task: {}", e),
+            }
+        },
+        Error::DetachedTaskStopped,
+        ex.clone(),
+    );
+
+    info!(target: "darkfid", "Starting pending transactions notifications task");
+    let pending_txs_task_ = StoppableTask::new();
+    pending_txs_task_.clone().start(
+        pending_txs_task(
+            validator.clone(),
+            darkfid.subscribers.get("pending_txs").unwrap().clone(),
+            darkfid.pending_filters.clone(),
+        ),
+        |res| async {
+            match res {
+                Ok(()) | Err(Error::DetachedTaskStopped) => { /* Do nothing */ }
+                Err(e) => error!(target: "darkfid", "Failed starting pending txs task: {}", e),
+            }
+        },
+        Error::DetachedTaskStopped,
+        ex.clone(),
+    );
+
     info!(target: "darkfid", "Starting sync P2P network");
     sync_p2p.clone().start().await?;
     StoppableTask::new().start(
@@ -249,6 +493,20 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
     // Clean node pending transactions
     darkfid.validator.write().await.purge_pending_txs().await?;
 
+    info!(target: "darkfid", "Starting transactions rebroadcast task");
+    let rebroadcast_task_ = StoppableTask::new();
+    rebroadcast_task_.clone().start(
+        rebroadcast_task(darkfid.clone()),
+        |res| async {
+            match res {
+                Ok(()) | Err(Error::DetachedTaskStopped) => { /* Do nothing */ }
+                Err(e) => error!(target: "darkfid", "Failed starting rebroadcast task: {}", e),
+            }
+        },
+        Error::DetachedTaskStopped,
+        ex.clone(),
+    );
+
     // Signal handling for graceful termination.
     let (signals_handler, signals_task) = SignalHandler::new(ex)?;
     signals_handler.wait_termination(signals_task).await?;
@@ -257,6 +515,15 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
     info!(target: "darkfid", "Stopping JSON-RPC server...");
     rpc_task.stop().await;
 
+    info!(target: "darkfid", "Stopping mempool conflicts notifications task...");
+    conflicts_task_.stop().await;
+
+    info!(target: "darkfid", "Stopping pending transactions notifications task...");
+    pending_txs_task_.stop().await;
+
+    info!(target: "darkfid", "Stopping transactions rebroadcast task...");
+    rebroadcast_task_.stop().await;
+
     info!(target: "darkfid", "Stopping syncing P2P network...");
     sync_p2p.stop().await;
 
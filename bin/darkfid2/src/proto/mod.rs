@@ -20,9 +20,13 @@
 mod protocol_block;
 pub use protocol_block::{BlockInfoMessage, ProtocolBlock};
 
+/// Authority checkpoint broadcast protocol
+mod protocol_checkpoint;
+pub use protocol_checkpoint::{CheckpointMessage, ProtocolCheckpoint};
+
 /// Block proposal broadcast protocol
 mod protocol_proposal;
-pub use protocol_proposal::ProtocolProposal;
+pub use protocol_proposal::{ProposalMessage, ProtocolProposal};
 
 /// Validator blockchain sync protocol
 mod protocol_sync;
@@ -25,7 +25,7 @@ use tinyjson::JsonValue;
 use url::Url;
 
 use darkfi::{
-    blockchain::BlockInfo,
+    blockchain::{BlockInfo, BLOCK_SIZE_CAP},
     impl_p2p_message,
     net::{
         ChannelPtr, Message, MessageSubscription, P2pPtr, ProtocolBase, ProtocolBasePtr,
@@ -48,7 +48,7 @@ impl From<&BlockInfo> for BlockInfoMessage {
     }
 }
 
-impl_p2p_message!(BlockInfoMessage, "block");
+impl_p2p_message!(BlockInfoMessage, "block", BLOCK_SIZE_CAP);
 
 pub struct ProtocolBlock {
     block_sub: MessageSubscription<BlockInfoMessage>,
@@ -0,0 +1,142 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use log::debug;
+use smol::Executor;
+use url::Url;
+
+use darkfi::{
+    impl_p2p_message,
+    net::{
+        ChannelPtr, Message, MessageSubscription, P2pPtr, ProtocolBase, ProtocolBasePtr,
+        ProtocolJobsManager, ProtocolJobsManagerPtr,
+    },
+    rpc::jsonrpc::JsonSubscriber,
+    validator::{checkpoint::Checkpoint, ValidatorPtr},
+    Result,
+};
+use darkfi_serial::{SerialDecodable, SerialEncodable};
+
+use crate::utils::checkpoint_to_json;
+
+/// Auxiliary [`Checkpoint`] wrapper structure used for messaging.
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct CheckpointMessage(pub Checkpoint);
+
+impl_p2p_message!(CheckpointMessage, "checkpoint");
+
+pub struct ProtocolCheckpoint {
+    checkpoint_sub: MessageSubscription<CheckpointMessage>,
+    jobsman: ProtocolJobsManagerPtr,
+    validator: ValidatorPtr,
+    p2p: P2pPtr,
+    channel_address: Url,
+    subscriber: JsonSubscriber,
+}
+
+impl ProtocolCheckpoint {
+    pub async fn init(
+        channel: ChannelPtr,
+        validator: ValidatorPtr,
+        p2p: P2pPtr,
+        subscriber: JsonSubscriber,
+    ) -> Result<ProtocolBasePtr> {
+        debug!(
+            target: "validator::protocol_checkpoint::init",
+            "Adding ProtocolCheckpoint to the protocol registry"
+        );
+        let msg_subsystem = channel.message_subsystem();
+        msg_subsystem.add_dispatch::<CheckpointMessage>().await;
+
+        let checkpoint_sub = channel.subscribe_msg::<CheckpointMessage>().await?;
+
+        Ok(Arc::new(Self {
+            checkpoint_sub,
+            jobsman: ProtocolJobsManager::new("CheckpointProtocol", channel.clone()),
+            validator,
+            p2p,
+            channel_address: channel.address().clone(),
+            subscriber,
+        }))
+    }
+
+    async fn handle_receive_checkpoint(self: Arc<Self>) -> Result<()> {
+        debug!(target: "validator::protocol_checkpoint::handle_receive_checkpoint", "START");
+        let exclude_list = vec![self.channel_address.clone()];
+        loop {
+            let checkpoint = match self.checkpoint_sub.receive().await {
+                Ok(v) => v,
+                Err(e) => {
+                    debug!(
+                        target: "validator::protocol_checkpoint::handle_receive_checkpoint",
+                        "recv fail: {}",
+                        e
+                    );
+                    continue
+                }
+            };
+
+            // Check if node has finished syncing its blockchain
+            if !self.validator.read().await.synced {
+                debug!(
+                    target: "validator::protocol_checkpoint::handle_receive_checkpoint",
+                    "Node still syncing blockchain, skipping..."
+                );
+                continue
+            }
+
+            let checkpoint_copy = (*checkpoint).clone();
+
+            match self.validator.write().await.receive_checkpoint(&checkpoint_copy.0) {
+                Ok(true) => {
+                    self.p2p.broadcast_with_exclude(&checkpoint_copy, &exclude_list).await;
+                    self.subscriber.notify(vec![checkpoint_to_json(&checkpoint_copy.0)]).await;
+                }
+                Ok(false) => { /* Already known, nothing to relay */ }
+                Err(e) => {
+                    debug!(
+                        target: "validator::protocol_checkpoint::handle_receive_checkpoint",
+                        "receive_checkpoint fail: {}",
+                        e
+                    );
+                }
+            };
+        }
+    }
+}
+
+#[async_trait]
+impl ProtocolBase for ProtocolCheckpoint {
+    async fn start(self: Arc<Self>, executor: Arc<Executor<'_>>) -> Result<()> {
+        debug!(target: "validator::protocol_checkpoint::start", "START");
+        self.jobsman.clone().start(executor.clone());
+        self.jobsman
+            .clone()
+            .spawn(self.clone().handle_receive_checkpoint(), executor.clone())
+            .await;
+        debug!(target: "validator::protocol_checkpoint::start", "END");
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "ProtocolCheckpoint"
+    }
+}
@@ -25,6 +25,7 @@ use tinyjson::JsonValue;
 use url::Url;
 
 use darkfi::{
+    blockchain::BLOCK_SIZE_CAP,
     impl_p2p_message,
     net::{
         ChannelPtr, Message, MessageSubscription, P2pPtr, ProtocolBase, ProtocolBasePtr,
@@ -39,9 +40,10 @@ use darkfi_serial::{serialize, SerialDecodable, SerialEncodable};
 
 /// Auxiliary [`Proposal`] wrapper structure used for messaging.
 #[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
-struct ProposalMessage(Proposal);
+pub struct ProposalMessage(pub Proposal);
 
-impl_p2p_message!(ProposalMessage, "proposal");
+// The block, plus its hash
+impl_p2p_message!(ProposalMessage, "proposal", 32 + BLOCK_SIZE_CAP);
 
 pub struct ProtocolProposal {
     proposal_sub: MessageSubscription<ProposalMessage>,
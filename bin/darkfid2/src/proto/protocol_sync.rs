@@ -23,7 +23,7 @@ use log::{debug, error};
 use smol::Executor;
 
 use darkfi::{
-    blockchain::BlockInfo,
+    blockchain::{BlockInfo, BLOCK_SIZE_CAP},
     impl_p2p_message,
     net::{
         ChannelPtr, Message, MessageSubscription, ProtocolBase, ProtocolBasePtr,
@@ -55,7 +55,8 @@ pub struct SyncResponse {
     pub blocks: Vec<BlockInfo>,
 }
 
-impl_p2p_message!(SyncResponse, "syncresponse");
+// A full batch of blocks, plus the length prefix
+impl_p2p_message!(SyncResponse, "syncresponse", 9 + BATCH * BLOCK_SIZE_CAP);
 
 pub struct ProtocolSync {
     request_sub: MessageSubscription<SyncRequest>,
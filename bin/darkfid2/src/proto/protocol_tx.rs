@@ -25,16 +25,17 @@ use tinyjson::JsonValue;
 use url::Url;
 
 use darkfi::{
+    error::TxVerifyFailed,
     impl_p2p_message,
     net::{
-        ChannelPtr, Message, MessageSubscription, P2pPtr, ProtocolBase, ProtocolBasePtr,
-        ProtocolJobsManager, ProtocolJobsManagerPtr,
+        reputation::Violation, ChannelPtr, Message, MessageSubscription, P2pPtr, ProtocolBase,
+        ProtocolBasePtr, ProtocolJobsManager, ProtocolJobsManagerPtr,
     },
     rpc::jsonrpc::JsonSubscriber,
     tx::Transaction,
     util::encoding::base64,
     validator::ValidatorPtr,
-    Result,
+    Error, Result,
 };
 use darkfi_serial::{serialize, SerialDecodable, SerialEncodable};
 
@@ -122,6 +123,20 @@ impl ProtocolTx {
                         "append_tx fail: {}",
                         e
                     );
+
+                    // Transactions failing for state reasons may be relayed
+                    // in good faith, but malformed ones can't be
+                    if let Error::TxVerifyFailed(
+                        TxVerifyFailed::InvalidSignature |
+                        TxVerifyFailed::InvalidZkProof |
+                        TxVerifyFailed::MissingSignatures |
+                        TxVerifyFailed::MissingCalls,
+                    ) = e
+                    {
+                        self.p2p
+                            .report_violation(&self.channel_address, Violation::ProtocolViolation)
+                            .await;
+                    }
                 }
             }
         }
@@ -28,13 +28,32 @@ use darkfi::{
     util::time::Timestamp,
 };
 
-use crate::Darkfid;
+use crate::{server_error, Darkfid, RpcError};
+
+/// JSON-RPC methods charged against the expensive rate limit bucket,
+/// since serving them involves full transaction verification.
+pub const EXPENSIVE_METHODS: &[&str] =
+    &["tx.simulate", "tx.simulate_trace", "tx.broadcast", "consensus.propose_block"];
+
+/// JSON-RPC methods refused by observer nodes, since they submit local
+/// transactions, modify the mempool, or take part in consensus.
+pub const OBSERVER_DISABLED_METHODS: &[&str] = &[
+    "tx.broadcast",
+    "tx.clean_pending",
+    "consensus.propose_block",
+    "consensus.sign_checkpoint",
+    "consensus.submit_checkpoint",
+];
 
 #[async_trait]
 impl RequestHandler for Darkfid {
     async fn handle_request(&self, req: JsonRequest) -> JsonResult {
         debug!(target: "darkfid::rpc", "--> {}", req.stringify().unwrap());
 
+        if self.observer && OBSERVER_DISABLED_METHODS.contains(&req.method.as_str()) {
+            return server_error(RpcError::ObserverMode, req.id, None)
+        }
+
         match req.method.as_str() {
             // =====================
             // Miscellaneous methods
@@ -48,13 +67,34 @@ impl RequestHandler for Darkfid {
             // Blockchain methods
             // ==================
             "blockchain.get_slot" => return self.blockchain_get_slot(req.id, req.params).await,
+            "blockchain.get_block_by_height" => {
+                return self.blockchain_get_block_by_height(req.id, req.params).await
+            }
+            "blockchain.get_block_by_hash" => {
+                return self.blockchain_get_block_by_hash(req.id, req.params).await
+            }
             "blockchain.get_tx" => return self.blockchain_get_tx(req.id, req.params).await,
             "blockchain.last_known_slot" => {
                 return self.blockchain_last_known_slot(req.id, req.params).await
             }
+            "blockchain.get_mmr_root" => {
+                return self.blockchain_get_mmr_root(req.id, req.params).await
+            }
+            "blockchain.get_mmr_proof" => {
+                return self.blockchain_get_mmr_proof(req.id, req.params).await
+            }
             "blockchain.lookup_zkas" => {
                 return self.blockchain_lookup_zkas(req.id, req.params).await
             }
+            "blockchain.lookup_token" => {
+                return self.blockchain_lookup_token(req.id, req.params).await
+            }
+            "blockchain.token_supply" => {
+                return self.blockchain_token_supply(req.id, req.params).await
+            }
+            "blockchain.export_money_set" => {
+                return self.blockchain_export_money_set(req.id, req.params).await
+            }
             "blockchain.subscribe_blocks" => {
                 return self.blockchain_subscribe_blocks(req.id, req.params).await
             }
@@ -64,14 +104,58 @@ impl RequestHandler for Darkfid {
             "blockchain.subscribe_proposals" => {
                 return self.blockchain_subscribe_proposals(req.id, req.params).await
             }
+            "blockchain.last_checkpoint" => {
+                return self.blockchain_last_checkpoint(req.id, req.params).await
+            }
+            "blockchain.subscribe_checkpoints" => {
+                return self.blockchain_subscribe_checkpoints(req.id, req.params).await
+            }
+            "blockchain.audit_log" => return self.blockchain_audit_log(req.id, req.params).await,
 
             // ===================
             // Transaction methods
             // ===================
             "tx.simulate" => return self.tx_simulate(req.id, req.params).await,
+            "tx.simulate_trace" => return self.tx_simulate_trace(req.id, req.params).await,
+            "tx.decode" => return self.tx_decode(req.id, req.params).await,
             "tx.broadcast" => return self.tx_broadcast(req.id, req.params).await,
             "tx.pending" => return self.tx_pending(req.id, req.params).await,
             "tx.clean_pending" => return self.tx_pending(req.id, req.params).await,
+            "tx.subscribe_conflicts" => {
+                return self.tx_subscribe_conflicts(req.id, req.params).await
+            }
+            "tx.subscribe_pending" => return self.tx_subscribe_pending(req.id, req.params).await,
+            "tx.mempool_metrics" => return self.tx_mempool_metrics(req.id, req.params).await,
+            "tx.rebroadcast_status" => return self.tx_rebroadcast_status(req.id, req.params).await,
+
+            // ===========
+            // Fee methods
+            // ===========
+            "fee.estimate" => return self.fee_estimate(req.id, req.params).await,
+
+            // ===============
+            // Gateway methods
+            // ===============
+            #[cfg(feature = "gateway")]
+            "gateway.get_block" => return self.gateway_get_block(req.id, req.params).await,
+            #[cfg(feature = "gateway")]
+            "gateway.get_tx" => return self.gateway_get_tx(req.id, req.params).await,
+            #[cfg(feature = "gateway")]
+            "gateway.get_state" => return self.gateway_get_state(req.id, req.params).await,
+
+            // =================
+            // Consensus methods
+            // =================
+            "consensus.propose_block" => {
+                return self.consensus_propose_block(req.id, req.params).await
+            }
+            "consensus.sign_checkpoint" => {
+                return self.consensus_sign_checkpoint(req.id, req.params).await
+            }
+            "consensus.submit_checkpoint" => {
+                return self.consensus_submit_checkpoint(req.id, req.params).await
+            }
+            "consensus.get_forks" => return self.consensus_get_forks(req.id, req.params).await,
 
             // ==============
             // Invalid method
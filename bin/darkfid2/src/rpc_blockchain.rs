@@ -18,7 +18,12 @@
 
 use std::str::FromStr;
 
-use darkfi_sdk::crypto::ContractId;
+use darkfi_money_contract::{
+    model::{TokenMetadata, TokenSupply},
+    MONEY_CONTRACT_COINS_TREE, MONEY_CONTRACT_NULLIFIERS_TREE, MONEY_CONTRACT_TOKEN_ISSUERS_TREE,
+    MONEY_CONTRACT_TOKEN_METADATA_TREE, MONEY_CONTRACT_TOKEN_SUPPLY_TREE,
+};
+use darkfi_sdk::crypto::{ContractId, PublicKey, TokenId, MONEY_CONTRACT_ID};
 use darkfi_serial::{deserialize, serialize};
 use log::{debug, error};
 use tinyjson::JsonValue;
@@ -30,9 +35,15 @@ use darkfi::{
     },
     runtime::vm_runtime::SMART_CONTRACT_ZKAS_DB_NAME,
     util::encoding::base64,
+    Error,
 };
 
-use crate::{server_error, Darkfid, RpcError};
+use crate::{
+    decode::{object, token_metadata, token_supply},
+    server_error,
+    utils::{audit_entry_to_json, checkpoint_to_json},
+    Darkfid, RpcError,
+};
 
 impl Darkfid {
     // RPCAPI:
@@ -75,42 +86,134 @@ impl Darkfid {
         JsonResponse::new(JsonValue::String(block), id).into()
     }
 
+    // RPCAPI:
+    // Queries the blockchain database for the block at given height, counting
+    // from the genesis block at height 0.
+    // Returns the block header, along with the hash and the called contract
+    // functions of each of its transactions.
+    //
+    // **Params:**
+    // * `array[0]`: `u64` block height (as string)
+    //
+    // **Returns:**
+    // * `object` describing the block
+    //
+    // --> {"jsonrpc": "2.0", "method": "blockchain.get_block_by_height", "params": ["1"], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": {"hash": "...", "height": 1, "header": {...}, "txs": [...], "slots": [...]}, "id": 1}
+    pub async fn blockchain_get_block_by_height(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 1 || !params[0].is_string() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let height = match params[0].get::<String>().unwrap().parse::<u64>() {
+            Ok(v) => v,
+            Err(_) => return JsonError::new(ParseError, None, id).into(),
+        };
+
+        let block = match self.validator.read().await.blockchain.get_block_by_height(height) {
+            Ok(Some(v)) => v,
+            Ok(None) => return server_error(RpcError::UnknownBlock, id, None),
+            Err(e) => {
+                error!(target: "darkfid::rpc::blockchain_get_block_by_height", "Failed fetching block by height: {}", e);
+                return JsonError::new(InternalError, None, id).into()
+            }
+        };
+
+        JsonResponse::new(self.decoders.decode_block(&block, height), id).into()
+    }
+
+    // RPCAPI:
+    // Queries the blockchain database for the block with given hash.
+    // Returns the block header, along with the hash and the called contract
+    // functions of each of its transactions.
+    //
+    // **Params:**
+    // * `array[0]`: Hex-encoded block hash string
+    //
+    // **Returns:**
+    // * `object` describing the block
+    //
+    // --> {"jsonrpc": "2.0", "method": "blockchain.get_block_by_hash", "params": ["BlockHash"], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": {"hash": "...", "height": 1, "header": {...}, "txs": [...], "slots": [...]}, "id": 1}
+    pub async fn blockchain_get_block_by_hash(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 1 || !params[0].is_string() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let block_hash = match blake3::Hash::from_hex(params[0].get::<String>().unwrap()) {
+            Ok(v) => v,
+            Err(_) => return JsonError::new(ParseError, None, id).into(),
+        };
+
+        let validator = self.validator.read().await;
+        let blocks = match validator.blockchain.blocks.contains(&block_hash) {
+            Ok(true) => validator.blockchain.get_blocks_by_hash(&[block_hash]),
+            Ok(false) => return server_error(RpcError::UnknownBlock, id, None),
+            Err(e) => Err(e),
+        };
+        let block = match blocks {
+            Ok(v) => v[0].clone(),
+            Err(e) => {
+                error!(target: "darkfid::rpc::blockchain_get_block_by_hash", "Failed fetching block by hash: {}", e);
+                return JsonError::new(InternalError, None, id).into()
+            }
+        };
+        let height = validator.blockchain.block_height(&block);
+
+        JsonResponse::new(self.decoders.decode_block(&block, height), id).into()
+    }
+
     // RPCAPI:
     // Queries the blockchain database for a given transaction.
     // Returns a serialized `Transaction` object.
+    // If the optional second param is `true`, the transaction is instead
+    // returned as readable JSON, with each of its contract calls decoded.
     //
     // **Params:**
     // * `array[0]`: Hex-encoded transaction hash string
+    // * `array[1]`: Optional `bool` requesting readable JSON
     //
     // **Returns:**
     // * Serialized [`Transaction`](https://darkrenaissance.github.io/darkfi/development/darkfi/tx/struct.Transaction.html)
-    //   object encoded with base64
+    //   object encoded with base64, or `object` describing it
     //
     // --> {"jsonrpc": "2.0", "method": "blockchain.get_tx", "params": ["TxHash"], "id": 1}
     // <-- {"jsonrpc": "2.0", "result": "ABCD...", "id": 1}
+    //
+    // --> {"jsonrpc": "2.0", "method": "blockchain.get_tx", "params": ["TxHash", true], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": {"hash": "...", "calls": [...], "proofs": [...], "signatures": [...]}, "id": 1}
     pub async fn blockchain_get_tx(&self, id: u16, params: JsonValue) -> JsonResult {
         let params = params.get::<Vec<JsonValue>>().unwrap();
-        if params.len() != 1 {
+        if params.is_empty() || params.len() > 2 || !params[0].is_string() {
             return JsonError::new(InvalidParams, None, id).into()
         }
 
+        let verbose = match params.get(1) {
+            Some(JsonValue::Boolean(v)) => *v,
+            Some(_) => return JsonError::new(InvalidParams, None, id).into(),
+            None => false,
+        };
+
         let tx_hash = params[0].get::<String>().unwrap();
         let tx_hash = match blake3::Hash::from_hex(tx_hash) {
             Ok(v) => v,
             Err(_) => return JsonError::new(ParseError, None, id).into(),
         };
 
-        let txs = match self.validator.read().await.blockchain.transactions.get(&[tx_hash], true) {
+        let txs = match self.validator.read().await.blockchain.transactions.get(&[tx_hash], false) {
             Ok(txs) => txs,
             Err(e) => {
                 error!(target: "darkfid::rpc::blockchain_get_tx", "Failed fetching tx by hash: {}", e);
                 return JsonError::new(InternalError, None, id).into()
             }
         };
-        // This would be an logic error somewhere
-        assert_eq!(txs.len(), 1);
-        // and strict was used during .get()
-        let tx = txs[0].as_ref().unwrap();
+        let Some(tx) = &txs[0] else { return server_error(RpcError::UnknownTx, id, None) };
+
+        if verbose {
+            return JsonResponse::new(self.decoders.decode_tx(tx), id).into()
+        }
 
         let tx_enc = base64::encode(&serialize(tx));
         JsonResponse::new(JsonValue::String(tx_enc), id).into()
@@ -141,6 +244,132 @@ impl Darkfid {
         JsonResponse::new(JsonValue::String(last_slot.0.to_string()), id).into()
     }
 
+    // RPCAPI:
+    // Queries the blockchain database for the root of the block header MMR
+    // (Merkle Mountain Range) over the first `size` blocks.
+    //
+    // **Params:**
+    // * `array[0]`: `u64` number of blocks covered by the MMR (as string)
+    //
+    // **Returns:**
+    // * Hex-encoded MMR root string
+    //
+    // --> {"jsonrpc": "2.0", "method": "blockchain.get_mmr_root", "params": ["10"], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": "MmrRoot", "id": 1}
+    pub async fn blockchain_get_mmr_root(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 1 || !params[0].is_string() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let Ok(size) = params[0].get::<String>().unwrap().parse::<u64>() else {
+            return JsonError::new(ParseError, None, id).into()
+        };
+
+        let blockchain = { self.validator.read().await.blockchain.clone() };
+        let root = match blockchain.mmr.get_root(size) {
+            Ok(v) => v,
+            Err(Error::MmrLeafOutOfRange(_, _)) => {
+                return server_error(RpcError::MmrOutOfRange, id, None)
+            }
+            Err(e) => {
+                error!(target: "darkfid::rpc::blockchain_get_mmr_root", "Failed computing MMR root: {}", e);
+                return JsonError::new(InternalError, None, id).into()
+            }
+        };
+
+        JsonResponse::new(JsonValue::String(root.to_hex().to_string()), id).into()
+    }
+
+    // RPCAPI:
+    // Builds a proof that the header of the block at the given height is part
+    // of the block header MMR over the first `size` blocks. The proof can be
+    // checked against the root returned by `blockchain.get_mmr_root` for the
+    // same size.
+    //
+    // **Params:**
+    // * `array[0]`: `u64` block height, i.e. the MMR leaf index (as string)
+    // * `array[1]`: `u64` number of blocks covered by the MMR (as string)
+    //
+    // **Returns:**
+    // * Serialized [`MmrProof`](https://darkrenaissance.github.io/darkfi/development/darkfi/blockchain/mmr_store/struct.MmrProof.html)
+    //   object encoded with base64
+    //
+    // --> {"jsonrpc": "2.0", "method": "blockchain.get_mmr_proof", "params": ["3", "10"], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": "ABCD...", "id": 1}
+    pub async fn blockchain_get_mmr_proof(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 2 || !params[0].is_string() || !params[1].is_string() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let Ok(leaf_index) = params[0].get::<String>().unwrap().parse::<u64>() else {
+            return JsonError::new(ParseError, None, id).into()
+        };
+
+        let Ok(size) = params[1].get::<String>().unwrap().parse::<u64>() else {
+            return JsonError::new(ParseError, None, id).into()
+        };
+
+        let blockchain = { self.validator.read().await.blockchain.clone() };
+        let proof = match blockchain.mmr.get_proof(leaf_index, size) {
+            Ok(v) => v,
+            Err(Error::MmrLeafOutOfRange(_, _)) => {
+                return server_error(RpcError::MmrOutOfRange, id, None)
+            }
+            Err(e) => {
+                error!(target: "darkfid::rpc::blockchain_get_mmr_proof", "Failed building MMR proof: {}", e);
+                return JsonError::new(InternalError, None, id).into()
+            }
+        };
+
+        let proof = base64::encode(&serialize(&proof));
+        JsonResponse::new(JsonValue::String(proof), id).into()
+    }
+
+    // RPCAPI:
+    // Exports one of the Money contract sets auditors need to verify the total
+    // supply, along with its sparse Merkle tree digest, as of the last block.
+    // Nothing on chain commits to the export, so its contents have to be
+    // trusted from this node. The digest can be recomputed from the elements with
+    // [`set_digest`](https://darkrenaissance.github.io/darkfi/development/darkfi/blockchain/set_export/fn.set_digest.html).
+    //
+    // **Params:**
+    // * `array[0]`: Set to export, either `"nullifiers"` or `"coins"`
+    //
+    // **Returns:**
+    // * Serialized [`SetExport`](https://darkrenaissance.github.io/darkfi/development/darkfi/blockchain/set_export/struct.SetExport.html)
+    //   object encoded with base64
+    //
+    // --> {"jsonrpc": "2.0", "method": "blockchain.export_money_set", "params": ["nullifiers"], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": "ABCD...", "id": 1}
+    pub async fn blockchain_export_money_set(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 1 || !params[0].is_string() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let tree_name = match params[0].get::<String>().unwrap().as_str() {
+            "nullifiers" => MONEY_CONTRACT_NULLIFIERS_TREE,
+            "coins" => MONEY_CONTRACT_COINS_TREE,
+            _ => return JsonError::new(InvalidParams, None, id).into(),
+        };
+
+        // Hold the validator lock, so no block gets applied while exporting
+        let validator = self.validator.read().await;
+        let export = match validator.blockchain.export_set(&MONEY_CONTRACT_ID, tree_name) {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "darkfid::rpc::blockchain_export_money_set", "Failed exporting set: {}", e);
+                return JsonError::new(InternalError, None, id).into()
+            }
+        };
+        drop(validator);
+
+        let export = base64::encode(&serialize(&export));
+        JsonResponse::new(JsonValue::String(export), id).into()
+    }
+
     // RPCAPI:
     // Initializes a subscription to new incoming blocks.
     // Once a subscription is established, `darkfid` will send JSON-RPC notifications of
@@ -197,6 +426,84 @@ impl Darkfid {
         proposals_subscriber.unwrap().clone().into()
     }
 
+    // RPCAPI:
+    // Returns the last checkpoint the node received from its configured authority
+    // set, with the slot and hash of the checkpointed block and the public keys
+    // of the authorities that signed it. Returns `null` if none was received yet.
+    //
+    // --> {"jsonrpc": "2.0", "method": "blockchain.last_checkpoint", "params": [], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": {"slot": 42, "block_hash": "...", "signers": ["..."]}, "id": 1}
+    pub async fn blockchain_last_checkpoint(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if !params.is_empty() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let lock = self.validator.read().await;
+        if lock.checkpoint_config.is_none() {
+            return server_error(RpcError::CheckpointsDisabled, id, None)
+        }
+
+        let checkpoint = match &lock.last_checkpoint {
+            Some(checkpoint) => checkpoint_to_json(checkpoint),
+            None => JsonValue::Null,
+        };
+
+        JsonResponse::new(checkpoint, id).into()
+    }
+
+    // RPCAPI:
+    // Initializes a subscription to new checkpoints received from the configured
+    // authority set. Once a subscription is established, `darkfid` will send JSON-RPC
+    // notifications of every accepted checkpoint to the subscriber.
+    //
+    // --> {"jsonrpc": "2.0", "method": "blockchain.subscribe_checkpoints", "params": [], "id": 1}
+    // <-- {"jsonrpc": "2.0", "method": "blockchain.subscribe_checkpoints", "params": [{"slot": 42, "block_hash": "...", "signers": ["..."]}]}
+    pub async fn blockchain_subscribe_checkpoints(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if !params.is_empty() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        self.subscribers.get("checkpoints").unwrap().clone().into()
+    }
+
+    // RPCAPI:
+    // Returns the most recent entries of the node's audit log, newest first.
+    // Entries record block and proposal acceptance or rejection, transaction
+    // rejections and fork switches or finalizations, along with the reason of
+    // rejections and the time they were recorded.
+    //
+    // **Params:**
+    // * `array[0]`: Maximum number of entries to return (integer)
+    //
+    // --> {"jsonrpc": "2.0", "method": "blockchain.audit_log", "params": [10], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": [{"id": 7, "timestamp": 1700000000, "event": "tx_rejected", "hash": "...", "reason": "..."}], "id": 1}
+    pub async fn blockchain_audit_log(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 1 || !params[0].is_number() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let limit = *params[0].get::<f64>().unwrap() as usize;
+
+        let lock = self.validator.read().await;
+        if !lock.audit_log.is_enabled() {
+            return server_error(RpcError::AuditLogDisabled, id, None)
+        }
+
+        let entries = match lock.audit_log.recent(limit) {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "darkfid::rpc::blockchain_audit_log", "Failed reading audit log: {}", e);
+                return JsonError::new(InternalError, None, id).into()
+            }
+        };
+
+        let entries = entries.iter().map(audit_entry_to_json).collect();
+        JsonResponse::new(JsonValue::Array(entries), id).into()
+    }
+
     // RPCAPI:
     // Performs a lookup of zkas bincodes for a given contract ID and returns all of
     // them, including their namespace.
@@ -262,4 +569,139 @@ impl Darkfid {
 
         JsonResponse::new(JsonValue::Array(ret), id).into()
     }
+
+    // RPCAPI:
+    // Looks up the metadata registered on-chain for a token, so wallets can
+    // display human-readable token information. The token is given either by
+    // its ID, or by its issuer's public key and the symbol nonce its mint
+    // authority was derived with. Returns `null` if no metadata is registered.
+    //
+    // **Params:**
+    // * `array[0]`: base58-encoded token ID, or issuer public key string
+    // * `array[1]`: `u64` symbol nonce, if an issuer public key was given
+    //
+    // --> {"jsonrpc": "2.0", "method": "blockchain.lookup_token", "params": ["9pK6e7UZ..."], "id": 1}
+    // --> {"jsonrpc": "2.0", "method": "blockchain.lookup_token", "params": ["5oT8hYc9...", 0], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": {"token_id": "9pK6e7UZ...", "metadata": {"issuer": "5oT8hYc9...", "symbol_nonce": 0, "name": "Dark", "symbol": "DRK", "decimals": 8, "uri": ""}}, "id": 1}
+    pub async fn blockchain_lookup_token(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.is_empty() || params.len() > 2 || !params[0].is_string() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let blockchain = { self.validator.read().await.blockchain.clone() };
+        let key = params[0].get::<String>().unwrap();
+
+        let token_id = match params.get(1) {
+            None => match TokenId::from_str(key) {
+                Ok(v) => v,
+                Err(e) => {
+                    error!(target: "darkfid::rpc::blockchain_lookup_token", "Error decoding string to TokenId: {}", e);
+                    return JsonError::new(InvalidParams, None, id).into()
+                }
+            },
+            Some(JsonValue::Number(nonce)) => {
+                let Ok(issuer) = PublicKey::from_str(key) else {
+                    error!(target: "darkfid::rpc::blockchain_lookup_token", "Error decoding string to PublicKey");
+                    return JsonError::new(InvalidParams, None, id).into()
+                };
+
+                let Ok(issuers_db) = blockchain.contracts.lookup(
+                    &blockchain.sled_db,
+                    &MONEY_CONTRACT_ID,
+                    MONEY_CONTRACT_TOKEN_ISSUERS_TREE,
+                ) else {
+                    return JsonError::new(InternalError, None, id).into()
+                };
+
+                match issuers_db.get(serialize(&(issuer, *nonce as u64))) {
+                    Ok(Some(v)) => match deserialize(&v) {
+                        Ok(v) => v,
+                        Err(_) => return JsonError::new(InternalError, None, id).into(),
+                    },
+                    Ok(None) => return JsonResponse::new(JsonValue::Null, id).into(),
+                    Err(_) => return JsonError::new(InternalError, None, id).into(),
+                }
+            }
+            Some(_) => return JsonError::new(InvalidParams, None, id).into(),
+        };
+
+        let Ok(metadata_db) = blockchain.contracts.lookup(
+            &blockchain.sled_db,
+            &MONEY_CONTRACT_ID,
+            MONEY_CONTRACT_TOKEN_METADATA_TREE,
+        ) else {
+            return JsonError::new(InternalError, None, id).into()
+        };
+
+        let metadata: TokenMetadata = match metadata_db.get(serialize(&token_id)) {
+            Ok(Some(v)) => match deserialize(&v) {
+                Ok(v) => v,
+                Err(_) => return JsonError::new(InternalError, None, id).into(),
+            },
+            Ok(None) => return JsonResponse::new(JsonValue::Null, id).into(),
+            Err(_) => return JsonError::new(InternalError, None, id).into(),
+        };
+
+        let result = object([
+            ("token_id", JsonValue::String(token_id.to_string())),
+            ("metadata", token_metadata(&metadata)),
+        ]);
+
+        JsonResponse::new(result, id).into()
+    }
+
+    // RPCAPI:
+    // Queries the supply of a token recorded on-chain by its mints, so anyone
+    // can check no hidden inflation occurred. The supply is the homomorphic
+    // sum of the value commitments of the token's minted coins. For tokens
+    // whose every mint was transparent, the opening of the sum is returned
+    // too, and should satisfy `value_commit == value * G + blind * H`.
+    // Returns `null` if the token was never minted. The native token isn't
+    // tracked, as it's also minted by staking rewards.
+    //
+    // **Params:**
+    // * `array[0]`: base58-encoded token ID
+    //
+    // --> {"jsonrpc": "2.0", "method": "blockchain.token_supply", "params": ["9pK6e7UZ..."], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": {"token_id": "9pK6e7UZ...", "supply": {"value_commit": "a1b2...", "opening": {"value": 100, "blind": "c3d4..."}}}, "id": 1}
+    pub async fn blockchain_token_supply(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 1 || !params[0].is_string() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let token_id = match TokenId::from_str(params[0].get::<String>().unwrap()) {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "darkfid::rpc::blockchain_token_supply", "Error decoding string to TokenId: {}", e);
+                return JsonError::new(InvalidParams, None, id).into()
+            }
+        };
+
+        let blockchain = { self.validator.read().await.blockchain.clone() };
+        let Ok(supply_db) = blockchain.contracts.lookup(
+            &blockchain.sled_db,
+            &MONEY_CONTRACT_ID,
+            MONEY_CONTRACT_TOKEN_SUPPLY_TREE,
+        ) else {
+            return JsonError::new(InternalError, None, id).into()
+        };
+
+        let supply: TokenSupply = match supply_db.get(serialize(&token_id)) {
+            Ok(Some(v)) => match deserialize(&v) {
+                Ok(v) => v,
+                Err(_) => return JsonError::new(InternalError, None, id).into(),
+            },
+            Ok(None) => return JsonResponse::new(JsonValue::Null, id).into(),
+            Err(_) => return JsonError::new(InternalError, None, id).into(),
+        };
+
+        let result = object([
+            ("token_id", JsonValue::String(token_id.to_string())),
+            ("supply", token_supply(&supply)),
+        ]);
+
+        JsonResponse::new(result, id).into()
+    }
 }
@@ -0,0 +1,334 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::collections::HashMap;
+
+use darkfi_serial::{deserialize, serialize};
+use log::{error, info};
+use tinyjson::JsonValue;
+
+use darkfi::{
+    blockchain::BlockInfo,
+    rpc::jsonrpc::{
+        ErrorCode::{InternalError, InvalidParams, ParseError},
+        JsonError, JsonResponse, JsonResult,
+    },
+    util::encoding::base64,
+    validator::{checkpoint::Checkpoint, consensus::BlockTemplate},
+};
+
+use super::Darkfid;
+use crate::{
+    decode::{number, object},
+    proto::{CheckpointMessage, ProposalMessage},
+    server_error,
+    utils::checkpoint_to_json,
+    RpcError,
+};
+
+impl Darkfid {
+    // RPCAPI:
+    // Propose a block assembled by an external block producer.
+    // The template contains the block transactions and the block producer
+    // transaction. The node builds the block on top of its longest fork,
+    // validates it against all consensus rules, signs it with its configured
+    // block producer key and relays it to the consensus network.
+    // Returns the hash of the proposed block.
+    //
+    // --> {"jsonrpc": "2.0", "method": "consensus.propose_block", "params": ["base64encodedTemplate"], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": "blockHash...", "id": 1}
+    pub async fn consensus_propose_block(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 1 || !params[0].is_string() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let Some(secret_key) = self.block_producer_key else {
+            error!(target: "darkfid::rpc::consensus_propose_block", "External block production is not enabled");
+            return server_error(RpcError::BlockProductionDisabled, id, None)
+        };
+
+        let Some(ref consensus_p2p) = self.consensus_p2p else {
+            error!(target: "darkfid::rpc::consensus_propose_block", "Node is not participating in consensus");
+            return server_error(RpcError::NotParticipating, id, None)
+        };
+
+        if !self.validator.read().await.synced {
+            error!(target: "darkfid::rpc::consensus_propose_block", "Blockchain is not synced");
+            return server_error(RpcError::NotSynced, id, None)
+        }
+
+        if !self.validator.read().await.consensus.participating {
+            error!(target: "darkfid::rpc::consensus_propose_block", "Node is not participating in consensus");
+            return server_error(RpcError::NotParticipating, id, None)
+        }
+
+        // Try to deserialize the template
+        let template_enc = params[0].get::<String>().unwrap().trim();
+        let template_bytes = match base64::decode(template_enc) {
+            Some(v) => v,
+            None => {
+                error!(target: "darkfid::rpc::consensus_propose_block", "Failed decoding base64 template");
+                return server_error(RpcError::ParseError, id, None)
+            }
+        };
+
+        let template: BlockTemplate = match deserialize(&template_bytes) {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "darkfid::rpc::consensus_propose_block", "Failed deserializing bytes into BlockTemplate: {}", e);
+                return server_error(RpcError::ParseError, id, None)
+            }
+        };
+
+        // Build the proposal and pass it through the same checks
+        // as proposals received from the network.
+        let mut lock = self.validator.write().await;
+        let proposal = match lock
+            .consensus
+            .generate_proposal_from_template(secret_key, template)
+            .await
+        {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "darkfid::rpc::consensus_propose_block", "Invalid block template: {}", e);
+                let msg = format!("Block proposal rejected: {}", e);
+                return server_error(RpcError::ProposalRejected, id, Some(&msg))
+            }
+        };
+
+        if let Err(e) = lock.consensus.append_proposal(&proposal).await {
+            error!(target: "darkfid::rpc::consensus_propose_block", "Proposal failed consensus checks: {}", e);
+            let msg = format!("Block proposal rejected: {}", e);
+            return server_error(RpcError::ProposalRejected, id, Some(&msg))
+        }
+        drop(lock);
+
+        info!(target: "darkfid::rpc::consensus_propose_block", "Proposing externally produced block: {}", proposal.hash);
+        let message = ProposalMessage(proposal);
+        consensus_p2p.broadcast(&message).await;
+        if let Some(subscriber) = self.subscribers.get("proposals") {
+            let enc_prop = JsonValue::String(base64::encode(&serialize(&message)));
+            subscriber.notify(vec![enc_prop]).await;
+        }
+
+        JsonResponse::new(JsonValue::String(message.0.hash.to_string()), id).into()
+    }
+
+    // RPCAPI:
+    // Sign a checkpoint over the finalized block of the given slot with the
+    // node's configured checkpoint authority key. If a base64-encoded checkpoint
+    // already signed by other authorities is given, the signature is added to it,
+    // so authorities can pass it along until it reaches the threshold.
+    // Returns the base64-encoded checkpoint, ready for `consensus.submit_checkpoint`.
+    //
+    // --> {"jsonrpc": "2.0", "method": "consensus.sign_checkpoint", "params": ["42", "base64encodedCheckpoint"], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": "base64encodedCheckpoint", "id": 1}
+    pub async fn consensus_sign_checkpoint(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.is_empty() || params.len() > 2 || !params.iter().all(|p| p.is_string()) {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let Some(secret_key) = self.checkpoint_key else {
+            error!(target: "darkfid::rpc::consensus_sign_checkpoint", "Checkpoint signing is not enabled");
+            return server_error(RpcError::CheckpointSigningDisabled, id, None)
+        };
+
+        let slot = match params[0].get::<String>().unwrap().parse::<u64>() {
+            Ok(v) => v,
+            Err(_) => return JsonError::new(ParseError, None, id).into(),
+        };
+
+        // Only blocks we have finalized ourselves can be checkpointed
+        let block_hash = match self.validator.read().await.blockchain.order.get(&[slot], false) {
+            Ok(v) => v[0],
+            Err(e) => {
+                error!(target: "darkfid::rpc::consensus_sign_checkpoint", "Failed fetching block by slot: {}", e);
+                return JsonError::new(InternalError, None, id).into()
+            }
+        };
+        let Some(block_hash) = block_hash else {
+            return server_error(RpcError::UnknownSlot, id, None)
+        };
+
+        let mut checkpoint = match params.get(1) {
+            Some(partial) => {
+                let partial = partial.get::<String>().unwrap().trim();
+                let Some(bytes) = base64::decode(partial) else {
+                    error!(target: "darkfid::rpc::consensus_sign_checkpoint", "Failed decoding base64 checkpoint");
+                    return server_error(RpcError::ParseError, id, None)
+                };
+                let checkpoint: Checkpoint = match deserialize(&bytes) {
+                    Ok(v) => v,
+                    Err(e) => {
+                        error!(target: "darkfid::rpc::consensus_sign_checkpoint", "Failed deserializing bytes into Checkpoint: {}", e);
+                        return server_error(RpcError::ParseError, id, None)
+                    }
+                };
+                if checkpoint.slot != slot || checkpoint.block_hash != block_hash {
+                    let msg = "Checkpoint doesn't match our finalized block";
+                    return server_error(RpcError::CheckpointRejected, id, Some(msg))
+                }
+                checkpoint
+            }
+            None => Checkpoint::new(slot, block_hash),
+        };
+
+        checkpoint.sign(&secret_key);
+        info!(target: "darkfid::rpc::consensus_sign_checkpoint", "Signed checkpoint for block {} at slot {}", block_hash, slot);
+
+        JsonResponse::new(JsonValue::String(base64::encode(&serialize(&checkpoint))), id).into()
+    }
+
+    // RPCAPI:
+    // Submit a checkpoint signed by the configured authority set. The node
+    // verifies it, keeps it if it's newer than the last one and relays it to
+    // the sync network. Returns `true` if the checkpoint was new.
+    //
+    // --> {"jsonrpc": "2.0", "method": "consensus.submit_checkpoint", "params": ["base64encodedCheckpoint"], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": true, "id": 1}
+    pub async fn consensus_submit_checkpoint(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 1 || !params[0].is_string() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        if self.validator.read().await.checkpoint_config.is_none() {
+            error!(target: "darkfid::rpc::consensus_submit_checkpoint", "Node has no checkpoint authorities configured");
+            return server_error(RpcError::CheckpointsDisabled, id, None)
+        }
+
+        // Try to deserialize the checkpoint
+        let checkpoint_enc = params[0].get::<String>().unwrap().trim();
+        let checkpoint_bytes = match base64::decode(checkpoint_enc) {
+            Some(v) => v,
+            None => {
+                error!(target: "darkfid::rpc::consensus_submit_checkpoint", "Failed decoding base64 checkpoint");
+                return server_error(RpcError::ParseError, id, None)
+            }
+        };
+
+        let checkpoint: Checkpoint = match deserialize(&checkpoint_bytes) {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "darkfid::rpc::consensus_submit_checkpoint", "Failed deserializing bytes into Checkpoint: {}", e);
+                return server_error(RpcError::ParseError, id, None)
+            }
+        };
+
+        let new = match self.validator.write().await.receive_checkpoint(&checkpoint) {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "darkfid::rpc::consensus_submit_checkpoint", "Checkpoint rejected: {}", e);
+                let msg = format!("Checkpoint rejected: {}", e);
+                return server_error(RpcError::CheckpointRejected, id, Some(&msg))
+            }
+        };
+
+        if new {
+            self.subscribers
+                .get("checkpoints")
+                .unwrap()
+                .notify(vec![checkpoint_to_json(&checkpoint)])
+                .await;
+            self.sync_p2p.broadcast(&CheckpointMessage(checkpoint)).await;
+        }
+
+        JsonResponse::new(JsonValue::Boolean(new), id).into()
+    }
+
+    // RPCAPI:
+    // Returns the node's current fork tree, for monitoring fork rates.
+    // Along with the canonical tip, each fork reports its tip hash, slot and
+    // height, its length in proposals, the height of the last block it shares
+    // with any other fork, and how many of its proposals each block producer
+    // proposed, identified by the proposal transaction signature public key.
+    //
+    // --> {"jsonrpc": "2.0", "method": "consensus.get_forks", "params": [], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": {"canonical": {...}, "forks": [...]}, "id": 1}
+    pub async fn consensus_get_forks(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if !params.is_empty() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let validator = self.validator.read().await;
+        let (canonical_slot, canonical_hash) = match validator.blockchain.last() {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "darkfid::rpc::consensus_get_forks", "Failed retrieving canonical tip: {}", e);
+                return JsonError::new(InternalError, None, id).into()
+            }
+        };
+        let canonical_height = validator.blockchain.len().saturating_sub(1) as u64;
+
+        let summaries = match validator.consensus.forks_summary() {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "darkfid::rpc::consensus_get_forks", "Failed summarizing forks: {}", e);
+                return JsonError::new(InternalError, None, id).into()
+            }
+        };
+        drop(validator);
+
+        let mut forks = Vec::with_capacity(summaries.len());
+        for summary in summaries {
+            let mut proposers: HashMap<String, JsonValue> = HashMap::new();
+            for block in &summary.blocks {
+                let proposer = self.block_proposer(block);
+                let count = proposers.get(&proposer).map_or(0.0, |c| *c.get::<f64>().unwrap());
+                proposers.insert(proposer, JsonValue::Number(count + 1.0));
+            }
+
+            forks.push(object([
+                ("tip", JsonValue::String(summary.tip.to_string())),
+                ("tip_slot", number(summary.tip_slot)),
+                ("height", number(summary.height)),
+                ("length", number(summary.blocks.len() as u64)),
+                ("common_ancestor", number(summary.common_ancestor)),
+                ("proposers", JsonValue::Object(proposers)),
+            ]));
+        }
+
+        let canonical = object([
+            ("hash", JsonValue::String(canonical_hash.to_string())),
+            ("slot", number(canonical_slot)),
+            ("height", number(canonical_height)),
+        ]);
+
+        JsonResponse::new(
+            object([("canonical", canonical), ("forks", JsonValue::Array(forks))]),
+            id,
+        )
+        .into()
+    }
+
+    /// Identify the producer of the given block by the signature public key
+    /// of its proposal transaction, or "unknown" if it can't be decoded.
+    fn block_proposer(&self, block: &BlockInfo) -> String {
+        let Some(call) = block.producer.proposal.calls.first() else {
+            return "unknown".to_string()
+        };
+
+        match &self.decoders.decode_call(call)["params"]["input"]["signature_public"] {
+            JsonValue::String(public) => public.clone(),
+            _ => "unknown".to_string(),
+        }
+    }
+}
@@ -16,36 +16,63 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use std::{collections::HashMap, str::FromStr};
+
+use darkfi_sdk::crypto::ContractId;
 use darkfi_serial::deserialize;
 use log::error;
 use tinyjson::JsonValue;
 
 use darkfi::{
+    consensus::fees::FEE_CONFIRMATION_TARGETS,
+    error::TxVerifyFailed,
     rpc::jsonrpc::{
         ErrorCode::{InternalError, InvalidParams},
         JsonError, JsonResponse, JsonResult,
     },
     tx::Transaction,
     util::encoding::base64,
+    Error,
 };
 
 use super::Darkfid;
-use crate::{server_error, RpcError};
+use crate::{
+    decode::{number, object},
+    error::tx_verify_error,
+    server_error,
+    task::pending::{filtered_subscriber, PendingTxFilter},
+    RpcError,
+};
 
 impl Darkfid {
     // RPCAPI:
     // Simulate a network state transition with the given transaction.
     // Returns `true` if the transaction is valid, otherwise, a corresponding
-    // error.
+    // error describing why verification failed.
+    // If the optional second param is `true`, the simulation runs in estimate
+    // mode: the transaction doesn't have to pay any fee yet, and the gas it
+    // used is returned along with the fee it has to pay at the current gas price.
+    // If verification failed in one of the transaction's calls, the error's
+    // data holds the call index, its contract ID and the phase that failed:
+    // `metadata`, `exec`, `apply`, `sig` or `zk`.
     //
     // --> {"jsonrpc": "2.0", "method": "tx.simulate", "params": ["base64encodedTX"], "id": 1}
     // <-- {"jsonrpc": "2.0", "result": true, "id": 1}
+    //
+    // --> {"jsonrpc": "2.0", "method": "tx.simulate", "params": ["base64encodedTX", true], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": {"gas": {"wasm": 1000, "signatures": 50000, "zk_circuits": 0, "storage": 0, "total": 51000}, "fee": 510}, "id": 1}
     pub async fn tx_simulate(&self, id: u16, params: JsonValue) -> JsonResult {
         let params = params.get::<Vec<JsonValue>>().unwrap();
-        if params.len() != 1 || !params[0].is_string() {
+        if params.is_empty() || params.len() > 2 || !params[0].is_string() {
             return JsonError::new(InvalidParams, None, id).into()
         }
 
+        let estimate = match params.get(1) {
+            Some(JsonValue::Boolean(v)) => *v,
+            Some(_) => return JsonError::new(InvalidParams, None, id).into(),
+            None => false,
+        };
+
         if !self.validator.read().await.synced {
             error!(target: "darkfid::rpc::tx_simulate", "Blockchain is not synced");
             return server_error(RpcError::NotSynced, id, None)
@@ -72,16 +99,164 @@ impl Darkfid {
         // Simulate state transition
         let lock = self.validator.read().await;
         let current_slot = lock.consensus.time_keeper.current_slot();
-        let result = lock.add_transactions(&[tx], current_slot, false).await;
-        if result.is_err() {
-            error!(
-                target: "darkfid::rpc::tx_simulate", "Failed to validate state transition: {}",
-                result.err().unwrap()
-            );
-            return server_error(RpcError::TxSimulationFail, id, None)
+        let result = if estimate {
+            match lock.estimate_transaction_gas(&tx, current_slot).await {
+                Ok(gas) => lock.fee_model().map(|model| Some((gas, model.min_fee(&gas)))),
+                Err(e) => Err(e),
+            }
+        } else {
+            lock.simulate_transaction(&tx, current_slot).await.map(|()| None)
+        };
+
+        let gas = match result {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "darkfid::rpc::tx_simulate", "Failed to validate state transition: {}", e);
+                // Give the caller the verification failure, e.g. the contract's
+                // error report, along with the call that caused it.
+                return tx_verify_error(RpcError::TxSimulationFail, id, &e)
+            }
+        };
+
+        let Some((gas, fee)) = gas else {
+            return JsonResponse::new(JsonValue::Boolean(true), id).into()
+        };
+
+        let result = object([
+            (
+                "gas",
+                object([
+                    ("wasm", number(gas.wasm)),
+                    ("signatures", number(gas.signatures)),
+                    ("zk_circuits", number(gas.zk_circuits)),
+                    ("storage", number(gas.storage)),
+                    ("total", number(gas.total())),
+                ]),
+            ),
+            ("fee", number(fee)),
+        ]);
+
+        JsonResponse::new(result, id).into()
+    }
+
+    // RPCAPI:
+    // Simulate a network state transition with the given transaction, like
+    // `tx.simulate`, additionally returning the structured debug traces its
+    // contract calls emitted. Traces are returned even if the simulation
+    // failed. Only available when the node runs in testing mode.
+    //
+    // --> {"jsonrpc": "2.0", "method": "tx.simulate_trace", "params": ["base64encodedTX"], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": {"valid": false, "error": "...", "traces": [{"call": 0, "key": "...", "branch": "...", "values": ["..."]}]}, "id": 1}
+    pub async fn tx_simulate_trace(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 1 || !params[0].is_string() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let lock = self.validator.read().await;
+        if !lock.testing_mode {
+            error!(target: "darkfid::rpc::tx_simulate_trace", "Node is not in testing mode");
+            return server_error(RpcError::TracingDisabled, id, None)
+        }
+
+        if !lock.synced {
+            error!(target: "darkfid::rpc::tx_simulate_trace", "Blockchain is not synced");
+            return server_error(RpcError::NotSynced, id, None)
+        }
+
+        // Try to deserialize the transaction
+        let tx_enc = params[0].get::<String>().unwrap().trim();
+        let tx_bytes = match base64::decode(tx_enc) {
+            Some(v) => v,
+            None => {
+                error!(target: "darkfid::rpc::tx_simulate_trace", "Failed decoding base64 transaction");
+                return server_error(RpcError::ParseError, id, None)
+            }
+        };
+
+        let tx: Transaction = match deserialize(&tx_bytes) {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "darkfid::rpc::tx_simulate_trace", "Failed deserializing bytes into Transaction: {}", e);
+                return server_error(RpcError::ParseError, id, None)
+            }
+        };
+
+        // Simulate state transition, collecting the contract traces
+        let current_slot = lock.consensus.time_keeper.current_slot();
+        let (result, traces) = match lock.simulate_transaction_traced(&tx, current_slot).await {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "darkfid::rpc::tx_simulate_trace", "Failed to simulate transaction: {}", e);
+                return JsonError::new(InternalError, None, id).into()
+            }
+        };
+
+        let error = match result {
+            Ok(()) => JsonValue::Null,
+            Err(e) => JsonValue::String(e.to_string()),
+        };
+
+        let traces = traces
+            .into_iter()
+            .map(|(call, trace)| {
+                let branch = match trace.branch {
+                    Some(branch) => JsonValue::String(branch),
+                    None => JsonValue::Null,
+                };
+                let values = trace.values.into_iter().map(JsonValue::String).collect();
+                JsonValue::Object(HashMap::from([
+                    ("call".to_string(), JsonValue::Number(call as f64)),
+                    ("key".to_string(), JsonValue::String(trace.key)),
+                    ("branch".to_string(), branch),
+                    ("values".to_string(), JsonValue::Array(values)),
+                ]))
+            })
+            .collect();
+
+        let result = HashMap::from([
+            ("valid".to_string(), JsonValue::Boolean(error.is_null())),
+            ("error".to_string(), error),
+            ("traces".to_string(), JsonValue::Array(traces)),
+        ]);
+
+        JsonResponse::new(JsonValue::Object(result), id).into()
+    }
+
+    // RPCAPI:
+    // Decode the given transaction into human-readable JSON. Each call of a
+    // native contract is decoded into its function name and params, with
+    // commitments and other field elements hex-encoded. Calls that can't be
+    // decoded have a `null` function and params. All calls carry their raw
+    // hex-encoded data.
+    //
+    // --> {"jsonrpc": "2.0", "method": "tx.decode", "params": ["base64encodedTX"], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": {"hash": "...", "calls": [{"contract_id": "...", "function": "TransferV1", "params": {...}, "data": "..."}], "proofs": [2], "signatures": [2]}, "id": 1}
+    pub async fn tx_decode(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 1 || !params[0].is_string() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        // Try to deserialize the transaction
+        let tx_enc = params[0].get::<String>().unwrap().trim();
+        let tx_bytes = match base64::decode(tx_enc) {
+            Some(v) => v,
+            None => {
+                error!(target: "darkfid::rpc::tx_decode", "Failed decoding base64 transaction");
+                return server_error(RpcError::ParseError, id, None)
+            }
+        };
+
+        let tx: Transaction = match deserialize(&tx_bytes) {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "darkfid::rpc::tx_decode", "Failed deserializing bytes into Transaction: {}", e);
+                return server_error(RpcError::ParseError, id, None)
+            }
         };
 
-        JsonResponse::new(JsonValue::Boolean(true), id).into()
+        JsonResponse::new(self.decoders.decode_tx(&tx), id).into()
     }
 
     // RPCAPI:
@@ -125,21 +300,21 @@ impl Darkfid {
             // Consensus participants can directly perform
             // the state transition check and append to their
             // pending transactions store.
-            if self.validator.write().await.append_tx(&tx).await.is_err() {
-                error!(target: "darkfid::rpc::tx_broadcast", "Failed to append transaction to mempool");
-                return server_error(RpcError::TxSimulationFail, id, None)
+            if let Err(e) = self.validator.write().await.append_tx(&tx).await {
+                error!(target: "darkfid::rpc::tx_broadcast", "Failed to append transaction to mempool: {}", e);
+                let e = self.verification_failure(&tx, e).await;
+                return tx_verify_error(RpcError::TxSimulationFail, id, &e)
             }
         } else {
             // We'll perform the state transition check here.
             let lock = self.validator.read().await;
             let current_slot = lock.consensus.time_keeper.current_slot();
             let result = lock.add_transactions(&[tx.clone()], current_slot, false).await;
-            if result.is_err() {
-                error!(
-                    target: "darkfid::rpc::tx_broadcast", "Failed to validate state transition: {}",
-                    result.err().unwrap()
-                );
-                return server_error(RpcError::TxSimulationFail, id, None)
+            drop(lock);
+            if let Err(e) = result {
+                error!(target: "darkfid::rpc::tx_broadcast", "Failed to validate state transition: {}", e);
+                let e = self.verification_failure(&tx, e).await;
+                return tx_verify_error(RpcError::TxSimulationFail, id, &e)
             };
         }
 
@@ -149,10 +324,58 @@ impl Darkfid {
             return server_error(RpcError::TxBroadcastFail, id, None)
         }
 
+        // Keep rebroadcasting the transaction until it gets confirmed
         let tx_hash = tx.hash().to_string();
+        self.rebroadcaster.track(tx).await;
+
         JsonResponse::new(JsonValue::String(tx_hash), id).into()
     }
 
+    // RPCAPI:
+    // Returns the rebroadcast status of the transactions submitted through
+    // this node with `tx.broadcast`, or of a single one if its hash is given.
+    // Unconfirmed transactions get rebroadcast with an exponential backoff
+    // until they are `confirmed`, `expired` or `replaced`.
+    //
+    // --> {"jsonrpc": "2.0", "method": "tx.rebroadcast_status", "params": ["TxHash"], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": [{"tx_hash": "...", "status": "pending", "attempts": 2, ...}], "id": 1}
+    pub async fn tx_rebroadcast_status(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() > 1 || (params.len() == 1 && !params[0].is_string()) {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let filter = match params.first() {
+            Some(tx_hash) => match blake3::Hash::from_hex(tx_hash.get::<String>().unwrap()) {
+                Ok(v) => Some(v),
+                Err(_) => return JsonError::new(InvalidParams, None, id).into(),
+            },
+            None => None,
+        };
+
+        let entries = self.rebroadcaster.entries.lock().await;
+        let mut ret = vec![];
+        for (tx_hash, entry) in entries.iter() {
+            if filter.is_some_and(|f| f != *tx_hash) {
+                continue
+            }
+
+            ret.push(object([
+                ("tx_hash", JsonValue::String(tx_hash.to_string())),
+                ("status", JsonValue::String(entry.status.as_str().to_string())),
+                ("attempts", number(entry.attempts as u64)),
+                ("submitted", number(entry.submitted.0)),
+                ("next_attempt", number(entry.next_attempt.0)),
+            ]));
+        }
+
+        if filter.is_some() && ret.is_empty() {
+            return server_error(RpcError::UnknownTx, id, None)
+        }
+
+        JsonResponse::new(JsonValue::Array(ret), id).into()
+    }
+
     // RPCAPI:
     // Queries the node pending transactions store to retrieve all transactions.
     // Returns a vector of hex-encoded transaction hashes.
@@ -219,4 +442,155 @@ impl Darkfid {
 
         JsonResponse::new(JsonValue::Array(pending_txs), id).into()
     }
+
+    // RPCAPI:
+    // Initializes a subscription to mempool conflicts, i.e. incoming transactions
+    // spending the same state as pending ones. Once a subscription is established,
+    // `darkfid` will send JSON-RPC notifications with the incoming transaction hash,
+    // the hashes of the pending transactions it conflicts with, and whether it
+    // replaced them. Under the default first-seen conflict policy the incoming
+    // transaction is always rejected, while under fee replacement it replaces
+    // the pending ones if it paid a higher fee than each of them.
+    //
+    // --> {"jsonrpc": "2.0", "method": "tx.subscribe_conflicts", "params": [], "id": 1}
+    // <-- {"jsonrpc": "2.0", "method": "tx.subscribe_conflicts", "params": [`tx_hash`, [`tx_hash`, ...], `replaced`]}
+    pub async fn tx_subscribe_conflicts(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if !params.is_empty() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        self.subscribers.get("conflicts").unwrap().clone().into()
+    }
+
+    // RPCAPI:
+    // Initializes a subscription to new pending transactions. Once a subscription
+    // is established, `darkfid` will send JSON-RPC notifications whenever a
+    // transaction is appended to the node's pending transactions store, with its
+    // hash and the contract ID and function name of each of its calls.
+    //
+    // Optionally, a contract ID and a function byte can be provided, so that
+    // the node only pushes notifications of transactions having a call to that
+    // contract, and function.
+    //
+    // --> {"jsonrpc": "2.0", "method": "tx.subscribe_pending", "params": [], "id": 1}
+    // --> {"jsonrpc": "2.0", "method": "tx.subscribe_pending", "params": ["contract_id", 2], "id": 1}
+    // <-- {"jsonrpc": "2.0", "method": "tx.subscribe_pending", "params": [`tx_hash`, [{"contract_id": "...", "function": "TransferV1"}, ...]]}
+    pub async fn tx_subscribe_pending(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.is_empty() {
+            return self.subscribers.get("pending_txs").unwrap().clone().into()
+        }
+
+        if params.len() > 2 || !params[0].is_string() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let contract_id = params[0].get::<String>().unwrap();
+        let Ok(contract_id) = ContractId::from_str(contract_id) else {
+            return JsonError::new(InvalidParams, None, id).into()
+        };
+
+        let function = match params.get(1) {
+            Some(JsonValue::Number(f)) if f.fract() == 0.0 && (0.0..=255.0).contains(f) => {
+                Some(*f as u8)
+            }
+            Some(_) => return JsonError::new(InvalidParams, None, id).into(),
+            None => None,
+        };
+
+        let filter = PendingTxFilter { contract_id, function };
+        match filtered_subscriber(&self.pending_filters, filter).await {
+            Some(subscriber) => subscriber.into(),
+            None => server_error(RpcError::TooManyPendingFilters, id, None),
+        }
+    }
+
+    // RPCAPI:
+    // Returns the node's mempool conflict counters: the number of incoming
+    // transactions rejected for conflicting with pending ones, and the number
+    // of them that replaced the pending ones by paying a higher fee.
+    //
+    // --> {"jsonrpc": "2.0", "method": "tx.mempool_metrics", "params": [], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": {"conflicts": 3, "replacements": 1}, "id": 1}
+    pub async fn tx_mempool_metrics(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if !params.is_empty() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let metrics = self.validator.read().await.mempool_metrics.clone();
+        let metrics = HashMap::from([
+            ("conflicts".to_string(), JsonValue::Number(metrics.conflicts as f64)),
+            ("replacements".to_string(), JsonValue::Number(metrics.replacements as f64)),
+        ]);
+
+        JsonResponse::new(JsonValue::Object(metrics), id).into()
+    }
+
+    // RPCAPI:
+    // Suggests gas prices for a transaction to get included within each of
+    // the given confirmation targets, in blocks, taking into account the load
+    // of recent blocks and the current mempool depth. Prices are expressed in
+    // thousandths of a fee unit per 100 gas. `minimum` is the price the node
+    // currently verifies transaction fees against. Without params, estimates
+    // are given for 1, 3 and 10 blocks.
+    //
+    // --> {"jsonrpc": "2.0", "method": "fee.estimate", "params": [1, 3, 10], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": {"minimum": 1000, "pending_txs": 120, "estimates": [{"target": 1, "gas_price": 2400}, {"target": 3, "gas_price": 1000}, {"target": 10, "gas_price": 1000}]}, "id": 1}
+    pub async fn fee_estimate(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        let mut targets = Vec::with_capacity(params.len());
+        for param in params {
+            let Some(target) = param.get::<f64>() else {
+                return JsonError::new(InvalidParams, None, id).into()
+            };
+            if *target < 1.0 || target.fract() != 0.0 {
+                return JsonError::new(InvalidParams, None, id).into()
+            }
+            targets.push(*target as u64);
+        }
+        if targets.is_empty() {
+            targets = FEE_CONFIRMATION_TARGETS.to_vec();
+        }
+
+        let lock = self.validator.read().await;
+        let (model, pending_txs) = match (lock.fee_model(), lock.blockchain.get_pending_txs()) {
+            (Ok(model), Ok(pending_txs)) => (model, pending_txs.len()),
+            (Err(e), _) | (_, Err(e)) => {
+                error!(target: "darkfid::rpc::fee_estimate", "Failed retrieving fee model: {}", e);
+                return JsonError::new(InternalError, None, id).into()
+            }
+        };
+        drop(lock);
+
+        let estimates = targets
+            .into_iter()
+            .map(|target| {
+                let gas_price = model.suggest(pending_txs, target).gas_price;
+                object([("target", number(target)), ("gas_price", number(gas_price))])
+            })
+            .collect();
+
+        let result = object([
+            ("minimum", number(model.gas_price)),
+            ("pending_txs", number(pending_txs as u64)),
+            ("estimates", JsonValue::Array(estimates)),
+        ]);
+
+        JsonResponse::new(result, id).into()
+    }
+
+    /// Mempool admission only reports which transactions failed verification,
+    /// so when that's all we got, simulate the transaction against canonical
+    /// state to find out why, e.g. which of its calls broke.
+    async fn verification_failure(&self, tx: &Transaction, e: Error) -> Error {
+        if !matches!(e, Error::TxVerifyFailed(TxVerifyFailed::ErroneousTxs(_))) {
+            return e
+        }
+
+        let lock = self.validator.read().await;
+        let current_slot = lock.consensus.time_keeper.current_slot();
+        lock.simulate_transaction(tx, current_slot).await.err().unwrap_or(e)
+    }
 }
@@ -0,0 +1,45 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{rpc::jsonrpc::JsonSubscriber, validator::ValidatorPtr, Result};
+use log::info;
+use tinyjson::JsonValue;
+
+/// async task forwarding the validator's mempool conflict notifications
+/// to JSON-RPC subscribers
+pub async fn conflicts_task(validator: ValidatorPtr, subscriber: JsonSubscriber) -> Result<()> {
+    let subscription = validator.read().await.conflict_subscriber.clone().subscribe().await;
+
+    loop {
+        let conflict = subscription.receive().await;
+        info!(
+            target: "darkfid::task::conflicts_task",
+            "Transaction {} conflicts with pending transactions (replaced: {})",
+            conflict.tx_hash, conflict.replaced,
+        );
+
+        let conflicts =
+            conflict.conflicts.iter().map(|h| JsonValue::String(h.to_string())).collect();
+        let params = vec![
+            JsonValue::String(conflict.tx_hash.to_string()),
+            JsonValue::Array(conflicts),
+            JsonValue::Boolean(conflict.replaced),
+        ];
+        subscriber.notify(params).await;
+    }
+}
@@ -20,3 +20,12 @@
 
 pub mod sync;
 pub use sync::sync_task;
+
+pub mod conflicts;
+pub use conflicts::conflicts_task;
+
+pub mod pending;
+pub use pending::{pending_txs_task, PendingTxFilter, PendingTxFiltersPtr};
+
+pub mod rebroadcast;
+pub use rebroadcast::{rebroadcast_task, Rebroadcaster, RebroadcasterPtr};
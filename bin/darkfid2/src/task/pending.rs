@@ -0,0 +1,103 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{collections::HashMap, sync::Arc};
+
+use darkfi::{rpc::jsonrpc::JsonSubscriber, tx::Transaction, validator::ValidatorPtr, Result};
+use darkfi_sdk::crypto::ContractId;
+use log::debug;
+use smol::lock::Mutex;
+use tinyjson::JsonValue;
+
+use crate::decode::DecoderRegistry;
+
+/// Maximum number of distinct pending transaction filters the node
+/// keeps subscribers for
+pub const MAX_PENDING_TX_FILTERS: usize = 256;
+
+/// Server-side filter applied to pending transactions before notifying
+/// a subscriber. A transaction matches if any of its calls targets the
+/// filter's contract, and, if set, the filter's function byte.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct PendingTxFilter {
+    /// Contract ID the call must target
+    pub contract_id: ContractId,
+    /// Optional function byte the call data must begin with
+    pub function: Option<u8>,
+}
+
+impl PendingTxFilter {
+    /// Check if any call of the given transaction matches the filter
+    pub fn matches(&self, tx: &Transaction) -> bool {
+        tx.calls.iter().any(|call| {
+            call.contract_id == self.contract_id &&
+                self.function.map_or(true, |f| call.data.first() == Some(&f))
+        })
+    }
+}
+
+/// Atomic pointer to the filtered pending transactions subscribers
+pub type PendingTxFiltersPtr = Arc<Mutex<HashMap<PendingTxFilter, JsonSubscriber>>>;
+
+/// Retrieve the subscriber notified for the given filter, creating it if
+/// it doesn't exist yet. Subscribers are shared between connections using
+/// the same filter. Returns `None` if the filters limit has been reached.
+pub async fn filtered_subscriber(
+    filters: &PendingTxFiltersPtr,
+    filter: PendingTxFilter,
+) -> Option<JsonSubscriber> {
+    let mut filters = filters.lock().await;
+    if let Some(subscriber) = filters.get(&filter) {
+        return Some(subscriber.clone())
+    }
+
+    if filters.len() >= MAX_PENDING_TX_FILTERS {
+        return None
+    }
+
+    let subscriber = JsonSubscriber::new("tx.subscribe_pending");
+    filters.insert(filter, subscriber.clone());
+    Some(subscriber)
+}
+
+/// async task forwarding the transactions appended to the validator's
+/// pending txs store to JSON-RPC subscribers, along with a summary of
+/// their contract calls. Filtered subscribers only get notified of the
+/// transactions matching their filter.
+pub async fn pending_txs_task(
+    validator: ValidatorPtr,
+    subscriber: JsonSubscriber,
+    filters: PendingTxFiltersPtr,
+) -> Result<()> {
+    let subscription = validator.read().await.pending_tx_subscriber.clone().subscribe().await;
+    let decoders = DecoderRegistry::native();
+
+    loop {
+        let tx = subscription.receive().await;
+        let tx_hash = tx.hash();
+        debug!(target: "darkfid::task::pending_txs_task", "Notifying pending transaction {}", tx_hash);
+
+        let params = vec![JsonValue::String(tx_hash.to_string()), decoders.summarize_tx(&tx)];
+        for (filter, filtered) in filters.lock().await.iter() {
+            if filter.matches(&tx) {
+                filtered.notify(params.clone()).await;
+            }
+        }
+        subscriber.notify(params).await;
+    }
+}
@@ -0,0 +1,192 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{collections::HashMap, sync::Arc};
+
+use darkfi::{system::sleep, tx::Transaction, util::time::Timestamp, Result};
+use log::{debug, info};
+use smol::lock::Mutex;
+
+use crate::Darkfid;
+
+/// Seconds between rebroadcaster checks
+pub const REBROADCAST_INTERVAL: u64 = 5;
+/// Seconds to wait before the first rebroadcast of a transaction
+pub const REBROADCAST_INITIAL_BACKOFF: u64 = 30;
+/// Maximum number of seconds between rebroadcasts of a transaction
+pub const REBROADCAST_MAX_BACKOFF: u64 = 960;
+/// Seconds after which an unconfirmed transaction stops being rebroadcast,
+/// and after which finished entries get pruned
+pub const REBROADCAST_EXPIRY: u64 = 3600;
+
+/// Lifecycle status of a locally submitted transaction
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum RebroadcastStatus {
+    /// Transaction is still waiting to be confirmed
+    Pending,
+    /// Transaction got included in the canonical blockchain
+    Confirmed,
+    /// Transaction didn't confirm before the expiry period
+    Expired,
+    /// Transaction got evicted from the pending txs store, e.g. replaced
+    /// by a conflicting transaction
+    Replaced,
+}
+
+impl RebroadcastStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Confirmed => "confirmed",
+            Self::Expired => "expired",
+            Self::Replaced => "replaced",
+        }
+    }
+}
+
+/// A locally submitted transaction tracked by the [`Rebroadcaster`]
+pub struct RebroadcastEntry {
+    /// The tracked transaction
+    pub tx: Transaction,
+    /// Current status of the transaction
+    pub status: RebroadcastStatus,
+    /// Time the transaction was submitted
+    pub submitted: Timestamp,
+    /// Time of the next rebroadcast
+    pub next_attempt: Timestamp,
+    /// Current backoff, doubled after each rebroadcast
+    pub backoff: u64,
+    /// Number of times the transaction got rebroadcast
+    pub attempts: u32,
+}
+
+impl RebroadcastEntry {
+    pub fn new(tx: Transaction, now: Timestamp) -> Self {
+        Self {
+            tx,
+            status: RebroadcastStatus::Pending,
+            submitted: now,
+            next_attempt: Timestamp(now.0 + REBROADCAST_INITIAL_BACKOFF),
+            backoff: REBROADCAST_INITIAL_BACKOFF,
+            attempts: 0,
+        }
+    }
+
+    /// Update the entry status at the given time, knowing whether the
+    /// transaction got confirmed, and, if the node keeps a pending txs
+    /// store, whether it's still in there. Returns `true` if the
+    /// transaction should be rebroadcast now.
+    pub fn poll(&mut self, now: Timestamp, confirmed: bool, pending: Option<bool>) -> bool {
+        if self.status != RebroadcastStatus::Pending {
+            return false
+        }
+
+        if confirmed {
+            self.status = RebroadcastStatus::Confirmed;
+            return false
+        }
+
+        if pending == Some(false) {
+            self.status = RebroadcastStatus::Replaced;
+            return false
+        }
+
+        if now.0 >= self.submitted.0 + REBROADCAST_EXPIRY {
+            self.status = RebroadcastStatus::Expired;
+            return false
+        }
+
+        if now.0 < self.next_attempt.0 {
+            return false
+        }
+
+        self.attempts += 1;
+        self.backoff = (self.backoff * 2).min(REBROADCAST_MAX_BACKOFF);
+        self.next_attempt = Timestamp(now.0 + self.backoff);
+        true
+    }
+}
+
+/// Tracks the transactions submitted through the node, re-announcing
+/// them on an exponential backoff schedule until they are confirmed,
+/// expire, or get replaced.
+#[derive(Default)]
+pub struct Rebroadcaster {
+    pub entries: Mutex<HashMap<blake3::Hash, RebroadcastEntry>>,
+}
+
+pub type RebroadcasterPtr = Arc<Rebroadcaster>;
+
+impl Rebroadcaster {
+    pub fn new() -> RebroadcasterPtr {
+        Arc::new(Self::default())
+    }
+
+    /// Start tracking the given transaction. Already tracked transactions
+    /// get their schedule reset.
+    pub async fn track(&self, tx: Transaction) {
+        let entry = RebroadcastEntry::new(tx, Timestamp::current_time());
+        self.entries.lock().await.insert(entry.tx.hash(), entry);
+    }
+}
+
+/// async task rebroadcasting the node's locally submitted transactions
+/// that haven't been confirmed yet
+pub async fn rebroadcast_task(node: Arc<Darkfid>) -> Result<()> {
+    loop {
+        sleep(REBROADCAST_INTERVAL).await;
+
+        let now = Timestamp::current_time();
+        let mut due = vec![];
+        {
+            let validator = node.validator.read().await;
+            let blockchain = &validator.blockchain;
+            let mut entries = node.rebroadcaster.entries.lock().await;
+
+            // Prune finished entries once their expiry period passed
+            entries.retain(|_, entry| {
+                entry.status == RebroadcastStatus::Pending ||
+                    now.0 < entry.submitted.0 + REBROADCAST_EXPIRY
+            });
+
+            for (tx_hash, entry) in entries.iter_mut() {
+                let confirmed = blockchain.transactions.contains(tx_hash)?;
+                // Only consensus participants keep a pending txs store
+                let pending = match node.consensus_p2p {
+                    Some(_) => Some(blockchain.pending_txs.contains(tx_hash)?),
+                    None => None,
+                };
+
+                let previous = entry.status;
+                if entry.poll(now, confirmed, pending) {
+                    due.push(entry.tx.clone());
+                } else if entry.status != previous {
+                    info!(
+                        target: "darkfid::task::rebroadcast_task",
+                        "Transaction {} is now {}", tx_hash, entry.status.as_str(),
+                    );
+                }
+            }
+        }
+
+        for tx in due {
+            debug!(target: "darkfid::task::rebroadcast_task", "Rebroadcasting transaction {}", tx.hash());
+            node.sync_p2p.broadcast(&tx).await;
+        }
+    }
+}
@@ -0,0 +1,71 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use url::Url;
+
+use crate::{
+    config::{validate_config, ConfigReport},
+    Args,
+};
+
+fn errors(report: &ConfigReport) -> Vec<&str> {
+    report.errors.iter().map(|issue| issue.setting.as_str()).collect()
+}
+
+#[test]
+fn config_validation_defaults() {
+    let report = validate_config(&Args::default());
+    assert!(report.errors.is_empty());
+    assert!(report.check().is_ok());
+}
+
+#[test]
+fn config_validation_aggregates_errors() {
+    let mut args = Args::default();
+    args.observer = true;
+    args.consensus = true;
+    args.checkpoint_key = Some("invalid".to_string());
+    args.proof_verification_limit = Some(0);
+    args.rpc_cheap_rate_limit = Some("50".to_string());
+    args.rpc_listen = Url::parse("tcp://127.0.0.1:8342").unwrap();
+    args.sync_net.inbound = vec![Url::parse("tcp+tls://0.0.0.0:8342").unwrap()];
+    args.sync_net.allowed_transports = vec!["carrier-pigeon".to_string()];
+    args.sync_net.peers = vec![Url::parse("tcp+tls://peer.dark.fi").unwrap()];
+    args.consensus_net.channel_heartbeat_interval = Some(0);
+    args.consensus_net.channel_missed_pongs = Some(0);
+
+    // Every problem is reported at once
+    let report = validate_config(&args);
+    let errors = errors(&report);
+    assert!(errors.contains(&"observer"));
+    assert!(errors.contains(&"checkpoint_key"));
+    assert!(errors.contains(&"proof_verification_limit"));
+    assert!(errors.contains(&"rpc_*_rate_limit"));
+    assert!(errors.contains(&"rpc_listen"));
+    assert!(errors.contains(&"sync_net.allowed_transports"));
+    assert!(errors.contains(&"sync_net.peers"));
+    assert!(errors.contains(&"consensus_net.channel_heartbeat_interval"));
+    assert!(errors.contains(&"consensus_net.channel_missed_pongs"));
+    assert!(report.check().is_err());
+
+    // Listeners on different ports or hosts don't collide
+    let mut args = Args::default();
+    args.rpc_listen = Url::parse("tcp://127.0.0.1:8342").unwrap();
+    args.sync_net.inbound = vec![Url::parse("tcp+tls://192.168.1.1:8342").unwrap()];
+    assert!(validate_config(&args).errors.is_empty());
+}
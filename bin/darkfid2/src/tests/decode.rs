@@ -0,0 +1,69 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{tx::Transaction, Result};
+use darkfi_money_contract::{model::MoneyTokenFreezeParamsV1, MoneyFunction};
+use darkfi_sdk::{
+    crypto::{ContractId, PublicKey, SecretKey, MONEY_CONTRACT_ID},
+    pasta::pallas,
+    tx::ContractCall,
+};
+use darkfi_serial::Encodable;
+use tinyjson::JsonValue;
+
+use crate::decode::DecoderRegistry;
+
+#[test]
+fn decode_tx() -> Result<()> {
+    let public = PublicKey::from_secret(SecretKey::from(pallas::Base::from(42)));
+
+    // A well-formed call, one with malformed params, and one to an unknown contract
+    let mut data = vec![MoneyFunction::TokenFreezeV1 as u8];
+    MoneyTokenFreezeParamsV1 { signature_public: public }.encode(&mut data)?;
+    let calls = vec![
+        ContractCall { contract_id: *MONEY_CONTRACT_ID, data },
+        ContractCall {
+            contract_id: *MONEY_CONTRACT_ID,
+            data: vec![MoneyFunction::TransferV1 as u8, 0xff],
+        },
+        ContractCall { contract_id: ContractId::from(pallas::Base::from(42)), data: vec![0x00] },
+    ];
+    let tx = Transaction {
+        calls,
+        proofs: vec![vec![]; 3],
+        signatures: vec![vec![]; 3],
+        access_list: None,
+    };
+
+    let decoded = DecoderRegistry::native().decode_tx(&tx);
+    assert_eq!(decoded["hash"], JsonValue::String(tx.hash().to_string()));
+
+    let calls: &Vec<JsonValue> = decoded["calls"].get().unwrap();
+    assert_eq!(calls.len(), 3);
+
+    assert_eq!(calls[0]["function"], JsonValue::String("TokenFreezeV1".to_string()));
+    assert_eq!(calls[0]["params"]["signature_public"], JsonValue::String(public.to_string()));
+
+    for call in &calls[1..] {
+        assert_eq!(call["function"], JsonValue::Null);
+        assert_eq!(call["params"], JsonValue::Null);
+    }
+    assert_eq!(calls[2]["data"], JsonValue::String("00".to_string()));
+
+    Ok(())
+}
@@ -16,7 +16,11 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use darkfi::{blockchain::Blockchain, validator::consensus::Fork, Result};
+use darkfi::{
+    blockchain::Blockchain,
+    validator::consensus::{forks_shared_proposals, Fork},
+    Result,
+};
 
 #[test]
 fn forks() -> Result<()> {
@@ -25,7 +29,7 @@ fn forks() -> Result<()> {
     let record1 = blake3::hash(b"Never skip brain day.");
 
     // Create a temporary blockchain
-    let blockchain = Blockchain::new(&sled::Config::new().temporary(true).open()?)?;
+    let blockchain = Blockchain::in_memory()?;
 
     // Create a fork
     let fork = Fork::new(&blockchain)?;
@@ -56,3 +60,18 @@ fn forks() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn forks_common_ancestors() {
+    let hashes: Vec<blake3::Hash> = (0..5u8).map(|i| blake3::hash(&[i])).collect();
+
+    // Fork 1 branches off fork 0 after its second proposal,
+    // while fork 2 shares none of their proposals.
+    let fork0 = [hashes[0], hashes[1], hashes[2]];
+    let fork1 = [hashes[0], hashes[1], hashes[3]];
+    let fork2 = [hashes[4]];
+
+    assert_eq!(forks_shared_proposals(&[&fork0, &fork1, &fork2]), vec![2, 2, 0]);
+    assert_eq!(forks_shared_proposals(&[&fork0]), vec![0]);
+    assert!(forks_shared_proposals(&[]).is_empty());
+}
@@ -0,0 +1,55 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use tinyjson::JsonValue;
+
+use crate::gateway::to_cbor;
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[test]
+fn cbor_encoding() {
+    // Test vectors from RFC 8949, Appendix A
+    let vectors = [
+        ("0", "00"),
+        ("23", "17"),
+        ("24", "1818"),
+        ("1000", "1903e8"),
+        ("1000000", "1a000f4240"),
+        ("1000000000000", "1b000000e8d4a51000"),
+        ("-1", "20"),
+        ("-1000", "3903e7"),
+        ("1.1", "fb3ff199999999999a"),
+        ("false", "f4"),
+        ("null", "f6"),
+        ("\"IETF\"", "6449455446"),
+        ("[1, [2, 3], [4, 5]]", "8301820203820405"),
+        ("{\"a\": 1, \"b\": [2, 3]}", "a26161016162820203"),
+    ];
+
+    for (json, cbor) in vectors {
+        let value: JsonValue = json.parse().unwrap();
+        assert_eq!(hex(&to_cbor(&value)), cbor, "{}", json);
+    }
+
+    // Map keys are sorted by their encoding, so shorter keys come first
+    let value: JsonValue = "{\"aa\": 1, \"b\": 2}".parse().unwrap();
+    assert_eq!(hex(&to_cbor(&value)), "a261620262616101");
+}
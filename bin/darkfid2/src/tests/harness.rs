@@ -19,13 +19,14 @@
 use std::{collections::HashMap, sync::Arc};
 
 use darkfi::{
-    blockchain::{BlockInfo, Header},
+    blockchain::{BlockInfo, Header, MemoryBackend, StorageBackend},
     net::Settings,
     rpc::jsonrpc::JsonSubscriber,
     system::StoppableTask,
     util::time::TimeKeeper,
     validator::{
         consensus::{next_block_reward, pid::slot_pid_output},
+        policy::AllowAll,
         Validator, ValidatorConfig,
     },
     Error, Result,
@@ -85,6 +86,11 @@ impl Harness {
             genesis_txs_total,
             vec![],
             config.testing_node,
+            false,
+            None,
+            None,
+            Arc::new(AllowAll),
+            false,
         );
 
         // Generate validators using pregenerated vks
@@ -219,7 +225,7 @@ pub async fn generate_node(
     ex: &Arc<smol::Executor<'static>>,
     skip_sync: bool,
 ) -> Result<Darkfid> {
-    let sled_db = sled::Config::new().temporary(true).open()?;
+    let sled_db = MemoryBackend.open()?;
     vks::inject(&sled_db, &vks)?;
 
     let validator = Validator::new(&sled_db, config.clone()).await?;
@@ -227,6 +233,9 @@ pub async fn generate_node(
     let mut subscribers = HashMap::new();
     subscribers.insert("blocks", JsonSubscriber::new("blockchain.subscribe_blocks"));
     subscribers.insert("txs", JsonSubscriber::new("blockchain.subscribe_txs"));
+    subscribers.insert("conflicts", JsonSubscriber::new("tx.subscribe_conflicts"));
+    subscribers.insert("pending_txs", JsonSubscriber::new("tx.subscribe_pending"));
+    subscribers.insert("checkpoints", JsonSubscriber::new("blockchain.subscribe_checkpoints"));
     if consensus_settings.is_some() {
         subscribers.insert("proposals", JsonSubscriber::new("blockchain.subscribe_proposals"));
     }
@@ -237,7 +246,16 @@ pub async fn generate_node(
     } else {
         None
     };
-    let node = Darkfid::new(sync_p2p.clone(), consensus_p2p.clone(), validator, subscribers).await;
+    let node = Darkfid::new(
+        sync_p2p.clone(),
+        consensus_p2p.clone(),
+        validator,
+        subscribers,
+        None,
+        false,
+        None,
+    )
+    .await;
 
     sync_p2p.clone().start().await?;
     StoppableTask::new().start(
@@ -28,6 +28,17 @@ use harness::{generate_node, Harness, HarnessConfig};
 
 mod forks;
 
+mod decode;
+
+mod pending;
+
+mod rebroadcast;
+
+mod config;
+
+#[cfg(feature = "gateway")]
+mod gateway;
+
 async fn sync_blocks_real(ex: Arc<Executor<'static>>) -> Result<()> {
     init_logger();
 
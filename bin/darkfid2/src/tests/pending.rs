@@ -0,0 +1,74 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{collections::HashMap, sync::Arc};
+
+use darkfi::tx::Transaction;
+use darkfi_sdk::{
+    crypto::{ContractId, DAO_CONTRACT_ID, MONEY_CONTRACT_ID},
+    pasta::pallas,
+    tx::ContractCall,
+};
+use smol::lock::Mutex;
+
+use crate::task::pending::{filtered_subscriber, PendingTxFilter, MAX_PENDING_TX_FILTERS};
+
+#[test]
+fn pending_tx_filters() {
+    let tx = Transaction {
+        calls: vec![
+            ContractCall { contract_id: *MONEY_CONTRACT_ID, data: vec![0x03, 0xff] },
+            ContractCall { contract_id: *DAO_CONTRACT_ID, data: vec![0x01] },
+        ],
+        proofs: vec![vec![]; 2],
+        signatures: vec![vec![]; 2],
+        access_list: None,
+    };
+
+    // Any call matching the filter selects the transaction
+    assert!(PendingTxFilter { contract_id: *DAO_CONTRACT_ID, function: None }.matches(&tx));
+    assert!(PendingTxFilter { contract_id: *DAO_CONTRACT_ID, function: Some(0x01) }.matches(&tx));
+    assert!(PendingTxFilter { contract_id: *MONEY_CONTRACT_ID, function: Some(0x03) }.matches(&tx));
+    assert!(!PendingTxFilter { contract_id: *DAO_CONTRACT_ID, function: Some(0x03) }.matches(&tx));
+    let unknown = ContractId::from(pallas::Base::from(42));
+    assert!(!PendingTxFilter { contract_id: unknown, function: None }.matches(&tx));
+
+    smol::block_on(async {
+        let filters = Arc::new(Mutex::new(HashMap::new()));
+
+        // Subscribers are shared between identical filters
+        let filter = PendingTxFilter { contract_id: *DAO_CONTRACT_ID, function: None };
+        let a = filtered_subscriber(&filters, filter).await.unwrap();
+        let b = filtered_subscriber(&filters, filter).await.unwrap();
+        assert!(Arc::ptr_eq(&a.sub, &b.sub));
+        assert_eq!(filters.lock().await.len(), 1);
+
+        // New filters get rejected once the limit is reached
+        for i in 1..MAX_PENDING_TX_FILTERS as u64 {
+            let contract_id = ContractId::from(pallas::Base::from(i));
+            let filter = PendingTxFilter { contract_id, function: None };
+            assert!(filtered_subscriber(&filters, filter).await.is_some());
+        }
+        let rejected = PendingTxFilter { contract_id: *MONEY_CONTRACT_ID, function: None };
+        assert!(filtered_subscriber(&filters, rejected).await.is_none());
+
+        // Existing filters can still be subscribed to
+        let existing = PendingTxFilter { contract_id: *DAO_CONTRACT_ID, function: None };
+        assert!(filtered_subscriber(&filters, existing).await.is_some());
+    });
+}
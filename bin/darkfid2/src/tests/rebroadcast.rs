@@ -0,0 +1,73 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{tx::Transaction, util::time::Timestamp};
+
+use crate::task::rebroadcast::{
+    RebroadcastEntry, RebroadcastStatus, REBROADCAST_EXPIRY, REBROADCAST_INITIAL_BACKOFF,
+    REBROADCAST_MAX_BACKOFF,
+};
+
+fn dummy_tx() -> Transaction {
+    Transaction { calls: vec![], proofs: vec![], signatures: vec![], access_list: None }
+}
+
+#[test]
+fn rebroadcast_backoff() {
+    let mut entry = RebroadcastEntry::new(dummy_tx(), Timestamp(0));
+
+    // Nothing happens before the initial backoff passes
+    assert!(!entry.poll(Timestamp(REBROADCAST_INITIAL_BACKOFF - 1), false, None));
+
+    // Rebroadcasts happen with a doubling backoff, up to its maximum
+    let mut now = REBROADCAST_INITIAL_BACKOFF;
+    let mut backoff = REBROADCAST_INITIAL_BACKOFF;
+    for attempt in 1..=6 {
+        assert!(entry.poll(Timestamp(now), false, None));
+        backoff = (backoff * 2).min(REBROADCAST_MAX_BACKOFF);
+        assert_eq!(entry.attempts, attempt);
+        assert_eq!(entry.next_attempt.0, now + backoff);
+        assert!(!entry.poll(Timestamp(now + backoff - 1), false, None));
+        now += backoff;
+    }
+    assert_eq!(entry.backoff, REBROADCAST_MAX_BACKOFF);
+
+    // Unconfirmed transactions expire
+    assert!(!entry.poll(Timestamp(REBROADCAST_EXPIRY), false, None));
+    assert_eq!(entry.status, RebroadcastStatus::Expired);
+    assert!(!entry.poll(Timestamp(REBROADCAST_EXPIRY), true, None));
+    assert_eq!(entry.status, RebroadcastStatus::Expired);
+}
+
+#[test]
+fn rebroadcast_finished() {
+    // Confirmation takes precedence over eviction from the pending txs store
+    let mut entry = RebroadcastEntry::new(dummy_tx(), Timestamp(0));
+    assert!(!entry.poll(Timestamp(REBROADCAST_INITIAL_BACKOFF), true, Some(false)));
+    assert_eq!(entry.status, RebroadcastStatus::Confirmed);
+
+    // Evicted transactions are considered replaced
+    let mut entry = RebroadcastEntry::new(dummy_tx(), Timestamp(0));
+    assert!(!entry.poll(Timestamp(REBROADCAST_INITIAL_BACKOFF), false, Some(false)));
+    assert_eq!(entry.status, RebroadcastStatus::Replaced);
+
+    // While still pending ones keep being rebroadcast
+    let mut entry = RebroadcastEntry::new(dummy_tx(), Timestamp(0));
+    assert!(entry.poll(Timestamp(REBROADCAST_INITIAL_BACKOFF), false, Some(true)));
+    assert_eq!(entry.status, RebroadcastStatus::Pending);
+}
@@ -16,27 +16,39 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, str::FromStr, sync::Arc};
 
-use log::info;
+use log::{error, info};
 use smol::Executor;
 
 use darkfi::{
     error::TxVerifyFailed,
     net::{P2p, P2pPtr, Settings, SESSION_ALL},
-    rpc::jsonrpc::JsonSubscriber,
+    rpc::{
+        jsonrpc::JsonSubscriber,
+        ratelimit::{RateLimit, RateLimitConfig},
+    },
     tx::Transaction,
-    validator::ValidatorPtr,
-    Result,
+    validator::{
+        audit::{AuditEntry, AuditEvent},
+        checkpoint::{Checkpoint, CheckpointConfig},
+        policy::{ConflictPolicy, PolicyRules},
+        ValidatorPtr,
+    },
+    Error, Result,
 };
 use darkfi_consensus_contract::{
     model::ConsensusGenesisStakeParamsV1, ConsensusFunction::GenesisStakeV1,
 };
 use darkfi_money_contract::{model::MoneyTokenMintParamsV1, MoneyFunction::GenesisMintV1};
-use darkfi_sdk::crypto::{CONSENSUS_CONTRACT_ID, MONEY_CONTRACT_ID};
+use darkfi_sdk::crypto::{ContractId, PublicKey, CONSENSUS_CONTRACT_ID, MONEY_CONTRACT_ID};
 use darkfi_serial::deserialize;
+use tinyjson::JsonValue;
 
-use crate::proto::{ProtocolBlock, ProtocolProposal, ProtocolSync, ProtocolTx};
+use crate::{
+    proto::{ProtocolBlock, ProtocolCheckpoint, ProtocolProposal, ProtocolSync, ProtocolTx},
+    rpc::EXPENSIVE_METHODS,
+};
 
 /// Auxiliary function to calculate the total amount of minted tokens in provided
 /// genesis transactions set. This includes both staked and normal tokens.
@@ -72,6 +84,155 @@ pub fn genesis_txs_total(txs: &[Transaction]) -> Result<u64> {
     Ok(total)
 }
 
+/// Auxiliary function to parse the configured mempool admission rules.
+pub fn parse_policy_rules(
+    blocked_contracts: &[String],
+    max_tx_size: Option<usize>,
+    max_calls: Option<usize>,
+    contract_call_limits: &[String],
+    conflict_policy: Option<&str>,
+) -> Result<PolicyRules> {
+    let mut rules = PolicyRules { max_tx_size, max_calls, ..Default::default() };
+
+    if let Some(conflict_policy) = conflict_policy {
+        rules.conflict_policy = ConflictPolicy::from_str(conflict_policy)?;
+    }
+
+    for contract_id in blocked_contracts {
+        rules.blocked_contracts.push(ContractId::from_str(contract_id)?);
+    }
+
+    for limit in contract_call_limits {
+        let Some((contract_id, max_calls)) = limit.split_once(':') else {
+            error!(target: "darkfid", "Invalid contract call limit: {}", limit);
+            return Err(Error::ParseFailed("Invalid contract call limit"))
+        };
+        rules.contract_call_limits.push((ContractId::from_str(contract_id)?, max_calls.parse()?));
+    }
+
+    Ok(rules)
+}
+
+/// Auxiliary function to parse the expected native contract code hashes,
+/// given as "contract_id:code_hash".
+pub fn parse_native_contract_hashes(hashes: &[String]) -> Result<HashMap<[u8; 32], blake3::Hash>> {
+    let mut ret = HashMap::new();
+
+    for entry in hashes {
+        let Some((contract_id, hash)) = entry.split_once(':') else {
+            error!(target: "darkfid", "Invalid native contract hash: {}", entry);
+            return Err(Error::ParseFailed("Invalid native contract hash"))
+        };
+        let Ok(hash) = blake3::Hash::from_hex(hash) else {
+            error!(target: "darkfid", "Invalid native contract hash: {}", entry);
+            return Err(Error::ParseFailed("Invalid native contract hash"))
+        };
+        ret.insert(ContractId::from_str(contract_id)?.to_bytes(), hash);
+    }
+
+    Ok(ret)
+}
+
+/// Auxiliary function to parse the configured checkpoint authority set.
+/// If no threshold is given, a majority of the authorities is required.
+pub fn parse_checkpoint_config(
+    authorities: &[String],
+    threshold: Option<usize>,
+    finality_hints: bool,
+) -> Result<Option<CheckpointConfig>> {
+    if authorities.is_empty() {
+        return Ok(None)
+    }
+
+    let mut keys = vec![];
+    for authority in authorities {
+        keys.push(PublicKey::from_str(authority)?);
+    }
+
+    let threshold = threshold.unwrap_or(keys.len() / 2 + 1);
+    if threshold == 0 || threshold > keys.len() {
+        error!(target: "darkfid", "Checkpoint threshold must be between 1 and {}", keys.len());
+        return Err(Error::ConfigInvalid)
+    }
+
+    Ok(Some(CheckpointConfig { authorities: keys, threshold, finality_hints }))
+}
+
+/// Auxiliary function to convert a [`Checkpoint`] into readable JSON.
+pub fn checkpoint_to_json(checkpoint: &Checkpoint) -> JsonValue {
+    let signers =
+        checkpoint.signers().iter().map(|signer| JsonValue::String(signer.to_string())).collect();
+
+    JsonValue::Object(HashMap::from([
+        ("slot".to_string(), JsonValue::Number(checkpoint.slot as f64)),
+        ("block_hash".to_string(), JsonValue::String(checkpoint.block_hash.to_string())),
+        ("signers".to_string(), JsonValue::Array(signers)),
+    ]))
+}
+
+/// Auxiliary function to convert an audit log entry to JSON.
+pub fn audit_entry_to_json(entry: &AuditEntry) -> JsonValue {
+    let hash = |hash: &blake3::Hash| JsonValue::String(hash.to_string());
+    let mut fields = HashMap::from([
+        ("id".to_string(), JsonValue::Number(entry.id as f64)),
+        ("timestamp".to_string(), JsonValue::Number(entry.timestamp.0 as f64)),
+        ("event".to_string(), JsonValue::String(entry.event.kind().to_string())),
+    ]);
+
+    match &entry.event {
+        AuditEvent::BlockAccepted { hash: h, slot } |
+        AuditEvent::ProposalAccepted { hash: h, slot } => {
+            fields.insert("hash".to_string(), hash(h));
+            fields.insert("slot".to_string(), JsonValue::Number(*slot as f64));
+        }
+        AuditEvent::BlockRejected { hash: h, slot, reason } |
+        AuditEvent::ProposalRejected { hash: h, slot, reason } => {
+            fields.insert("hash".to_string(), hash(h));
+            fields.insert("slot".to_string(), JsonValue::Number(*slot as f64));
+            fields.insert("reason".to_string(), JsonValue::String(reason.clone()));
+        }
+        AuditEvent::TxRejected { hash: h, reason } => {
+            fields.insert("hash".to_string(), hash(h));
+            fields.insert("reason".to_string(), JsonValue::String(reason.clone()));
+        }
+        AuditEvent::ForkSwitch { from, to } => {
+            fields.insert("from".to_string(), hash(from));
+            fields.insert("to".to_string(), hash(to));
+        }
+        AuditEvent::ForkFinalized { tip, blocks } => {
+            fields.insert("tip".to_string(), hash(tip));
+            fields.insert("blocks".to_string(), JsonValue::Number(*blocks as f64));
+        }
+    }
+
+    JsonValue::Object(fields)
+}
+
+/// Auxiliary function to parse a rate limit given as "burst:per_second".
+fn parse_rate_limit(limit: &str) -> Result<RateLimit> {
+    let Some((burst, per_second)) = limit.split_once(':') else {
+        error!(target: "darkfid", "Invalid rate limit: {}", limit);
+        return Err(Error::ParseFailed("Invalid rate limit"))
+    };
+
+    Ok(RateLimit::new(burst.parse()?, per_second.parse()?))
+}
+
+/// Auxiliary function to parse the configured JSON-RPC rate limits.
+pub fn parse_rate_limits(
+    connection: Option<&str>,
+    cheap: Option<&str>,
+    expensive: Option<&str>,
+) -> Result<RateLimitConfig> {
+    Ok(RateLimitConfig {
+        connection: connection.map(parse_rate_limit).transpose()?,
+        cheap: cheap.map(parse_rate_limit).transpose()?,
+        expensive: expensive.map(parse_rate_limit).transpose()?,
+        expensive_methods: EXPENSIVE_METHODS.iter().map(|m| m.to_string()).collect(),
+        ..Default::default()
+    })
+}
+
 /// Auxiliary function to generate the sync P2P network and register all its protocols.
 pub async fn spawn_sync_p2p(
     settings: &Settings,
@@ -111,6 +272,21 @@ pub async fn spawn_sync_p2p(
         })
         .await;
 
+    // Checkpoints are only gossiped by nodes configured with an authority set
+    if validator.read().await.checkpoint_config.is_some() {
+        let _validator = validator.clone();
+        let _subscriber = subscribers.get("checkpoints").unwrap().clone();
+        registry
+            .register(SESSION_ALL, move |channel, p2p| {
+                let validator = _validator.clone();
+                let subscriber = _subscriber.clone();
+                async move {
+                    ProtocolCheckpoint::init(channel, validator, p2p, subscriber).await.unwrap()
+                }
+            })
+            .await;
+    }
+
     p2p
 }
 
@@ -44,6 +44,8 @@ impl RequestHandler for JsonRpcInterface {
             "ping" => self.pong(req.id, req.params).await,
             "dnet.switch" => self.dnet_switch(req.id, req.params).await,
             "dnet.subscribe_events" => self.dnet_subscribe_events(req.id, req.params).await,
+            "dnet.protocols" => self.dnet_protocols(req.id, req.params).await,
+            "dnet.get_events" => self.dnet_get_events(req.id, req.params).await,
             _ => JsonError::new(ErrorCode::MethodNotFound, None, req.id).into(),
         }
     }
@@ -89,4 +91,45 @@ impl JsonRpcInterface {
 
         self.dnet_sub.clone().into()
     }
+
+    // RPCAPI:
+    // Lists, per connected channel, the message types that have a dispatcher
+    // and the protocols attached to it. Useful to debug a protocol whose
+    // subscription silently misses a dispatcher.
+    //
+    // --> {"jsonrpc": "2.0", "method": "dnet.protocols", "params": [], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": [{"chan": {"addr": "tcp://...", "id": 1}, "session": "outbound", "dispatchers": ["addr", ...], "protocols": ["ProtocolPing", ...]}], "id": 1}
+    async fn dnet_protocols(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if !params.is_empty() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let channels = self.p2p.dnet_protocols().await;
+        let channels = channels.into_iter().map(|c| c.into()).collect();
+        JsonResponse::new(JsonValue::Array(channels), id).into()
+    }
+
+    // RPCAPI:
+    // Retrieves recent dnet events, oldest first, so connection and message
+    // history can be inspected after attaching. Returns at most `limit` events
+    // with a sequence number greater than `since`. Pass 0 to start from the
+    // oldest kept event, or the `seq` of the last seen event to resume.
+    // Events are only recorded while dnet is switched on.
+    //
+    // --> {"jsonrpc": "2.0", "method": "dnet.get_events", "params": [0, 100], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": [{"seq": 1, "time": "...", "event": `event`}, ...], "id": 1}
+    async fn dnet_get_events(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 2 || !params[0].is_number() || !params[1].is_number() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let since = *params[0].get::<f64>().unwrap() as u64;
+        let limit = *params[1].get::<f64>().unwrap() as usize;
+
+        let events = self.p2p.dnet_events(since, limit).await;
+        let events = events.into_iter().map(|e| e.into()).collect();
+        JsonResponse::new(JsonValue::Array(events), id).into()
+    }
 }
@@ -0,0 +1,243 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Merkle tree over the chunks of a file, so that chunks can be fetched from
+//! multiple peers and verified one by one against the root hash, which acts
+//! as the content address of the file.
+
+use darkfi::dht2::MAX_CHUNK_SIZE;
+use darkfi_serial::{SerialDecodable, SerialEncodable};
+use url::Url;
+
+/// Domain separation of leaf and inner node hashes
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+fn leaf_hash(chunk_hash: &blake3::Hash) -> blake3::Hash {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&[LEAF_PREFIX]);
+    hasher.update(chunk_hash.as_bytes());
+    hasher.finalize()
+}
+
+fn node_hash(left: &blake3::Hash, right: &blake3::Hash) -> blake3::Hash {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&[NODE_PREFIX]);
+    hasher.update(left.as_bytes());
+    hasher.update(right.as_bytes());
+    hasher.finalize()
+}
+
+/// Split a blob into fixed-size chunks. The last chunk may be shorter.
+pub fn split_chunks(data: &[u8]) -> Vec<&[u8]> {
+    data.chunks(MAX_CHUNK_SIZE).collect()
+}
+
+/// Inclusion proof of a chunk in a [`ChunkTree`]: the sibling hashes from
+/// the leaf up to the root. Levels where the node has no sibling are skipped.
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct ChunkProof {
+    pub siblings: Vec<blake3::Hash>,
+}
+
+/// Merkle tree built over the chunk hashes of a file.
+/// A node without a sibling is promoted to the next level as is.
+pub struct ChunkTree {
+    /// Tree levels, from the leaves up to the root
+    levels: Vec<Vec<blake3::Hash>>,
+}
+
+impl ChunkTree {
+    /// Build the tree over the given chunk hashes, in file order.
+    pub fn new(chunk_hashes: &[blake3::Hash]) -> Self {
+        let mut levels = vec![chunk_hashes.iter().map(leaf_hash).collect::<Vec<_>>()];
+        while levels.last().unwrap().len() > 1 {
+            let level = levels.last().unwrap();
+            let next = level
+                .chunks(2)
+                .map(|pair| if pair.len() == 2 { node_hash(&pair[0], &pair[1]) } else { pair[0] })
+                .collect();
+            levels.push(next);
+        }
+
+        Self { levels }
+    }
+
+    /// Build the tree over the given file chunks.
+    pub fn from_chunks(chunks: &[&[u8]]) -> Self {
+        let hashes: Vec<blake3::Hash> = chunks.iter().map(|c| blake3::hash(c)).collect();
+        Self::new(&hashes)
+    }
+
+    /// Number of chunks in the tree
+    pub fn len(&self) -> usize {
+        self.levels[0].len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Root hash of the tree, acting as the content address of the file.
+    /// An empty tree has the hash of an empty chunk list as its root.
+    pub fn root(&self) -> blake3::Hash {
+        match self.levels.last().unwrap().first() {
+            Some(root) => *root,
+            None => blake3::hash(&[]),
+        }
+    }
+
+    /// Generate the inclusion proof of the chunk at the given index.
+    pub fn proof(&self, index: usize) -> Option<ChunkProof> {
+        if index >= self.len() {
+            return None
+        }
+
+        let mut siblings = vec![];
+        let mut index = index;
+        for level in &self.levels[..self.levels.len() - 1] {
+            let sibling = index ^ 1;
+            if sibling < level.len() {
+                siblings.push(level[sibling]);
+            }
+            index /= 2;
+        }
+
+        Some(ChunkProof { siblings })
+    }
+}
+
+/// Verify that `chunk` is the chunk at `index` of the file with the given
+/// root hash and total number of chunks.
+pub fn verify_chunk(
+    root: &blake3::Hash,
+    index: usize,
+    total: usize,
+    chunk: &[u8],
+    proof: &ChunkProof,
+) -> bool {
+    if index >= total || chunk.len() > MAX_CHUNK_SIZE {
+        return false
+    }
+
+    let mut siblings = proof.siblings.iter();
+    let mut node = leaf_hash(&blake3::hash(chunk));
+    let mut index = index;
+    let mut width = total;
+    while width > 1 {
+        let sibling = index ^ 1;
+        if sibling < width {
+            let Some(hash) = siblings.next() else { return false };
+            node = if index % 2 == 0 { node_hash(&node, hash) } else { node_hash(hash, &node) };
+        }
+        index /= 2;
+        width = (width + 1) / 2;
+    }
+
+    siblings.next().is_none() && &node == root
+}
+
+/// State of a file being fetched chunk by chunk, possibly from multiple
+/// peers. Chunks are only accepted after verifying them against the root.
+pub struct ChunkFetch {
+    /// Root hash of the file being fetched
+    pub root: blake3::Hash,
+    /// Total number of chunks, learned from the first verified reply
+    pub total: Option<usize>,
+    /// Verified chunks received so far
+    chunks: Vec<Option<Vec<u8>>>,
+}
+
+impl ChunkFetch {
+    pub fn new(root: blake3::Hash) -> Self {
+        Self { root, total: None, chunks: vec![] }
+    }
+
+    /// Indexes of the chunks still missing. While the total is unknown,
+    /// only the first chunk is requested.
+    pub fn missing(&self) -> Vec<usize> {
+        match self.total {
+            Some(_) => self
+                .chunks
+                .iter()
+                .enumerate()
+                .filter(|(_, c)| c.is_none())
+                .map(|(i, _)| i)
+                .collect(),
+            None => vec![0],
+        }
+    }
+
+    /// Distribute the missing chunks across the given providers, round-robin.
+    pub fn schedule(&self, providers: &[Url]) -> Vec<(Url, Vec<usize>)> {
+        if providers.is_empty() {
+            return vec![]
+        }
+
+        let mut schedule: Vec<(Url, Vec<usize>)> =
+            providers.iter().map(|p| (p.clone(), vec![])).collect();
+        for (i, index) in self.missing().into_iter().enumerate() {
+            schedule[i % providers.len()].1.push(index);
+        }
+        schedule.retain(|(_, indexes)| !indexes.is_empty());
+
+        schedule
+    }
+
+    /// Verify and store a received chunk. Returns `false` if the chunk
+    /// doesn't verify against the root, or if its total doesn't match the
+    /// one learned so far.
+    pub fn insert(
+        &mut self,
+        index: usize,
+        total: usize,
+        chunk: Vec<u8>,
+        proof: &ChunkProof,
+    ) -> bool {
+        if let Some(known) = self.total {
+            if known != total {
+                return false
+            }
+        }
+
+        if !verify_chunk(&self.root, index, total, &chunk, proof) {
+            return false
+        }
+
+        if self.total.is_none() {
+            self.total = Some(total);
+            self.chunks = vec![None; total];
+        }
+        self.chunks[index] = Some(chunk);
+
+        true
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.total.is_some() && self.chunks.iter().all(|c| c.is_some())
+    }
+
+    /// Reassemble the file, once all of its chunks have been received.
+    pub fn assemble(&self) -> Option<Vec<u8>> {
+        if !self.is_complete() {
+            return None
+        }
+
+        Some(self.chunks.iter().flatten().flatten().copied().collect())
+    }
+}
@@ -19,20 +19,145 @@
 use std::collections::{HashMap, HashSet};
 
 use async_std::sync::{Arc, RwLock};
-use darkfi::{dht2::Dht, Result};
+use darkfi::{
+    dht2::{net_hashmap::NetHashMapInsert, Dht},
+    net::P2pPtr,
+    system::sleep,
+    util::time::Timestamp,
+    Result,
+};
+use log::debug;
 use url::Url;
 
+/// Merkle tree chunking of large files
+mod chunks;
+use chunks::{ChunkFetch, ChunkTree};
+
 /// Protocol implementations
 mod proto;
+use proto::TreeChunkRequest;
 
 //#[cfg(test)]
 mod tests;
 
+/// Lifetime of a provider record, in seconds, unless it gets refreshed
+pub const PROVIDER_RECORD_TTL: u64 = 60 * 60 * 24;
+
+/// Interval at which locally provided content is republished, in seconds
+pub const REPUBLISH_INTERVAL: u64 = 60 * 60 * 12;
+
 pub type DhtdPtr = Arc<RwLock<Dhtd>>;
 
 pub struct Dhtd {
     pub dht: Dht,
-    pub routing_table: HashMap<blake3::Hash, HashSet<Url>>,
+    /// Known providers of each key, along with their record expiry timestamp
+    pub routing_table: HashMap<blake3::Hash, HashMap<Url, u64>>,
+    /// Content we provide ourselves, republished every `REPUBLISH_INTERVAL`
+    pub local_records: HashMap<blake3::Hash, Vec<blake3::Hash>>,
+    /// Lifetime of provider records, in seconds
+    pub record_ttl: u64,
+    /// Files being fetched chunk by chunk, by their chunk tree root
+    pub fetches: HashMap<blake3::Hash, ChunkFetch>,
+}
+
+impl Dhtd {
+    pub fn new(dht: Dht) -> Self {
+        Self {
+            dht,
+            routing_table: HashMap::new(),
+            local_records: HashMap::new(),
+            record_ttl: PROVIDER_RECORD_TTL,
+            fetches: HashMap::new(),
+        }
+    }
+
+    /// Insert a file and provide it under the root of its chunk tree,
+    /// which is returned.
+    pub async fn insert_file(&mut self, data: &[u8]) -> Result<blake3::Hash> {
+        let (_, chunk_hashes) = self.dht.insert(data).await?;
+        let root = ChunkTree::new(&chunk_hashes).root();
+        self.local_records.insert(root, chunk_hashes);
+        Ok(root)
+    }
+
+    /// Insert or refresh a provider record for the given key.
+    pub fn insert_provider(&mut self, key: blake3::Hash, provider: Url) {
+        let expiry = Timestamp::current_time().0 + self.record_ttl;
+        self.routing_table.entry(key).or_default().insert(provider, expiry);
+    }
+
+    /// Remove a provider record for the given key.
+    pub fn remove_provider(&mut self, key: &blake3::Hash, provider: &Url) {
+        let Some(providers) = self.routing_table.get_mut(key) else { return };
+        providers.remove(provider);
+        if providers.is_empty() {
+            self.routing_table.remove(key);
+        }
+    }
+
+    /// Retrieve the live providers of the given key, dropping any expired
+    /// records along the way.
+    pub fn providers(&mut self, key: &blake3::Hash) -> HashSet<Url> {
+        let now = Timestamp::current_time().0;
+        let Some(providers) = self.routing_table.get_mut(key) else { return HashSet::new() };
+        providers.retain(|_, expiry| *expiry > now);
+        let live = providers.keys().cloned().collect();
+        if providers.is_empty() {
+            self.routing_table.remove(key);
+        }
+        live
+    }
+
+    /// Drop all expired provider records from the routing table.
+    pub fn prune_expired(&mut self) {
+        let now = Timestamp::current_time().0;
+        self.routing_table.retain(|_, providers| {
+            providers.retain(|_, expiry| *expiry > now);
+            !providers.is_empty()
+        });
+    }
+}
+
+/// Background task periodically republishing locally provided content so
+/// that remote provider records don't expire, and pruning expired records
+/// from our own routing table.
+pub async fn republish_task(state: DhtdPtr, p2p: P2pPtr) -> Result<()> {
+    loop {
+        sleep(REPUBLISH_INTERVAL).await;
+
+        let mut state = state.write().await;
+        state.prune_expired();
+
+        debug!("Republishing {} locally provided records", state.local_records.len());
+        for (k, v) in state.local_records.iter() {
+            p2p.broadcast(&NetHashMapInsert { k: *k, v: v.clone() }).await;
+        }
+    }
+}
+
+/// Request the missing chunks of the file with the given chunk tree root,
+/// spreading the requests across its known providers. Until the number of
+/// chunks is known, only the first one is requested.
+pub async fn fetch_file(state: &DhtdPtr, p2p: &P2pPtr, root: blake3::Hash) -> Result<()> {
+    let mut state = state.write().await;
+    let providers: Vec<Url> = state.providers(&root).into_iter().collect();
+    let schedule =
+        state.fetches.entry(root).or_insert_with(|| ChunkFetch::new(root)).schedule(&providers);
+    drop(state);
+
+    let channels = p2p.channels().lock().await;
+    for (provider, indexes) in schedule {
+        let Some(channel) = channels.get(&provider) else { continue };
+        for index in indexes {
+            let request = TreeChunkRequest { root, index: index as u32 };
+            if let Err(e) = channel.send(&request).await {
+                debug!("fetch_file: Failed requesting chunk {} from {}: {}", index, provider, e);
+                break
+            }
+        }
+    }
+
+    Ok(())
 }
 
 fn main() -> Result<()> {
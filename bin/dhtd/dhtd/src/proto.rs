@@ -16,16 +16,14 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::collections::HashSet;
-
-use async_std::sync::Arc;
+use async_std::{fs, sync::Arc};
 use async_trait::async_trait;
 use darkfi::{
     dht2::net_hashmap::{NetHashMapInsert, NetHashMapRemove},
     impl_p2p_message,
     net::{
-        ChannelPtr, Message, MessageSubscription, P2pPtr, ProtocolBase, ProtocolBasePtr,
-        ProtocolJobsManager, ProtocolJobsManagerPtr,
+        reputation::Violation, ChannelPtr, Message, MessageSubscription, P2pPtr, ProtocolBase,
+        ProtocolBasePtr, ProtocolJobsManager, ProtocolJobsManagerPtr,
     },
     Result,
 };
@@ -33,12 +31,15 @@ use darkfi_serial::{SerialDecodable, SerialEncodable};
 use log::debug;
 use smol::Executor;
 
-use super::DhtdPtr;
+use super::{
+    chunks::{ChunkProof, ChunkTree},
+    fetch_file, DhtdPtr,
+};
 
 pub struct ProtocolDht {
     jobsman: ProtocolJobsManagerPtr,
     channel: ChannelPtr,
-    _p2p: P2pPtr,
+    p2p: P2pPtr,
     state: DhtdPtr,
     insert_sub: MessageSubscription<NetHashMapInsert<blake3::Hash, Vec<blake3::Hash>>>,
     remove_sub: MessageSubscription<NetHashMapRemove<blake3::Hash>>,
@@ -46,8 +47,15 @@ pub struct ProtocolDht {
     chunk_reply_sub: MessageSubscription<ChunkReply>,
     file_request_sub: MessageSubscription<FileRequest>,
     file_reply_sub: MessageSubscription<FileReply>,
+    bundle_request_sub: MessageSubscription<BundleRequest>,
+    bundle_reply_sub: MessageSubscription<BundleReply>,
+    tree_chunk_request_sub: MessageSubscription<TreeChunkRequest>,
+    tree_chunk_reply_sub: MessageSubscription<TreeChunkReply>,
 }
 
+/// Maximum number of chunks served in reply to a single `BundleRequest`
+pub const MAX_BUNDLED_CHUNKS: u32 = 8;
+
 #[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
 pub struct ChunkRequest {
     pub hash: blake3::Hash,
@@ -78,6 +86,49 @@ pub struct FileReply {
 
 impl_p2p_message!(FileReply, "dhtfilereply");
 
+/// Request for a file manifest along with its first `chunks` chunks,
+/// saving a round trip per chunk when fetching small files.
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct BundleRequest {
+    pub hash: blake3::Hash,
+    pub chunks: u32,
+}
+
+impl_p2p_message!(BundleRequest, "dhtbundlerequest");
+
+/// Reply to a `BundleRequest`, carrying the file manifest. It is followed
+/// by a `ChunkReply` for each of the first `bundled` chunks of the manifest.
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct BundleReply {
+    pub hash: blake3::Hash,
+    pub chunks: Vec<blake3::Hash>,
+    pub bundled: u32,
+}
+
+impl_p2p_message!(BundleReply, "dhtbundlereply");
+
+/// Request for the chunk at `index` of the file with the given chunk tree root
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct TreeChunkRequest {
+    pub root: blake3::Hash,
+    pub index: u32,
+}
+
+impl_p2p_message!(TreeChunkRequest, "dhttreechunkrequest");
+
+/// Reply to a `TreeChunkRequest`, carrying the chunk along with its
+/// inclusion proof in the chunk tree of `total` chunks.
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct TreeChunkReply {
+    pub root: blake3::Hash,
+    pub index: u32,
+    pub total: u32,
+    pub data: Vec<u8>,
+    pub proof: ChunkProof,
+}
+
+impl_p2p_message!(TreeChunkReply, "dhttreechunkreply");
+
 impl ProtocolDht {
     #[allow(dead_code)]
     pub async fn init(channel: ChannelPtr, p2p: P2pPtr, state: DhtdPtr) -> Result<ProtocolBasePtr> {
@@ -88,6 +139,10 @@ impl ProtocolDht {
         msg_subsystem.add_dispatch::<ChunkReply>().await;
         msg_subsystem.add_dispatch::<FileRequest>().await;
         msg_subsystem.add_dispatch::<FileReply>().await;
+        msg_subsystem.add_dispatch::<BundleRequest>().await;
+        msg_subsystem.add_dispatch::<BundleReply>().await;
+        msg_subsystem.add_dispatch::<TreeChunkRequest>().await;
+        msg_subsystem.add_dispatch::<TreeChunkReply>().await;
 
         let insert_sub = channel.subscribe_msg().await?;
         let remove_sub = channel.subscribe_msg().await?;
@@ -95,11 +150,15 @@ impl ProtocolDht {
         let chunk_reply_sub = channel.subscribe_msg().await?;
         let file_request_sub = channel.subscribe_msg().await?;
         let file_reply_sub = channel.subscribe_msg().await?;
+        let bundle_request_sub = channel.subscribe_msg().await?;
+        let bundle_reply_sub = channel.subscribe_msg().await?;
+        let tree_chunk_request_sub = channel.subscribe_msg().await?;
+        let tree_chunk_reply_sub = channel.subscribe_msg().await?;
 
         Ok(Arc::new(Self {
             jobsman: ProtocolJobsManager::new("DHTProto", channel.clone()),
             channel,
-            _p2p: p2p,
+            p2p,
             state,
             insert_sub,
             remove_sub,
@@ -107,43 +166,35 @@ impl ProtocolDht {
             chunk_reply_sub,
             file_request_sub,
             file_reply_sub,
+            bundle_request_sub,
+            bundle_reply_sub,
+            tree_chunk_request_sub,
+            tree_chunk_reply_sub,
         }))
     }
 
     async fn handle_insert(self: Arc<Self>) -> Result<()> {
         debug!("ProtocolDht::handle_insert START");
         loop {
-            let Ok(msg) = self.insert_sub.receive().await else { continue };
+            let msg = self.insert_sub.receive().await?;
 
-            let mut state = self.state.write().await;
-
-            state.routing_table.entry(msg.k).or_insert_with(HashSet::new);
-
-            let hashset = state.routing_table.get_mut(&msg.k).unwrap();
-            hashset.insert(self.channel.address().clone());
+            self.state.write().await.insert_provider(msg.k, self.channel.address().clone());
         }
     }
 
     async fn handle_remove(self: Arc<Self>) -> Result<()> {
         debug!("ProtocolDht::handle_remove START");
         loop {
-            let Ok(msg) = self.remove_sub.receive().await else { continue };
-
-            let mut state = self.state.write().await;
+            let msg = self.remove_sub.receive().await?;
 
-            if !state.routing_table.contains_key(&msg.k) {
-                continue
-            }
-
-            let hashset = state.routing_table.get_mut(&msg.k).unwrap();
-            hashset.remove(self.channel.address());
+            self.state.write().await.remove_provider(&msg.k, self.channel.address());
         }
     }
 
     async fn handle_chunk_request(self: Arc<Self>) -> Result<()> {
         debug!("ProtocolDht::handle_chunk_request START");
         loop {
-            let Ok(msg) = self.chunk_request_sub.receive().await else { continue };
+            let msg = self.chunk_request_sub.receive().await?;
 
             println!("{:?}", msg);
         }
@@ -152,7 +203,7 @@ impl ProtocolDht {
     async fn handle_chunk_reply(self: Arc<Self>) -> Result<()> {
         debug!("ProtocolDht::handle_chunk_reply START");
         loop {
-            let Ok(msg) = self.chunk_reply_sub.receive().await else { continue };
+            let msg = self.chunk_reply_sub.receive().await?;
 
             println!("{:?}", msg);
         }
@@ -161,7 +212,7 @@ impl ProtocolDht {
     async fn handle_file_request(self: Arc<Self>) -> Result<()> {
         debug!("ProtocolDht::handle_file_request START");
         loop {
-            let Ok(msg) = self.file_request_sub.receive().await else { continue };
+            let msg = self.file_request_sub.receive().await?;
 
             println!("{:?}", msg);
         }
@@ -170,11 +221,144 @@ impl ProtocolDht {
     async fn handle_file_reply(self: Arc<Self>) -> Result<()> {
         debug!("ProtocolDht::handle_file_reply START");
         loop {
-            let Ok(msg) = self.file_reply_sub.receive().await else { continue };
+            let msg = self.file_reply_sub.receive().await?;
+
+            println!("{:?}", msg);
+        }
+    }
+
+    async fn handle_bundle_request(self: Arc<Self>) -> Result<()> {
+        debug!("ProtocolDht::handle_bundle_request START");
+        loop {
+            let msg = self.bundle_request_sub.receive().await?;
+
+            let state = self.state.read().await;
+            let Some(chunks) = state.local_records.get(&msg.hash) else {
+                debug!("ProtocolDht::handle_bundle_request: {} not found", msg.hash);
+                continue
+            };
+
+            // Only bundle chunks we can actually serve, in manifest order
+            let mut bundle = vec![];
+            for hash in chunks.iter().take(msg.chunks.min(MAX_BUNDLED_CHUNKS) as usize) {
+                let mut chunk_path = state.dht.chunks_path();
+                chunk_path.push(hash.to_hex().as_str());
+                let Ok(data) = fs::read(chunk_path).await else { break };
+                bundle.push(ChunkReply { hash: *hash, data });
+            }
+
+            let reply = BundleReply {
+                hash: msg.hash,
+                chunks: chunks.clone(),
+                bundled: bundle.len() as u32,
+            };
+            drop(state);
+
+            if let Err(e) = self.channel.send(&reply).await {
+                debug!("ProtocolDht::handle_bundle_request: Failed sending reply: {}", e);
+                continue
+            }
+
+            for chunk in bundle {
+                if let Err(e) = self.channel.send(&chunk).await {
+                    debug!("ProtocolDht::handle_bundle_request: Failed sending chunk: {}", e);
+                    break
+                }
+            }
+        }
+    }
+
+    async fn handle_bundle_reply(self: Arc<Self>) -> Result<()> {
+        debug!("ProtocolDht::handle_bundle_reply START");
+        loop {
+            let msg = self.bundle_reply_sub.receive().await?;
 
             println!("{:?}", msg);
         }
     }
+
+    async fn handle_tree_chunk_request(self: Arc<Self>) -> Result<()> {
+        debug!("ProtocolDht::handle_tree_chunk_request START");
+        loop {
+            let msg = self.tree_chunk_request_sub.receive().await?;
+
+            let state = self.state.read().await;
+            let Some(chunks) = state.local_records.get(&msg.root) else {
+                debug!("ProtocolDht::handle_tree_chunk_request: {} not found", msg.root);
+                continue
+            };
+
+            let tree = ChunkTree::new(chunks);
+            let Some(proof) = tree.proof(msg.index as usize) else {
+                debug!("ProtocolDht::handle_tree_chunk_request: Invalid chunk index {}", msg.index);
+                continue
+            };
+
+            let mut chunk_path = state.dht.chunks_path();
+            chunk_path.push(chunks[msg.index as usize].to_hex().as_str());
+            drop(state);
+
+            let Ok(data) = fs::read(chunk_path).await else {
+                debug!("ProtocolDht::handle_tree_chunk_request: Chunk {} missing", msg.index);
+                continue
+            };
+
+            let reply = TreeChunkReply {
+                root: msg.root,
+                index: msg.index,
+                total: tree.len() as u32,
+                data,
+                proof,
+            };
+
+            if let Err(e) = self.channel.send(&reply).await {
+                debug!("ProtocolDht::handle_tree_chunk_request: Failed sending reply: {}", e);
+            }
+        }
+    }
+
+    async fn handle_tree_chunk_reply(self: Arc<Self>) -> Result<()> {
+        debug!("ProtocolDht::handle_tree_chunk_reply START");
+        loop {
+            let msg = self.tree_chunk_reply_sub.receive().await?;
+            let msg = (*msg).clone();
+
+            let mut state = self.state.write().await;
+            let Some(fetch) = state.fetches.get_mut(&msg.root) else { continue };
+
+            let learned_total = fetch.total.is_none();
+            if !fetch.insert(msg.index as usize, msg.total as usize, msg.data, &msg.proof) {
+                debug!(
+                    "ProtocolDht::handle_tree_chunk_reply: Chunk {} of {} failed verification",
+                    msg.index, msg.root,
+                );
+                drop(state);
+                self.channel.report_violation(Violation::FailedReply).await;
+                continue
+            }
+
+            if fetch.is_complete() {
+                let data = fetch.assemble().unwrap();
+                state.fetches.remove(&msg.root);
+                // Store the file, so we can provide it ourselves
+                if let Err(e) = state.insert_file(&data).await {
+                    debug!("ProtocolDht::handle_tree_chunk_reply: Failed storing file: {}", e);
+                }
+                continue
+            }
+            drop(state);
+
+            // Now that the number of chunks is known, request the rest
+            if learned_total {
+                if let Err(e) = fetch_file(&self.state, &self.p2p, msg.root).await {
+                    debug!(
+                        "ProtocolDht::handle_tree_chunk_reply: Failed fetching {}: {}",
+                        msg.root, e
+                    );
+                }
+            }
+        }
+    }
 }
 
 #[async_trait]
@@ -188,6 +372,10 @@ impl ProtocolBase for ProtocolDht {
         self.jobsman.clone().spawn(self.clone().handle_chunk_reply(), ex.clone()).await;
         self.jobsman.clone().spawn(self.clone().handle_file_request(), ex.clone()).await;
         self.jobsman.clone().spawn(self.clone().handle_file_reply(), ex.clone()).await;
+        self.jobsman.clone().spawn(self.clone().handle_bundle_request(), ex.clone()).await;
+        self.jobsman.clone().spawn(self.clone().handle_bundle_reply(), ex.clone()).await;
+        self.jobsman.clone().spawn(self.clone().handle_tree_chunk_request(), ex.clone()).await;
+        self.jobsman.clone().spawn(self.clone().handle_tree_chunk_reply(), ex.clone()).await;
         Ok(())
     }
 
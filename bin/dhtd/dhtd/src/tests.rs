@@ -16,8 +16,6 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::collections::HashMap;
-
 use async_std::{
     fs,
     net::TcpListener,
@@ -35,7 +33,11 @@ use smol::Executor;
 use url::Url;
 use log::error;
 
-use super::{proto::ProtocolDht, Dhtd};
+use super::{
+    chunks::{split_chunks, verify_chunk, ChunkFetch, ChunkTree},
+    proto::ProtocolDht,
+    Dhtd,
+};
 
 #[allow(dead_code)]
 async fn dht_remote_get_insert_real(ex: Arc<Executor<'_>>) -> Result<()> {
@@ -67,7 +69,7 @@ async fn dht_remote_get_insert_real(ex: Arc<Executor<'_>>) -> Result<()> {
         let mut node_path = base_path.clone();
         node_path.push(format!("node_{}", i));
         let dht = Dht::new(&node_path.into(), p2p.clone()).await?;
-        let dhtd = Arc::new(RwLock::new(Dhtd { dht, routing_table: HashMap::new() }));
+        let dhtd = Arc::new(RwLock::new(Dhtd::new(dht)));
 
         // Register P2P protocol
         let registry = p2p.protocol_registry();
@@ -108,13 +110,14 @@ async fn dht_remote_get_insert_real(ex: Arc<Executor<'_>>) -> Result<()> {
     let mut data = vec![0u8; MAX_CHUNK_SIZE];
     rng.fill_bytes(&mut data);
     let (file_hash, chunk_hashes) = dhtd.write().await.dht.insert(&data).await?;
+    dhtd.write().await.local_records.insert(file_hash, chunk_hashes.clone());
     msleep(1000).await;
 
     for (i, node) in dhtds.iter().enumerate() {
         if i == NET_SIZE - 1 {
             continue
         }
-        assert!(node.read().await.routing_table.contains_key(&file_hash));
+        assert!(!node.write().await.providers(&file_hash).is_empty());
     }
 
     let dhtd = &mut dhtds[NET_SIZE - 1];
@@ -129,8 +132,7 @@ async fn dht_remote_get_insert_real(ex: Arc<Executor<'_>>) -> Result<()> {
             continue
         }
 
-        let peers = node.read().await.routing_table.get(&file_hash).unwrap().clone();
-        assert!(peers.is_empty());
+        assert!(node.write().await.providers(&file_hash).is_empty());
     }
 
     fs::remove_dir_all(base_path).await?;
@@ -165,3 +167,87 @@ fn dht_remote_get_insert() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn dht_provider_record_expiry() -> Result<()> {
+    smol::block_on(async {
+        let mut node_path = std::env::temp_dir();
+        node_path.push("dht_expiry");
+
+        let p2p = P2p::new(net::Settings::default()).await;
+        let dht = Dht::new(&node_path.clone().into(), p2p).await?;
+        let mut dhtd = Dhtd::new(dht);
+
+        let key = blake3::hash(b"provided");
+        let provider = Url::parse("tcp://127.0.0.1:1234")?;
+
+        // Live records are returned
+        dhtd.insert_provider(key, provider.clone());
+        assert!(dhtd.providers(&key).contains(&provider));
+
+        // Refreshing a record keeps a single entry
+        dhtd.insert_provider(key, provider.clone());
+        assert_eq!(dhtd.providers(&key).len(), 1);
+
+        // Expired records are dropped lazily on lookup
+        dhtd.record_ttl = 0;
+        dhtd.insert_provider(key, provider.clone());
+        assert!(dhtd.providers(&key).is_empty());
+        assert!(!dhtd.routing_table.contains_key(&key));
+
+        // And on pruning
+        dhtd.insert_provider(key, provider);
+        assert!(dhtd.routing_table.contains_key(&key));
+        dhtd.prune_expired();
+        assert!(dhtd.routing_table.is_empty());
+
+        fs::remove_dir_all(node_path).await?;
+        Ok(())
+    })
+}
+
+#[test]
+fn dht_chunk_tree_verification() {
+    let mut data = vec![0u8; MAX_CHUNK_SIZE * 4 + 42];
+    OsRng.fill_bytes(&mut data);
+
+    let chunks = split_chunks(&data);
+    assert_eq!(chunks.len(), 5);
+    let tree = ChunkTree::from_chunks(&chunks);
+    let root = tree.root();
+
+    // Every chunk verifies against the root with its proof, and only at its index
+    for (i, chunk) in chunks.iter().enumerate() {
+        let proof = tree.proof(i).unwrap();
+        assert!(verify_chunk(&root, i, chunks.len(), chunk, &proof));
+        assert!(!verify_chunk(&root, (i + 1) % chunks.len(), chunks.len(), chunk, &proof));
+    }
+    assert!(tree.proof(chunks.len()).is_none());
+
+    // Fetch the chunks from two providers, out of order
+    let providers = vec![
+        Url::parse("tcp://127.0.0.1:1234").unwrap(),
+        Url::parse("tcp://127.0.0.1:1235").unwrap(),
+    ];
+    let mut fetch = ChunkFetch::new(root);
+    assert_eq!(fetch.missing(), vec![0]);
+
+    // A tampered chunk is rejected
+    let mut tampered = chunks[0].to_vec();
+    tampered[0] ^= 1;
+    assert!(!fetch.insert(0, chunks.len(), tampered, &tree.proof(0).unwrap()));
+    assert!(fetch.total.is_none());
+
+    assert!(fetch.insert(0, chunks.len(), chunks[0].to_vec(), &tree.proof(0).unwrap()));
+    let schedule = fetch.schedule(&providers);
+    assert_eq!(schedule.len(), 2);
+    assert_eq!(schedule[0].1, vec![1, 3]);
+    assert_eq!(schedule[1].1, vec![2, 4]);
+
+    for i in [4, 2, 3, 1] {
+        assert!(fetch.assemble().is_none());
+        assert!(fetch.insert(i, chunks.len(), chunks[i].to_vec(), &tree.proof(i).unwrap()));
+    }
+    assert!(fetch.is_complete());
+    assert_eq!(fetch.assemble().unwrap(), data);
+}
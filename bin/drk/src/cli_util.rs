@@ -17,10 +17,8 @@
  */
 use std::{io::Cursor, process::exit};
 
-use darkfi::{
-    util::{async_util::sleep, parse::decode_base10},
-    Result,
-};
+use darkfi::{util::async_util::sleep, Result};
+use darkfi_money_contract::client::amount::{parse_amount, DEFAULT_DECIMALS};
 use darkfi_sdk::crypto::TokenId;
 use rodio::{source::Source, Decoder, OutputStream};
 
@@ -34,8 +32,8 @@ pub fn parse_value_pair(s: &str) -> Result<(u64, u64)> {
     }
 
     // TODO: We shouldn't be hardcoding everything to 8 decimals.
-    let val0 = decode_base10(v[0], 8, true);
-    let val1 = decode_base10(v[1], 8, true);
+    let val0 = parse_amount(v[0], DEFAULT_DECIMALS);
+    let val1 = parse_amount(v[1], DEFAULT_DECIMALS);
 
     if val0.is_err() || val1.is_err() {
         eprintln!("Invalid value pair. Use a pair such as 13.37:11.0");
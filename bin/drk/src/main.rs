@@ -17,6 +17,7 @@
  */
 
 use std::{
+    collections::HashMap,
     io::{stdin, Read},
     process::exit,
     str::FromStr,
@@ -26,10 +27,20 @@ use std::{
 use anyhow::{anyhow, Context, Result};
 use clap::{CommandFactory, Parser, Subcommand};
 use clap_complete::{generate, Shell};
-use darkfi::{tx::Transaction, util::parse::decode_base10, zk::halo2::Field};
-use darkfi_money_contract::model::Coin;
+use darkfi::{
+    tx::Transaction,
+    zk::halo2::Field,
+    zkas::{compat::check_compatibility, ZkBinary},
+};
+use darkfi_money_contract::{
+    client::{
+        amount::{format_amount, parse_amount, DEFAULT_DECIMALS},
+        view_key::ViewKey,
+    },
+    model::Coin,
+};
 use darkfi_sdk::{
-    crypto::{PublicKey, SecretKey, TokenId},
+    crypto::{ContractId, Keypair, PublicKey, SecretKey, TokenId},
     pasta::{group::ff::PrimeField, pallas},
 };
 use darkfi_serial::{deserialize, serialize};
@@ -42,10 +53,7 @@ use url::Url;
 use darkfi::{
     cli_desc,
     rpc::{client::RpcClient, jsonrpc::JsonRequest},
-    util::{
-        cli::{get_log_config, get_log_level},
-        parse::encode_base10,
-    },
+    util::cli::{get_log_config, get_log_level},
 };
 
 /// Airdrop methods
@@ -53,6 +61,7 @@ mod rpc_airdrop;
 
 /// Payment methods
 mod rpc_transfer;
+use rpc_transfer::UnsignedTransfer;
 
 /// Swap methods
 mod rpc_swap;
@@ -67,6 +76,9 @@ mod rpc_token;
 /// Blockchain methods
 mod rpc_blockchain;
 
+/// Paper key and chunk encoding utilities for offline signing
+mod paper_key;
+
 /// CLI utility functions
 mod cli_util;
 use cli_util::{kaching, parse_token_pair, parse_value_pair};
@@ -87,6 +99,16 @@ mod wallet_token;
 /// Wallet functionality related to transactions history
 mod wallet_txs_history;
 
+/// Wallet functionality related to spend intents
+mod wallet_spend_intents;
+
+/// Wallet functionality related to coin receive sources
+mod wallet_coin_sources;
+use wallet_coin_sources::CoinSelection;
+
+/// Wallet functionality related to Merkle witnesses of our coins
+mod wallet_coin_witnesses;
+
 #[derive(Parser)]
 #[command(about = cli_desc!())]
 struct Args {
@@ -142,6 +164,16 @@ enum Subcmd {
         /// Import secret keys from stdin into the wallet, separated by newlines
         import_secrets: bool,
 
+        #[arg(long)]
+        /// Import view keys from stdin into the wallet, separated by newlines.
+        /// A wallet holding only view keys is watch-only and can't spend.
+        import_view_keys: bool,
+
+        #[arg(long)]
+        /// Print the view keys of the wallet's secret keys. They can find
+        /// incoming coins when imported into a watch-only wallet.
+        export_view_keys: bool,
+
         #[arg(long)]
         /// Print the Merkle tree in the wallet
         tree: bool,
@@ -187,6 +219,25 @@ enum Subcmd {
 
         /// DAO bulla, if the tokens are being sent to a DAO
         dao_bulla: Option<String>,
+
+        /// Export an unsigned transfer to be signed elsewhere,
+        /// instead of creating the transaction
+        #[clap(long)]
+        unsigned: bool,
+
+        /// Only spend coins received from a single source, so coins
+        /// from different counterparties don't get linked
+        #[clap(long)]
+        private: bool,
+
+        /// Confirm linking coins from different sources, if no single
+        /// source can fund a private payment
+        #[clap(long, requires = "private")]
+        allow_linking: bool,
+
+        /// Recipient's view address, so their view key can see the payment
+        #[clap(long)]
+        view_address: Option<String>,
     },
 
     /// OTC atomic swap
@@ -222,6 +273,31 @@ enum Subcmd {
         checkpoint: Option<u64>,
     },
 
+    /// Recover a wallet from its mnemonic by scanning the entire blockchain.
+    /// This wipes the existing wallet.
+    Recover {
+        /// Path to a file containing the mnemonic
+        mnemonic_file: String,
+
+        #[arg(short, long, default_value = "10")]
+        /// Number of keys to derive from the mnemonic
+        keys: u32,
+
+        #[arg(long)]
+        /// Resume an interrupted recovery from the last scanned slot
+        resume: bool,
+    },
+
+    /// Check locally compiled zkas circuits against the versions deployed
+    /// for a contract, reporting changes that break proof compatibility
+    CheckZkas {
+        /// Contract ID the circuits are deployed under
+        contract_id: String,
+
+        /// Paths to the compiled zkas circuits
+        files: Vec<String>,
+    },
+
     /// Explorer related subcommands
     #[command(subcommand)]
     Explorer(ExplorerSubcmd),
@@ -233,6 +309,10 @@ enum Subcmd {
     /// Token functionalities
     #[command(subcommand)]
     Token(TokenSubcmd),
+
+    /// Air-gapped signing with paper keys
+    #[command(subcommand)]
+    Offline(OfflineSubcmd),
 }
 
 #[derive(Subcommand)]
@@ -354,6 +434,41 @@ enum DaoSubcmd {
     },
 }
 
+#[derive(Subcommand)]
+enum OfflineSubcmd {
+    /// Generate a new mnemonic and print it along with the derived keys
+    Keygen {
+        #[arg(short, long, default_value = "0")]
+        /// Derivation index of the key
+        index: u32,
+    },
+
+    /// Split base58-encoded data from stdin into QR-sized chunks
+    Encode {
+        #[arg(short, long, default_value_t = paper_key::DEFAULT_CHUNK_SIZE)]
+        /// Maximum payload bytes per chunk
+        chunk_size: usize,
+    },
+
+    /// Reassemble chunks from stdin, one per line, into base58-encoded data
+    Decode,
+
+    /// Sign an unsigned transfer read from stdin as chunks, and print
+    /// the signed transaction as chunks. Does not need darkfid.
+    Sign {
+        /// Path to a file containing the mnemonic
+        mnemonic_file: String,
+
+        #[arg(short, long, default_value = "0")]
+        /// Derivation index of the key
+        index: u32,
+
+        #[arg(short, long, default_value_t = paper_key::DEFAULT_CHUNK_SIZE)]
+        /// Maximum payload bytes per chunk
+        chunk_size: usize,
+    },
+}
+
 #[derive(Subcommand)]
 enum ExplorerSubcmd {
     /// Fetch a blockchain transaction by hash
@@ -383,6 +498,34 @@ enum ExplorerSubcmd {
         /// to base58.
         encode: bool,
     },
+
+    /// Manage transaction labels
+    #[command(subcommand)]
+    Label(LabelSubcmd),
+}
+
+#[derive(Subcommand)]
+enum LabelSubcmd {
+    /// Set the label of a transaction
+    Set {
+        /// Transaction hash
+        tx_hash: String,
+
+        /// Label text
+        label: String,
+    },
+
+    /// Remove the label of a transaction
+    Remove {
+        /// Transaction hash
+        tx_hash: String,
+    },
+
+    /// Export all labels as JSON
+    Export,
+
+    /// Import labels from JSON read from stdin, as produced by export
+    Import,
 }
 
 #[derive(Subcommand)]
@@ -436,6 +579,10 @@ enum TokenSubcmd {
 
         /// Recipient of the minted tokens
         recipient: String,
+
+        #[arg(long)]
+        /// Maximum supply of the token, only settable on its first mint
+        max_supply: Option<String>,
     },
 
     /// Freeze a token mint
@@ -515,6 +662,8 @@ async fn main() -> Result<()> {
             address,
             secrets,
             import_secrets,
+            import_view_keys,
+            export_view_keys,
             tree,
             coins,
         } => {
@@ -525,7 +674,9 @@ async fn main() -> Result<()> {
                 !secrets &&
                 !tree &&
                 !coins &&
-                !import_secrets
+                !import_secrets &&
+                !import_view_keys &&
+                !export_view_keys
             {
                 eprintln!("Error: You must use at least one flag for this subcommand");
                 eprintln!("Run with \"wallet -h\" to see the subcommand usage.");
@@ -566,7 +717,11 @@ async fn main() -> Result<()> {
                     };
 
                     // FIXME: Don't hardcode to 8 decimals
-                    table.add_row(row![token_id, aliases, encode_base10(*balance, 8)]);
+                    table.add_row(row![
+                        token_id,
+                        aliases,
+                        format_amount(*balance, DEFAULT_DECIMALS)
+                    ]);
                 }
 
                 if table.is_empty() {
@@ -575,14 +730,44 @@ async fn main() -> Result<()> {
                     println!("{}", table);
                 }
 
+                // Coins found through view keys can't be seen getting spent,
+                // so they are listed apart and never counted as a balance.
+                let received =
+                    drk.money_received().await.with_context(|| "Failed to fetch received coins")?;
+                if !received.is_empty() {
+                    let mut table = Table::new();
+                    table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+                    table.set_titles(row!["Token ID", "Aliases", "Received"]);
+                    for (token_id, received) in received.iter() {
+                        let aliases = match aliases_map.get(token_id) {
+                            Some(a) => a,
+                            None => "-",
+                        };
+
+                        table.add_row(row![
+                            token_id,
+                            aliases,
+                            format_amount(*received, DEFAULT_DECIMALS)
+                        ]);
+                    }
+
+                    eprintln!("Received through view keys, spends are not visible to them:");
+                    println!("{}", table);
+                }
+
                 return Ok(())
             }
 
             if address {
-                let address = drk
-                    .wallet_address(1) // <-- TODO: Use is_default from the sql table
-                    .await
-                    .with_context(|| "Failed to fetch default address")?;
+                let address = if drk.is_watch_only().await? {
+                    drk.wallet_view_address()
+                        .await
+                        .with_context(|| "Failed to fetch view key address")?
+                } else {
+                    drk.wallet_address(1) // <-- TODO: Use is_default from the sql table
+                        .await
+                        .with_context(|| "Failed to fetch default address")?
+                };
 
                 println!("{}", address);
 
@@ -632,6 +817,49 @@ async fn main() -> Result<()> {
                 return Ok(())
             }
 
+            if import_view_keys {
+                let mut keys = vec![];
+                let lines = stdin().lines();
+                for (i, line) in lines.enumerate() {
+                    if let Ok(line) = line {
+                        let bytes = bs58::decode(&line.trim()).into_vec()?;
+                        let Ok(key) = deserialize::<SecretKey>(&bytes) else {
+                            eprintln!("Warning: Failed to deserialize view key on line {}", i);
+                            continue
+                        };
+                        keys.push(ViewKey::from(key));
+                    }
+                }
+
+                let pubkeys = drk
+                    .import_money_view_keys(keys)
+                    .await
+                    .with_context(|| "Failed to import view keys into wallet")?;
+
+                drk.rpc_client.close().await?;
+
+                for key in pubkeys {
+                    println!("{}", key);
+                }
+
+                return Ok(())
+            }
+
+            if export_view_keys {
+                let keys = drk
+                    .export_money_view_keys()
+                    .await
+                    .with_context(|| "Failed to export view keys from wallet")?;
+
+                drk.rpc_client.close().await?;
+
+                for key in keys {
+                    println!("{}", bs58::encode(&serialize(&key.secret())).into_string());
+                }
+
+                return Ok(())
+            }
+
             if tree {
                 let v =
                     drk.get_money_tree().await.with_context(|| "Failed to fetch Merkle tree")?;
@@ -694,7 +922,11 @@ async fn main() -> Result<()> {
                         coin.1,
                         coin.0.note.token_id,
                         aliases,
-                        format!("{} ({})", coin.0.note.value, encode_base10(coin.0.note.value, 8)),
+                        format!(
+                            "{} ({})",
+                            coin.0.note.value,
+                            format_amount(coin.0.note.value, DEFAULT_DECIMALS)
+                        ),
                         spend_hook,
                         user_data
                     ]);
@@ -744,14 +976,44 @@ async fn main() -> Result<()> {
             Ok(())
         }
 
-        Subcmd::Transfer { amount, token, recipient, dao, dao_bulla } => {
+        Subcmd::Transfer {
+            amount,
+            token,
+            recipient,
+            dao,
+            dao_bulla,
+            unsigned,
+            private,
+            allow_linking,
+            view_address,
+        } => {
             let _ = f64::from_str(&amount).with_context(|| "Invalid amount")?;
             let rcpt = PublicKey::from_str(&recipient).with_context(|| "Invalid recipient")?;
+            let rcpt_view = view_address
+                .map(|v| PublicKey::from_str(&v))
+                .transpose()
+                .with_context(|| "Invalid view address")?;
             let drk = Drk::new(args.endpoint).await?;
             let token_id = drk.get_token(token).await.with_context(|| "Invalid token alias")?;
 
+            let selection =
+                if private { CoinSelection::Private { allow_linking } } else { CoinSelection::Any };
+
+            if unsigned {
+                let transfer = drk
+                    .transfer_unsigned(
+                        &amount, token_id, rcpt, rcpt_view, dao, dao_bulla, selection,
+                    )
+                    .await
+                    .with_context(|| "Failed to create unsigned transfer")?;
+
+                println!("{}", bs58::encode(&serialize(&transfer)).into_string());
+
+                return Ok(())
+            }
+
             let tx = drk
-                .transfer(&amount, token_id, rcpt, dao, dao_bulla)
+                .transfer(&amount, token_id, rcpt, rcpt_view, dao, dao_bulla, selection)
                 .await
                 .with_context(|| "Failed to create payment transaction")?;
 
@@ -871,6 +1133,9 @@ async fn main() -> Result<()> {
             if reset {
                 eprintln!("Reset requested.");
                 drk.scan_blocks(true).await.with_context(|| "Failed during scanning")?;
+                drk.reconcile_spend_intents()
+                    .await
+                    .with_context(|| "Failed to reconcile spend intents")?;
 
                 return Ok(())
             }
@@ -892,6 +1157,72 @@ async fn main() -> Result<()> {
             drk.scan_blocks(false).await.with_context(|| "Failed during scanning")?;
             eprintln!("Finished scanning blockchain");
 
+            drk.reconcile_spend_intents()
+                .await
+                .with_context(|| "Failed to reconcile spend intents")?;
+
+            Ok(())
+        }
+
+        Subcmd::Recover { mnemonic_file, keys, resume } => {
+            let phrase = std::fs::read_to_string(&mnemonic_file)
+                .with_context(|| "Failed to read mnemonic file")?;
+            let mnemonic = paper_key::parse_mnemonic(&phrase)?;
+
+            let drk = Drk::new(args.endpoint).await?;
+            drk.recover_wallet(&mnemonic, keys, resume)
+                .await
+                .with_context(|| "Failed during wallet recovery")?;
+            eprintln!("Finished recovery scan. If it was interrupted, rerun with --resume");
+
+            Ok(())
+        }
+
+        Subcmd::CheckZkas { contract_id, files } => {
+            let contract_id = ContractId::from_str(&contract_id)?;
+
+            let drk = Drk::new(args.endpoint).await?;
+            let zkas_bins = drk
+                .lookup_zkas(&contract_id)
+                .await
+                .with_context(|| "Failed to fetch deployed zkas circuits")?;
+
+            let mut compatible = true;
+            for file in files {
+                let bincode =
+                    std::fs::read(&file).with_context(|| format!("Failed to read {}", file))?;
+                let local = ZkBinary::decode(&bincode)?;
+
+                let Some((_, deployed)) = zkas_bins.iter().find(|(ns, _)| ns == &local.namespace)
+                else {
+                    println!(
+                        "{}: \"{}\" is not deployed, nothing to compare",
+                        file, local.namespace
+                    );
+                    continue
+                };
+                let deployed = ZkBinary::decode(deployed)?;
+
+                let incompatibilities = check_compatibility(&deployed, &local);
+                if incompatibilities.is_empty() {
+                    println!("{}: \"{}\" is compatible", file, local.namespace);
+                    continue
+                }
+
+                compatible = false;
+                println!(
+                    "{}: \"{}\" is incompatible with the deployed version:",
+                    file, local.namespace
+                );
+                for incompatibility in incompatibilities {
+                    println!("  - {}", incompatibility);
+                }
+            }
+
+            if !compatible {
+                exit(1);
+            }
+
             Ok(())
         }
 
@@ -900,8 +1231,8 @@ async fn main() -> Result<()> {
                 let _ = f64::from_str(&proposer_limit).with_context(|| "Invalid proposer limit")?;
                 let _ = f64::from_str(&quorum).with_context(|| "Invalid quorum")?;
 
-                let proposer_limit = decode_base10(&proposer_limit, 8, true)?;
-                let quorum = decode_base10(&quorum, 8, true)?;
+                let proposer_limit = parse_amount(&proposer_limit, DEFAULT_DECIMALS)?;
+                let quorum = parse_amount(&quorum, DEFAULT_DECIMALS)?;
 
                 if approval_ratio > 1.0 {
                     eprintln!("Error: Approval ratio cannot be >1.0");
@@ -995,7 +1326,11 @@ async fn main() -> Result<()> {
                     };
 
                     // FIXME: Don't hardcode to 8 decimals
-                    table.add_row(row![token_id, aliases, encode_base10(*balance, 8)]);
+                    table.add_row(row![
+                        token_id,
+                        aliases,
+                        format_amount(*balance, DEFAULT_DECIMALS)
+                    ]);
                 }
 
                 if table.is_empty() {
@@ -1018,7 +1353,7 @@ async fn main() -> Result<()> {
 
             DaoSubcmd::Propose { dao_alias, recipient, amount, token } => {
                 let _ = f64::from_str(&amount).with_context(|| "Invalid amount")?;
-                let amount = decode_base10(&amount, 8, true)?;
+                let amount = parse_amount(&amount, DEFAULT_DECIMALS)?;
                 let rcpt = PublicKey::from_str(&recipient).with_context(|| "Invalid recipient")?;
                 let drk = Drk::new(args.endpoint).await?;
                 let dao_id = drk.get_dao_id(&dao_alias).await?;
@@ -1073,7 +1408,7 @@ async fn main() -> Result<()> {
                 let dao_id = drk.get_dao_id(&dao_alias).await?;
 
                 let _ = f64::from_str(&vote_weight).with_context(|| "Invalid vote weight")?;
-                let weight = decode_base10(&vote_weight, 8, true)?;
+                let weight = parse_amount(&vote_weight, DEFAULT_DECIMALS)?;
 
                 if vote > 1 {
                     eprintln!("Vote can be either 0 (NO) or 1 (YES)");
@@ -1171,21 +1506,31 @@ async fn main() -> Result<()> {
                         exit(1)
                     }
 
+                    let labels = drk.get_tx_labels().await?;
+
                     println!("Transaction ID: {}", tx_hash);
                     println!("Status: {}", status);
+                    if let Some(label) = labels.get(&tx_hash) {
+                        println!("Label: {}", label);
+                    }
                     println!("{:?}", tx);
 
                     return Ok(())
                 }
 
                 let map = drk.get_txs_history().await?;
+                let labels = drk.get_tx_labels().await?;
 
                 // Create a prettytable with the new data:
                 let mut table = Table::new();
                 table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
-                table.set_titles(row!["Transaction Hash", "Status"]);
+                table.set_titles(row!["Transaction Hash", "Status", "Label"]);
                 for (txs_hash, status) in map.iter() {
-                    table.add_row(row![txs_hash, status]);
+                    let label = match labels.get(txs_hash) {
+                        Some(l) => l,
+                        None => "-",
+                    };
+                    table.add_row(row![txs_hash, status, label]);
                 }
 
                 if table.is_empty() {
@@ -1196,6 +1541,46 @@ async fn main() -> Result<()> {
 
                 Ok(())
             }
+
+            ExplorerSubcmd::Label(cmd) => {
+                let drk = Drk::new(args.endpoint).await?;
+
+                match cmd {
+                    LabelSubcmd::Set { tx_hash, label } => {
+                        drk.set_tx_label(&tx_hash, &label)
+                            .await
+                            .with_context(|| "Failed to set transaction label")?;
+                    }
+
+                    LabelSubcmd::Remove { tx_hash } => {
+                        drk.remove_tx_label(&tx_hash)
+                            .await
+                            .with_context(|| "Failed to remove transaction label")?;
+                    }
+
+                    LabelSubcmd::Export => {
+                        let labels = drk
+                            .get_tx_labels()
+                            .await
+                            .with_context(|| "Failed to fetch transaction labels")?;
+                        println!("{}", serde_json::to_string_pretty(&labels)?);
+                    }
+
+                    LabelSubcmd::Import => {
+                        let mut buf = String::new();
+                        stdin().read_to_string(&mut buf)?;
+                        let labels: HashMap<String, String> = serde_json::from_str(&buf)
+                            .with_context(|| "Failed to parse transaction labels")?;
+
+                        drk.import_tx_labels(&labels)
+                            .await
+                            .with_context(|| "Failed to import transaction labels")?;
+                        eprintln!("Imported {} transaction labels", labels.len());
+                    }
+                }
+
+                Ok(())
+            }
         },
 
         Subcmd::Alias(cmd) => match cmd {
@@ -1308,14 +1693,18 @@ async fn main() -> Result<()> {
             }
 
             // TODO: Mint directly into DAO treasury
-            TokenSubcmd::Mint { token, amount, recipient } => {
+            TokenSubcmd::Mint { token, amount, recipient, max_supply } => {
                 let drk = Drk::new(args.endpoint).await?;
                 let _ = f64::from_str(&amount).with_context(|| "Invalid amount")?;
                 let rcpt = PublicKey::from_str(&recipient).with_context(|| "Invalid recipient")?;
                 let token_id = drk.get_token(token).await.with_context(|| "Invalid Token ID")?;
+                let max_supply = max_supply
+                    .map(|v| parse_amount(&v, DEFAULT_DECIMALS))
+                    .transpose()
+                    .with_context(|| "Invalid maximum supply")?;
 
                 let tx = drk
-                    .mint_token(&amount, rcpt, token_id)
+                    .mint_token(&amount, rcpt, token_id, max_supply)
                     .await
                     .with_context(|| "Failed to create token mint transaction")?;
 
@@ -1338,5 +1727,81 @@ async fn main() -> Result<()> {
                 Ok(())
             }
         },
+
+        Subcmd::Offline(cmd) => match cmd {
+            OfflineSubcmd::Keygen { index } => {
+                let mnemonic = paper_key::generate_mnemonic()?;
+                let secret = paper_key::derive_secret(&mnemonic, index);
+
+                let view_key = ViewKey::derive(&secret);
+
+                eprintln!("Write down the mnemonic and keep it safe. It controls your funds.");
+                eprintln!("The view key only sees coins sent to the view address. Import it");
+                eprintln!("into watch-only wallets with `drk wallet --import-view-keys`.");
+                println!("Mnemonic: {}", mnemonic);
+                println!("Address: {}", PublicKey::from_secret(secret));
+                println!("View address: {}", view_key.public());
+                println!(
+                    "View key: {}",
+                    bs58::encode(&serialize(&view_key.secret())).into_string()
+                );
+
+                Ok(())
+            }
+
+            OfflineSubcmd::Encode { chunk_size } => {
+                let mut buf = String::new();
+                stdin().read_to_string(&mut buf)?;
+                let bytes = bs58::decode(&buf.trim()).into_vec()?;
+
+                for chunk in paper_key::encode_chunks(&bytes, chunk_size)? {
+                    println!("{}", chunk);
+                }
+
+                Ok(())
+            }
+
+            OfflineSubcmd::Decode => {
+                let chunks: Vec<String> = stdin()
+                    .lines()
+                    .map_while(|l| l.ok())
+                    .filter(|l| !l.trim().is_empty())
+                    .collect();
+
+                let bytes = paper_key::decode_chunks(&chunks)?;
+                println!("{}", bs58::encode(&bytes).into_string());
+
+                Ok(())
+            }
+
+            OfflineSubcmd::Sign { mnemonic_file, index, chunk_size } => {
+                let phrase = std::fs::read_to_string(&mnemonic_file)
+                    .with_context(|| "Failed to read mnemonic file")?;
+                let mnemonic = paper_key::parse_mnemonic(&phrase)?;
+                let keypair = Keypair::new(paper_key::derive_secret(&mnemonic, index));
+
+                let chunks: Vec<String> = stdin()
+                    .lines()
+                    .map_while(|l| l.ok())
+                    .filter(|l| !l.trim().is_empty())
+                    .collect();
+
+                let bytes = paper_key::decode_chunks(&chunks)?;
+                let transfer: UnsignedTransfer =
+                    deserialize(&bytes).with_context(|| "Failed to decode unsigned transfer")?;
+
+                // The transfer carries no secrets, so the coins' secrets and
+                // nullifiers get re-derived from the mnemonic key.
+                let (tx, _) = transfer
+                    .sign(keypair, &[keypair.secret])
+                    .with_context(|| "Failed to sign transfer with the mnemonic key")?;
+
+                for chunk in paper_key::encode_chunks(&serialize(&tx), chunk_size)? {
+                    println!("{}", chunk);
+                }
+
+                Ok(())
+            }
+        },
     }
 }
@@ -0,0 +1,224 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use anyhow::{anyhow, Result};
+use bip39::Mnemonic;
+use darkfi_sdk::{
+    crypto::SecretKey,
+    pasta::{group::ff::FromUniformBytes, pallas},
+};
+use darkfi_serial::{deserialize, serialize, SerialDecodable, SerialEncodable};
+use rand::{rngs::OsRng, RngCore};
+
+/// Default maximum payload size of a single chunk, in bytes. Once base58
+/// encoded, a chunk of this size still fits a QR code comfortably.
+pub const DEFAULT_CHUNK_SIZE: usize = 1024;
+
+/// Context string for deriving spend keys out of a mnemonic seed
+const KEY_DERIVATION_CONTEXT: &str = "DarkFi drk paper key derivation";
+
+/// A size-bounded piece of a larger payload, which can be moved across an
+/// air gap on its own (e.g. as a QR code) and reassembled on the other side.
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+struct Chunk {
+    /// Hash of the complete payload, used to group and verify chunks
+    digest: [u8; 32],
+    /// Position of this chunk in the payload
+    index: u16,
+    /// Total number of chunks the payload was split into
+    total: u16,
+    /// Chunk data
+    data: Vec<u8>,
+}
+
+/// Split the given payload into base58-encoded chunks carrying at most
+/// `chunk_size` bytes of it each.
+pub fn encode_chunks(payload: &[u8], chunk_size: usize) -> Result<Vec<String>> {
+    if chunk_size == 0 {
+        return Err(anyhow!("Chunk size must be greater than zero"))
+    }
+
+    let digest = *blake3::hash(payload).as_bytes();
+    let pieces: Vec<&[u8]> =
+        if payload.is_empty() { vec![&[]] } else { payload.chunks(chunk_size).collect() };
+
+    let Ok(total) = u16::try_from(pieces.len()) else {
+        return Err(anyhow!("Payload too large for chunk size {}", chunk_size))
+    };
+
+    let mut ret = Vec::with_capacity(pieces.len());
+    for (index, data) in pieces.into_iter().enumerate() {
+        let chunk = Chunk { digest, index: index as u16, total, data: data.to_vec() };
+        ret.push(bs58::encode(&serialize(&chunk)).into_string());
+    }
+
+    Ok(ret)
+}
+
+/// Reassemble a payload out of its base58-encoded chunks. Chunks can be
+/// given in any order and duplicates are ignored, but all of them must
+/// belong to the same payload.
+pub fn decode_chunks<S: AsRef<str>>(encoded: &[S]) -> Result<Vec<u8>> {
+    let mut chunks: Vec<Chunk> = Vec::with_capacity(encoded.len());
+    for (i, e) in encoded.iter().enumerate() {
+        let bytes = bs58::decode(e.as_ref().trim()).into_vec()?;
+        let Ok(chunk) = deserialize::<Chunk>(&bytes) else {
+            return Err(anyhow!("Failed to decode chunk {}", i))
+        };
+        chunks.push(chunk);
+    }
+
+    let Some(first) = chunks.first() else { return Err(anyhow!("No chunks given")) };
+    let (digest, total) = (first.digest, first.total);
+
+    let mut slots: Vec<Option<Vec<u8>>> = vec![None; total as usize];
+    for chunk in chunks {
+        if chunk.digest != digest || chunk.total != total {
+            return Err(anyhow!("Chunks belong to different payloads"))
+        }
+
+        let Some(slot) = slots.get_mut(chunk.index as usize) else {
+            return Err(anyhow!("Chunk index {} out of range", chunk.index))
+        };
+        *slot = Some(chunk.data);
+    }
+
+    let mut payload = vec![];
+    for (i, slot) in slots.into_iter().enumerate() {
+        let Some(data) = slot else { return Err(anyhow!("Missing chunk {}/{}", i + 1, total)) };
+        payload.extend_from_slice(&data);
+    }
+
+    if blake3::hash(&payload).as_bytes() != &digest {
+        return Err(anyhow!("Reassembled payload does not match its digest"))
+    }
+
+    Ok(payload)
+}
+
+/// Generate a new random 24-word mnemonic.
+pub fn generate_mnemonic() -> Result<Mnemonic> {
+    let mut entropy = [0u8; 32];
+    OsRng.fill_bytes(&mut entropy);
+    Ok(Mnemonic::from_entropy(&entropy)?)
+}
+
+/// Parse a mnemonic phrase, e.g. as read back from paper.
+pub fn parse_mnemonic(phrase: &str) -> Result<Mnemonic> {
+    match Mnemonic::parse(phrase.trim()) {
+        Ok(m) => Ok(m),
+        Err(e) => Err(anyhow!("Invalid mnemonic: {}", e)),
+    }
+}
+
+/// Deterministically derive the spend key at `index` from a mnemonic.
+pub fn derive_secret(mnemonic: &Mnemonic, index: u32) -> SecretKey {
+    let mut hasher = blake3::Hasher::new_derive_key(KEY_DERIVATION_CONTEXT);
+    hasher.update(&mnemonic.to_seed(""));
+    hasher.update(&index.to_le_bytes());
+
+    let mut bytes = [0u8; 64];
+    hasher.finalize_xof().fill(&mut bytes);
+
+    SecretKey::from(pallas::Base::from_uniform_bytes(&bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// BIP-39 test vector mnemonic
+    const PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon \
+                          abandon abandon abandon abandon abandon abandon abandon abandon \
+                          abandon abandon abandon abandon abandon abandon abandon art";
+
+    #[test]
+    fn chunks_round_trip() {
+        let payload: Vec<u8> = (0..2500u32).map(|i| (i % 251) as u8).collect();
+
+        let mut chunks = encode_chunks(&payload, 1000).unwrap();
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(decode_chunks(&chunks).unwrap(), payload);
+
+        // Order doesn't matter and duplicates are ignored
+        chunks.reverse();
+        chunks.push(chunks[0].clone());
+        assert_eq!(decode_chunks(&chunks).unwrap(), payload);
+
+        // An empty payload still makes a single chunk
+        let chunks = encode_chunks(&[], DEFAULT_CHUNK_SIZE).unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert!(decode_chunks(&chunks).unwrap().is_empty());
+
+        assert!(encode_chunks(&payload, 0).is_err());
+        assert!(decode_chunks::<String>(&[]).is_err());
+    }
+
+    #[test]
+    fn chunks_corrupted() {
+        let payload: Vec<u8> = (0..2500u32).map(|i| (i % 251) as u8).collect();
+        let chunks = encode_chunks(&payload, 1000).unwrap();
+
+        // Flipping a data byte breaks the payload digest
+        let mut bytes = bs58::decode(&chunks[1]).into_vec().unwrap();
+        *bytes.last_mut().unwrap() ^= 1;
+        let mut corrupted = chunks.clone();
+        corrupted[1] = bs58::encode(&bytes).into_string();
+        assert!(decode_chunks(&corrupted).is_err());
+
+        // Garbage isn't a chunk at all
+        let mut garbage = chunks.clone();
+        garbage[0] = bs58::encode(b"garbage").into_string();
+        assert!(decode_chunks(&garbage).is_err());
+
+        // A missing chunk is reported
+        assert!(decode_chunks(&chunks[..2]).is_err());
+
+        // Chunks of another payload can't be mixed in
+        let other = encode_chunks(&payload[1..], 1000).unwrap();
+        let mixed = vec![chunks[0].clone(), other[1].clone(), chunks[2].clone()];
+        assert!(decode_chunks(&mixed).is_err());
+    }
+
+    #[test]
+    fn mnemonic_round_trip() {
+        let mnemonic = generate_mnemonic().unwrap();
+        assert_eq!(mnemonic.word_count(), 24);
+
+        let parsed = parse_mnemonic(&format!("  {}\n", mnemonic)).unwrap();
+        assert_eq!(parsed.to_string(), mnemonic.to_string());
+        assert_eq!(derive_secret(&parsed, 0), derive_secret(&mnemonic, 0));
+
+        assert!(parse_mnemonic("not a mnemonic").is_err());
+        // Valid words, but a wrong checksum
+        assert!(parse_mnemonic(&PHRASE.replace(" art", " abandon")).is_err());
+    }
+
+    #[test]
+    fn mnemonic_key_derivation() {
+        let mnemonic = parse_mnemonic(PHRASE).unwrap();
+
+        // Derivation is deterministic, and separate for each index
+        assert_eq!(derive_secret(&mnemonic, 0), derive_secret(&mnemonic, 0));
+        assert_ne!(derive_secret(&mnemonic, 0), derive_secret(&mnemonic, 1));
+
+        // Different mnemonics give different keys
+        let other = generate_mnemonic().unwrap();
+        assert_ne!(derive_secret(&mnemonic, 0), derive_secret(&other, 0));
+    }
+}
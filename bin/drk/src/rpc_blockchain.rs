@@ -29,7 +29,7 @@ use darkfi::{
     wallet::walletdb::QueryType,
 };
 use darkfi_money_contract::client::{MONEY_INFO_COL_LAST_SCANNED_SLOT, MONEY_INFO_TABLE};
-use darkfi_sdk::crypto::ContractId;
+use darkfi_sdk::crypto::{ContractId, MerkleNode};
 use darkfi_serial::{deserialize, serialize};
 use serde_json::json;
 use signal_hook::consts::{SIGINT, SIGQUIT, SIGTERM};
@@ -128,12 +128,27 @@ impl Drk {
     /// `scan_block_money` will go over transactions in a block and fetch the ones dealing
     /// with the money contract. Then over all of them, try to see if any are related
     /// to us. If any are found, the metadata is extracted and placed into the wallet
-    /// for future use.
+    /// for future use. Related transactions missing from our transactions history,
+    /// e.g. when recovering a wallet, are added to it.
     async fn scan_block_money(&self, block: &BlockInfo) -> Result<()> {
         eprintln!("[Money] Iterating over {} transactions", block.txs.len());
 
+        let mut history: Option<Vec<String>> = None;
         for tx in block.txs.iter() {
-            self.apply_tx_money_data(tx, true).await?;
+            if !self.apply_tx_money_data(tx, true).await? {
+                continue
+            }
+
+            if history.is_none() {
+                let records = self.get_txs_history().await?;
+                history = Some(records.into_iter().map(|(hash, _)| hash).collect());
+            }
+
+            let tx_hash = tx.hash().to_string();
+            if !history.as_ref().unwrap().contains(&tx_hash) {
+                self.insert_tx_history_record(tx).await?;
+                history.as_mut().unwrap().push(tx_hash);
+            }
         }
 
         // Write this slot into `last_scanned_slot`
@@ -159,11 +174,27 @@ impl Drk {
         Ok(ret)
     }
 
+    /// Queries darkfid for the latest root of the Money contract's Merkle tree.
+    pub async fn latest_coin_root(&self) -> Result<MerkleNode> {
+        let req = JsonRequest::new("blockchain.latest_coin_root", json!([]));
+        let rep = self.rpc_client.request(req).await?;
+
+        let Some(root) = rep.as_str() else {
+            return Err(anyhow!("[latest_coin_root] Unexpected response from darkfid: {}", rep))
+        };
+
+        Ok(deserialize(&bs58::decode(root).into_vec()?)?)
+    }
+
     /// Broadcast a given transaction to darkfid and forward onto the network.
     /// Returns the transaction ID upon success
     pub async fn broadcast_tx(&self, tx: &Transaction) -> Result<String> {
         eprintln!("Broadcasting transaction...");
 
+        // Journal the broadcast before the transaction leaves the wallet,
+        // so its coins get reconciled if it never makes it into the chain.
+        self.mark_spend_intents_broadcast(&tx.hash().to_string()).await?;
+
         let params = json!([bs58::encode(&serialize(tx)).into_string()]);
         let req = JsonRequest::new("tx.broadcast", params);
         let rep = self.rpc_client.request(req).await?;
@@ -176,6 +207,15 @@ impl Drk {
         Ok(txid)
     }
 
+    /// Queries darkfid for the hashes of the transactions in its mempool
+    pub async fn get_pending_txs(&self) -> Result<Vec<String>> {
+        let req = JsonRequest::new("tx.pending", json!([]));
+        let rep = self.rpc_client.request(req).await?;
+
+        let pending = serde_json::from_value(rep)?;
+        Ok(pending)
+    }
+
     /// Simulate the transaction with the state machine
     pub async fn simulate_tx(&self, tx: &Transaction) -> Result<bool> {
         let params = json!([bs58::encode(&serialize(tx)).into_string()]);
@@ -186,6 +226,21 @@ impl Drk {
         Ok(is_valid)
     }
 
+    /// Simulate the transaction with the state machine in estimate mode,
+    /// returning the fee it has to pay. The transaction doesn't have to
+    /// pay any fee yet, so this can be used to find the value of its fee
+    /// call before building it.
+    pub async fn estimate_tx_fee(&self, tx: &Transaction) -> Result<u64> {
+        let params = json!([bs58::encode(&serialize(tx)).into_string(), true]);
+        let req = JsonRequest::new("tx.simulate", params);
+        let rep = self.rpc_client.request(req).await?;
+
+        let Some(fee) = rep["fee"].as_u64() else {
+            return Err(anyhow!("Unexpected tx.simulate estimate reply: {}", rep))
+        };
+        Ok(fee)
+    }
+
     /// Queries darkfid for a block with given slot
     async fn get_block_by_slot(&self, slot: u64) -> Result<Option<BlockInfo>> {
         let req = JsonRequest::new("blockchain.get_slot", json!([slot]));
@@ -272,7 +327,7 @@ impl Drk {
                 break
             }
 
-            eprint!("Requesting slot {}... ", sl);
+            eprint!("Requesting slot {}/{}... ", sl, last);
             if let Some(block) = self.get_block_by_slot(sl).await? {
                 eprintln!("Found");
                 self.scan_block_money(&block).await?;
@@ -336,6 +391,7 @@ impl Drk {
                     eprintln!("Erroneous transaction: {}", tx_hash);
                     eprintln!("===================================");
                     self.update_tx_history_record_status(&tx_hash, "Rejected").await?;
+                    self.release_spend_intents(&tx_hash).await?;
                 }
 
                 JsonResult::Error(e) => {
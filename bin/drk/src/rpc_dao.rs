@@ -25,7 +25,7 @@ use darkfi::{
 use darkfi_dao_contract::{
     client as dao_client,
     client::{DaoInfo, DaoProposalInfo, DaoVoteCall, DaoVoteInput},
-    model::DaoBlindAggregateVote,
+    model::{DaoBlindAggregateVote, DaoProposalAction},
     DaoFunction, DAO_CONTRACT_ZKAS_DAO_EXEC_NS, DAO_CONTRACT_ZKAS_DAO_MINT_NS,
     DAO_CONTRACT_ZKAS_DAO_PROPOSE_BURN_NS, DAO_CONTRACT_ZKAS_DAO_PROPOSE_MAIN_NS,
     DAO_CONTRACT_ZKAS_DAO_VOTE_BURN_NS, DAO_CONTRACT_ZKAS_DAO_VOTE_MAIN_NS,
@@ -85,7 +85,7 @@ impl Drk {
         params.encode(&mut data)?;
         let calls = vec![ContractCall { contract_id: *DAO_CONTRACT_ID, data }];
         let proofs = vec![proofs];
-        let mut tx = Transaction { calls, proofs, signatures: vec![] };
+        let mut tx = Transaction { calls, proofs, signatures: vec![], access_list: None };
         let sigs = tx.create_sigs(&mut OsRng, &[dao.secret_key])?;
         tx.signatures = vec![sigs];
 
@@ -100,6 +100,8 @@ impl Drk {
         amount: u64,
         token_id: TokenId,
     ) -> Result<Transaction> {
+        self.ensure_spendable().await?;
+
         let Ok(dao) = self.get_dao_by_id(dao_id).await else {
             return Err(anyhow!("DAO not found in wallet"))
         };
@@ -175,9 +177,15 @@ impl Drk {
         // Now create the parameters for the proposal tx
         let signature_secret = SecretKey::random(&mut OsRng);
 
-        // Get the Merkle path for the gov coin in the money tree
-        let money_merkle_tree = self.get_money_tree().await?;
-        let gov_coin_merkle_path = money_merkle_tree.witness(gov_coin.leaf_position, 0).unwrap();
+        // Get the Merkle path for the gov coin in the money tree. Coins found
+        // before witnesses were kept in the wallet need the full tree.
+        let gov_coin_merkle_path = match self.get_merkle_path(&gov_coin.coin).await? {
+            Some(witness) => witness.path,
+            None => {
+                let money_merkle_tree = self.get_money_tree().await?;
+                money_merkle_tree.witness(gov_coin.leaf_position, 0).unwrap()
+            }
+        };
 
         // Fetch the daos Merkle tree
         let (daos_tree, _) = self.get_dao_trees().await?;
@@ -202,6 +210,7 @@ impl Drk {
             dest: recipient,
             amount,
             token_id,
+            action: DaoProposalAction::TreasuryTransfer,
             blind: proposal_blind,
         };
 
@@ -236,7 +245,7 @@ impl Drk {
         params.encode(&mut data)?;
         let calls = vec![ContractCall { contract_id: *DAO_CONTRACT_ID, data }];
         let proofs = vec![proofs];
-        let mut tx = Transaction { calls, proofs, signatures: vec![] };
+        let mut tx = Transaction { calls, proofs, signatures: vec![], access_list: None };
         let sigs = tx.create_sigs(&mut OsRng, &[signature_secret])?;
         tx.signatures = vec![sigs];
 
@@ -251,6 +260,8 @@ impl Drk {
         vote_option: bool,
         weight: u64,
     ) -> Result<Transaction> {
+        self.ensure_spendable().await?;
+
         let dao = self.get_dao_by_id(dao_id).await?;
         let proposals = self.get_dao_proposals(dao_id).await?;
         let Some(proposal) = proposals.iter().find(|x| x.id == proposal_id) else {
@@ -308,6 +319,7 @@ impl Drk {
             dest: proposal.recipient,
             amount: proposal.amount,
             token_id: proposal.token_id,
+            action: DaoProposalAction::TreasuryTransfer,
             blind: proposal.bulla_blind,
         };
 
@@ -367,7 +379,7 @@ impl Drk {
         params.encode(&mut data)?;
         let calls = vec![ContractCall { contract_id: *DAO_CONTRACT_ID, data }];
         let proofs = vec![proofs];
-        let mut tx = Transaction { calls, proofs, signatures: vec![] };
+        let mut tx = Transaction { calls, proofs, signatures: vec![], access_list: None };
         let sigs = tx.create_sigs(&mut OsRng, &input_secrets)?;
         tx.signatures = vec![sigs];
 
@@ -429,9 +441,11 @@ impl Drk {
             rcpt_spend_hook,
             rcpt_user_data,
             rcpt_user_data_blind,
+            rcpt_view_public: None,
             change_spend_hook,
             change_user_data,
             change_user_data_blind,
+            change_view_public: None,
             coins,
             tree: money_merkle_tree,
             mint_zkbin: mint_zkbin.clone(),
@@ -486,6 +500,7 @@ impl Drk {
             dest: proposal.recipient,
             amount: proposal.amount,
             token_id: proposal.token_id,
+            action: DaoProposalAction::TreasuryTransfer,
             blind: proposal.bulla_blind, // <-- FIXME: wtf
         };
 
@@ -542,6 +557,7 @@ impl Drk {
             calls: vec![xfer_call, exec_call],
             proofs: vec![xfer_debris.proofs, exec_proofs],
             signatures: vec![],
+            access_list: None,
         };
 
         let xfer_sigs = tx.create_sigs(&mut OsRng, &xfer_debris.signature_secrets)?;
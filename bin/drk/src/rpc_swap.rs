@@ -20,12 +20,15 @@ use std::fmt;
 use anyhow::{anyhow, Result};
 use darkfi::{
     tx::Transaction,
-    util::parse::encode_base10,
     zk::{halo2::Field, proof::ProvingKey, vm::ZkCircuit, vm_heap::empty_witnesses, Proof},
     zkas::ZkBinary,
 };
 use darkfi_money_contract::{
-    client::{swap_v1::SwapCallBuilder, MoneyNote},
+    client::{
+        amount::{format_amount, DEFAULT_DECIMALS},
+        swap_v1::SwapCallBuilder,
+        MoneyNote,
+    },
     model::{Coin, MoneyTransferParamsV1},
     MoneyFunction, MONEY_CONTRACT_ZKAS_BURN_NS_V1, MONEY_CONTRACT_ZKAS_MINT_NS_V1,
 };
@@ -76,8 +79,10 @@ impl Drk {
         value_recv: u64,
         token_recv: TokenId,
     ) -> Result<PartialSwapData> {
+        self.ensure_spendable().await?;
+
         // First we'll fetch all of our unspent coins from the wallet.
-        let mut owncoins = self.get_coins(false).await?;
+        let mut owncoins = self.get_spendable_coins().await?;
         // Then we see if we have one that we can send.
         owncoins.retain(|x| {
             x.0.note.value == value_send &&
@@ -171,9 +176,11 @@ impl Drk {
     /// Create a full transaction by inspecting and verifying given partial swap data,
     /// making the other half, and joining all this into a `Transaction` object.
     pub async fn join_swap(&self, partial: PartialSwapData) -> Result<Transaction> {
+        self.ensure_spendable().await?;
+
         // Our side of the tx in the pairs is the second half, so we try to find
         // an unspent coin like that in our wallet.
-        let mut owncoins = self.get_coins(false).await?;
+        let mut owncoins = self.get_spendable_coins().await?;
         owncoins.retain(|x| {
             x.0.note.value == partial.value_pair.1 && x.0.note.token_id == partial.token_pair.1
         });
@@ -265,6 +272,7 @@ impl Drk {
             calls: vec![ContractCall { contract_id, data }],
             proofs: vec![full_proofs],
             signatures: vec![],
+            access_list: None,
         };
         eprintln!("Signing swap transaction");
         let sigs = tx.create_sigs(&mut OsRng, &[debris.signature_secret])?;
@@ -349,7 +357,7 @@ impl Drk {
                 "Output[{}] value: {} ({})",
                 output_idx,
                 note.value,
-                encode_base10(note.value, 8)
+                format_amount(note.value, DEFAULT_DECIMALS)
             );
             eprintln!("Output[{}] token ID: {}", output_idx, note.token_id);
 
@@ -429,6 +437,8 @@ impl Drk {
     /// Sign a given transaction by retrieving the secret key from the encrypted
     /// note and prepending it to the transaction's signatures.
     pub async fn sign_swap(&self, tx: &mut Transaction) -> Result<()> {
+        self.ensure_spendable().await?;
+
         // We need our secret keys to try and decrypt the note
         let secret_keys = self.get_money_secrets().await?;
         let params: MoneyTransferParamsV1 = deserialize(&tx.calls[0].data[1..])?;
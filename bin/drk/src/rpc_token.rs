@@ -19,12 +19,15 @@
 use anyhow::{anyhow, Result};
 use darkfi::{
     tx::Transaction,
-    util::parse::decode_base10,
     zk::{proof::ProvingKey, vm::ZkCircuit, vm_heap::empty_witnesses},
     zkas::ZkBinary,
 };
 use darkfi_money_contract::{
-    client::{token_freeze_v1::TokenFreezeCallBuilder, token_mint_v1::TokenMintCallBuilder},
+    client::{
+        amount::{parse_amount, DEFAULT_DECIMALS},
+        token_freeze_v1::TokenFreezeCallBuilder,
+        token_mint_v1::TokenMintCallBuilder,
+    },
     MoneyFunction, MONEY_CONTRACT_ZKAS_TOKEN_FRZ_NS_V1, MONEY_CONTRACT_ZKAS_TOKEN_MINT_NS_V1,
 };
 use darkfi_sdk::{
@@ -44,12 +47,13 @@ impl Drk {
         amount: &str,
         recipient: PublicKey,
         token_id: TokenId,
+        max_supply: Option<u64>,
     ) -> Result<Transaction> {
         // TODO: Mint directly into DAO treasury
         let spend_hook = pallas::Base::zero();
         let user_data = pallas::Base::zero();
 
-        let amount = decode_base10(amount, 8, false)?;
+        let amount = parse_amount(amount, DEFAULT_DECIMALS)?;
 
         let mut tokens = self.list_tokens().await?;
         tokens.retain(|x| x.0 == token_id);
@@ -84,6 +88,7 @@ impl Drk {
             mint_authority,
             recipient,
             amount,
+            max_supply,
             spend_hook,
             user_data,
             token_mint_zkbin,
@@ -98,7 +103,7 @@ impl Drk {
         debris.params.encode(&mut data)?;
         let calls = vec![ContractCall { contract_id: *MONEY_CONTRACT_ID, data }];
         let proofs = vec![debris.proofs];
-        let mut tx = Transaction { calls, proofs, signatures: vec![] };
+        let mut tx = Transaction { calls, proofs, signatures: vec![], access_list: None };
         let sigs = tx.create_sigs(&mut OsRng, &[mint_authority.secret])?;
         tx.signatures = vec![sigs];
 
@@ -144,7 +149,7 @@ impl Drk {
         debris.params.encode(&mut data)?;
         let calls = vec![ContractCall { contract_id: *MONEY_CONTRACT_ID, data }];
         let proofs = vec![debris.proofs];
-        let mut tx = Transaction { calls, proofs, signatures: vec![] };
+        let mut tx = Transaction { calls, proofs, signatures: vec![], access_list: None };
         let sigs = tx.create_sigs(&mut OsRng, &[mint_authority.secret])?;
         tx.signatures = vec![sigs];
 
@@ -21,38 +21,262 @@ use std::str::FromStr;
 use anyhow::{anyhow, Result};
 use darkfi::{
     tx::Transaction,
-    util::parse::{decode_base10, encode_base10},
     zk::{halo2::Field, proof::ProvingKey, vm::ZkCircuit, vm_heap::empty_witnesses},
     zkas::ZkBinary,
+    ClientFailed,
 };
 use darkfi_dao_contract::model::DaoBulla;
 use darkfi_money_contract::{
-    client::{transfer_v1::TransferCallBuilder, OwnCoin},
+    client::{
+        amount::{checked_sum, format_amount, parse_amount, DEFAULT_DECIMALS},
+        transfer_v1::{is_root_fresh, TransferCallBuilder, MAX_ROOT_REBUILDS},
+        view_key::ViewKey,
+        MoneyNote, OwnCoin,
+    },
+    model::Coin,
     MoneyFunction, MONEY_CONTRACT_ZKAS_BURN_NS_V1, MONEY_CONTRACT_ZKAS_MINT_NS_V1,
 };
 use darkfi_sdk::{
+    bridgetree,
     crypto::{
         contract_id::{DAO_CONTRACT_ID, MONEY_CONTRACT_ID},
-        Keypair, PublicKey, TokenId,
+        poseidon_hash, Keypair, MerkleTree, Nullifier, PublicKey, SecretKey, TokenId,
     },
     pasta::pallas,
     tx::ContractCall,
 };
-use darkfi_serial::Encodable;
+use darkfi_serial::{Encodable, SerialDecodable, SerialEncodable};
 use rand::rngs::OsRng;
 
-use super::Drk;
+use super::{CoinSelection, Drk};
+
+/// A coin funding an [`UnsignedTransfer`]. It carries everything needed
+/// to spend the coin except its secret key, so exporting it grants no
+/// spend authority. The signer re-derives the secret and the nullifier.
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct UnsignedCoin {
+    /// The coin hash
+    pub coin: Coin,
+    /// The attached `MoneyNote`
+    pub note: MoneyNote,
+    /// Coin's leaf position in the Merkle tree of coins
+    pub leaf_position: bridgetree::Position,
+}
+
+impl UnsignedCoin {
+    /// Rebuild the `OwnCoin` with whichever of the given secret keys the
+    /// coin was sent to. Fails if none of them owns the coin.
+    pub fn to_owncoin(&self, secrets: &[SecretKey]) -> Result<OwnCoin> {
+        for secret in secrets {
+            let (pub_x, pub_y) = PublicKey::from_secret(*secret).xy();
+            let coin = Coin::from(poseidon_hash([
+                pub_x,
+                pub_y,
+                pallas::Base::from(self.note.value),
+                self.note.token_id.inner(),
+                self.note.serial,
+                self.note.spend_hook,
+                self.note.user_data,
+            ]));
+
+            if coin != self.coin {
+                continue
+            }
+
+            return Ok(OwnCoin {
+                coin: self.coin,
+                note: self.note.clone(),
+                secret: *secret,
+                nullifier: Nullifier::from(poseidon_hash([secret.inner(), self.note.serial])),
+                leaf_position: self.leaf_position,
+            })
+        }
+
+        Err(anyhow!("None of the keys own coin {:?}", self.coin))
+    }
+}
+
+impl From<&OwnCoin> for UnsignedCoin {
+    fn from(owncoin: &OwnCoin) -> Self {
+        Self {
+            coin: owncoin.coin,
+            note: owncoin.note.clone(),
+            leaf_position: owncoin.leaf_position,
+        }
+    }
+}
+
+/// A payment that has been prepared by a wallet, but not yet proven or
+/// signed. This is what a watch-only wallet exports, so the transaction
+/// can be finalized by whoever holds the spend key.
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct UnsignedTransfer {
+    /// Payment recipient
+    pub recipient: PublicKey,
+    /// Value to send
+    pub value: u64,
+    /// Token ID to send
+    pub token_id: TokenId,
+    /// Spend hook for the recipient's coin
+    pub rcpt_spend_hook: pallas::Base,
+    /// User data for the recipient's coin
+    pub rcpt_user_data: pallas::Base,
+    /// User data blind for the recipient's coin
+    pub rcpt_user_data_blind: pallas::Base,
+    /// Recipient's view address, if their note should be readable by their view key
+    pub rcpt_view_public: Option<PublicKey>,
+    /// Coins selected to fund the payment
+    pub coins: Vec<UnsignedCoin>,
+    /// Merkle tree of coins used to create inclusion proofs
+    pub tree: MerkleTree,
+    /// Compiled `Money::Mint` zkas circuit
+    pub mint_zkbin: Vec<u8>,
+    /// Compiled `Money::Burn` zkas circuit
+    pub burn_zkbin: Vec<u8>,
+}
+
+impl UnsignedTransfer {
+    /// Create the proofs and signatures for this payment, with the given
+    /// secrets owning the coins and the keypair receiving the change.
+    /// Returns the transaction along with the coins it spends. No wallet
+    /// or network access is needed, so this can be done offline.
+    pub fn sign(
+        self,
+        keypair: Keypair,
+        secrets: &[SecretKey],
+    ) -> Result<(Transaction, Vec<OwnCoin>)> {
+        let contract_id = *MONEY_CONTRACT_ID;
+
+        // The coins' secrets and nullifiers are re-derived from our keys
+        let coins =
+            self.coins.iter().map(|coin| coin.to_owncoin(secrets)).collect::<Result<Vec<_>>>()?;
+
+        let mint_zkbin = ZkBinary::decode(&self.mint_zkbin)?;
+        let burn_zkbin = ZkBinary::decode(&self.burn_zkbin)?;
+
+        let mint_circuit = ZkCircuit::new(empty_witnesses(&mint_zkbin)?, &mint_zkbin);
+        let burn_circuit = ZkCircuit::new(empty_witnesses(&burn_zkbin)?, &burn_zkbin);
+
+        eprintln!("Creating Mint and Burn circuit proving keys");
+        let mint_pk = ProvingKey::build(mint_zkbin.k, &mint_circuit);
+        let burn_pk = ProvingKey::build(burn_zkbin.k, &burn_circuit);
+
+        let change_view = ViewKey::derive(&keypair.secret);
+        let transfer_builder = TransferCallBuilder {
+            keypair,
+            recipient: self.recipient,
+            value: self.value,
+            token_id: self.token_id,
+            rcpt_spend_hook: self.rcpt_spend_hook,
+            rcpt_user_data: self.rcpt_user_data,
+            rcpt_user_data_blind: self.rcpt_user_data_blind,
+            rcpt_view_public: self.rcpt_view_public,
+            change_spend_hook: pallas::Base::zero(),
+            change_user_data: pallas::Base::zero(),
+            // FIXME: I'm reusing this blind but dunno why
+            change_user_data_blind: self.rcpt_user_data_blind,
+            // Keep our change visible to our own view key
+            change_view_public: Some(change_view.public()),
+            coins,
+            tree: self.tree,
+            mint_zkbin,
+            mint_pk,
+            burn_zkbin,
+            burn_pk,
+            clear_input: false,
+        };
+
+        eprintln!("Building transaction parameters");
+        let debris = transfer_builder.build()?;
+
+        // Encode and sign the transaction
+        let mut data = vec![MoneyFunction::TransferV1 as u8];
+        debris.params.encode(&mut data)?;
+        let calls = vec![ContractCall { contract_id, data }];
+        let proofs = vec![debris.proofs];
+        let mut tx = Transaction { calls, proofs, signatures: vec![], access_list: None };
+        let sigs = tx.create_sigs(&mut OsRng, &debris.signature_secrets)?;
+        tx.signatures = vec![sigs];
+
+        Ok((tx, debris.spent_coins))
+    }
+}
 
 impl Drk {
     /// Create a payment transaction. Returns the transaction object on success.
+    #[allow(clippy::too_many_arguments)]
     pub async fn transfer(
         &self,
         amount: &str,
         token_id: TokenId,
         recipient: PublicKey,
+        rcpt_view: Option<PublicKey>,
         dao: bool,
         dao_bulla: Option<String>,
+        selection: CoinSelection,
     ) -> Result<Transaction> {
+        self.ensure_spendable().await?;
+        self.reconcile_spend_intents().await?;
+
+        // TODO: Which keypair to actually use?
+        let secrets = self.get_money_secrets().await?;
+        let keypair = Keypair::new(secrets[0]);
+
+        // The proofs reference our Merkle tree's root. If the chain moved
+        // past it, we rescan to re-derive the paths and build again.
+        for rebuild in 0..=MAX_ROOT_REBUILDS {
+            if rebuild > 0 {
+                eprintln!("Merkle root went stale, rebuilding ({}/{})", rebuild, MAX_ROOT_REBUILDS);
+                self.scan_blocks(false).await?;
+            }
+
+            let unsigned = self
+                .transfer_unsigned(
+                    amount,
+                    token_id,
+                    recipient,
+                    rcpt_view,
+                    dao,
+                    dao_bulla.clone(),
+                    selection,
+                )
+                .await?;
+
+            if !is_root_fresh(&unsigned.tree, &self.latest_coin_root().await?) {
+                continue
+            }
+
+            let tree = unsigned.tree.clone();
+            let (tx, spent_coins) = unsigned.sign(keypair, &secrets)?;
+
+            // The chain might have moved on while we were proving
+            if !is_root_fresh(&tree, &self.latest_coin_root().await?) {
+                continue
+            }
+
+            // We need to reserve the coins we've spent in our wallet
+            self.reserve_coins(&spent_coins, &tx.hash()).await?;
+
+            return Ok(tx)
+        }
+
+        Err(ClientFailed::StaleMerkleRoot(MAX_ROOT_REBUILDS).into())
+    }
+
+    /// Prepare a payment without proving or signing it. This works with
+    /// watch-only wallets, and the result can be finalized elsewhere
+    /// using [`UnsignedTransfer::sign`].
+    #[allow(clippy::too_many_arguments)]
+    pub async fn transfer_unsigned(
+        &self,
+        amount: &str,
+        token_id: TokenId,
+        recipient: PublicKey,
+        rcpt_view: Option<PublicKey>,
+        dao: bool,
+        dao_bulla: Option<String>,
+        selection: CoinSelection,
+    ) -> Result<UnsignedTransfer> {
         let dao_bulla: Option<DaoBulla> = if dao {
             let Some(dao_bulla) = dao_bulla else {
                 return Err(anyhow!("Missing DAO bulla in parameters"))
@@ -71,7 +295,7 @@ impl Drk {
 
         // First get all unspent OwnCoins to see what our balance is.
         eprintln!("Fetching OwnCoins");
-        let owncoins = self.get_coins(false).await?;
+        let owncoins = self.get_spendable_coins().await?;
         let mut owncoins: Vec<OwnCoin> = owncoins.iter().map(|x| x.0.clone()).collect();
         // We're only interested in the ones for the token_id we're sending
         // And the ones not owned by some protocol (meaning spend-hook should be 0)
@@ -82,33 +306,26 @@ impl Drk {
         }
 
         // FIXME: Do not hardcode 8 decimals
-        let amount = decode_base10(amount, 8, false)?;
-        let mut balance = 0;
-        for coin in owncoins.iter() {
-            balance += coin.note.value;
-        }
+        let amount = parse_amount(amount, DEFAULT_DECIMALS)?;
+        let balance = checked_sum(owncoins.iter().map(|coin| coin.note.value))?;
 
         if balance < amount {
             return Err(anyhow!(
                 "Not enough balance for token ID: {}, found: {}",
                 token_id,
-                encode_base10(balance, 8)
+                format_amount(balance, DEFAULT_DECIMALS)
             ))
         }
 
+        // Pick the coins funding the payment
+        let owncoins = self.select_coins(owncoins, amount, selection).await?;
+
         // We'll also need our Merkle tree
         let tree = self.get_money_tree().await?;
 
-        // TODO: Which keypair to actually use?
-        let secrets = self.get_money_secrets().await?;
-        let keypair = Keypair::new(secrets[0]);
-
-        let contract_id = *MONEY_CONTRACT_ID;
-
-        // Now we need to do a lookup for the zkas proof bincodes, and create
-        // the circuit objects and proving keys so we can build the transaction.
-        // We also do this through the RPC.
-        let zkas_bins = self.lookup_zkas(&contract_id).await?;
+        // Now we need to do a lookup for the zkas proof bincodes, so they
+        // can be shipped along and the transaction built without the RPC.
+        let zkas_bins = self.lookup_zkas(&MONEY_CONTRACT_ID).await?;
 
         let Some(mint_zkbin) = zkas_bins.iter().find(|x| x.0 == MONEY_CONTRACT_ZKAS_MINT_NS_V1)
         else {
@@ -120,52 +337,65 @@ impl Drk {
             return Err(anyhow!("Burn circuit not found"))
         };
 
-        let mint_zkbin = ZkBinary::decode(&mint_zkbin.1)?;
-        let burn_zkbin = ZkBinary::decode(&burn_zkbin.1)?;
-
-        let mint_circuit = ZkCircuit::new(empty_witnesses(&mint_zkbin)?, &mint_zkbin);
-        let burn_circuit = ZkCircuit::new(empty_witnesses(&burn_zkbin)?, &burn_zkbin);
-
-        eprintln!("Creating Mint and Burn circuit proving keys");
-        let mint_pk = ProvingKey::build(mint_zkbin.k, &mint_circuit);
-        let burn_pk = ProvingKey::build(burn_zkbin.k, &burn_circuit);
-        let transfer_builder = TransferCallBuilder {
-            keypair,
+        Ok(UnsignedTransfer {
             recipient,
             value: amount,
             token_id,
             rcpt_spend_hook: spend_hook,
             rcpt_user_data: user_data,
             rcpt_user_data_blind: user_data_blind,
-            change_spend_hook: pallas::Base::zero(),
-            change_user_data: pallas::Base::zero(),
-            change_user_data_blind: user_data_blind, // FIXME: I'm reusing this blind but dunno why
-            coins: owncoins,
+            rcpt_view_public: rcpt_view,
+            coins: owncoins.iter().map(UnsignedCoin::from).collect(),
             tree,
-            mint_zkbin,
-            mint_pk,
-            burn_zkbin,
-            burn_pk,
-            clear_input: false,
-        };
+            mint_zkbin: mint_zkbin.1.clone(),
+            burn_zkbin: burn_zkbin.1.clone(),
+        })
+    }
+}
 
-        eprintln!("Building transaction parameters");
-        let debris = transfer_builder.build()?;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        // Encode and sign the transaction
-        let mut data = vec![MoneyFunction::TransferV1 as u8];
-        debris.params.encode(&mut data)?;
-        let calls = vec![ContractCall { contract_id, data }];
-        let proofs = vec![debris.proofs];
-        let mut tx = Transaction { calls, proofs, signatures: vec![] };
-        let sigs = tx.create_sigs(&mut OsRng, &debris.signature_secrets)?;
-        tx.signatures = vec![sigs];
+    #[test]
+    fn unsigned_coin_rederives_secret() {
+        let secret = SecretKey::random(&mut OsRng);
+        let note = MoneyNote {
+            serial: pallas::Base::random(&mut OsRng),
+            value: 42,
+            token_id: TokenId::from(pallas::Base::random(&mut OsRng)),
+            spend_hook: pallas::Base::zero(),
+            user_data: pallas::Base::zero(),
+            value_blind: pallas::Scalar::random(&mut OsRng),
+            token_blind: pallas::Base::random(&mut OsRng),
+            memo: vec![],
+        };
+        let (pub_x, pub_y) = PublicKey::from_secret(secret).xy();
+        let coin = Coin::from(poseidon_hash([
+            pub_x,
+            pub_y,
+            pallas::Base::from(note.value),
+            note.token_id.inner(),
+            note.serial,
+            note.spend_hook,
+            note.user_data,
+        ]));
+        let unsigned = UnsignedCoin {
+            coin,
+            note: note.clone(),
+            leaf_position: bridgetree::Position::from(7u64),
+        };
 
-        // We need to mark the coins we've spent in our wallet
-        for spent_coin in debris.spent_coins {
-            self.mark_spent_coin(&spent_coin.coin).await?;
-        }
+        // Only the owner's key rebuilds the coin
+        let other = SecretKey::random(&mut OsRng);
+        assert!(unsigned.to_owncoin(&[other]).is_err());
 
-        Ok(tx)
+        let owncoin = unsigned.to_owncoin(&[other, secret]).unwrap();
+        assert_eq!(owncoin.secret, secret);
+        assert_eq!(
+            owncoin.nullifier,
+            Nullifier::from(poseidon_hash([secret.inner(), note.serial]))
+        );
+        assert_eq!(UnsignedCoin::from(&owncoin).coin, coin);
     }
 }
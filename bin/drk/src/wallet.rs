@@ -16,11 +16,13 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use bip39::Mnemonic;
 use darkfi::rpc::jsonrpc::JsonRequest;
+use darkfi_sdk::crypto::SecretKey;
 use serde_json::json;
 
-use super::Drk;
+use super::{paper_key::derive_secret, Drk};
 
 impl Drk {
     /// Initialize wallet with tables for drk
@@ -40,4 +42,39 @@ impl Drk {
 
         Ok(())
     }
+
+    /// Recover a wallet out of a mnemonic alone. The wallet is wiped, the
+    /// first `keys` keys derived from the mnemonic are imported, and coins,
+    /// transactions history and Merkle witnesses are rebuilt by scanning the
+    /// entire blockchain. With `resume`, an interrupted recovery continues
+    /// scanning from the last scanned slot instead.
+    pub async fn recover_wallet(&self, mnemonic: &Mnemonic, keys: u32, resume: bool) -> Result<()> {
+        let secrets: Vec<SecretKey> = (0..keys).map(|i| derive_secret(mnemonic, i)).collect();
+
+        if resume {
+            let known = self.get_money_secrets().await?;
+            if !secrets.iter().all(|s| known.contains(s)) {
+                return Err(anyhow!("Wallet does not hold a recovery in progress for this mnemonic"))
+            }
+
+            eprintln!("Resuming wallet recovery from slot {}", self.last_scanned_slot().await?);
+        } else {
+            let req = JsonRequest::new("wallet.recover", json!([]));
+            let rep = self.rpc_client.request(req).await?;
+
+            if rep != true {
+                return Err(anyhow!("[recover_wallet] Got unexpected reply from darkfid: {}", rep))
+            }
+
+            eprintln!("Wallet wiped, initializing a fresh one");
+            self.initialize_wallet().await?;
+            self.initialize_money().await?;
+            self.initialize_dao().await?;
+
+            let pubkeys = self.import_money_secrets(secrets).await?;
+            eprintln!("Imported {} keys derived from the mnemonic", pubkeys.len());
+        }
+
+        self.scan_blocks(false).await
+    }
 }
@@ -0,0 +1,211 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use anyhow::{anyhow, Result};
+use darkfi::{rpc::jsonrpc::JsonRequest, wallet::walletdb::QueryType};
+use darkfi_money_contract::{
+    client::{
+        OwnCoin, MONEY_COIN_SOURCES_COL_CLUSTER, MONEY_COIN_SOURCES_COL_COIN,
+        MONEY_COIN_SOURCES_TABLE,
+    },
+    model::Coin,
+};
+use darkfi_serial::{deserialize, serialize};
+use serde_json::json;
+
+use super::Drk;
+
+/// How coins are picked to fund a payment
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CoinSelection {
+    /// Spend coins in wallet order, regardless of where they came from
+    Any,
+    /// Spend coins received from a single source, so the payment doesn't
+    /// link coins from different counterparties. If no single source can
+    /// fund it, the payment fails unless `allow_linking` is set, in which
+    /// case as few sources as possible are combined.
+    Private { allow_linking: bool },
+}
+
+impl Drk {
+    /// Record the cluster the given coins were received in.
+    pub async fn put_coin_sources(&self, coins: &[Coin], cluster: &str) -> Result<()> {
+        let query = format!(
+            "INSERT OR REPLACE INTO {} ({}, {}) VALUES (?1, ?2);",
+            MONEY_COIN_SOURCES_TABLE, MONEY_COIN_SOURCES_COL_COIN, MONEY_COIN_SOURCES_COL_CLUSTER,
+        );
+
+        for coin in coins {
+            let params = json!([
+                query,
+                QueryType::Blob as u8,
+                serialize(coin),
+                QueryType::Text as u8,
+                cluster
+            ]);
+
+            let req = JsonRequest::new("wallet.exec_sql", params);
+            let _ = self.rpc_client.request(req).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Move all coins of the cluster `from` into the cluster `into`.
+    pub async fn merge_coin_sources(&self, from: &str, into: &str) -> Result<()> {
+        let query = format!(
+            "UPDATE {} SET {} = ?1 WHERE {} = ?2;",
+            MONEY_COIN_SOURCES_TABLE,
+            MONEY_COIN_SOURCES_COL_CLUSTER,
+            MONEY_COIN_SOURCES_COL_CLUSTER,
+        );
+
+        let params = json!([query, QueryType::Text as u8, into, QueryType::Text as u8, from]);
+
+        let req = JsonRequest::new("wallet.exec_sql", params);
+        let _ = self.rpc_client.request(req).await?;
+
+        Ok(())
+    }
+
+    /// Fetch all coins with a recorded receive source, along with their cluster.
+    pub async fn get_coin_sources(&self) -> Result<Vec<(Coin, String)>> {
+        let query = format!("SELECT * FROM {};", MONEY_COIN_SOURCES_TABLE);
+
+        let params = json!([
+            query,
+            QueryType::Blob as u8,
+            MONEY_COIN_SOURCES_COL_COIN,
+            QueryType::Text as u8,
+            MONEY_COIN_SOURCES_COL_CLUSTER,
+        ]);
+
+        let req = JsonRequest::new("wallet.query_row_multi", params);
+        let rep = self.rpc_client.request(req).await?;
+
+        let Some(rows) = rep.as_array() else {
+            return Err(anyhow!("[get_coin_sources] Unexpected response from darkfid: {}", rep))
+        };
+
+        let mut ret = Vec::with_capacity(rows.len());
+        for row in rows {
+            let coin_bytes: Vec<u8> = serde_json::from_value(row[0].clone())?;
+            let coin: Coin = deserialize(&coin_bytes)?;
+            let cluster: String = serde_json::from_value(row[1].clone())?;
+            ret.push((coin, cluster));
+        }
+
+        Ok(ret)
+    }
+
+    /// Record the receive source of the coins we found in the transaction
+    /// with the given hash. If the transaction spends any of our coins, it
+    /// links their clusters, so those get merged and the new coins join
+    /// them. Otherwise the transaction starts a cluster of its own.
+    pub async fn track_coin_sources(
+        &self,
+        tx_hash: &str,
+        spent: &[Coin],
+        received: &[Coin],
+    ) -> Result<()> {
+        let sources = self.get_coin_sources().await?;
+
+        let mut clusters: Vec<&String> = vec![];
+        for coin in spent {
+            if let Some((_, cluster)) = sources.iter().find(|(c, _)| c == coin) {
+                if !clusters.contains(&cluster) {
+                    clusters.push(cluster);
+                }
+            }
+        }
+
+        let target = clusters.first().map(|c| c.to_string()).unwrap_or_else(|| tx_hash.to_string());
+        for cluster in clusters.iter().skip(1) {
+            self.merge_coin_sources(cluster, &target).await?;
+        }
+
+        if !received.is_empty() {
+            self.put_coin_sources(received, &target).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Pick the coins to fund a payment of `amount` according to the
+    /// given selection mode. Coins without a recorded receive source,
+    /// e.g. ones scanned by an older wallet, count as their own cluster.
+    pub async fn select_coins(
+        &self,
+        coins: Vec<OwnCoin>,
+        amount: u64,
+        selection: CoinSelection,
+    ) -> Result<Vec<OwnCoin>> {
+        let CoinSelection::Private { allow_linking } = selection else { return Ok(coins) };
+
+        let sources = self.get_coin_sources().await?;
+
+        // Group the coins by cluster, along with the cluster balance
+        let mut clusters: Vec<(Option<String>, Vec<OwnCoin>, u64)> = vec![];
+        for coin in coins {
+            let cluster = sources.iter().find(|(c, _)| *c == coin.coin).map(|(_, c)| c.clone());
+            let value = coin.note.value;
+
+            match clusters.iter_mut().find(|(c, _, _)| cluster.is_some() && *c == cluster) {
+                Some((_, cluster_coins, balance)) => {
+                    cluster_coins.push(coin);
+                    *balance = balance.saturating_add(value);
+                }
+                None => clusters.push((cluster, vec![coin], value)),
+            }
+        }
+
+        // Prefer the smallest cluster able to fund the payment, keeping
+        // the larger ones intact for later payments
+        if let Some((_, cluster_coins, _)) =
+            clusters.iter().filter(|(_, _, balance)| *balance >= amount).min_by_key(|(_, _, b)| *b)
+        {
+            return Ok(cluster_coins.clone())
+        }
+
+        if !allow_linking {
+            return Err(anyhow!(
+                "No single receive source can fund this payment, it would link coins from {} sources",
+                clusters.len()
+            ))
+        }
+
+        // Combine the largest clusters first, so as few as possible get linked
+        clusters.sort_by(|a, b| b.2.cmp(&a.2));
+
+        let mut selected = vec![];
+        let mut value = 0_u64;
+        let mut linked = 0;
+        for (_, cluster_coins, balance) in clusters {
+            if value >= amount {
+                break
+            }
+
+            selected.extend(cluster_coins);
+            value = value.saturating_add(balance);
+            linked += 1;
+        }
+
+        eprintln!("Warning: This payment links coins received from {} different sources", linked);
+        Ok(selected)
+    }
+}
@@ -0,0 +1,106 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use anyhow::{anyhow, Result};
+use darkfi::{rpc::jsonrpc::JsonRequest, wallet::walletdb::QueryType};
+use darkfi_money_contract::{
+    client::{
+        witness::CoinWitness, MONEY_COIN_WITNESSES_COL_COIN, MONEY_COIN_WITNESSES_COL_WITNESS,
+        MONEY_COIN_WITNESSES_TABLE,
+    },
+    model::Coin,
+};
+use darkfi_serial::{deserialize, serialize};
+use serde_json::json;
+
+use super::Drk;
+
+impl Drk {
+    /// Replace the stored Merkle witnesses with the given ones.
+    pub async fn put_coin_witnesses(&self, witnesses: &[CoinWitness]) -> Result<()> {
+        self.reset_coin_witnesses().await?;
+
+        let query = format!(
+            "INSERT INTO {} ({}, {}) VALUES (?1, ?2);",
+            MONEY_COIN_WITNESSES_TABLE,
+            MONEY_COIN_WITNESSES_COL_COIN,
+            MONEY_COIN_WITNESSES_COL_WITNESS,
+        );
+
+        for witness in witnesses {
+            let params = json!([
+                query,
+                QueryType::Blob as u8,
+                serialize(&witness.coin),
+                QueryType::Blob as u8,
+                serialize(witness),
+            ]);
+
+            let req = JsonRequest::new("wallet.exec_sql", params);
+            let _ = self.rpc_client.request(req).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Fetch all stored Merkle witnesses.
+    pub async fn get_coin_witnesses(&self) -> Result<Vec<CoinWitness>> {
+        let query = format!("SELECT * FROM {};", MONEY_COIN_WITNESSES_TABLE);
+
+        let params = json!([
+            query,
+            QueryType::Blob as u8,
+            MONEY_COIN_WITNESSES_COL_COIN,
+            QueryType::Blob as u8,
+            MONEY_COIN_WITNESSES_COL_WITNESS,
+        ]);
+
+        let req = JsonRequest::new("wallet.query_row_multi", params);
+        let rep = self.rpc_client.request(req).await?;
+
+        let Some(rows) = rep.as_array() else {
+            return Err(anyhow!("[get_coin_witnesses] Unexpected response from darkfid: {}", rep))
+        };
+
+        let mut ret = Vec::with_capacity(rows.len());
+        for row in rows {
+            let witness_bytes: Vec<u8> = serde_json::from_value(row[1].clone())?;
+            ret.push(deserialize(&witness_bytes)?);
+        }
+
+        Ok(ret)
+    }
+
+    /// Fetch the stored Merkle authentication path of the given coin,
+    /// as of the last scanned block. Returns `None` for coins that are
+    /// spent or not ours.
+    pub async fn get_merkle_path(&self, coin: &Coin) -> Result<Option<CoinWitness>> {
+        let witnesses = self.get_coin_witnesses().await?;
+        Ok(witnesses.into_iter().find(|w| &w.coin == coin))
+    }
+
+    /// Remove all stored Merkle witnesses.
+    pub async fn reset_coin_witnesses(&self) -> Result<()> {
+        let query = format!("DELETE FROM {};", MONEY_COIN_WITNESSES_TABLE);
+        let params = json!([query]);
+        let req = JsonRequest::new("wallet.exec_sql", params);
+        let _ = self.rpc_client.request(req).await?;
+
+        Ok(())
+    }
+}
@@ -19,10 +19,7 @@
 use std::{collections::HashMap, fmt};
 
 use anyhow::{anyhow, Result};
-use darkfi::{
-    rpc::jsonrpc::JsonRequest, tx::Transaction, util::parse::encode_base10,
-    wallet::walletdb::QueryType,
-};
+use darkfi::{rpc::jsonrpc::JsonRequest, tx::Transaction, wallet::walletdb::QueryType};
 use darkfi_dao_contract::{
     client::{
         DaoProposeNote, DaoVoteNote, DAO_DAOS_COL_APPROVAL_RATIO_BASE,
@@ -40,9 +37,10 @@ use darkfi_dao_contract::{
         DAO_VOTES_COL_TX_HASH, DAO_VOTES_COL_VOTE_ID, DAO_VOTES_COL_VOTE_OPTION,
         DAO_VOTES_COL_YES_VOTE_BLIND, DAO_VOTES_TABLE,
     },
-    model::{DaoBulla, DaoMintParams, DaoProposeParams, DaoVoteParams},
+    model::{DaoBulla, DaoMintParams, DaoProposalAction, DaoProposeParams, DaoVoteParams},
     DaoFunction,
 };
+use darkfi_money_contract::client::amount::{format_amount, DEFAULT_DECIMALS};
 use darkfi_sdk::{
     bridgetree,
     crypto::{
@@ -81,10 +79,10 @@ impl fmt::Display for DaoParams {
             "DAO Parameters",
             "==============",
             "Proposer limit",
-            encode_base10(self.proposer_limit, 8),
+            format_amount(self.proposer_limit, DEFAULT_DECIMALS),
             self.proposer_limit,
             "Quorum",
-            encode_base10(self.quorum, 8),
+            format_amount(self.quorum, DEFAULT_DECIMALS),
             self.quorum,
             "Approval ratio",
             self.approval_ratio_quot as f64 / self.approval_ratio_base as f64,
@@ -163,10 +161,10 @@ impl fmt::Display for Dao {
             "Bulla",
             self.bulla(),
             "Proposer limit",
-            encode_base10(self.proposer_limit, 8),
+            format_amount(self.proposer_limit, DEFAULT_DECIMALS),
             self.proposer_limit,
             "Quorum",
-            encode_base10(self.quorum, 8),
+            format_amount(self.quorum, DEFAULT_DECIMALS),
             self.quorum,
             "Approval ratio",
             self.approval_ratio_quot as f64 / self.approval_ratio_base as f64,
@@ -226,6 +224,7 @@ impl DaoProposal {
             dest_y,
             pallas::Base::from(self.amount),
             self.token_id.inner(),
+            DaoProposalAction::TreasuryTransfer.commit(),
             self.dao_bulla.inner(),
             self.bulla_blind,
         ])
@@ -250,7 +249,7 @@ impl fmt::Display for DaoProposal {
             ),
             self.dao_bulla,
             self.recipient,
-            encode_base10(self.amount, 8),
+            format_amount(self.amount, DEFAULT_DECIMALS),
             self.amount,
             self.token_id,
             self.bulla_blind,
@@ -1095,6 +1094,11 @@ impl Drk {
                         // We also assume we don't mantain duplicate DAOs in the
                         // wallet.
                         eprintln!("Managed to decrypt DAO proposal note");
+                        // The wallet only tracks treasury transfer proposals
+                        if note.proposal.action != DaoProposalAction::TreasuryTransfer {
+                            eprintln!("Skipping non-transfer DAO proposal");
+                            break
+                        }
                         let daos_proposals = self.get_dao_proposals(dao.id).await?;
                         let our_prop = DaoProposal {
                             // This ID stuff is flaky.
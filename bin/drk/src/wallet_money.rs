@@ -22,27 +22,26 @@ use anyhow::{anyhow, Result};
 use darkfi::{rpc::jsonrpc::JsonRequest, tx::Transaction, wallet::walletdb::QueryType};
 use darkfi_money_contract::{
     client::{
-        MoneyNote, OwnCoin, MONEY_ALIASES_COL_ALIAS, MONEY_ALIASES_COL_TOKEN_ID,
-        MONEY_ALIASES_TABLE, MONEY_COINS_COL_COIN, MONEY_COINS_COL_IS_SPENT,
-        MONEY_COINS_COL_LEAF_POSITION, MONEY_COINS_COL_MEMO, MONEY_COINS_COL_NULLIFIER,
-        MONEY_COINS_COL_SECRET, MONEY_COINS_COL_SERIAL, MONEY_COINS_COL_SPEND_HOOK,
-        MONEY_COINS_COL_TOKEN_BLIND, MONEY_COINS_COL_TOKEN_ID, MONEY_COINS_COL_USER_DATA,
-        MONEY_COINS_COL_VALUE, MONEY_COINS_COL_VALUE_BLIND, MONEY_COINS_TABLE,
-        MONEY_INFO_COL_LAST_SCANNED_SLOT, MONEY_INFO_TABLE, MONEY_KEYS_COL_IS_DEFAULT,
-        MONEY_KEYS_COL_KEY_ID, MONEY_KEYS_COL_PUBLIC, MONEY_KEYS_COL_SECRET, MONEY_KEYS_TABLE,
-        MONEY_TOKENS_COL_IS_FROZEN, MONEY_TOKENS_COL_TOKEN_ID, MONEY_TOKENS_TABLE,
-        MONEY_TREE_COL_TREE, MONEY_TREE_TABLE,
+        scan::MoneyTxEffects, view_key::ViewKey, witness::CoinWitnessService, MoneyNote, OwnCoin,
+        MONEY_ALIASES_COL_ALIAS, MONEY_ALIASES_COL_TOKEN_ID, MONEY_ALIASES_TABLE,
+        MONEY_COINS_COL_COIN, MONEY_COINS_COL_IS_SPENT, MONEY_COINS_COL_LEAF_POSITION,
+        MONEY_COINS_COL_MEMO, MONEY_COINS_COL_NULLIFIER, MONEY_COINS_COL_SECRET,
+        MONEY_COINS_COL_SERIAL, MONEY_COINS_COL_SPEND_HOOK, MONEY_COINS_COL_TOKEN_BLIND,
+        MONEY_COINS_COL_TOKEN_ID, MONEY_COINS_COL_USER_DATA, MONEY_COINS_COL_VALUE,
+        MONEY_COINS_COL_VALUE_BLIND, MONEY_COINS_TABLE, MONEY_INFO_COL_LAST_SCANNED_SLOT,
+        MONEY_INFO_TABLE, MONEY_KEYS_COL_IS_DEFAULT, MONEY_KEYS_COL_KEY_ID, MONEY_KEYS_COL_PUBLIC,
+        MONEY_KEYS_COL_SECRET, MONEY_KEYS_TABLE, MONEY_TOKENS_COL_IS_FROZEN,
+        MONEY_TOKENS_COL_TOKEN_ID, MONEY_TOKENS_TABLE, MONEY_TREE_COL_TREE, MONEY_TREE_TABLE,
+        MONEY_VIEW_KEYS_COL_KEY_ID, MONEY_VIEW_KEYS_COL_PUBLIC, MONEY_VIEW_KEYS_COL_SECRET,
+        MONEY_VIEW_KEYS_TABLE,
     },
-    model::{
-        Coin, MoneyTokenFreezeParamsV1, MoneyTokenMintParamsV1, MoneyTransferParamsV1, Output,
-    },
-    MoneyFunction,
+    model::Coin,
 };
 use darkfi_sdk::{
     bridgetree,
     crypto::{
-        pasta_prelude::Field, poseidon_hash, Keypair, MerkleNode, MerkleTree, Nullifier, PublicKey,
-        SecretKey, TokenId, MONEY_CONTRACT_ID,
+        pasta_prelude::Field, Keypair, MerkleNode, MerkleTree, Nullifier, PublicKey, SecretKey,
+        TokenId,
     },
     pasta::pallas,
 };
@@ -211,6 +210,125 @@ impl Drk {
         Ok(ret)
     }
 
+    /// Fetch all view keys from the wallet
+    pub async fn get_money_view_keys(&self) -> Result<Vec<SecretKey>> {
+        let query =
+            format!("SELECT {} FROM {};", MONEY_VIEW_KEYS_COL_SECRET, MONEY_VIEW_KEYS_TABLE);
+        let params = json!([query, QueryType::Blob as u8, MONEY_VIEW_KEYS_COL_SECRET]);
+        let req = JsonRequest::new("wallet.query_row_multi", params);
+        let rep = self.rpc_client.request(req).await?;
+
+        // The returned thing should be an array of found rows.
+        let Some(rows) = rep.as_array() else {
+            return Err(anyhow!("[get_money_view_keys] Unexpected response from darkfid: {}", rep))
+        };
+
+        let mut keys = Vec::with_capacity(rows.len());
+
+        for row in rows {
+            let key_bytes: Vec<u8> = serde_json::from_value(row[0].clone())?;
+            let key = deserialize(&key_bytes)?;
+            keys.push(key);
+        }
+
+        Ok(keys)
+    }
+
+    /// Import given view keys into the wallet, so coins sent to their view
+    /// addresses are found when scanning. Returns the respective view addresses.
+    pub async fn import_money_view_keys(&self, keys: Vec<ViewKey>) -> Result<Vec<PublicKey>> {
+        let keys: Vec<String> =
+            keys.iter().map(|key| bs58::encode(&serialize(&key.secret())).into_string()).collect();
+
+        let req = JsonRequest::new("wallet.import_view_keys", json!(keys));
+        let rep = self.rpc_client.request(req).await?;
+
+        let Some(addresses) = rep.as_array() else {
+            return Err(anyhow!(
+                "[import_money_view_keys] Unexpected response from darkfid: {}",
+                rep
+            ))
+        };
+
+        let mut ret = Vec::with_capacity(addresses.len());
+        for address in addresses {
+            let Some(address) = address.as_str() else {
+                return Err(anyhow!("[import_money_view_keys] Invalid view address: {}", address))
+            };
+            ret.push(PublicKey::from_str(address)?);
+        }
+
+        Ok(ret)
+    }
+
+    /// Fetch the view keys derived from the wallet's secret keys. These can
+    /// be imported into a watch-only wallet, but can't spend.
+    pub async fn export_money_view_keys(&self) -> Result<Vec<ViewKey>> {
+        let req = JsonRequest::new("wallet.export_view_keys", json!([]));
+        let rep = self.rpc_client.request(req).await?;
+
+        let Some(keys) = rep.as_array() else {
+            return Err(anyhow!(
+                "[export_money_view_keys] Unexpected response from darkfid: {}",
+                rep
+            ))
+        };
+
+        let mut ret = Vec::with_capacity(keys.len());
+        for key in keys {
+            let Some(key) = key.as_str() else {
+                return Err(anyhow!("[export_money_view_keys] Invalid view key: {}", key))
+            };
+            ret.push(ViewKey::from(SecretKey::from_str(key)?));
+        }
+
+        Ok(ret)
+    }
+
+    /// A wallet is watch-only when it holds view keys, but no spend keys.
+    pub async fn is_watch_only(&self) -> Result<bool> {
+        Ok(self.get_money_secrets().await?.is_empty() &&
+            !self.get_money_view_keys().await?.is_empty())
+    }
+
+    /// Fail with a descriptive error if the wallet is watch-only, and thus
+    /// can't create transactions spending its coins.
+    pub async fn ensure_spendable(&self) -> Result<()> {
+        if self.is_watch_only().await? {
+            return Err(anyhow!(
+                "Wallet is watch-only and holds no spend keys. \
+                 Use `drk transfer --unsigned` to export a transfer for offline signing."
+            ))
+        }
+
+        Ok(())
+    }
+
+    /// Fetch the address of the first view key in the wallet.
+    pub async fn wallet_view_address(&self) -> Result<PublicKey> {
+        let query = format!(
+            "SELECT {} FROM {} ORDER BY {} LIMIT 1;",
+            MONEY_VIEW_KEYS_COL_PUBLIC, MONEY_VIEW_KEYS_TABLE, MONEY_VIEW_KEYS_COL_KEY_ID
+        );
+
+        let params = json!([query, QueryType::Blob as u8, MONEY_VIEW_KEYS_COL_PUBLIC]);
+        let req = JsonRequest::new("wallet.query_row_single", params);
+        let rep = self.rpc_client.request(req).await?;
+
+        let Some(arr) = rep.as_array() else {
+            return Err(anyhow!("[wallet_view_address] Unexpected response from darkfid: {}", rep))
+        };
+
+        if arr.len() != 1 {
+            return Err(anyhow!("Did not find any view key"))
+        }
+
+        let key_bytes: Vec<u8> = serde_json::from_value(arr[0].clone())?;
+        let public_key: PublicKey = deserialize(&key_bytes)?;
+
+        Ok(public_key)
+    }
+
     /// Fetch pubkeys from the wallet and return the requested index.
     pub async fn wallet_address(&self, idx: u64) -> Result<PublicKey> {
         let query = format!(
@@ -236,6 +354,15 @@ impl Drk {
         Ok(public_key)
     }
 
+    /// Fetch the unspent coins the wallet can spend, leaving out the ones
+    /// found through view keys.
+    pub async fn get_spendable_coins(&self) -> Result<Vec<(OwnCoin, bool)>> {
+        let view_keys = self.get_money_view_keys().await?;
+        let mut coins = self.get_coins(false).await?;
+        coins.retain(|x| !view_keys.contains(&x.0.secret));
+        Ok(coins)
+    }
+
     /// Fetch all coins and their metadata related to the Money contract from the wallet.
     /// Optionally also fetch spent ones.
     /// The boolean in the returned tuple notes if the coin was marked as spent.
@@ -453,122 +580,58 @@ impl Drk {
         let params = json!([query]);
         let req = JsonRequest::new("wallet.exec_sql", params);
         let _ = self.rpc_client.request(req).await?;
+        self.reset_coin_witnesses().await?;
         eprintln!("Successfully reset coins");
 
         Ok(())
     }
 
     /// Fetch known unspent balances from the wallet and return them as a hashmap.
+    /// Coins found through view keys are left out, see [`Drk::money_received`].
     pub async fn money_balance(&self) -> Result<HashMap<String, u64>> {
-        let mut coins = self.get_coins(false).await?;
+        let mut coins = self.get_spendable_coins().await?;
         coins.retain(|x| x.0.note.spend_hook == pallas::Base::zero());
 
-        // Fill this map with balances
-        let mut balmap: HashMap<String, u64> = HashMap::new();
-
-        for coin in coins {
-            let mut value = coin.0.note.value;
+        Ok(sum_by_token(&coins))
+    }
 
-            if let Some(prev) = balmap.get(&coin.0.note.token_id.to_string()) {
-                value += prev;
-            }
+    /// Fetch the totals of the coins found through the wallet's view keys,
+    /// and return them as a hashmap. View keys can't compute nullifiers, so
+    /// these coins never show up as spent. The totals only tell what the
+    /// watched addresses have received, not what they hold.
+    pub async fn money_received(&self) -> Result<HashMap<String, u64>> {
+        let view_keys = self.get_money_view_keys().await?;
+        let mut coins = self.get_coins(true).await?;
+        coins.retain(|x| view_keys.contains(&x.0.secret));
 
-            balmap.insert(coin.0.note.token_id.to_string(), value);
-        }
-
-        Ok(balmap)
+        Ok(sum_by_token(&coins))
     }
 
     /// Append data related to Money contract transactions into the wallet database.
-    pub async fn apply_tx_money_data(&self, tx: &Transaction, _confirm: bool) -> Result<()> {
-        let cid = *MONEY_CONTRACT_ID;
-
-        let mut nullifiers: Vec<Nullifier> = vec![];
-        let mut outputs: Vec<Output> = vec![];
-        let mut freezes: Vec<TokenId> = vec![];
-
-        for (i, call) in tx.calls.iter().enumerate() {
-            if call.contract_id == cid && call.data[0] == MoneyFunction::TransferV1 as u8 {
-                eprintln!("Found Money::TransferV1 in call {}", i);
-                let params: MoneyTransferParamsV1 = deserialize(&call.data[1..])?;
-
-                for input in params.inputs {
-                    nullifiers.push(input.nullifier);
-                }
-
-                for output in params.outputs {
-                    outputs.push(output);
-                }
-
-                continue
-            }
-
-            if call.contract_id == cid && call.data[0] == MoneyFunction::OtcSwapV1 as u8 {
-                eprintln!("Found Money::OtcSwapV1 in call {}", i);
-                let params: MoneyTransferParamsV1 = deserialize(&call.data[1..])?;
-
-                for input in params.inputs {
-                    nullifiers.push(input.nullifier);
-                }
-
-                for output in params.outputs {
-                    outputs.push(output);
-                }
-
-                continue
-            }
-
-            if call.contract_id == cid && call.data[0] == MoneyFunction::TokenMintV1 as u8 {
-                eprintln!("Found Money::MintV1 in call {}", i);
-                let params: MoneyTokenMintParamsV1 = deserialize(&call.data[1..])?;
-                outputs.push(params.output);
-                continue
-            }
-
-            if call.contract_id == cid && call.data[0] == MoneyFunction::TokenFreezeV1 as u8 {
-                eprintln!("Found Money::FreezeV1 in call {}", i);
-                let params: MoneyTokenFreezeParamsV1 = deserialize(&call.data[1..])?;
-                let token_id = TokenId::derive_public(params.signature_public);
-                freezes.push(token_id);
-            }
+    /// Returns `true` if the transaction created or spent any of our coins.
+    pub async fn apply_tx_money_data(&self, tx: &Transaction, _confirm: bool) -> Result<bool> {
+        let effects = MoneyTxEffects::from_tx(tx)?;
+        if effects.is_empty() {
+            return Ok(false)
         }
 
         let secrets = self.get_money_secrets().await?;
+        let view_keys = self.get_money_view_keys().await?;
         let dao_secrets = self.get_dao_secrets().await?;
-        let mut tree = self.get_money_tree().await?;
-
-        let mut owncoins = vec![];
-
-        for output in outputs {
-            let coin = output.coin;
-
-            // Append the new coin to the Merkle tree. Every coin has to be added.
-            tree.append(MerkleNode::from(coin.inner()));
-
-            // Attempt to decrypt the note
-            for secret in secrets.iter().chain(dao_secrets.iter()) {
-                if let Ok(note) = output.note.decrypt::<MoneyNote>(secret) {
-                    eprintln!("Successfully decrypted a Money Note");
-                    eprintln!("Witnessing coin in Merkle tree");
-                    let leaf_position = tree.mark().unwrap();
-
-                    let owncoin = OwnCoin {
-                        coin,
-                        note: note.clone(),
-                        secret: *secret,
-                        nullifier: Nullifier::from(poseidon_hash([secret.inner(), note.serial])),
-                        leaf_position,
-                    };
-
-                    owncoins.push(owncoin);
-                }
-            }
-        }
+        let keys: Vec<SecretKey> =
+            secrets.into_iter().chain(view_keys).chain(dao_secrets).collect();
+        let coins: Vec<OwnCoin> =
+            self.get_coins(false).await?.into_iter().map(|(c, _)| c).collect();
+        let spent_own = coins.iter().any(|c| effects.nullifiers.contains(&c.nullifier));
+        let mut witnesses = CoinWitnessService::new(self.get_money_tree().await?, coins);
+
+        let owncoins = witnesses.apply_effects(&effects, &keys);
+        let MoneyTxEffects { nullifiers, freezes, .. } = effects;
+        eprintln!("Found {} OwnCoin(s) in transaction", owncoins.len());
 
-        self.put_money_tree(&tree).await?;
-        if !nullifiers.is_empty() {
-            self.mark_spent_coins(&nullifiers).await?;
-        }
+        self.put_money_tree(witnesses.tree()).await?;
+        self.put_coin_witnesses(&witnesses.witnesses()).await?;
+        self.mark_spent_coins(&nullifiers).await?;
 
         // This is the SQL query we'll be executing to insert new coins
         // into the wallet
@@ -590,7 +653,6 @@ impl Drk {
             MONEY_COINS_COL_MEMO,
         );
 
-        eprintln!("Found {} OwnCoin(s) in transaction", owncoins.len());
         for owncoin in &owncoins {
             eprintln!("OwnCoin: {:?}", owncoin.coin);
             let params = json!([
@@ -627,6 +689,20 @@ impl Drk {
             let _ = self.rpc_client.request(req).await?;
         }
 
+        // Track where our coins came from, for private coin selection
+        let spent: Vec<Coin> = if nullifiers.is_empty() {
+            vec![]
+        } else {
+            let coins = self.get_coins(true).await?;
+            coins
+                .into_iter()
+                .filter(|(c, _)| nullifiers.contains(&c.nullifier))
+                .map(|(c, _)| c.coin)
+                .collect()
+        };
+        let received: Vec<Coin> = owncoins.iter().map(|c| c.coin).collect();
+        self.track_coin_sources(&tx.hash().to_string(), &spent, &received).await?;
+
         for token_id in freezes {
             let query = format!(
                 "UPDATE {} SET {} = 1 WHERE {} = ?1;",
@@ -643,7 +719,7 @@ impl Drk {
             kaching().await;
         }
 
-        Ok(())
+        Ok(spent_own || !owncoins.is_empty())
     }
 
     /// Get the last scanned slot from the wallet
@@ -786,3 +862,14 @@ impl Drk {
         Ok(())
     }
 }
+
+/// Sum the values of the given coins per token ID.
+fn sum_by_token(coins: &[(OwnCoin, bool)]) -> HashMap<String, u64> {
+    let mut balmap: HashMap<String, u64> = HashMap::new();
+
+    for (coin, _) in coins {
+        *balmap.entry(coin.note.token_id.to_string()).or_default() += coin.note.value;
+    }
+
+    balmap
+}
@@ -0,0 +1,200 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use darkfi::{rpc::jsonrpc::JsonRequest, wallet::walletdb::QueryType};
+use darkfi_money_contract::{
+    client::{
+        OwnCoin, MONEY_SPEND_INTENTS_COL_COIN, MONEY_SPEND_INTENTS_COL_IS_BROADCAST,
+        MONEY_SPEND_INTENTS_COL_TX_HASH, MONEY_SPEND_INTENTS_TABLE,
+    },
+    model::Coin,
+};
+use darkfi_serial::{deserialize, serialize};
+use serde_json::json;
+
+use super::Drk;
+
+impl Drk {
+    /// Journal the coins spent by the transaction with the given hash and
+    /// mark them as spent, so they can't be picked by another transaction
+    /// while this one hasn't been finalized. This has to happen before the
+    /// transaction leaves the wallet.
+    pub async fn reserve_coins(&self, coins: &[OwnCoin], tx_hash: &blake3::Hash) -> Result<()> {
+        let query = format!(
+            "INSERT OR REPLACE INTO {} ({}, {}, {}) VALUES (?1, ?2, ?3);",
+            MONEY_SPEND_INTENTS_TABLE,
+            MONEY_SPEND_INTENTS_COL_COIN,
+            MONEY_SPEND_INTENTS_COL_TX_HASH,
+            MONEY_SPEND_INTENTS_COL_IS_BROADCAST,
+        );
+
+        for coin in coins {
+            let params = json!([
+                query,
+                QueryType::Blob as u8,
+                serialize(&coin.coin.inner()),
+                QueryType::Text as u8,
+                tx_hash.to_string(),
+                QueryType::Integer as u8,
+                0,
+            ]);
+
+            let req = JsonRequest::new("wallet.exec_sql", params);
+            let _ = self.rpc_client.request(req).await?;
+
+            self.mark_spent_coin(&coin.coin).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Mark the spend intents of the transaction with the given hash as
+    /// broadcasted. From here on the transaction may reach the network.
+    pub async fn mark_spend_intents_broadcast(&self, tx_hash: &str) -> Result<()> {
+        let query = format!(
+            "UPDATE {} SET {} = ?1 WHERE {} = ?2;",
+            MONEY_SPEND_INTENTS_TABLE,
+            MONEY_SPEND_INTENTS_COL_IS_BROADCAST,
+            MONEY_SPEND_INTENTS_COL_TX_HASH,
+        );
+
+        let params = json!([query, QueryType::Integer as u8, 1, QueryType::Text as u8, tx_hash]);
+
+        let req = JsonRequest::new("wallet.exec_sql", params);
+        let _ = self.rpc_client.request(req).await?;
+
+        Ok(())
+    }
+
+    /// Fetch all spend intents in the wallet, grouped by transaction hash.
+    /// Each transaction maps to its spent coins and whether it has been
+    /// broadcasted.
+    pub async fn get_spend_intents(&self) -> Result<HashMap<String, (Vec<Coin>, bool)>> {
+        let query = format!("SELECT * FROM {};", MONEY_SPEND_INTENTS_TABLE);
+
+        let params = json!([
+            query,
+            QueryType::Blob as u8,
+            MONEY_SPEND_INTENTS_COL_COIN,
+            QueryType::Text as u8,
+            MONEY_SPEND_INTENTS_COL_TX_HASH,
+            QueryType::Integer as u8,
+            MONEY_SPEND_INTENTS_COL_IS_BROADCAST,
+        ]);
+
+        let req = JsonRequest::new("wallet.query_row_multi", params);
+        let rep = self.rpc_client.request(req).await?;
+
+        let Some(rows) = rep.as_array() else {
+            return Err(anyhow!("[get_spend_intents] Unexpected response from darkfid: {}", rep))
+        };
+
+        let mut ret: HashMap<String, (Vec<Coin>, bool)> = HashMap::new();
+        for row in rows {
+            let coin_bytes: Vec<u8> = serde_json::from_value(row[0].clone())?;
+            let coin: Coin = deserialize(&coin_bytes)?;
+            let tx_hash: String = serde_json::from_value(row[1].clone())?;
+            let is_broadcast: u64 = serde_json::from_value(row[2].clone())?;
+
+            let entry = ret.entry(tx_hash).or_default();
+            entry.0.push(coin);
+            entry.1 |= is_broadcast > 0;
+        }
+
+        Ok(ret)
+    }
+
+    /// Remove the spend intents of the transaction with the given hash.
+    pub async fn remove_spend_intents(&self, tx_hash: &str) -> Result<()> {
+        let query = format!(
+            "DELETE FROM {} WHERE {} = ?1;",
+            MONEY_SPEND_INTENTS_TABLE, MONEY_SPEND_INTENTS_COL_TX_HASH,
+        );
+
+        let params = json!([query, QueryType::Text as u8, tx_hash]);
+
+        let req = JsonRequest::new("wallet.exec_sql", params);
+        let _ = self.rpc_client.request(req).await?;
+
+        Ok(())
+    }
+
+    /// Release the coins reserved by the transaction with the given hash,
+    /// marking them as unspent and removing its spend intents. Used when
+    /// the transaction is known to never make it into the chain.
+    pub async fn release_spend_intents(&self, tx_hash: &str) -> Result<()> {
+        let intents = self.get_spend_intents().await?;
+        let Some((coins, _)) = intents.get(tx_hash) else { return Ok(()) };
+
+        for coin in coins {
+            self.unspend_coin(coin).await?;
+        }
+
+        self.remove_spend_intents(tx_hash).await
+    }
+
+    /// Reconcile the spend intents journal against the node's chain and
+    /// mempool state:
+    /// * Intents of transactions found in the chain are cleared.
+    /// * Intents of broadcasted transactions the node doesn't know about
+    ///   anymore are released, so their coins become spendable again.
+    /// * Anything else keeps its coins reserved.
+    pub async fn reconcile_spend_intents(&self) -> Result<()> {
+        let intents = self.get_spend_intents().await?;
+        if intents.is_empty() {
+            return Ok(())
+        }
+
+        // Without a view of the mempool we can't tell a pending transaction
+        // from a dropped one, so we leave everything as is.
+        let pending = match self.get_pending_txs().await {
+            Ok(v) => v,
+            Err(e) => {
+                eprintln!("Unable to fetch pending transactions, skipping reconciliation: {}", e);
+                return Ok(())
+            }
+        };
+
+        for (tx_hash, (coins, is_broadcast)) in intents {
+            let hash = blake3::Hash::from_hex(&tx_hash)?;
+
+            if self.get_tx(&hash).await?.is_some() {
+                self.remove_spend_intents(&tx_hash).await?;
+                continue
+            }
+
+            if is_broadcast && !pending.contains(&tx_hash) {
+                eprintln!("Transaction {} was dropped, releasing its coins", tx_hash);
+                self.release_spend_intents(&tx_hash).await?;
+                self.update_tx_history_record_status(&tx_hash, "Rejected").await?;
+                continue
+            }
+
+            // Rescanning might have reset the coins, so make sure they
+            // remain reserved.
+            for coin in coins {
+                self.mark_spent_coin(&coin).await?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -16,9 +16,13 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use std::collections::HashMap;
+
 use anyhow::{anyhow, Result};
 use darkfi::{rpc::jsonrpc::JsonRequest, tx::Transaction, wallet::walletdb::QueryType};
+use darkfi_sdk::crypto::{note::AeadEncryptedNote, PublicKey, SecretKey};
 use darkfi_serial::{deserialize, serialize};
+use rand::rngs::OsRng;
 use serde_json::json;
 
 use super::Drk;
@@ -29,6 +33,9 @@ const WALLET_TXS_HISTORY_TABLE: &str = "transactions_history";
 const WALLET_TXS_HISTORY_COL_TX_HASH: &str = "transaction_hash";
 const WALLET_TXS_HISTORY_COL_STATUS: &str = "status";
 const WALLET_TXS_HISTORY_COL_TX: &str = "tx";
+const WALLET_TXS_LABELS_TABLE: &str = "transactions_labels";
+const WALLET_TXS_LABELS_COL_TX_HASH: &str = "transaction_hash";
+const WALLET_TXS_LABELS_COL_LABEL: &str = "label";
 
 impl Drk {
     /// Fetch all transactions history records, excluding bytes column.
@@ -189,4 +196,108 @@ impl Drk {
 
         Ok(())
     }
+
+    /// Retrieve the secret key transaction labels are encrypted with.
+    /// This is the first spend key in the wallet, or the first view key
+    /// for watch-only wallets.
+    async fn labels_secret(&self) -> Result<SecretKey> {
+        if let Some(secret) = self.get_money_secrets().await?.first() {
+            return Ok(*secret)
+        }
+
+        match self.get_money_view_keys().await?.first() {
+            Some(key) => Ok(*key),
+            None => Err(anyhow!("Wallet holds no keys to encrypt transaction labels with")),
+        }
+    }
+
+    /// Set the label of a transaction, replacing any existing one.
+    pub async fn set_tx_label(&self, tx_hash: &str, label: &str) -> Result<()> {
+        let public = PublicKey::from_secret(self.labels_secret().await?);
+        let encrypted = AeadEncryptedNote::encrypt(&label.to_string(), &public, &mut OsRng)?;
+
+        let query = format!(
+            "INSERT OR REPLACE INTO {} ({}, {}) VALUES (?1, ?2);",
+            WALLET_TXS_LABELS_TABLE, WALLET_TXS_LABELS_COL_TX_HASH, WALLET_TXS_LABELS_COL_LABEL,
+        );
+
+        let params = json!([
+            query,
+            QueryType::Text as u8,
+            tx_hash,
+            QueryType::Blob as u8,
+            serialize(&encrypted),
+        ]);
+
+        let req = JsonRequest::new("wallet.exec_sql", params);
+        let _ = self.rpc_client.request(req).await?;
+
+        Ok(())
+    }
+
+    /// Remove the label of a transaction.
+    pub async fn remove_tx_label(&self, tx_hash: &str) -> Result<()> {
+        let query = format!(
+            "DELETE FROM {} WHERE {} = ?1;",
+            WALLET_TXS_LABELS_TABLE, WALLET_TXS_LABELS_COL_TX_HASH,
+        );
+
+        let params = json!([query, QueryType::Text as u8, tx_hash]);
+
+        let req = JsonRequest::new("wallet.exec_sql", params);
+        let _ = self.rpc_client.request(req).await?;
+
+        Ok(())
+    }
+
+    /// Fetch and decrypt all transaction labels, mapped by transaction hash.
+    pub async fn get_tx_labels(&self) -> Result<HashMap<String, String>> {
+        let query = format!(
+            "SELECT {}, {} FROM {};",
+            WALLET_TXS_LABELS_COL_TX_HASH, WALLET_TXS_LABELS_COL_LABEL, WALLET_TXS_LABELS_TABLE
+        );
+
+        let params = json!([
+            query,
+            QueryType::Text as u8,
+            WALLET_TXS_LABELS_COL_TX_HASH,
+            QueryType::Blob as u8,
+            WALLET_TXS_LABELS_COL_LABEL,
+        ]);
+
+        let req = JsonRequest::new("wallet.query_row_multi", params);
+        let rep = self.rpc_client.request(req).await?;
+
+        let Some(rows) = rep.as_array() else {
+            return Err(anyhow!("[get_tx_labels] Unexpected response from darkfid: {}", rep))
+        };
+
+        let mut ret = HashMap::new();
+        if rows.is_empty() {
+            return Ok(ret)
+        }
+
+        let secret = self.labels_secret().await?;
+        for row in rows {
+            let tx_hash: String = serde_json::from_value(row[0].clone())?;
+            let label_bytes: Vec<u8> = serde_json::from_value(row[1].clone())?;
+            let encrypted: AeadEncryptedNote = deserialize(&label_bytes)?;
+            let Ok(label) = encrypted.decrypt::<String>(&secret) else {
+                eprintln!("Warning: Failed to decrypt label of transaction {}", tx_hash);
+                continue
+            };
+            ret.insert(tx_hash, label);
+        }
+
+        Ok(ret)
+    }
+
+    /// Import given transaction labels, replacing existing ones.
+    pub async fn import_tx_labels(&self, labels: &HashMap<String, String>) -> Result<()> {
+        for (tx_hash, label) in labels {
+            self.set_tx_label(tx_hash, label).await?;
+        }
+
+        Ok(())
+    }
 }
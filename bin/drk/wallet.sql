@@ -7,3 +7,9 @@ CREATE TABLE IF NOT EXISTS transactions_history (
     status TEXT NOT NULL,
 	tx BLOB NOT NULL
 );
+
+-- User annotations of transactions, encrypted to the wallet's key
+CREATE TABLE IF NOT EXISTS transactions_labels (
+    transaction_hash TEXT PRIMARY KEY NOT NULL,
+    label BLOB NOT NULL
+);
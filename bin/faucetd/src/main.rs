@@ -22,8 +22,10 @@ use async_trait::async_trait;
 use chrono::Utc;
 use darkfi_money_contract::{
     client::{
-        transfer_v1::TransferCallBuilder, MONEY_KEYS_COL_IS_DEFAULT, MONEY_KEYS_COL_PUBLIC,
-        MONEY_KEYS_COL_SECRET, MONEY_KEYS_TABLE, MONEY_TREE_COL_TREE, MONEY_TREE_TABLE,
+        amount::{parse_amount, DEFAULT_DECIMALS},
+        transfer_v1::TransferCallBuilder,
+        MONEY_KEYS_COL_IS_DEFAULT, MONEY_KEYS_COL_PUBLIC, MONEY_KEYS_COL_SECRET, MONEY_KEYS_TABLE,
+        MONEY_TREE_COL_TREE, MONEY_TREE_TABLE,
     },
     MoneyFunction::TransferV1 as MoneyTransfer,
     MONEY_CONTRACT_ZKAS_BURN_NS_V1, MONEY_CONTRACT_ZKAS_MINT_NS_V1,
@@ -53,9 +55,10 @@ use darkfi::{
     async_daemonize, cli_desc,
     consensus::{
         constants::{
-            MAINNET_BOOTSTRAP_TIMESTAMP, MAINNET_GENESIS_HASH_BYTES, MAINNET_GENESIS_TIMESTAMP,
-            MAINNET_INITIAL_DISTRIBUTION, TESTNET_BOOTSTRAP_TIMESTAMP, TESTNET_GENESIS_HASH_BYTES,
-            TESTNET_GENESIS_TIMESTAMP, TESTNET_INITIAL_DISTRIBUTION,
+            MAINNET_BOOTSTRAP_TIMESTAMP, MAINNET_CHECKPOINTS, MAINNET_GENESIS_HASH_BYTES,
+            MAINNET_GENESIS_TIMESTAMP, MAINNET_INITIAL_DISTRIBUTION, TESTNET_BOOTSTRAP_TIMESTAMP,
+            TESTNET_CHECKPOINTS, TESTNET_GENESIS_HASH_BYTES, TESTNET_GENESIS_TIMESTAMP,
+            TESTNET_INITIAL_DISTRIBUTION,
         },
         proto::{ProtocolSync, ProtocolTx},
         task::block_sync_task,
@@ -73,8 +76,8 @@ use darkfi::{
     runtime::vm_runtime::SMART_CONTRACT_ZKAS_DB_NAME,
     system::{sleep, StoppableTask},
     tx::Transaction,
-    util::{parse::decode_base10, path::expand_path},
-    wallet::{WalletDb, WalletPtr},
+    util::path::expand_path,
+    wallet::{WalletDb, WalletMigration, WalletPtr},
     zk::{proof::ProvingKey, vm::ZkCircuit, vm_heap::empty_witnesses},
     zkas::ZkBinary,
     Error, Result,
@@ -86,6 +89,13 @@ use error::{server_error, RpcError};
 const CONFIG_FILE: &str = "faucetd_config.toml";
 const CONFIG_FILE_CONTENTS: &str = include_str!("../faucetd_config.toml");
 
+/// Wallet schema migrations, applied in order
+const WALLET_MIGRATIONS: &[WalletMigration] = &[WalletMigration {
+    version: 1,
+    description: "Money contract tables",
+    sql: include_str!("../../../src/contract/money/wallet.sql"),
+}];
+
 #[derive(Clone, Debug, Deserialize, StructOpt, StructOptToml)]
 #[serde(default)]
 #[structopt(name = "faucetd", about = cli_desc!())]
@@ -156,7 +166,7 @@ struct Args {
 
     #[structopt(long, default_value = "10")]
     /// Airdrop amount limit
-    airdrop_limit: String, // We convert this to u64 with decode_base10
+    airdrop_limit: String, // We convert this to u64 with parse_amount
 
     #[structopt(short, long)]
     /// Set log file to ouput into
@@ -277,15 +287,14 @@ impl Faucetd {
 
     async fn initialize_wallet(wallet: WalletPtr) -> Result<MerkleTree> {
         // Perform wallet initialization for the money contract
-        let wallet_schema = include_str!("../../../src/contract/money/wallet.sql");
+        info!(target: "faucetd", "Migrating wallet schema");
+        let version = wallet.migrate(WALLET_MIGRATIONS).await?;
+        info!(target: "faucetd", "Wallet schema is at version {}", version);
 
         // Get a wallet connection
         info!(target: "faucetd", "Acquiring wallet connection");
         let conn = wallet.conn.lock().await;
 
-        info!(target: "faucetd", "Initializing wallet schema");
-        conn.execute(wallet_schema, rusqlite::params![])?;
-
         let query = format!("SELECT * FROM {}", MONEY_TREE_COL_TREE);
         let merkle_tree = conn.query_row(&query, [], |row| {
             let tree_bytes: Vec<u8> = row.get(MONEY_TREE_COL_TREE)?;
@@ -460,7 +469,7 @@ impl Faucetd {
 
         // Decode requested airdrop amount
         let amount = params[1].get::<f64>().unwrap().to_string();
-        let amount = match decode_base10(&amount, 8, true) {
+        let amount = match parse_amount(&amount, DEFAULT_DECIMALS) {
             Ok(v) => v,
             Err(_) => {
                 error!(target: "faucetd", "airdrop(): Failed parsing amount from string");
@@ -542,9 +551,11 @@ impl Faucetd {
             rcpt_spend_hook: pallas::Base::zero(),
             rcpt_user_data: pallas::Base::zero(),
             rcpt_user_data_blind: pallas::Base::random(&mut OsRng),
+            rcpt_view_public: None,
             change_spend_hook: pallas::Base::zero(),
             change_user_data: pallas::Base::zero(),
             change_user_data_blind: pallas::Base::random(&mut OsRng),
+            change_view_public: None,
             coins: vec![],
             tree: self.merkle_tree.clone(),
             mint_zkbin,
@@ -567,7 +578,7 @@ impl Faucetd {
         debris.params.encode(&mut data).unwrap();
         let calls = vec![ContractCall { contract_id: cid, data }];
         let proofs = vec![debris.proofs];
-        let mut tx = Transaction { calls, proofs, signatures: vec![] };
+        let mut tx = Transaction { calls, proofs, signatures: vec![], access_list: None };
         let sigs = tx.create_sigs(&mut OsRng, &debris.signature_secrets).unwrap();
         tx.signatures = vec![sigs];
 
@@ -637,24 +648,27 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
     let sled_db = sled::open(&db_path)?;
 
     // Initialize validator state
-    let (bootstrap_ts, genesis_ts, genesis_data, initial_distribution) = match args.chain.as_str() {
-        "mainnet" => (
-            *MAINNET_BOOTSTRAP_TIMESTAMP,
-            *MAINNET_GENESIS_TIMESTAMP,
-            *MAINNET_GENESIS_HASH_BYTES,
-            *MAINNET_INITIAL_DISTRIBUTION,
-        ),
-        "testnet" => (
-            *TESTNET_BOOTSTRAP_TIMESTAMP,
-            *TESTNET_GENESIS_TIMESTAMP,
-            *TESTNET_GENESIS_HASH_BYTES,
-            *TESTNET_INITIAL_DISTRIBUTION,
-        ),
-        x => {
-            error!(target: "faucetd", "Unsupported chain `{}`", x);
-            return Err(Error::UnsupportedChain)
-        }
-    };
+    let (bootstrap_ts, genesis_ts, genesis_data, initial_distribution, checkpoints) =
+        match args.chain.as_str() {
+            "mainnet" => (
+                *MAINNET_BOOTSTRAP_TIMESTAMP,
+                *MAINNET_GENESIS_TIMESTAMP,
+                *MAINNET_GENESIS_HASH_BYTES,
+                *MAINNET_INITIAL_DISTRIBUTION,
+                &*MAINNET_CHECKPOINTS,
+            ),
+            "testnet" => (
+                *TESTNET_BOOTSTRAP_TIMESTAMP,
+                *TESTNET_GENESIS_TIMESTAMP,
+                *TESTNET_GENESIS_HASH_BYTES,
+                *TESTNET_INITIAL_DISTRIBUTION,
+                &*TESTNET_CHECKPOINTS,
+            ),
+            x => {
+                error!(target: "faucetd", "Unsupported chain `{}`", x);
+                return Err(Error::UnsupportedChain)
+            }
+        };
 
     // Parse faucet addresses
     let mut faucet_pubkeys = vec![];
@@ -717,7 +731,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
         .await;
 
     let airdrop_timeout = args.airdrop_timeout;
-    let airdrop_limit = decode_base10(&args.airdrop_limit, 8, true)?;
+    let airdrop_limit = parse_amount(&args.airdrop_limit, DEFAULT_DECIMALS)?;
 
     // Initialize program state
     let faucetd = Faucetd::new(
@@ -781,7 +795,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
     //info!("Waiting for sync P2P outbound connections");
     //sync_p2p.clone().wait_for_outbound(ex).await?;
 
-    match block_sync_task(sync_p2p.clone(), state.clone()).await {
+    match block_sync_task(sync_p2p.clone(), state.clone(), checkpoints).await {
         Ok(()) => *faucetd.synced.lock().await = true,
         Err(e) => error!(target: "faucetd", "Failed syncing blockchain: {}", e),
     }
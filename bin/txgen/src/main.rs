@@ -0,0 +1,365 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Synthetic transaction load generator.
+//!
+//! Creates valid `Money::TokenMintV1` transactions, each minting under a
+//! fresh mint authority so they never conflict with each other, and sends
+//! them to a (regtest) darkfid node at a configurable rate. While doing so
+//! it samples the node's mempool and follows the produced blocks, so the
+//! resulting report can be used to tune the fee model empirically.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use clap::Parser;
+use darkfi::{
+    rpc::client::RpcClient,
+    tx::Transaction,
+    util::cli::{get_log_config, get_log_level},
+    zk::{proof::ProvingKey, vm::ZkCircuit, vm_heap::empty_witnesses},
+    zkas::ZkBinary,
+    Result,
+};
+use darkfi_money_contract::{client::token_mint_v1::TokenMintCallBuilder, MoneyFunction};
+use darkfi_sdk::{
+    crypto::{contract_id::MONEY_CONTRACT_ID, Keypair},
+    pasta::pallas,
+    tx::ContractCall,
+};
+use darkfi_serial::Encodable;
+use log::{info, warn};
+use rand::{rngs::OsRng, Rng};
+use simplelog::{ColorChoice, TermLogger, TerminalMode};
+use smol::{Executor, Timer};
+use url::Url;
+
+mod rpc;
+use rpc::MempoolMetrics;
+
+#[derive(Parser)]
+#[clap(name = "txgen", version)]
+struct Args {
+    #[arg(short, action = clap::ArgAction::Count)]
+    /// Increase verbosity (-vvv supported)
+    verbose: u8,
+
+    #[clap(short, long, default_value = "tcp://127.0.0.1:8340")]
+    /// darkfid JSON-RPC endpoint
+    endpoint: Url,
+
+    #[clap(short, long, default_value = "1.0")]
+    /// Transactions to send per second
+    rate: f64,
+
+    #[clap(short = 'n', long, default_value = "100")]
+    /// Total number of transactions to send
+    count: u64,
+
+    #[clap(long, default_value = "1")]
+    /// Minimum number of contract calls per transaction
+    min_calls: usize,
+
+    #[clap(long, default_value = "1")]
+    /// Maximum number of contract calls per transaction
+    max_calls: usize,
+
+    #[clap(long, default_value = "1000")]
+    /// Amount minted by each contract call
+    amount: u64,
+
+    #[clap(long, default_value = "5")]
+    /// Seconds between mempool and block samples
+    poll_interval: u64,
+
+    #[clap(long, default_value = "120")]
+    /// Seconds to keep following blocks after the last transaction was sent
+    drain_timeout: u64,
+}
+
+/// Load generator state
+pub struct TxGen {
+    pub rpc_client: RpcClient,
+}
+
+/// A transaction we sent, waiting to be included in a block
+struct SentTx {
+    sent_at: Instant,
+    calls: usize,
+}
+
+/// Collected measurements
+#[derive(Default)]
+struct Stats {
+    /// Transactions accepted by the node
+    sent: u64,
+    /// Transactions the node refused to accept
+    refused: u64,
+    /// Accepted transactions that left the mempool without being included
+    dropped: u64,
+    /// Inclusion latency of our included transactions
+    latencies: Vec<Duration>,
+    /// Estimated `(calls, gas, fee)` of each transaction we sent
+    fees: Vec<(usize, u64, u64)>,
+    /// `(height, total txs, our txs)` of each block seen
+    blocks: Vec<(u64, usize, usize)>,
+    /// Pending transactions count at each sample
+    mempool: Vec<usize>,
+}
+
+impl TxGen {
+    /// Build a transaction consisting of `calls` token mint calls, each
+    /// under a freshly generated mint authority.
+    fn build_tx(
+        &self,
+        calls: usize,
+        amount: u64,
+        zkbin: &ZkBinary,
+        pk: &ProvingKey,
+    ) -> Result<Transaction> {
+        let recipient = Keypair::random(&mut OsRng).public;
+
+        let mut tx_calls = Vec::with_capacity(calls);
+        let mut proofs = Vec::with_capacity(calls);
+        let mut authorities = Vec::with_capacity(calls);
+        for _ in 0..calls {
+            let mint_authority = Keypair::random(&mut OsRng);
+            let builder = TokenMintCallBuilder {
+                mint_authority,
+                recipient,
+                amount,
+                max_supply: None,
+                spend_hook: pallas::Base::zero(),
+                user_data: pallas::Base::zero(),
+                token_mint_zkbin: zkbin.clone(),
+                token_mint_pk: pk.clone(),
+            };
+            let debris = builder.build()?;
+
+            let mut data = vec![MoneyFunction::TokenMintV1 as u8];
+            debris.params.encode(&mut data)?;
+            tx_calls.push(ContractCall { contract_id: *MONEY_CONTRACT_ID, data });
+            proofs.push(debris.proofs);
+            authorities.push(mint_authority.secret);
+        }
+
+        let mut tx = Transaction { calls: tx_calls, proofs, signatures: vec![], access_list: None };
+        let mut signatures = Vec::with_capacity(calls);
+        for secret in authorities {
+            signatures.push(tx.create_sigs(&mut OsRng, &[secret])?);
+        }
+        tx.signatures = signatures;
+
+        Ok(tx)
+    }
+
+    /// Sample the node's mempool and record any blocks produced since
+    /// `height`, returning the new last seen height along with the pending
+    /// transactions found before following the blocks.
+    async fn sample(
+        &self,
+        mut height: u64,
+        inflight: &mut HashMap<String, SentTx>,
+        stats: &mut Stats,
+    ) -> Result<(u64, HashSet<String>)> {
+        let pending: HashSet<String> = self.pending().await?.into_iter().collect();
+        stats.mempool.push(pending.len());
+
+        while let Some(txs) = self.block_txs(height + 1).await? {
+            height += 1;
+
+            let mut ours = 0;
+            for tx in &txs {
+                if let Some(sent) = inflight.remove(tx) {
+                    stats.latencies.push(sent.sent_at.elapsed());
+                    ours += 1;
+                }
+            }
+
+            info!("Block {}: {} txs, {} ours", height, txs.len(), ours);
+            stats.blocks.push((height, txs.len(), ours));
+        }
+
+        Ok((height, pending))
+    }
+}
+
+/// Print the collected measurements.
+fn report(stats: &Stats, metrics: &MempoolMetrics, elapsed: Duration, unconfirmed: usize) {
+    println!("=== Load ===");
+    println!("Sent: {} ({} refused, {} dropped)", stats.sent, stats.refused, stats.dropped);
+    println!("Effective rate: {:.2} tx/s", stats.sent as f64 / elapsed.as_secs_f64());
+
+    println!("=== Fees ===");
+    if !stats.fees.is_empty() {
+        let fees: Vec<u64> = stats.fees.iter().map(|x| x.2).collect();
+        let total: u64 = fees.iter().sum();
+        println!(
+            "Fee min/avg/max: {}/{}/{}",
+            fees.iter().min().unwrap(),
+            total / fees.len() as u64,
+            fees.iter().max().unwrap()
+        );
+
+        let mut by_calls: HashMap<usize, (u64, u64, u64)> = HashMap::new();
+        for (calls, gas, fee) in &stats.fees {
+            let entry = by_calls.entry(*calls).or_default();
+            entry.0 += 1;
+            entry.1 += gas;
+            entry.2 += fee;
+        }
+        let mut by_calls: Vec<_> = by_calls.into_iter().collect();
+        by_calls.sort();
+        for (calls, (n, gas, fee)) in by_calls {
+            println!("  {} calls: avg gas {}, avg fee {} ({} txs)", calls, gas / n, fee / n, n);
+        }
+    }
+
+    println!("=== Mempool ===");
+    if !stats.mempool.is_empty() {
+        let total: usize = stats.mempool.iter().sum();
+        println!(
+            "Pending avg/max: {}/{}",
+            total / stats.mempool.len(),
+            stats.mempool.iter().max().unwrap()
+        );
+    }
+    println!("Conflicts: {} ({} replaced)", metrics.conflicts, metrics.replacements);
+
+    println!("=== Blocks ===");
+    if !stats.blocks.is_empty() {
+        let total: usize = stats.blocks.iter().map(|x| x.1).sum();
+        let ours: usize = stats.blocks.iter().map(|x| x.2).sum();
+        println!(
+            "Blocks: {}, txs per block avg/max: {:.2}/{}, ours included: {}",
+            stats.blocks.len(),
+            total as f64 / stats.blocks.len() as f64,
+            stats.blocks.iter().map(|x| x.1).max().unwrap(),
+            ours
+        );
+    }
+    if !stats.latencies.is_empty() {
+        let total: Duration = stats.latencies.iter().sum();
+        println!(
+            "Inclusion latency min/avg/max: {:?}/{:?}/{:?}",
+            stats.latencies.iter().min().unwrap(),
+            total / stats.latencies.len() as u32,
+            stats.latencies.iter().max().unwrap()
+        );
+    }
+    println!("Unconfirmed: {}", unconfirmed);
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let log_level = get_log_level(args.verbose);
+    let log_config = get_log_config(args.verbose);
+    TermLogger::init(log_level, log_config, TerminalMode::Mixed, ColorChoice::Auto)?;
+
+    if args.rate <= 0.0 ||
+        args.min_calls == 0 ||
+        args.min_calls > args.max_calls ||
+        args.amount == 0
+    {
+        eprintln!("Rate and amount must be positive, and 0 < min_calls <= max_calls");
+        std::process::exit(1)
+    }
+
+    let executor = Arc::new(Executor::new());
+
+    smol::block_on(executor.run(async {
+        let rpc_client = RpcClient::new(args.endpoint, executor.clone()).await?;
+        let txgen = TxGen { rpc_client };
+
+        info!("Building token mint circuit proving key");
+        let zkbin = txgen.token_mint_zkbin().await?;
+        let circuit = ZkCircuit::new(empty_witnesses(&zkbin)?, &zkbin);
+        let pk = ProvingKey::build(zkbin.k, &circuit);
+
+        let mut height = txgen.last_height().await?;
+        info!("Starting at block height {}", height);
+
+        let interval = Duration::from_secs_f64(1.0 / args.rate);
+        let poll_interval = Duration::from_secs(args.poll_interval);
+        let mut inflight = HashMap::new();
+        let mut stats = Stats::default();
+
+        let start = Instant::now();
+        let mut last_poll = start;
+        for i in 0..args.count {
+            let next = start + interval * i as u32;
+            Timer::at(next).await;
+
+            let calls = OsRng.gen_range(args.min_calls..=args.max_calls);
+            let tx = txgen.build_tx(calls, args.amount, &zkbin, &pk)?;
+
+            match txgen.estimate(&tx).await {
+                Ok((gas, fee)) => stats.fees.push((calls, gas, fee)),
+                Err(e) => warn!("Failed estimating tx fee: {}", e),
+            }
+
+            match txgen.broadcast(&tx).await {
+                Ok(txid) => {
+                    stats.sent += 1;
+                    inflight.insert(txid, SentTx { sent_at: Instant::now(), calls });
+                }
+                Err(e) => {
+                    warn!("Node refused tx: {}", e);
+                    stats.refused += 1;
+                }
+            }
+
+            if last_poll.elapsed() >= poll_interval {
+                (height, _) = txgen.sample(height, &mut inflight, &mut stats).await?;
+                last_poll = Instant::now();
+            }
+        }
+        let elapsed = start.elapsed();
+
+        info!("All transactions sent, waiting for {} to get included", inflight.len());
+        let drain_start = Instant::now();
+        while !inflight.is_empty() &&
+            drain_start.elapsed() < Duration::from_secs(args.drain_timeout)
+        {
+            Timer::after(poll_interval).await;
+            let (last_height, pending) = txgen.sample(height, &mut inflight, &mut stats).await?;
+            height = last_height;
+
+            // Transactions that left the mempool without being included got dropped
+            inflight.retain(|txid, sent| {
+                let keep = pending.contains(txid);
+                if !keep {
+                    warn!("Tx {} ({} calls) dropped from mempool", txid, sent.calls);
+                    stats.dropped += 1;
+                }
+                keep
+            });
+        }
+
+        let metrics = txgen.mempool_metrics().await?;
+        report(&stats, &metrics, elapsed, inflight.len());
+
+        txgen.rpc_client.close().await?;
+
+        Ok(())
+    }))
+}
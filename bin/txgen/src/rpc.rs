@@ -0,0 +1,172 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::collections::HashMap;
+
+use darkfi::{
+    rpc::jsonrpc::JsonRequest, tx::Transaction, util::encoding::base64, zkas::ZkBinary, Error,
+    Result,
+};
+use darkfi_money_contract::MONEY_CONTRACT_ZKAS_TOKEN_MINT_NS_V1;
+use darkfi_sdk::crypto::contract_id::MONEY_CONTRACT_ID;
+use darkfi_serial::serialize;
+use tinyjson::JsonValue;
+
+use super::TxGen;
+
+/// darkfid error code returned when a requested block doesn't exist
+const UNKNOWN_BLOCK: i32 = -32124;
+
+/// Node mempool conflict counters, as returned by `tx.mempool_metrics`
+#[derive(Default)]
+pub struct MempoolMetrics {
+    pub conflicts: u64,
+    pub replacements: u64,
+}
+
+impl TxGen {
+    /// Fetch the `TokenMint_V1` zkas circuit from the node.
+    pub async fn token_mint_zkbin(&self) -> Result<ZkBinary> {
+        let params = vec![JsonValue::String(MONEY_CONTRACT_ID.to_string())];
+        let req = JsonRequest::new("blockchain.lookup_zkas", JsonValue::Array(params));
+        let rep = self.rpc_client.request(req).await?;
+
+        let Some(bins) = rep.get::<Vec<JsonValue>>() else {
+            return Err(Error::ParseFailed("Invalid blockchain.lookup_zkas reply"))
+        };
+
+        for bin in bins {
+            let Some([ns, bincode]) = bin.get::<Vec<JsonValue>>().map(|v| v.as_slice()) else {
+                continue
+            };
+
+            if ns.get::<String>().map(|x| x.as_str()) != Some(MONEY_CONTRACT_ZKAS_TOKEN_MINT_NS_V1)
+            {
+                continue
+            }
+
+            let Some(bincode) = bincode.get::<String>().and_then(|x| base64::decode(x)) else {
+                return Err(Error::ParseFailed("Invalid zkas bincode encoding"))
+            };
+
+            return ZkBinary::decode(&bincode)
+        }
+
+        Err(Error::ParseFailed("Token mint circuit not found"))
+    }
+
+    /// Simulate the given transaction in estimate mode, returning the total
+    /// gas it used and the fee it has to pay.
+    pub async fn estimate(&self, tx: &Transaction) -> Result<(u64, u64)> {
+        let params =
+            vec![JsonValue::String(base64::encode(&serialize(tx))), JsonValue::Boolean(true)];
+        let req = JsonRequest::new("tx.simulate", JsonValue::Array(params));
+        let rep = self.rpc_client.request(req).await?;
+
+        let Some(gas) = rep["gas"]["total"].get::<f64>() else {
+            return Err(Error::ParseFailed("Invalid tx.simulate reply"))
+        };
+        let Some(fee) = rep["fee"].get::<f64>() else {
+            return Err(Error::ParseFailed("Invalid tx.simulate reply"))
+        };
+
+        Ok((*gas as u64, *fee as u64))
+    }
+
+    /// Broadcast the given transaction, returning its hash.
+    pub async fn broadcast(&self, tx: &Transaction) -> Result<String> {
+        let params = vec![JsonValue::String(base64::encode(&serialize(tx)))];
+        let req = JsonRequest::new("tx.broadcast", JsonValue::Array(params));
+        let rep = self.rpc_client.request(req).await?;
+
+        let Some(txid) = rep.get::<String>() else {
+            return Err(Error::ParseFailed("Invalid tx.broadcast reply"))
+        };
+
+        Ok(txid.clone())
+    }
+
+    /// Retrieve the hashes of the node's pending transactions.
+    pub async fn pending(&self) -> Result<Vec<String>> {
+        let req = JsonRequest::new("tx.pending", JsonValue::Array(vec![]));
+        let rep = self.rpc_client.request(req).await?;
+
+        let Some(txs) = rep.get::<Vec<JsonValue>>() else {
+            return Err(Error::ParseFailed("Invalid tx.pending reply"))
+        };
+
+        Ok(txs.iter().filter_map(|x| x.get::<String>().cloned()).collect())
+    }
+
+    /// Retrieve the node's mempool conflict counters.
+    pub async fn mempool_metrics(&self) -> Result<MempoolMetrics> {
+        let req = JsonRequest::new("tx.mempool_metrics", JsonValue::Array(vec![]));
+        let rep = self.rpc_client.request(req).await?;
+
+        let Some(metrics) = rep.get::<HashMap<String, JsonValue>>() else {
+            return Err(Error::ParseFailed("Invalid tx.mempool_metrics reply"))
+        };
+
+        let counter = |name: &str| {
+            metrics.get(name).and_then(|x| x.get::<f64>()).map(|x| *x as u64).unwrap_or_default()
+        };
+        Ok(MempoolMetrics {
+            conflicts: counter("conflicts"),
+            replacements: counter("replacements"),
+        })
+    }
+
+    /// Retrieve the transaction hashes of the block at given height.
+    /// Returns `None` if the node doesn't have such a block yet.
+    pub async fn block_txs(&self, height: u64) -> Result<Option<Vec<String>>> {
+        let params = vec![JsonValue::String(height.to_string())];
+        let req = JsonRequest::new("blockchain.get_block_by_height", JsonValue::Array(params));
+        let rep = match self.rpc_client.request(req).await {
+            Ok(v) => v,
+            Err(Error::JsonRpcError((UNKNOWN_BLOCK, _))) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        let Some(txs) = rep["txs"].get::<Vec<JsonValue>>() else {
+            return Err(Error::ParseFailed("Invalid blockchain.get_block_by_height reply"))
+        };
+
+        Ok(Some(txs.iter().filter_map(|x| x["hash"].get::<String>().cloned()).collect()))
+    }
+
+    /// Find the height of the node's last block.
+    pub async fn last_height(&self) -> Result<u64> {
+        // Gallop forward until we overshoot, then bisect back.
+        let mut lo = 0;
+        let mut hi = 1;
+        while self.block_txs(hi).await?.is_some() {
+            lo = hi;
+            hi *= 2;
+        }
+
+        while hi - lo > 1 {
+            let mid = lo + (hi - lo) / 2;
+            match self.block_txs(mid).await? {
+                Some(_) => lo = mid,
+                None => hi = mid,
+            }
+        }
+
+        Ok(lo)
+    }
+}
@@ -0,0 +1,90 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+// Embed, show or verify the build metadata of a contract wasm bincode.
+//
+// contract-build-meta embed <contract.wasm> <source_dir>...
+// contract-build-meta show <contract.wasm>
+// contract-build-meta verify <contract.wasm> <package> <workspace> <source_dir>...
+
+use std::{fs, path::PathBuf, process::exit};
+
+use darkfi::{
+    runtime::build_meta::{
+        code_hash, embed_build_metadata, read_build_metadata, verify_contract_build, BuildMetadata,
+    },
+    Result,
+};
+
+fn usage() -> ! {
+    eprintln!("Usage:");
+    eprintln!("    contract-build-meta embed <contract.wasm> <source_dir>...");
+    eprintln!("    contract-build-meta show <contract.wasm>");
+    eprintln!(
+        "    contract-build-meta verify <contract.wasm> <package> <workspace> <source_dir>..."
+    );
+    exit(1)
+}
+
+fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.len() < 2 {
+        usage()
+    }
+
+    let wasm_path = PathBuf::from(&args[1]);
+    let wasm = fs::read(&wasm_path)?;
+
+    match args[0].as_str() {
+        "embed" => {
+            let sources: Vec<PathBuf> = args[2..].iter().map(PathBuf::from).collect();
+            if sources.is_empty() {
+                usage()
+            }
+
+            let metadata = BuildMetadata::current(&sources)?;
+            fs::write(&wasm_path, embed_build_metadata(&wasm, &metadata)?)?;
+            println!("Embedded build metadata into {}", wasm_path.display());
+        }
+
+        "show" => {
+            println!("Code hash: {}", code_hash(&wasm)?);
+            match read_build_metadata(&wasm)? {
+                Some(metadata) => {
+                    println!("Source hash: {}", blake3::Hash::from(metadata.source_hash));
+                    println!("Compiler: {}", metadata.compiler_version);
+                }
+                None => println!("No build metadata embedded"),
+            }
+        }
+
+        "verify" => {
+            if args.len() < 5 {
+                usage()
+            }
+
+            let sources: Vec<PathBuf> = args[4..].iter().map(PathBuf::from).collect();
+            verify_contract_build(&wasm, &args[2], &PathBuf::from(&args[3]), &sources)?;
+            println!("{} reproduces from source", wasm_path.display());
+        }
+
+        _ => usage(),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,115 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::str::FromStr;
+
+use darkfi::{
+    zk::{empty_witnesses, ProvingKey, ZkCircuit},
+    zkas::ZkBinary,
+};
+use darkfi_money_contract::{
+    client::{
+        transfer_v1::{create_transfer_mint_proof, TransactionBuilderOutputInfo},
+        MoneyNote,
+    },
+    model::Output,
+};
+use darkfi_sdk::{
+    crypto::{note::AeadEncryptedNote, pasta_prelude::*, PublicKey, TokenId},
+    pasta::pallas,
+};
+use darkfi_serial::serialize;
+use rand::rngs::OsRng;
+use wasm_bindgen::prelude::*;
+
+/// The Money mint circuit, compiled with `zkas`
+const MINT_ZKBIN: &[u8] = include_bytes!("../../../src/contract/money/proof/mint_v1.zk.bin");
+
+/// A mint proof created by [`prove_mint`], with every field serialized.
+#[wasm_bindgen(getter_with_clone)]
+pub struct MintProof {
+    /// The mint `Proof`
+    pub proof: Vec<u8>,
+    /// Its public inputs, as a `Vec<pallas::Base>`
+    pub public_inputs: Vec<u8>,
+    /// The `Output` to put in the transaction, with its note encrypted
+    /// to the recipient
+    pub output: Vec<u8>,
+    /// The `MoneyNote` holding the random value and token blinds and the
+    /// coin serial, needed to balance the transaction
+    pub note: Vec<u8>,
+}
+
+/// Create a `Money::Transfer` mint proof for an output of `value` units
+/// of `token_id`, paid to `public_key`. Both are base58-encoded.
+#[wasm_bindgen]
+pub fn prove_mint(value: u64, token_id: &str, public_key: &str) -> Result<MintProof, JsError> {
+    let token_id = TokenId::from_str(token_id).map_err(|e| JsError::new(&e.to_string()))?;
+    let public_key = PublicKey::from_str(public_key).map_err(|e| JsError::new(&e.to_string()))?;
+
+    let zkbin = ZkBinary::decode(MINT_ZKBIN).map_err(|e| JsError::new(&e.to_string()))?;
+    let witnesses = empty_witnesses(&zkbin).map_err(|e| JsError::new(&e.to_string()))?;
+    let circuit = ZkCircuit::new(witnesses, &zkbin);
+    let pk = ProvingKey::build(zkbin.k, &circuit);
+
+    let output = TransactionBuilderOutputInfo { value, token_id, public_key, view_public: None };
+
+    let value_blind = pallas::Scalar::random(&mut OsRng);
+    let token_blind = pallas::Base::random(&mut OsRng);
+    let serial = pallas::Base::random(&mut OsRng);
+
+    let (proof, revealed) = create_transfer_mint_proof(
+        &zkbin,
+        &pk,
+        &output,
+        value_blind,
+        token_blind,
+        serial,
+        pallas::Base::ZERO,
+        pallas::Base::ZERO,
+    )
+    .map_err(|e| JsError::new(&e.to_string()))?;
+
+    let note = MoneyNote {
+        serial,
+        value,
+        token_id,
+        spend_hook: pallas::Base::ZERO,
+        user_data: pallas::Base::ZERO,
+        value_blind,
+        token_blind,
+        memo: vec![],
+    };
+
+    let encrypted_note = AeadEncryptedNote::encrypt(&note, &public_key, &mut OsRng)
+        .map_err(|e| JsError::new(&e.to_string()))?;
+
+    let money_output = Output {
+        value_commit: revealed.value_commit,
+        token_commit: revealed.token_commit,
+        coin: revealed.coin,
+        note: encrypted_note,
+    };
+
+    Ok(MintProof {
+        proof: serialize(&proof),
+        public_inputs: serialize(&revealed.to_vec()),
+        output: serialize(&money_output),
+        note: serialize(&note),
+    })
+}
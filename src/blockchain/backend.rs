@@ -0,0 +1,60 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::path::PathBuf;
+
+use crate::Result;
+
+/// Storage a [`super::Blockchain`] can be opened on.
+pub trait StorageBackend {
+    /// Open the database backing the blockchain.
+    fn open(&self) -> Result<sled::Db>;
+}
+
+/// Persistent `sled` database stored at the given path.
+/// This is the default backend of a node.
+#[derive(Clone, Debug)]
+pub struct SledBackend {
+    /// Path of the database directory
+    pub path: PathBuf,
+}
+
+impl SledBackend {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl StorageBackend for SledBackend {
+    fn open(&self) -> Result<sled::Db> {
+        Ok(sled::open(&self.path)?)
+    }
+}
+
+/// Ephemeral database, discarded once the last handle to it is dropped.
+/// On Linux it lives in shared memory, so no files have to be created or
+/// cleaned up, making it suitable for unit tests, fuzzers and simulation
+/// nodes.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MemoryBackend;
+
+impl StorageBackend for MemoryBackend {
+    fn open(&self) -> Result<sled::Db> {
+        Ok(sled::Config::new().temporary(true).open()?)
+    }
+}
@@ -21,7 +21,9 @@ use darkfi_sdk::{
     crypto::schnorr::Signature,
     pasta::{group::ff::Field, pallas},
 };
-use darkfi_serial::{async_trait, deserialize, serialize, SerialDecodable, SerialEncodable};
+use darkfi_serial::{
+    async_trait, deserialize, serialize, SerialDecodable, SerialEncodable, SerialSchema,
+};
 
 use crate::{tx::Transaction, Error, Result};
 
@@ -36,7 +38,7 @@ const BLOCK_MAGIC_BYTES: [u8; 4] = [0x11, 0x6d, 0x75, 0x1f];
 /// This struct represents a tuple of the form (`magic`, `header`, `txs`, `producer`, `slots`).
 /// The header and transactions are stored as hashes, while slots are stored as integers,
 /// serving as pointers to the actual data in the sled database.
-#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable, SerialSchema)]
 pub struct Block {
     /// Block magic bytes
     pub magic: [u8; 4],
@@ -68,7 +70,7 @@ impl Block {
 }
 
 /// Structure representing full block data.
-#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable, SerialSchema)]
 pub struct BlockInfo {
     /// Block magic bytes
     pub magic: [u8; 4],
@@ -445,6 +447,22 @@ impl BlockOrderStore {
         Ok(ret)
     }
 
+    /// Fetch the order number and hash of the block at given height, meaning
+    /// the `height`-th record of the tree, counting from 0. This walks the
+    /// tree, so its cost grows with the height.
+    pub fn get_by_height(&self, height: u64) -> Result<Option<(u64, blake3::Hash)>> {
+        match self.0.iter().nth(height as usize) {
+            Some(record) => Ok(Some(parse_u64_key_record(record?)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Retrieve the height of the block with given order number, meaning
+    /// the number of records preceding it in the tree.
+    pub fn height(&self, number: u64) -> u64 {
+        self.0.range(..number.to_be_bytes()).count() as u64
+    }
+
     /// Fetch the first block hash in the tree, based on the `Ord`
     /// implementation for `Vec<u8>`.
     pub fn get_first(&self) -> Result<(u64, blake3::Hash)> {
@@ -543,7 +561,7 @@ impl BlockOrderStoreOverlay {
 }
 
 /// This struct represents [`Block`] producer information.
-#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable, SerialSchema)]
 pub struct BlockProducer {
     /// Block producer signature
     pub signature: Signature,
@@ -19,7 +19,9 @@ r* This program is distributed in the hope that it will be useful,
 use std::io::Cursor;
 
 use darkfi_sdk::crypto::ContractId;
-use darkfi_serial::{deserialize, serialize};
+use darkfi_serial::{
+    async_trait, deserialize, serialize, SerialDecodable, SerialEncodable, SerialSchema,
+};
 use log::{debug, error};
 
 use crate::{
@@ -33,6 +35,17 @@ use super::SledDbOverlayPtr;
 
 const SLED_CONTRACTS_TREE: &[u8] = b"_contracts";
 const SLED_BINCODE_TREE: &[u8] = b"_wasm_bincode";
+const SLED_DEPLOYMENTS_TREE: &[u8] = b"_contract_deployments";
+
+/// Upgrade record of a non-native contract, written on each of its
+/// deployments. Every redeployment bumps its version.
+#[derive(Clone, Debug, PartialEq, Eq, SerialEncodable, SerialDecodable, SerialSchema)]
+pub struct DeploymentRecord {
+    /// Deployment version, starting at 1 and bumped on each upgrade
+    pub version: u32,
+    /// blake3 hash of the currently deployed wasm bincode
+    pub code_hash: [u8; 32],
+}
 
 /// The `WasmStore` is a `sled` tree that stores the wasm bincode for deployed
 /// contracts.
@@ -187,6 +200,25 @@ impl ContractStateStore {
         Ok(())
     }
 
+    /// Fetch the [`DeploymentRecord`] of a non-native contract, if it was
+    /// ever deployed. The records live in their own tree:
+    /// ```plaintext
+    ///  tree: "_contract_deployments"
+    ///   key: ContractId
+    /// value: DeploymentRecord
+    /// ```
+    pub fn get_deployment(
+        &self,
+        db: &sled::Db,
+        contract_id: &ContractId,
+    ) -> Result<Option<DeploymentRecord>> {
+        let tree = db.open_tree(SLED_DEPLOYMENTS_TREE)?;
+        match tree.get(serialize(contract_id))? {
+            Some(bytes) => Ok(Some(deserialize(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
     /// Abstraction function for fetching a `ZkBinary` and its respective `VerifyingKey`
     /// from a contract's zkas sled tree.
     pub fn get_zkas(
@@ -227,9 +259,33 @@ pub struct ContractStateStoreOverlay(SledDbOverlayPtr);
 impl ContractStateStoreOverlay {
     pub fn new(overlay: &SledDbOverlayPtr) -> Result<Self> {
         overlay.lock().unwrap().open_tree(SLED_CONTRACTS_TREE)?;
+        overlay.lock().unwrap().open_tree(SLED_DEPLOYMENTS_TREE)?;
         Ok(Self(overlay.clone()))
     }
 
+    /// Fetch the [`DeploymentRecord`] of a non-native contract, if it was
+    /// ever deployed.
+    pub fn get_deployment(&self, contract_id: &ContractId) -> Result<Option<DeploymentRecord>> {
+        match self.0.lock().unwrap().get(SLED_DEPLOYMENTS_TREE, &serialize(contract_id))? {
+            Some(bytes) => Ok(Some(deserialize(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Insert or replace the [`DeploymentRecord`] of a non-native contract.
+    pub fn insert_deployment(
+        &self,
+        contract_id: &ContractId,
+        record: &DeploymentRecord,
+    ) -> Result<()> {
+        self.0.lock().unwrap().insert(
+            SLED_DEPLOYMENTS_TREE,
+            &serialize(contract_id),
+            &serialize(record),
+        )?;
+        Ok(())
+    }
+
     /// Try to initialize a new contract state. Contracts can create a number
     /// of trees, separated by `tree_name`, which they can then use from the
     /// smart contract API. `init()` will look into the main `ContractStateStoreOverlay`
@@ -331,4 +387,22 @@ impl ContractStateStoreOverlay {
 
         Ok((zkbin, vk))
     }
+
+    /// Abstraction function for fetching just the `ZkBinary` from a contract's
+    /// zkas sled tree, skipping the more expensive `VerifyingKey` decoding.
+    pub fn get_zkbin(&self, contract_id: &ContractId, zkas_ns: &str) -> Result<ZkBinary> {
+        debug!(target: "blockchain::contractstore", "Looking up \"{}:{}\" zkas circuit", contract_id, zkas_ns);
+
+        let zkas_tree = self.lookup(contract_id, SMART_CONTRACT_ZKAS_DB_NAME)?;
+
+        let Some(zkas_bytes) = self.0.lock().unwrap().get(&zkas_tree, &serialize(&zkas_ns))? else {
+            return Err(Error::ZkasBincodeNotFound)
+        };
+
+        // If anything in this function panics, that means corrupted data managed
+        // to get into this sled tree. This should not be possible.
+        let (zkbin, _): (Vec<u8>, Vec<u8>) = deserialize(&zkas_bytes).unwrap();
+
+        Ok(ZkBinary::decode(&zkbin).unwrap())
+    }
 }
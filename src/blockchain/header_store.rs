@@ -17,14 +17,16 @@
  */
 
 use darkfi_sdk::crypto::{MerkleNode, MerkleTree};
-use darkfi_serial::{async_trait, deserialize, serialize, SerialDecodable, SerialEncodable};
+use darkfi_serial::{
+    async_trait, deserialize, serialize, SerialDecodable, SerialEncodable, SerialSchema,
+};
 
 use crate::{util::time::Timestamp, Error, Result};
 
 use super::{block_store::BLOCK_VERSION, parse_record, SledDbOverlayPtr};
 
 /// This struct represents a tuple of the form (version, previous, epoch, slot, timestamp, merkle_root).
-#[derive(Debug, Clone, PartialEq, Eq, SerialEncodable, SerialDecodable)]
+#[derive(Debug, Clone, PartialEq, Eq, SerialEncodable, SerialDecodable, SerialSchema)]
 pub struct Header {
     /// Block version
     pub version: u8,
@@ -0,0 +1,346 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Merkle Mountain Range over the canonical chain's block headers.
+//!
+//! Every block added to the blockchain appends its header hash as a new
+//! leaf, so the MMR of the first `size` blocks never changes once those
+//! blocks are final. This lets a node prove that a header is part of the
+//! canonical chain at any past size, using only the MMR root at that size.
+//!
+//! Nodes are numbered in post-order, the way they are appended, and the
+//! tree is keyed by the node position as a big-endian `u64`.
+
+use std::collections::BTreeMap;
+
+use darkfi_serial::{SerialDecodable, SerialEncodable};
+
+use crate::{Error, Result};
+
+use super::SledDbOverlayPtr;
+
+/// Header MMR sled tree
+const SLED_HEADER_MMR_TREE: &[u8] = b"_header_mmr";
+
+/// Domain separators so leaves, inner nodes and roots never collide
+const MMR_LEAF_PREFIX: u8 = 0;
+const MMR_NODE_PREFIX: u8 = 1;
+const MMR_ROOT_PREFIX: u8 = 2;
+
+/// Proof that a header is the leaf at `leaf_index` of the header MMR
+/// containing the first `size` blocks.
+#[derive(Debug, Clone, PartialEq, Eq, SerialEncodable, SerialDecodable)]
+pub struct MmrProof {
+    /// Index of the proven leaf, i.e. the block's height
+    pub leaf_index: u64,
+    /// Number of leaves in the MMR this proof was built against
+    pub size: u64,
+    /// Siblings on the way from the leaf up to its peak
+    pub siblings: Vec<blake3::Hash>,
+    /// All the MMR peaks at `size`, from left to right
+    pub peaks: Vec<blake3::Hash>,
+}
+
+impl MmrProof {
+    /// Verify that `headerhash` is the leaf at `self.leaf_index` of the
+    /// MMR with the given root.
+    pub fn verify(&self, root: &blake3::Hash, headerhash: &blake3::Hash) -> bool {
+        if self.leaf_index >= self.size || self.peaks.len() != self.size.count_ones() as usize {
+            return false
+        }
+
+        // Find the mountain holding our leaf
+        let mut first_leaf = 0;
+        for (peak, height) in self.peaks.iter().zip(mountain_heights(self.size)) {
+            let leaves = 1 << height;
+            if self.leaf_index >= first_leaf + leaves {
+                first_leaf += leaves;
+                continue
+            }
+
+            if self.siblings.len() != height as usize {
+                return false
+            }
+
+            // Climb up to the peak, the bits of the local leaf index
+            // telling on which side of its sibling each node lies.
+            let local_index = self.leaf_index - first_leaf;
+            let mut node = mmr_leaf(headerhash);
+            for (level, sibling) in self.siblings.iter().enumerate() {
+                node = if (local_index >> level) & 1 == 0 {
+                    mmr_merge(&node, sibling)
+                } else {
+                    mmr_merge(sibling, &node)
+                };
+            }
+
+            return node == *peak && mmr_bag_peaks(self.size, &self.peaks) == *root
+        }
+
+        false
+    }
+}
+
+/// Hash a header hash into an MMR leaf.
+pub fn mmr_leaf(headerhash: &blake3::Hash) -> blake3::Hash {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&[MMR_LEAF_PREFIX]);
+    hasher.update(headerhash.as_bytes());
+    hasher.finalize()
+}
+
+/// Hash two MMR nodes into their parent.
+pub fn mmr_merge(left: &blake3::Hash, right: &blake3::Hash) -> blake3::Hash {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&[MMR_NODE_PREFIX]);
+    hasher.update(left.as_bytes());
+    hasher.update(right.as_bytes());
+    hasher.finalize()
+}
+
+/// Commit to the peaks of an MMR with `size` leaves, producing its root.
+pub fn mmr_bag_peaks(size: u64, peaks: &[blake3::Hash]) -> blake3::Hash {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&[MMR_ROOT_PREFIX]);
+    hasher.update(&size.to_le_bytes());
+    for peak in peaks {
+        hasher.update(peak.as_bytes());
+    }
+    hasher.finalize()
+}
+
+/// Number of nodes in an MMR with `size` leaves.
+fn node_count(size: u64) -> u64 {
+    2 * size - size.count_ones() as u64
+}
+
+/// Heights of the mountains of an MMR with `size` leaves, from left to right.
+fn mountain_heights(size: u64) -> impl Iterator<Item = u32> {
+    (0..u64::BITS - 1).rev().filter(move |height| size & (1 << height) != 0)
+}
+
+/// Number of leaves in an MMR made of `nodes` nodes.
+fn leaf_count(mut nodes: u64) -> u64 {
+    let mut size = 0;
+    for height in (0..u64::BITS - 1).rev() {
+        let mountain_nodes = (2 << height) - 1;
+        if nodes >= mountain_nodes {
+            nodes -= mountain_nodes;
+            size += 1 << height;
+        }
+    }
+    size
+}
+
+/// Compute the nodes created by appending `headerhashes` to an MMR
+/// with `size` leaves, reading existing nodes through `get`.
+fn append_nodes(
+    size: u64,
+    headerhashes: &[blake3::Hash],
+    get: impl Fn(u64) -> Result<blake3::Hash>,
+) -> Result<BTreeMap<u64, blake3::Hash>> {
+    let mut nodes = BTreeMap::new();
+
+    for (i, headerhash) in headerhashes.iter().enumerate() {
+        let leaf_index = size + i as u64;
+        let mut pos = node_count(leaf_index);
+        let mut node = mmr_leaf(headerhash);
+        nodes.insert(pos, node);
+
+        // Every trailing one in the leaf index closes a mountain
+        for height in 0..leaf_index.trailing_ones() {
+            let left_pos = pos + 1 - (2 << height);
+            let left = match nodes.get(&left_pos) {
+                Some(left) => *left,
+                None => get(left_pos)?,
+            };
+            node = mmr_merge(&left, &node);
+            pos += 1;
+            nodes.insert(pos, node);
+        }
+    }
+
+    Ok(nodes)
+}
+
+/// Retrieve the peaks of the MMR with `size` leaves through `get`.
+fn peaks(size: u64, get: &impl Fn(u64) -> Result<blake3::Hash>) -> Result<Vec<blake3::Hash>> {
+    let mut peaks = vec![];
+    let mut offset = 0;
+    for height in mountain_heights(size) {
+        let mountain_nodes = (2 << height) - 1;
+        peaks.push(get(offset + mountain_nodes - 1)?);
+        offset += mountain_nodes;
+    }
+
+    Ok(peaks)
+}
+
+/// Build the proof for `leaf_index` in the MMR with `size` leaves.
+fn build_proof(
+    leaf_index: u64,
+    size: u64,
+    get: impl Fn(u64) -> Result<blake3::Hash>,
+) -> Result<MmrProof> {
+    if leaf_index >= size {
+        return Err(Error::MmrLeafOutOfRange(leaf_index, size))
+    }
+
+    let mut siblings = vec![];
+    let mut offset = 0;
+    let mut first_leaf = 0;
+    for height in mountain_heights(size) {
+        let leaves = 1 << height;
+        if leaf_index < first_leaf + leaves {
+            // Descend from the peak, collecting the sibling of each
+            // subtree we step into.
+            let mut local_index = leaf_index - first_leaf;
+            for level in (0..height).rev() {
+                let half = 1 << level;
+                let subtree_nodes = (2 << level) - 1;
+                if local_index < half {
+                    siblings.push(get(offset + 2 * subtree_nodes - 1)?);
+                } else {
+                    siblings.push(get(offset + subtree_nodes - 1)?);
+                    offset += subtree_nodes;
+                    local_index -= half;
+                }
+            }
+            siblings.reverse();
+            break
+        }
+
+        offset += (2 << height) - 1;
+        first_leaf += leaves;
+    }
+
+    Ok(MmrProof { leaf_index, size, siblings, peaks: peaks(size, &get)? })
+}
+
+/// The `HeaderMmrStore` is a `sled` tree storing the Merkle Mountain Range
+/// over all the blockchain's headers, where the key is the node position,
+/// and value is the node hash.
+#[derive(Clone)]
+pub struct HeaderMmrStore(pub sled::Tree);
+
+impl HeaderMmrStore {
+    /// Opens a new or existing `HeaderMmrStore` on the given sled database.
+    pub fn new(db: &sled::Db) -> Result<Self> {
+        let tree = db.open_tree(SLED_HEADER_MMR_TREE)?;
+        Ok(Self(tree))
+    }
+
+    /// Append a slice of header hashes into the store.
+    pub fn insert(&self, headerhashes: &[blake3::Hash]) -> Result<()> {
+        let batch = self.insert_batch(headerhashes)?;
+        self.0.apply_batch(batch)?;
+        Ok(())
+    }
+
+    /// Generate the sled batch corresponding to appending the given
+    /// header hashes, so caller can handle the write operation.
+    pub fn insert_batch(&self, headerhashes: &[blake3::Hash]) -> Result<sled::Batch> {
+        let nodes = append_nodes(self.size()?, headerhashes, |pos| self.get_node(pos))?;
+
+        let mut batch = sled::Batch::default();
+        for (pos, node) in nodes {
+            batch.insert(&pos.to_be_bytes(), node.as_bytes());
+        }
+
+        Ok(batch)
+    }
+
+    /// Retrieve the number of leaves in the MMR.
+    pub fn size(&self) -> Result<u64> {
+        match self.0.last()? {
+            Some((pos, _)) => Ok(leaf_count(parse_pos(&pos) + 1)),
+            None => Ok(0),
+        }
+    }
+
+    /// Retrieve the MMR root over the first `size` leaves.
+    pub fn get_root(&self, size: u64) -> Result<blake3::Hash> {
+        let current = self.size()?;
+        if size > current {
+            return Err(Error::MmrLeafOutOfRange(size, current))
+        }
+
+        Ok(mmr_bag_peaks(size, &peaks(size, &|pos| self.get_node(pos))?))
+    }
+
+    /// Build a proof that the leaf at `leaf_index` is part of the MMR
+    /// over the first `size` leaves.
+    pub fn get_proof(&self, leaf_index: u64, size: u64) -> Result<MmrProof> {
+        let current = self.size()?;
+        if size > current {
+            return Err(Error::MmrLeafOutOfRange(size, current))
+        }
+
+        build_proof(leaf_index, size, |pos| self.get_node(pos))
+    }
+
+    /// Fetch the node at the given position.
+    fn get_node(&self, pos: u64) -> Result<blake3::Hash> {
+        match self.0.get(pos.to_be_bytes())? {
+            Some(found) => Ok(parse_node(&found)),
+            None => Err(Error::MmrNodeNotFound(pos)),
+        }
+    }
+}
+
+/// Overlay structure over a [`HeaderMmrStore`] instance.
+pub struct HeaderMmrStoreOverlay(SledDbOverlayPtr);
+
+impl HeaderMmrStoreOverlay {
+    pub fn new(overlay: &SledDbOverlayPtr) -> Result<Self> {
+        overlay.lock().unwrap().open_tree(SLED_HEADER_MMR_TREE)?;
+        Ok(Self(overlay.clone()))
+    }
+
+    /// Append a slice of header hashes into the overlay.
+    pub fn insert(&self, headerhashes: &[blake3::Hash]) -> Result<()> {
+        let mut lock = self.0.lock().unwrap();
+
+        let size = match lock.last(SLED_HEADER_MMR_TREE)? {
+            Some((pos, _)) => leaf_count(parse_pos(&pos) + 1),
+            None => 0,
+        };
+
+        let nodes = append_nodes(size, headerhashes, |pos| {
+            match lock.get(SLED_HEADER_MMR_TREE, &pos.to_be_bytes())? {
+                Some(found) => Ok(parse_node(&found)),
+                None => Err(Error::MmrNodeNotFound(pos)),
+            }
+        })?;
+
+        for (pos, node) in nodes {
+            lock.insert(SLED_HEADER_MMR_TREE, &pos.to_be_bytes(), node.as_bytes())?;
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_pos(bytes: &[u8]) -> u64 {
+    u64::from_be_bytes(bytes.try_into().unwrap())
+}
+
+fn parse_node(bytes: &[u8]) -> blake3::Hash {
+    let bytes: [u8; 32] = bytes.try_into().unwrap();
+    blake3::Hash::from(bytes)
+}
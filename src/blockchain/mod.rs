@@ -24,7 +24,21 @@ use sled::Transactional;
 use darkfi_sdk::blockchain::Slot;
 use darkfi_serial::{deserialize, serialize, Decodable};
 
-use crate::{tx::Transaction, validator::consensus::next_block_reward, Error, Result};
+use crate::{
+    tx::Transaction,
+    util::slow_log::{SlowLog, SlowLogPtr},
+    validator::consensus::next_block_reward,
+    Error, Result,
+};
+
+/// Version of the serialization format of consensus-critical types.
+/// Must be bumped whenever the layout of any type covered by the
+/// `tests/serial_schema.rs` snapshot changes.
+pub const SERIAL_FORMAT_VERSION: u32 = 2;
+
+/// Serialized size cap of a block, in bytes. Blocks are relayed whole in
+/// P2P messages, whose payload limits are derived from it.
+pub const BLOCK_SIZE_CAP: u64 = 8 * 1024 * 1024;
 
 /// Block related definitions and storage implementations
 pub mod block_store;
@@ -48,9 +62,29 @@ pub use tx_store::{PendingTxOrderStore, PendingTxStore, TxStore, TxStoreOverlay}
 /// Contracts and Wasm storage implementations
 pub mod contract_store;
 pub use contract_store::{
-    ContractStateStore, ContractStateStoreOverlay, WasmStore, WasmStoreOverlay,
+    ContractStateStore, ContractStateStoreOverlay, DeploymentRecord, WasmStore, WasmStoreOverlay,
 };
 
+/// Header Merkle Mountain Range storage implementation
+pub mod mmr_store;
+pub use mmr_store::{HeaderMmrStore, HeaderMmrStoreOverlay, MmrProof};
+
+/// Sparse Merkle tree node storage implementation
+pub mod smt_store;
+pub use smt_store::SmtStore;
+
+/// Pluggable storage backends
+pub mod backend;
+pub use backend::{MemoryBackend, SledBackend, StorageBackend};
+
+/// Chain state snapshot export and import
+pub mod snapshot;
+pub use snapshot::SnapshotHeader;
+
+/// Contract state set export for auditors
+pub mod set_export;
+pub use set_export::{set_digest, SetExport};
+
 /// Structure holding all sled trees that define the concept of Blockchain.
 #[derive(Clone)]
 pub struct Blockchain {
@@ -74,9 +108,23 @@ pub struct Blockchain {
     pub contracts: ContractStateStore,
     /// Wasm bincodes
     pub wasm_bincode: WasmStore,
+    /// Merkle Mountain Range over block headers
+    pub mmr: HeaderMmrStore,
+    /// Log of sled operations exceeding the configured duration threshold
+    pub slow_log: SlowLogPtr,
 }
 
 impl Blockchain {
+    /// Instantiate a new `Blockchain` on a database opened by the given backend.
+    pub fn open(backend: &impl StorageBackend) -> Result<Self> {
+        Self::new(&backend.open()?)
+    }
+
+    /// Instantiate a new ephemeral `Blockchain`, using the [`MemoryBackend`].
+    pub fn in_memory() -> Result<Self> {
+        Self::open(&MemoryBackend)
+    }
+
     /// Instantiate a new `Blockchain` with the given `sled` database.
     pub fn new(db: &sled::Db) -> Result<Self> {
         let headers = HeaderStore::new(db)?;
@@ -88,6 +136,7 @@ impl Blockchain {
         let pending_txs_order = PendingTxOrderStore::new(db)?;
         let contracts = ContractStateStore::new(db)?;
         let wasm_bincode = WasmStore::new(db)?;
+        let mmr = HeaderMmrStore::new(db)?;
 
         Ok(Self {
             sled_db: db.clone(),
@@ -100,6 +149,8 @@ impl Blockchain {
             pending_txs_order,
             contracts,
             wasm_bincode,
+            mmr,
+            slow_log: SlowLog::new("sled"),
         })
     }
 
@@ -137,6 +188,11 @@ impl Blockchain {
         trees.push(self.headers.0.clone());
         batches.push(headers_batch);
 
+        // Append header to the MMR
+        let mmr_batch = self.mmr.insert_batch(&[block.header.headerhash()])?;
+        trees.push(self.mmr.0.clone());
+        batches.push(mmr_batch);
+
         // Store block
         let blk: Block = Block::from(block.clone());
         let (bocks_batch, block_hashes) = self.blocks.insert_batch(&[blk])?;
@@ -155,7 +211,7 @@ impl Blockchain {
         batches.push(slots_batch);
 
         // Perform an atomic transaction over the trees and apply the batches.
-        self.atomic_write(&trees, &batches)?;
+        self.atomic_write("add_block", &trees, &batches)?;
 
         Ok(block_hash)
     }
@@ -186,11 +242,11 @@ impl Blockchain {
 
     /// Retrieve [`BlockInfo`]s by given hashes. Fails if any of them is not found.
     pub fn get_blocks_by_hash(&self, hashes: &[blake3::Hash]) -> Result<Vec<BlockInfo>> {
-        let blocks = self.blocks.get(hashes, true)?;
-        let blocks: Vec<Block> = blocks.iter().map(|x| x.clone().unwrap()).collect();
-        let ret = self.get_blocks_infos(&blocks)?;
-
-        Ok(ret)
+        self.slow_log.time("get_blocks_by_hash", || {
+            let blocks = self.blocks.get(hashes, true)?;
+            let blocks: Vec<Block> = blocks.iter().map(|x| x.clone().unwrap()).collect();
+            self.get_blocks_infos(&blocks)
+        })
     }
 
     /// Retrieve all [`BlockInfo`] for given slice of [`Block`].
@@ -228,6 +284,19 @@ impl Blockchain {
         self.get_blocks_by_hash(&hashes)
     }
 
+    /// Retrieve the [`BlockInfo`] at given height, counting from the genesis
+    /// block at height 0. Returns `None` if the chain isn't that long.
+    pub fn get_block_by_height(&self, height: u64) -> Result<Option<BlockInfo>> {
+        let Some((_, hash)) = self.order.get_by_height(height)? else { return Ok(None) };
+        Ok(Some(self.get_blocks_by_hash(&[hash])?[0].clone()))
+    }
+
+    /// Retrieve the height of the given block, counting from the genesis
+    /// block at height 0.
+    pub fn block_height(&self, block: &BlockInfo) -> u64 {
+        self.order.height(block.header.slot)
+    }
+
     /// Retrieve n blocks after given start slot.
     pub fn get_blocks_after(&self, slot: u64, n: u64) -> Result<Vec<BlockInfo>> {
         debug!(target: "blockchain", "get_blocks_after(): {} -> {}", slot, n);
@@ -274,13 +343,13 @@ impl Blockchain {
     /// Retrieve n slots after given start slot.
     pub fn get_slots_after(&self, slot: u64, n: u64) -> Result<Vec<Slot>> {
         debug!(target: "blockchain", "get_slots_after(): {} -> {}", slot, n);
-        self.slots.get_after(slot, n)
+        self.slow_log.time("get_slots_after", || self.slots.get_after(slot, n))
     }
 
     /// Retrieve [`Slot`]s by given ids. Does not fail if any of them are not found.
     pub fn get_slots_by_id(&self, ids: &[u64]) -> Result<Vec<Option<Slot>>> {
         debug!(target: "blockchain", "get_slots_by_id(): {:?}", ids);
-        self.slots.get(ids, true)
+        self.slow_log.time("get_slots_by_id", || self.slots.get(ids, true))
     }
 
     /// Check if the given [`Slot`] is in the database and all trees.
@@ -307,7 +376,7 @@ impl Blockchain {
         // Perform an atomic transaction over the trees and apply the batches.
         let trees = [self.pending_txs.0.clone(), self.pending_txs_order.0.clone()];
         let batches = [txs_batch, txs_order_batch];
-        self.atomic_write(&trees, &batches)?;
+        self.atomic_write("add_pending_txs", &trees, &batches)?;
 
         Ok(txs_hashes)
     }
@@ -315,8 +384,9 @@ impl Blockchain {
     /// Retrieve all transactions from the pending tx store.
     /// Be careful as this will try to load everything in memory.
     pub fn get_pending_txs(&self) -> Result<Vec<Transaction>> {
-        let txs = self.pending_txs.get_all()?;
-        let indexes = self.pending_txs_order.get_all()?;
+        let (txs, indexes) = self.slow_log.time("get_pending_txs", || {
+            Ok::<_, Error>((self.pending_txs.get_all()?, self.pending_txs_order.get_all()?))
+        })?;
         if txs.len() != indexes.len() {
             return Err(Error::InvalidInputLengths)
         }
@@ -349,23 +419,31 @@ impl Blockchain {
         // Perform an atomic transaction over the trees and apply the batches.
         let trees = [self.pending_txs.0.clone(), self.pending_txs_order.0.clone()];
         let batches = [txs_batch, txs_order_batch];
-        self.atomic_write(&trees, &batches)?;
+        self.atomic_write("remove_pending_txs", &trees, &batches)?;
 
         Ok(())
     }
 
     /// Auxiliary function to write to multiple trees completely atomic.
-    fn atomic_write(&self, trees: &[sled::Tree], batches: &[sled::Batch]) -> Result<()> {
+    /// The write is timed in the slow log under the given `label`.
+    fn atomic_write(
+        &self,
+        label: &str,
+        trees: &[sled::Tree],
+        batches: &[sled::Batch],
+    ) -> Result<()> {
         if trees.len() != batches.len() {
             return Err(Error::InvalidInputLengths)
         }
 
-        trees.transaction(|trees| {
-            for (index, tree) in trees.iter().enumerate() {
-                tree.apply_batch(&batches[index])?;
-            }
+        self.slow_log.time(label, || {
+            trees.transaction(|trees| {
+                for (index, tree) in trees.iter().enumerate() {
+                    tree.apply_batch(&batches[index])?;
+                }
 
-            Ok::<(), sled::transaction::ConflictableTransactionError<sled::Error>>(())
+                Ok::<(), sled::transaction::ConflictableTransactionError<sled::Error>>(())
+            })
         })?;
 
         Ok(())
@@ -406,6 +484,8 @@ pub struct BlockchainOverlay {
     pub contracts: ContractStateStoreOverlay,
     /// Wasm bincodes overlay
     pub wasm_bincode: WasmStoreOverlay,
+    /// Header MMR overlay
+    pub mmr: HeaderMmrStoreOverlay,
 }
 
 impl BlockchainOverlay {
@@ -419,6 +499,7 @@ impl BlockchainOverlay {
         let transactions = TxStoreOverlay::new(&overlay)?;
         let contracts = ContractStateStoreOverlay::new(&overlay)?;
         let wasm_bincode = WasmStoreOverlay::new(&overlay)?;
+        let mmr = HeaderMmrStoreOverlay::new(&overlay)?;
 
         Ok(Arc::new(Mutex::new(Self {
             overlay,
@@ -429,6 +510,7 @@ impl BlockchainOverlay {
             transactions,
             contracts,
             wasm_bincode,
+            mmr,
         })))
     }
 
@@ -448,6 +530,28 @@ impl BlockchainOverlay {
         Ok(self.get_blocks_by_hash(&[hash])?[0].clone())
     }
 
+    /// Retrieve the number of transactions in each of the last `n` blocks,
+    /// following the chain back from the last block, most recent first.
+    pub fn recent_block_txs(&self, n: usize) -> Result<Vec<usize>> {
+        let mut ret = Vec::with_capacity(n);
+        if self.order.is_empty()? {
+            return Ok(ret)
+        }
+
+        let (_, mut hash) = self.last()?;
+        while ret.len() < n {
+            // Genesis points to a block we don't have
+            let Some(block) = self.blocks.get(&[hash], false)?[0].clone() else { break };
+            ret.push(block.txs.len());
+
+            let headers = self.headers.get(&[block.header], true)?;
+            // Since we used strict get, its safe to unwrap here
+            hash = headers[0].as_ref().unwrap().previous;
+        }
+
+        Ok(ret)
+    }
+
     /// Insert a given [`BlockInfo`] into the overlay.
     /// This functions wraps all the logic of separating the block into specific
     /// data that can be fed into the different trees of the overlay.
@@ -460,7 +564,10 @@ impl BlockchainOverlay {
         self.transactions.insert(&block.txs)?;
 
         // Store header
-        self.headers.insert(&[block.header.clone()])?;
+        let headerhash = self.headers.insert(&[block.header.clone()])?[0];
+
+        // Append header to the MMR
+        self.mmr.insert(&[headerhash])?;
 
         // Store block
         let blk: Block = Block::from(block.clone());
@@ -553,6 +660,7 @@ impl BlockchainOverlay {
         let transactions = TxStoreOverlay::new(&overlay)?;
         let contracts = ContractStateStoreOverlay::new(&overlay)?;
         let wasm_bincode = WasmStoreOverlay::new(&overlay)?;
+        let mmr = HeaderMmrStoreOverlay::new(&overlay)?;
 
         Ok(Arc::new(Mutex::new(Self {
             overlay,
@@ -563,6 +671,7 @@ impl BlockchainOverlay {
             transactions,
             contracts,
             wasm_bincode,
+            mmr,
         })))
     }
 }
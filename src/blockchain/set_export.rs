@@ -0,0 +1,97 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_sdk::{
+    crypto::{
+        smt::{MemoryStorageFp, SparseMerkleTreeFp},
+        ContractId,
+    },
+    pasta::pallas,
+};
+use darkfi_serial::{deserialize, SerialDecodable, SerialEncodable};
+
+use super::Blockchain;
+use crate::{Error, Result};
+
+/// Export of the set of keys held in a contract state tree, such as the
+/// `Money` nullifiers or coins, for auditors to verify e.g. the total supply.
+/// The export carries the root of a sparse Merkle tree holding each element
+/// as both key and value, so any element can be proven in or out of this
+/// export against its digest.
+/// NOTE: Block headers don't carry a state root yet, and the coins tree
+/// doesn't keep the insertion order the on-chain coin Merkle root is built
+/// over, so nothing on chain commits to the export. Auditors have to trust
+/// the node they got it from for its contents.
+#[derive(Clone, Debug, PartialEq, Eq, SerialEncodable, SerialDecodable)]
+pub struct SetExport {
+    /// Contract owning the exported state tree
+    pub contract_id: ContractId,
+    /// Name of the exported state tree
+    pub tree_name: String,
+    /// Height of the last block when the set was exported
+    pub height: u64,
+    /// Hash of the last block when the set was exported
+    pub block: blake3::Hash,
+    /// Elements of the set, in ascending order of their encoding
+    pub elements: Vec<pallas::Base>,
+    /// Sparse Merkle tree root over the elements
+    pub digest: pallas::Base,
+}
+
+impl Blockchain {
+    /// Export the set of keys of the given contract state tree, along with
+    /// its digest. Every key must be an encoded `pallas::Base`, as is the
+    /// case for nullifiers and coins.
+    /// The last block and the tree are read one after the other, so the
+    /// caller has to make sure no block gets applied in between, e.g. by
+    /// holding the validator lock.
+    /// Be careful as this will load the whole set in memory.
+    pub fn export_set(&self, contract_id: &ContractId, tree_name: &str) -> Result<SetExport> {
+        if self.is_empty() {
+            return Err(Error::SetExportInvalid("Blockchain is empty".to_string()))
+        }
+
+        let height = self.len() as u64 - 1;
+        let (_, block) = self.last()?;
+
+        let tree = self.contracts.lookup(&self.sled_db, contract_id, tree_name)?;
+        let mut elements = Vec::with_capacity(tree.len());
+        for key in tree.iter().keys() {
+            elements.push(deserialize(&key?)?);
+        }
+
+        let digest = set_digest(&elements)?;
+
+        Ok(SetExport {
+            contract_id: *contract_id,
+            tree_name: tree_name.to_string(),
+            height,
+            block,
+            elements,
+            digest,
+        })
+    }
+}
+
+/// Compute the sparse Merkle tree root over the given set elements.
+pub fn set_digest(elements: &[pallas::Base]) -> Result<pallas::Base> {
+    let mut smt = SparseMerkleTreeFp::new(MemoryStorageFp::new());
+    let leaves: Vec<(pallas::Base, pallas::Base)> = elements.iter().map(|x| (*x, *x)).collect();
+    smt.insert_batch(&leaves)?;
+    Ok(smt.root()?)
+}
@@ -0,0 +1,70 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_sdk::{
+    crypto::smt::StorageAdapter,
+    error::{ContractError, GenericResult},
+    num_bigint::BigUint,
+    pasta::pallas,
+};
+use darkfi_serial::{deserialize, serialize};
+
+use crate::Result;
+
+/// The `SmtStore` is a `sled` tree holding the nodes of a
+/// [`SparseMerkleTreeFp`](darkfi_sdk::crypto::smt::SparseMerkleTreeFp),
+/// where the key is the node's little-endian heap index, and value is
+/// the serialized node.
+#[derive(Clone)]
+pub struct SmtStore(pub sled::Tree);
+
+impl SmtStore {
+    /// Opens a new or existing `SmtStore` under the given tree name
+    /// on the given sled database.
+    pub fn new(db: &sled::Db, tree_name: &[u8]) -> Result<Self> {
+        let tree = db.open_tree(tree_name)?;
+        Ok(Self(tree))
+    }
+}
+
+impl StorageAdapter for SmtStore {
+    fn put(&mut self, key: &BigUint, value: pallas::Base) -> GenericResult<()> {
+        if self.0.insert(key.to_bytes_le(), serialize(&value)).is_err() {
+            return Err(ContractError::DbSetFailed)
+        }
+        Ok(())
+    }
+
+    fn get(&self, key: &BigUint) -> GenericResult<Option<pallas::Base>> {
+        let Ok(value) = self.0.get(key.to_bytes_le()) else {
+            return Err(ContractError::DbGetFailed)
+        };
+
+        match value {
+            Some(bytes) => Ok(Some(deserialize(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn del(&mut self, key: &BigUint) -> GenericResult<()> {
+        if self.0.remove(key.to_bytes_le()).is_err() {
+            return Err(ContractError::DbDelFailed)
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,165 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{fs, io::Cursor, path::Path};
+
+use darkfi_serial::{
+    async_trait, deserialize, serialize, Decodable, SerialDecodable, SerialEncodable,
+};
+use log::info;
+
+use super::{Blockchain, SERIAL_FORMAT_VERSION};
+use crate::{Error, Result};
+
+/// Header of a blockchain snapshot archive, identifying the chain state
+/// it holds and committing to its contents.
+#[derive(Clone, Debug, PartialEq, Eq, SerialEncodable, SerialDecodable)]
+pub struct SnapshotHeader {
+    /// Serialization format version the snapshot was written with
+    pub version: u32,
+    /// Height of the last block included in the snapshot
+    pub height: u64,
+    /// Hash of the last block included in the snapshot
+    pub block: blake3::Hash,
+    /// BLAKE3 hash of the serialized snapshot trees
+    pub content_hash: blake3::Hash,
+}
+
+/// Contents of a single sled tree: its name and all its records
+type SnapshotTree = (Vec<u8>, Vec<(Vec<u8>, Vec<u8>)>);
+
+impl Blockchain {
+    /// Names of the trees that hold node-local data rather than chain
+    /// state, and are therefore excluded from snapshots.
+    fn snapshot_excluded_trees(&self) -> [sled::IVec; 2] {
+        [self.pending_txs.0.name(), self.pending_txs_order.0.name()]
+    }
+
+    /// Export the current chain state into a snapshot archive at `path`.
+    /// The archive contains every sled tree of the database, including
+    /// all contract states, their Merkle trees and the header MMR, at the
+    /// height of the last block. Pending transactions are not included.
+    /// Be careful as this will load the whole database in memory.
+    pub fn export_snapshot(&self, path: &Path) -> Result<SnapshotHeader> {
+        if self.is_empty() {
+            return Err(Error::SnapshotInvalid("Blockchain is empty".to_string()))
+        }
+
+        let height = self.len() as u64 - 1;
+        let (_, block) = self.last()?;
+
+        let excluded = self.snapshot_excluded_trees();
+        let mut names = self.sled_db.tree_names();
+        names.retain(|name| !excluded.contains(name));
+        names.sort();
+
+        let mut trees: Vec<SnapshotTree> = Vec::with_capacity(names.len());
+        for name in names {
+            let tree = self.sled_db.open_tree(&name)?;
+            let mut records = vec![];
+            for record in tree.iter() {
+                let (key, value) = record?;
+                records.push((key.to_vec(), value.to_vec()));
+            }
+            trees.push((name.to_vec(), records));
+        }
+
+        let content = serialize(&trees);
+        let header = SnapshotHeader {
+            version: SERIAL_FORMAT_VERSION,
+            height,
+            block,
+            content_hash: blake3::hash(&content),
+        };
+
+        let mut archive = serialize(&header);
+        archive.extend_from_slice(&content);
+        fs::write(path, archive)?;
+
+        info!(
+            target: "blockchain::snapshot",
+            "Exported snapshot of block {} ({}) to {}", height, block, path.display(),
+        );
+
+        Ok(header)
+    }
+
+    /// Import the snapshot archive at `path` into this blockchain's
+    /// database. The archive contents are verified against the hash in
+    /// its header, and the database must not hold any of the snapshot
+    /// trees' records yet.
+    pub fn import_snapshot(&self, path: &Path) -> Result<SnapshotHeader> {
+        let archive = fs::read(path)?;
+        let mut cursor = Cursor::new(&archive[..]);
+        let header = SnapshotHeader::decode(&mut cursor)?;
+
+        if header.version != SERIAL_FORMAT_VERSION {
+            return Err(Error::SnapshotInvalid(format!(
+                "Unsupported serialization format version {}",
+                header.version
+            )))
+        }
+
+        let content = &archive[cursor.position() as usize..];
+        if blake3::hash(content) != header.content_hash {
+            return Err(Error::SnapshotInvalid("Content hash mismatch".to_string()))
+        }
+
+        let trees: Vec<SnapshotTree> = deserialize(content)?;
+
+        // Make sure we don't mix the snapshot with existing state
+        let excluded = self.snapshot_excluded_trees();
+        for (name, _) in &trees {
+            if excluded.iter().any(|x| x.as_ref() == name.as_slice()) {
+                return Err(Error::SnapshotInvalid("Snapshot contains pending txs".to_string()))
+            }
+
+            if !self.sled_db.open_tree(name)?.is_empty() {
+                return Err(Error::SnapshotInvalid(format!(
+                    "Tree {} is not empty",
+                    String::from_utf8_lossy(name)
+                )))
+            }
+        }
+
+        for (name, records) in trees {
+            let tree = self.sled_db.open_tree(name)?;
+            let mut batch = sled::Batch::default();
+            for (key, value) in records {
+                batch.insert(key, value);
+            }
+            tree.apply_batch(batch)?;
+        }
+        self.sled_db.flush()?;
+
+        // The imported chain must end at the block the header claims
+        if self.is_empty() ||
+            self.len() as u64 - 1 != header.height ||
+            self.last()?.1 != header.block
+        {
+            return Err(Error::SnapshotInvalid("Last block mismatch".to_string()))
+        }
+
+        info!(
+            target: "blockchain::snapshot",
+            "Imported snapshot of block {} ({}) from {}", header.height, header.block, path.display(),
+        );
+
+        Ok(header)
+    }
+}
@@ -35,6 +35,10 @@ lazy_static! {
     /// Total sum of initial staking coins for the mainnet chain
     pub static ref MAINNET_INITIAL_DISTRIBUTION: u64 = 0;
 
+    /// Finalized (slot, block hash) pairs of the mainnet chain, which
+    /// synced headers must match. Extended on each release.
+    pub static ref MAINNET_CHECKPOINTS: Vec<(u64, blake3::Hash)> = vec![];
+
     /// Genesis hash for the testnet chain
     pub static ref TESTNET_GENESIS_HASH_BYTES: blake3::Hash = blake3::hash(b"darkfi_testnet");
 
@@ -47,6 +51,10 @@ lazy_static! {
     /// Total sum of initial staking coins for the testnet chain
     pub static ref TESTNET_INITIAL_DISTRIBUTION: u64 = 1000;
 
+    /// Finalized (slot, block hash) pairs of the testnet chain, which
+    /// synced headers must match. Extended on each release.
+    pub static ref TESTNET_CHECKPOINTS: Vec<(u64, blake3::Hash)> = vec![];
+
     // Commonly used Float10
     pub static ref FLOAT10_EPSILON: Float10 = Float10::try_from("1").unwrap();
     pub static ref FLOAT10_NEG_TWO: Float10 = Float10::try_from("-2").unwrap();
@@ -95,6 +103,12 @@ pub const SYNC_RETRIES_DURATION: u64 = 2;
 /// Max resync retries
 pub const SYNC_MAX_RETRIES: u64 = 10;
 
+/// Number of headers or blocks requested at once during block sync
+pub const SYNC_BATCH: u64 = 20;
+
+/// Block sync request timeout in seconds
+pub const SYNC_REQUEST_TIMEOUT: u64 = 30;
+
 /// Transactions included in a block cap
 pub const TXS_CAP: usize = 50;
 
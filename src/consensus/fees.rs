@@ -18,6 +18,129 @@
 
 use crate::zkas::{Opcode, VarType, ZkBinary};
 
+/// Gas charged for verifying a single Schnorr signature, in WASM gas units
+pub const SIGNATURE_GAS: u64 = 50_000;
+
+/// Multiplier converting zkas circuit gas into WASM gas units,
+/// since verifying a ZK proof is far heavier than executing WASM.
+pub const CIRCUIT_GAS_MULTIPLIER: u64 = 1_000;
+
+/// Gas charged for each byte of contract bincode stored on-chain
+pub const STORAGE_GAS_PER_BYTE: u64 = 100;
+
+/// Amount of gas paid for by a single unit of fee, at the base gas price
+pub const GAS_PER_FEE_UNIT: u64 = 100;
+
+/// Gas price charged when blocks aren't congested, in thousandths
+/// of the fee unit paid for [`GAS_PER_FEE_UNIT`] gas
+pub const BASE_GAS_PRICE: u64 = 1_000;
+
+/// Upper bound of the gas price, however congested the network is
+pub const MAX_GAS_PRICE: u64 = 100 * BASE_GAS_PRICE;
+
+/// Number of most recent blocks the gas price is derived from
+pub const FEE_WINDOW: usize = 10;
+
+/// Average number of transactions per block above which the gas price rises
+pub const TARGET_BLOCK_TXS: u64 = 50;
+
+/// Confirmation targets, in blocks, fee estimates are given for
+pub const FEE_CONFIRMATION_TARGETS: [u64; 3] = [1, 3, 10];
+
+/// Gas used by a transaction, in WASM gas units, split by what it was spent on.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub struct GasData {
+    /// Gas used executing the contract calls
+    pub wasm: u64,
+    /// Gas charged for verifying the signatures
+    pub signatures: u64,
+    /// Gas charged for verifying the ZK proofs
+    pub zk_circuits: u64,
+    /// Gas charged for storing deployed contract bincodes
+    pub storage: u64,
+}
+
+impl GasData {
+    /// Total gas used by the transaction
+    pub fn total(&self) -> u64 {
+        self.wasm
+            .saturating_add(self.signatures)
+            .saturating_add(self.zk_circuits)
+            .saturating_add(self.storage)
+    }
+}
+
+/// Congestion-aware gas pricing. The gas price stays at [`BASE_GAS_PRICE`]
+/// while recent blocks carry at most [`TARGET_BLOCK_TXS`] transactions on
+/// average, and rises proportionally to their load above that.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct FeeModel {
+    /// Current gas price, in thousandths of a fee unit per [`GAS_PER_FEE_UNIT`] gas
+    pub gas_price: u64,
+}
+
+impl Default for FeeModel {
+    fn default() -> Self {
+        Self { gas_price: BASE_GAS_PRICE }
+    }
+}
+
+impl FeeModel {
+    /// Derive the gas price from the number of transactions in recent blocks.
+    pub fn from_block_txs(block_txs: &[usize]) -> Self {
+        if block_txs.is_empty() {
+            return Self::default()
+        }
+
+        let total: u128 = block_txs.iter().map(|n| *n as u128).sum();
+        let target = block_txs.len() as u128 * TARGET_BLOCK_TXS as u128;
+        Self { gas_price: scale_price(BASE_GAS_PRICE, total, target) }
+    }
+
+    /// Calculate the minimum fee a transaction has to pay for the given gas use.
+    pub fn min_fee(&self, gas: &GasData) -> u64 {
+        let fee = (gas.total() as u128 * self.gas_price as u128)
+            .div_ceil(GAS_PER_FEE_UNIT as u128 * BASE_GAS_PRICE as u128);
+        fee.min(u64::MAX as u128) as u64
+    }
+
+    /// Suggest a gas price for a transaction to get included within `target`
+    /// blocks, while `pending` transactions wait in the mempool ahead of it.
+    /// If the backlog doesn't fit in the target blocks at the target load,
+    /// the price rises proportionally to the overflow.
+    pub fn suggest(&self, pending: usize, target: u64) -> Self {
+        let target = target.max(1) as u128 * TARGET_BLOCK_TXS as u128;
+        Self { gas_price: scale_price(self.gas_price, pending as u128, target) }
+    }
+}
+
+/// Scale `price` by `load / target` when the load exceeds the target,
+/// keeping it within [`MAX_GAS_PRICE`].
+fn scale_price(price: u64, load: u128, target: u128) -> u64 {
+    if load <= target {
+        return price
+    }
+
+    let scaled = price as u128 * load / target;
+    scaled.min(MAX_GAS_PRICE as u128).max(price as u128) as u64
+}
+
+/// Calculate the fee a transaction has to pay for the given gas use,
+/// at the base gas price.
+pub fn compute_fee(gas: &GasData) -> u64 {
+    FeeModel::default().min_fee(gas)
+}
+
+/// Calculate the gas use for verifying the given number of signatures.
+pub fn signatures_gas_use(signatures: usize) -> u64 {
+    SIGNATURE_GAS.saturating_mul(signatures as u64)
+}
+
+/// Calculate the gas use for storing the given number of bytes on-chain.
+pub fn storage_gas_use(bytes: usize) -> u64 {
+    STORAGE_GAS_PER_BYTE.saturating_mul(bytes as u64)
+}
+
 /// Calculate the gas use for verifying a given zkas circuit.
 /// This function assumes that the zkbin was properly decoded.
 pub fn circuit_gas_use(zkbin: &ZkBinary) -> u64 {
@@ -85,3 +208,58 @@ pub fn circuit_gas_use(zkbin: &ZkBinary) -> u64 {
 
     accumulator
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fee_rounds_gas_up() {
+        assert_eq!(compute_fee(&GasData::default()), 0);
+
+        let gas = GasData { wasm: 1, ..Default::default() };
+        assert_eq!(compute_fee(&gas), 1);
+
+        let gas = GasData {
+            wasm: 150,
+            signatures: signatures_gas_use(2),
+            zk_circuits: 50,
+            storage: storage_gas_use(10),
+        };
+        assert_eq!(gas.total(), 101_200);
+        assert_eq!(compute_fee(&gas), 1_012);
+
+        let gas = GasData { wasm: u64::MAX, signatures: 1, zk_circuits: 1, storage: 1 };
+        assert_eq!(gas.total(), u64::MAX);
+    }
+
+    #[test]
+    fn fee_model_follows_congestion() {
+        let gas = GasData { wasm: 100_000, ..Default::default() };
+
+        // Blocks at or below target keep the base price
+        let model = FeeModel::from_block_txs(&[]);
+        assert_eq!(model, FeeModel::default());
+        let model = FeeModel::from_block_txs(&[10, TARGET_BLOCK_TXS as usize, 0]);
+        assert_eq!(model.gas_price, BASE_GAS_PRICE);
+        assert_eq!(model.min_fee(&gas), compute_fee(&gas));
+
+        // Twice the target load doubles the price
+        let full = 2 * TARGET_BLOCK_TXS as usize;
+        let model = FeeModel::from_block_txs(&[full, full]);
+        assert_eq!(model.gas_price, 2 * BASE_GAS_PRICE);
+        assert_eq!(model.min_fee(&gas), 2 * compute_fee(&gas));
+
+        // The price is capped
+        let model = FeeModel::from_block_txs(&[usize::MAX]);
+        assert_eq!(model.gas_price, MAX_GAS_PRICE);
+
+        // A deep mempool raises the price for short targets only
+        let model = FeeModel::default();
+        let pending = 3 * TARGET_BLOCK_TXS as usize;
+        assert_eq!(model.suggest(pending, 1).gas_price, 3 * BASE_GAS_PRICE);
+        assert_eq!(model.suggest(pending, 3).gas_price, BASE_GAS_PRICE);
+        assert_eq!(model.suggest(pending, 10).gas_price, BASE_GAS_PRICE);
+        assert_eq!(model.suggest(0, 0).gas_price, BASE_GAS_PRICE);
+    }
+}
@@ -47,6 +47,9 @@ pub mod proto;
 /// async tasks to utilize the protocols
 pub mod task;
 
+/// Checkpointed block sync messages and verification rules
+pub mod sync;
+
 /// Lamport clock
 pub mod clock;
 pub use clock::{Clock, Ticks};
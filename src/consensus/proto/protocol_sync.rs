@@ -27,7 +27,11 @@ use darkfi_sdk::blockchain::Slot;
 use crate::{
     consensus::{
         block::{BlockInfo, BlockOrder, BlockResponse},
+        constants::SYNC_BATCH,
         state::{SlotRequest, SlotResponse},
+        sync::{
+            BlockSyncRequest, BlockSyncResponse, HeaderSyncRequest, HeaderSyncResponse, SyncHeader,
+        },
         ValidatorStatePtr,
     },
     net::{
@@ -46,6 +50,8 @@ pub struct ProtocolSync {
     slot_request_sub: MessageSubscription<SlotRequest>,
     block_sub: MessageSubscription<BlockInfo>,
     slots_sub: MessageSubscription<Slot>,
+    header_request_sub: MessageSubscription<HeaderSyncRequest>,
+    block_request_sub: MessageSubscription<BlockSyncRequest>,
     jobsman: ProtocolJobsManagerPtr,
     state: ValidatorStatePtr,
     p2p: P2pPtr,
@@ -64,11 +70,15 @@ impl ProtocolSync {
         msg_subsystem.add_dispatch::<SlotRequest>().await;
         msg_subsystem.add_dispatch::<BlockInfo>().await;
         msg_subsystem.add_dispatch::<Slot>().await;
+        msg_subsystem.add_dispatch::<HeaderSyncRequest>().await;
+        msg_subsystem.add_dispatch::<BlockSyncRequest>().await;
 
         let request_sub = channel.subscribe_msg::<BlockOrder>().await?;
         let slot_request_sub = channel.subscribe_msg::<SlotRequest>().await?;
         let block_sub = channel.subscribe_msg::<BlockInfo>().await?;
         let slots_sub = channel.subscribe_msg::<Slot>().await?;
+        let header_request_sub = channel.subscribe_msg::<HeaderSyncRequest>().await?;
+        let block_request_sub = channel.subscribe_msg::<BlockSyncRequest>().await?;
 
         Ok(Arc::new(Self {
             channel: channel.clone(),
@@ -76,6 +86,8 @@ impl ProtocolSync {
             slot_request_sub,
             block_sub,
             slots_sub,
+            header_request_sub,
+            block_request_sub,
             jobsman: ProtocolJobsManager::new("SyncProtocol", channel),
             state,
             p2p,
@@ -140,6 +152,118 @@ impl ProtocolSync {
         }
     }
 
+    async fn handle_receive_header_request(self: Arc<Self>) -> Result<()> {
+        debug!(
+            target: "consensus::protocol_sync::handle_receive_header_request()",
+            "START"
+        );
+        loop {
+            let request = match self.header_request_sub.receive().await {
+                Ok(v) => v,
+                Err(e) => {
+                    debug!(
+                        target: "consensus::protocol_sync::handle_receive_header_request()",
+                        "recv fail: {}",
+                        e
+                    );
+                    continue
+                }
+            };
+
+            debug!(
+                target: "consensus::protocol_sync::handle_receive_header_request()",
+                "received {:?}",
+                request
+            );
+
+            let blocks =
+                match self.state.read().await.blockchain.get_blocks_after(request.slot, SYNC_BATCH)
+                {
+                    Ok(v) => v,
+                    Err(e) => {
+                        error!(
+                            target: "consensus::protocol_sync::handle_receive_header_request()",
+                            "get_blocks_after fail: {}",
+                            e
+                        );
+                        continue
+                    }
+                };
+
+            let headers = blocks
+                .iter()
+                .map(|block| SyncHeader { hash: block.blockhash(), header: block.header.clone() })
+                .collect();
+
+            let response = HeaderSyncResponse { headers };
+            if let Err(e) = self.channel.send(&response).await {
+                error!(
+                    target: "consensus::protocol_sync::handle_receive_header_request()",
+                    "channel send fail: {}",
+                    e
+                )
+            };
+        }
+    }
+
+    async fn handle_receive_block_request(self: Arc<Self>) -> Result<()> {
+        debug!(
+            target: "consensus::protocol_sync::handle_receive_block_request()",
+            "START"
+        );
+        loop {
+            let request = match self.block_request_sub.receive().await {
+                Ok(v) => v,
+                Err(e) => {
+                    debug!(
+                        target: "consensus::protocol_sync::handle_receive_block_request()",
+                        "recv fail: {}",
+                        e
+                    );
+                    continue
+                }
+            };
+
+            debug!(
+                target: "consensus::protocol_sync::handle_receive_block_request()",
+                "received request for {} blocks",
+                request.hashes.len()
+            );
+
+            if request.hashes.len() as u64 > SYNC_BATCH {
+                debug!(
+                    target: "consensus::protocol_sync::handle_receive_block_request()",
+                    "Request exceeds batch size, skipping..."
+                );
+                continue
+            }
+
+            // Unknown hashes fail the lookup, in which case the requesting
+            // node times out and asks another peer.
+            let blocks =
+                match self.state.read().await.blockchain.get_blocks_by_hash(&request.hashes) {
+                    Ok(v) => v,
+                    Err(e) => {
+                        error!(
+                            target: "consensus::protocol_sync::handle_receive_block_request()",
+                            "get_blocks_by_hash fail: {}",
+                            e
+                        );
+                        continue
+                    }
+                };
+
+            let response = BlockSyncResponse { blocks };
+            if let Err(e) = self.channel.send(&response).await {
+                error!(
+                    target: "consensus::protocol_sync::handle_receive_block_request()",
+                    "channel send fail: {}",
+                    e
+                )
+            };
+        }
+    }
+
     async fn handle_receive_block(self: Arc<Self>) -> Result<()> {
         debug!(target: "consensus::protocol_sync::handle_receive_block()", "START");
         let _exclude_list = [self.channel.address()];
@@ -364,6 +488,14 @@ impl ProtocolBase for ProtocolSync {
             .await;
         self.jobsman.clone().spawn(self.clone().handle_receive_block(), executor.clone()).await;
         self.jobsman.clone().spawn(self.clone().handle_receive_slot(), executor.clone()).await;
+        self.jobsman
+            .clone()
+            .spawn(self.clone().handle_receive_header_request(), executor.clone())
+            .await;
+        self.jobsman
+            .clone()
+            .spawn(self.clone().handle_receive_block_request(), executor.clone())
+            .await;
         debug!(target: "consensus::protocol_sync::start()", "END");
         Ok(())
     }
@@ -0,0 +1,222 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Messages and verification rules of the checkpointed block sync.
+//!
+//! A syncing node first requests the headers of the canonical blocks
+//! following its last block, checking they extend its chain and match the
+//! hardcoded checkpoints. Afterwards, the block bodies are requested in
+//! batches from multiple peers and checked against the verified headers
+//! before they get applied.
+
+use darkfi_serial::{async_trait, SerialDecodable, SerialEncodable};
+
+use super::constants::SYNC_BATCH;
+use crate::{
+    blockchain::{BlockInfo, Header, BLOCK_SIZE_CAP},
+    impl_p2p_message,
+    net::Message,
+    Error, Result,
+};
+
+/// Request for the headers of the canonical blocks following the given slot.
+#[derive(Debug, SerialEncodable, SerialDecodable)]
+pub struct HeaderSyncRequest {
+    /// Slot of the last block the requesting node knows
+    pub slot: u64,
+}
+
+impl_p2p_message!(HeaderSyncRequest, "headersyncrequest");
+
+/// A canonical block's hash along with its header.
+#[derive(Debug, Clone, PartialEq, Eq, SerialEncodable, SerialDecodable)]
+pub struct SyncHeader {
+    /// Block hash
+    pub hash: blake3::Hash,
+    /// Block header
+    pub header: Header,
+}
+
+/// Response to a [`HeaderSyncRequest`]. Empty if the requesting node is
+/// already at the tip of the responding node's chain.
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct HeaderSyncResponse {
+    /// Headers following the requested slot, in order
+    pub headers: Vec<SyncHeader>,
+}
+
+impl_p2p_message!(HeaderSyncResponse, "headersyncresponse");
+
+/// Request for the full blocks of the given block hashes.
+#[derive(Debug, SerialEncodable, SerialDecodable)]
+pub struct BlockSyncRequest {
+    /// Requested block hashes
+    pub hashes: Vec<blake3::Hash>,
+}
+
+impl_p2p_message!(BlockSyncRequest, "blocksyncrequest");
+
+/// Response to a [`BlockSyncRequest`].
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct BlockSyncResponse {
+    /// Requested blocks, in the order they were requested
+    pub blocks: Vec<BlockInfo>,
+}
+
+// A full batch of blocks, plus the length prefix
+impl_p2p_message!(BlockSyncResponse, "blocksyncresponse", 9 + SYNC_BATCH * BLOCK_SIZE_CAP);
+
+/// Verify the given headers form a chain extending the block with the given
+/// slot and hash, and that every checkpoint they pass is matched. A chain
+/// skipping over a checkpointed slot is rejected.
+pub fn verify_header_chain(
+    last: (u64, blake3::Hash),
+    headers: &[SyncHeader],
+    checkpoints: &[(u64, blake3::Hash)],
+) -> Result<()> {
+    let (mut slot, mut hash) = last;
+
+    for header in headers {
+        if header.header.previous != hash || header.header.slot <= slot {
+            return Err(Error::BlockIsInvalid(header.hash.to_string()))
+        }
+
+        for (checkpoint_slot, checkpoint_hash) in checkpoints {
+            if *checkpoint_slot <= slot || *checkpoint_slot > header.header.slot {
+                continue
+            }
+
+            if *checkpoint_slot != header.header.slot || *checkpoint_hash != header.hash {
+                return Err(Error::CheckpointInvalid(format!(
+                    "Expected block {} at slot {}",
+                    checkpoint_hash, checkpoint_slot
+                )))
+            }
+        }
+
+        slot = header.header.slot;
+        hash = header.hash;
+    }
+
+    Ok(())
+}
+
+/// Verify the given blocks are the ones described by the given headers.
+pub fn verify_block_bodies(headers: &[SyncHeader], blocks: &[BlockInfo]) -> Result<()> {
+    if headers.len() != blocks.len() {
+        return Err(Error::BlockIsInvalid(format!(
+            "Expected {} blocks, got {}",
+            headers.len(),
+            blocks.len()
+        )))
+    }
+
+    for (header, block) in headers.iter().zip(blocks) {
+        if block.header != header.header || block.blockhash() != header.hash {
+            return Err(Error::BlockIsInvalid(header.hash.to_string()))
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        blockchain::{BlockInfo, Header},
+        util::time::Timestamp,
+        Error,
+    };
+
+    use super::{verify_block_bodies, verify_header_chain, SyncHeader};
+
+    /// Build a chain of `n` blocks following `previous`, one per slot.
+    fn chain(previous: &BlockInfo, n: u64) -> Vec<BlockInfo> {
+        let mut blocks: Vec<BlockInfo> = vec![];
+        for i in 1..=n {
+            let prev = blocks.last().unwrap_or(previous);
+            let mut block = BlockInfo::default();
+            block.header = Header::new(
+                prev.blockhash(),
+                0,
+                previous.header.slot + i,
+                Timestamp(prev.header.timestamp.0 + 1),
+                prev.header.root,
+            );
+            blocks.push(block);
+        }
+        blocks
+    }
+
+    fn headers(blocks: &[BlockInfo]) -> Vec<SyncHeader> {
+        blocks
+            .iter()
+            .map(|b| SyncHeader { hash: b.blockhash(), header: b.header.clone() })
+            .collect()
+    }
+
+    #[test]
+    fn header_chain_verification() {
+        let genesis = BlockInfo::default();
+        let last = (genesis.header.slot, genesis.blockhash());
+        let blocks = chain(&genesis, 5);
+        let synced = headers(&blocks);
+
+        assert!(verify_header_chain(last, &synced, &[]).is_ok());
+        assert!(verify_header_chain(last, &synced, &[(3, synced[2].hash)]).is_ok());
+        // Checkpoints below our own last block are not our concern
+        assert!(
+            verify_header_chain((2, synced[1].hash), &synced[2..], &[(1, synced[0].hash)]).is_ok()
+        );
+
+        // Mismatching checkpoint
+        assert!(matches!(
+            verify_header_chain(last, &synced, &[(3, synced[1].hash)]),
+            Err(Error::CheckpointInvalid(_))
+        ));
+
+        // Chain skipping over a checkpointed slot
+        let mut skipping = synced.clone();
+        skipping.remove(2);
+        skipping[2].header.previous = skipping[1].hash;
+        assert!(matches!(
+            verify_header_chain(last, &skipping, &[(3, synced[2].hash)]),
+            Err(Error::CheckpointInvalid(_))
+        ));
+
+        // Broken links
+        assert!(verify_header_chain(last, &synced[1..], &[]).is_err());
+        let mut reordered = synced.clone();
+        reordered.swap(1, 2);
+        assert!(verify_header_chain(last, &reordered, &[]).is_err());
+    }
+
+    #[test]
+    fn block_bodies_verification() {
+        let genesis = BlockInfo::default();
+        let blocks = chain(&genesis, 3);
+        let synced = headers(&blocks);
+
+        assert!(verify_block_bodies(&synced, &blocks).is_ok());
+        assert!(verify_block_bodies(&synced, &blocks[..2]).is_err());
+
+        let mut tampered = blocks.clone();
+        tampered[1].header.epoch = 1;
+        assert!(verify_block_bodies(&synced, &tampered).is_err());
+    }
+}
@@ -16,18 +16,36 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use std::time::Duration;
+
+use futures::future::join_all;
+use log::{debug, info, warn};
+
 use crate::{
+    blockchain::BlockInfo,
     consensus::{
-        block::{BlockOrder, BlockResponse},
+        constants::{SYNC_BATCH, SYNC_REQUEST_TIMEOUT},
         state::{SlotRequest, SlotResponse},
+        sync::{
+            verify_block_bodies, verify_header_chain, BlockSyncRequest, BlockSyncResponse,
+            HeaderSyncRequest, HeaderSyncResponse, SyncHeader,
+        },
         ValidatorStatePtr,
     },
-    net, Result,
+    net::{self, ChannelPtr, MessageSubscription},
+    system::timeout::timeout,
+    Error, Result,
 };
-use log::{debug, info, warn};
 
 /// async task used for block syncing.
-pub async fn block_sync_task(p2p: net::P2pPtr, state: ValidatorStatePtr) -> Result<()> {
+/// Blocks are synced headers first: their headers are retrieved from a random
+/// peer and verified against the given checkpoints, then the block bodies are
+/// downloaded in parallel from all connected peers and applied in order.
+pub async fn block_sync_task(
+    p2p: net::P2pPtr,
+    state: ValidatorStatePtr,
+    checkpoints: &[(u64, blake3::Hash)],
+) -> Result<()> {
     info!(target: "consensus::block_sync", "Starting blockchain sync...");
     // Getting a random connected channel to ask from peers
     match p2p.random_channel().await {
@@ -38,9 +56,9 @@ pub async fn block_sync_task(p2p: net::P2pPtr, state: ValidatorStatePtr) -> Resu
             msg_subsystem.add_dispatch::<SlotResponse>().await;
             let slot_response_sub = channel.subscribe_msg::<SlotResponse>().await?;
 
-            // Communication setup for blocks
-            msg_subsystem.add_dispatch::<BlockResponse>().await;
-            let block_response_sub = channel.subscribe_msg::<BlockResponse>().await?;
+            // Communication setup for headers
+            msg_subsystem.add_dispatch::<HeaderSyncResponse>().await;
+            let header_response_sub = channel.subscribe_msg::<HeaderSyncResponse>().await?;
 
             // Node loops until both slots and blocks have been synced
             let mut slots_synced = false;
@@ -79,34 +97,21 @@ pub async fn block_sync_task(p2p: net::P2pPtr, state: ValidatorStatePtr) -> Resu
                     slots_synced = true;
                 }
 
-                // Node sends the last known block hash of the canonical blockchain
-                // and loops until the response is the same block (used to utilize
-                // batch requests).
-                let mut last = state.read().await.blockchain.last()?;
+                // Node retrieves the headers following its last known block,
+                // and then the blocks they describe.
+                let last = state.read().await.blockchain.last()?;
                 info!(target: "consensus::block_sync", "Last known block: {:?} - {:?}", last.0, last.1);
 
-                loop {
-                    // Node creates a `BlockOrder` and sends it
-                    let order = BlockOrder { slot: last.0, block: last.1 };
-                    channel.send(&order).await?;
-
-                    // Node stores response data.
-                    let _resp = block_response_sub.receive().await?;
-
-                    // Verify and store retrieved blocks
-                    debug!(target: "consensus::block_sync", "block_sync_task(): Processing received blocks");
-                    //state.write().await.receive_sync_blocks(&resp.blocks).await?;
+                let headers =
+                    sync_headers(&channel, &header_response_sub, last, checkpoints).await?;
 
+                if headers.is_empty() {
+                    blocks_synced = true;
+                } else {
+                    sync_blocks(&p2p, &state, &headers, checkpoints).await?;
                     let last_received = state.read().await.blockchain.last()?;
                     info!(target: "consensus::block_sync", "Last received block: {:?} - {:?}", last_received.0, last_received.1);
-
-                    if last == last_received {
-                        blocks_synced = true;
-                        break
-                    }
-
                     slots_synced = false;
-                    last = last_received;
                 }
 
                 if slots_synced && blocks_synced {
@@ -121,3 +126,129 @@ pub async fn block_sync_task(p2p: net::P2pPtr, state: ValidatorStatePtr) -> Resu
     info!(target: "consensus::block_sync", "Blockchain synced!");
     Ok(())
 }
+
+/// Retrieve the headers of the blocks following `last` from the given
+/// channel, verifying they extend our chain and match the checkpoints.
+async fn sync_headers(
+    channel: &ChannelPtr,
+    response_sub: &MessageSubscription<HeaderSyncResponse>,
+    last: (u64, blake3::Hash),
+    checkpoints: &[(u64, blake3::Hash)],
+) -> Result<Vec<SyncHeader>> {
+    let mut headers: Vec<SyncHeader> = vec![];
+    let mut tip = last;
+
+    loop {
+        channel.send(&HeaderSyncRequest { slot: tip.0 }).await?;
+        let resp =
+            timeout(Duration::from_secs(SYNC_REQUEST_TIMEOUT), response_sub.receive()).await??;
+
+        if resp.headers.is_empty() {
+            break
+        }
+
+        verify_header_chain(tip, &resp.headers, checkpoints)?;
+        let new_tip = resp.headers.last().unwrap();
+        tip = (new_tip.header.slot, new_tip.hash);
+        headers.extend_from_slice(&resp.headers);
+        debug!(target: "consensus::block_sync", "sync_headers(): Retrieved headers up to slot {}", tip.0);
+    }
+
+    // A peer whose chain ends before a checkpoint can't be trusted to be
+    // on the canonical chain.
+    if let Some((slot, hash)) = checkpoints.iter().max_by_key(|(slot, _)| *slot) {
+        if *slot > tip.0 {
+            return Err(Error::CheckpointInvalid(format!(
+                "Peer chain ends before block {} at slot {}",
+                hash, slot
+            )))
+        }
+    }
+
+    Ok(headers)
+}
+
+/// Download the blocks described by the given verified headers from all
+/// connected peers in parallel, and apply them in order.
+async fn sync_blocks(
+    p2p: &net::P2pPtr,
+    state: &ValidatorStatePtr,
+    headers: &[SyncHeader],
+    checkpoints: &[(u64, blake3::Hash)],
+) -> Result<()> {
+    // Blocks up to the last checkpoint are covered by it
+    let tip = headers.last().unwrap().header.slot;
+    let trusted_slot =
+        checkpoints.iter().map(|(slot, _)| *slot).filter(|slot| *slot <= tip).max().unwrap_or(0);
+
+    let mut peers = vec![];
+    for channel in p2p.channels().lock().await.values() {
+        channel.message_subsystem().add_dispatch::<BlockSyncResponse>().await;
+        let response_sub = channel.subscribe_msg::<BlockSyncResponse>().await?;
+        peers.push((channel.clone(), response_sub));
+    }
+
+    if peers.is_empty() {
+        return Err(Error::NetworkNotConnected)
+    }
+
+    let batches: Vec<&[SyncHeader]> = headers.chunks(SYNC_BATCH as usize).collect();
+    for wave in batches.chunks(peers.len()) {
+        // Each batch of the wave is requested from a different peer
+        let requests =
+            wave.iter().zip(peers.iter()).map(|(batch, peer)| request_blocks(peer, batch));
+        let responses = join_all(requests).await;
+
+        for (i, (batch, response)) in wave.iter().zip(responses).enumerate() {
+            let blocks = match response {
+                Ok(v) => v,
+                Err(e) => {
+                    warn!(target: "consensus::block_sync", "sync_blocks(): Batch request failed: {}", e);
+                    retry_blocks(&peers, i, batch).await?
+                }
+            };
+
+            state.write().await.apply_sync_blocks(&blocks, trusted_slot).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Retry a failed batch request with the rest of the peers, one by one.
+async fn retry_blocks(
+    peers: &[(ChannelPtr, MessageSubscription<BlockSyncResponse>)],
+    failed: usize,
+    batch: &[SyncHeader],
+) -> Result<Vec<BlockInfo>> {
+    for (i, peer) in peers.iter().enumerate() {
+        if i == failed {
+            continue
+        }
+
+        match request_blocks(peer, batch).await {
+            Ok(blocks) => return Ok(blocks),
+            Err(e) => {
+                warn!(target: "consensus::block_sync", "retry_blocks(): Batch request failed: {}", e)
+            }
+        }
+    }
+
+    Err(Error::NetworkOperationFailed)
+}
+
+/// Request the blocks described by the given headers from a peer, and
+/// verify the response matches them.
+async fn request_blocks(
+    peer: &(ChannelPtr, MessageSubscription<BlockSyncResponse>),
+    batch: &[SyncHeader],
+) -> Result<Vec<BlockInfo>> {
+    let (channel, response_sub) = peer;
+    let hashes = batch.iter().map(|header| header.hash).collect();
+    channel.send(&BlockSyncRequest { hashes }).await?;
+
+    let resp = timeout(Duration::from_secs(SYNC_REQUEST_TIMEOUT), response_sub.receive()).await??;
+    verify_block_bodies(batch, &resp.blocks)?;
+
+    Ok(resp.blocks.clone())
+}
@@ -39,7 +39,7 @@ use crate::{
     runtime::vm_runtime::Runtime,
     tx::Transaction,
     util::time::{TimeKeeper, Timestamp},
-    wallet::WalletPtr,
+    wallet::{WalletMigration, WalletPtr},
     zk::{
         proof::{ProvingKey, VerifyingKey},
         vm::ZkCircuit,
@@ -56,6 +56,13 @@ use super::{
     BlockProposal, Header, LeadInfo, LeadProof,
 };
 
+/// Consensus wallet schema migrations, applied in order
+const WALLET_MIGRATIONS: &[WalletMigration] = &[WalletMigration {
+    version: 1,
+    description: "Consensus lead coins",
+    sql: include_str!("consensus_coin.sql"),
+}];
+
 /// Atomic pointer to validator state.
 pub type ValidatorStatePtr = Arc<RwLock<ValidatorState>>;
 
@@ -99,7 +106,7 @@ impl ValidatorState {
         // Initialize consensus coin table.
         // NOTE: In future this will be redundant as consensus coins will live in the money contract.
         if enable_participation {
-            wallet.exec_sql(include_str!("consensus_coin.sql")).await?;
+            wallet.migrate(WALLET_MIGRATIONS).await?;
         }
 
         debug!(target: "consensus::validator", "Generating leader proof keys with k: {}", constants::LEADER_PROOF_K);
@@ -972,12 +979,14 @@ impl ValidatorState {
 
     /// Validate signatures, wasm execution, and zk proofs for given transaction in
     /// provided runtimes. If all of those succeed, try to execute a state update
-    /// for the contract calls.
+    /// for the contract calls. When `verify_proofs` is false, signatures and zk
+    /// proofs are skipped, which is only acceptable for checkpointed blocks.
     async fn verify_transaction(
         &self,
         overlay: &BlockchainOverlayPtr,
         tx: &Transaction,
         verifying_slot: u64,
+        verify_proofs: bool,
     ) -> Result<()> {
         let mut runtimes = HashMap::new();
         let tx_hash = blake3::hash(&serialize(tx));
@@ -1018,8 +1027,9 @@ impl ValidatorState {
             let runtime_key = call.contract_id.to_string();
             if !runtimes.contains_key(&runtime_key) {
                 let wasm = self.blockchain.wasm_bincode.get(call.contract_id)?;
-                let r =
+                let mut r =
                     Runtime::new(&wasm, overlay.clone(), call.contract_id, time_keeper.clone())?;
+                r.set_access_list(tx.access_list.clone());
                 runtimes.insert(runtime_key.clone(), r);
             }
             let runtime = runtimes.get_mut(&runtime_key).unwrap();
@@ -1072,32 +1082,36 @@ impl ValidatorState {
         // When we're done looping and executing over the tx's contract calls, we
         // move on with verification. First we verify the signatures as that's
         // cheaper, and then finally we verify the ZK proofs.
-        info!(target: "consensus::validator", "Verifying signatures for transaction {}", tx_hash);
-        if sig_table.len() != tx.signatures.len() {
-            error!(target: "consensus::validator", "Incorrect number of signatures in tx {}", tx_hash);
-            return Err(Error::InvalidSignature)
-        }
-
-        match tx.verify_sigs(sig_table) {
-            Ok(()) => {
-                info!(target: "consensus::validator", "Signatures verification for tx {} successful", tx_hash)
+        if verify_proofs {
+            info!(target: "consensus::validator", "Verifying signatures for transaction {}", tx_hash);
+            if sig_table.len() != tx.signatures.len() {
+                error!(target: "consensus::validator", "Incorrect number of signatures in tx {}", tx_hash);
+                return Err(Error::InvalidSignature)
             }
-            Err(e) => {
-                error!(target: "consensus::validator", "Signature verification for tx {} failed: {}", tx_hash, e);
-                return Err(e)
-            }
-        };
 
-        info!(target: "consensus::validator", "Verifying ZK proofs for transaction {}", tx_hash);
-        match tx.verify_zkps(&verifying_keys, zkp_table).await {
-            Ok(()) => {
-                info!(target: "consensus::validator", "ZK proof verification for tx {} successful", tx_hash)
-            }
-            Err(e) => {
-                error!(target: "consensus::validator", "ZK proof verification for tx {} failed: {}", tx_hash, e);
-                return Err(e)
-            }
-        };
+            match tx.verify_sigs(sig_table) {
+                Ok(()) => {
+                    info!(target: "consensus::validator", "Signatures verification for tx {} successful", tx_hash)
+                }
+                Err(e) => {
+                    error!(target: "consensus::validator", "Signature verification for tx {} failed: {}", tx_hash, e);
+                    return Err(e)
+                }
+            };
+
+            info!(target: "consensus::validator", "Verifying ZK proofs for transaction {}", tx_hash);
+            match tx.verify_zkps(&verifying_keys, zkp_table).await {
+                Ok(()) => {
+                    info!(target: "consensus::validator", "ZK proof verification for tx {} successful", tx_hash)
+                }
+                Err(e) => {
+                    error!(target: "consensus::validator", "ZK proof verification for tx {} failed: {}", tx_hash, e);
+                    return Err(e)
+                }
+            };
+        } else {
+            info!(target: "consensus::validator", "Skipping signatures and ZK proofs of checkpointed transaction {}", tx_hash);
+        }
 
         // After the verifications stage passes we can apply the state updates.
         assert!(tx.calls.len() == updates.len());
@@ -1134,7 +1148,7 @@ impl ValidatorState {
         let overlay = BlockchainOverlay::new(&self.blockchain)?;
 
         for tx in txs {
-            if let Err(e) = self.verify_transaction(&overlay, tx, verifying_slot).await {
+            if let Err(e) = self.verify_transaction(&overlay, tx, verifying_slot, true).await {
                 warn!(target: "consensus::validator", "Transaction verification failed: {}", e);
                 erroneous_txs.push(tx.clone());
             }
@@ -1159,6 +1173,47 @@ impl ValidatorState {
         Ok(erroneous_txs)
     }
 
+    /// Apply blocks retrieved by the checkpointed block sync, in order. Each
+    /// block's transactions are executed through the overlay and the block is
+    /// appended to the canonical chain along with its state changes. Blocks at
+    /// or below `trusted_slot` are covered by a hardcoded checkpoint, so their
+    /// signatures and zk proofs are not verified. Already existing blocks are
+    /// ignored.
+    pub async fn apply_sync_blocks(
+        &mut self,
+        blocks: &[BlockInfo],
+        trusted_slot: u64,
+    ) -> Result<()> {
+        for block in blocks {
+            if self.blockchain.has_block(block)? {
+                debug!(target: "consensus::validator", "apply_sync_blocks(): Existing block {} received", block.header.slot);
+                continue
+            }
+
+            info!(target: "consensus::validator", "apply_sync_blocks(): Applying block {}", block.header.slot);
+            let verify_proofs = block.header.slot > trusted_slot;
+            let overlay = BlockchainOverlay::new(&self.blockchain)?;
+
+            for tx in &block.txs {
+                if let Err(e) =
+                    self.verify_transaction(&overlay, tx, block.header.slot, verify_proofs).await
+                {
+                    error!(target: "consensus::validator", "apply_sync_blocks(): Transaction verification failed: {}", e);
+                    overlay.lock().unwrap().overlay.lock().unwrap().purge_new_trees()?;
+                    return Err(Error::ErroneousTxsDetected)
+                }
+            }
+
+            let lock = overlay.lock().unwrap();
+            lock.add_block(block)?;
+            lock.overlay.lock().unwrap().apply()?;
+
+            self.blockchain.remove_pending_txs(&block.txs)?;
+        }
+
+        Ok(())
+    }
+
     /// Append to canonical state received finalized slots from block sync task.
     pub async fn receive_slots(&mut self, slots: &[Slot]) -> Result<()> {
         info!(target: "consensus::validator", "receive_slots(): Appending slots to ledger");
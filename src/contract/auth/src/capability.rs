@@ -0,0 +1,64 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_sdk::{
+    crypto::{PublicKey, AUTH_CONTRACT_ID},
+    error::ContractError,
+    msg, ContractCall,
+};
+use darkfi_serial::deserialize;
+
+use crate::{
+    error::AuthError,
+    model::{AuthExerciseParamsV1, AuthRole},
+    AuthFunction,
+};
+
+/// Enforce that the call at `call_idx` is authorized by a capability
+/// granting `role`, issued by `issuer`. This is the case when the call
+/// directly follows an `Auth::Exercise` call for that capability. The
+/// Auth contract itself verifies the exercised capability coin is
+/// spent in the same transaction, so contracts only have to check the
+/// claimed issuer and role here.
+pub fn require_capability(
+    call_idx: u32,
+    calls: &[ContractCall],
+    issuer: PublicKey,
+    role: AuthRole,
+) -> Result<(), ContractError> {
+    if call_idx == 0 || call_idx as usize >= calls.len() {
+        msg!("[Auth::require_capability] Error: No call preceding call {}", call_idx);
+        return Err(AuthError::MissingCapability.into())
+    }
+
+    let auth_call = &calls[call_idx as usize - 1];
+    if auth_call.contract_id != *AUTH_CONTRACT_ID ||
+        auth_call.data.first() != Some(&(AuthFunction::ExerciseV1 as u8))
+    {
+        msg!("[Auth::require_capability] Error: Call {} is not authorized", call_idx);
+        return Err(AuthError::MissingCapability.into())
+    }
+
+    let params: AuthExerciseParamsV1 = deserialize(&auth_call.data[1..])?;
+    if params.issuer != issuer || params.role != role {
+        msg!("[Auth::require_capability] Error: Capability mismatch for call {}", call_idx);
+        return Err(AuthError::MissingCapability.into())
+    }
+
+    Ok(())
+}
@@ -0,0 +1,110 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{
+    zk::{halo2::Field, ProvingKey},
+    zkas::ZkBinary,
+    Result,
+};
+use darkfi_money_contract::client::{
+    transfer_v1::{TransferCallBuilder, TransferCallDebris},
+    OwnCoin,
+};
+use darkfi_sdk::{
+    crypto::{Keypair, MerkleTree, PublicKey, AUTH_CONTRACT_ID},
+    pasta::pallas,
+};
+use log::info;
+use rand::rngs::OsRng;
+
+use crate::model::{AuthExerciseParamsV1, AuthRole};
+
+pub struct AuthExerciseCallDebris {
+    /// The `Money::TransferV1` call spending the capability coin and
+    /// minting it back to its owner
+    pub xfer: TransferCallDebris,
+    /// Parameters for the `Auth::ExerciseV1` call
+    pub params: AuthExerciseParamsV1,
+}
+
+/// Struct holding necessary information to build the `Money::TransferV1`
+/// and `Auth::ExerciseV1` contract calls exercising a capability. The
+/// privileged call has to be placed right after them in the transaction.
+pub struct AuthExerciseCallBuilder {
+    /// Keypair owning the capability coin
+    pub keypair: Keypair,
+    /// Issuer of the capability
+    pub issuer: PublicKey,
+    /// Role granted by the capability
+    pub role: AuthRole,
+    /// The capability coin
+    pub coin: OwnCoin,
+    /// Merkle tree of coins used to create inclusion proofs
+    pub tree: MerkleTree,
+    /// `Mint_V1` zkas circuit ZkBinary
+    pub mint_zkbin: ZkBinary,
+    /// Proving key for the `Mint_V1` zk circuit
+    pub mint_pk: ProvingKey,
+    /// `Burn_V1` zkas circuit ZkBinary
+    pub burn_zkbin: ZkBinary,
+    /// Proving key for the `Burn_V1` zk circuit
+    pub burn_pk: ProvingKey,
+}
+
+impl AuthExerciseCallBuilder {
+    pub fn build(self) -> Result<AuthExerciseCallDebris> {
+        info!("Building Auth::ExerciseV1 contract call");
+
+        // The capability is spent and minted back to us, keeping it
+        // locked to the Auth contract with the same role.
+        let user_data_blind = pallas::Base::random(&mut OsRng);
+        let xfer_builder = TransferCallBuilder {
+            keypair: self.keypair,
+            recipient: self.keypair.public,
+            value: self.coin.note.value,
+            token_id: self.coin.note.token_id,
+            rcpt_spend_hook: AUTH_CONTRACT_ID.inner(),
+            rcpt_user_data: self.role.inner(),
+            rcpt_user_data_blind: pallas::Base::random(&mut OsRng),
+            rcpt_view_public: None,
+            change_spend_hook: AUTH_CONTRACT_ID.inner(),
+            change_user_data: self.role.inner(),
+            change_user_data_blind: user_data_blind,
+            change_view_public: None,
+            coins: vec![self.coin],
+            tree: self.tree,
+            mint_zkbin: self.mint_zkbin,
+            mint_pk: self.mint_pk,
+            burn_zkbin: self.burn_zkbin,
+            burn_pk: self.burn_pk,
+            clear_input: false,
+        };
+        let xfer = xfer_builder.build()?;
+
+        // All inputs and outputs of the transfer share the same token blind
+        let params = AuthExerciseParamsV1 {
+            issuer: self.issuer,
+            role: self.role,
+            input_idx: 0,
+            token_blind: xfer.minted_coins[0].note.token_blind,
+            user_data_blind,
+        };
+
+        Ok(AuthExerciseCallDebris { xfer, params })
+    }
+}
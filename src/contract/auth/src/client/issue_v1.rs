@@ -0,0 +1,87 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{
+    zk::{Proof, ProvingKey},
+    zkas::ZkBinary,
+    Result,
+};
+use darkfi_money_contract::{
+    client::token_mint_v1::TokenMintCallBuilder, model::MoneyTokenMintParamsV1,
+};
+use darkfi_sdk::crypto::{Keypair, PublicKey, AUTH_CONTRACT_ID};
+use log::info;
+
+use crate::model::{AuthIssueParamsV1, AuthRole};
+
+pub struct AuthIssueCallDebris {
+    /// Parameters for the `Money::TokenMintV1` call minting the capability
+    pub mint_params: MoneyTokenMintParamsV1,
+    /// ZK proofs for the `Money::TokenMintV1` call
+    pub mint_proofs: Vec<Proof>,
+    /// Parameters for the `Auth::IssueV1` call
+    pub params: AuthIssueParamsV1,
+}
+
+/// Struct holding necessary information to build the `Money::TokenMintV1`
+/// and `Auth::IssueV1` contract calls issuing a capability.
+/// Both calls have to be signed by the issuer.
+pub struct AuthIssueCallBuilder {
+    /// Issuer keypair, used as the capability token mint authority
+    pub issuer: Keypair,
+    /// Recipient of the capability
+    pub recipient: PublicKey,
+    /// Role granted by the capability
+    pub role: AuthRole,
+    /// `TokenMint_V1` zkas circuit ZkBinary
+    pub token_mint_zkbin: ZkBinary,
+    /// Proving key for the `TokenMint_V1` zk circuit,
+    pub token_mint_pk: ProvingKey,
+}
+
+impl AuthIssueCallBuilder {
+    pub fn build(self) -> Result<AuthIssueCallDebris> {
+        info!("Building Auth::IssueV1 contract call");
+
+        // A capability is a single coin of the issuer's token, which can
+        // only be spent together with an `Auth::Exercise` call.
+        let mint_builder = TokenMintCallBuilder {
+            mint_authority: self.issuer,
+            recipient: self.recipient,
+            amount: 1,
+            max_supply: None,
+            spend_hook: AUTH_CONTRACT_ID.inner(),
+            user_data: self.role.inner(),
+            token_mint_zkbin: self.token_mint_zkbin,
+            token_mint_pk: self.token_mint_pk,
+        };
+        let mint_debris = mint_builder.build()?;
+
+        let params = AuthIssueParamsV1 {
+            issuer: self.issuer.public,
+            role: self.role,
+            coin: mint_debris.params.output.coin,
+        };
+
+        Ok(AuthIssueCallDebris {
+            mint_params: mint_debris.params,
+            mint_proofs: mint_debris.proofs,
+            params,
+        })
+    }
+}
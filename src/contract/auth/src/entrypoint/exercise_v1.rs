@@ -0,0 +1,128 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_money_contract::{model::MoneyTransferParamsV1, MoneyFunction};
+use darkfi_sdk::{
+    crypto::{poseidon_hash, ContractId, PublicKey, TokenId, MONEY_CONTRACT_ID},
+    error::{ContractError, ContractResult},
+    msg,
+    pasta::pallas,
+    ContractCall,
+};
+use darkfi_serial::{deserialize, Encodable, WriteExt};
+
+use crate::{
+    error::AuthError,
+    model::{AuthExerciseParamsV1, AuthExerciseUpdateV1},
+    AuthFunction,
+};
+
+/// `get_metadata` function for `Auth::ExerciseV1`
+pub(crate) fn auth_exercise_get_metadata_v1(
+    _cid: ContractId,
+    _call_idx: u32,
+    _calls: Vec<ContractCall>,
+) -> Result<Vec<u8>, ContractError> {
+    // The capability input is already signed and proven in the
+    // preceding `Money::Transfer` call.
+    let zk_public_inputs: Vec<(String, Vec<pallas::Base>)> = vec![];
+    let signature_pubkeys: Vec<PublicKey> = vec![];
+
+    // Serialize everything gathered and return it
+    let mut metadata = vec![];
+    zk_public_inputs.encode(&mut metadata)?;
+    signature_pubkeys.encode(&mut metadata)?;
+
+    Ok(metadata)
+}
+
+/// `process_instruction` function for `Auth::ExerciseV1`
+pub(crate) fn auth_exercise_process_instruction_v1(
+    cid: ContractId,
+    call_idx: u32,
+    calls: Vec<ContractCall>,
+) -> Result<Vec<u8>, ContractError> {
+    let self_ = &calls[call_idx as usize];
+    let params: AuthExerciseParamsV1 = deserialize(&self_.data[1..])?;
+
+    // ==========================================
+    // Enforce the transaction has correct format
+    // ==========================================
+    // The capability coin is spent in the call before this one, and the
+    // call being authorized comes right after it.
+    if call_idx == 0 || call_idx as usize + 1 >= calls.len() {
+        msg!("[ExerciseV1] Error: Transaction has incorrect format");
+        return Err(AuthError::CallInvalidFormat.into())
+    }
+
+    let xfer_call = &calls[call_idx as usize - 1];
+    if xfer_call.contract_id != *MONEY_CONTRACT_ID ||
+        xfer_call.data[0] != MoneyFunction::TransferV1 as u8
+    {
+        msg!("[ExerciseV1] Error: Preceding call is not Money::Transfer");
+        return Err(AuthError::CallInvalidFormat.into())
+    }
+
+    let xfer_params: MoneyTransferParamsV1 = deserialize(&xfer_call.data[1..])?;
+    let Some(input) = xfer_params.inputs.get(params.input_idx as usize) else {
+        msg!("[ExerciseV1] Error: Capability input {} not found", params.input_idx);
+        return Err(AuthError::CapabilityInputNonexistent.into())
+    };
+
+    // ======
+    // Checks
+    // ======
+    // 1. The input is locked to this contract, so Money enforced it's
+    //    spent together with this call.
+    if input.spend_hook != cid.inner() {
+        msg!("[ExerciseV1] Error: Input spend hook is not the Auth contract");
+        return Err(AuthError::CapabilityMismatch.into())
+    }
+
+    // 2. The input is a coin of the issuer's token
+    let token_id = TokenId::derive_public(params.issuer);
+    if input.token_commit != poseidon_hash([token_id.inner(), params.token_blind]) {
+        msg!("[ExerciseV1] Error: Input token is not issued by {}", params.issuer);
+        return Err(AuthError::CapabilityMismatch.into())
+    }
+
+    // 3. The input grants the claimed role
+    if input.user_data_enc != poseidon_hash([params.role.inner(), params.user_data_blind]) {
+        msg!("[ExerciseV1] Error: Input doesn't grant the {:?} role", params.role);
+        return Err(AuthError::CapabilityMismatch.into())
+    }
+
+    let update = AuthExerciseUpdateV1 { issuer: params.issuer, role: params.role };
+    let mut update_data = vec![];
+    update_data.write_u8(AuthFunction::ExerciseV1 as u8)?;
+    update.encode(&mut update_data)?;
+
+    Ok(update_data)
+}
+
+/// `process_update` function for `Auth::ExerciseV1`
+pub(crate) fn auth_exercise_process_update_v1(
+    _cid: ContractId,
+    update: AuthExerciseUpdateV1,
+) -> ContractResult {
+    // Exercising a capability doesn't touch any state, the coin itself
+    // is handled by the Money contract.
+    msg!("[ExerciseV1] Exercised {:?} capability of {}", update.role, update.issuer);
+
+    Ok(())
+}
@@ -0,0 +1,113 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_money_contract::{model::MoneyTokenMintParamsV1, MoneyFunction};
+use darkfi_sdk::{
+    crypto::{ContractId, PublicKey, TokenId, MONEY_CONTRACT_ID},
+    db::{db_lookup, db_set},
+    error::{ContractError, ContractResult},
+    msg,
+    pasta::pallas,
+    ContractCall,
+};
+use darkfi_serial::{deserialize, serialize, Encodable, WriteExt};
+
+use crate::{
+    error::AuthError,
+    model::{AuthIssueParamsV1, AuthIssueUpdateV1},
+    AuthFunction, AUTH_CONTRACT_CAPABILITIES_TREE,
+};
+
+/// `get_metadata` function for `Auth::IssueV1`
+pub(crate) fn auth_issue_get_metadata_v1(
+    _cid: ContractId,
+    call_idx: u32,
+    calls: Vec<ContractCall>,
+) -> Result<Vec<u8>, ContractError> {
+    let self_ = &calls[call_idx as usize];
+    let params: AuthIssueParamsV1 = deserialize(&self_.data[1..])?;
+
+    // Public inputs for the ZK proofs we have to verify
+    let zk_public_inputs: Vec<(String, Vec<pallas::Base>)> = vec![];
+    // Public keys for the transaction signatures we have to verify
+    let signature_pubkeys: Vec<PublicKey> = vec![params.issuer];
+
+    // Serialize everything gathered and return it
+    let mut metadata = vec![];
+    zk_public_inputs.encode(&mut metadata)?;
+    signature_pubkeys.encode(&mut metadata)?;
+
+    Ok(metadata)
+}
+
+/// `process_instruction` function for `Auth::IssueV1`
+pub(crate) fn auth_issue_process_instruction_v1(
+    _cid: ContractId,
+    call_idx: u32,
+    calls: Vec<ContractCall>,
+) -> Result<Vec<u8>, ContractError> {
+    let self_ = &calls[call_idx as usize];
+    let params: AuthIssueParamsV1 = deserialize(&self_.data[1..])?;
+
+    // The capability coin has to be minted by the issuer in the call
+    // right before this one.
+    if call_idx == 0 {
+        msg!("[IssueV1] Error: Missing Money::TokenMint call");
+        return Err(AuthError::CallInvalidFormat.into())
+    }
+
+    let mint_call = &calls[call_idx as usize - 1];
+    if mint_call.contract_id != *MONEY_CONTRACT_ID ||
+        mint_call.data[0] != MoneyFunction::TokenMintV1 as u8
+    {
+        msg!("[IssueV1] Error: Preceding call is not Money::TokenMint");
+        return Err(AuthError::CallInvalidFormat.into())
+    }
+
+    let mint_params: MoneyTokenMintParamsV1 = deserialize(&mint_call.data[1..])?;
+    if mint_params.input.signature_public != params.issuer ||
+        mint_params.input.token_id != TokenId::derive_public(params.issuer)
+    {
+        msg!("[IssueV1] Error: Token not minted by the issuer");
+        return Err(AuthError::CallInvalidFormat.into())
+    }
+
+    if mint_params.output.coin != params.coin {
+        msg!("[IssueV1] Error: Minted coin doesn't match the capability coin");
+        return Err(AuthError::IssueCoinMismatch.into())
+    }
+
+    let update = AuthIssueUpdateV1 { coin: params.coin, issuer: params.issuer, role: params.role };
+    let mut update_data = vec![];
+    update_data.write_u8(AuthFunction::IssueV1 as u8)?;
+    update.encode(&mut update_data)?;
+
+    Ok(update_data)
+}
+
+/// `process_update` function for `Auth::IssueV1`
+pub(crate) fn auth_issue_process_update_v1(
+    cid: ContractId,
+    update: AuthIssueUpdateV1,
+) -> ContractResult {
+    msg!("[IssueV1] Recording issued {:?} capability", update.role);
+    let capabilities_db = db_lookup(cid, AUTH_CONTRACT_CAPABILITIES_TREE)?;
+    db_set(capabilities_db, &serialize(&update.coin), &serialize(&(update.issuer, update.role)))?;
+
+    Ok(())
+}
@@ -0,0 +1,49 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_sdk::error::ContractError;
+
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum AuthError {
+    #[error("Call has invalid tx format")]
+    CallInvalidFormat,
+
+    #[error("Issued coin mismatch")]
+    IssueCoinMismatch,
+
+    #[error("Capability input not found")]
+    CapabilityInputNonexistent,
+
+    #[error("Capability input doesn't match the claimed role")]
+    CapabilityMismatch,
+
+    #[error("Missing capability")]
+    MissingCapability,
+}
+
+impl From<AuthError> for ContractError {
+    fn from(e: AuthError) -> Self {
+        match e {
+            AuthError::CallInvalidFormat => Self::Custom(1),
+            AuthError::IssueCoinMismatch => Self::Custom(2),
+            AuthError::CapabilityInputNonexistent => Self::Custom(3),
+            AuthError::CapabilityMismatch => Self::Custom(4),
+            AuthError::MissingCapability => Self::Custom(5),
+        }
+    }
+}
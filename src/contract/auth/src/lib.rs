@@ -0,0 +1,71 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Smart contract implementing role-based capability tokens.
+//!
+//! A capability is a Money coin of the issuer's token, carrying this
+//! contract's ID as spend hook and the granted role as user data.
+//! Spending such a coin forces an `Auth::Exercise` call right after
+//! the `Money::Transfer`, which in turn authorizes the call following
+//! it. Other contracts use [`capability::require_capability`] to gate
+//! their privileged functions on it.
+
+use darkfi_sdk::error::ContractError;
+
+/// Functions available in the contract
+#[repr(u8)]
+pub enum AuthFunction {
+    IssueV1 = 0x00,
+    ExerciseV1 = 0x01,
+}
+
+impl TryFrom<u8> for AuthFunction {
+    type Error = ContractError;
+
+    fn try_from(b: u8) -> core::result::Result<Self, Self::Error> {
+        match b {
+            0x00 => Ok(Self::IssueV1),
+            0x01 => Ok(Self::ExerciseV1),
+            _ => Err(ContractError::InvalidFunction),
+        }
+    }
+}
+
+/// Internal contract errors
+pub mod error;
+
+/// Call parameters definitions
+pub mod model;
+
+/// Capability checks for use by other contracts
+pub mod capability;
+
+#[cfg(not(feature = "no-entrypoint"))]
+/// WASM entrypoint functions
+pub mod entrypoint;
+
+#[cfg(feature = "client")]
+/// Client API for interaction with this smart contract
+pub mod client;
+
+// These are the different sled trees that will be created
+pub const AUTH_CONTRACT_INFO_TREE: &str = "info";
+pub const AUTH_CONTRACT_CAPABILITIES_TREE: &str = "capabilities";
+
+// These are keys inside the info tree
+pub const AUTH_CONTRACT_DB_VERSION: &str = "db_version";
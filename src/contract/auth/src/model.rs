@@ -0,0 +1,86 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_money_contract::model::Coin;
+use darkfi_sdk::{crypto::PublicKey, pasta::pallas};
+use darkfi_serial::{SerialDecodable, SerialEncodable};
+
+#[cfg(feature = "client")]
+use darkfi_serial::async_trait;
+
+/// Roles a capability token can grant
+#[derive(Debug, Copy, Clone, Eq, PartialEq, SerialEncodable, SerialDecodable)]
+pub enum AuthRole {
+    Admin = 0x00,
+    Minter = 0x01,
+    Auditor = 0x02,
+}
+
+impl AuthRole {
+    /// The role as stored in the user data of a capability coin
+    pub fn inner(&self) -> pallas::Base {
+        pallas::Base::from(*self as u64)
+    }
+}
+
+/// Parameters for `Auth::Issue`
+#[derive(Debug, Copy, Clone, SerialEncodable, SerialDecodable)]
+pub struct AuthIssueParamsV1 {
+    /// Public key of the issuer, which is the mint authority of the
+    /// capability token
+    pub issuer: PublicKey,
+    /// Role granted by the capability
+    pub role: AuthRole,
+    /// Capability coin minted in the preceding `Money::TokenMint` call
+    pub coin: Coin,
+}
+
+/// State update for `Auth::Issue`
+#[derive(Debug, Copy, Clone, SerialEncodable, SerialDecodable)]
+pub struct AuthIssueUpdateV1 {
+    /// Issued capability coin
+    pub coin: Coin,
+    /// Public key of the issuer
+    pub issuer: PublicKey,
+    /// Role granted by the capability
+    pub role: AuthRole,
+}
+
+/// Parameters for `Auth::Exercise`
+#[derive(Debug, Copy, Clone, SerialEncodable, SerialDecodable)]
+pub struct AuthExerciseParamsV1 {
+    /// Public key of the capability issuer
+    pub issuer: PublicKey,
+    /// Role granted by the capability
+    pub role: AuthRole,
+    /// Index of the capability input in the preceding `Money::Transfer` call
+    pub input_idx: u32,
+    /// Blind opening the token commitment of the capability input
+    pub token_blind: pallas::Base,
+    /// Blind opening the user data commitment of the capability input
+    pub user_data_blind: pallas::Base,
+}
+
+/// State update for `Auth::Exercise`
+#[derive(Debug, Copy, Clone, SerialEncodable, SerialDecodable)]
+pub struct AuthExerciseUpdateV1 {
+    /// Public key of the capability issuer
+    pub issuer: PublicKey,
+    /// Role that was exercised
+    pub role: AuthRole,
+}
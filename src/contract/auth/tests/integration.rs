@@ -0,0 +1,137 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::Result;
+use darkfi_auth_contract::{capability::require_capability, model::AuthRole};
+use darkfi_contract_test_harness::{init_logger, Holder, TestHarness, TxAction};
+use log::info;
+
+#[test]
+fn integration_test() -> Result<()> {
+    smol::block_on(async {
+        init_logger();
+
+        // Holders this test will use:
+        // * Faucet issues the capabilities
+        // * Alice holds an admin capability
+        // * Bob is used to build an unrelated privileged call
+        const HOLDERS: [Holder; 3] = [Holder::Faucet, Holder::Alice, Holder::Bob];
+
+        // Initialize harness
+        let mut th = TestHarness::new(&["money".to_string(), "auth".to_string()]).await?;
+
+        // Slot to verify against
+        let current_slot = 0;
+
+        let issuer = th.holders.get(&Holder::Faucet).unwrap().token_mint_authority.public;
+
+        // ==========================================
+        // Auth::Issue
+        // Faucet issues an admin capability to Alice
+        // ==========================================
+        info!("Stage 1. Issuing admin capability");
+
+        info!("[Faucet] Building capability issue tx");
+        let (issue_tx, mint_params, issue_params) =
+            th.auth_issue(&Holder::Faucet, &Holder::Alice, AuthRole::Admin)?;
+        assert_eq!(issue_params.coin, mint_params.output.coin);
+
+        for holder in &HOLDERS {
+            info!("[{holder:?}] Executing capability issue tx");
+            th.execute_auth_issue_tx(holder, &issue_tx, &mint_params, current_slot).await?;
+        }
+
+        th.assert_trees(&HOLDERS);
+
+        let capability = th.gather_owncoin(&Holder::Alice, &mint_params.output, None)?;
+
+        // ===================================================
+        // Auth::Exercise
+        // Alice uses the capability to authorize a privileged
+        // call, here freezing her own token.
+        // ===================================================
+        info!("Stage 2. Exercising admin capability");
+
+        let (frz_tx, _) = th.token_freeze(&Holder::Alice)?;
+        let frz_secret = th.holders.get(&Holder::Alice).unwrap().token_mint_authority.secret;
+
+        info!("[Alice] Building capability exercise tx");
+        let (exercise_tx, xfer_params, _) = th.auth_exercise(
+            &Holder::Alice,
+            &Holder::Faucet,
+            AuthRole::Admin,
+            &capability,
+            frz_tx.calls[0].clone(),
+            frz_tx.proofs[0].clone(),
+            &[frz_secret],
+        )?;
+
+        // The privileged call can verify the capability it was given
+        require_capability(2, &exercise_tx.calls, issuer, AuthRole::Admin)?;
+        assert!(require_capability(2, &exercise_tx.calls, issuer, AuthRole::Minter).is_err());
+        assert!(require_capability(1, &exercise_tx.calls, issuer, AuthRole::Admin).is_err());
+
+        for holder in &HOLDERS {
+            info!("[{holder:?}] Executing capability exercise tx");
+            th.execute_auth_exercise_tx(holder, &exercise_tx, &xfer_params, current_slot).await?;
+        }
+
+        th.assert_trees(&HOLDERS);
+
+        // The capability was minted back to Alice
+        let capability = th.gather_owncoin(&Holder::Alice, &xfer_params.outputs[0], None)?;
+
+        // ===========================================
+        // Claiming a role the capability doesn't
+        // grant must fail.
+        // ===========================================
+        info!("Stage 3. Exercising capability with the wrong role");
+
+        let (frz_tx, _) = th.token_freeze(&Holder::Bob)?;
+        let frz_secret = th.holders.get(&Holder::Bob).unwrap().token_mint_authority.secret;
+
+        info!("[Alice] Building erroneous capability exercise tx");
+        let (exercise_tx, _, _) = th.auth_exercise(
+            &Holder::Alice,
+            &Holder::Faucet,
+            AuthRole::Minter,
+            &capability,
+            frz_tx.calls[0].clone(),
+            frz_tx.proofs[0].clone(),
+            &[frz_secret],
+        )?;
+
+        for holder in &HOLDERS {
+            info!("[{holder:?}] Executing erroneous capability exercise tx");
+            th.execute_erroneous_txs(
+                TxAction::AuthExercise,
+                holder,
+                &[exercise_tx.clone()],
+                current_slot,
+                1,
+            )
+            .await?;
+        }
+
+        // Statistics
+        th.statistics();
+
+        // Thanks for reading
+        Ok(())
+    })
+}
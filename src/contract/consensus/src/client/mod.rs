@@ -42,3 +42,6 @@ pub mod unstake_request_v1;
 
 /// `Consensus::UnstakeV1` API
 pub mod unstake_v1;
+
+/// `Consensus::SlashV1` API
+pub mod slash_v1;
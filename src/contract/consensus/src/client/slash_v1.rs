@@ -0,0 +1,62 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{zk::Proof, Error, Result};
+use log::info;
+
+use crate::model::{ConsensusSlashEvidenceV1, ConsensusSlashParamsV1};
+
+pub struct ConsensusSlashCallDebris {
+    /// Payload params
+    pub params: ConsensusSlashParamsV1,
+    /// ZK proofs
+    pub proofs: Vec<Proof>,
+}
+
+/// Struct holding necessary information to build a `Consensus::SlashV1` contract call.
+pub struct ConsensusSlashCallBuilder {
+    /// First signed block header
+    pub first: ConsensusSlashEvidenceV1,
+    /// ZK proof of the first block producer's proposal, as found
+    /// in its proposal transaction
+    pub first_proof: Proof,
+    /// Second signed block header, conflicting with the first one
+    pub second: ConsensusSlashEvidenceV1,
+    /// ZK proof of the second block producer's proposal
+    pub second_proof: Proof,
+}
+
+impl ConsensusSlashCallBuilder {
+    pub fn build(&self) -> Result<ConsensusSlashCallDebris> {
+        info!("Building Consensus::SlashV1 contract call");
+
+        let (first, second) = (&self.first.proposal.input, &self.second.proposal.input);
+        if self.first.header.slot != self.second.header.slot ||
+            first.signature_public != second.signature_public ||
+            first.nullifier != second.nullifier
+        {
+            return Err(Error::Custom("Headers are not a double-sign".to_string()))
+        }
+
+        let params =
+            ConsensusSlashParamsV1 { first: self.first.clone(), second: self.second.clone() };
+        let proofs = vec![self.first_proof.clone(), self.second_proof.clone()];
+
+        Ok(ConsensusSlashCallDebris { params, proofs })
+    }
+}
@@ -19,14 +19,16 @@
 use darkfi_money_contract::{
     model::{ConsensusStakeUpdateV1, ConsensusUnstakeUpdateV1},
     CONSENSUS_CONTRACT_DB_VERSION, CONSENSUS_CONTRACT_INFO_TREE,
-    CONSENSUS_CONTRACT_NULLIFIERS_TREE, CONSENSUS_CONTRACT_STAKED_COINS_TREE,
-    CONSENSUS_CONTRACT_STAKED_COIN_MERKLE_TREE, CONSENSUS_CONTRACT_STAKED_COIN_ROOTS_TREE,
-    CONSENSUS_CONTRACT_UNSTAKED_COINS_TREE, CONSENSUS_CONTRACT_UNSTAKED_COIN_MERKLE_TREE,
-    CONSENSUS_CONTRACT_UNSTAKED_COIN_ROOTS_TREE,
+    CONSENSUS_CONTRACT_NULLIFIERS_TREE, CONSENSUS_CONTRACT_SLASHED_COINS_TREE,
+    CONSENSUS_CONTRACT_STAKED_COINS_TREE, CONSENSUS_CONTRACT_STAKED_COIN_MERKLE_TREE,
+    CONSENSUS_CONTRACT_STAKED_COIN_ROOTS_TREE, CONSENSUS_CONTRACT_UNSTAKED_COINS_TREE,
+    CONSENSUS_CONTRACT_UNSTAKED_COIN_MERKLE_TREE, CONSENSUS_CONTRACT_UNSTAKED_COIN_ROOTS_TREE,
+    CONSENSUS_CONTRACT_UNSTAKE_DELAY, CONSENSUS_CONTRACT_ZKAS_BURN_PI_V1,
+    CONSENSUS_CONTRACT_ZKAS_MINT_PI_V1, CONSENSUS_CONTRACT_ZKAS_PROPOSAL_PI_V1,
 };
 use darkfi_sdk::{
     crypto::{ContractId, MerkleTree},
-    db::{db_init, db_lookup, db_set, zkas_db_set},
+    db::{db_contains_key, db_init, db_lookup, db_set, zkas_db_set_declared},
     error::{ContractError, ContractResult},
     msg,
     util::set_return_data,
@@ -34,7 +36,13 @@ use darkfi_sdk::{
 };
 use darkfi_serial::{deserialize, serialize, Encodable, WriteExt};
 
-use crate::{model::ConsensusProposalUpdateV1, ConsensusFunction};
+use crate::{
+    model::{
+        ConsensusProposalUpdateV1, ConsensusSlashUpdateV1, ConsensusUnstakeRequestUpdateV1,
+        GRACE_PERIOD,
+    },
+    ConsensusFunction,
+};
 
 /// `Consensus::GenesisStake` functions
 mod genesis_stake_v1;
@@ -70,6 +78,13 @@ use unstake_v1::{
     consensus_unstake_process_update_v1,
 };
 
+/// `Consensus::Slash` functions
+mod slash_v1;
+use slash_v1::{
+    consensus_slash_get_metadata_v1, consensus_slash_process_instruction_v1,
+    consensus_slash_process_update_v1,
+};
+
 darkfi_sdk::define_contract!(
     init: init_contract,
     exec: process_instruction,
@@ -81,7 +96,8 @@ darkfi_sdk::define_contract!(
 /// We use this function to initialize all the necessary databases and prepare them
 /// with initial data if necessary. This is also the place where we bundle the zkas
 /// circuits that are to be used with functions provided by the contract.
-fn init_contract(cid: ContractId, _ix: &[u8]) -> ContractResult {
+/// The payload optionally holds the unstake timelock, in epochs.
+fn init_contract(cid: ContractId, ix: &[u8]) -> ContractResult {
     // zkas circuits can simply be embedded in the wasm and set up by using
     // respective db functions. The special `zkas db` operations exist in
     // order to be able to verify the circuits being bundled and enforcing
@@ -90,10 +106,14 @@ fn init_contract(cid: ContractId, _ix: &[u8]) -> ContractResult {
     let consensus_burn_v1_bincode = include_bytes!("../proof/consensus_burn_v1.zk.bin");
     let consensus_proposal_v1_bincode = include_bytes!("../proof/consensus_proposal_v1.zk.bin");
 
-    // For that, we use `zkas_db_set` and pass in the bincode.
-    zkas_db_set(&consensus_mint_v1_bincode[..])?;
-    zkas_db_set(&consensus_burn_v1_bincode[..])?;
-    zkas_db_set(&consensus_proposal_v1_bincode[..])?;
+    // For that, we use `zkas_db_set_declared` and pass in the bincode along
+    // with the number of public inputs we build for it in metadata.
+    zkas_db_set_declared(&consensus_mint_v1_bincode[..], CONSENSUS_CONTRACT_ZKAS_MINT_PI_V1)?;
+    zkas_db_set_declared(&consensus_burn_v1_bincode[..], CONSENSUS_CONTRACT_ZKAS_BURN_PI_V1)?;
+    zkas_db_set_declared(
+        &consensus_proposal_v1_bincode[..],
+        CONSENSUS_CONTRACT_ZKAS_PROPOSAL_PI_V1,
+    )?;
 
     // Set up a database tree to hold Merkle roots of all staked coins
     // k=MerkleNode, v=[]
@@ -125,6 +145,12 @@ fn init_contract(cid: ContractId, _ix: &[u8]) -> ContractResult {
         db_init(cid, CONSENSUS_CONTRACT_UNSTAKED_COINS_TREE)?;
     }
 
+    // Set up a database tree to hold the staked coins slashed for double-signing
+    // k=Nullifier, v=slot
+    if db_lookup(cid, CONSENSUS_CONTRACT_SLASHED_COINS_TREE).is_err() {
+        db_init(cid, CONSENSUS_CONTRACT_SLASHED_COINS_TREE)?;
+    }
+
     // Set up a database tree for arbitrary data
     let info_db = match db_lookup(cid, CONSENSUS_CONTRACT_INFO_TREE) {
         Ok(v) => v,
@@ -153,6 +179,16 @@ fn init_contract(cid: ContractId, _ix: &[u8]) -> ContractResult {
         }
     };
 
+    // Set the number of epochs an unstake request has to wait before the
+    // coin can be unstaked. Redeploying with an empty payload keeps the
+    // configured value.
+    if !ix.is_empty() {
+        let unstake_delay: u64 = deserialize(ix)?;
+        db_set(info_db, &serialize(&CONSENSUS_CONTRACT_UNSTAKE_DELAY), &serialize(&unstake_delay))?;
+    } else if !db_contains_key(info_db, &serialize(&CONSENSUS_CONTRACT_UNSTAKE_DELAY))? {
+        db_set(info_db, &serialize(&CONSENSUS_CONTRACT_UNSTAKE_DELAY), &serialize(&GRACE_PERIOD))?;
+    }
+
     // Update db version
     db_set(
         info_db,
@@ -198,6 +234,10 @@ fn get_metadata(cid: ContractId, ix: &[u8]) -> ContractResult {
             let metadata = consensus_unstake_get_metadata_v1(cid, call_idx, calls)?;
             Ok(set_return_data(&metadata)?)
         }
+        ConsensusFunction::SlashV1 => {
+            let metadata = consensus_slash_get_metadata_v1(cid, call_idx, calls)?;
+            Ok(set_return_data(&metadata)?)
+        }
     }
 }
 
@@ -238,6 +278,10 @@ fn process_instruction(cid: ContractId, ix: &[u8]) -> ContractResult {
             let update_data = consensus_unstake_process_instruction_v1(cid, call_idx, calls)?;
             Ok(set_return_data(&update_data)?)
         }
+        ConsensusFunction::SlashV1 => {
+            let update_data = consensus_slash_process_instruction_v1(cid, call_idx, calls)?;
+            Ok(set_return_data(&update_data)?)
+        }
     }
 }
 
@@ -261,12 +305,16 @@ fn process_update(cid: ContractId, update_data: &[u8]) -> ContractResult {
             Ok(consensus_proposal_process_update_v1(cid, update)?)
         }
         ConsensusFunction::UnstakeRequestV1 => {
-            let update: ConsensusProposalUpdateV1 = deserialize(&update_data[1..])?;
+            let update: ConsensusUnstakeRequestUpdateV1 = deserialize(&update_data[1..])?;
             Ok(consensus_unstake_request_process_update_v1(cid, update)?)
         }
         ConsensusFunction::UnstakeV1 => {
             let update: ConsensusUnstakeUpdateV1 = deserialize(&update_data[1..])?;
             Ok(consensus_unstake_process_update_v1(cid, update)?)
         }
+        ConsensusFunction::SlashV1 => {
+            let update: ConsensusSlashUpdateV1 = deserialize(&update_data[1..])?;
+            Ok(consensus_slash_process_update_v1(cid, update)?)
+        }
     }
 }
@@ -25,7 +25,7 @@ use darkfi_money_contract::{
 use darkfi_sdk::{
     blockchain::Slot,
     crypto::{pasta_prelude::*, pedersen_commitment_u64, poseidon_hash, ContractId, MerkleNode},
-    db::{db_contains_key, db_lookup, db_set},
+    db::{db_contains_key, db_lookup, db_set, NullifierDomain, NullifierSet},
     error::{ContractError, ContractResult},
     merkle_add, msg,
     pasta::{group::ff::FromUniformBytes, pallas},
@@ -59,6 +59,34 @@ pub(crate) fn consensus_proposal_get_metadata_v1(
     // the VRF proof, and also constrained in ZK by enforcing its derivation.
     let signature_pubkeys = vec![params.input.signature_public];
 
+    // Grab the slot to validate consensus params against
+    let v_slot = get_verifying_slot();
+    let Some(slot) = get_slot(v_slot)? else {
+        msg!("[ConsensusProposalV1] Error: Missing slot {} from db", v_slot);
+        return Err(ConsensusError::ProposalMissingSlot.into())
+    };
+    let slot: Slot = deserialize(&slot)?;
+
+    zk_public_inputs.push((
+        CONSENSUS_CONTRACT_ZKAS_PROPOSAL_NS_V1.to_string(),
+        consensus_proposal_public_inputs_v1(&params, &slot)?,
+    ));
+
+    // Serialize everything gathered and return it
+    let mut metadata = vec![];
+    zk_public_inputs.encode(&mut metadata)?;
+    signature_pubkeys.encode(&mut metadata)?;
+
+    Ok(metadata)
+}
+
+/// Verify the proposal's fork and VRF proof against the given slot, and
+/// return the public inputs of the proposal's ZK proof. Also used to verify
+/// proposals submitted as `Consensus::SlashV1` evidence.
+pub(crate) fn consensus_proposal_public_inputs_v1(
+    params: &ConsensusProposalParamsV1,
+    slot: &Slot,
+) -> Result<Vec<pallas::Base>, ContractError> {
     // Grab the public key coordinates for the burnt coin
     let (pub_x, pub_y) = &params.input.signature_public.xy();
 
@@ -68,13 +96,6 @@ pub(crate) fn consensus_proposal_get_metadata_v1(
     // Grab the pedersen commitment for the minted value
     let output_value_coords = &params.output.value_commit.to_affine().coordinates().unwrap();
 
-    // Grab the slot to validate consensus params against
-    let v_slot = get_verifying_slot();
-    let Some(slot) = get_slot(v_slot)? else {
-        msg!("[ConsensusProposalV1] Error: Missing slot {} from db", v_slot);
-        return Err(ConsensusError::ProposalMissingSlot.into())
-    };
-    let slot: Slot = deserialize(&slot)?;
     let slot_fp = pallas::Base::from(slot.id);
 
     // Verify proposal extends a known fork
@@ -116,36 +137,26 @@ pub(crate) fn consensus_proposal_get_metadata_v1(
     // Grab sigmas from slot
     let (sigma1, sigma2) = (slot.pid.sigma1, slot.pid.sigma2);
 
-    zk_public_inputs.push((
-        CONSENSUS_CONTRACT_ZKAS_PROPOSAL_NS_V1.to_string(),
-        vec![
-            params.input.nullifier.inner(),
-            pallas::Base::from(params.input.epoch),
-            *pub_x,
-            *pub_y,
-            params.input.merkle_root.inner(),
-            *input_value_coords.x(),
-            *input_value_coords.y(),
-            REWARD_PALLAS,
-            *output_value_coords.x(),
-            *output_value_coords.y(),
-            params.output.coin.inner(),
-            mu_y,
-            params.y,
-            mu_rho,
-            params.rho,
-            sigma1,
-            sigma2,
-            HEADSTART,
-        ],
-    ));
-
-    // Serialize everything gathered and return it
-    let mut metadata = vec![];
-    zk_public_inputs.encode(&mut metadata)?;
-    signature_pubkeys.encode(&mut metadata)?;
-
-    Ok(metadata)
+    Ok(vec![
+        params.input.nullifier.inner(),
+        pallas::Base::from(params.input.epoch),
+        *pub_x,
+        *pub_y,
+        params.input.merkle_root.inner(),
+        *input_value_coords.x(),
+        *input_value_coords.y(),
+        REWARD_PALLAS,
+        *output_value_coords.x(),
+        *output_value_coords.y(),
+        params.output.coin.inner(),
+        mu_y,
+        params.y,
+        mu_rho,
+        params.rho,
+        sigma1,
+        sigma2,
+        HEADSTART,
+    ])
 }
 
 /// `process_instruction` function for `Consensus::ProposalV1`
@@ -161,7 +172,7 @@ pub(crate) fn consensus_proposal_process_instruction_v1(
 
     // Access the necessary databases where there is information to
     // validate this state transition.
-    let nullifiers_db = db_lookup(cid, CONSENSUS_CONTRACT_NULLIFIERS_TREE)?;
+    let nullifiers = NullifierSet::lookup(cid, CONSENSUS_CONTRACT_NULLIFIERS_TREE)?;
     let staked_coins_db = db_lookup(cid, CONSENSUS_CONTRACT_STAKED_COINS_TREE)?;
     let staked_coin_roots_db = db_lookup(cid, CONSENSUS_CONTRACT_STAKED_COIN_ROOTS_TREE)?;
 
@@ -188,7 +199,7 @@ pub(crate) fn consensus_proposal_process_instruction_v1(
     }
 
     // The nullifier should not already exist. It is the double-spend protection.
-    if db_contains_key(nullifiers_db, &serialize(&input.nullifier))? {
+    if nullifiers.contains(&input.nullifier)? {
         msg!("[ConsensusProposalV1] Error: Duplicate nullifier found");
         return Err(MoneyError::DuplicateNullifier.into())
     }
@@ -226,12 +237,13 @@ pub(crate) fn consensus_proposal_process_update_v1(
 ) -> ContractResult {
     // Grab all necessary db handles for where we want to write
     let info_db = db_lookup(cid, CONSENSUS_CONTRACT_INFO_TREE)?;
-    let nullifiers_db = db_lookup(cid, CONSENSUS_CONTRACT_NULLIFIERS_TREE)?;
+    let nullifiers = NullifierSet::lookup(cid, CONSENSUS_CONTRACT_NULLIFIERS_TREE)?;
+    let domain = NullifierDomain::new(cid, ConsensusFunction::ProposalV1 as u8);
     let staked_coins_db = db_lookup(cid, CONSENSUS_CONTRACT_STAKED_COINS_TREE)?;
     let staked_coin_roots_db = db_lookup(cid, CONSENSUS_CONTRACT_STAKED_COIN_ROOTS_TREE)?;
 
     msg!("[ConsensusProposalV1] Adding new nullifier to the set");
-    db_set(nullifiers_db, &serialize(&update.nullifier), &[])?;
+    nullifiers.insert(&update.nullifier, &domain)?;
 
     msg!("[ConsensusProposalV1] Adding new coin to the staked coin set");
     db_set(staked_coins_db, &serialize(&update.coin), &[])?;
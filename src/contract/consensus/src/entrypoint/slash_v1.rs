@@ -0,0 +1,189 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_money_contract::{
+    error::MoneyError, CONSENSUS_CONTRACT_NULLIFIERS_TREE, CONSENSUS_CONTRACT_SLASHED_COINS_TREE,
+    CONSENSUS_CONTRACT_STAKED_COINS_TREE, CONSENSUS_CONTRACT_STAKED_COIN_ROOTS_TREE,
+    CONSENSUS_CONTRACT_ZKAS_PROPOSAL_NS_V1,
+};
+use darkfi_sdk::{
+    blockchain::Slot,
+    crypto::{schnorr::SchnorrPublic, ContractId, PublicKey},
+    db::{db_contains_key, db_del, db_lookup, db_set, NullifierDomain, NullifierSet},
+    error::{ContractError, ContractResult},
+    msg,
+    pasta::pallas,
+    util::{get_slot, get_verifying_slot},
+    ContractCall,
+};
+use darkfi_serial::{deserialize, serialize, Encodable, WriteExt};
+
+use super::proposal_v1::consensus_proposal_public_inputs_v1;
+use crate::{
+    error::ConsensusError,
+    model::{ConsensusSlashParamsV1, ConsensusSlashUpdateV1},
+    ConsensusFunction,
+};
+
+/// `get_metadata` function for `Consensus::SlashV1`
+pub(crate) fn consensus_slash_get_metadata_v1(
+    _cid: ContractId,
+    call_idx: u32,
+    calls: Vec<ContractCall>,
+) -> Result<Vec<u8>, ContractError> {
+    let self_ = &calls[call_idx as usize];
+    let params: ConsensusSlashParamsV1 = deserialize(&self_.data[1..])?;
+
+    // Public inputs for the ZK proofs we have to verify. Each piece of
+    // evidence carries the block producer's proposal, whose proof binds
+    // the header signing key to a staked coin and its nullifier.
+    let mut zk_public_inputs: Vec<(String, Vec<pallas::Base>)> = vec![];
+    // Evidence can be submitted by anyone, so there are no transaction
+    // signatures to verify here. The header signatures are verified in
+    // `process_instruction`.
+    let signature_pubkeys: Vec<PublicKey> = vec![];
+
+    for evidence in [&params.first, &params.second] {
+        // Grab the slot the proposal was made for
+        let Some(slot) = get_slot(evidence.header.slot)? else {
+            msg!("[ConsensusSlashV1] Error: Missing slot {} from db", evidence.header.slot);
+            return Err(ConsensusError::ProposalMissingSlot.into())
+        };
+        let slot: Slot = deserialize(&slot)?;
+
+        zk_public_inputs.push((
+            CONSENSUS_CONTRACT_ZKAS_PROPOSAL_NS_V1.to_string(),
+            consensus_proposal_public_inputs_v1(&evidence.proposal, &slot)?,
+        ));
+    }
+
+    // Serialize everything gathered and return it
+    let mut metadata = vec![];
+    zk_public_inputs.encode(&mut metadata)?;
+    signature_pubkeys.encode(&mut metadata)?;
+    Ok(metadata)
+}
+
+/// `process_instruction` function for `Consensus::SlashV1`
+pub(crate) fn consensus_slash_process_instruction_v1(
+    cid: ContractId,
+    call_idx: u32,
+    calls: Vec<ContractCall>,
+) -> Result<Vec<u8>, ContractError> {
+    let self_ = &calls[call_idx as usize];
+    let params: ConsensusSlashParamsV1 = deserialize(&self_.data[1..])?;
+    let first = &params.first;
+    let second = &params.second;
+
+    // Access the necessary databases where there is information to
+    // validate this state transition.
+    let staked_coin_roots_db = db_lookup(cid, CONSENSUS_CONTRACT_STAKED_COIN_ROOTS_TREE)?;
+    let slashed_coins_db = db_lookup(cid, CONSENSUS_CONTRACT_SLASHED_COINS_TREE)?;
+
+    msg!("[ConsensusSlashV1] Validating slashing evidence");
+
+    // Both headers have to be made for the same slot, which can't be
+    // in the future.
+    let slot = first.header.slot;
+    if slot != second.header.slot || slot > get_verifying_slot() {
+        msg!("[ConsensusSlashV1] Error: Headers are not for the same past slot");
+        return Err(ConsensusError::SlashInvalidEvidence.into())
+    }
+
+    // Both proposals have to be made by the same staker, using the same
+    // staked coin.
+    let input = &first.proposal.input;
+    if input.signature_public != second.proposal.input.signature_public ||
+        input.nullifier != second.proposal.input.nullifier
+    {
+        msg!("[ConsensusSlashV1] Error: Proposals are not from the same staker");
+        return Err(ConsensusError::SlashInvalidEvidence.into())
+    }
+
+    // The staked coin has to exist in a previous state. Along with the
+    // proposal proofs, this binds the signing key to a real stake.
+    for evidence in [first, second] {
+        let merkle_root = &evidence.proposal.input.merkle_root;
+        if !db_contains_key(staked_coin_roots_db, &serialize(merkle_root))? {
+            msg!("[ConsensusSlashV1] Error: Merkle root not found in previous state");
+            return Err(MoneyError::TransferMerkleRootNotFound.into())
+        }
+    }
+
+    // The headers have to conflict
+    let (first_hash, second_hash) = (first.header.headerhash(), second.header.headerhash());
+    if first_hash == second_hash {
+        msg!("[ConsensusSlashV1] Error: Headers don't conflict");
+        return Err(ConsensusError::SlashInvalidEvidence.into())
+    }
+
+    // Both headers have to be signed by the staker, the same way block
+    // producers sign the headers of their proposals.
+    for (evidence, hash) in [(first, first_hash), (second, second_hash)] {
+        if !input.signature_public.verify(hash.as_bytes(), &evidence.signature) {
+            msg!("[ConsensusSlashV1] Error: Header signature couldn't be verified");
+            return Err(ConsensusError::SlashInvalidSignature.into())
+        }
+    }
+
+    // The offender can only be slashed once for their staked coin
+    if db_contains_key(slashed_coins_db, &serialize(&input.nullifier))? {
+        msg!("[ConsensusSlashV1] Error: Coin has already been slashed");
+        return Err(ConsensusError::SlashCoinAlreadySlashed.into())
+    }
+
+    // At this point the state transition has passed, so we create a state update
+    let update = ConsensusSlashUpdateV1 {
+        nullifier: input.nullifier,
+        coins: vec![first.proposal.output.coin, second.proposal.output.coin],
+        slot,
+    };
+    let mut update_data = vec![];
+    update_data.write_u8(ConsensusFunction::SlashV1 as u8)?;
+    update.encode(&mut update_data)?;
+    Ok(update_data)
+}
+
+/// `process_update` function for `Consensus::SlashV1`
+pub(crate) fn consensus_slash_process_update_v1(
+    cid: ContractId,
+    update: ConsensusSlashUpdateV1,
+) -> ContractResult {
+    // Grab all necessary db handles for where we want to write
+    let nullifiers = NullifierSet::lookup(cid, CONSENSUS_CONTRACT_NULLIFIERS_TREE)?;
+    let domain = NullifierDomain::new(cid, ConsensusFunction::SlashV1 as u8);
+    let staked_coins_db = db_lookup(cid, CONSENSUS_CONTRACT_STAKED_COINS_TREE)?;
+    let slashed_coins_db = db_lookup(cid, CONSENSUS_CONTRACT_SLASHED_COINS_TREE)?;
+
+    // If none of the conflicting proposals made it on chain, the staked
+    // coin is still spendable, so we burn it by revealing its nullifier.
+    if !nullifiers.contains(&update.nullifier)? {
+        msg!("[ConsensusSlashV1] Burning the offender's staked coin");
+        nullifiers.insert(&update.nullifier, &domain)?;
+    }
+
+    msg!("[ConsensusSlashV1] Removing proposal coins from the staked coin set");
+    for coin in &update.coins {
+        db_del(staked_coins_db, &serialize(coin))?;
+    }
+
+    msg!("[ConsensusSlashV1] Recording slashed coin");
+    db_set(slashed_coins_db, &serialize(&update.nullifier), &serialize(&update.slot))?;
+
+    Ok(())
+}
@@ -27,7 +27,7 @@ use darkfi_money_contract::{
 };
 use darkfi_sdk::{
     crypto::{pasta_prelude::*, ContractId, MerkleNode, PublicKey, MONEY_CONTRACT_ID},
-    db::{db_contains_key, db_lookup, db_set},
+    db::{db_contains_key, db_lookup, db_set, NullifierDomain, NullifierSet},
     error::{ContractError, ContractResult},
     merkle_add, msg,
     pasta::pallas,
@@ -116,7 +116,8 @@ pub(crate) fn consensus_stake_process_instruction_v1(
     // validate this state transition.
     let consensus_coins_db = db_lookup(cid, CONSENSUS_CONTRACT_STAKED_COINS_TREE)?;
     let consensus_unstaked_coins_db = db_lookup(cid, CONSENSUS_CONTRACT_UNSTAKED_COINS_TREE)?;
-    let money_nullifiers_db = db_lookup(*MONEY_CONTRACT_ID, MONEY_CONTRACT_NULLIFIERS_TREE)?;
+    let money_nullifiers =
+        NullifierSet::lookup(*MONEY_CONTRACT_ID, MONEY_CONTRACT_NULLIFIERS_TREE)?;
     let money_coin_roots_db = db_lookup(*MONEY_CONTRACT_ID, MONEY_CONTRACT_COIN_ROOTS_TREE)?;
 
     // ===================================
@@ -140,8 +141,10 @@ pub(crate) fn consensus_stake_process_instruction_v1(
         return Err(MoneyError::TransferMerkleRootNotFound.into())
     }
 
-    // The nullifiers should not already exist. It is the double-mint protection.
-    if !db_contains_key(money_nullifiers_db, &serialize(&input.nullifier))? {
+    // The nullifier should have been revealed by the preceding Money::StakeV1
+    // call. Nullifiers revealed by any other Money call can't be staked.
+    let domain = NullifierDomain::new(*MONEY_CONTRACT_ID, MoneyFunction::StakeV1 as u8);
+    if !money_nullifiers.was_revealed_by(&input.nullifier, &domain)? {
         msg!("[ConsensusStakeV1] Error: Missing nullifier");
         return Err(MoneyError::StakeMissingNullifier.into())
     }
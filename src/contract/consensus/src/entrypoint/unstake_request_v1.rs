@@ -25,7 +25,7 @@ use darkfi_money_contract::{
 };
 use darkfi_sdk::{
     crypto::{pasta_prelude::*, ContractId, MerkleNode},
-    db::{db_contains_key, db_lookup, db_set},
+    db::{db_contains_key, db_lookup, db_set, NullifierDomain, NullifierSet},
     error::{ContractError, ContractResult},
     merkle_add, msg,
     pasta::pallas,
@@ -36,7 +36,7 @@ use darkfi_serial::{deserialize, serialize, Encodable, WriteExt};
 
 use crate::{
     error::ConsensusError,
-    model::{ConsensusProposalUpdateV1, GRACE_PERIOD},
+    model::{ConsensusUnstakeRequestUpdateV1, GRACE_PERIOD},
     ConsensusFunction,
 };
 
@@ -109,7 +109,7 @@ pub(crate) fn consensus_unstake_request_process_instruction_v1(
 
     // Access the necessary databases where there is information to
     // validate this state transition.
-    let nullifiers_db = db_lookup(cid, CONSENSUS_CONTRACT_NULLIFIERS_TREE)?;
+    let nullifiers = NullifierSet::lookup(cid, CONSENSUS_CONTRACT_NULLIFIERS_TREE)?;
     let unstaked_coins_db = db_lookup(cid, CONSENSUS_CONTRACT_UNSTAKED_COINS_TREE)?;
     let staked_coins_roots_db = db_lookup(cid, CONSENSUS_CONTRACT_STAKED_COIN_ROOTS_TREE)?;
 
@@ -133,7 +133,7 @@ pub(crate) fn consensus_unstake_request_process_instruction_v1(
     }
 
     // The nullifiers should not already exist. It is the double-spend protection.
-    if db_contains_key(nullifiers_db, &serialize(&input.nullifier))? {
+    if nullifiers.contains(&input.nullifier)? {
         msg!("[ConsensusUnstakeRequestV1] Error: Duplicate nullifier found");
         return Err(MoneyError::DuplicateNullifier.into())
     }
@@ -154,7 +154,13 @@ pub(crate) fn consensus_unstake_request_process_instruction_v1(
     }
 
     // At this point the state transition has passed, so we create a state update
-    let update = ConsensusProposalUpdateV1 { nullifier: input.nullifier, coin: output.coin };
+    // The request epoch is what the unstake timelock counts from. It is
+    // also committed to in the unstaked coin by the mint proof.
+    let update = ConsensusUnstakeRequestUpdateV1 {
+        nullifier: input.nullifier,
+        coin: output.coin,
+        epoch: get_verifying_slot_epoch(),
+    };
     let mut update_data = vec![];
     update_data.write_u8(ConsensusFunction::UnstakeRequestV1 as u8)?;
     update.encode(&mut update_data)?;
@@ -164,19 +170,20 @@ pub(crate) fn consensus_unstake_request_process_instruction_v1(
 /// `process_update` function for `Consensus::UnstakeRequestV1`
 pub(crate) fn consensus_unstake_request_process_update_v1(
     cid: ContractId,
-    update: ConsensusProposalUpdateV1,
+    update: ConsensusUnstakeRequestUpdateV1,
 ) -> ContractResult {
     // Grab all necessary db handles for where we want to write
     let info_db = db_lookup(cid, CONSENSUS_CONTRACT_INFO_TREE)?;
-    let nullifiers_db = db_lookup(cid, CONSENSUS_CONTRACT_NULLIFIERS_TREE)?;
+    let nullifiers = NullifierSet::lookup(cid, CONSENSUS_CONTRACT_NULLIFIERS_TREE)?;
+    let domain = NullifierDomain::new(cid, ConsensusFunction::UnstakeRequestV1 as u8);
     let unstaked_coins_db = db_lookup(cid, CONSENSUS_CONTRACT_UNSTAKED_COINS_TREE)?;
     let unstaked_coin_roots_db = db_lookup(cid, CONSENSUS_CONTRACT_UNSTAKED_COIN_ROOTS_TREE)?;
 
     msg!("[ConsensusUnstakeRequestV1] Adding new nullifier to the set");
-    db_set(nullifiers_db, &serialize(&update.nullifier), &[])?;
+    nullifiers.insert(&update.nullifier, &domain)?;
 
     msg!("[ConsensusUnstakeRequestV1] Adding new coin to the unstaked coins set");
-    db_set(unstaked_coins_db, &serialize(&update.coin), &[])?;
+    db_set(unstaked_coins_db, &serialize(&update.coin), &serialize(&update.epoch))?;
 
     msg!("[ConsensusUnstakeRequestV1] Adding new coin to the unstaked coins Merkle tree");
     let coins: Vec<_> = vec![MerkleNode::from(update.coin.inner())];
@@ -19,12 +19,13 @@
 use darkfi_money_contract::{
     error::MoneyError,
     model::{ConsensusUnstakeParamsV1, ConsensusUnstakeUpdateV1, MoneyUnstakeParamsV1},
-    MoneyFunction, CONSENSUS_CONTRACT_NULLIFIERS_TREE, CONSENSUS_CONTRACT_UNSTAKED_COIN_ROOTS_TREE,
+    MoneyFunction, CONSENSUS_CONTRACT_INFO_TREE, CONSENSUS_CONTRACT_NULLIFIERS_TREE,
+    CONSENSUS_CONTRACT_UNSTAKED_COIN_ROOTS_TREE, CONSENSUS_CONTRACT_UNSTAKE_DELAY,
     CONSENSUS_CONTRACT_ZKAS_BURN_NS_V1,
 };
 use darkfi_sdk::{
     crypto::{pasta_prelude::*, ContractId, MONEY_CONTRACT_ID},
-    db::{db_contains_key, db_lookup, db_set},
+    db::{db_contains_key, db_get, db_lookup, NullifierDomain, NullifierSet},
     error::{ContractError, ContractResult},
     msg,
     pasta::pallas,
@@ -90,8 +91,9 @@ pub(crate) fn consensus_unstake_process_instruction_v1(
 
     // Access the necessary databases where there is information to
     // validate this state transition.
-    let nullifiers_db = db_lookup(cid, CONSENSUS_CONTRACT_NULLIFIERS_TREE)?;
+    let nullifiers = NullifierSet::lookup(cid, CONSENSUS_CONTRACT_NULLIFIERS_TREE)?;
     let unstaked_coin_roots_db = db_lookup(cid, CONSENSUS_CONTRACT_UNSTAKED_COIN_ROOTS_TREE)?;
+    let info_db = db_lookup(cid, CONSENSUS_CONTRACT_INFO_TREE)?;
 
     // ===================================
     // Perform the actual state transition
@@ -124,10 +126,19 @@ pub(crate) fn consensus_unstake_process_instruction_v1(
     }
 
     msg!("[ConsensusUnstakeV1] Validating anonymous input");
-    // The coin has passed through the grace period and is allowed to get unstaked.
-    if get_verifying_slot_epoch() - input.epoch <= GRACE_PERIOD {
-        msg!("[ConsensusUnstakeV1] Error: Coin is not allowed to get unstaked yet");
-        return Err(ConsensusError::CoinStillInGracePeriod.into())
+    // The coin has to wait out the unstake timelock, counted from the epoch
+    // its unstake was requested in, which the burn proof reveals.
+    let unstake_delay = match db_get(info_db, &serialize(&CONSENSUS_CONTRACT_UNSTAKE_DELAY))? {
+        Some(v) => deserialize(&v)?,
+        None => GRACE_PERIOD,
+    };
+    let elapsed = get_verifying_slot_epoch().saturating_sub(input.epoch);
+    if elapsed <= unstake_delay {
+        msg!(
+            "[ConsensusUnstakeV1] Error: Coin is timelocked for {} more epochs",
+            unstake_delay - elapsed + 1
+        );
+        return Err(ConsensusError::UnstakeTimelocked.into())
     }
 
     // The Merkle root is used to know whether this is an unstaked coin that
@@ -138,7 +149,7 @@ pub(crate) fn consensus_unstake_process_instruction_v1(
     }
 
     // The nullifiers should not already exist. It is the double-spend protection.
-    if db_contains_key(nullifiers_db, &serialize(&input.nullifier))? {
+    if nullifiers.contains(&input.nullifier)? {
         msg!("[ConsensusUnstakeV1] Error: Duplicate nullifier found");
         return Err(MoneyError::DuplicateNullifier.into())
     }
@@ -157,10 +168,11 @@ pub(crate) fn consensus_unstake_process_update_v1(
     update: ConsensusUnstakeUpdateV1,
 ) -> ContractResult {
     // Grab all necessary db handles for where we want to write
-    let nullifiers_db = db_lookup(cid, CONSENSUS_CONTRACT_NULLIFIERS_TREE)?;
+    let nullifiers = NullifierSet::lookup(cid, CONSENSUS_CONTRACT_NULLIFIERS_TREE)?;
+    let domain = NullifierDomain::new(cid, ConsensusFunction::UnstakeV1 as u8);
 
     msg!("[ConsensusUnstakeV1] Adding new nullifier to the set");
-    db_set(nullifiers_db, &serialize(&update.nullifier), &[])?;
+    nullifiers.insert(&update.nullifier, &domain)?;
 
     Ok(())
 }
@@ -34,6 +34,18 @@ pub enum ConsensusError {
 
     #[error("Coin doesn't exist in unstake set")]
     CoinNotInUnstakeSet,
+
+    #[error("Unstake request is still timelocked")]
+    UnstakeTimelocked,
+
+    #[error("Slashing evidence is not a valid double-sign")]
+    SlashInvalidEvidence,
+
+    #[error("Slashing evidence signature couldn't be verified")]
+    SlashInvalidSignature,
+
+    #[error("Coin has already been slashed")]
+    SlashCoinAlreadySlashed,
 }
 
 impl From<ConsensusError> for ContractError {
@@ -44,6 +56,10 @@ impl From<ConsensusError> for ContractError {
             ConsensusError::ProposalErroneousVrfProof => Self::Custom(3),
             ConsensusError::CoinStillInGracePeriod => Self::Custom(4),
             ConsensusError::CoinNotInUnstakeSet => Self::Custom(5),
+            ConsensusError::UnstakeTimelocked => Self::Custom(6),
+            ConsensusError::SlashInvalidEvidence => Self::Custom(7),
+            ConsensusError::SlashInvalidSignature => Self::Custom(8),
+            ConsensusError::SlashCoinAlreadySlashed => Self::Custom(9),
         }
     }
 }
@@ -29,6 +29,7 @@ pub enum ConsensusFunction {
     ProposalV1 = 0x02,
     UnstakeRequestV1 = 0x03,
     UnstakeV1 = 0x04,
+    SlashV1 = 0x05,
 }
 
 impl TryFrom<u8> for ConsensusFunction {
@@ -41,6 +42,7 @@ impl TryFrom<u8> for ConsensusFunction {
             0x02 => Ok(Self::ProposalV1),
             0x03 => Ok(Self::UnstakeRequestV1),
             0x04 => Ok(Self::UnstakeV1),
+            0x05 => Ok(Self::SlashV1),
             _ => Err(ContractError::InvalidFunction),
         }
     }
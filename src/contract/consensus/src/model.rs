@@ -18,16 +18,16 @@
 
 use darkfi_money_contract::model::{ClearInput, Coin, ConsensusInput, ConsensusOutput, Output};
 use darkfi_sdk::{
-    crypto::{ecvrf::VrfProof, Nullifier},
+    crypto::{ecvrf::VrfProof, schnorr::Signature, MerkleNode, Nullifier},
     pasta::pallas,
 };
-use darkfi_serial::{SerialDecodable, SerialEncodable};
+use darkfi_serial::{serialize, SerialDecodable, SerialEncodable, SerialSchema};
 
 #[cfg(feature = "client")]
 use darkfi_serial::async_trait;
 
 /// Parameters for `Consensus::GenesisStake`
-#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable, SerialSchema)]
 // ANCHOR: ConsensusGenesisStakeParams
 pub struct ConsensusGenesisStakeParamsV1 {
     /// Clear input
@@ -38,7 +38,7 @@ pub struct ConsensusGenesisStakeParamsV1 {
 // ANCHOR_END: ConsensusGenesisStakeParams
 
 /// Parameters for `Consensus::Proposal`
-#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable, SerialSchema)]
 // ANCHOR: ConsensusProposalParams
 pub struct ConsensusProposalParamsV1 {
     /// Anonymous input
@@ -63,7 +63,7 @@ pub struct ConsensusProposalParamsV1 {
 // ANCHOR_END: ConsensusProposalParams
 
 /// State update for `Consensus::Proposal`
-#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable, SerialSchema)]
 // ANCHOR: ConsensusProposalUpdate
 pub struct ConsensusProposalUpdateV1 {
     /// Revealed nullifier
@@ -74,7 +74,7 @@ pub struct ConsensusProposalUpdateV1 {
 // ANCHOR_END: ConsensusProposalUpdate
 
 /// Parameters for `Consensus::UnstakeRequest`
-#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable, SerialSchema)]
 // ANCHOR: ConsensusUnstakeRequestParams
 pub struct ConsensusUnstakeRequestParamsV1 {
     /// Burnt token revealed info
@@ -84,6 +84,84 @@ pub struct ConsensusUnstakeRequestParamsV1 {
 }
 // ANCHOR_END: ConsensusUnstakeRequestParams
 
+/// State update for `Consensus::UnstakeRequest`
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable, SerialSchema)]
+// ANCHOR: ConsensusUnstakeRequestUpdate
+pub struct ConsensusUnstakeRequestUpdateV1 {
+    /// Revealed nullifier
+    pub nullifier: Nullifier,
+    /// The newly minted unstaked coin
+    pub coin: Coin,
+    /// Epoch the unstake was requested in
+    pub epoch: u64,
+}
+// ANCHOR_END: ConsensusUnstakeRequestUpdate
+
+/// Block header as signed by a block producer. It is encoded the same
+/// way as the node's block `Header`, so its hash is the header hash the
+/// producer signs when proposing a block.
+#[derive(Clone, Debug, PartialEq, SerialEncodable, SerialDecodable, SerialSchema)]
+// ANCHOR: ConsensusSlashHeader
+pub struct ConsensusSlashHeaderV1 {
+    /// Block version
+    pub version: u8,
+    /// Previous block hash
+    pub previous: blake3::Hash,
+    /// Epoch
+    pub epoch: u64,
+    /// Slot UID
+    pub slot: u64,
+    /// Block creation timestamp
+    pub timestamp: u64,
+    /// Root of the transaction hashes merkle tree
+    pub root: MerkleNode,
+}
+// ANCHOR_END: ConsensusSlashHeader
+
+impl ConsensusSlashHeaderV1 {
+    /// Calculate the header hash
+    pub fn headerhash(&self) -> blake3::Hash {
+        blake3::hash(&serialize(self))
+    }
+}
+
+/// A signed block header used as evidence in `Consensus::Slash`
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable, SerialSchema)]
+// ANCHOR: ConsensusSlashEvidence
+pub struct ConsensusSlashEvidenceV1 {
+    /// Header of the proposed block
+    pub header: ConsensusSlashHeaderV1,
+    /// Block producer signature over the header hash
+    pub signature: Signature,
+    /// The block producer's proposal call parameters
+    pub proposal: ConsensusProposalParamsV1,
+}
+// ANCHOR_END: ConsensusSlashEvidence
+
+/// Parameters for `Consensus::Slash`
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable, SerialSchema)]
+// ANCHOR: ConsensusSlashParams
+pub struct ConsensusSlashParamsV1 {
+    /// First signed block header
+    pub first: ConsensusSlashEvidenceV1,
+    /// Second signed block header, conflicting with the first one
+    pub second: ConsensusSlashEvidenceV1,
+}
+// ANCHOR_END: ConsensusSlashParams
+
+/// State update for `Consensus::Slash`
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable, SerialSchema)]
+// ANCHOR: ConsensusSlashUpdate
+pub struct ConsensusSlashUpdateV1 {
+    /// Nullifier of the offender's staked coin
+    pub nullifier: Nullifier,
+    /// Coins minted by the conflicting proposals
+    pub coins: Vec<Coin>,
+    /// Slot the offender double-signed in
+    pub slot: u64,
+}
+// ANCHOR_END: ConsensusSlashUpdate
+
 // ======================================================================
 // Consensus parameters configuration
 // NOTE: In case of changes, always verify that the `pallas::Base` consts
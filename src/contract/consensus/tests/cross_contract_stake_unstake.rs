@@ -0,0 +1,274 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Integration test vectors for the cross-contract staking protocol.
+//!
+//! Alice receives native tokens with a `Money::Transfer`, stakes them
+//! with `Money::Stake` + `Consensus::Stake`, proposes a block, and gets
+//! them back into the Money contract with `Consensus::UnstakeRequest`
+//! followed by `Consensus::Unstake` + `Money::Unstake`, after which the
+//! coin can be transferred again.
+//! The following malicious cases are also tested:
+//!     1. Staking a coin already spent by `Money::Transfer`
+//!     2. Staking a coin with a non-zero spend hook
+//!     3. Staking with mismatching value commitments
+//!     4. Replaying the staked input
+//!     5. Unstaking with mismatching value commitments
+//!     6. Replaying the unstaked input
+//!
+//! Tampered calls aren't re-signed, since contract execution happens
+//! before signature verification and must already reject them.
+
+use darkfi::{tx::Transaction, Result};
+use darkfi_serial::{deserialize, Decodable, Encodable};
+use log::info;
+
+use darkfi_consensus_contract::model::{calculate_grace_period, EPOCH_LENGTH, REWARD};
+use darkfi_contract_test_harness::{init_logger, Holder, TestHarness, TxAction};
+use darkfi_money_contract::model::{
+    ConsensusStakeParamsV1, MoneyStakeParamsV1, MoneyUnstakeParamsV1,
+};
+use darkfi_sdk::{
+    crypto::{pedersen_commitment_u64, DAO_CONTRACT_ID, DARK_TOKEN_ID},
+    pasta::pallas,
+};
+
+/// Decode the params of the given call, modify them and encode them back.
+fn tamper_call<P: Decodable + Encodable>(
+    tx: &mut Transaction,
+    call_idx: usize,
+    tamper: impl FnOnce(&mut P),
+) -> Result<()> {
+    let call = &mut tx.calls[call_idx];
+    let mut params: P = deserialize(&call.data[1..])?;
+    tamper(&mut params);
+
+    let mut data = vec![call.data[0]];
+    params.encode(&mut data)?;
+    call.data = data;
+
+    Ok(())
+}
+
+#[test]
+fn consensus_contract_cross_contract_stake_unstake() -> Result<()> {
+    smol::block_on(async {
+        init_logger();
+
+        // Holders this test will use
+        const HOLDERS: [Holder; 2] = [Holder::Faucet, Holder::Alice];
+
+        // Some numbers we want to assert
+        const ALICE_AIRDROP: u64 = 1000;
+
+        // Slot to verify against
+        let mut current_slot = 1;
+
+        // Initialize harness
+        let mut th = TestHarness::new(&["money".to_string(), "consensus".to_string()]).await?;
+
+        // Alice airdrops some native tokens to herself
+        let alice_airdrop_oc =
+            th.execute_airdrop(&HOLDERS, &Holder::Alice, ALICE_AIRDROP, current_slot).await?;
+
+        // And moves them to a fresh coin with a Money::Transfer
+        info!(target: "consensus", "[Alice] ====================");
+        info!(target: "consensus", "[Alice] Building transfer tx");
+        info!(target: "consensus", "[Alice] ====================");
+        let (transfer_tx, transfer_params, spent_coins) = th.transfer(
+            ALICE_AIRDROP,
+            &Holder::Alice,
+            &Holder::Alice,
+            &[alice_airdrop_oc.clone()],
+            *DARK_TOKEN_ID,
+        )?;
+        assert!(transfer_params.inputs.len() == 1);
+        assert!(transfer_params.outputs.len() == 1);
+        assert!(spent_coins == vec![alice_airdrop_oc.clone()]);
+
+        for holder in &HOLDERS {
+            info!(target: "consensus", "[{holder:?}] ===========================");
+            info!(target: "consensus", "[{holder:?}] Executing Alice transfer tx");
+            info!(target: "consensus", "[{holder:?}] ===========================");
+            let write = holder == &Holder::Faucet;
+            th.execute_transfer_tx(holder, &transfer_tx, &transfer_params, current_slot, write)
+                .await?;
+        }
+        let alice_oc = th.gather_owncoin_at_index(&Holder::Alice, &transfer_params.outputs, 0)?;
+        th.assert_trees(&HOLDERS);
+        assert!(alice_oc.note.value == ALICE_AIRDROP);
+
+        info!(target: "consensus", "[Malicious] =======================================");
+        info!(target: "consensus", "[Malicious] Checking staking coin spent by transfer");
+        info!(target: "consensus", "[Malicious] =======================================");
+        let (stake_tx, _, _) = th
+            .stake(&Holder::Alice, current_slot, &alice_airdrop_oc, pallas::Base::from(15))
+            .await?;
+        th.execute_erroneous_txs(
+            TxAction::ConsensusStake,
+            &Holder::Alice,
+            &[stake_tx],
+            current_slot,
+            1,
+        )
+        .await?;
+
+        info!(target: "consensus", "[Malicious] =======================================");
+        info!(target: "consensus", "[Malicious] Checking staking coin with a spend hook");
+        info!(target: "consensus", "[Malicious] =======================================");
+        // Pretend the coin is locked by the DAO contract. Both calls are
+        // tampered so they still agree on the input.
+        let spend_hook = DAO_CONTRACT_ID.inner();
+        let (mut stake_tx, _, _) =
+            th.stake(&Holder::Alice, current_slot, &alice_oc, pallas::Base::from(15)).await?;
+        tamper_call(&mut stake_tx, 0, |p: &mut MoneyStakeParamsV1| {
+            p.input.spend_hook = spend_hook
+        })?;
+        tamper_call(&mut stake_tx, 1, |p: &mut ConsensusStakeParamsV1| {
+            p.input.spend_hook = spend_hook
+        })?;
+        th.execute_erroneous_txs(
+            TxAction::ConsensusStake,
+            &Holder::Alice,
+            &[stake_tx],
+            current_slot,
+            1,
+        )
+        .await?;
+
+        info!(target: "consensus", "[Malicious] ====================================");
+        info!(target: "consensus", "[Malicious] Checking staking with value mismatch");
+        info!(target: "consensus", "[Malicious] ====================================");
+        let (mut stake_tx, _, _) =
+            th.stake(&Holder::Alice, current_slot, &alice_oc, pallas::Base::from(15)).await?;
+        tamper_call(&mut stake_tx, 1, |p: &mut ConsensusStakeParamsV1| {
+            p.output.value_commit += pedersen_commitment_u64(1, pallas::Scalar::from(1))
+        })?;
+        th.execute_erroneous_txs(
+            TxAction::ConsensusStake,
+            &Holder::Alice,
+            &[stake_tx],
+            current_slot,
+            1,
+        )
+        .await?;
+
+        // Now Alice can stake her transferred owncoin
+        let alice_staked_oc =
+            th.execute_stake(&HOLDERS, &Holder::Alice, current_slot, &alice_oc, 489).await?;
+
+        info!(target: "consensus", "[Malicious] ===============================");
+        info!(target: "consensus", "[Malicious] Checking replaying staked input");
+        info!(target: "consensus", "[Malicious] ===============================");
+        let (stake_tx, _, _) =
+            th.stake(&Holder::Alice, current_slot, &alice_oc, pallas::Base::from(489)).await?;
+        th.execute_erroneous_txs(
+            TxAction::ConsensusStake,
+            &Holder::Alice,
+            &[stake_tx],
+            current_slot,
+            1,
+        )
+        .await?;
+
+        // We progress after grace period
+        current_slot += (calculate_grace_period() * EPOCH_LENGTH) + EPOCH_LENGTH;
+        let slot = th.generate_slot(current_slot).await?;
+
+        // Alice becomes the slot proposer and gets rewarded
+        let alice_rewarded_staked_oc = th
+            .execute_proposal(&HOLDERS, &Holder::Alice, current_slot, slot, &alice_staked_oc)
+            .await?;
+
+        // We progress one slot
+        current_slot += 1;
+        th.generate_slot(current_slot).await?;
+
+        // Alice requests for her owncoin to get unstaked
+        let alice_unstake_request_oc = th
+            .execute_unstake_request(
+                &HOLDERS,
+                &Holder::Alice,
+                current_slot,
+                &alice_rewarded_staked_oc,
+            )
+            .await?;
+
+        // We progress after grace period
+        current_slot += (calculate_grace_period() * EPOCH_LENGTH) + EPOCH_LENGTH;
+
+        info!(target: "consensus", "[Malicious] ======================================");
+        info!(target: "consensus", "[Malicious] Checking unstaking with value mismatch");
+        info!(target: "consensus", "[Malicious] ======================================");
+        let (mut unstake_tx, _, _) = th.unstake(&Holder::Alice, &alice_unstake_request_oc)?;
+        tamper_call(&mut unstake_tx, 1, |p: &mut MoneyUnstakeParamsV1| {
+            p.output.value_commit += pedersen_commitment_u64(1, pallas::Scalar::from(1))
+        })?;
+        th.execute_erroneous_txs(
+            TxAction::ConsensusUnstake,
+            &Holder::Alice,
+            &[unstake_tx],
+            current_slot,
+            1,
+        )
+        .await?;
+
+        // Now Alice can unstake her owncoin back into the Money contract
+        let alice_unstaked_oc = th
+            .execute_unstake(&HOLDERS, &Holder::Alice, current_slot, &alice_unstake_request_oc)
+            .await?;
+        assert!(alice_unstaked_oc.note.value == ALICE_AIRDROP + REWARD);
+
+        info!(target: "consensus", "[Malicious] =================================");
+        info!(target: "consensus", "[Malicious] Checking replaying unstaked input");
+        info!(target: "consensus", "[Malicious] =================================");
+        let (unstake_tx, _, _) = th.unstake(&Holder::Alice, &alice_unstake_request_oc)?;
+        th.execute_erroneous_txs(
+            TxAction::ConsensusUnstake,
+            &Holder::Alice,
+            &[unstake_tx],
+            current_slot,
+            1,
+        )
+        .await?;
+
+        // The unstaked coin is a regular Money coin again, so Alice can
+        // transfer it, closing the round trip.
+        let (transfer_tx, transfer_params, _) = th.transfer(
+            alice_unstaked_oc.note.value,
+            &Holder::Alice,
+            &Holder::Alice,
+            &[alice_unstaked_oc],
+            *DARK_TOKEN_ID,
+        )?;
+        for holder in &HOLDERS {
+            let write = holder == &Holder::Faucet;
+            th.execute_transfer_tx(holder, &transfer_tx, &transfer_params, current_slot, write)
+                .await?;
+        }
+        let alice_oc = th.gather_owncoin_at_index(&Holder::Alice, &transfer_params.outputs, 0)?;
+        th.assert_trees(&HOLDERS);
+        assert!(alice_oc.note.value == ALICE_AIRDROP + REWARD);
+
+        // Statistics
+        th.statistics();
+
+        // Thanks for reading
+        Ok(())
+    })
+}
@@ -0,0 +1,137 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Integration test of consensus slashing for a double-signing Alice.
+//!
+//! We first airdrop Alice native tokens and she stakes them. Once the
+//! grace period has passed she signs two conflicting block headers for
+//! the same slot, and the Faucet submits them as evidence to slash her.
+//! The following malicious cases are also tested:
+//!     1. Evidence made of the same header twice
+//!     2. Evidence with a forged signature
+//!     3. Repeat slashing coin
+//!     4. Use slashed coin in proposal
+//!
+//! With this test, we want to confirm the consensus contract slashing
+//! state transitions work and are able to be verified.
+
+use darkfi::Result;
+use log::info;
+
+use darkfi_consensus_contract::model::{calculate_grace_period, EPOCH_LENGTH};
+use darkfi_contract_test_harness::{init_logger, Holder, TestHarness, TxAction};
+
+#[test]
+fn consensus_contract_slash() -> Result<()> {
+    smol::block_on(async {
+        init_logger();
+
+        // Holders this test will use
+        const HOLDERS: [Holder; 2] = [Holder::Faucet, Holder::Alice];
+
+        // Some numbers we want to assert
+        const ALICE_AIRDROP: u64 = 1000;
+
+        // Slot to verify against
+        let mut current_slot = 1;
+
+        // Initialize harness
+        let mut th = TestHarness::new(&["money".to_string(), "consensus".to_string()]).await?;
+
+        // Now Alice can airdrop some native tokens to herself
+        let alice_oc =
+            th.execute_airdrop(&HOLDERS, &Holder::Alice, ALICE_AIRDROP, current_slot).await?;
+
+        // Now Alice can stake her owncoin
+        let alice_staked_oc =
+            th.execute_stake(&HOLDERS, &Holder::Alice, current_slot, &alice_oc, 489).await?;
+
+        // We progress after grace period
+        current_slot += (calculate_grace_period() * EPOCH_LENGTH) + EPOCH_LENGTH;
+        let slot = th.generate_slot(current_slot).await?;
+
+        // Alice creates two proposals for the same slot, using the same
+        // staked coin, and signs two conflicting block headers with them.
+        let (first_tx, _, first_secret, output_secret) =
+            th.proposal(&Holder::Alice, slot.clone(), &alice_staked_oc).await?;
+        let (second_tx, _, second_secret, _) =
+            th.proposal(&Holder::Alice, slot.clone(), &alice_staked_oc).await?;
+        let timestamp = 1689772567 + current_slot * 90;
+        let first = th.signed_header(&first_secret, current_slot, timestamp, &first_tx)?;
+        let second = th.signed_header(&second_secret, current_slot, timestamp + 1, &second_tx)?;
+
+        info!(target: "consensus", "[Malicious] ====================================");
+        info!(target: "consensus", "[Malicious] Checking evidence of the same header");
+        info!(target: "consensus", "[Malicious] ====================================");
+        let (slash_tx, _) = th.slash(&first, &first)?;
+        th.execute_erroneous_txs(
+            TxAction::ConsensusSlash,
+            &Holder::Faucet,
+            &[slash_tx],
+            current_slot,
+            1,
+        )
+        .await?;
+
+        info!(target: "consensus", "[Malicious] =======================================");
+        info!(target: "consensus", "[Malicious] Checking evidence with forged signature");
+        info!(target: "consensus", "[Malicious] =======================================");
+        let forged = th.signed_header(&output_secret, current_slot, timestamp, &first_tx)?;
+        let (slash_tx, _) = th.slash(&forged, &second)?;
+        th.execute_erroneous_txs(
+            TxAction::ConsensusSlash,
+            &Holder::Faucet,
+            &[slash_tx],
+            current_slot,
+            1,
+        )
+        .await?;
+
+        // Faucet submits the conflicting proposals, burning Alice's staked coin
+        th.execute_slash(&HOLDERS, &first, &second, current_slot).await?;
+
+        info!(target: "consensus", "[Malicious] ============================");
+        info!(target: "consensus", "[Malicious] Checking slashing coin again");
+        info!(target: "consensus", "[Malicious] ============================");
+        let (slash_tx, _) = th.slash(&first, &second)?;
+        th.execute_erroneous_txs(
+            TxAction::ConsensusSlash,
+            &Holder::Faucet,
+            &[slash_tx],
+            current_slot,
+            1,
+        )
+        .await?;
+
+        info!(target: "consensus", "[Malicious] =================================");
+        info!(target: "consensus", "[Malicious] Checking slashed coin in proposal");
+        info!(target: "consensus", "[Malicious] =================================");
+        let (proposal_tx, _, _, _) = th.proposal(&Holder::Alice, slot, &alice_staked_oc).await?;
+        th.execute_erroneous_txs(
+            TxAction::ConsensusProposal,
+            &Holder::Alice,
+            &[proposal_tx],
+            current_slot,
+            1,
+        )
+        .await?;
+
+        // Thanks for reading
+        Ok(())
+    })
+}
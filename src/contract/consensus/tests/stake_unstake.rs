@@ -172,6 +172,19 @@ fn consensus_contract_stake_unstake() -> Result<()> {
         )
         .await?;
 
+        info!(target: "consensus", "[Malicious] ==================================");
+        info!(target: "consensus", "[Malicious] Checking unstaking before timelock");
+        info!(target: "consensus", "[Malicious] ==================================");
+        let (unstake_tx, _, _) = th.unstake(&Holder::Alice, &alice_unstake_request_oc)?;
+        th.execute_erroneous_txs(
+            TxAction::ConsensusUnstake,
+            &Holder::Alice,
+            &[unstake_tx],
+            current_slot,
+            1,
+        )
+        .await?;
+
         // We progress after grace period
         current_slot += (calculate_grace_period() * EPOCH_LENGTH) + EPOCH_LENGTH;
 
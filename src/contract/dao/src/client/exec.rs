@@ -85,11 +85,12 @@ impl DaoExecCall {
             self.dao.bulla_blind,
         ]);
 
-        let proposal_bulla = DaoProposalBulla::from(poseidon_hash::<6>([
+        let proposal_bulla = DaoProposalBulla::from(poseidon_hash::<7>([
             proposal_dest_x,
             proposal_dest_y,
             proposal_amount,
             self.proposal.token_id.inner(),
+            self.proposal.action.commit(),
             dao_bulla,
             self.proposal.blind,
         ]));
@@ -129,6 +130,7 @@ impl DaoExecCall {
             Witness::Base(Value::known(proposal_dest_y)),
             Witness::Base(Value::known(proposal_amount)),
             Witness::Base(Value::known(self.proposal.token_id.inner())),
+            Witness::Base(Value::known(self.proposal.action.commit())),
             Witness::Base(Value::known(self.proposal.blind)),
             // DAO params
             Witness::Base(Value::known(dao_proposer_limit)),
@@ -158,6 +160,7 @@ impl DaoExecCall {
         debug!(target: "dao", "proposal_bulla: {:?}", proposal_bulla);
         let public_inputs = vec![
             proposal_bulla.inner(),
+            self.proposal.action.commit(),
             coin_0,
             coin_1,
             *yes_vote_commit_coords.x(),
@@ -178,6 +181,7 @@ impl DaoExecCall {
 
         let params = DaoExecParams {
             proposal: proposal_bulla,
+            action: self.proposal.action,
             coin_0: coin_0.into(),
             coin_1: coin_1.into(),
             blind_total_vote: DaoBlindAggregateVote { yes_vote_commit, all_vote_commit },
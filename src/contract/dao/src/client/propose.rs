@@ -21,9 +21,10 @@ use darkfi_sdk::{
     bridgetree::Hashable,
     crypto::{
         note::AeadEncryptedNote, pasta_prelude::*, pedersen::pedersen_commitment_u64,
-        poseidon_hash, MerkleNode, PublicKey, SecretKey, TokenId,
+        poseidon_hash, ContractId, MerkleNode, PublicKey, SecretKey, TokenId,
     },
     pasta::pallas,
+    ContractCall,
 };
 use darkfi_serial::{async_trait, SerialDecodable, SerialEncodable};
 use rand::rngs::OsRng;
@@ -34,7 +35,9 @@ use darkfi::{
     Result,
 };
 
-use crate::model::{DaoProposalBulla, DaoProposeParams, DaoProposeParamsInput};
+use crate::model::{
+    DaoConsensusParam, DaoProposalAction, DaoProposalBulla, DaoProposeParams, DaoProposeParamsInput,
+};
 
 use super::DaoInfo;
 
@@ -43,9 +46,72 @@ pub struct DaoProposalInfo {
     pub dest: PublicKey,
     pub amount: u64,
     pub token_id: TokenId,
+    pub action: DaoProposalAction,
     pub blind: pallas::Base,
 }
 
+impl DaoProposalInfo {
+    /// Create a proposal sending `amount` of `token_id` from the DAO treasury to `dest`
+    pub fn treasury_transfer(dest: PublicKey, amount: u64, token_id: TokenId) -> Self {
+        Self {
+            dest,
+            amount,
+            token_id,
+            action: DaoProposalAction::TreasuryTransfer,
+            blind: pallas::Base::random(&mut OsRng),
+        }
+    }
+
+    /// Create a proposal changing a consensus parameter to `value`
+    pub fn consensus_param_change(dao: &DaoInfo, param: DaoConsensusParam, value: u64) -> Self {
+        Self::without_transfer(dao, DaoProposalAction::ConsensusParamChange { param, value })
+    }
+
+    /// Create a proposal authorizing an upgrade of `contract_id` to the code
+    /// with the given hash
+    pub fn contract_upgrade(dao: &DaoInfo, contract_id: ContractId, code_hash: [u8; 32]) -> Self {
+        Self::without_transfer(dao, DaoProposalAction::ContractUpgrade { contract_id, code_hash })
+    }
+
+    /// Create a proposal executing the given call to another contract
+    pub fn contract_call(dao: &DaoInfo, call: &ContractCall) -> Self {
+        let action = DaoProposalAction::ContractCall {
+            contract_id: call.contract_id,
+            call_hash: DaoProposalAction::call_hash(call),
+        };
+        Self::without_transfer(dao, action)
+    }
+
+    /// Create a proposal changing the DAO's quorum and approval ratio
+    pub fn dao_param_change(
+        dao: &DaoInfo,
+        quorum: u64,
+        approval_ratio_quot: u64,
+        approval_ratio_base: u64,
+    ) -> Self {
+        let action =
+            DaoProposalAction::DaoParamChange { quorum, approval_ratio_quot, approval_ratio_base };
+        Self::without_transfer(dao, action)
+    }
+
+    /// Create a proposal authorizing a mint of `amount` of `token_id`
+    pub fn token_mint_auth(dao: &DaoInfo, token_id: TokenId, amount: u64) -> Self {
+        Self::without_transfer(dao, DaoProposalAction::TokenMintAuth { token_id, amount })
+    }
+
+    /// Actions other than treasury transfers move no funds, so the transfer
+    /// fields are zeroed out and point back at the DAO.
+    fn without_transfer(dao: &DaoInfo, action: DaoProposalAction) -> Self {
+        Self {
+            dest: dao.public_key,
+            amount: 0,
+            token_id: dao.gov_token_id,
+            action,
+            blind: pallas::Base::random(&mut OsRng),
+        }
+    }
+}
+
 #[derive(SerialEncodable, SerialDecodable)]
 pub struct DaoProposeNote {
     pub proposal: DaoProposalInfo,
@@ -195,11 +261,12 @@ impl DaoProposeCall {
 
         let dao_leaf_position: u64 = self.dao_leaf_position.into();
 
-        let proposal_bulla = DaoProposalBulla::from(poseidon_hash::<6>([
+        let proposal_bulla = DaoProposalBulla::from(poseidon_hash::<7>([
             proposal_dest_x,
             proposal_dest_y,
             proposal_amount,
             self.proposal.token_id.inner(),
+            self.proposal.action.commit(),
             dao_bulla,
             self.proposal.blind,
         ]));
@@ -215,6 +282,7 @@ impl DaoProposeCall {
             Witness::Base(halo2::Value::known(proposal_dest_y)),
             Witness::Base(halo2::Value::known(proposal_amount)),
             Witness::Base(halo2::Value::known(self.proposal.token_id.inner())),
+            Witness::Base(halo2::Value::known(self.proposal.action.commit())),
             Witness::Base(halo2::Value::known(self.proposal.blind)),
             // DAO params
             Witness::Base(halo2::Value::known(dao_proposer_limit)),
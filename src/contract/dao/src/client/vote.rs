@@ -196,11 +196,12 @@ impl DaoVoteCall {
             self.dao.bulla_blind,
         ]);
 
-        let proposal_bulla = DaoProposalBulla::from(poseidon_hash::<6>([
+        let proposal_bulla = DaoProposalBulla::from(poseidon_hash::<7>([
             proposal_dest_x,
             proposal_dest_y,
             proposal_amount,
             self.proposal.token_id.inner(),
+            self.proposal.action.commit(),
             dao_bulla,
             self.proposal.blind,
         ]));
@@ -221,6 +222,7 @@ impl DaoVoteCall {
             Witness::Base(halo2::Value::known(proposal_dest_y)),
             Witness::Base(halo2::Value::known(proposal_amount)),
             Witness::Base(halo2::Value::known(self.proposal.token_id.inner())),
+            Witness::Base(halo2::Value::known(self.proposal.action.commit())),
             Witness::Base(halo2::Value::known(self.proposal.blind)),
             // DAO params
             Witness::Base(halo2::Value::known(dao_proposer_limit)),
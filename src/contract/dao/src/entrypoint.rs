@@ -30,9 +30,9 @@ use darkfi_serial::{deserialize, serialize, Decodable, Encodable, WriteExt};
 
 use crate::{
     model::{DaoExecUpdate, DaoMintUpdate, DaoProposeUpdate, DaoVoteUpdate},
-    DaoFunction, DAO_CONTRACT_DB_DAO_BULLAS, DAO_CONTRACT_DB_DAO_MERKLE_ROOTS,
-    DAO_CONTRACT_DB_INFO_TREE, DAO_CONTRACT_DB_PROPOSAL_BULLAS, DAO_CONTRACT_DB_VOTE_NULLIFIERS,
-    DAO_CONTRACT_KEY_DAO_MERKLE_TREE, DAO_CONTRACT_KEY_DB_VERSION,
+    DaoFunction, DAO_CONTRACT_DB_AUTHORIZED_ACTIONS, DAO_CONTRACT_DB_DAO_BULLAS,
+    DAO_CONTRACT_DB_DAO_MERKLE_ROOTS, DAO_CONTRACT_DB_INFO_TREE, DAO_CONTRACT_DB_PROPOSAL_BULLAS,
+    DAO_CONTRACT_DB_VOTE_NULLIFIERS, DAO_CONTRACT_KEY_DAO_MERKLE_TREE, DAO_CONTRACT_KEY_DB_VERSION,
 };
 
 /// `Dao::Mint` functions
@@ -127,6 +127,14 @@ fn init_contract(cid: ContractId, _ix: &[u8]) -> ContractResult {
         Err(_) => db_init(cid, DAO_CONTRACT_DB_VOTE_NULLIFIERS)?,
     };
 
+    // Set up db for actions authorized by executed proposals
+    // k: ProposalBulla
+    // v: DaoProposalAction
+    let _ = match db_lookup(cid, DAO_CONTRACT_DB_AUTHORIZED_ACTIONS) {
+        Ok(v) => v,
+        Err(_) => db_init(cid, DAO_CONTRACT_DB_AUTHORIZED_ACTIONS)?,
+    };
+
     // Update db version
     db_set(
         dao_info_db,
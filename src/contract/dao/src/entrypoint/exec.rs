@@ -16,10 +16,13 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use darkfi_money_contract::{model::MoneyTransferParamsV1, MoneyFunction};
+use darkfi_money_contract::{
+    model::{MoneyTokenMintParamsV1, MoneyTransferParamsV1},
+    MoneyFunction,
+};
 use darkfi_sdk::{
     crypto::{contract_id::MONEY_CONTRACT_ID, pasta_prelude::*, ContractId, PublicKey},
-    db::{db_del, db_get, db_lookup},
+    db::{db_del, db_get, db_lookup, db_set},
     error::{ContractError, ContractResult},
     msg,
     pasta::pallas,
@@ -29,8 +32,9 @@ use darkfi_serial::{deserialize, serialize, Encodable, WriteExt};
 
 use crate::{
     error::DaoError,
-    model::{DaoExecParams, DaoExecUpdate, DaoProposalMetadata},
-    DaoFunction, DAO_CONTRACT_DB_PROPOSAL_BULLAS, DAO_CONTRACT_ZKAS_DAO_EXEC_NS,
+    model::{DaoExecParams, DaoExecUpdate, DaoProposalAction, DaoProposalMetadata},
+    DaoFunction, DAO_CONTRACT_DB_AUTHORIZED_ACTIONS, DAO_CONTRACT_DB_PROPOSAL_BULLAS,
+    DAO_CONTRACT_ZKAS_DAO_EXEC_NS,
 };
 
 /// `get_metdata` function for `Dao::Exec`
@@ -56,6 +60,7 @@ pub(crate) fn dao_exec_get_metadata(
         DAO_CONTRACT_ZKAS_DAO_EXEC_NS.to_string(),
         vec![
             params.proposal.inner(),
+            params.action.commit(),
             params.coin_0.inner(),
             params.coin_1.inner(),
             *yes_vote_coords.x(),
@@ -87,6 +92,105 @@ pub(crate) fn dao_exec_process_instruction(
     let self_ = &calls[call_idx as usize];
     let params: DaoExecParams = deserialize(&self_.data[1..])?;
 
+    // ========================================
+    // Validate the action the proposal executes
+    // ========================================
+    match params.action {
+        DaoProposalAction::TreasuryTransfer => {
+            validate_treasury_transfer(&params, call_idx, &calls)?
+        }
+        DaoProposalAction::ConsensusParamChange { param, value } => {
+            validate_standalone(call_idx, &calls)?;
+            let (min, max) = param.bounds();
+            if value < min || value > max {
+                msg!("[Dao::Exec] Error: Value {} for {:?} out of bounds", value, param);
+                return Err(DaoError::ExecActionInvalid.into())
+            }
+        }
+        DaoProposalAction::ContractUpgrade { contract_id, code_hash } => {
+            validate_standalone(call_idx, &calls)?;
+            // A DAO can't authorize replacing its own governing contract
+            if contract_id == cid || code_hash == [0u8; 32] {
+                msg!("[Dao::Exec] Error: Invalid upgrade authorization for {}", contract_id);
+                return Err(DaoError::ExecActionInvalid.into())
+            }
+        }
+        DaoProposalAction::ContractCall { contract_id, call_hash } => {
+            // Calls back into the DAO contract would bypass its own checks
+            let target = paired_call(call_idx, &calls)?;
+            if contract_id == cid ||
+                target.contract_id != contract_id ||
+                DaoProposalAction::call_hash(target) != call_hash
+            {
+                msg!("[Dao::Exec] Error: Call doesn't match the one voted on");
+                return Err(DaoError::ExecActionInvalid.into())
+            }
+        }
+        DaoProposalAction::DaoParamChange { quorum, approval_ratio_quot, approval_ratio_base } => {
+            validate_standalone(call_idx, &calls)?;
+            if quorum == 0 ||
+                approval_ratio_quot == 0 ||
+                approval_ratio_base == 0 ||
+                approval_ratio_quot > approval_ratio_base
+            {
+                msg!("[Dao::Exec] Error: Invalid DAO parameters");
+                return Err(DaoError::ExecActionInvalid.into())
+            }
+        }
+        DaoProposalAction::TokenMintAuth { token_id, amount } => {
+            let target = paired_call(call_idx, &calls)?;
+            if target.contract_id != *MONEY_CONTRACT_ID ||
+                target.data[0] != MoneyFunction::TokenMintV1 as u8
+            {
+                msg!("[Dao::Exec] Error: Transaction has incorrect format");
+                return Err(DaoError::ExecCallInvalidFormat.into())
+            }
+
+            let mint_params: MoneyTokenMintParamsV1 = deserialize(&target.data[1..])?;
+            if mint_params.input.token_id != token_id || mint_params.input.value != amount {
+                msg!("[Dao::Exec] Error: Mint doesn't match the one voted on");
+                return Err(DaoError::ExecActionInvalid.into())
+            }
+        }
+    }
+
+    // ======
+    // Checks
+    // ======
+    // 1. Get the ProposalVote from DAO state
+    let proposal_db = db_lookup(cid, DAO_CONTRACT_DB_PROPOSAL_BULLAS)?;
+    let Some(data) = db_get(proposal_db, &serialize(&params.proposal))? else {
+        msg!("[Dao::Exec] Error: Proposal {:?} not found", params.proposal);
+        return Err(DaoError::ProposalNonexistent.into())
+    };
+    let proposal: DaoProposalMetadata = deserialize(&data)?;
+
+    if proposal.ended {
+        msg!("[Dao::Exec] Error: Proposal {:?} ended", params.proposal);
+        return Err(DaoError::ProposalEnded.into())
+    }
+
+    // 2. Check yes_vote commit and all_vote_commit are the same as in BlindAggregateVote
+    if proposal.vote_aggregate.yes_vote_commit != params.blind_total_vote.yes_vote_commit ||
+        proposal.vote_aggregate.all_vote_commit != params.blind_total_vote.all_vote_commit
+    {
+        return Err(DaoError::VoteCommitMismatch.into())
+    }
+
+    // Create state update
+    let update = DaoExecUpdate { proposal: params.proposal, action: params.action };
+    let mut update_data = vec![];
+    update_data.write_u8(DaoFunction::Exec as u8)?;
+    update.encode(&mut update_data)?;
+    Ok(update_data)
+}
+
+/// Enforce that a treasury transfer is paired with the matching `Money::Transfer`
+fn validate_treasury_transfer(
+    params: &DaoExecParams,
+    call_idx: u32,
+    calls: &[ContractCall],
+) -> Result<(), ContractError> {
     // ==========================================
     // Enforce the transaction has correct format
     // ==========================================
@@ -131,32 +235,28 @@ pub(crate) fn dao_exec_process_instruction(
         return Err(DaoError::ExecCallValueMismatch.into())
     }
 
-    // 3. Get the ProposalVote from DAO state
-    let proposal_db = db_lookup(cid, DAO_CONTRACT_DB_PROPOSAL_BULLAS)?;
-    let Some(data) = db_get(proposal_db, &serialize(&params.proposal))? else {
-        msg!("[Dao::Exec] Error: Proposal {:?} not found", params.proposal);
-        return Err(DaoError::ProposalNonexistent.into())
-    };
-    let proposal: DaoProposalMetadata = deserialize(&data)?;
+    Ok(())
+}
 
-    if proposal.ended {
-        msg!("[Dao::Exec] Error: Proposal {:?} ended", params.proposal);
-        return Err(DaoError::ProposalEnded.into())
+/// Enforce that a non-transfer action is executed in a standalone call
+fn validate_standalone(call_idx: u32, calls: &[ContractCall]) -> Result<(), ContractError> {
+    if calls.len() != 1 || call_idx != 0 {
+        msg!("[Dao::Exec] Error: Transaction has incorrect format");
+        return Err(DaoError::ExecCallInvalidFormat.into())
     }
 
-    // 4. Check yes_vote commit and all_vote_commit are the same as in BlindAggregateVote
-    if proposal.vote_aggregate.yes_vote_commit != params.blind_total_vote.yes_vote_commit ||
-        proposal.vote_aggregate.all_vote_commit != params.blind_total_vote.all_vote_commit
-    {
-        return Err(DaoError::VoteCommitMismatch.into())
+    Ok(())
+}
+
+/// Grab the call a paired action executes, which must directly precede
+/// `Dao::Exec` in a two-call transaction
+fn paired_call(call_idx: u32, calls: &[ContractCall]) -> Result<&ContractCall, ContractError> {
+    if calls.len() != 2 || call_idx != 1 || calls[0].data.is_empty() {
+        msg!("[Dao::Exec] Error: Transaction has incorrect format");
+        return Err(DaoError::ExecCallInvalidFormat.into())
     }
 
-    // Create state update
-    let update = DaoExecUpdate { proposal: params.proposal };
-    let mut update_data = vec![];
-    update_data.write_u8(DaoFunction::Exec as u8)?;
-    update.encode(&mut update_data)?;
-    Ok(update_data)
+    Ok(&calls[0])
 }
 
 /// `process_update` function for `Dao::Exec`
@@ -167,5 +267,17 @@ pub(crate) fn dao_exec_process_update(cid: ContractId, update: DaoExecUpdate) ->
     // Remove proposal from db
     db_del(proposal_vote_db, &serialize(&update.proposal))?;
 
+    // Record the authorization of actions which aren't carried out by
+    // the call paired with the execution
+    if matches!(
+        update.action,
+        DaoProposalAction::ConsensusParamChange { .. } |
+            DaoProposalAction::ContractUpgrade { .. } |
+            DaoProposalAction::DaoParamChange { .. }
+    ) {
+        let actions_db = db_lookup(cid, DAO_CONTRACT_DB_AUTHORIZED_ACTIONS)?;
+        db_set(actions_db, &serialize(&update.proposal), &serialize(&update.action))?;
+    }
+
     Ok(())
 }
@@ -19,7 +19,7 @@
 use darkfi_money_contract::MONEY_CONTRACT_NULLIFIERS_TREE;
 use darkfi_sdk::{
     crypto::{contract_id::MONEY_CONTRACT_ID, pasta_prelude::*, ContractId, PublicKey},
-    db::{db_contains_key, db_get, db_lookup, db_set},
+    db::{db_contains_key, db_get, db_lookup, db_set, NullifierSet},
     error::{ContractError, ContractResult},
     msg,
     pasta::pallas,
@@ -132,7 +132,8 @@ pub(crate) fn dao_vote_process_instruction(
     }
 
     // Check the Merkle root and nullifiers for the input coins are valid
-    let money_nullifier_db = db_lookup(*MONEY_CONTRACT_ID, MONEY_CONTRACT_NULLIFIERS_TREE)?;
+    let money_nullifiers =
+        NullifierSet::lookup(*MONEY_CONTRACT_ID, MONEY_CONTRACT_NULLIFIERS_TREE)?;
     let dao_vote_nullifier_db = db_lookup(cid, DAO_CONTRACT_DB_VOTE_NULLIFIERS)?;
     let mut vote_nullifiers = vec![];
 
@@ -146,7 +147,7 @@ pub(crate) fn dao_vote_process_instruction(
             return Err(DaoError::InvalidInputMerkleRoot.into())
         }
 
-        if db_contains_key(money_nullifier_db, &serialize(&input.nullifier))? {
+        if money_nullifiers.contains(&input.nullifier)? {
             msg!("[Dao::Vote] Error: Coin is already spent");
             return Err(DaoError::CoinAlreadySpent.into())
         }
@@ -61,6 +61,9 @@ pub enum DaoError {
 
     #[error("Vote commitments mismatch")]
     VoteCommitMismatch,
+
+    #[error("Exec call has an invalid proposal action")]
+    ExecActionInvalid,
 }
 
 impl From<DaoError> for ContractError {
@@ -80,6 +83,7 @@ impl From<DaoError> for ContractError {
             DaoError::ExecCallOutputsMismatch => Self::Custom(12),
             DaoError::ExecCallValueMismatch => Self::Custom(13),
             DaoError::VoteCommitMismatch => Self::Custom(14),
+            DaoError::ExecActionInvalid => Self::Custom(15),
         }
     }
 }
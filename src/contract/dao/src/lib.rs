@@ -63,6 +63,7 @@ pub const DAO_CONTRACT_DB_DAO_BULLAS: &str = "dao_bullas";
 pub const DAO_CONTRACT_DB_DAO_MERKLE_ROOTS: &str = "dao_roots";
 pub const DAO_CONTRACT_DB_PROPOSAL_BULLAS: &str = "dao_proposals";
 pub const DAO_CONTRACT_DB_VOTE_NULLIFIERS: &str = "dao_vote_nullifiers";
+pub const DAO_CONTRACT_DB_AUTHORIZED_ACTIONS: &str = "dao_authorized_actions";
 
 // These are keys inside the info tree
 pub const DAO_CONTRACT_KEY_DB_VERSION: &str = "db_version";
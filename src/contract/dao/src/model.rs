@@ -20,17 +20,21 @@ use core::str::FromStr;
 
 use darkfi_money_contract::model::Coin;
 use darkfi_sdk::{
-    crypto::{note::AeadEncryptedNote, pasta_prelude::*, MerkleNode, Nullifier, PublicKey},
+    crypto::{
+        note::AeadEncryptedNote, pasta_prelude::*, poseidon_hash, ContractId, MerkleNode,
+        Nullifier, PublicKey, TokenId,
+    },
     error::ContractError,
     pasta::pallas,
+    ContractCall,
 };
-use darkfi_serial::{SerialDecodable, SerialEncodable};
+use darkfi_serial::{SerialDecodable, SerialEncodable, SerialSchema};
 
 #[cfg(feature = "client")]
 use darkfi_serial::async_trait;
 
 /// A `DaoBulla` represented in the state
-#[derive(Debug, Copy, Clone, Eq, PartialEq, SerialEncodable, SerialDecodable)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, SerialEncodable, SerialDecodable, SerialSchema)]
 pub struct DaoBulla(pallas::Base);
 
 impl DaoBulla {
@@ -67,7 +71,7 @@ darkfi_sdk::fp_to_bs58!(DaoBulla);
 darkfi_sdk::ty_from_fp!(DaoBulla);
 
 /// A `DaoProposalBulla` represented in the state
-#[derive(Debug, Copy, Clone, Eq, PartialEq, SerialEncodable, SerialDecodable)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, SerialEncodable, SerialDecodable, SerialSchema)]
 pub struct DaoProposalBulla(pallas::Base);
 
 impl DaoProposalBulla {
@@ -103,8 +107,100 @@ darkfi_sdk::fp_from_bs58!(DaoProposalBulla);
 darkfi_sdk::fp_to_bs58!(DaoProposalBulla);
 darkfi_sdk::ty_from_fp!(DaoProposalBulla);
 
+/// Consensus parameters a DAO proposal is allowed to change
+#[derive(Debug, Copy, Clone, Eq, PartialEq, SerialEncodable, SerialDecodable, SerialSchema)]
+pub enum DaoConsensusParam {
+    /// Number of slots in an epoch
+    EpochLength = 0x00,
+    /// Slot duration in seconds
+    SlotTime = 0x01,
+    /// Block producer reward
+    Reward = 0x02,
+}
+
+impl DaoConsensusParam {
+    /// Inclusive bounds a new value for this parameter must fall within
+    pub fn bounds(&self) -> (u64, u64) {
+        match self {
+            Self::EpochLength => (1, 1_000),
+            Self::SlotTime => (10, 3_600),
+            Self::Reward => (1, 1_000_000_000),
+        }
+    }
+}
+
+/// The action a DAO proposal executes once it passes
+#[derive(Debug, Copy, Clone, Eq, PartialEq, SerialEncodable, SerialDecodable, SerialSchema)]
+pub enum DaoProposalAction {
+    /// Send funds from the DAO treasury to the proposal recipient
+    TreasuryTransfer,
+    /// Change a consensus parameter to the given value
+    ConsensusParamChange { param: DaoConsensusParam, value: u64 },
+    /// Authorize upgrading a contract to the code with the given hash
+    ContractUpgrade { contract_id: ContractId, code_hash: [u8; 32] },
+    /// Execute a call to another contract. The call must be the one
+    /// whose contract ID and data hash to `call_hash`.
+    ContractCall { contract_id: ContractId, call_hash: [u8; 32] },
+    /// Change the DAO's own voting parameters
+    DaoParamChange { quorum: u64, approval_ratio_quot: u64, approval_ratio_base: u64 },
+    /// Authorize minting `amount` of a token the DAO holds the mint authority of
+    TokenMintAuth { token_id: TokenId, amount: u64 },
+}
+
+impl DaoProposalAction {
+    /// Commitment to the action which is hashed into the proposal bulla.
+    /// Treasury transfers commit to zero so the proposal's transfer
+    /// parameters alone describe them.
+    pub fn commit(&self) -> pallas::Base {
+        match self {
+            Self::TreasuryTransfer => pallas::Base::ZERO,
+            Self::ConsensusParamChange { param, value } => poseidon_hash([
+                pallas::Base::from(1),
+                pallas::Base::from(*param as u64),
+                pallas::Base::from(*value),
+            ]),
+            Self::ContractUpgrade { contract_id, code_hash } => {
+                let (lo, hi) = split_hash(code_hash);
+                poseidon_hash([pallas::Base::from(2), contract_id.inner(), lo, hi])
+            }
+            Self::ContractCall { contract_id, call_hash } => {
+                let (lo, hi) = split_hash(call_hash);
+                poseidon_hash([pallas::Base::from(3), contract_id.inner(), lo, hi])
+            }
+            Self::DaoParamChange { quorum, approval_ratio_quot, approval_ratio_base } => {
+                poseidon_hash([
+                    pallas::Base::from(4),
+                    pallas::Base::from(*quorum),
+                    pallas::Base::from(*approval_ratio_quot),
+                    pallas::Base::from(*approval_ratio_base),
+                ])
+            }
+            Self::TokenMintAuth { token_id, amount } => poseidon_hash([
+                pallas::Base::from(5),
+                token_id.inner(),
+                pallas::Base::from(*amount),
+            ]),
+        }
+    }
+
+    /// Hash of a contract call, as committed to by `ContractCall` actions
+    pub fn call_hash(call: &ContractCall) -> [u8; 32] {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(&call.contract_id.to_bytes());
+        hasher.update(&call.data);
+        *hasher.finalize().as_bytes()
+    }
+}
+
+/// Split a 32 byte hash into two base field elements
+fn split_hash(hash: &[u8; 32]) -> (pallas::Base, pallas::Base) {
+    let lo = u128::from_le_bytes(hash[..16].try_into().unwrap());
+    let hi = u128::from_le_bytes(hash[16..].try_into().unwrap());
+    (pallas::Base::from_u128(lo), pallas::Base::from_u128(hi))
+}
+
 /// Parameters for `Dao::Mint`
-#[derive(Debug, Copy, Clone, SerialEncodable, SerialDecodable)]
+#[derive(Debug, Copy, Clone, SerialEncodable, SerialDecodable, SerialSchema)]
 pub struct DaoMintParams {
     /// The DAO bulla
     pub dao_bulla: DaoBulla,
@@ -113,14 +209,14 @@ pub struct DaoMintParams {
 }
 
 /// State update for `Dao::Mint`
-#[derive(Debug, Copy, Clone, SerialEncodable, SerialDecodable)]
+#[derive(Debug, Copy, Clone, SerialEncodable, SerialDecodable, SerialSchema)]
 pub struct DaoMintUpdate {
     /// Revealed DAO bulla
     pub dao_bulla: DaoBulla,
 }
 
 /// Parameters for `Dao::Propose`
-#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable, SerialSchema)]
 pub struct DaoProposeParams {
     /// Merkle root of the DAO in the DAO state
     pub dao_merkle_root: MerkleNode,
@@ -135,7 +231,7 @@ pub struct DaoProposeParams {
 }
 
 /// Input for a DAO proposal
-#[derive(Debug, Copy, Clone, SerialEncodable, SerialDecodable)]
+#[derive(Debug, Copy, Clone, SerialEncodable, SerialDecodable, SerialSchema)]
 pub struct DaoProposeParamsInput {
     /// Value commitment for the input
     pub value_commit: pallas::Point,
@@ -146,7 +242,7 @@ pub struct DaoProposeParamsInput {
 }
 
 /// State update for `Dao::Propose`
-#[derive(Debug, Copy, Clone, SerialEncodable, SerialDecodable)]
+#[derive(Debug, Copy, Clone, SerialEncodable, SerialDecodable, SerialSchema)]
 pub struct DaoProposeUpdate {
     /// Minted proposal bulla
     pub proposal_bulla: DaoProposalBulla,
@@ -155,7 +251,7 @@ pub struct DaoProposeUpdate {
 }
 
 /// Metadata for a DAO proposal on the blockchain
-#[derive(Debug, Copy, Clone, SerialEncodable, SerialDecodable)]
+#[derive(Debug, Copy, Clone, SerialEncodable, SerialDecodable, SerialSchema)]
 pub struct DaoProposalMetadata {
     /// Vote aggregate
     pub vote_aggregate: DaoBlindAggregateVote,
@@ -166,7 +262,7 @@ pub struct DaoProposalMetadata {
 }
 
 /// Parameters for `Dao::Vote`
-#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable, SerialSchema)]
 pub struct DaoVoteParams {
     /// Token commitment for the vote inputs
     pub token_commit: pallas::Base,
@@ -181,7 +277,7 @@ pub struct DaoVoteParams {
 }
 
 /// Input for a DAO proposal vote
-#[derive(Debug, Copy, Clone, SerialEncodable, SerialDecodable)]
+#[derive(Debug, Copy, Clone, SerialEncodable, SerialDecodable, SerialSchema)]
 pub struct DaoVoteParamsInput {
     /// Revealed nullifier
     pub nullifier: Nullifier,
@@ -194,7 +290,7 @@ pub struct DaoVoteParamsInput {
 }
 
 /// State update for `Dao::Vote`
-#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable, SerialSchema)]
 pub struct DaoVoteUpdate {
     /// The proposal bulla being voted on
     pub proposal_bulla: DaoProposalBulla,
@@ -206,7 +302,7 @@ pub struct DaoVoteUpdate {
 
 /// Represents a single or multiple blinded votes.
 /// These can be summed together.
-#[derive(Debug, Copy, Clone, SerialEncodable, SerialDecodable)]
+#[derive(Debug, Copy, Clone, SerialEncodable, SerialDecodable, SerialSchema)]
 pub struct DaoBlindAggregateVote {
     /// Weighted vote commit
     pub yes_vote_commit: pallas::Point,
@@ -232,10 +328,12 @@ impl Default for DaoBlindAggregateVote {
 }
 
 /// Parameters for `Dao::Exec`
-#[derive(Debug, Copy, Clone, SerialEncodable, SerialDecodable)]
+#[derive(Debug, Copy, Clone, SerialEncodable, SerialDecodable, SerialSchema)]
 pub struct DaoExecParams {
     /// The proposal bulla
     pub proposal: DaoProposalBulla,
+    /// The action the proposal executes
+    pub action: DaoProposalAction,
     /// The output coin for the proposal recipient
     pub coin_0: Coin,
     /// The output coin for the change returned to DAO
@@ -247,8 +345,10 @@ pub struct DaoExecParams {
 }
 
 /// State update for `Dao::Exec`
-#[derive(Debug, Copy, Clone, SerialEncodable, SerialDecodable)]
+#[derive(Debug, Copy, Clone, SerialEncodable, SerialDecodable, SerialSchema)]
 pub struct DaoExecUpdate {
     /// The proposal bulla
     pub proposal: DaoProposalBulla,
+    /// The executed action
+    pub action: DaoProposalAction,
 }
@@ -0,0 +1,62 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_dao_contract::model::{DaoConsensusParam, DaoProposalAction};
+use darkfi_sdk::{
+    crypto::{pasta_prelude::Field, ContractId, DARK_TOKEN_ID, MONEY_CONTRACT_ID},
+    pasta::pallas,
+    ContractCall,
+};
+
+#[test]
+fn proposal_action_commitments() {
+    let call = ContractCall { contract_id: *MONEY_CONTRACT_ID, data: vec![0x01, 0x02, 0x03] };
+    let call_hash = DaoProposalAction::call_hash(&call);
+
+    let actions = [
+        DaoProposalAction::TreasuryTransfer,
+        DaoProposalAction::ConsensusParamChange { param: DaoConsensusParam::Reward, value: 5 },
+        DaoProposalAction::ContractUpgrade {
+            contract_id: *MONEY_CONTRACT_ID,
+            code_hash: call_hash,
+        },
+        DaoProposalAction::ContractCall { contract_id: *MONEY_CONTRACT_ID, call_hash },
+        DaoProposalAction::DaoParamChange {
+            quorum: 5,
+            approval_ratio_quot: 1,
+            approval_ratio_base: 2,
+        },
+        DaoProposalAction::TokenMintAuth { token_id: *DARK_TOKEN_ID, amount: 5 },
+    ];
+
+    // Treasury transfers commit to zero, every other action to a
+    // commitment distinct from all others.
+    assert_eq!(actions[0].commit(), pallas::Base::ZERO);
+    for (i, a) in actions.iter().enumerate() {
+        for b in &actions[i + 1..] {
+            assert_ne!(a.commit(), b.commit());
+        }
+    }
+
+    // The call hash binds both the called contract and the call data
+    let other_contract =
+        ContractCall { contract_id: ContractId::from(pallas::Base::ONE), ..call.clone() };
+    let other_data = ContractCall { contract_id: call.contract_id, data: vec![0x01, 0x02] };
+    assert_ne!(DaoProposalAction::call_hash(&other_contract), call_hash);
+    assert_ne!(DaoProposalAction::call_hash(&other_data), call_hash);
+}
@@ -19,6 +19,7 @@
 use darkfi_sdk::{
     crypto::{ContractId, PublicKey},
     db::{db_get, db_lookup, db_set},
+    deploy::{MAX_DEPLOY_IX_SIZE, MAX_WASM_BINCODE_SIZE},
     error::{ContractError, ContractResult},
     msg,
     pasta::pallas,
@@ -73,6 +74,18 @@ pub(crate) fn deploy_process_instruction_v1(
     let self_ = &calls[call_idx as usize];
     let params: DeployParamsV1 = deserialize(&self_.data[1..])?;
 
+    // The bincode gets stored on-chain, so its size is bounded. The host
+    // verifies the bincode itself when deploying it.
+    if params.wasm_bincode.len() > MAX_WASM_BINCODE_SIZE {
+        msg!("[DeployV1] Error: Wasm bincode is too large.");
+        return Err(DeployError::WasmBincodeTooLarge.into())
+    }
+
+    if params.ix.len() > MAX_DEPLOY_IX_SIZE {
+        msg!("[DeployV1] Error: Deploy payload is too large.");
+        return Err(DeployError::DeployIxTooLarge.into())
+    }
+
     // In this function, we have to check that the contract isn't locked.
     let lock_db = db_lookup(cid, DEPLOY_CONTRACT_LOCK_TREE)?;
     let contract_id = ContractId::derive_public(params.public_key);
@@ -25,6 +25,12 @@ pub enum DeployError {
 
     #[error("Contract does not exist.")]
     ContractNonExistent,
+
+    #[error("Wasm bincode is too large.")]
+    WasmBincodeTooLarge,
+
+    #[error("Deploy payload is too large.")]
+    DeployIxTooLarge,
 }
 
 impl From<DeployError> for ContractError {
@@ -32,6 +38,8 @@ impl From<DeployError> for ContractError {
         match e {
             DeployError::ContractLocked => Self::Custom(1),
             DeployError::ContractNonExistent => Self::Custom(2),
+            DeployError::WasmBincodeTooLarge => Self::Custom(3),
+            DeployError::DeployIxTooLarge => Self::Custom(4),
         }
     }
 }
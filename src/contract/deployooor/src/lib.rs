@@ -18,12 +18,12 @@
 
 //! Smart contract implementing non-native smart contract deployment.
 
-use darkfi_sdk::error::ContractError;
+use darkfi_sdk::{deploy::DEPLOY_FUNCTION_DEPLOY_V1, error::ContractError};
 
 /// Functions available in the contract
 #[repr(u8)]
 pub enum DeployFunction {
-    DeployV1 = 0x00,
+    DeployV1 = DEPLOY_FUNCTION_DEPLOY_V1,
     LockV1 = 0x01,
 }
 
@@ -32,7 +32,7 @@ impl TryFrom<u8> for DeployFunction {
 
     fn try_from(b: u8) -> core::result::Result<Self, Self::Error> {
         match b {
-            0x00 => Ok(Self::DeployV1),
+            DEPLOY_FUNCTION_DEPLOY_V1 => Ok(Self::DeployV1),
             0x01 => Ok(Self::LockV1),
             _ => Err(ContractError::InvalidFunction),
         }
@@ -22,14 +22,9 @@ use darkfi_serial::async_trait;
 use darkfi_sdk::crypto::{ContractId, PublicKey};
 use darkfi_serial::{SerialDecodable, SerialEncodable};
 
-/// Parameters for `Deploy::Deploy`
-#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
-pub struct DeployParamsV1 {
-    /// Webassembly bincode of the smart contract
-    pub wasm_bincode: Vec<u8>,
-    /// Public key used to sign the transaction and derive the `ContractId`
-    pub public_key: PublicKey,
-}
+/// Parameters for `Deploy::Deploy`, shared with the host which performs
+/// the actual deployment.
+pub use darkfi_sdk::deploy::DeployParamsV1;
 
 /// State update for `Deploy::Deploy`
 #[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
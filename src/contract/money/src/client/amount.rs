@@ -0,0 +1,69 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Helpers for handling token amounts. Amounts are kept on-chain as `u64`
+//! atomic units, and only turned into decimal strings for display, using the
+//! number of decimals registered in the token's metadata.
+
+use darkfi::{
+    util::parse::{decode_base10, encode_base10},
+    ClientFailed, Error, Result,
+};
+
+use crate::model::{TokenMetadata, TOKEN_MAX_DECIMALS};
+
+/// Number of decimals used for tokens without registered metadata
+pub const DEFAULT_DECIMALS: u8 = 8;
+
+/// Returns the number of decimals to display a token's amounts with,
+/// given its registered metadata, if any.
+pub fn token_decimals(metadata: Option<&TokenMetadata>) -> u8 {
+    metadata.map_or(DEFAULT_DECIMALS, |m| m.decimals)
+}
+
+/// Parse a decimal string into atomic units. Fails instead of rounding
+/// if the string has more decimal places than the token supports.
+pub fn parse_amount(amount: &str, decimals: u8) -> Result<u64> {
+    if decimals > TOKEN_MAX_DECIMALS {
+        return Err(Error::ParseFailed("Too many decimals"))
+    }
+
+    if amount.is_empty() || amount.starts_with('.') {
+        return Err(Error::ParseFailed("Invalid amount"))
+    }
+
+    decode_base10(amount, decimals as usize, true)
+}
+
+/// Format atomic units as a decimal string, omitting trailing zeroes.
+pub fn format_amount(value: u64, decimals: u8) -> String {
+    encode_base10(value, decimals as usize)
+}
+
+/// Sum the given amounts, failing if the total overflows.
+pub fn checked_sum(values: impl IntoIterator<Item = u64>) -> Result<u64> {
+    values
+        .into_iter()
+        .try_fold(0_u64, |total, value| total.checked_add(value))
+        .ok_or_else(|| ClientFailed::AmountOverflow.into())
+}
+
+/// Subtract `amount` from `balance`, failing if the balance is too low.
+pub fn checked_change(balance: u64, amount: u64) -> Result<u64> {
+    balance.checked_sub(amount).ok_or_else(|| ClientFailed::NotEnoughValue(balance).into())
+}
@@ -0,0 +1,194 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use darkfi::{
+    zk::{Proof, ProvingKey},
+    zkas::ZkBinary,
+    ClientFailed, Result,
+};
+use darkfi_sdk::{
+    crypto::{
+        note::AeadEncryptedNote, pasta_prelude::*, Keypair, MerkleTree, Nullifier, SecretKey,
+        DARK_TOKEN_ID,
+    },
+    pasta::pallas,
+};
+use log::{debug, error, info};
+use rand::rngs::OsRng;
+
+use crate::{
+    client::{
+        transfer_v1::{
+            create_transfer_burn_proof, create_transfer_mint_proof, TransactionBuilderInputInfo,
+            TransactionBuilderOutputInfo,
+        },
+        MoneyNote, OwnCoin,
+    },
+    model::{Input, MoneyFeeParamsV1, Output},
+};
+
+/// Output metadata claimed from building a `Money::Fee` call
+pub struct FeeCallDebris {
+    /// The parameters for `Money::Fee` respective to this call
+    pub params: MoneyFeeParamsV1,
+    /// The ZK proofs created in this builder
+    pub proofs: Vec<Proof>,
+    /// The ephemeral secret key created for signing
+    pub signature_secret: SecretKey,
+    /// The coin that has been spent in this builder
+    pub spent_coin: OwnCoin,
+    /// The change coin that has been minted in this builder
+    pub change_coin: OwnCoin,
+}
+
+/// Struct holding necessary information to build a `Money::FeeV1` contract call.
+pub struct FeeCallBuilder {
+    /// Caller's keypair, receiving the change
+    pub keypair: Keypair,
+    /// Native token `OwnCoin` the fee is paid from
+    pub coin: OwnCoin,
+    /// Fee to pay, usually computed from the transaction's estimated gas
+    pub fee: u64,
+    /// Merkle tree of coins used to create the inclusion proof
+    pub tree: MerkleTree,
+    /// `Mint_V1` zkas circuit ZkBinary
+    pub mint_zkbin: ZkBinary,
+    /// Proving key for the `Mint_V1` zk circuit
+    pub mint_pk: ProvingKey,
+    /// `Burn_V1` zkas circuit ZkBinary
+    pub burn_zkbin: ZkBinary,
+    /// Proving key for the `Burn_V1` zk circuit
+    pub burn_pk: ProvingKey,
+}
+
+impl FeeCallBuilder {
+    pub fn build(&self) -> Result<FeeCallDebris> {
+        debug!("Building Money::FeeV1 contract call");
+        assert_eq!(self.coin.note.token_id, *DARK_TOKEN_ID);
+        assert_eq!(self.coin.note.spend_hook, pallas::Base::ZERO);
+
+        if self.coin.note.value < self.fee {
+            error!("Not enough value to pay the fee");
+            return Err(ClientFailed::NotEnoughValue(self.coin.note.value).into())
+        }
+
+        let input = TransactionBuilderInputInfo {
+            leaf_position: self.coin.leaf_position,
+            merkle_path: self.tree.witness(self.coin.leaf_position, 0).unwrap(),
+            secret: self.coin.secret,
+            note: self.coin.note.clone(),
+        };
+
+        let output = TransactionBuilderOutputInfo {
+            value: self.coin.note.value - self.fee,
+            token_id: *DARK_TOKEN_ID,
+            public_key: self.keypair.public,
+            view_public: None,
+        };
+
+        // The fee is committed to with the difference of the blinds, so that
+        // the input's value commitment is the sum of the output's and the fee's.
+        let token_blind = pallas::Base::random(&mut OsRng);
+        let input_value_blind = pallas::Scalar::random(&mut OsRng);
+        let output_value_blind = pallas::Scalar::random(&mut OsRng);
+        let fee_value_blind = input_value_blind - output_value_blind;
+
+        let signature_secret = SecretKey::random(&mut OsRng);
+        let user_data_blind = pallas::Base::random(&mut OsRng);
+
+        info!("Creating fee burn proof for input");
+        let (burn_proof, burn_revealed) = create_transfer_burn_proof(
+            &self.burn_zkbin,
+            &self.burn_pk,
+            &input,
+            input_value_blind,
+            token_blind,
+            user_data_blind,
+            signature_secret,
+        )?;
+
+        let serial = pallas::Base::random(&mut OsRng);
+        let spend_hook = pallas::Base::ZERO;
+        let user_data = pallas::Base::ZERO;
+
+        info!("Creating fee mint proof for change output");
+        let (mint_proof, mint_revealed) = create_transfer_mint_proof(
+            &self.mint_zkbin,
+            &self.mint_pk,
+            &output,
+            output_value_blind,
+            token_blind,
+            serial,
+            spend_hook,
+            user_data,
+        )?;
+
+        // Encrypted note
+        let note = MoneyNote {
+            serial,
+            value: output.value,
+            token_id: output.token_id,
+            spend_hook,
+            user_data,
+            value_blind: output_value_blind,
+            token_blind,
+            memo: vec![],
+        };
+
+        let encrypted_note = AeadEncryptedNote::encrypt(&note, &output.public_key, &mut OsRng)?;
+
+        let change_coin = OwnCoin {
+            coin: mint_revealed.coin,
+            note,
+            secret: SecretKey::from(pallas::Base::ZERO),
+            nullifier: Nullifier::from(pallas::Base::ZERO),
+            leaf_position: 0.into(),
+        };
+
+        let params = MoneyFeeParamsV1 {
+            input: Input {
+                value_commit: burn_revealed.value_commit,
+                token_commit: burn_revealed.token_commit,
+                nullifier: burn_revealed.nullifier,
+                merkle_root: burn_revealed.merkle_root,
+                spend_hook: burn_revealed.spend_hook,
+                user_data_enc: burn_revealed.user_data_enc,
+                signature_public: burn_revealed.signature_public,
+            },
+            output: Output {
+                value_commit: mint_revealed.value_commit,
+                token_commit: mint_revealed.token_commit,
+                coin: mint_revealed.coin,
+                note: encrypted_note,
+            },
+            fee_value: self.fee,
+            fee_value_blind,
+            token_blind,
+        };
+
+        // Now we should have all the params, zk proofs, and signature secret.
+        // We return it all and let the caller deal with it.
+        let debris = FeeCallDebris {
+            params,
+            proofs: vec![burn_proof, mint_proof],
+            signature_secret,
+            spent_coin: self.coin.clone(),
+            change_coin,
+        };
+        Ok(debris)
+    }
+}
@@ -95,6 +95,7 @@ impl GenesisMintCallBuilder {
             value: self.amount,
             token_id,
             public_key: self.keypair.public,
+            view_public: None,
         };
 
         // We just create the commitment blinds here. We simply encofce
@@ -145,7 +146,7 @@ impl GenesisMintCallBuilder {
             note: encrypted_note,
         };
 
-        let params = MoneyTokenMintParamsV1 { input: c_input, output: c_output };
+        let params = MoneyTokenMintParamsV1 { input: c_input, output: c_output, max_supply: None };
         let debris = GenesisMintCallDebris { params, proofs: vec![proof] };
         Ok(debris)
     }
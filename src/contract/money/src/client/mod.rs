@@ -35,6 +35,9 @@ use darkfi_serial::{async_trait, SerialDecodable, SerialEncodable};
 
 use crate::model::Coin;
 
+/// `Money::FeeV1` API
+pub mod fee_v1;
+
 /// `Money::TransferV1` API
 pub mod transfer_v1;
 
@@ -50,12 +53,30 @@ pub mod token_mint_v1;
 /// `Money::TokenFreezeV1` API
 pub mod token_freeze_v1;
 
+/// `Money::TokenMetadataV1` API
+pub mod token_metadata_v1;
+
 /// `Money::StakeV1` API
 pub mod stake_v1;
 
 /// `Money::UnstakeV1` API
 pub mod unstake_v1;
 
+/// Multi-call `Transaction` builder
+pub mod tx_builder;
+
+/// Token amount parsing and formatting
+pub mod amount;
+
+/// Discovery of wallet coins from blocks
+pub mod scan;
+
+/// Merkle witness upkeep for wallet coins
+pub mod witness;
+
+/// Incoming viewing keys
+pub mod view_key;
+
 // Wallet SQL table constant names. These have to represent the `wallet.sql`
 // SQL schema.
 // TODO: They should also be prefixed with the contract ID to avoid collisions.
@@ -71,6 +92,11 @@ pub const MONEY_KEYS_COL_IS_DEFAULT: &str = "is_default";
 pub const MONEY_KEYS_COL_PUBLIC: &str = "public";
 pub const MONEY_KEYS_COL_SECRET: &str = "secret";
 
+pub const MONEY_VIEW_KEYS_TABLE: &str = "money_view_keys";
+pub const MONEY_VIEW_KEYS_COL_KEY_ID: &str = "key_id";
+pub const MONEY_VIEW_KEYS_COL_PUBLIC: &str = "public";
+pub const MONEY_VIEW_KEYS_COL_SECRET: &str = "secret";
+
 pub const MONEY_COINS_TABLE: &str = "money_coins";
 pub const MONEY_COINS_COL_COIN: &str = "coin";
 pub const MONEY_COINS_COL_IS_SPENT: &str = "is_spent";
@@ -86,6 +112,19 @@ pub const MONEY_COINS_COL_NULLIFIER: &str = "nullifier";
 pub const MONEY_COINS_COL_LEAF_POSITION: &str = "leaf_position";
 pub const MONEY_COINS_COL_MEMO: &str = "memo";
 
+pub const MONEY_SPEND_INTENTS_TABLE: &str = "money_spend_intents";
+pub const MONEY_SPEND_INTENTS_COL_COIN: &str = "coin";
+pub const MONEY_SPEND_INTENTS_COL_TX_HASH: &str = "tx_hash";
+pub const MONEY_SPEND_INTENTS_COL_IS_BROADCAST: &str = "is_broadcast";
+
+pub const MONEY_COIN_SOURCES_TABLE: &str = "money_coin_sources";
+pub const MONEY_COIN_SOURCES_COL_COIN: &str = "coin";
+pub const MONEY_COIN_SOURCES_COL_CLUSTER: &str = "cluster";
+
+pub const MONEY_COIN_WITNESSES_TABLE: &str = "money_coin_witnesses";
+pub const MONEY_COIN_WITNESSES_COL_COIN: &str = "coin";
+pub const MONEY_COIN_WITNESSES_COL_WITNESS: &str = "witness";
+
 pub const MONEY_TOKENS_TABLE: &str = "money_tokens";
 pub const MONEY_TOKENS_COL_MINT_AUTHORITY: &str = "mint_authority";
 pub const MONEY_TOKENS_COL_TOKEN_ID: &str = "token_id";
@@ -0,0 +1,158 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Wallet-side discovery of coins from blocks. The `Money` calls of each
+//! transaction are walked in the order the contract applies them, so the
+//! wallet's Merkle tree of coins stays in sync with the on-chain one. New
+//! coins get their notes trial-decrypted with the wallet's keys, and the
+//! revealed nullifiers tell which of the wallet's coins got spent.
+
+use darkfi::{tx::Transaction, Result};
+use darkfi_sdk::{
+    crypto::{
+        poseidon_hash, ContractId, MerkleNode, MerkleTree, Nullifier, SecretKey, TokenId,
+        MONEY_CONTRACT_ID,
+    },
+    ContractCall,
+};
+use darkfi_serial::deserialize;
+
+use crate::{
+    client::{view_key::ViewKey, MoneyNote, OwnCoin},
+    model::{
+        MoneyFeeParamsV1, MoneyStakeParamsV1, MoneyTokenFreezeParamsV1, MoneyTokenMintParamsV1,
+        MoneyTransferParamsV1, MoneyUnstakeParamsV1, Output,
+    },
+    MoneyFunction,
+};
+
+/// Effects of a transaction's `Money` calls that a wallet has to track.
+#[derive(Clone, Debug, Default)]
+pub struct MoneyTxEffects {
+    /// Nullifiers revealed by the transaction
+    pub nullifiers: Vec<Nullifier>,
+    /// Outputs whose coins got appended to the Merkle tree, in order
+    pub outputs: Vec<Output>,
+    /// Tokens whose minting got frozen
+    pub freezes: Vec<TokenId>,
+}
+
+impl MoneyTxEffects {
+    /// Gather the effects of the `Money` calls in the given transaction.
+    pub fn from_tx(tx: &Transaction) -> Result<Self> {
+        Self::from_calls(&tx.calls, *MONEY_CONTRACT_ID)
+    }
+
+    /// Gather the effects of the calls to the `Money` contract with the
+    /// given `ContractId`.
+    pub fn from_calls(calls: &[ContractCall], cid: ContractId) -> Result<Self> {
+        let mut effects = Self::default();
+
+        for call in calls.iter().filter(|c| c.contract_id == cid && !c.data.is_empty()) {
+            let Ok(function) = MoneyFunction::try_from(call.data[0]) else { continue };
+
+            match function {
+                MoneyFunction::FeeV1 => {
+                    let params: MoneyFeeParamsV1 = deserialize(&call.data[1..])?;
+                    effects.nullifiers.push(params.input.nullifier);
+                    effects.outputs.push(params.output);
+                }
+
+                MoneyFunction::TransferV1 | MoneyFunction::OtcSwapV1 => {
+                    let params: MoneyTransferParamsV1 = deserialize(&call.data[1..])?;
+                    effects.nullifiers.extend(params.inputs.iter().map(|x| x.nullifier));
+                    effects.outputs.extend(params.outputs);
+                }
+
+                MoneyFunction::GenesisMintV1 | MoneyFunction::TokenMintV1 => {
+                    let params: MoneyTokenMintParamsV1 = deserialize(&call.data[1..])?;
+                    effects.outputs.push(params.output);
+                }
+
+                MoneyFunction::TokenFreezeV1 => {
+                    let params: MoneyTokenFreezeParamsV1 = deserialize(&call.data[1..])?;
+                    effects.freezes.push(TokenId::derive_public(params.signature_public));
+                }
+
+                MoneyFunction::StakeV1 => {
+                    let params: MoneyStakeParamsV1 = deserialize(&call.data[1..])?;
+                    effects.nullifiers.push(params.input.nullifier);
+                }
+
+                MoneyFunction::UnstakeV1 => {
+                    let params: MoneyUnstakeParamsV1 = deserialize(&call.data[1..])?;
+                    effects.outputs.push(params.output);
+                }
+
+                MoneyFunction::TokenMetadataV1 => {}
+            }
+        }
+
+        Ok(effects)
+    }
+
+    /// Returns `true` if the transaction doesn't affect any wallet.
+    pub fn is_empty(&self) -> bool {
+        self.nullifiers.is_empty() && self.outputs.is_empty() && self.freezes.is_empty()
+    }
+}
+
+/// Append the coins of the given outputs to the Merkle tree, trying to
+/// decrypt each output's note with the given secrets, and with the view
+/// keys derived from them. The coins whose notes decrypt are witnessed
+/// in the tree, and returned as `OwnCoin`s.
+///
+/// Secrets that are themselves view keys, as held by watch-only wallets,
+/// find the coins sent to their view address, but can't compute their
+/// nullifiers, so such coins never show up as spent. Their `nullifier`
+/// is meaningless, and wallets must keep them apart from their balance.
+pub fn discover_owncoins(
+    tree: &mut MerkleTree,
+    outputs: &[Output],
+    secrets: &[SecretKey],
+) -> Vec<OwnCoin> {
+    let view_keys: Vec<ViewKey> = secrets.iter().map(ViewKey::derive).collect();
+    let mut owncoins = vec![];
+
+    for output in outputs {
+        // Every coin has to be added, to keep the tree in sync
+        tree.append(MerkleNode::from(output.coin.inner()));
+
+        // The coin belongs to the spend secret, even when the note was
+        // encrypted to its view key.
+        let decrypted = secrets.iter().zip(&view_keys).find_map(|(secret, view_key)| {
+            output
+                .note
+                .decrypt::<MoneyNote>(secret)
+                .ok()
+                .or_else(|| view_key.decrypt_note(output))
+                .map(|note| (*secret, note))
+        });
+        let Some((secret, note)) = decrypted else { continue };
+
+        owncoins.push(OwnCoin {
+            coin: output.coin,
+            nullifier: Nullifier::from(poseidon_hash([secret.inner(), note.serial])),
+            note,
+            secret,
+            leaf_position: tree.mark().unwrap(),
+        });
+    }
+
+    owncoins
+}
@@ -121,6 +121,7 @@ impl SwapCallBuilder {
             value: self.value_recv,
             token_id: self.token_id_recv,
             public_key: self.pubkey,
+            view_public: None,
         };
 
         // Now we fill this with necessary stuff
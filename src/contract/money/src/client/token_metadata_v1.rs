@@ -0,0 +1,74 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{ClientFailed, Result};
+use darkfi_sdk::crypto::{Keypair, SecretKey, TokenId};
+use log::info;
+
+use crate::model::{MoneyTokenMetadataParamsV1, TokenMetadata};
+
+pub struct TokenMetadataCallDebris {
+    pub params: MoneyTokenMetadataParamsV1,
+    /// Secrets to sign the transaction with, in the order the contract
+    /// expects the call's signatures
+    pub signature_secrets: Vec<SecretKey>,
+}
+
+/// Struct holding necessary information to build a `Money::TokenMetadataV1` contract call.
+pub struct TokenMetadataCallBuilder {
+    /// Token issuer keypair
+    pub issuer: Keypair,
+    /// Nonce the token's mint authority is derived with
+    pub symbol_nonce: u64,
+    /// Token name
+    pub name: String,
+    /// Token symbol
+    pub symbol: String,
+    /// Number of decimals used to display token amounts
+    pub decimals: u8,
+    /// URI pointing to further token information
+    pub uri: String,
+}
+
+impl TokenMetadataCallBuilder {
+    pub fn build(&self) -> Result<TokenMetadataCallDebris> {
+        info!("Building Money::TokenMetadataV1 contract call");
+
+        let metadata = TokenMetadata {
+            issuer: self.issuer.public,
+            symbol_nonce: self.symbol_nonce,
+            name: self.name.clone(),
+            symbol: self.symbol.clone(),
+            decimals: self.decimals,
+            uri: self.uri.clone(),
+        };
+
+        if !metadata.is_valid() {
+            return Err(ClientFailed::VerifyError("Token metadata is invalid".to_string()).into())
+        }
+
+        // The token's mint authority follows the standard derivation, so the
+        // registered issuer and nonce are enough to recover it.
+        let mint_authority =
+            Keypair::new(TokenId::derive_mint_authority(self.issuer.secret, self.symbol_nonce));
+
+        let params = MoneyTokenMetadataParamsV1 { mint_public: mint_authority.public, metadata };
+        let signature_secrets = vec![mint_authority.secret, self.issuer.secret];
+        Ok(TokenMetadataCallDebris { params, signature_secrets })
+    }
+}
@@ -79,6 +79,8 @@ pub struct TokenMintCallBuilder {
     pub recipient: PublicKey,
     /// Amount of tokens we want to mint
     pub amount: u64,
+    /// Maximum supply of the token, only settable on its first mint
+    pub max_supply: Option<u64>,
     /// Spend hook for the output
     pub spend_hook: pallas::Base,
     /// User data for the output
@@ -108,6 +110,7 @@ impl TokenMintCallBuilder {
             value: self.amount,
             token_id,
             public_key: self.recipient,
+            view_public: None,
         };
 
         // We just create the pedersen commitment blinds here. We simply
@@ -160,7 +163,11 @@ impl TokenMintCallBuilder {
             note: encrypted_note,
         };
 
-        let params = MoneyTokenMintParamsV1 { input: c_input, output: c_output };
+        let params = MoneyTokenMintParamsV1 {
+            input: c_input,
+            output: c_output,
+            max_supply: self.max_supply,
+        };
         let debris = TokenMintCallDebris { params, proofs: vec![proof] };
         Ok(debris)
     }
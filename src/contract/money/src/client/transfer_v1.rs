@@ -122,6 +122,20 @@ pub struct TransactionBuilderOutputInfo {
     pub value: u64,
     pub token_id: TokenId,
     pub public_key: PublicKey,
+    /// View address to encrypt the note to, instead of `public_key`
+    pub view_public: Option<PublicKey>,
+}
+
+/// Number of times a client should rebuild a call whose proofs were
+/// created against a Merkle root that's no longer the latest one.
+pub const MAX_ROOT_REBUILDS: usize = 3;
+
+/// Check that the given Merkle tree is at the latest root known to the
+/// chain. Inclusion proofs created against a tree that's behind or diverged
+/// from the chain reference a stale root, and the call has to be rebuilt
+/// with Merkle paths re-derived from a freshly scanned tree.
+pub fn is_root_fresh(tree: &MerkleTree, latest_root: &MerkleNode) -> bool {
+    tree.root(0).as_ref() == Some(latest_root)
 }
 
 /// Struct holding necessary information to build a `Money::TransferV1` contract call.
@@ -140,12 +154,16 @@ pub struct TransferCallBuilder {
     pub rcpt_user_data: pallas::Base,
     /// User data blind for the recipient's output
     pub rcpt_user_data_blind: pallas::Base,
+    /// Recipient's view address, to let their view key see the output
+    pub rcpt_view_public: Option<PublicKey>,
     /// Spend hook for the change output
     pub change_spend_hook: pallas::Base,
     /// User data for the change output
     pub change_user_data: pallas::Base,
     /// User data blind for the change output
     pub change_user_data_blind: pallas::Base,
+    /// View address for the change output
+    pub change_view_public: Option<PublicKey>,
     /// Set of `OwnCoin` we're given to use in this builder
     pub coins: Vec<OwnCoin>,
     /// Merkle tree of coins used to create inclusion proofs
@@ -163,6 +181,12 @@ pub struct TransferCallBuilder {
 }
 
 impl TransferCallBuilder {
+    /// Check that the builder's Merkle tree is at the chain's latest root,
+    /// so the inclusion proofs it creates won't be stale.
+    pub fn is_root_fresh(&self, latest_root: &MerkleNode) -> bool {
+        is_root_fresh(&self.tree, latest_root)
+    }
+
     pub fn build(&self) -> Result<TransferCallDebris> {
         debug!("Building Money::TransferV1 contract call");
         assert!(self.value != 0);
@@ -185,6 +209,8 @@ impl TransferCallBuilder {
         let mut minted_coins = vec![];
         let mut signature_secrets = vec![];
         let mut proofs = vec![];
+        let (mut burn_circuits, mut burn_instances) = (vec![], vec![]);
+        let (mut mint_circuits, mut mint_instances) = (vec![], vec![]);
 
         if self.clear_input {
             debug!("Building clear input");
@@ -231,6 +257,7 @@ impl TransferCallBuilder {
                     value: return_value,
                     token_id: self.token_id,
                     public_key: self.keypair.public,
+                    view_public: self.change_view_public,
                 });
             }
 
@@ -241,6 +268,7 @@ impl TransferCallBuilder {
             value: self.value,
             token_id: self.token_id,
             public_key: self.recipient,
+            view_public: self.rcpt_view_public,
         });
 
         assert!(clear_inputs.len() + inputs.len() > 0);
@@ -273,16 +301,15 @@ impl TransferCallBuilder {
             let signature_secret = SecretKey::random(&mut OsRng);
             signature_secrets.push(signature_secret);
 
-            info!("Creating transfer burn proof for input {}", i);
-            let (proof, public_inputs) = create_transfer_burn_proof(
+            info!("Creating transfer burn circuit for input {}", i);
+            let (circuit, public_inputs) = transfer_burn_circuit(
                 &self.burn_zkbin,
-                &self.burn_pk,
                 input,
                 value_blind,
                 token_blind,
                 self.change_user_data_blind, // FIXME: We assume this, but it's just 1 usecase
                 signature_secret,
-            )?;
+            );
 
             params.inputs.push(Input {
                 value_commit: public_inputs.value_commit,
@@ -294,7 +321,8 @@ impl TransferCallBuilder {
                 signature_public: public_inputs.signature_public,
             });
 
-            proofs.push(proof);
+            burn_circuits.push(circuit);
+            burn_instances.push(public_inputs.to_vec());
         }
 
         // This value_blind calc assumes there will always be at least a single output
@@ -319,19 +347,19 @@ impl TransferCallBuilder {
                 }
             };
 
-            info!("Creating transfer mint proof for output {}", i);
-            let (proof, public_inputs) = create_transfer_mint_proof(
+            info!("Creating transfer mint circuit for output {}", i);
+            let (circuit, public_inputs) = transfer_mint_circuit(
                 &self.mint_zkbin,
-                &self.mint_pk,
                 output,
                 value_blind,
                 token_blind,
                 serial,
                 scoped_sh,
                 scoped_ud,
-            )?;
+            );
 
-            proofs.push(proof);
+            mint_instances.push(public_inputs.to_vec());
+            mint_circuits.push(circuit);
 
             // Encrypted note
             let note = MoneyNote {
@@ -345,7 +373,8 @@ impl TransferCallBuilder {
                 memo: vec![],
             };
 
-            let encrypted_note = AeadEncryptedNote::encrypt(&note, &output.public_key, &mut OsRng)?;
+            let note_public = output.view_public.unwrap_or(output.public_key);
+            let encrypted_note = AeadEncryptedNote::encrypt(&note, &note_public, &mut OsRng)?;
 
             minted_coins.push(OwnCoin {
                 coin: public_inputs.coin,
@@ -363,6 +392,20 @@ impl TransferCallBuilder {
             });
         }
 
+        // The inputs and the outputs each get a single aggregated proof, which
+        // is smaller and cheaper to verify than a proof per input and output.
+        if !burn_circuits.is_empty() {
+            info!("Creating aggregated transfer burn proof for {} inputs", burn_circuits.len());
+            let proof =
+                Proof::create_batch(&self.burn_pk, &burn_circuits, &burn_instances, &mut OsRng)?;
+            proofs.push(proof);
+        }
+
+        info!("Creating aggregated transfer mint proof for {} outputs", mint_circuits.len());
+        let proof =
+            Proof::create_batch(&self.mint_pk, &mint_circuits, &mint_instances, &mut OsRng)?;
+        proofs.push(proof);
+
         // Now we should have all the params, zk proofs, and signature secrets.
         // We return it all and let the caller deal with it.
         let debris = TransferCallDebris {
@@ -387,6 +430,29 @@ pub fn create_transfer_burn_proof(
     user_data_blind: pallas::Base,
     signature_secret: SecretKey,
 ) -> Result<(Proof, TransferBurnRevealed)> {
+    let (circuit, public_inputs) = transfer_burn_circuit(
+        zkbin,
+        input,
+        value_blind,
+        token_blind,
+        user_data_blind,
+        signature_secret,
+    );
+    let proof = Proof::create(pk, &[circuit], &public_inputs.to_vec(), &mut OsRng)?;
+
+    Ok((proof, public_inputs))
+}
+
+/// Build the `Burn_V1` circuit spending the given input, along with the
+/// public inputs it reveals, so it can be proven on its own or aggregated.
+pub fn transfer_burn_circuit(
+    zkbin: &ZkBinary,
+    input: &TransactionBuilderInputInfo,
+    value_blind: pallas::Scalar,
+    token_blind: pallas::Base,
+    user_data_blind: pallas::Base,
+    signature_secret: SecretKey,
+) -> (ZkCircuit, TransferBurnRevealed) {
     let nullifier = Nullifier::from(poseidon_hash([input.secret.inner(), input.note.serial]));
     let public_key = PublicKey::from_secret(input.secret);
     let (pub_x, pub_y) = public_key.xy();
@@ -446,10 +512,7 @@ pub fn create_transfer_burn_proof(
         Witness::Base(Value::known(signature_secret.inner())),
     ];
 
-    let circuit = ZkCircuit::new(prover_witnesses, zkbin);
-    let proof = Proof::create(pk, &[circuit], &public_inputs.to_vec(), &mut OsRng)?;
-
-    Ok((proof, public_inputs))
+    (ZkCircuit::new(prover_witnesses, zkbin), public_inputs)
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -463,6 +526,31 @@ pub fn create_transfer_mint_proof(
     spend_hook: pallas::Base,
     user_data: pallas::Base,
 ) -> Result<(Proof, TransferMintRevealed)> {
+    let (circuit, public_inputs) = transfer_mint_circuit(
+        zkbin,
+        output,
+        value_blind,
+        token_blind,
+        serial,
+        spend_hook,
+        user_data,
+    );
+    let proof = Proof::create(pk, &[circuit], &public_inputs.to_vec(), &mut OsRng)?;
+
+    Ok((proof, public_inputs))
+}
+
+/// Build the `Mint_V1` circuit creating the given output, along with the
+/// public inputs it reveals, so it can be proven on its own or aggregated.
+pub fn transfer_mint_circuit(
+    zkbin: &ZkBinary,
+    output: &TransactionBuilderOutputInfo,
+    value_blind: pallas::Scalar,
+    token_blind: pallas::Base,
+    serial: pallas::Base,
+    spend_hook: pallas::Base,
+    user_data: pallas::Base,
+) -> (ZkCircuit, TransferMintRevealed) {
     let value_commit = pedersen_commitment_u64(output.value, value_blind);
     let token_commit = poseidon_hash([output.token_id.inner(), token_blind]);
     let (pub_x, pub_y) = output.public_key.xy();
@@ -491,10 +579,7 @@ pub fn create_transfer_mint_proof(
         Witness::Base(Value::known(token_blind)),
     ];
 
-    let circuit = ZkCircuit::new(prover_witnesses, zkbin);
-    let proof = Proof::create(pk, &[circuit], &public_inputs.to_vec(), &mut OsRng)?;
-
-    Ok((proof, public_inputs))
+    (ZkCircuit::new(prover_witnesses, zkbin), public_inputs)
 }
 
 fn compute_remainder_blind(
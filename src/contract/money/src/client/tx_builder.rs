@@ -0,0 +1,105 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{tx::Transaction, zk::Proof, ClientFailed, Result};
+use darkfi_sdk::{
+    crypto::{Nullifier, SecretKey, MONEY_CONTRACT_ID},
+    ContractCall,
+};
+use darkfi_serial::Encodable;
+use log::{debug, error};
+use rand::rngs::OsRng;
+
+use crate::{
+    client::{transfer_v1::TransferCallDebris, OwnCoin},
+    MoneyFunction,
+};
+
+/// Assembles several contract calls into a single atomic `Transaction`,
+/// so clients don't have to glue call debris together manually. Calls
+/// are executed in the order they are added, and the transaction is only
+/// signed once all of them are in, since every signature covers all calls.
+#[derive(Default)]
+pub struct TransactionBuilder {
+    /// Contract calls, in order of execution
+    calls: Vec<ContractCall>,
+    /// ZK proofs of each call
+    proofs: Vec<Vec<Proof>>,
+    /// Secret keys signing each call
+    signature_secrets: Vec<Vec<SecretKey>>,
+    /// Nullifiers of the coins spent so far, so no coin is used by two calls
+    spent_nullifiers: Vec<Nullifier>,
+}
+
+impl TransactionBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a contract call, along with its proofs, signing keys, and the
+    /// coins it spends. Fails if any of the coins is already spent by a
+    /// previously added call, as the transaction would then be rejected.
+    pub fn add_call(
+        &mut self,
+        call: ContractCall,
+        proofs: Vec<Proof>,
+        signature_secrets: Vec<SecretKey>,
+        spent_coins: &[OwnCoin],
+    ) -> Result<()> {
+        for coin in spent_coins {
+            if self.spent_nullifiers.contains(&coin.nullifier) {
+                error!(target: "money::client::tx_builder", "Coin {:?} is spent by multiple calls", coin.coin);
+                return Err(ClientFailed::DuplicateCoin(format!("{:?}", coin.coin)).into())
+            }
+            self.spent_nullifiers.push(coin.nullifier);
+        }
+
+        debug!(target: "money::client::tx_builder", "Adding call {} to transaction", self.calls.len());
+        self.calls.push(call);
+        self.proofs.push(proofs);
+        self.signature_secrets.push(signature_secrets);
+        Ok(())
+    }
+
+    /// Append a `Money::TransferV1` call built from the given debris.
+    pub fn add_transfer(&mut self, debris: TransferCallDebris) -> Result<()> {
+        let mut data = vec![MoneyFunction::TransferV1 as u8];
+        debris.params.encode(&mut data)?;
+        let call = ContractCall { contract_id: *MONEY_CONTRACT_ID, data };
+
+        self.add_call(call, debris.proofs, debris.signature_secrets, &debris.spent_coins)
+    }
+
+    /// Produce the fully-signed transaction.
+    pub fn build(self) -> Result<Transaction> {
+        let mut tx = Transaction {
+            calls: self.calls,
+            proofs: self.proofs,
+            signatures: vec![],
+            access_list: None,
+        };
+
+        let mut signatures = Vec::with_capacity(self.signature_secrets.len());
+        for secrets in &self.signature_secrets {
+            signatures.push(tx.create_sigs(&mut OsRng, secrets)?);
+        }
+        tx.signatures = signatures;
+
+        Ok(tx)
+    }
+}
@@ -0,0 +1,71 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Incoming viewing keys. A view key is derived one-way from a spend
+//! secret, so handing it out lets e.g. an auditor trial-decrypt the notes
+//! of outputs sent to its view address, without being able to compute
+//! their nullifiers or sign for them. Senders opt into encrypting a note
+//! to the view address instead of the coin's public key, see the
+//! `rcpt_view_public` and `change_view_public` fields of
+//! [`TransferCallBuilder`](super::transfer_v1::TransferCallBuilder).
+
+use darkfi_sdk::{
+    crypto::{poseidon_hash, PublicKey, SecretKey},
+    pasta::pallas,
+};
+
+use crate::{client::MoneyNote, model::Output};
+
+/// Prefix separating view key derivation from other Poseidon uses
+const VIEW_KEY_PREFIX: u64 = 0x7669_6577;
+
+/// Key able to decrypt notes of outputs sent to its view address,
+/// but not to spend them.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ViewKey(SecretKey);
+
+impl ViewKey {
+    /// Derive the view key of the given spend secret.
+    pub fn derive(secret: &SecretKey) -> Self {
+        let prefix = pallas::Base::from(VIEW_KEY_PREFIX);
+        Self(SecretKey::from(poseidon_hash([prefix, secret.inner()])))
+    }
+
+    /// Secret used to decrypt notes. This is what gets exported to and
+    /// imported by watch-only wallets.
+    pub fn secret(&self) -> SecretKey {
+        self.0
+    }
+
+    /// View address that senders encrypt notes to.
+    pub fn public(&self) -> PublicKey {
+        PublicKey::from_secret(self.0)
+    }
+
+    /// Try to decrypt the note of the given output.
+    pub fn decrypt_note(&self, output: &Output) -> Option<MoneyNote> {
+        output.note.decrypt(&self.0).ok()
+    }
+}
+
+impl From<SecretKey> for ViewKey {
+    /// Wrap an already derived view key, e.g. one imported into a wallet.
+    fn from(secret: SecretKey) -> Self {
+        Self(secret)
+    }
+}
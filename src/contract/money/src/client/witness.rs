@@ -0,0 +1,124 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Incremental upkeep of the Merkle authentication paths of a wallet's
+//! coins. The service owns the wallet's copy of the coin Merkle tree and
+//! keeps a witness marked for each unspent coin, so call builders can get
+//! a fresh path without rescanning. Spent coins get their mark removed, to
+//! let the tree prune the nodes that were only needed for them.
+
+use darkfi::{tx::Transaction, Result};
+use darkfi_sdk::{
+    bridgetree,
+    crypto::{MerkleNode, MerkleTree, SecretKey},
+};
+use darkfi_serial::{SerialDecodable, SerialEncodable};
+
+use super::{
+    scan::{discover_owncoins, MoneyTxEffects},
+    OwnCoin,
+};
+use crate::model::Coin;
+
+/// Merkle authentication path of a coin against a given tree root
+#[derive(Debug, Clone, Eq, PartialEq, SerialEncodable, SerialDecodable)]
+pub struct CoinWitness {
+    /// The witnessed coin
+    pub coin: Coin,
+    /// Coin's leaf position in the Merkle tree of coins
+    pub leaf_position: bridgetree::Position,
+    /// Root of the tree the path authenticates against
+    pub root: MerkleNode,
+    /// Authentication path of the coin
+    pub path: Vec<MerkleNode>,
+}
+
+/// Service maintaining the coin Merkle tree and the witnesses of the
+/// wallet's unspent coins as transactions get scanned.
+pub struct CoinWitnessService {
+    /// The wallet's copy of the coin Merkle tree
+    tree: MerkleTree,
+    /// Unspent coins witnessed in the tree
+    coins: Vec<OwnCoin>,
+}
+
+impl CoinWitnessService {
+    /// Create a new service over the given tree, witnessing the given
+    /// unspent coins. Their leaves must already be marked in the tree.
+    pub fn new(tree: MerkleTree, coins: Vec<OwnCoin>) -> Self {
+        Self { tree, coins }
+    }
+
+    /// Reference to the maintained Merkle tree
+    pub fn tree(&self) -> &MerkleTree {
+        &self.tree
+    }
+
+    /// Unspent coins being witnessed
+    pub fn coins(&self) -> &[OwnCoin] {
+        &self.coins
+    }
+
+    /// Apply the `Money` effects of a transaction. New coins are appended
+    /// to the tree, the ones decrypting with the given secrets are witnessed
+    /// and returned, and coins whose nullifier got revealed are dropped.
+    pub fn apply_effects(
+        &mut self,
+        effects: &MoneyTxEffects,
+        secrets: &[SecretKey],
+    ) -> Vec<OwnCoin> {
+        let owncoins = discover_owncoins(&mut self.tree, &effects.outputs, secrets);
+        self.coins.extend(owncoins.iter().cloned());
+
+        let (spent, unspent) =
+            self.coins.drain(..).partition(|c| effects.nullifiers.contains(&c.nullifier));
+        self.coins = unspent;
+        for coin in spent {
+            self.tree.remove_mark(coin.leaf_position);
+        }
+
+        owncoins
+    }
+
+    /// Scan a transaction, see [`CoinWitnessService::apply_effects`].
+    pub fn apply_tx(&mut self, tx: &Transaction, secrets: &[SecretKey]) -> Result<Vec<OwnCoin>> {
+        let effects = MoneyTxEffects::from_tx(tx)?;
+        Ok(self.apply_effects(&effects, secrets))
+    }
+
+    /// Retrieve the current authentication path of the given coin,
+    /// or `None` if it isn't one of the witnessed unspent coins.
+    pub fn get_merkle_path(&self, coin: &Coin) -> Option<CoinWitness> {
+        let owncoin = self.coins.iter().find(|c| &c.coin == coin)?;
+        self.witness(owncoin)
+    }
+
+    /// Retrieve the current authentication paths of all witnessed coins.
+    pub fn witnesses(&self) -> Vec<CoinWitness> {
+        self.coins.iter().filter_map(|c| self.witness(c)).collect()
+    }
+
+    fn witness(&self, owncoin: &OwnCoin) -> Option<CoinWitness> {
+        Some(CoinWitness {
+            coin: owncoin.coin,
+            leaf_position: owncoin.leaf_position,
+            root: self.tree.root(0)?,
+            path: self.tree.witness(owncoin.leaf_position, 0)?,
+        })
+    }
+}
@@ -17,24 +17,34 @@
  */
 
 use darkfi_sdk::{
-    crypto::{pasta_prelude::Field, ContractId, MerkleNode, MerkleTree, PublicKey},
-    db::{db_init, db_lookup, db_set, zkas_db_set},
+    crypto::{pasta_prelude::Field, ContractId, MerkleFrontier, MerkleNode, PublicKey},
+    db::{db_init, db_lookup, db_set, zkas_db_set_declared},
     error::{ContractError, ContractResult},
     msg,
     pasta::pallas,
     util::set_return_data,
     ContractCall,
 };
-use darkfi_serial::{deserialize, serialize, Encodable, WriteExt};
+use darkfi_serial::{deserialize, serialize};
 
 use crate::{
     model::{
-        MoneyStakeUpdateV1, MoneyTokenFreezeUpdateV1, MoneyTokenMintUpdateV1,
-        MoneyTransferUpdateV1, MoneyUnstakeUpdateV1,
+        MoneyStakeUpdateV1, MoneyTokenFreezeUpdateV1, MoneyTokenMetadataUpdateV1,
+        MoneyTokenMintUpdateV1, MoneyTransferUpdateV1, MoneyUnstakeUpdateV1,
     },
-    MoneyFunction, MONEY_CONTRACT_COINS_TREE, MONEY_CONTRACT_COIN_MERKLE_TREE,
+    MoneyFunction, MONEY_CONTRACT_COINS_TREE, MONEY_CONTRACT_COIN_MERKLE_FRONTIER,
     MONEY_CONTRACT_COIN_ROOTS_TREE, MONEY_CONTRACT_DB_VERSION, MONEY_CONTRACT_FAUCET_PUBKEYS,
     MONEY_CONTRACT_INFO_TREE, MONEY_CONTRACT_NULLIFIERS_TREE, MONEY_CONTRACT_TOKEN_FREEZE_TREE,
+    MONEY_CONTRACT_TOKEN_ISSUERS_TREE, MONEY_CONTRACT_TOKEN_METADATA_TREE,
+    MONEY_CONTRACT_TOKEN_MINT_INFO_TREE, MONEY_CONTRACT_TOKEN_SUPPLY_TREE,
+    MONEY_CONTRACT_ZKAS_BURN_PI_V1, MONEY_CONTRACT_ZKAS_MINT_PI_V1,
+    MONEY_CONTRACT_ZKAS_TOKEN_FRZ_PI_V1, MONEY_CONTRACT_ZKAS_TOKEN_MINT_PI_V1,
+};
+
+/// `Money::Fee` functions
+mod fee_v1;
+use fee_v1::{
+    money_fee_get_metadata_v1, money_fee_process_instruction_v1, money_fee_process_update_v1,
 };
 
 /// `Money::Transfer` functions
@@ -84,6 +94,13 @@ use unstake_v1::{
     money_unstake_process_update_v1,
 };
 
+/// `Money::TokenMetadata` functions
+mod token_metadata_v1;
+use token_metadata_v1::{
+    money_token_metadata_get_metadata_v1, money_token_metadata_process_instruction_v1,
+    money_token_metadata_process_update_v1,
+};
+
 darkfi_sdk::define_contract!(
     init: init_contract,
     exec: process_instruction,
@@ -109,11 +126,12 @@ fn init_contract(cid: ContractId, ix: &[u8]) -> ContractResult {
     let token_mint_v1_bincode = include_bytes!("../proof/token_mint_v1.zk.bin");
     let token_frz_v1_bincode = include_bytes!("../proof/token_freeze_v1.zk.bin");
 
-    // For that, we use `zkas_db_set` and pass in the bincode.
-    zkas_db_set(&mint_v1_bincode[..])?;
-    zkas_db_set(&burn_v1_bincode[..])?;
-    zkas_db_set(&token_mint_v1_bincode[..])?;
-    zkas_db_set(&token_frz_v1_bincode[..])?;
+    // For that, we use `zkas_db_set_declared` and pass in the bincode along
+    // with the number of public inputs we build for it in metadata.
+    zkas_db_set_declared(&mint_v1_bincode[..], MONEY_CONTRACT_ZKAS_MINT_PI_V1)?;
+    zkas_db_set_declared(&burn_v1_bincode[..], MONEY_CONTRACT_ZKAS_BURN_PI_V1)?;
+    zkas_db_set_declared(&token_mint_v1_bincode[..], MONEY_CONTRACT_ZKAS_TOKEN_MINT_PI_V1)?;
+    zkas_db_set_declared(&token_frz_v1_bincode[..], MONEY_CONTRACT_ZKAS_TOKEN_FRZ_PI_V1)?;
 
     // Set up a database tree to hold Merkle roots of all coins
     // k=MerkleNode, v=[]
@@ -139,22 +157,46 @@ fn init_contract(cid: ContractId, ix: &[u8]) -> ContractResult {
         db_init(cid, MONEY_CONTRACT_TOKEN_FREEZE_TREE)?;
     }
 
+    // Set up a database tree to hold registered token metadata
+    // k=TokenId, v=TokenMetadata
+    if db_lookup(cid, MONEY_CONTRACT_TOKEN_METADATA_TREE).is_err() {
+        db_init(cid, MONEY_CONTRACT_TOKEN_METADATA_TREE)?;
+    }
+
+    // Set up a database tree to look up tokens by their issuer
+    // k=(PublicKey, u64), v=TokenId
+    if db_lookup(cid, MONEY_CONTRACT_TOKEN_ISSUERS_TREE).is_err() {
+        db_init(cid, MONEY_CONTRACT_TOKEN_ISSUERS_TREE)?;
+    }
+
+    // Set up a database tree to hold the mint state of each token
+    // k=TokenId, v=TokenMintInfo
+    if db_lookup(cid, MONEY_CONTRACT_TOKEN_MINT_INFO_TREE).is_err() {
+        db_init(cid, MONEY_CONTRACT_TOKEN_MINT_INFO_TREE)?;
+    }
+
+    // Set up a database tree to hold the homomorphic supply of each token
+    // k=TokenId, v=TokenSupply
+    if db_lookup(cid, MONEY_CONTRACT_TOKEN_SUPPLY_TREE).is_err() {
+        db_init(cid, MONEY_CONTRACT_TOKEN_SUPPLY_TREE)?;
+    }
+
     // Set up a database tree for arbitrary data
     let info_db = match db_lookup(cid, MONEY_CONTRACT_INFO_TREE) {
         Ok(v) => v,
         Err(_) => {
             let info_db = db_init(cid, MONEY_CONTRACT_INFO_TREE)?;
 
-            // Create the incrementalmerkletree for seen coins and initialize
+            // Create the Merkle frontier for seen coins and initialize
             // it with a "fake" coin that can be used for dummy inputs.
-            let mut coin_tree = MerkleTree::new(100);
-            coin_tree.append(MerkleNode::from(pallas::Base::ZERO));
-
-            let mut coin_tree_data = vec![];
-            coin_tree_data.write_u32(0)?;
-            coin_tree.encode(&mut coin_tree_data)?;
-
-            db_set(info_db, &serialize(&MONEY_CONTRACT_COIN_MERKLE_TREE), &coin_tree_data)?;
+            let mut coin_frontier = MerkleFrontier::empty();
+            coin_frontier.append(MerkleNode::from(pallas::Base::ZERO));
+
+            db_set(
+                info_db,
+                &serialize(&MONEY_CONTRACT_COIN_MERKLE_FRONTIER),
+                &serialize(&coin_frontier),
+            )?;
             info_db
         }
     };
@@ -183,6 +225,11 @@ fn get_metadata(cid: ContractId, ix: &[u8]) -> ContractResult {
     }
 
     match MoneyFunction::try_from(calls[call_idx as usize].data[0])? {
+        MoneyFunction::FeeV1 => {
+            let metadata = money_fee_get_metadata_v1(cid, call_idx, calls)?;
+            Ok(set_return_data(&metadata)?)
+        }
+
         MoneyFunction::TransferV1 => {
             // We pass everything into the correct function, and it will return
             // the metadata for us, which we can then copy into the host with
@@ -221,6 +268,11 @@ fn get_metadata(cid: ContractId, ix: &[u8]) -> ContractResult {
             let metadata = money_unstake_get_metadata_v1(cid, call_idx, calls)?;
             Ok(set_return_data(&metadata)?)
         }
+
+        MoneyFunction::TokenMetadataV1 => {
+            let metadata = money_token_metadata_get_metadata_v1(cid, call_idx, calls)?;
+            Ok(set_return_data(&metadata)?)
+        }
     }
 }
 
@@ -235,6 +287,11 @@ fn process_instruction(cid: ContractId, ix: &[u8]) -> ContractResult {
     }
 
     match MoneyFunction::try_from(calls[call_idx as usize].data[0])? {
+        MoneyFunction::FeeV1 => {
+            let update_data = money_fee_process_instruction_v1(cid, call_idx, calls)?;
+            Ok(set_return_data(&update_data)?)
+        }
+
         MoneyFunction::TransferV1 => {
             // Again, we pass everything into the correct function.
             // If it executes successfully, we'll get a state update
@@ -274,6 +331,11 @@ fn process_instruction(cid: ContractId, ix: &[u8]) -> ContractResult {
             let update_data = money_unstake_process_instruction_v1(cid, call_idx, calls)?;
             Ok(set_return_data(&update_data)?)
         }
+
+        MoneyFunction::TokenMetadataV1 => {
+            let update_data = money_token_metadata_process_instruction_v1(cid, call_idx, calls)?;
+            Ok(set_return_data(&update_data)?)
+        }
     }
 }
 
@@ -283,6 +345,12 @@ fn process_instruction(cid: ContractId, ix: &[u8]) -> ContractResult {
 /// is the update data retrieved from `process_instruction()`.
 fn process_update(cid: ContractId, update_data: &[u8]) -> ContractResult {
     match MoneyFunction::try_from(update_data[0])? {
+        MoneyFunction::FeeV1 => {
+            // The fee call uses the same state update as `Money::Transfer`
+            let update: MoneyTransferUpdateV1 = deserialize(&update_data[1..])?;
+            Ok(money_fee_process_update_v1(cid, update)?)
+        }
+
         MoneyFunction::TransferV1 => {
             let update: MoneyTransferUpdateV1 = deserialize(&update_data[1..])?;
             Ok(money_transfer_process_update_v1(cid, update)?)
@@ -320,5 +388,10 @@ fn process_update(cid: ContractId, update_data: &[u8]) -> ContractResult {
             let update: MoneyUnstakeUpdateV1 = deserialize(&update_data[1..])?;
             Ok(money_unstake_process_update_v1(cid, update)?)
         }
+
+        MoneyFunction::TokenMetadataV1 => {
+            let update: MoneyTokenMetadataUpdateV1 = deserialize(&update_data[1..])?;
+            Ok(money_token_metadata_process_update_v1(cid, update)?)
+        }
     }
 }
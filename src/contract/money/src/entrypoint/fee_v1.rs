@@ -0,0 +1,171 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use darkfi_sdk::{
+    crypto::{pasta_prelude::*, ContractId, PublicKey, DARK_TOKEN_ID},
+    db::{db_contains_key, db_lookup, NullifierSet},
+    error::{ContractError, ContractResult},
+    hash::{pedersen_commitment_u64, poseidon_hash},
+    msg,
+    pasta::pallas,
+    util::set_fee_paid,
+    ContractCall,
+};
+use darkfi_serial::{deserialize, serialize, Encodable, WriteExt};
+
+use super::transfer_v1::money_transfer_apply_update_v1;
+use crate::{
+    error::MoneyError,
+    model::{MoneyFeeParamsV1, MoneyTransferUpdateV1},
+    MoneyFunction, MONEY_CONTRACT_COINS_TREE, MONEY_CONTRACT_COIN_ROOTS_TREE,
+    MONEY_CONTRACT_NULLIFIERS_TREE, MONEY_CONTRACT_ZKAS_BURN_NS_V1, MONEY_CONTRACT_ZKAS_MINT_NS_V1,
+};
+
+/// `get_metadata` function for `Money::FeeV1`
+pub(crate) fn money_fee_get_metadata_v1(
+    _cid: ContractId,
+    call_idx: u32,
+    calls: Vec<ContractCall>,
+) -> Result<Vec<u8>, ContractError> {
+    let self_ = &calls[call_idx as usize];
+    let params: MoneyFeeParamsV1 = deserialize(&self_.data[1..])?;
+    let input = &params.input;
+    let output = &params.output;
+
+    // Public inputs for the ZK proofs we have to verify
+    let mut zk_public_inputs: Vec<(String, Vec<pallas::Base>)> = vec![];
+    // Public keys for the transaction signatures we have to verify
+    let signature_pubkeys: Vec<PublicKey> = vec![input.signature_public];
+
+    let input_value_coords = input.value_commit.to_affine().coordinates().unwrap();
+    let (sig_x, sig_y) = input.signature_public.xy();
+
+    // It is very important that these are in the same order as the
+    // `constrain_instance` calls in the zkas code.
+    // Otherwise verification will fail.
+    zk_public_inputs.push((
+        MONEY_CONTRACT_ZKAS_BURN_NS_V1.to_string(),
+        vec![
+            input.nullifier.inner(),
+            *input_value_coords.x(),
+            *input_value_coords.y(),
+            input.token_commit,
+            input.merkle_root.inner(),
+            input.user_data_enc,
+            input.spend_hook,
+            sig_x,
+            sig_y,
+        ],
+    ));
+
+    let output_value_coords = output.value_commit.to_affine().coordinates().unwrap();
+    zk_public_inputs.push((
+        MONEY_CONTRACT_ZKAS_MINT_NS_V1.to_string(),
+        vec![
+            output.coin.inner(),
+            *output_value_coords.x(),
+            *output_value_coords.y(),
+            output.token_commit,
+        ],
+    ));
+
+    // Serialize everything gathered and return it
+    let mut metadata = vec![];
+    zk_public_inputs.encode(&mut metadata)?;
+    signature_pubkeys.encode(&mut metadata)?;
+
+    Ok(metadata)
+}
+
+/// `process_instruction` function for `Money::FeeV1`
+pub(crate) fn money_fee_process_instruction_v1(
+    cid: ContractId,
+    call_idx: u32,
+    calls: Vec<ContractCall>,
+) -> Result<Vec<u8>, ContractError> {
+    let self_ = &calls[call_idx as usize];
+    let params: MoneyFeeParamsV1 = deserialize(&self_.data[1..])?;
+    let input = &params.input;
+    let output = &params.output;
+
+    // Access the necessary databases where there is information to
+    // validate this state transition.
+    let coins_db = db_lookup(cid, MONEY_CONTRACT_COINS_TREE)?;
+    let nullifiers = NullifierSet::lookup(cid, MONEY_CONTRACT_NULLIFIERS_TREE)?;
+    let coin_roots_db = db_lookup(cid, MONEY_CONTRACT_COIN_ROOTS_TREE)?;
+
+    // The Merkle root is used to know whether this is a coin that
+    // existed in a previous state.
+    if !db_contains_key(coin_roots_db, &serialize(&input.merkle_root))? {
+        msg!("[FeeV1] Error: Merkle root not found in previous state");
+        return Err(MoneyError::TransferMerkleRootNotFound.into())
+    }
+
+    // The nullifier should not already exist. It is the double-spend protection.
+    if nullifiers.contains(&input.nullifier)? {
+        msg!("[FeeV1] Error: Duplicate nullifier found");
+        return Err(MoneyError::DuplicateNullifier.into())
+    }
+
+    // The fee is paid on its own, so the spent coin can't hand over
+    // execution to another contract.
+    if input.spend_hook != pallas::Base::ZERO {
+        msg!("[FeeV1] Error: Input has a spend hook set");
+        return Err(MoneyError::SpendHookNonZero.into())
+    }
+
+    if db_contains_key(coins_db, &serialize(&output.coin))? {
+        msg!("[FeeV1] Error: Duplicate coin found in output");
+        return Err(MoneyError::DuplicateCoin.into())
+    }
+
+    // The input value has to cover the change output along with the
+    // paid fee, which is committed to in the clear.
+    let fee_commit = pedersen_commitment_u64(params.fee_value, params.fee_value_blind)?;
+    if input.value_commit != output.value_commit + fee_commit {
+        msg!("[FeeV1] Error: Value commitments do not match the paid fee");
+        return Err(MoneyError::ValueMismatch.into())
+    }
+
+    // Fees can only be paid in the native token
+    let tokcom = poseidon_hash(&[DARK_TOKEN_ID.inner(), params.token_blind])?;
+    if input.token_commit != tokcom || output.token_commit != tokcom {
+        msg!("[FeeV1] Error: Fee is not paid in the native token");
+        return Err(MoneyError::FeeNonNativeToken.into())
+    }
+
+    // Let the host know how much this call paid, so it can check it
+    // against the cost of verifying the whole transaction.
+    set_fee_paid(params.fee_value)?;
+
+    // At this point the state transition has passed, so we create a state update
+    let update =
+        MoneyTransferUpdateV1 { nullifiers: vec![input.nullifier], coins: vec![output.coin] };
+    let mut update_data = vec![];
+    update_data.write_u8(MoneyFunction::FeeV1 as u8)?;
+    update.encode(&mut update_data)?;
+    // and return it
+    Ok(update_data)
+}
+
+/// `process_update` function for `Money::FeeV1`
+pub(crate) fn money_fee_process_update_v1(
+    cid: ContractId,
+    update: MoneyTransferUpdateV1,
+) -> ContractResult {
+    money_transfer_apply_update_v1(cid, update, MoneyFunction::FeeV1)
+}
@@ -17,9 +17,10 @@
  */
 
 use darkfi_sdk::{
-    crypto::{pasta_prelude::*, pedersen_commitment_u64, poseidon_hash, ContractId, DARK_TOKEN_ID},
+    crypto::{pasta_prelude::*, ContractId, DARK_TOKEN_ID},
     db::{db_contains_key, db_lookup},
     error::ContractError,
+    hash::{pedersen_commitment_u64, poseidon_hash},
     msg,
     pasta::pallas,
     util::get_verifying_slot,
@@ -103,22 +104,28 @@ pub(crate) fn money_genesis_mint_process_instruction_v1(
     // Verify that the value and token commitments match. In here we just
     // confirm that the clear input and the anon output have the same
     // commitments.
-    if pedersen_commitment_u64(params.input.value, params.input.value_blind) !=
+    if pedersen_commitment_u64(params.input.value, params.input.value_blind)? !=
         params.output.value_commit
     {
         msg!("[GenesisMintV1] Error: Value commitment mismatch");
         return Err(MoneyError::ValueMismatch.into())
     }
 
-    if poseidon_hash([params.input.token_id.inner(), params.input.token_blind]) !=
+    if poseidon_hash(&[params.input.token_id.inner(), params.input.token_blind])? !=
         params.output.token_commit
     {
         msg!("[GenesisMintV1] Error: Token commitment mismatch");
         return Err(MoneyError::TokenMismatch.into())
     }
 
-    // Create a state update. We only need the new coin.
-    let update = MoneyTokenMintUpdateV1 { coin: params.output.coin };
+    // Create a state update. We only need the new coin, the native
+    // token has no mint state or supply to track.
+    let update = MoneyTokenMintUpdateV1 {
+        coin: params.output.coin,
+        token_id: params.input.token_id,
+        mint_info: None,
+        supply: None,
+    };
     let mut update_data = vec![];
     update_data.write_u8(MoneyFunction::TokenMintV1 as u8)?;
     update.encode(&mut update_data)?;
@@ -17,9 +17,10 @@
  */
 
 use darkfi_sdk::{
-    crypto::{pasta_prelude::*, poseidon_hash, ContractId, CONSENSUS_CONTRACT_ID, DARK_TOKEN_ID},
-    db::{db_contains_key, db_lookup, db_set},
+    crypto::{pasta_prelude::*, ContractId, CONSENSUS_CONTRACT_ID, DARK_TOKEN_ID},
+    db::{db_contains_key, db_lookup, NullifierDomain, NullifierSet},
     error::{ContractError, ContractResult},
+    hash::poseidon_hash,
     msg,
     pasta::pallas,
     ContractCall,
@@ -90,7 +91,7 @@ pub(crate) fn money_stake_process_instruction_v1(
 
     // Access the necessary databases where there is information to
     // validate this state transition.
-    let nullifiers_db = db_lookup(cid, MONEY_CONTRACT_NULLIFIERS_TREE)?;
+    let nullifiers = NullifierSet::lookup(cid, MONEY_CONTRACT_NULLIFIERS_TREE)?;
     let coin_roots_db = db_lookup(cid, MONEY_CONTRACT_COIN_ROOTS_TREE)?;
 
     // ===================================
@@ -107,7 +108,7 @@ pub(crate) fn money_stake_process_instruction_v1(
     }
 
     // Only native token can be staked
-    if input.token_commit != poseidon_hash([DARK_TOKEN_ID.inner(), params.token_blind]) {
+    if input.token_commit != poseidon_hash(&[DARK_TOKEN_ID.inner(), params.token_blind])? {
         msg!("[MoneyStakeV1] Error: Input used non-native token");
         return Err(MoneyError::StakeInputNonNativeToken.into())
     }
@@ -120,7 +121,7 @@ pub(crate) fn money_stake_process_instruction_v1(
     }
 
     // The nullifiers should not already exist. It is the double-spend protection.
-    if db_contains_key(nullifiers_db, &serialize(&input.nullifier))? {
+    if nullifiers.contains(&input.nullifier)? {
         msg!("[MoneyStakeV1] Error: Duplicate nullifier found");
         return Err(MoneyError::DuplicateNullifier.into())
     }
@@ -167,10 +168,11 @@ pub(crate) fn money_stake_process_update_v1(
     update: MoneyStakeUpdateV1,
 ) -> ContractResult {
     // Grab all necessary db handles for where we want to write
-    let nullifiers_db = db_lookup(cid, MONEY_CONTRACT_NULLIFIERS_TREE)?;
+    let nullifiers = NullifierSet::lookup(cid, MONEY_CONTRACT_NULLIFIERS_TREE)?;
+    let domain = NullifierDomain::new(cid, MoneyFunction::StakeV1 as u8);
 
     msg!("[MoneyStakeV1] Adding new nullifier to the set");
-    db_set(nullifiers_db, &serialize(&update.nullifier), &[])?;
+    nullifiers.insert(&update.nullifier, &domain)?;
 
     Ok(())
 }
@@ -18,7 +18,7 @@
 
 use darkfi_sdk::{
     crypto::{pasta_prelude::*, ContractId},
-    db::{db_contains_key, db_lookup},
+    db::{db_contains_key, db_lookup, NullifierSet},
     error::{ContractError, ContractResult},
     msg,
     pasta::pallas,
@@ -26,7 +26,7 @@ use darkfi_sdk::{
 };
 use darkfi_serial::{deserialize, serialize, Encodable, WriteExt};
 
-use super::transfer_v1::{money_transfer_get_metadata_v1, money_transfer_process_update_v1};
+use super::transfer_v1::{money_transfer_apply_update_v1, money_transfer_get_metadata_v1};
 use crate::{
     error::MoneyError,
     model::{MoneyTransferParamsV1, MoneyTransferUpdateV1},
@@ -77,7 +77,7 @@ pub(crate) fn money_otcswap_process_instruction_v1(
 
     // Grab the db handles we'll be using here
     let coins_db = db_lookup(cid, MONEY_CONTRACT_COINS_TREE)?;
-    let nullifiers_db = db_lookup(cid, MONEY_CONTRACT_NULLIFIERS_TREE)?;
+    let nullifiers = NullifierSet::lookup(cid, MONEY_CONTRACT_NULLIFIERS_TREE)?;
     let coin_roots_db = db_lookup(cid, MONEY_CONTRACT_COIN_ROOTS_TREE)?;
 
     // We expect two new nullifiers and two new coins
@@ -125,9 +125,7 @@ pub(crate) fn money_otcswap_process_instruction_v1(
         }
 
         // The nullifiers should not already exist. It is the double-spend protection.
-        if new_nullifiers.contains(&input.nullifier) ||
-            db_contains_key(nullifiers_db, &serialize(&input.nullifier))?
-        {
+        if new_nullifiers.contains(&input.nullifier) || nullifiers.contains(&input.nullifier)? {
             msg!("[OtcSwapV1] Error: Duplicate nullifier found in input {}", i);
             return Err(MoneyError::DuplicateNullifier.into())
         }
@@ -165,6 +163,7 @@ pub(crate) fn money_otcswap_process_update_v1(
     cid: ContractId,
     update: MoneyTransferUpdateV1,
 ) -> ContractResult {
-    // In here we can use the same function as we use in `TransferV1`.
-    money_transfer_process_update_v1(cid, update)
+    // In here we can use the same function as we use in `TransferV1`,
+    // recording the nullifiers as revealed by `OtcSwapV1`.
+    money_transfer_apply_update_v1(cid, update, MoneyFunction::OtcSwapV1)
 }
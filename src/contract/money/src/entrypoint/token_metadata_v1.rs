@@ -0,0 +1,116 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_sdk::{
+    crypto::{ContractId, PublicKey, TokenId},
+    db::{db_contains_key, db_lookup, db_set},
+    error::{ContractError, ContractResult},
+    msg,
+    pasta::pallas,
+    ContractCall,
+};
+use darkfi_serial::{deserialize, serialize, Encodable, WriteExt};
+
+use crate::{
+    error::MoneyError,
+    model::{MoneyTokenMetadataParamsV1, MoneyTokenMetadataUpdateV1},
+    MoneyFunction, MONEY_CONTRACT_TOKEN_ISSUERS_TREE, MONEY_CONTRACT_TOKEN_METADATA_TREE,
+};
+
+/// `get_metadata` function for `Money::TokenMetadataV1`
+pub(crate) fn money_token_metadata_get_metadata_v1(
+    _cid: ContractId,
+    call_idx: u32,
+    calls: Vec<ContractCall>,
+) -> Result<Vec<u8>, ContractError> {
+    let self_ = &calls[call_idx as usize];
+    let params: MoneyTokenMetadataParamsV1 = deserialize(&self_.data[1..])?;
+
+    // Public inputs for the ZK proofs we have to verify
+    let zk_public_inputs: Vec<(String, Vec<pallas::Base>)> = vec![];
+    // Public keys for the transaction signatures we have to verify.
+    // The mint authority signs to prove it controls the token, and the
+    // issuer signs to claim it, so neither can register on the other's behalf.
+    let signature_pubkeys: Vec<PublicKey> = vec![params.mint_public, params.metadata.issuer];
+
+    // Serialize everything gathered and return it
+    let mut metadata = vec![];
+    zk_public_inputs.encode(&mut metadata)?;
+    signature_pubkeys.encode(&mut metadata)?;
+
+    Ok(metadata)
+}
+
+/// `process_instruction` function for `Money::TokenMetadataV1`
+pub(crate) fn money_token_metadata_process_instruction_v1(
+    cid: ContractId,
+    call_idx: u32,
+    calls: Vec<ContractCall>,
+) -> Result<Vec<u8>, ContractError> {
+    let self_ = &calls[call_idx as usize];
+    let params: MoneyTokenMetadataParamsV1 = deserialize(&self_.data[1..])?;
+
+    if !params.metadata.is_valid() {
+        msg!("[TokenMetadataV1] Error: Token metadata fields are out of bounds");
+        return Err(MoneyError::TokenMetadataInvalid.into())
+    }
+
+    let token_metadata_db = db_lookup(cid, MONEY_CONTRACT_TOKEN_METADATA_TREE)?;
+    let token_issuers_db = db_lookup(cid, MONEY_CONTRACT_TOKEN_ISSUERS_TREE)?;
+    let token_id = TokenId::derive_public(params.mint_public);
+
+    // Metadata can only be registered once per token, and an issuer can
+    // only use each nonce once.
+    if db_contains_key(token_metadata_db, &serialize(&token_id))? {
+        msg!("[TokenMetadataV1] Error: Metadata for token {} already exists", token_id);
+        return Err(MoneyError::TokenMetadataExists.into())
+    }
+
+    let issuer_key = serialize(&(params.metadata.issuer, params.metadata.symbol_nonce));
+    if db_contains_key(token_issuers_db, &issuer_key)? {
+        msg!(
+            "[TokenMetadataV1] Error: Issuer already registered nonce {}",
+            params.metadata.symbol_nonce
+        );
+        return Err(MoneyError::TokenMetadataExists.into())
+    }
+
+    // Create a state update
+    let update = MoneyTokenMetadataUpdateV1 { token_id, metadata: params.metadata };
+    let mut update_data = vec![];
+    update_data.write_u8(MoneyFunction::TokenMetadataV1 as u8)?;
+    update.encode(&mut update_data)?;
+
+    Ok(update_data)
+}
+
+/// `process_update` function for `Money::TokenMetadataV1`
+pub(crate) fn money_token_metadata_process_update_v1(
+    cid: ContractId,
+    update: MoneyTokenMetadataUpdateV1,
+) -> ContractResult {
+    let token_metadata_db = db_lookup(cid, MONEY_CONTRACT_TOKEN_METADATA_TREE)?;
+    let token_issuers_db = db_lookup(cid, MONEY_CONTRACT_TOKEN_ISSUERS_TREE)?;
+
+    msg!("[TokenMetadataV1] Registering metadata for token {}", update.token_id);
+    let issuer_key = serialize(&(update.metadata.issuer, update.metadata.symbol_nonce));
+    db_set(token_issuers_db, &issuer_key, &serialize(&update.token_id))?;
+    db_set(token_metadata_db, &serialize(&update.token_id), &serialize(&update.metadata))?;
+
+    Ok(())
+}
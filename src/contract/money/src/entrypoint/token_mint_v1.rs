@@ -17,12 +17,11 @@
  */
 
 use darkfi_sdk::{
-    crypto::{
-        pasta_prelude::*, pedersen_commitment_u64, poseidon_hash, ContractId, MerkleNode, TokenId,
-    },
-    db::{db_contains_key, db_lookup, db_set},
+    crypto::{pasta_prelude::*, ContractId, MerkleNode, TokenId},
+    db::{db_contains_key, db_get, db_lookup, db_set},
     error::{ContractError, ContractResult},
-    merkle_add, msg,
+    hash::{pedersen_commitment_u64, poseidon_hash},
+    merkle_frontier_add, msg,
     pasta::pallas,
     ContractCall,
 };
@@ -30,10 +29,11 @@ use darkfi_serial::{deserialize, serialize, Encodable, WriteExt};
 
 use crate::{
     error::MoneyError,
-    model::{MoneyTokenMintParamsV1, MoneyTokenMintUpdateV1},
-    MoneyFunction, MONEY_CONTRACT_COINS_TREE, MONEY_CONTRACT_COIN_MERKLE_TREE,
+    model::{MoneyTokenMintParamsV1, MoneyTokenMintUpdateV1, TokenMintInfo, TokenSupply},
+    MoneyFunction, MONEY_CONTRACT_COINS_TREE, MONEY_CONTRACT_COIN_MERKLE_FRONTIER,
     MONEY_CONTRACT_COIN_ROOTS_TREE, MONEY_CONTRACT_INFO_TREE, MONEY_CONTRACT_LATEST_COIN_ROOT,
-    MONEY_CONTRACT_TOKEN_FREEZE_TREE, MONEY_CONTRACT_ZKAS_TOKEN_MINT_NS_V1,
+    MONEY_CONTRACT_TOKEN_FREEZE_TREE, MONEY_CONTRACT_TOKEN_MINT_INFO_TREE,
+    MONEY_CONTRACT_TOKEN_SUPPLY_TREE, MONEY_CONTRACT_ZKAS_TOKEN_MINT_NS_V1,
 };
 
 /// `get_metadata` function for `Money::TokenMintV1`
@@ -93,6 +93,7 @@ pub(crate) fn money_token_mint_process_instruction_v1(
     // the minted coin has existed already.
     let coins_db = db_lookup(cid, MONEY_CONTRACT_COINS_TREE)?;
     let token_freeze_db = db_lookup(cid, MONEY_CONTRACT_TOKEN_FREEZE_TREE)?;
+    let token_mint_info_db = db_lookup(cid, MONEY_CONTRACT_TOKEN_MINT_INFO_TREE)?;
 
     // Check that the signature public key is actually the token ID
     let token_id = TokenId::derive_public(params.input.signature_public);
@@ -116,22 +117,72 @@ pub(crate) fn money_token_mint_process_instruction_v1(
     // Verify that the value and token commitments match. In here we just
     // confirm that the clear input and the anon output have the same
     // commitments.
-    if pedersen_commitment_u64(params.input.value, params.input.value_blind) !=
+    if pedersen_commitment_u64(params.input.value, params.input.value_blind)? !=
         params.output.value_commit
     {
         msg!("[MintV1] Error: Value commitment mismatch");
         return Err(MoneyError::ValueMismatch.into())
     }
 
-    if poseidon_hash([params.input.token_id.inner(), params.input.token_blind]) !=
+    if poseidon_hash(&[params.input.token_id.inner(), params.input.token_blind])? !=
         params.output.token_commit
     {
         msg!("[MintV1] Error: Token commitment mismatch");
         return Err(MoneyError::TokenMismatch.into())
     }
 
-    // Create a state update. We only need the new coin.
-    let update = MoneyTokenMintUpdateV1 { coin: params.output.coin };
+    // Grab the token's mint state. The first mint records the authority
+    // and fixes the maximum supply, later mints have to stay within it.
+    let mut mint_info = match db_get(token_mint_info_db, &serialize(&token_id))? {
+        Some(v) => deserialize::<TokenMintInfo>(&v)?,
+        None => TokenMintInfo {
+            authority: params.input.signature_public,
+            max_supply: params.max_supply,
+            minted: 0,
+        },
+    };
+
+    if params.max_supply.is_some() && params.max_supply != mint_info.max_supply {
+        msg!("[MintV1] Error: Maximum supply mismatch for {}", token_id);
+        return Err(MoneyError::TokenMaxSupplyMismatch.into())
+    }
+
+    let Some(minted) = mint_info.minted.checked_add(params.input.value) else {
+        msg!("[MintV1] Error: Minted supply overflow for {}", token_id);
+        return Err(MoneyError::TokenMaxSupplyExceeded.into())
+    };
+
+    if let Some(max_supply) = mint_info.max_supply {
+        if minted > max_supply {
+            msg!("[MintV1] Error: Mint exceeds maximum supply {} for {}", max_supply, token_id);
+            return Err(MoneyError::TokenMaxSupplyExceeded.into())
+        }
+    }
+
+    mint_info.minted = minted;
+
+    // Add the minted value to the token's supply commitment
+    let token_supply_db = db_lookup(cid, MONEY_CONTRACT_TOKEN_SUPPLY_TREE)?;
+    let supply = match db_get(token_supply_db, &serialize(&token_id))? {
+        Some(v) => deserialize::<TokenSupply>(&v)?,
+        None => TokenSupply::empty(),
+    };
+
+    let Some(supply) =
+        supply.add_clear(params.output.value_commit, params.input.value, params.input.value_blind)
+    else {
+        msg!("[MintV1] Error: Supply overflow for {}", token_id);
+        return Err(MoneyError::TokenMaxSupplyExceeded.into())
+    };
+
+    // Create a state update. We need the new coin, and the token's
+    // updated mint state and supply.
+    let update = MoneyTokenMintUpdateV1 {
+        coin: params.output.coin,
+        token_id,
+        mint_info: Some(mint_info),
+        supply: Some(supply),
+    };
     let mut update_data = vec![];
     update_data.write_u8(MoneyFunction::TokenMintV1 as u8)?;
     update.encode(&mut update_data)?;
@@ -149,16 +200,28 @@ pub(crate) fn money_token_mint_process_update_v1(
     let coins_db = db_lookup(cid, MONEY_CONTRACT_COINS_TREE)?;
     let coin_roots_db = db_lookup(cid, MONEY_CONTRACT_COIN_ROOTS_TREE)?;
 
+    if let Some(mint_info) = update.mint_info {
+        msg!("[MintV1] Updating mint state of {}", update.token_id);
+        let token_mint_info_db = db_lookup(cid, MONEY_CONTRACT_TOKEN_MINT_INFO_TREE)?;
+        db_set(token_mint_info_db, &serialize(&update.token_id), &serialize(&mint_info))?;
+    }
+
+    if let Some(supply) = update.supply {
+        msg!("[MintV1] Updating supply of {}", update.token_id);
+        let token_supply_db = db_lookup(cid, MONEY_CONTRACT_TOKEN_SUPPLY_TREE)?;
+        db_set(token_supply_db, &serialize(&update.token_id), &serialize(&supply))?;
+    }
+
     msg!("[MintV1] Adding new coin to the set");
     db_set(coins_db, &serialize(&update.coin), &[])?;
 
     msg!("[MintV1] Adding new coin to the Merkle tree");
     let coins = vec![MerkleNode::from(update.coin.inner())];
-    merkle_add(
+    merkle_frontier_add(
         info_db,
         coin_roots_db,
         &serialize(&MONEY_CONTRACT_LATEST_COIN_ROOT),
-        &serialize(&MONEY_CONTRACT_COIN_MERKLE_TREE),
+        &serialize(&MONEY_CONTRACT_COIN_MERKLE_FRONTIER),
         &coins,
     )?;
 
@@ -17,13 +17,11 @@
  */
 
 use darkfi_sdk::{
-    crypto::{
-        pasta_prelude::*, pedersen_commitment_u64, poseidon_hash, ContractId, MerkleNode,
-        PublicKey, DARK_TOKEN_ID,
-    },
-    db::{db_contains_key, db_get, db_lookup, db_set},
+    crypto::{pasta_prelude::*, ContractId, MerkleNode, PublicKey, DARK_TOKEN_ID},
+    db::{db_contains_key, db_get, db_lookup, db_set, NullifierDomain, NullifierSet},
     error::{ContractError, ContractResult},
-    merkle_add, msg,
+    hash::{pedersen_commitment_u64, poseidon_hash},
+    merkle_frontier_add, msg,
     pasta::pallas,
     ContractCall,
 };
@@ -32,7 +30,7 @@ use darkfi_serial::{deserialize, serialize, Encodable, WriteExt};
 use crate::{
     error::MoneyError,
     model::{MoneyTransferParamsV1, MoneyTransferUpdateV1},
-    MoneyFunction, MONEY_CONTRACT_COINS_TREE, MONEY_CONTRACT_COIN_MERKLE_TREE,
+    MoneyFunction, MONEY_CONTRACT_COINS_TREE, MONEY_CONTRACT_COIN_MERKLE_FRONTIER,
     MONEY_CONTRACT_COIN_ROOTS_TREE, MONEY_CONTRACT_FAUCET_PUBKEYS, MONEY_CONTRACT_INFO_TREE,
     MONEY_CONTRACT_LATEST_COIN_ROOT, MONEY_CONTRACT_NULLIFIERS_TREE,
     MONEY_CONTRACT_ZKAS_BURN_NS_V1, MONEY_CONTRACT_ZKAS_MINT_NS_V1,
@@ -125,7 +123,7 @@ pub(crate) fn money_transfer_process_instruction_v1(
     // validate this state transition.
     let info_db = db_lookup(cid, MONEY_CONTRACT_INFO_TREE)?;
     let coins_db = db_lookup(cid, MONEY_CONTRACT_COINS_TREE)?;
-    let nullifiers_db = db_lookup(cid, MONEY_CONTRACT_NULLIFIERS_TREE)?;
+    let nullifiers = NullifierSet::lookup(cid, MONEY_CONTRACT_NULLIFIERS_TREE)?;
     let coin_roots_db = db_lookup(cid, MONEY_CONTRACT_COIN_ROOTS_TREE)?;
 
     // Grab faucet pubkeys. They're allowed to create clear inputs.
@@ -162,7 +160,7 @@ pub(crate) fn money_transfer_process_instruction_v1(
         }
 
         // Add this input to the value commitment accumulator
-        valcom_total += pedersen_commitment_u64(input.value, input.value_blind);
+        valcom_total += pedersen_commitment_u64(input.value, input.value_blind)?;
     }
 
     // For anonymous inputs, we must also gather all the new nullifiers
@@ -178,9 +176,7 @@ pub(crate) fn money_transfer_process_instruction_v1(
         }
 
         // The nullifiers should not already exist. It is the double-spend protection.
-        if new_nullifiers.contains(&input.nullifier) ||
-            db_contains_key(nullifiers_db, &serialize(&input.nullifier))?
-        {
+        if new_nullifiers.contains(&input.nullifier) || nullifiers.contains(&input.nullifier)? {
             msg!("[TransferV1] Error: Duplicate nullifier found (input {})", i);
             return Err(MoneyError::DuplicateNullifier.into())
         }
@@ -234,11 +230,12 @@ pub(crate) fn money_transfer_process_instruction_v1(
     let tokcom = params.outputs[0].token_commit;
     let mut failed_tokcom = params.inputs.iter().any(|x| x.token_commit != tokcom);
     failed_tokcom = failed_tokcom || params.outputs.iter().any(|x| x.token_commit != tokcom);
-    failed_tokcom = failed_tokcom ||
-        params
-            .clear_inputs
-            .iter()
-            .any(|x| poseidon_hash([x.token_id.inner(), x.token_blind]) != tokcom);
+    for input in &params.clear_inputs {
+        if failed_tokcom {
+            break
+        }
+        failed_tokcom = poseidon_hash(&[input.token_id.inner(), input.token_blind])? != tokcom;
+    }
 
     if failed_tokcom {
         msg!("[TransferV1] Error: Token commitments do not match");
@@ -258,16 +255,27 @@ pub(crate) fn money_transfer_process_instruction_v1(
 pub(crate) fn money_transfer_process_update_v1(
     cid: ContractId,
     update: MoneyTransferUpdateV1,
+) -> ContractResult {
+    money_transfer_apply_update_v1(cid, update, MoneyFunction::TransferV1)
+}
+
+/// Apply a `MoneyTransferUpdateV1`, recording its nullifiers as revealed
+/// by the given `function`. Shared by the calls producing this update.
+pub(crate) fn money_transfer_apply_update_v1(
+    cid: ContractId,
+    update: MoneyTransferUpdateV1,
+    function: MoneyFunction,
 ) -> ContractResult {
     // Grab all necessary db handles for where we want to write
     let info_db = db_lookup(cid, MONEY_CONTRACT_INFO_TREE)?;
     let coins_db = db_lookup(cid, MONEY_CONTRACT_COINS_TREE)?;
-    let nullifiers_db = db_lookup(cid, MONEY_CONTRACT_NULLIFIERS_TREE)?;
+    let nullifiers = NullifierSet::lookup(cid, MONEY_CONTRACT_NULLIFIERS_TREE)?;
     let coin_roots_db = db_lookup(cid, MONEY_CONTRACT_COIN_ROOTS_TREE)?;
+    let domain = NullifierDomain::new(cid, function as u8);
 
     msg!("[TransferV1] Adding new nullifiers to the set");
     for nullifier in update.nullifiers {
-        db_set(nullifiers_db, &serialize(&nullifier), &[])?;
+        nullifiers.insert(&nullifier, &domain)?;
     }
 
     msg!("[TransferV1] Adding new coins to the set");
@@ -277,11 +285,11 @@ pub(crate) fn money_transfer_process_update_v1(
 
     msg!("[TransferV1] Adding new coins to the Merkle tree");
     let coins: Vec<_> = update.coins.iter().map(|x| MerkleNode::from(x.inner())).collect();
-    merkle_add(
+    merkle_frontier_add(
         info_db,
         coin_roots_db,
         &serialize(&MONEY_CONTRACT_LATEST_COIN_ROOT),
-        &serialize(&MONEY_CONTRACT_COIN_MERKLE_TREE),
+        &serialize(&MONEY_CONTRACT_COIN_MERKLE_FRONTIER),
         &coins,
     )?;
 
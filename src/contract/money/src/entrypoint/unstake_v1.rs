@@ -18,12 +18,12 @@
 
 use darkfi_sdk::{
     crypto::{
-        pasta_prelude::*, poseidon_hash, ContractId, MerkleNode, PublicKey, CONSENSUS_CONTRACT_ID,
-        DARK_TOKEN_ID,
+        pasta_prelude::*, ContractId, MerkleNode, PublicKey, CONSENSUS_CONTRACT_ID, DARK_TOKEN_ID,
     },
-    db::{db_contains_key, db_lookup, db_set},
+    db::{db_contains_key, db_lookup, db_set, NullifierDomain, NullifierSet},
     error::{ContractError, ContractResult},
-    merkle_add, msg,
+    hash::poseidon_hash,
+    merkle_frontier_add, msg,
     pasta::pallas,
     ContractCall,
 };
@@ -33,7 +33,7 @@ use crate::{
     error::MoneyError,
     model::{ConsensusUnstakeParamsV1, MoneyUnstakeParamsV1, MoneyUnstakeUpdateV1},
     MoneyFunction, CONSENSUS_CONTRACT_NULLIFIERS_TREE, CONSENSUS_CONTRACT_UNSTAKED_COIN_ROOTS_TREE,
-    MONEY_CONTRACT_COINS_TREE, MONEY_CONTRACT_COIN_MERKLE_TREE, MONEY_CONTRACT_COIN_ROOTS_TREE,
+    MONEY_CONTRACT_COINS_TREE, MONEY_CONTRACT_COIN_MERKLE_FRONTIER, MONEY_CONTRACT_COIN_ROOTS_TREE,
     MONEY_CONTRACT_INFO_TREE, MONEY_CONTRACT_LATEST_COIN_ROOT, MONEY_CONTRACT_ZKAS_MINT_NS_V1,
 };
 
@@ -86,8 +86,8 @@ pub(crate) fn money_unstake_process_instruction_v1(
     // Access the necessary databases where there is information to
     // validate this state transition.
     let money_coins_db = db_lookup(cid, MONEY_CONTRACT_COINS_TREE)?;
-    let consensus_nullifiers_db =
-        db_lookup(*CONSENSUS_CONTRACT_ID, CONSENSUS_CONTRACT_NULLIFIERS_TREE)?;
+    let consensus_nullifiers =
+        NullifierSet::lookup(*CONSENSUS_CONTRACT_ID, CONSENSUS_CONTRACT_NULLIFIERS_TREE)?;
     let consensus_unstaked_coin_roots_db =
         db_lookup(*CONSENSUS_CONTRACT_ID, CONSENSUS_CONTRACT_UNSTAKED_COIN_ROOTS_TREE)?;
 
@@ -126,7 +126,7 @@ pub(crate) fn money_unstake_process_instruction_v1(
     // Only native token can be minted here.
     // Since consensus coins don't have token commitments, we use zero as
     // the token blind for the token commitment of the newly minted token
-    if output.token_commit != poseidon_hash([DARK_TOKEN_ID.inner(), pallas::Base::ZERO]) {
+    if output.token_commit != poseidon_hash(&[DARK_TOKEN_ID.inner(), pallas::Base::ZERO])? {
         msg!("[MoneyUnstakeV1] Error: Input used non-native token");
         return Err(MoneyError::StakeInputNonNativeToken.into())
     }
@@ -144,8 +144,10 @@ pub(crate) fn money_unstake_process_instruction_v1(
         return Err(MoneyError::TransferMerkleRootNotFound.into())
     }
 
-    // The nullifiers should already exist in the Consensus nullifier set
-    if !db_contains_key(consensus_nullifiers_db, &serialize(&input.nullifier))? {
+    // The nullifier should already exist in the Consensus nullifier set,
+    // revealed by Consensus::UnstakeV1 (0x04) rather than any other call.
+    let domain = NullifierDomain::new(*CONSENSUS_CONTRACT_ID, 0x04);
+    if !consensus_nullifiers.was_revealed_by(&input.nullifier, &domain)? {
         msg!("[MoneyUnstakeV1] Error: Nullifier not found in Consensus nullifier set");
         return Err(MoneyError::MissingNullifier.into())
     }
@@ -180,11 +182,11 @@ pub(crate) fn money_unstake_process_update_v1(
 
     msg!("[MoneyUnstakeV1] Adding new coin to the Merkle tree");
     let coins: Vec<_> = vec![MerkleNode::from(update.coin.inner())];
-    merkle_add(
+    merkle_frontier_add(
         info_db,
         coin_roots_db,
         &serialize(&MONEY_CONTRACT_LATEST_COIN_ROOT),
-        &serialize(&MONEY_CONTRACT_COIN_MERKLE_TREE),
+        &serialize(&MONEY_CONTRACT_COIN_MERKLE_FRONTIER),
         &coins,
     )?;
 
@@ -117,6 +117,21 @@ pub enum MoneyError {
 
     #[error("Missing nullifier in set")]
     MissingNullifier,
+
+    #[error("Token metadata is already registered")]
+    TokenMetadataExists,
+
+    #[error("Token metadata is invalid")]
+    TokenMetadataInvalid,
+
+    #[error("Token mint exceeds the maximum supply")]
+    TokenMaxSupplyExceeded,
+
+    #[error("Token maximum supply mismatch")]
+    TokenMaxSupplyMismatch,
+
+    #[error("Fee paid in non-native token")]
+    FeeNonNativeToken,
 }
 
 impl From<MoneyError> for ContractError {
@@ -154,6 +169,11 @@ impl From<MoneyError> for ContractError {
             MoneyError::PreviousCallInputMismatch => Self::Custom(30),
             MoneyError::GenesisCallNonGenesisSlot => Self::Custom(31),
             MoneyError::MissingNullifier => Self::Custom(32),
+            MoneyError::TokenMetadataExists => Self::Custom(33),
+            MoneyError::TokenMetadataInvalid => Self::Custom(34),
+            MoneyError::TokenMaxSupplyExceeded => Self::Custom(35),
+            MoneyError::TokenMaxSupplyMismatch => Self::Custom(36),
+            MoneyError::FeeNonNativeToken => Self::Custom(37),
         }
     }
 }
@@ -24,7 +24,7 @@ use darkfi_sdk::error::ContractError;
 /// Functions available in the contract
 #[repr(u8)]
 pub enum MoneyFunction {
-    //Fee = 0x00,
+    FeeV1 = 0x00,
     GenesisMintV1 = 0x01,
     TransferV1 = 0x02,
     OtcSwapV1 = 0x03,
@@ -32,6 +32,7 @@ pub enum MoneyFunction {
     TokenFreezeV1 = 0x05,
     StakeV1 = 0x06,
     UnstakeV1 = 0x07,
+    TokenMetadataV1 = 0x08,
 }
 
 impl TryFrom<u8> for MoneyFunction {
@@ -39,7 +40,7 @@ impl TryFrom<u8> for MoneyFunction {
 
     fn try_from(b: u8) -> core::result::Result<Self, Self::Error> {
         match b {
-            //0x00 => Ok(Self::Fee),
+            0x00 => Ok(Self::FeeV1),
             0x01 => Ok(Self::GenesisMintV1),
             0x02 => Ok(Self::TransferV1),
             0x03 => Ok(Self::OtcSwapV1),
@@ -47,6 +48,7 @@ impl TryFrom<u8> for MoneyFunction {
             0x05 => Ok(Self::TokenFreezeV1),
             0x06 => Ok(Self::StakeV1),
             0x07 => Ok(Self::UnstakeV1),
+            0x08 => Ok(Self::TokenMetadataV1),
             _ => Err(ContractError::InvalidFunction),
         }
     }
@@ -72,21 +74,33 @@ pub const MONEY_CONTRACT_COINS_TREE: &str = "coins";
 pub const MONEY_CONTRACT_COIN_ROOTS_TREE: &str = "coin_roots";
 pub const MONEY_CONTRACT_NULLIFIERS_TREE: &str = "nullifiers";
 pub const MONEY_CONTRACT_TOKEN_FREEZE_TREE: &str = "token_freezes";
+pub const MONEY_CONTRACT_TOKEN_METADATA_TREE: &str = "token_metadata";
+pub const MONEY_CONTRACT_TOKEN_ISSUERS_TREE: &str = "token_issuers";
+pub const MONEY_CONTRACT_TOKEN_MINT_INFO_TREE: &str = "token_mint_info";
+pub const MONEY_CONTRACT_TOKEN_SUPPLY_TREE: &str = "token_supply";
 
 // These are keys inside the info tree
 pub const MONEY_CONTRACT_DB_VERSION: &str = "db_version";
-pub const MONEY_CONTRACT_COIN_MERKLE_TREE: &str = "coin_tree";
+pub const MONEY_CONTRACT_COIN_MERKLE_FRONTIER: &str = "coin_frontier";
 pub const MONEY_CONTRACT_LATEST_COIN_ROOT: &str = "last_root";
 pub const MONEY_CONTRACT_FAUCET_PUBKEYS: &str = "faucet_pubkeys";
 
 /// zkas mint circuit namespace
 pub const MONEY_CONTRACT_ZKAS_MINT_NS_V1: &str = "Mint_V1";
+/// Public inputs of the zkas mint circuit
+pub const MONEY_CONTRACT_ZKAS_MINT_PI_V1: u32 = 4;
 /// zkas burn circuit namespace
 pub const MONEY_CONTRACT_ZKAS_BURN_NS_V1: &str = "Burn_V1";
+/// Public inputs of the zkas burn circuit
+pub const MONEY_CONTRACT_ZKAS_BURN_PI_V1: u32 = 9;
 /// zkas token mint circuit namespace
 pub const MONEY_CONTRACT_ZKAS_TOKEN_MINT_NS_V1: &str = "TokenMint_V1";
+/// Public inputs of the zkas token mint circuit
+pub const MONEY_CONTRACT_ZKAS_TOKEN_MINT_PI_V1: u32 = 7;
 /// zkas token freeze circuit namespace
 pub const MONEY_CONTRACT_ZKAS_TOKEN_FRZ_NS_V1: &str = "TokenFreeze_V1";
+/// Public inputs of the zkas token freeze circuit
+pub const MONEY_CONTRACT_ZKAS_TOKEN_FRZ_PI_V1: u32 = 3;
 
 // These are the different sled trees that will be created
 // for the consensus contract.
@@ -98,6 +112,7 @@ pub const CONSENSUS_CONTRACT_STAKED_COINS_TREE: &str = "consensus_staked_coins";
 pub const CONSENSUS_CONTRACT_UNSTAKED_COINS_TREE: &str = "consensus_unstaked_coins";
 pub const CONSENSUS_CONTRACT_STAKED_COIN_ROOTS_TREE: &str = "consensus_staked_coin_roots";
 pub const CONSENSUS_CONTRACT_UNSTAKED_COIN_ROOTS_TREE: &str = "consensus_unstaked_coin_roots";
+pub const CONSENSUS_CONTRACT_SLASHED_COINS_TREE: &str = "consensus_slashed_coins";
 
 // These are keys inside the consensus info tree
 pub const CONSENSUS_CONTRACT_DB_VERSION: &str = "db_version";
@@ -105,10 +120,17 @@ pub const CONSENSUS_CONTRACT_STAKED_COIN_MERKLE_TREE: &str = "consensus_staked_c
 pub const CONSENSUS_CONTRACT_STAKED_COIN_LATEST_COIN_ROOT: &str = "consensus_staked_last_root";
 pub const CONSENSUS_CONTRACT_UNSTAKED_COIN_MERKLE_TREE: &str = "consensus_unstaked_coin_tree";
 pub const CONSENSUS_CONTRACT_UNSTAKED_COIN_LATEST_COIN_ROOT: &str = "consensus_unstaked_last_root";
+pub const CONSENSUS_CONTRACT_UNSTAKE_DELAY: &str = "consensus_unstake_delay";
 
 /// zkas consensus mint circuit namespace
 pub const CONSENSUS_CONTRACT_ZKAS_MINT_NS_V1: &str = "ConsensusMint_V1";
+/// Public inputs of the zkas consensus mint circuit
+pub const CONSENSUS_CONTRACT_ZKAS_MINT_PI_V1: u32 = 4;
 /// zkas consensus burn circuit namespace
 pub const CONSENSUS_CONTRACT_ZKAS_BURN_NS_V1: &str = "ConsensusBurn_V1";
+/// Public inputs of the zkas consensus burn circuit
+pub const CONSENSUS_CONTRACT_ZKAS_BURN_PI_V1: u32 = 7;
 /// zkas proposal circuit namespace
 pub const CONSENSUS_CONTRACT_ZKAS_PROPOSAL_NS_V1: &str = "ConsensusProposal_V1";
+/// Public inputs of the zkas proposal circuit
+pub const CONSENSUS_CONTRACT_ZKAS_PROPOSAL_PI_V1: u32 = 18;
@@ -18,19 +18,22 @@
 
 use darkfi_sdk::{
     crypto::{
-        note::AeadEncryptedNote, pasta_prelude::PrimeField, MerkleNode, Nullifier, PublicKey,
-        TokenId,
+        note::AeadEncryptedNote,
+        pasta_prelude::{Field, Group, PrimeField},
+        MerkleNode, Nullifier, PublicKey, TokenId,
     },
     error::ContractError,
     pasta::pallas,
 };
-use darkfi_serial::{SerialDecodable, SerialEncodable};
+use darkfi_serial::{SerialDecodable, SerialEncodable, SerialSchema};
 
+#[cfg(feature = "client")]
+use darkfi_sdk::crypto::pedersen_commitment_u64;
 #[cfg(feature = "client")]
 use darkfi_serial::async_trait;
 
 /// A `Coin` represented in the Money state
-#[derive(Debug, Clone, Copy, Eq, PartialEq, SerialEncodable, SerialDecodable)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, SerialEncodable, SerialDecodable, SerialSchema)]
 pub struct Coin(pallas::Base);
 
 impl Coin {
@@ -62,7 +65,7 @@ darkfi_sdk::fp_to_bs58!(Coin);
 darkfi_sdk::ty_from_fp!(Coin);
 
 /// A contract call's clear input
-#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable, SerialSchema)]
 pub struct ClearInput {
     /// Input's value (amount)
     pub value: u64,
@@ -77,7 +80,7 @@ pub struct ClearInput {
 }
 
 /// A contract call's anonymous input
-#[derive(Clone, Debug, PartialEq, SerialEncodable, SerialDecodable)]
+#[derive(Clone, Debug, PartialEq, SerialEncodable, SerialDecodable, SerialSchema)]
 pub struct Input {
     /// Pedersen commitment for the input's value
     pub value_commit: pallas::Point,
@@ -100,7 +103,7 @@ pub struct Input {
 }
 
 /// Anonymous input for consensus contract calls
-#[derive(Clone, Debug, PartialEq, SerialEncodable, SerialDecodable)]
+#[derive(Clone, Debug, PartialEq, SerialEncodable, SerialDecodable, SerialSchema)]
 pub struct ConsensusInput {
     /// Epoch the coin was minted
     pub epoch: u64,
@@ -115,7 +118,7 @@ pub struct ConsensusInput {
 }
 
 /// A contract call's anonymous output
-#[derive(Clone, Debug, PartialEq, SerialEncodable, SerialDecodable)]
+#[derive(Clone, Debug, PartialEq, SerialEncodable, SerialDecodable, SerialSchema)]
 pub struct Output {
     /// Pedersen commitment for the output's value
     pub value_commit: pallas::Point,
@@ -128,7 +131,7 @@ pub struct Output {
 }
 
 /// A consensus contract call's anonymous output
-#[derive(Clone, Debug, PartialEq, SerialEncodable, SerialDecodable)]
+#[derive(Clone, Debug, PartialEq, SerialEncodable, SerialDecodable, SerialSchema)]
 pub struct ConsensusOutput {
     /// Pedersen commitment for the output's value
     pub value_commit: pallas::Point,
@@ -139,7 +142,7 @@ pub struct ConsensusOutput {
 }
 
 /// Parameters for `Money::Transfer` and `Money::OtcSwap`
-#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable, SerialSchema)]
 pub struct MoneyTransferParamsV1 {
     /// Clear inputs
     pub clear_inputs: Vec<ClearInput>,
@@ -150,7 +153,7 @@ pub struct MoneyTransferParamsV1 {
 }
 
 /// State update for `Money::Transfer` and `Money::OtcSwap`
-#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable, SerialSchema)]
 pub struct MoneyTransferUpdateV1 {
     /// Revealed nullifiers
     pub nullifiers: Vec<Nullifier>,
@@ -158,24 +161,128 @@ pub struct MoneyTransferUpdateV1 {
     pub coins: Vec<Coin>,
 }
 
+/// Parameters for `Money::Fee`
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable, SerialSchema)]
+pub struct MoneyFeeParamsV1 {
+    /// Anonymous input spending a native token coin
+    pub input: Input,
+    /// Anonymous output returning the change to the payer
+    pub output: Output,
+    /// Paid fee
+    pub fee_value: u64,
+    /// Blinding factor for `fee_value`
+    pub fee_value_blind: pallas::Scalar,
+    /// Blinding factor for the native token ID, revealed so the
+    /// contract can check that the fee is paid in it
+    pub token_blind: pallas::Base,
+}
+
 /// Parameters for `Money::TokenMint`
-#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable, SerialSchema)]
 pub struct MoneyTokenMintParamsV1 {
     /// Clear input
     pub input: ClearInput,
     /// Anonymous output
     pub output: Output,
+    /// Maximum supply of the token, fixed by its first mint.
+    /// Ignored by `Money::GenesisMint`.
+    pub max_supply: Option<u64>,
+}
+
+/// Mint state of a token, as recorded on-chain by `Money::TokenMint`
+#[derive(Clone, Debug, Eq, PartialEq, SerialEncodable, SerialDecodable, SerialSchema)]
+pub struct TokenMintInfo {
+    /// Mint authority public key
+    pub authority: PublicKey,
+    /// Maximum supply of the token, if capped
+    pub max_supply: Option<u64>,
+    /// Total amount of tokens minted so far
+    pub minted: u64,
+}
+
+/// Opening of a [`TokenSupply`] value commitment
+#[derive(Clone, Debug, Eq, PartialEq, SerialEncodable, SerialDecodable, SerialSchema)]
+pub struct SupplyOpening {
+    /// Total value committed to
+    pub value: u64,
+    /// Sum of the value blinds
+    pub blind: pallas::Scalar,
+}
+
+/// Supply of a token, as recorded on-chain by `Money::TokenMint`.
+///
+/// Transfers and swaps balance their value commitments within the call,
+/// so a token's supply only grows when it gets minted. The value
+/// commitments of its minted coins are summed up homomorphically. As long
+/// as every mint revealed its value and blind, the sum of those is kept
+/// as the opening of the commitment, so anyone can check that no value
+/// got created besides the mints.
+///
+/// The native token isn't tracked, as staking rewards get minted into
+/// it by the `Consensus` contract.
+#[derive(Clone, Debug, PartialEq, SerialEncodable, SerialDecodable, SerialSchema)]
+pub struct TokenSupply {
+    /// Sum of the value commitments of the minted coins
+    pub value_commit: pallas::Point,
+    /// Opening of `value_commit`, unset once hidden value got added
+    pub opening: Option<SupplyOpening>,
+}
+
+impl TokenSupply {
+    /// Supply of a token that was never minted
+    pub fn empty() -> Self {
+        Self {
+            value_commit: pallas::Point::identity(),
+            opening: Some(SupplyOpening { value: 0, blind: pallas::Scalar::ZERO }),
+        }
+    }
+
+    /// Add a mint of `value` with the given blind and commitment.
+    /// Returns `None` if the opened value overflows.
+    pub fn add_clear(
+        &self,
+        value_commit: pallas::Point,
+        value: u64,
+        blind: pallas::Scalar,
+    ) -> Option<Self> {
+        let opening = match &self.opening {
+            Some(opening) => Some(SupplyOpening {
+                value: opening.value.checked_add(value)?,
+                blind: opening.blind + blind,
+            }),
+            None => None,
+        };
+
+        Some(Self { value_commit: self.value_commit + value_commit, opening })
+    }
+
+    /// Check that the opening, if any, opens the value commitment.
+    #[cfg(feature = "client")]
+    pub fn verify_opening(&self) -> bool {
+        match &self.opening {
+            Some(opening) => {
+                pedersen_commitment_u64(opening.value, opening.blind) == self.value_commit
+            }
+            None => false,
+        }
+    }
 }
 
 /// State update for `Money::TokenMint`
-#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable, SerialSchema)]
 pub struct MoneyTokenMintUpdateV1 {
     /// The newly minted coin
     pub coin: Coin,
+    /// Token the coin was minted for
+    pub token_id: TokenId,
+    /// Updated mint state of the token, unset for `Money::GenesisMint`
+    pub mint_info: Option<TokenMintInfo>,
+    /// Updated supply of the token, unset for `Money::GenesisMint`
+    pub supply: Option<TokenSupply>,
 }
 
 /// Parameters for `Money::TokenFreeze`
-#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable, SerialSchema)]
 pub struct MoneyTokenFreezeParamsV1 {
     /// Mint authority public key
     ///
@@ -184,14 +291,72 @@ pub struct MoneyTokenFreezeParamsV1 {
 }
 
 /// State update for `Money::TokenFreeze`
-#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable, SerialSchema)]
 pub struct MoneyTokenFreezeUpdateV1 {
     /// Mint authority public key
     pub signature_public: PublicKey,
 }
 
+/// Maximum length of a token's name, in bytes
+pub const TOKEN_NAME_MAX_LEN: usize = 64;
+/// Maximum length of a token's symbol, in bytes
+pub const TOKEN_SYMBOL_MAX_LEN: usize = 16;
+/// Maximum length of a token's metadata URI, in bytes
+pub const TOKEN_URI_MAX_LEN: usize = 256;
+/// Maximum number of decimals a token can declare
+pub const TOKEN_MAX_DECIMALS: u8 = 18;
+
+/// Human-readable token information, as registered on-chain by its issuer
+#[derive(Clone, Debug, Eq, PartialEq, SerialEncodable, SerialDecodable, SerialSchema)]
+pub struct TokenMetadata {
+    /// Public key of the token issuer
+    pub issuer: PublicKey,
+    /// Nonce the token's mint authority was derived with
+    pub symbol_nonce: u64,
+    /// Token name
+    pub name: String,
+    /// Token symbol
+    pub symbol: String,
+    /// Number of decimals used to display token amounts
+    pub decimals: u8,
+    /// URI pointing to further token information
+    pub uri: String,
+}
+
+impl TokenMetadata {
+    /// Check the metadata fields are within their limits
+    pub fn is_valid(&self) -> bool {
+        !self.name.is_empty() &&
+            self.name.len() <= TOKEN_NAME_MAX_LEN &&
+            !self.symbol.is_empty() &&
+            self.symbol.len() <= TOKEN_SYMBOL_MAX_LEN &&
+            self.uri.len() <= TOKEN_URI_MAX_LEN &&
+            self.decimals <= TOKEN_MAX_DECIMALS
+    }
+}
+
+/// Parameters for `Money::TokenMetadata`
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable, SerialSchema)]
+pub struct MoneyTokenMetadataParamsV1 {
+    /// Mint authority public key
+    ///
+    /// We use this to derive the token ID and verify the signature.
+    pub mint_public: PublicKey,
+    /// Token metadata to register, also signed by its issuer
+    pub metadata: TokenMetadata,
+}
+
+/// State update for `Money::TokenMetadata`
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable, SerialSchema)]
+pub struct MoneyTokenMetadataUpdateV1 {
+    /// Token the metadata is registered for
+    pub token_id: TokenId,
+    /// Registered token metadata
+    pub metadata: TokenMetadata,
+}
+
 /// Parameters for `Money::Stake`
-#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable, SerialSchema)]
 // ANCHOR: MoneyStakeParams
 pub struct MoneyStakeParamsV1 {
     /// Blinding factor for `token_id`
@@ -202,7 +367,7 @@ pub struct MoneyStakeParamsV1 {
 // ANCHOR_END: MoneyStakeParams
 
 /// State update for `Money::Stake`
-#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable, SerialSchema)]
 // ANCHOR: MoneyStakeUpdate
 pub struct MoneyStakeUpdateV1 {
     /// Revealed nullifier
@@ -211,7 +376,7 @@ pub struct MoneyStakeUpdateV1 {
 // ANCHOR_END: MoneyStakeUpdate
 
 /// Parameters for `Money::Unstake`
-#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable, SerialSchema)]
 // ANCHOR: MoneyUnstakeParams
 pub struct MoneyUnstakeParamsV1 {
     /// Burnt token revealed info
@@ -222,7 +387,7 @@ pub struct MoneyUnstakeParamsV1 {
 // ANCHOR_END: MoneyUnstakeParams
 
 /// State update for `Money::Unstake`
-#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable, SerialSchema)]
 // ANCHOR: MoneyUnstakeUpdate
 pub struct MoneyUnstakeUpdateV1 {
     /// The newly minted coin
@@ -231,7 +396,7 @@ pub struct MoneyUnstakeUpdateV1 {
 // ANCHOR_END: MoneyUnstakeUpdate
 
 /// Parameters for `Consensus::Stake`
-#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable, SerialSchema)]
 // ANCHOR: ConsensusStakeParams
 pub struct ConsensusStakeParamsV1 {
     /// Burnt token revealed info
@@ -242,7 +407,7 @@ pub struct ConsensusStakeParamsV1 {
 // ANCHOR_END: ConsensusStakeParams
 
 /// State update for `Consensus::Stake`
-#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable, SerialSchema)]
 // ANCHOR: ConsensusStakeUpdate
 pub struct ConsensusStakeUpdateV1 {
     /// The newly minted coin
@@ -251,7 +416,7 @@ pub struct ConsensusStakeUpdateV1 {
 // ANCHOR_END: ConsensusStakeUpdate
 
 /// Parameters for `Consensus::UnstakeRequest`
-#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable, SerialSchema)]
 // ANCHOR: ConsensusUnstakeReqParams
 pub struct ConsensusUnstakeReqParamsV1 {
     pub input: ConsensusInput,
@@ -260,7 +425,7 @@ pub struct ConsensusUnstakeReqParamsV1 {
 // ANCHOR_END: ConsensusUnstakeReqParams
 
 /// Parameters for `Consensus::Unstake`
-#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable, SerialSchema)]
 // ANCHOR: ConsensusUnstakeParams
 pub struct ConsensusUnstakeParamsV1 {
     /// Anonymous input
@@ -269,7 +434,7 @@ pub struct ConsensusUnstakeParamsV1 {
 // ANCHOR_END: ConsensusUnstakeParams
 
 /// State update for `Consensus::Unstake`
-#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable, SerialSchema)]
 // ANCHOR: ConsensusUnstakeUpdate
 pub struct ConsensusUnstakeUpdateV1 {
     /// Revealed nullifier
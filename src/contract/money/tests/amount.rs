@@ -0,0 +1,45 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Test for parsing, formatting and adding up token amounts.
+
+use darkfi_money_contract::{
+    client::amount::{checked_change, checked_sum, format_amount, parse_amount, token_decimals},
+    model::TOKEN_MAX_DECIMALS,
+};
+
+#[test]
+fn amounts() {
+    assert_eq!(token_decimals(None), 8);
+
+    assert_eq!(parse_amount("12.5", 2).unwrap(), 1250);
+    assert_eq!(parse_amount("12", 0).unwrap(), 12);
+    assert!(parse_amount("12.345", 2).is_err());
+    assert!(parse_amount("", 8).is_err());
+    assert!(parse_amount(".5", 8).is_err());
+    assert!(parse_amount("1", TOKEN_MAX_DECIMALS + 1).is_err());
+    assert!(parse_amount("18446744073709551616", 0).is_err());
+
+    assert_eq!(format_amount(1250, 2), "12.5");
+    assert_eq!(format_amount(parse_amount("0.001", 8).unwrap(), 8), "0.001");
+
+    assert_eq!(checked_sum([1, 2, 3]).unwrap(), 6);
+    assert!(checked_sum([u64::MAX, 1]).is_err());
+    assert_eq!(checked_change(5, 3).unwrap(), 2);
+    assert!(checked_change(3, 5).is_err());
+}
@@ -0,0 +1,111 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Test for wallet-side coin discovery.
+//!
+//! Alice mints herself some tokens and pays Bob with them. Scanning both
+//! transactions the way a wallet walks blocks, we confirm each of them
+//! discovers their coins at the right Merkle positions, that Alice's spent
+//! coin is caught by its nullifier, and that the scanned Merkle tree stays
+//! in sync with the one of a node that saw every coin.
+
+use darkfi::Result;
+use darkfi_contract_test_harness::{init_logger, Holder, TestHarness};
+use darkfi_money_contract::client::scan::{discover_owncoins, MoneyTxEffects};
+use darkfi_sdk::{
+    crypto::{pasta_prelude::Field, MerkleNode, MerkleTree},
+    pasta::pallas,
+};
+use log::info;
+
+#[test]
+fn coin_scan() -> Result<()> {
+    smol::block_on(async {
+        init_logger();
+
+        // Holders this test will use
+        const HOLDERS: [Holder; 3] = [Holder::Faucet, Holder::Alice, Holder::Bob];
+
+        // Slot to verify against
+        let current_slot = 0;
+
+        // Initialize harness
+        let mut th = TestHarness::new(&["money".to_string()]).await?;
+
+        info!(target: "money", "[Alice] ================================");
+        info!(target: "money", "[Alice] Building token mint tx for Alice");
+        info!(target: "money", "[Alice] ================================");
+        let (mint_tx, mint_params) =
+            th.token_mint(100, &Holder::Alice, &Holder::Alice, None, None)?;
+        for holder in &HOLDERS {
+            th.execute_token_mint_tx(holder, &mint_tx, &mint_params, current_slot).await?;
+        }
+        let alice_oc = th.gather_owncoin(&Holder::Alice, &mint_params.output, None)?;
+
+        info!(target: "money", "[Alice] ==========================");
+        info!(target: "money", "[Alice] Building payment tx to Bob");
+        info!(target: "money", "[Alice] ==========================");
+        let (transfer_tx, transfer_params, _) = th.transfer(
+            40,
+            &Holder::Alice,
+            &Holder::Bob,
+            &[alice_oc.clone()],
+            alice_oc.note.token_id,
+        )?;
+        for holder in &HOLDERS {
+            let write = holder == &Holder::Faucet;
+            th.execute_transfer_tx(holder, &transfer_tx, &transfer_params, current_slot, write)
+                .await?;
+        }
+
+        info!(target: "money", "[Scanner] ===========================");
+        info!(target: "money", "[Scanner] Scanning the transactions");
+        info!(target: "money", "[Scanner] ===========================");
+        let alice_secret = th.holders.get(&Holder::Alice).unwrap().keypair.secret;
+        let bob_secret = th.holders.get(&Holder::Bob).unwrap().keypair.secret;
+
+        let mut tree = MerkleTree::new(100);
+        tree.append(MerkleNode::from(pallas::Base::ZERO));
+
+        // The mint only carries Alice's coin
+        let effects = MoneyTxEffects::from_tx(&mint_tx)?;
+        assert!(effects.nullifiers.is_empty());
+        let found = discover_owncoins(&mut tree, &effects.outputs, &[alice_secret, bob_secret]);
+        assert_eq!(found, vec![alice_oc.clone()]);
+
+        // The payment spends it, and gives Bob his coin and Alice her change
+        let effects = MoneyTxEffects::from_tx(&transfer_tx)?;
+        assert_eq!(effects.nullifiers, vec![alice_oc.nullifier]);
+        let found = discover_owncoins(&mut tree, &effects.outputs, &[alice_secret, bob_secret]);
+        assert_eq!(found.len(), 2);
+        for owncoin in &found {
+            let (secret, value) =
+                if owncoin.secret == bob_secret { (bob_secret, 40) } else { (alice_secret, 60) };
+            assert_eq!(owncoin.secret, secret);
+            assert_eq!(owncoin.note.value, value);
+            assert!(tree.witness(owncoin.leaf_position, 0).is_some());
+        }
+
+        // Our tree matches the one of the node that saw every coin
+        let faucet = th.holders.get(&Holder::Faucet).unwrap();
+        assert_eq!(tree.root(0), faucet.money_merkle_tree.root(0));
+
+        // Thanks for reading
+        Ok(())
+    })
+}
@@ -0,0 +1,130 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Test for the wallet-side Merkle witness upkeep.
+//!
+//! Alice mints herself some tokens and pays Bob with them. Feeding both
+//! transactions to Alice's witness service, we confirm her coins' paths
+//! authenticate against the root of a node that saw every coin, that
+//! her spent coin stops being witnessed, and that her tree's root is only
+//! fresh once she scanned the latest transaction.
+
+use darkfi::Result;
+use darkfi_contract_test_harness::{init_logger, Holder, TestHarness};
+use darkfi_money_contract::client::{
+    transfer_v1::is_root_fresh,
+    witness::{CoinWitness, CoinWitnessService},
+};
+use darkfi_sdk::{
+    bridgetree::{Hashable, Level},
+    crypto::{pasta_prelude::Field, MerkleNode, MerkleTree},
+    pasta::pallas,
+};
+use log::info;
+
+/// Recompute the Merkle root from a coin's authentication path
+fn path_root(witness: &CoinWitness) -> MerkleNode {
+    let position = u64::from(witness.leaf_position);
+    let mut node = MerkleNode::from(witness.coin.inner());
+    for (i, sibling) in witness.path.iter().enumerate() {
+        node = if (position >> i) & 1 == 0 {
+            MerkleNode::combine(Level::from(i as u8), &node, sibling)
+        } else {
+            MerkleNode::combine(Level::from(i as u8), sibling, &node)
+        };
+    }
+    node
+}
+
+#[test]
+fn coin_witness() -> Result<()> {
+    smol::block_on(async {
+        init_logger();
+
+        // Holders this test will use
+        const HOLDERS: [Holder; 3] = [Holder::Faucet, Holder::Alice, Holder::Bob];
+
+        // Slot to verify against
+        let current_slot = 0;
+
+        // Initialize harness
+        let mut th = TestHarness::new(&["money".to_string()]).await?;
+
+        // Alice's witness service, starting from an empty wallet
+        let mut tree = MerkleTree::new(100);
+        tree.append(MerkleNode::from(pallas::Base::ZERO));
+        let mut service = CoinWitnessService::new(tree, vec![]);
+        let alice_secret = th.holders.get(&Holder::Alice).unwrap().keypair.secret;
+
+        info!(target: "money", "[Alice] ================================");
+        info!(target: "money", "[Alice] Building token mint tx for Alice");
+        info!(target: "money", "[Alice] ================================");
+        let (mint_tx, mint_params) =
+            th.token_mint(100, &Holder::Alice, &Holder::Alice, None, None)?;
+        for holder in &HOLDERS {
+            th.execute_token_mint_tx(holder, &mint_tx, &mint_params, current_slot).await?;
+        }
+        let alice_oc = th.gather_owncoin(&Holder::Alice, &mint_params.output, None)?;
+
+        assert_eq!(service.apply_tx(&mint_tx, &[alice_secret])?, vec![alice_oc.clone()]);
+        let witness = service.get_merkle_path(&alice_oc.coin).unwrap();
+        let faucet_root = th.holders.get(&Holder::Faucet).unwrap().money_merkle_tree.root(0);
+        assert_eq!(Some(witness.root), faucet_root);
+        assert_eq!(path_root(&witness), witness.root);
+
+        // The path is the one the harness wallet would use
+        let alice_tree = &th.holders.get(&Holder::Alice).unwrap().money_merkle_tree;
+        assert_eq!(Some(witness.path), alice_tree.witness(alice_oc.leaf_position, 0));
+
+        info!(target: "money", "[Alice] ==========================");
+        info!(target: "money", "[Alice] Building payment tx to Bob");
+        info!(target: "money", "[Alice] ==========================");
+        let (transfer_tx, transfer_params, _) = th.transfer(
+            40,
+            &Holder::Alice,
+            &Holder::Bob,
+            &[alice_oc.clone()],
+            alice_oc.note.token_id,
+        )?;
+        for holder in &HOLDERS {
+            th.execute_transfer_tx(holder, &transfer_tx, &transfer_params, current_slot, true)
+                .await?;
+        }
+
+        // Until the payment is scanned, Alice's tree is behind the chain
+        let faucet_root = th.holders.get(&Holder::Faucet).unwrap().money_merkle_tree.root(0);
+        assert!(!is_root_fresh(service.tree(), &faucet_root.unwrap()));
+
+        // The spent coin is dropped, and only the change is witnessed
+        let found = service.apply_tx(&transfer_tx, &[alice_secret])?;
+        assert!(is_root_fresh(service.tree(), &faucet_root.unwrap()));
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].note.value, 60);
+        assert!(service.get_merkle_path(&alice_oc.coin).is_none());
+        assert_eq!(service.coins(), &found[..]);
+
+        let witnesses = service.witnesses();
+        assert_eq!(witnesses.len(), 1);
+        assert_eq!(Some(witnesses[0].root), faucet_root);
+        assert_eq!(path_root(&witnesses[0]), witnesses[0].root);
+        assert_eq!(witnesses[0].coin, found[0].coin);
+
+        // Thanks for reading
+        Ok(())
+    })
+}
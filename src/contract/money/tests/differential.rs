@@ -0,0 +1,500 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Differential test of the Money contract's `Transfer` state transition.
+//!
+//! A native Rust reference implementation of the coin, nullifier and value
+//! commitment rules is fed the same random sequence of calls as the wasm
+//! contract running inside the `Runtime`. After every call, both must have
+//! agreed on accepting or rejecting it, produced the same state update, and
+//! ended up in the same state. This catches divergences the wasm build or
+//! the serialization between host and guest could introduce.
+
+use darkfi::{
+    blockchain::{Blockchain, BlockchainOverlay, BlockchainOverlayPtr},
+    runtime::vm_runtime::Runtime,
+    util::time::{TimeKeeper, Timestamp},
+    validator::utils::{deploy_native_contracts, native_contract_bincodes},
+    Error, Result,
+};
+use darkfi_money_contract::{
+    error::MoneyError,
+    model::{ClearInput, Coin, Input, MoneyTransferParamsV1, MoneyTransferUpdateV1, Output},
+    MoneyFunction, MONEY_CONTRACT_COINS_TREE, MONEY_CONTRACT_COIN_MERKLE_FRONTIER,
+    MONEY_CONTRACT_COIN_ROOTS_TREE, MONEY_CONTRACT_INFO_TREE, MONEY_CONTRACT_LATEST_COIN_ROOT,
+    MONEY_CONTRACT_NULLIFIERS_TREE,
+};
+use darkfi_sdk::{
+    crypto::{
+        note::AeadEncryptedNote, pasta_prelude::*, pedersen_commitment_u64, poseidon_hash, Keypair,
+        MerkleFrontier, MerkleNode, MerkleTree, Nullifier, PublicKey, DARK_TOKEN_ID,
+        MONEY_CONTRACT_ID,
+    },
+    db::NullifierDomain,
+    error::ContractError,
+    pasta::pallas,
+    ContractCall,
+};
+use darkfi_serial::{deserialize, serialize, Encodable, WriteExt};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// Number of random calls fed to both implementations
+const STEPS: usize = 200;
+
+/// Native reference implementation of the Money contract's coin,
+/// nullifier and value commitment state transitions.
+struct MoneyReference {
+    /// Public keys allowed to create clear inputs
+    faucet_pubkeys: Vec<PublicKey>,
+    /// All coins ever seen
+    coins: Vec<Coin>,
+    /// Revealed nullifiers, along with the call that revealed them
+    nullifiers: Vec<(Nullifier, NullifierDomain)>,
+    /// Merkle tree of all coins ever seen
+    tree: MerkleTree,
+    /// Merkle roots of all the states the tree went through
+    roots: Vec<MerkleNode>,
+}
+
+impl MoneyReference {
+    fn new(faucet_pubkeys: Vec<PublicKey>) -> Self {
+        // The contract initializes its tree with a "fake" coin
+        let mut tree = MerkleTree::new(100);
+        tree.append(MerkleNode::from(pallas::Base::ZERO));
+
+        Self { faucet_pubkeys, coins: vec![], nullifiers: vec![], tree, roots: vec![] }
+    }
+
+    /// Domain that revealed the given nullifier, if any.
+    fn revealed_by(&self, nullifier: &Nullifier) -> Option<NullifierDomain> {
+        self.nullifiers.iter().find(|(n, _)| n == nullifier).map(|(_, domain)| *domain)
+    }
+
+    /// Verify a `Money::Transfer` call made of the given parameters,
+    /// returning the state update it produces.
+    fn transfer(
+        &self,
+        params: &MoneyTransferParamsV1,
+        n_calls: usize,
+    ) -> std::result::Result<MoneyTransferUpdateV1, MoneyError> {
+        if params.clear_inputs.len() + params.inputs.len() < 1 {
+            return Err(MoneyError::TransferMissingInputs)
+        }
+
+        if params.outputs.is_empty() {
+            return Err(MoneyError::TransferMissingOutputs)
+        }
+
+        let mut valcom_total = pallas::Point::identity();
+
+        for input in &params.clear_inputs {
+            if input.token_id != *DARK_TOKEN_ID {
+                return Err(MoneyError::TransferClearInputNonNativeToken)
+            }
+
+            if !self.faucet_pubkeys.contains(&input.signature_public) {
+                return Err(MoneyError::TransferClearInputUnauthorised)
+            }
+
+            valcom_total += pedersen_commitment_u64(input.value, input.value_blind);
+        }
+
+        let mut nullifiers = vec![];
+        for input in &params.inputs {
+            if !self.roots.contains(&input.merkle_root) {
+                return Err(MoneyError::TransferMerkleRootNotFound)
+            }
+
+            if nullifiers.contains(&input.nullifier) || self.revealed_by(&input.nullifier).is_some()
+            {
+                return Err(MoneyError::DuplicateNullifier)
+            }
+
+            // The call is always the last one in its transaction
+            if input.spend_hook != pallas::Base::ZERO && n_calls < 2 {
+                return Err(MoneyError::CallIdxOutOfBounds)
+            }
+
+            nullifiers.push(input.nullifier);
+            valcom_total += input.value_commit;
+        }
+
+        let mut coins = vec![];
+        for output in &params.outputs {
+            if coins.contains(&output.coin) || self.coins.contains(&output.coin) {
+                return Err(MoneyError::DuplicateCoin)
+            }
+
+            coins.push(output.coin);
+            valcom_total -= output.value_commit;
+        }
+
+        if valcom_total != pallas::Point::identity() {
+            return Err(MoneyError::ValueMismatch)
+        }
+
+        let token_commit = params.outputs[0].token_commit;
+        let token_commits = params.inputs.iter().map(|x| x.token_commit).chain(
+            params.clear_inputs.iter().map(|x| poseidon_hash([x.token_id.inner(), x.token_blind])),
+        );
+        if token_commits
+            .chain(params.outputs.iter().map(|x| x.token_commit))
+            .any(|x| x != token_commit)
+        {
+            return Err(MoneyError::TokenMismatch)
+        }
+
+        Ok(MoneyTransferUpdateV1 { nullifiers, coins })
+    }
+
+    /// Apply a state update produced by the given function.
+    fn apply(&mut self, update: &MoneyTransferUpdateV1, function: MoneyFunction) {
+        let domain = NullifierDomain::new(*MONEY_CONTRACT_ID, function as u8);
+        self.nullifiers.extend(update.nullifiers.iter().map(|n| (*n, domain)));
+        self.coins.extend(&update.coins);
+
+        if update.coins.is_empty() {
+            return
+        }
+
+        for coin in &update.coins {
+            self.tree.append(MerkleNode::from(coin.inner()));
+        }
+        self.roots.push(self.tree.root(0).unwrap());
+    }
+}
+
+/// A coin the generator can spend, along with what it needs to know to
+/// build an input out of it
+#[derive(Clone)]
+struct Spendable {
+    value: u64,
+    value_blind: pallas::Scalar,
+    nullifier: Nullifier,
+    merkle_root: MerkleNode,
+}
+
+/// Generator of random `Money::Transfer` calls, mostly valid but with
+/// every kind of error the contract checks for mixed in
+struct CallGenerator {
+    rng: StdRng,
+    faucet: Keypair,
+    token_blind: pallas::Base,
+    /// Coins that haven't been spent yet
+    unspent: Vec<Spendable>,
+    /// Nullifiers that were already revealed
+    spent: Vec<Nullifier>,
+    /// Every coin the generator ever put in an output
+    coins: Vec<Coin>,
+    /// Every nullifier the generator ever put in an input
+    nullifiers: Vec<Nullifier>,
+    /// Outputs of the last generated call, to track if it gets accepted
+    pending: Vec<(Coin, u64, pallas::Scalar)>,
+}
+
+impl CallGenerator {
+    fn new(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let faucet = Keypair::random(&mut rng);
+        let token_blind = pallas::Base::random(&mut rng);
+
+        Self {
+            rng,
+            faucet,
+            token_blind,
+            unspent: vec![],
+            spent: vec![],
+            coins: vec![],
+            nullifiers: vec![],
+            pending: vec![],
+        }
+    }
+
+    fn token_commit(&self) -> pallas::Base {
+        poseidon_hash([DARK_TOKEN_ID.inner(), self.token_blind])
+    }
+
+    /// Build outputs carrying the given values, whose value blinds add up
+    /// to `blind_total`.
+    fn outputs(&mut self, values: &[u64], blind_total: pallas::Scalar) -> Vec<Output> {
+        let mut outputs = vec![];
+        let mut blind_sum = pallas::Scalar::ZERO;
+        self.pending.clear();
+
+        for (i, value) in values.iter().enumerate() {
+            let value_blind = if i == values.len() - 1 {
+                blind_total - blind_sum
+            } else {
+                pallas::Scalar::random(&mut self.rng)
+            };
+            blind_sum += value_blind;
+
+            let coin = Coin::from(pallas::Base::random(&mut self.rng));
+            self.coins.push(coin);
+            self.pending.push((coin, *value, value_blind));
+
+            outputs.push(Output {
+                value_commit: pedersen_commitment_u64(*value, value_blind),
+                token_commit: self.token_commit(),
+                coin,
+                note: AeadEncryptedNote {
+                    ciphertext: vec![],
+                    ephem_public: Keypair::random(&mut self.rng).public,
+                },
+            });
+        }
+
+        outputs
+    }
+
+    fn input(&mut self, spendable: &Spendable) -> Input {
+        self.nullifiers.push(spendable.nullifier);
+        Input {
+            value_commit: pedersen_commitment_u64(spendable.value, spendable.value_blind),
+            token_commit: self.token_commit(),
+            nullifier: spendable.nullifier,
+            merkle_root: spendable.merkle_root,
+            spend_hook: pallas::Base::ZERO,
+            user_data_enc: pallas::Base::ZERO,
+            signature_public: Keypair::random(&mut self.rng).public,
+        }
+    }
+
+    /// Split `value` into up to three parts.
+    fn split(&mut self, value: u64) -> Vec<u64> {
+        let mut values = vec![];
+        let mut left = value;
+        for _ in 0..self.rng.gen_range(0..3) {
+            let part = self.rng.gen_range(0..=left);
+            values.push(part);
+            left -= part;
+        }
+        values.push(left);
+        values
+    }
+
+    /// Generate the parameters of the next call.
+    fn next(&mut self) -> MoneyTransferParamsV1 {
+        let mut params =
+            MoneyTransferParamsV1 { clear_inputs: vec![], inputs: vec![], outputs: vec![] };
+
+        // Start out with a valid airdrop or spend
+        if self.unspent.is_empty() || self.rng.gen_bool(0.3) {
+            let value = self.rng.gen_range(1..1000);
+            let value_blind = pallas::Scalar::random(&mut self.rng);
+            params.clear_inputs.push(ClearInput {
+                value,
+                token_id: *DARK_TOKEN_ID,
+                value_blind,
+                token_blind: self.token_blind,
+                signature_public: self.faucet.public,
+            });
+            let values = self.split(value);
+            params.outputs = self.outputs(&values, value_blind);
+        } else {
+            let n_inputs = self.rng.gen_range(1..=self.unspent.len().min(3));
+            let mut value = 0;
+            let mut blind_total = pallas::Scalar::ZERO;
+            for _ in 0..n_inputs {
+                let idx = self.rng.gen_range(0..self.unspent.len());
+                let spendable = self.unspent[idx].clone();
+                value += spendable.value;
+                blind_total += spendable.value_blind;
+                let input = self.input(&spendable);
+                params.inputs.push(input);
+            }
+            let values = self.split(value);
+            params.outputs = self.outputs(&values, blind_total);
+        }
+
+        // Then break it in some way, every now and then
+        match self.rng.gen_range(0..16) {
+            0 if !self.spent.is_empty() => {
+                let nullifier = self.spent[self.rng.gen_range(0..self.spent.len())];
+                if let Some(input) = params.inputs.first_mut() {
+                    input.nullifier = nullifier;
+                }
+            }
+            1 => {
+                if let Some(input) = params.inputs.first_mut() {
+                    input.merkle_root = MerkleNode::from(pallas::Base::random(&mut self.rng));
+                }
+            }
+            2 => {
+                params.outputs[0].value_commit +=
+                    pedersen_commitment_u64(1, pallas::Scalar::random(&mut self.rng));
+            }
+            3 if !self.coins.is_empty() => {
+                let coin = self.coins[self.rng.gen_range(0..self.coins.len())];
+                params.outputs[0].coin = coin;
+            }
+            4 => {
+                if let Some(input) = params.clear_inputs.first_mut() {
+                    input.signature_public = Keypair::random(&mut self.rng).public;
+                }
+            }
+            5 => {
+                params.outputs[0].token_commit = pallas::Base::random(&mut self.rng);
+            }
+            6 => {
+                if let Some(input) = params.inputs.first_mut() {
+                    input.spend_hook = pallas::Base::random(&mut self.rng);
+                }
+            }
+            7 => params.outputs.clear(),
+            _ => {}
+        }
+
+        params
+    }
+
+    /// Track the outcome of the last generated call, with the Merkle root
+    /// its coins got included under.
+    fn accepted(&mut self, update: &MoneyTransferUpdateV1, merkle_root: MerkleNode) {
+        self.unspent.retain(|s| !update.nullifiers.contains(&s.nullifier));
+        self.spent.extend(&update.nullifiers);
+
+        for (coin, value, value_blind) in self.pending.drain(..) {
+            if !update.coins.contains(&coin) {
+                continue
+            }
+
+            let nullifier = Nullifier::from(pallas::Base::random(&mut self.rng));
+            self.unspent.push(Spendable { value, value_blind, nullifier, merkle_root });
+        }
+    }
+}
+
+/// Execute a `Money::Transfer` call through the wasm runtime, applying the
+/// state update it produces. Returns the update, or the contract's error code.
+fn wasm_transfer(
+    overlay: &BlockchainOverlayPtr,
+    time_keeper: &TimeKeeper,
+    params: &MoneyTransferParamsV1,
+) -> Result<std::result::Result<Vec<u8>, i64>> {
+    let mut data = vec![MoneyFunction::TransferV1 as u8];
+    params.encode(&mut data)?;
+    let calls = vec![ContractCall { contract_id: *MONEY_CONTRACT_ID, data }];
+
+    let mut payload = vec![];
+    payload.write_u32(0)?;
+    calls.encode(&mut payload)?;
+
+    let (_, _, bincode) = native_contract_bincodes()[0];
+    let mut runtime =
+        Runtime::new(bincode, overlay.clone(), *MONEY_CONTRACT_ID, time_keeper.clone())?;
+
+    let update = match runtime.exec(&payload) {
+        Ok(update) => update,
+        Err(Error::ContractFailure(report)) => return Ok(Err(report.code)),
+        Err(e) => return Err(e),
+    };
+
+    runtime.apply(&update)?;
+    Ok(Ok(update))
+}
+
+/// Assert the contract's state in the overlay matches the reference.
+fn assert_same_state(
+    overlay: &BlockchainOverlayPtr,
+    reference: &MoneyReference,
+    generator: &CallGenerator,
+) -> Result<()> {
+    let lock = overlay.lock().unwrap();
+    let lookup = |tree| lock.contracts.lookup(&MONEY_CONTRACT_ID, tree);
+    let info_tree = lookup(MONEY_CONTRACT_INFO_TREE)?;
+    let coins_tree = lookup(MONEY_CONTRACT_COINS_TREE)?;
+    let roots_tree = lookup(MONEY_CONTRACT_COIN_ROOTS_TREE)?;
+    let nullifiers_tree = lookup(MONEY_CONTRACT_NULLIFIERS_TREE)?;
+    let db = lock.overlay.lock().unwrap();
+
+    for coin in &generator.coins {
+        let seen = db.contains_key(&coins_tree, &serialize(coin))?;
+        assert_eq!(seen, reference.coins.contains(coin), "Coin {:?} differs", coin);
+    }
+
+    for nullifier in &generator.nullifiers {
+        let domain = db.get(&nullifiers_tree, &serialize(nullifier))?;
+        let domain: Option<NullifierDomain> = domain.map(|d| deserialize(&d)).transpose()?;
+        assert_eq!(domain, reference.revealed_by(nullifier), "Nullifier {:?} differs", nullifier);
+    }
+
+    for root in &reference.roots {
+        assert!(db.contains_key(&roots_tree, &serialize(root))?, "Root {:?} missing", root);
+    }
+
+    let latest_root = db.get(&info_tree, &serialize(&MONEY_CONTRACT_LATEST_COIN_ROOT))?;
+    let latest_root: Option<MerkleNode> = latest_root.map(|r| deserialize(&r)).transpose()?;
+    assert_eq!(latest_root.as_ref(), reference.roots.last());
+
+    let frontier = db.get(&info_tree, &serialize(&MONEY_CONTRACT_COIN_MERKLE_FRONTIER))?.unwrap();
+    let frontier: MerkleFrontier = deserialize(&frontier)?;
+    assert_eq!(Some(frontier.root()), reference.tree.root(0));
+
+    Ok(())
+}
+
+#[test]
+fn money_transfer_differential() -> Result<()> {
+    let sled_db = sled::Config::new().temporary(true).open()?;
+    let blockchain = Blockchain::new(&sled_db)?;
+    let overlay = BlockchainOverlay::new(&blockchain)?;
+    let time_keeper = TimeKeeper::new(Timestamp(1689772567), 10, 90, 0);
+
+    let mut generator = CallGenerator::new(0x6d6f6e6579);
+    let faucet_pubkeys = vec![generator.faucet.public];
+    deploy_native_contracts(&overlay, &time_keeper, &faucet_pubkeys)?;
+    let mut reference = MoneyReference::new(faucet_pubkeys);
+
+    let mut accepted = 0;
+    for step in 0..STEPS {
+        let params = generator.next();
+
+        let expected = reference.transfer(&params, 1);
+        let got = wasm_transfer(&overlay, &time_keeper, &params)?;
+
+        match (expected, got) {
+            (Ok(update), Ok(update_data)) => {
+                let mut expected_data = vec![];
+                expected_data.write_u8(MoneyFunction::TransferV1 as u8)?;
+                update.encode(&mut expected_data)?;
+                assert_eq!(update_data, expected_data, "Step {}: state updates differ", step);
+
+                reference.apply(&update, MoneyFunction::TransferV1);
+                let merkle_root = *reference.roots.last().unwrap();
+                generator.accepted(&update, merkle_root);
+                accepted += 1;
+            }
+            (Err(e), Err(code)) => {
+                let expected_code: i64 = ContractError::from(e.clone()).into();
+                assert_eq!(code, expected_code, "Step {}: expected {:?}", step, e);
+            }
+            (expected, got) => {
+                panic!("Step {}: reference gave {:?}, wasm gave {:?}", step, expected, got)
+            }
+        }
+
+        assert_same_state(&overlay, &reference, &generator)?;
+    }
+
+    // Make sure the sequence wasn't degenerate
+    assert!(accepted > STEPS / 4);
+    assert!(accepted < STEPS);
+
+    Ok(())
+}
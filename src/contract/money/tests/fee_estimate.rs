@@ -0,0 +1,69 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Test for estimating a transaction's fee before it pays one.
+//!
+//! Alice mints herself some tokens, and a node estimates the gas the mint
+//! uses. The estimate must account for every part of verification, and
+//! leave the node's state untouched so the transaction can still execute.
+
+use darkfi::{
+    consensus::fees::{compute_fee, signatures_gas_use},
+    Result,
+};
+use darkfi_contract_test_harness::{init_logger, Holder, TestHarness};
+use log::info;
+
+#[test]
+fn fee_estimate() -> Result<()> {
+    smol::block_on(async {
+        init_logger();
+
+        // Slot to verify against
+        let current_slot = 0;
+
+        // Initialize harness
+        let mut th = TestHarness::new(&["money".to_string()]).await?;
+
+        info!(target: "money", "[Alice] ================================");
+        info!(target: "money", "[Alice] Building token mint tx for Alice");
+        info!(target: "money", "[Alice] ================================");
+        let (token_mint_tx, token_mint_params) =
+            th.token_mint(100, &Holder::Alice, &Holder::Alice, None, None)?;
+
+        info!(target: "money", "[Faucet] =================================");
+        info!(target: "money", "[Faucet] Estimating Alice token mint tx gas");
+        info!(target: "money", "[Faucet] =================================");
+        let validator = th.holders.get(&Holder::Faucet).unwrap().validator.clone();
+        let gas =
+            validator.read().await.estimate_transaction_gas(&token_mint_tx, current_slot).await?;
+
+        let n_signatures = token_mint_tx.signatures.iter().map(|s| s.len()).sum();
+        assert!(gas.wasm > 0);
+        assert_eq!(gas.signatures, signatures_gas_use(n_signatures));
+        assert!(gas.zk_circuits > 0);
+        assert!(compute_fee(&gas) > 0);
+
+        // The estimate didn't apply anything, so the mint still executes
+        th.execute_token_mint_tx(&Holder::Faucet, &token_mint_tx, &token_mint_params, current_slot)
+            .await?;
+
+        // Thanks for reading
+        Ok(())
+    })
+}
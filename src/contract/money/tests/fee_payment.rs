@@ -0,0 +1,102 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Test for paying transaction fees with `Money::Fee`.
+//!
+//! Alice gets airdropped some native tokens, and the nodes start enforcing
+//! fees. A fee call paying nothing gets rejected, while one paying the minimum
+//! fee of the current fee model for its estimated gas goes through, and
+//! returns her the change.
+
+use darkfi::{error::TxVerifyFailed, Error, Result};
+use darkfi_contract_test_harness::{init_logger, Holder, TestHarness, TxAction};
+use log::info;
+
+#[test]
+fn fee_payment() -> Result<()> {
+    smol::block_on(async {
+        init_logger();
+
+        // Holders this test uses
+        const HOLDERS: [Holder; 2] = [Holder::Faucet, Holder::Alice];
+
+        // Some numbers we want to assert
+        const ALICE_AIRDROP: u64 = 1_000_000_000;
+
+        // Slot to verify against
+        let current_slot = 0;
+
+        // Initialize harness
+        let mut th = TestHarness::new(&["money".to_string()]).await?;
+
+        // The airdrop doesn't pay a fee, so fees are enforced only after it
+        let alice_oc =
+            th.execute_airdrop(&HOLDERS, &Holder::Alice, ALICE_AIRDROP, current_slot).await?;
+        for holder in &HOLDERS {
+            th.holders.get(holder).unwrap().validator.write().await.verify_fees = true;
+        }
+
+        info!(target: "money", "[Alice] =================================");
+        info!(target: "money", "[Alice] Building fee tx paying nothing");
+        info!(target: "money", "[Alice] =================================");
+        let (fee_tx, _, _) = th.fee(0, &Holder::Alice, &alice_oc)?;
+
+        let validator = th.holders.get(&Holder::Faucet).unwrap().validator.clone();
+        let gas = validator.read().await.estimate_transaction_gas(&fee_tx, current_slot).await?;
+        let required = validator.read().await.fee_model()?.min_fee(&gas);
+        assert!(required > 0);
+
+        match validator.read().await.simulate_transaction(&fee_tx, current_slot).await {
+            Err(Error::TxVerifyFailed(TxVerifyFailed::InsufficientFee(0, r))) => {
+                assert_eq!(r, required)
+            }
+            other => panic!("Expected an insufficient fee error, got {:?}", other),
+        }
+
+        for holder in &HOLDERS {
+            th.execute_erroneous_txs(
+                TxAction::MoneyFee,
+                holder,
+                &[fee_tx.clone()],
+                current_slot,
+                1,
+            )
+            .await?;
+        }
+
+        info!(target: "money", "[Alice] ===================================");
+        info!(target: "money", "[Alice] Building fee tx paying its cost");
+        info!(target: "money", "[Alice] ===================================");
+        let (fee_tx, fee_params, _) = th.fee(required, &Holder::Alice, &alice_oc)?;
+
+        for holder in &HOLDERS {
+            info!(target: "money", "[{holder:?}] ========================");
+            info!(target: "money", "[{holder:?}] Executing Alice fee tx");
+            info!(target: "money", "[{holder:?}] ========================");
+            th.execute_fee_tx(holder, &fee_tx, &fee_params, current_slot).await?;
+        }
+
+        th.assert_trees(&HOLDERS);
+
+        // Alice gets back her change, minus the paid fee
+        let alice_change = th.gather_owncoin(&Holder::Alice, &fee_params.output, None)?;
+        assert_eq!(alice_change.note.value, ALICE_AIRDROP - required);
+
+        // Thanks for reading
+        Ok(())
+    })
+}
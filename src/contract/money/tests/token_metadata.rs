@@ -0,0 +1,113 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Test for the token metadata registry.
+//!
+//! Alice registers metadata for a token following the standard derivation,
+//! and we confirm it can be looked up both by token ID and by issuer, while
+//! registering the same token again gets rejected.
+
+use darkfi::{tx::Transaction, Result};
+use darkfi_contract_test_harness::{init_logger, Holder, TestHarness};
+use darkfi_money_contract::{
+    client::token_metadata_v1::TokenMetadataCallBuilder, model::TokenMetadata, MoneyFunction,
+    MONEY_CONTRACT_TOKEN_ISSUERS_TREE, MONEY_CONTRACT_TOKEN_METADATA_TREE,
+};
+use darkfi_sdk::{
+    crypto::{TokenId, MONEY_CONTRACT_ID},
+    ContractCall,
+};
+use darkfi_serial::{deserialize, serialize, Encodable};
+use log::info;
+use rand::rngs::OsRng;
+
+#[test]
+fn token_metadata() -> Result<()> {
+    smol::block_on(async {
+        init_logger();
+
+        // Slot to verify against
+        let current_slot = 0;
+
+        // Initialize harness
+        let th = TestHarness::new(&["money".to_string()]).await?;
+        let alice = th.holders.get(&Holder::Alice).unwrap();
+
+        info!(target: "money", "[Alice] ====================================");
+        info!(target: "money", "[Alice] Building token metadata tx for Alice");
+        info!(target: "money", "[Alice] ====================================");
+        let builder = TokenMetadataCallBuilder {
+            issuer: alice.keypair,
+            symbol_nonce: 0,
+            name: "Alice Token".to_string(),
+            symbol: "ALICE".to_string(),
+            decimals: 8,
+            uri: "https://example.com/alice.json".to_string(),
+        };
+        let debris = builder.build()?;
+
+        let mut data = vec![MoneyFunction::TokenMetadataV1 as u8];
+        debris.params.encode(&mut data)?;
+        let calls = vec![ContractCall { contract_id: *MONEY_CONTRACT_ID, data }];
+        let mut tx =
+            Transaction { calls, proofs: vec![vec![]], signatures: vec![], access_list: None };
+        let sigs = tx.create_sigs(&mut OsRng, &debris.signature_secrets)?;
+        tx.signatures = vec![sigs];
+
+        // Metadata fields outside their limits are refused by the builder
+        let invalid = TokenMetadataCallBuilder { decimals: 19, ..builder };
+        assert!(invalid.build().is_err());
+
+        info!(target: "money", "[Alice] ================================");
+        info!(target: "money", "[Alice] Executing Alice token metadata tx");
+        info!(target: "money", "[Alice] ================================");
+        let validator = alice.validator.read().await;
+        validator.add_transactions(&[tx.clone()], current_slot, true).await?;
+
+        // The token follows the standard derivation
+        let token_id = TokenId::derive_standard(alice.keypair.secret, 0);
+        assert_eq!(token_id, TokenId::derive_public(debris.params.mint_public));
+
+        // The metadata can be found by token ID
+        let blockchain = &validator.blockchain;
+        let metadata_db = blockchain.contracts.lookup(
+            &blockchain.sled_db,
+            &MONEY_CONTRACT_ID,
+            MONEY_CONTRACT_TOKEN_METADATA_TREE,
+        )?;
+        let metadata: TokenMetadata =
+            deserialize(&metadata_db.get(serialize(&token_id))?.unwrap())?;
+        assert_eq!(metadata, debris.params.metadata);
+
+        // And by its issuer and nonce
+        let issuers_db = blockchain.contracts.lookup(
+            &blockchain.sled_db,
+            &MONEY_CONTRACT_ID,
+            MONEY_CONTRACT_TOKEN_ISSUERS_TREE,
+        )?;
+        let issued: TokenId =
+            deserialize(&issuers_db.get(serialize(&(alice.keypair.public, 0_u64)))?.unwrap())?;
+        assert_eq!(issued, token_id);
+
+        // Metadata can only be registered once
+        assert!(validator.simulate_transaction(&tx, current_slot).await.is_err());
+
+        // Thanks for reading
+        Ok(())
+    })
+}
@@ -0,0 +1,120 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Test for capped token supplies.
+//!
+//! Alice mints a token with a maximum supply, and we confirm mints going
+//! over the cap or trying to change it get rejected, while the recorded
+//! mint state follows the minted amount, and the recorded supply
+//! commitment opens to it.
+
+use darkfi::Result;
+use darkfi_contract_test_harness::{init_logger, Holder, TestHarness};
+use darkfi_money_contract::{
+    model::{TokenMintInfo, TokenSupply},
+    MONEY_CONTRACT_TOKEN_MINT_INFO_TREE, MONEY_CONTRACT_TOKEN_SUPPLY_TREE,
+};
+use darkfi_sdk::crypto::{TokenId, MONEY_CONTRACT_ID};
+use darkfi_serial::{deserialize, serialize};
+use log::info;
+
+const MAX_SUPPLY: u64 = 100;
+
+#[test]
+fn token_supply() -> Result<()> {
+    smol::block_on(async {
+        init_logger();
+
+        // Slot to verify against
+        let current_slot = 0;
+
+        // Initialize harness
+        let mut th = TestHarness::new(&["money".to_string()]).await?;
+
+        info!(target: "money", "[Alice] ===================================");
+        info!(target: "money", "[Alice] Minting capped token supply to Bob");
+        info!(target: "money", "[Alice] ===================================");
+        let (tx, params) =
+            th.token_mint_capped(60, &Holder::Alice, &Holder::Bob, None, None, Some(MAX_SUPPLY))?;
+        th.execute_token_mint_tx(&Holder::Alice, &tx, &params, current_slot).await?;
+        let mut value_commit = params.output.value_commit;
+
+        info!(target: "money", "[Alice] =====================================");
+        info!(target: "money", "[Alice] Checking mints over the cap are refused");
+        info!(target: "money", "[Alice] =====================================");
+        let (tx, params) = th.token_mint(50, &Holder::Alice, &Holder::Bob, None, None)?;
+        assert!(th
+            .execute_token_mint_tx(&Holder::Alice, &tx, &params, current_slot)
+            .await
+            .is_err());
+
+        info!(target: "money", "[Alice] =========================================");
+        info!(target: "money", "[Alice] Checking the maximum supply can't be changed");
+        info!(target: "money", "[Alice] =========================================");
+        let (tx, params) = th.token_mint_capped(
+            40,
+            &Holder::Alice,
+            &Holder::Bob,
+            None,
+            None,
+            Some(MAX_SUPPLY * 2),
+        )?;
+        assert!(th
+            .execute_token_mint_tx(&Holder::Alice, &tx, &params, current_slot)
+            .await
+            .is_err());
+
+        info!(target: "money", "[Alice] ===================================");
+        info!(target: "money", "[Alice] Minting the rest of the supply to Bob");
+        info!(target: "money", "[Alice] ===================================");
+        let (tx, params) = th.token_mint(40, &Holder::Alice, &Holder::Bob, None, None)?;
+        th.execute_token_mint_tx(&Holder::Alice, &tx, &params, current_slot).await?;
+        value_commit += params.output.value_commit;
+
+        // The recorded mint state reached the cap
+        let alice = th.holders.get(&Holder::Alice).unwrap();
+        let token_id = TokenId::derive_public(alice.token_mint_authority.public);
+        let validator = alice.validator.read().await;
+        let blockchain = &validator.blockchain;
+        let mint_info_db = blockchain.contracts.lookup(
+            &blockchain.sled_db,
+            &MONEY_CONTRACT_ID,
+            MONEY_CONTRACT_TOKEN_MINT_INFO_TREE,
+        )?;
+        let mint_info: TokenMintInfo =
+            deserialize(&mint_info_db.get(serialize(&token_id))?.unwrap())?;
+        assert_eq!(mint_info.authority, alice.token_mint_authority.public);
+        assert_eq!(mint_info.max_supply, Some(MAX_SUPPLY));
+        assert_eq!(mint_info.minted, MAX_SUPPLY);
+
+        // The supply commitment sums up the applied mints only, and its
+        // opening gives away the minted amount.
+        let supply_db = blockchain.contracts.lookup(
+            &blockchain.sled_db,
+            &MONEY_CONTRACT_ID,
+            MONEY_CONTRACT_TOKEN_SUPPLY_TREE,
+        )?;
+        let supply: TokenSupply = deserialize(&supply_db.get(serialize(&token_id))?.unwrap())?;
+        assert_eq!(supply.value_commit, value_commit);
+        assert_eq!(supply.opening.as_ref().map(|x| x.value), Some(MAX_SUPPLY));
+        assert!(supply.verify_opening());
+
+        // Thanks for reading
+        Ok(())
+    })
+}
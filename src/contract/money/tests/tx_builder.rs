@@ -0,0 +1,83 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Test for assembling multiple contract calls into a single transaction.
+//!
+//! We add two calls spending distinct coins, and confirm every call's
+//! signatures cover the whole transaction, while a call reusing an already
+//! spent coin gets rejected.
+
+use darkfi::{ClientFailed, Error, Result};
+use darkfi_money_contract::{
+    client::{tx_builder::TransactionBuilder, MoneyNote, OwnCoin},
+    model::Coin,
+};
+use darkfi_sdk::{
+    bridgetree::Position,
+    crypto::{Nullifier, PublicKey, SecretKey, DARK_TOKEN_ID, MONEY_CONTRACT_ID},
+    pasta::pallas,
+    ContractCall,
+};
+use rand::rngs::OsRng;
+
+fn dummy_coin(serial: u64) -> OwnCoin {
+    let note = MoneyNote {
+        serial: pallas::Base::from(serial),
+        value: 42,
+        token_id: *DARK_TOKEN_ID,
+        spend_hook: pallas::Base::from(0),
+        user_data: pallas::Base::from(0),
+        value_blind: pallas::Scalar::from(0),
+        token_blind: pallas::Base::from(0),
+        memo: vec![],
+    };
+
+    OwnCoin {
+        coin: Coin::from(pallas::Base::from(serial)),
+        note,
+        secret: SecretKey::random(&mut OsRng),
+        nullifier: Nullifier::from(pallas::Base::from(serial)),
+        leaf_position: Position::from(serial),
+    }
+}
+
+#[test]
+fn tx_builder() -> Result<()> {
+    let call = |data: u8| ContractCall { contract_id: *MONEY_CONTRACT_ID, data: vec![data] };
+    let secrets = [SecretKey::random(&mut OsRng), SecretKey::random(&mut OsRng)];
+    let (coin0, coin1) = (dummy_coin(0), dummy_coin(1));
+
+    let mut builder = TransactionBuilder::new();
+    builder.add_call(call(0x00), vec![], vec![secrets[0]], &[coin0.clone()])?;
+    builder.add_call(call(0x01), vec![], vec![secrets[1]], &[coin1])?;
+
+    // Spending an already used coin is rejected
+    assert!(matches!(
+        builder.add_call(call(0x02), vec![], vec![], &[coin0]),
+        Err(Error::ClientFailed(ClientFailed::DuplicateCoin(_)))
+    ));
+
+    let tx = builder.build()?;
+    assert_eq!(tx.calls.len(), 2);
+    assert_eq!(tx.calls[1].data, vec![0x01]);
+
+    let pub_table = secrets.iter().map(|s| vec![PublicKey::from_secret(*s)]).collect();
+    tx.verify_sigs(pub_table)?;
+
+    Ok(())
+}
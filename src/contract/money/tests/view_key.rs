@@ -0,0 +1,138 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Test for incoming viewing keys.
+//!
+//! Alice mints herself some tokens and pays Bob with them, encrypting
+//! the notes to Bob's view address and to her own for the change. We
+//! confirm the view keys find those coins while the spend secrets still
+//! own them, and that a view key can't see the other party's coin.
+
+use darkfi::{zk::halo2::Field, Result};
+use darkfi_contract_test_harness::{init_logger, Holder, TestHarness};
+use darkfi_money_contract::{
+    client::{
+        scan::discover_owncoins, transfer_v1::TransferCallBuilder, view_key::ViewKey, MoneyNote,
+    },
+    MONEY_CONTRACT_ZKAS_BURN_NS_V1, MONEY_CONTRACT_ZKAS_MINT_NS_V1,
+};
+use darkfi_sdk::{
+    crypto::{poseidon_hash, Nullifier, PublicKey},
+    pasta::pallas,
+};
+use log::info;
+use rand::rngs::OsRng;
+
+#[test]
+fn view_key() -> Result<()> {
+    smol::block_on(async {
+        init_logger();
+
+        // Holders this test will use
+        const HOLDERS: [Holder; 3] = [Holder::Faucet, Holder::Alice, Holder::Bob];
+
+        // Slot to verify against
+        let current_slot = 0;
+
+        // Initialize harness
+        let mut th = TestHarness::new(&["money".to_string()]).await?;
+
+        let alice_keypair = th.holders.get(&Holder::Alice).unwrap().keypair;
+        let bob_keypair = th.holders.get(&Holder::Bob).unwrap().keypair;
+        let alice_view = ViewKey::derive(&alice_keypair.secret);
+        let bob_view = ViewKey::derive(&bob_keypair.secret);
+        assert_ne!(bob_view.secret(), bob_keypair.secret);
+        assert_ne!(bob_view.public(), bob_keypair.public);
+        assert_eq!(bob_view.public(), PublicKey::from_secret(bob_view.secret()));
+
+        info!(target: "money", "[Alice] ================================");
+        info!(target: "money", "[Alice] Building token mint tx for Alice");
+        info!(target: "money", "[Alice] ================================");
+        let (mint_tx, mint_params) =
+            th.token_mint(100, &Holder::Alice, &Holder::Alice, None, None)?;
+        for holder in &HOLDERS {
+            th.execute_token_mint_tx(holder, &mint_tx, &mint_params, current_slot).await?;
+        }
+        let alice_oc = th.gather_owncoin(&Holder::Alice, &mint_params.output, None)?;
+
+        info!(target: "money", "[Alice] ======================================");
+        info!(target: "money", "[Alice] Building payment to Bob's view address");
+        info!(target: "money", "[Alice] ======================================");
+        let (mint_pk, mint_zkbin) =
+            th.proving_keys.get(&MONEY_CONTRACT_ZKAS_MINT_NS_V1.to_string()).unwrap();
+        let (burn_pk, burn_zkbin) =
+            th.proving_keys.get(&MONEY_CONTRACT_ZKAS_BURN_NS_V1.to_string()).unwrap();
+        let tree = th.holders.get(&Holder::Alice).unwrap().money_merkle_tree.clone();
+
+        let builder = TransferCallBuilder {
+            keypair: alice_keypair,
+            recipient: bob_keypair.public,
+            value: 40,
+            token_id: alice_oc.note.token_id,
+            rcpt_spend_hook: pallas::Base::ZERO,
+            rcpt_user_data: pallas::Base::ZERO,
+            rcpt_user_data_blind: pallas::Base::random(&mut OsRng),
+            rcpt_view_public: Some(bob_view.public()),
+            change_spend_hook: pallas::Base::ZERO,
+            change_user_data: pallas::Base::ZERO,
+            change_user_data_blind: pallas::Base::random(&mut OsRng),
+            change_view_public: Some(alice_view.public()),
+            coins: vec![alice_oc],
+            tree: tree.clone(),
+            mint_zkbin: mint_zkbin.clone(),
+            mint_pk: mint_pk.clone(),
+            burn_zkbin: burn_zkbin.clone(),
+            burn_pk: burn_pk.clone(),
+            clear_input: false,
+        };
+        let outputs = builder.build()?.params.outputs;
+        assert_eq!(outputs.len(), 2);
+
+        // The notes are no longer encrypted to the coins' public keys
+        for output in &outputs {
+            assert!(output.note.decrypt::<MoneyNote>(&bob_keypair.secret).is_err());
+            assert!(output.note.decrypt::<MoneyNote>(&alice_keypair.secret).is_err());
+        }
+
+        // Bob's wallet finds his coin through his derived view key, and it
+        // stays spendable since it's attributed to his spend secret.
+        let found = discover_owncoins(&mut tree.clone(), &outputs, &[bob_keypair.secret]);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].note.value, 40);
+        assert_eq!(found[0].secret, bob_keypair.secret);
+        let nullifier = poseidon_hash([bob_keypair.secret.inner(), found[0].note.serial]);
+        assert_eq!(found[0].nullifier, Nullifier::from(nullifier));
+
+        // A watch-only wallet holding Bob's view key sees the same coin
+        let watched = discover_owncoins(&mut tree.clone(), &outputs, &[bob_view.secret()]);
+        assert_eq!(watched.len(), 1);
+        assert_eq!(watched[0].coin, found[0].coin);
+        assert_eq!(bob_view.decrypt_note(&outputs[1]).map(|n| n.value), Some(40));
+
+        // Alice's view key only sees her change
+        let watched = discover_owncoins(&mut tree.clone(), &outputs, &[alice_view.secret()]);
+        assert_eq!(watched.len(), 1);
+        assert_eq!(watched[0].note.value, 60);
+        let found = discover_owncoins(&mut tree.clone(), &outputs, &[alice_keypair.secret]);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].coin, watched[0].coin);
+
+        // Thanks for reading
+        Ok(())
+    })
+}
@@ -0,0 +1,63 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Test that the public input counts declared by the money and consensus
+//! contracts match the circuits they deploy.
+
+use darkfi::{zkas::ZkBinary, Result};
+use darkfi_money_contract::{
+    CONSENSUS_CONTRACT_ZKAS_BURN_PI_V1, CONSENSUS_CONTRACT_ZKAS_MINT_PI_V1,
+    CONSENSUS_CONTRACT_ZKAS_PROPOSAL_PI_V1, MONEY_CONTRACT_ZKAS_BURN_PI_V1,
+    MONEY_CONTRACT_ZKAS_MINT_PI_V1, MONEY_CONTRACT_ZKAS_TOKEN_FRZ_PI_V1,
+    MONEY_CONTRACT_ZKAS_TOKEN_MINT_PI_V1,
+};
+
+#[test]
+fn zkas_declarations() -> Result<()> {
+    let declarations = [
+        (&include_bytes!("../proof/mint_v1.zk.bin")[..], MONEY_CONTRACT_ZKAS_MINT_PI_V1),
+        (&include_bytes!("../proof/burn_v1.zk.bin")[..], MONEY_CONTRACT_ZKAS_BURN_PI_V1),
+        (
+            &include_bytes!("../proof/token_mint_v1.zk.bin")[..],
+            MONEY_CONTRACT_ZKAS_TOKEN_MINT_PI_V1,
+        ),
+        (
+            &include_bytes!("../proof/token_freeze_v1.zk.bin")[..],
+            MONEY_CONTRACT_ZKAS_TOKEN_FRZ_PI_V1,
+        ),
+        (
+            &include_bytes!("../../consensus/proof/consensus_mint_v1.zk.bin")[..],
+            CONSENSUS_CONTRACT_ZKAS_MINT_PI_V1,
+        ),
+        (
+            &include_bytes!("../../consensus/proof/consensus_burn_v1.zk.bin")[..],
+            CONSENSUS_CONTRACT_ZKAS_BURN_PI_V1,
+        ),
+        (
+            &include_bytes!("../../consensus/proof/consensus_proposal_v1.zk.bin")[..],
+            CONSENSUS_CONTRACT_ZKAS_PROPOSAL_PI_V1,
+        ),
+    ];
+
+    for (bincode, declared) in declarations {
+        let zkbin = ZkBinary::decode(bincode)?;
+        assert_eq!(zkbin.public_inputs_count(), declared as usize, "{}", zkbin.namespace);
+    }
+
+    Ok(())
+}
@@ -20,6 +20,16 @@ CREATE TABLE IF NOT EXISTS money_keys (
 	secret BLOB NOT NULL
 );
 
+-- The view keys of a watch-only wallet, used to find our coins
+-- NOTE: A view key is derived one-way from a spend secret. It decrypts the
+-- notes of coins sent to its view address, but can't compute their
+-- nullifiers or sign for them, so it grants no spend authority.
+CREATE TABLE IF NOT EXISTS money_view_keys (
+	key_id INTEGER PRIMARY KEY NOT NULL,
+	public BLOB NOT NULL,
+	secret BLOB NOT NULL
+);
+
 -- The coins we have the information to and can spend
 CREATE TABLE IF NOT EXISTS money_coins (
 	coin BLOB PRIMARY KEY NOT NULL,
@@ -37,6 +47,29 @@ CREATE TABLE IF NOT EXISTS money_coins (
 	memo BLOB
 );
 
+-- Write-ahead journal of the coins we've spent in transactions that
+-- haven't made it into the chain yet
+CREATE TABLE IF NOT EXISTS money_spend_intents (
+	coin BLOB PRIMARY KEY NOT NULL,
+	tx_hash TEXT NOT NULL,
+	is_broadcast INTEGER NOT NULL
+);
+
+-- The receive source of our coins. Coins found in the same transaction
+-- share a cluster, and spending coins together merges their clusters,
+-- since they're linked on chain from then on.
+CREATE TABLE IF NOT EXISTS money_coin_sources (
+	coin BLOB PRIMARY KEY NOT NULL,
+	cluster TEXT NOT NULL
+);
+
+-- Merkle authentication paths of our unspent coins, kept up to date
+-- as blocks get scanned
+CREATE TABLE IF NOT EXISTS money_coin_witnesses (
+	coin BLOB PRIMARY KEY NOT NULL,
+	witness BLOB NOT NULL
+);
+
 -- Arbitrary tokens
 CREATE TABLE IF NOT EXISTS money_tokens (
 	mint_authority BLOB PRIMARY KEY NOT NULL,
@@ -0,0 +1,207 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::time::Instant;
+
+use darkfi::{tx::Transaction, zk::Proof, Result};
+use darkfi_auth_contract::{
+    client::{exercise_v1::AuthExerciseCallBuilder, issue_v1::AuthIssueCallBuilder},
+    model::{AuthExerciseParamsV1, AuthIssueParamsV1, AuthRole},
+    AuthFunction,
+};
+use darkfi_money_contract::{
+    client::OwnCoin,
+    model::{MoneyTokenMintParamsV1, MoneyTransferParamsV1},
+    MoneyFunction, MONEY_CONTRACT_ZKAS_BURN_NS_V1, MONEY_CONTRACT_ZKAS_MINT_NS_V1,
+    MONEY_CONTRACT_ZKAS_TOKEN_MINT_NS_V1,
+};
+use darkfi_sdk::{
+    crypto::{MerkleNode, SecretKey, AUTH_CONTRACT_ID, MONEY_CONTRACT_ID},
+    ContractCall,
+};
+use darkfi_serial::{serialize, Encodable};
+use rand::rngs::OsRng;
+
+use super::{Holder, TestHarness, TxAction};
+
+impl TestHarness {
+    pub fn auth_issue(
+        &mut self,
+        issuer: &Holder,
+        recipient: &Holder,
+        role: AuthRole,
+    ) -> Result<(Transaction, MoneyTokenMintParamsV1, AuthIssueParamsV1)> {
+        let wallet = self.holders.get(issuer).unwrap();
+        let issuer = wallet.token_mint_authority;
+
+        let rcpt = self.holders.get(recipient).unwrap().keypair.public;
+
+        let (mint_pk, mint_zkbin) =
+            self.proving_keys.get(&MONEY_CONTRACT_ZKAS_TOKEN_MINT_NS_V1.to_string()).unwrap();
+
+        let tx_action_benchmark = self.tx_action_benchmarks.get_mut(&TxAction::AuthIssue).unwrap();
+        let timer = Instant::now();
+
+        let builder = AuthIssueCallBuilder {
+            issuer,
+            recipient: rcpt,
+            role,
+            token_mint_zkbin: mint_zkbin.clone(),
+            token_mint_pk: mint_pk.clone(),
+        };
+
+        let debris = builder.build()?;
+
+        let mut data = vec![MoneyFunction::TokenMintV1 as u8];
+        debris.mint_params.encode(&mut data)?;
+        let mint_call = ContractCall { contract_id: *MONEY_CONTRACT_ID, data };
+
+        let mut data = vec![AuthFunction::IssueV1 as u8];
+        debris.params.encode(&mut data)?;
+        let issue_call = ContractCall { contract_id: *AUTH_CONTRACT_ID, data };
+
+        let mut tx = Transaction {
+            calls: vec![mint_call, issue_call],
+            proofs: vec![debris.mint_proofs, vec![]],
+            signatures: vec![],
+            access_list: None,
+        };
+        let mint_sigs = tx.create_sigs(&mut OsRng, &[issuer.secret])?;
+        let issue_sigs = tx.create_sigs(&mut OsRng, &[issuer.secret])?;
+        tx.signatures = vec![mint_sigs, issue_sigs];
+        tx_action_benchmark.creation_times.push(timer.elapsed());
+
+        // Calculate transaction sizes
+        let encoded: Vec<u8> = serialize(&tx);
+        let size = std::mem::size_of_val(&*encoded);
+        tx_action_benchmark.sizes.push(size);
+        let base58 = bs58::encode(&encoded).into_string();
+        let size = std::mem::size_of_val(&*base58);
+        tx_action_benchmark.broadcasted_sizes.push(size);
+
+        Ok((tx, debris.mint_params, debris.params))
+    }
+
+    pub async fn execute_auth_issue_tx(
+        &mut self,
+        holder: &Holder,
+        tx: &Transaction,
+        mint_params: &MoneyTokenMintParamsV1,
+        slot: u64,
+    ) -> Result<()> {
+        let wallet = self.holders.get_mut(holder).unwrap();
+        let tx_action_benchmark = self.tx_action_benchmarks.get_mut(&TxAction::AuthIssue).unwrap();
+        let timer = Instant::now();
+
+        wallet.validator.read().await.add_transactions(&[tx.clone()], slot, true).await?;
+        wallet.money_merkle_tree.append(MerkleNode::from(mint_params.output.coin.inner()));
+        tx_action_benchmark.verify_times.push(timer.elapsed());
+
+        Ok(())
+    }
+
+    /// Build a transaction exercising `holder`'s capability `coin` to
+    /// authorize the given privileged call, which is placed last.
+    #[allow(clippy::too_many_arguments)]
+    pub fn auth_exercise(
+        &mut self,
+        holder: &Holder,
+        issuer: &Holder,
+        role: AuthRole,
+        coin: &OwnCoin,
+        privileged_call: ContractCall,
+        privileged_proofs: Vec<Proof>,
+        privileged_secrets: &[SecretKey],
+    ) -> Result<(Transaction, MoneyTransferParamsV1, AuthExerciseParamsV1)> {
+        let wallet = self.holders.get(holder).unwrap();
+        let issuer = self.holders.get(issuer).unwrap().token_mint_authority.public;
+
+        let (mint_pk, mint_zkbin) =
+            self.proving_keys.get(&MONEY_CONTRACT_ZKAS_MINT_NS_V1.to_string()).unwrap();
+        let (burn_pk, burn_zkbin) =
+            self.proving_keys.get(&MONEY_CONTRACT_ZKAS_BURN_NS_V1.to_string()).unwrap();
+
+        let tx_action_benchmark =
+            self.tx_action_benchmarks.get_mut(&TxAction::AuthExercise).unwrap();
+        let timer = Instant::now();
+
+        let builder = AuthExerciseCallBuilder {
+            keypair: wallet.keypair,
+            issuer,
+            role,
+            coin: coin.clone(),
+            tree: wallet.money_merkle_tree.clone(),
+            mint_zkbin: mint_zkbin.clone(),
+            mint_pk: mint_pk.clone(),
+            burn_zkbin: burn_zkbin.clone(),
+            burn_pk: burn_pk.clone(),
+        };
+
+        let debris = builder.build()?;
+
+        let mut data = vec![MoneyFunction::TransferV1 as u8];
+        debris.xfer.params.encode(&mut data)?;
+        let xfer_call = ContractCall { contract_id: *MONEY_CONTRACT_ID, data };
+
+        let mut data = vec![AuthFunction::ExerciseV1 as u8];
+        debris.params.encode(&mut data)?;
+        let exercise_call = ContractCall { contract_id: *AUTH_CONTRACT_ID, data };
+
+        let mut tx = Transaction {
+            calls: vec![xfer_call, exercise_call, privileged_call],
+            proofs: vec![debris.xfer.proofs, vec![], privileged_proofs],
+            signatures: vec![],
+            access_list: None,
+        };
+        let xfer_sigs = tx.create_sigs(&mut OsRng, &debris.xfer.signature_secrets)?;
+        let privileged_sigs = tx.create_sigs(&mut OsRng, privileged_secrets)?;
+        tx.signatures = vec![xfer_sigs, vec![], privileged_sigs];
+        tx_action_benchmark.creation_times.push(timer.elapsed());
+
+        // Calculate transaction sizes
+        let encoded: Vec<u8> = serialize(&tx);
+        let size = std::mem::size_of_val(&*encoded);
+        tx_action_benchmark.sizes.push(size);
+        let base58 = bs58::encode(&encoded).into_string();
+        let size = std::mem::size_of_val(&*base58);
+        tx_action_benchmark.broadcasted_sizes.push(size);
+
+        Ok((tx, debris.xfer.params, debris.params))
+    }
+
+    pub async fn execute_auth_exercise_tx(
+        &mut self,
+        holder: &Holder,
+        tx: &Transaction,
+        xfer_params: &MoneyTransferParamsV1,
+        slot: u64,
+    ) -> Result<()> {
+        let wallet = self.holders.get_mut(holder).unwrap();
+        let tx_action_benchmark =
+            self.tx_action_benchmarks.get_mut(&TxAction::AuthExercise).unwrap();
+        let timer = Instant::now();
+
+        wallet.validator.read().await.add_transactions(&[tx.clone()], slot, true).await?;
+        for output in &xfer_params.outputs {
+            wallet.money_merkle_tree.append(MerkleNode::from(output.coin.inner()));
+        }
+        tx_action_benchmark.verify_times.push(timer.elapsed());
+
+        Ok(())
+    }
+}
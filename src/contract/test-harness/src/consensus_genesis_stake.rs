@@ -74,7 +74,8 @@ impl TestHarness {
         let contract_call = ContractCall { contract_id: *CONSENSUS_CONTRACT_ID, data };
         let calls = vec![contract_call];
         let proofs = vec![genesis_stake_proofs];
-        let mut genesis_stake_tx = Transaction { calls, proofs, signatures: vec![] };
+        let mut genesis_stake_tx =
+            Transaction { calls, proofs, signatures: vec![], access_list: None };
         let sigs = genesis_stake_tx.create_sigs(&mut OsRng, &[wallet.keypair.secret])?;
         genesis_stake_tx.signatures = vec![sigs];
         tx_action_benchmark.creation_times.push(timer.elapsed());
@@ -81,7 +81,7 @@ impl TestHarness {
 
         let calls = vec![call];
         let proofs = vec![proofs];
-        let mut tx = Transaction { calls, proofs, signatures: vec![] };
+        let mut tx = Transaction { calls, proofs, signatures: vec![], access_list: None };
         let sigs = tx.create_sigs(&mut OsRng, &[signature_secret_key])?;
         tx.signatures = vec![sigs];
         tx_action_benchmark.creation_times.push(timer.elapsed());
@@ -0,0 +1,158 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::time::Instant;
+
+use darkfi::{blockchain::Header, tx::Transaction, util::time::Timestamp, zk::Proof, Result};
+use darkfi_consensus_contract::{
+    client::slash_v1::ConsensusSlashCallBuilder,
+    model::{
+        ConsensusProposalParamsV1, ConsensusSlashEvidenceV1, ConsensusSlashHeaderV1,
+        ConsensusSlashParamsV1, EPOCH_LENGTH,
+    },
+    ConsensusFunction,
+};
+use darkfi_sdk::{
+    crypto::{schnorr::SchnorrSecret, MerkleTree, SecretKey, CONSENSUS_CONTRACT_ID},
+    ContractCall,
+};
+use darkfi_serial::{deserialize, serialize, Encodable};
+use log::info;
+use rand::rngs::OsRng;
+
+use super::{Holder, TestHarness, TxAction};
+
+impl TestHarness {
+    /// Sign a block header for the given slot, the same way a block
+    /// producer does when proposing a block with the given proposal
+    /// transaction. Returns the slashing evidence and the proposal proof.
+    pub fn signed_header(
+        &self,
+        signature_secret: &SecretKey,
+        slot: u64,
+        timestamp: u64,
+        proposal_tx: &Transaction,
+    ) -> Result<(ConsensusSlashEvidenceV1, Proof)> {
+        // The proposed block carries no transactions
+        let root = MerkleTree::new(100).root(0).unwrap();
+        let header =
+            Header::new(self.genesis_block, slot / EPOCH_LENGTH, slot, Timestamp(timestamp), root);
+        let signature = signature_secret.sign(&mut OsRng, &header.headerhash().as_bytes()[..]);
+
+        let evidence_header = ConsensusSlashHeaderV1 {
+            version: header.version,
+            previous: header.previous,
+            epoch: header.epoch,
+            slot: header.slot,
+            timestamp: header.timestamp.0,
+            root: header.root,
+        };
+        assert_eq!(evidence_header.headerhash(), header.headerhash());
+
+        let proposal: ConsensusProposalParamsV1 = deserialize(&proposal_tx.calls[0].data[1..])?;
+        let proof = proposal_tx.proofs[0][0].clone();
+
+        Ok((ConsensusSlashEvidenceV1 { header: evidence_header, signature, proposal }, proof))
+    }
+
+    pub fn slash(
+        &mut self,
+        first: &(ConsensusSlashEvidenceV1, Proof),
+        second: &(ConsensusSlashEvidenceV1, Proof),
+    ) -> Result<(Transaction, ConsensusSlashParamsV1)> {
+        let tx_action_benchmark =
+            self.tx_action_benchmarks.get_mut(&TxAction::ConsensusSlash).unwrap();
+
+        let timer = Instant::now();
+
+        // Building Consensus::Slash params
+        let slash_call_debris = ConsensusSlashCallBuilder {
+            first: first.0.clone(),
+            first_proof: first.1.clone(),
+            second: second.0.clone(),
+            second_proof: second.1.clone(),
+        }
+        .build()?;
+        let (params, proofs) = (slash_call_debris.params, slash_call_debris.proofs);
+
+        // Slashing evidence carries its own signatures, so the
+        // transaction itself doesn't have to be signed.
+        let mut data = vec![ConsensusFunction::SlashV1 as u8];
+        params.encode(&mut data)?;
+        let call = ContractCall { contract_id: *CONSENSUS_CONTRACT_ID, data };
+
+        let calls = vec![call];
+        let proofs = vec![proofs];
+        let tx = Transaction { calls, proofs, signatures: vec![vec![]], access_list: None };
+        tx_action_benchmark.creation_times.push(timer.elapsed());
+
+        // Calculate transaction sizes
+        let encoded: Vec<u8> = serialize(&tx);
+        let size = std::mem::size_of_val(&*encoded);
+        tx_action_benchmark.sizes.push(size);
+        let base58 = bs58::encode(&encoded).into_string();
+        let size = std::mem::size_of_val(&*base58);
+        tx_action_benchmark.broadcasted_sizes.push(size);
+
+        Ok((tx, params))
+    }
+
+    pub async fn execute_slash_tx(
+        &mut self,
+        holder: &Holder,
+        tx: &Transaction,
+        slot: u64,
+    ) -> Result<()> {
+        let wallet = self.holders.get_mut(holder).unwrap();
+
+        let tx_action_benchmark =
+            self.tx_action_benchmarks.get_mut(&TxAction::ConsensusSlash).unwrap();
+
+        let timer = Instant::now();
+
+        wallet.validator.read().await.add_transactions(&[tx.clone()], slot, true).await?;
+        tx_action_benchmark.verify_times.push(timer.elapsed());
+
+        Ok(())
+    }
+
+    // Execute a slash transaction against the given double-signed headers
+    pub async fn execute_slash(
+        &mut self,
+        holders: &[Holder],
+        first: &(ConsensusSlashEvidenceV1, Proof),
+        second: &(ConsensusSlashEvidenceV1, Proof),
+        current_slot: u64,
+    ) -> Result<()> {
+        info!(target: "consensus", "[Faucet] =================");
+        info!(target: "consensus", "[Faucet] Building slash tx");
+        info!(target: "consensus", "[Faucet] =================");
+        let (slash_tx, _) = self.slash(first, second)?;
+
+        for h in holders {
+            info!(target: "consensus", "[{h:?}] ==================");
+            info!(target: "consensus", "[{h:?}] Executing slash tx");
+            info!(target: "consensus", "[{h:?}] ==================");
+            self.execute_slash_tx(h, &slash_tx, current_slot).await?;
+        }
+
+        self.assert_trees(holders);
+
+        Ok(())
+    }
+}
@@ -107,7 +107,7 @@ impl TestHarness {
 
         let calls = vec![money_call, consensus_call];
         let proofs = vec![money_stake_proofs, consensus_stake_proofs];
-        let mut stake_tx = Transaction { calls, proofs, signatures: vec![] };
+        let mut stake_tx = Transaction { calls, proofs, signatures: vec![], access_list: None };
         let money_sigs = stake_tx.create_sigs(&mut OsRng, &[money_stake_secret_key])?;
         let consensus_sigs = stake_tx.create_sigs(&mut OsRng, &[consensus_stake_secret_key])?;
         stake_tx.signatures = vec![money_sigs, consensus_sigs];
@@ -101,7 +101,7 @@ impl TestHarness {
 
         let calls = vec![consensus_call, money_call];
         let proofs = vec![consensus_unstake_proofs, money_unstake_proofs];
-        let mut unstake_tx = Transaction { calls, proofs, signatures: vec![] };
+        let mut unstake_tx = Transaction { calls, proofs, signatures: vec![], access_list: None };
         let consensus_sigs = unstake_tx.create_sigs(&mut OsRng, &[consensus_unstake_secret_key])?;
         let money_sigs = unstake_tx.create_sigs(&mut OsRng, &[consensus_unstake_secret_key])?;
         unstake_tx.signatures = vec![consensus_sigs, money_sigs];
@@ -86,7 +86,8 @@ impl TestHarness {
         let call = ContractCall { contract_id: *CONSENSUS_CONTRACT_ID, data };
         let calls = vec![call];
         let proofs = vec![unstake_request_proofs];
-        let mut unstake_request_tx = Transaction { calls, proofs, signatures: vec![] };
+        let mut unstake_request_tx =
+            Transaction { calls, proofs, signatures: vec![], access_list: None };
         let sigs =
             unstake_request_tx.create_sigs(&mut OsRng, &[unstake_request_signature_secret_key])?;
         unstake_request_tx.signatures = vec![sigs];
@@ -90,9 +90,11 @@ impl TestHarness {
             rcpt_spend_hook,
             rcpt_user_data,
             rcpt_user_data_blind,
+            rcpt_view_public: None,
             change_spend_hook,
             change_user_data,
             change_user_data_blind,
+            change_view_public: None,
             coins,
             tree,
             mint_zkbin: mint_zkbin.clone(),
@@ -144,6 +146,7 @@ impl TestHarness {
             calls: vec![xfer_call, exec_call],
             proofs: vec![xfer_debris.proofs, exec_proofs],
             signatures: vec![],
+            access_list: None,
         };
         let xfer_sigs = tx.create_sigs(&mut OsRng, &xfer_debris.signature_secrets)?;
         let exec_sigs = tx.create_sigs(&mut OsRng, &[exec_signature_secret])?;
@@ -50,7 +50,7 @@ impl TestHarness {
         params.encode(&mut data)?;
         let calls = vec![ContractCall { contract_id: *DAO_CONTRACT_ID, data }];
         let proofs = vec![proofs];
-        let mut tx = Transaction { calls, proofs, signatures: vec![] };
+        let mut tx = Transaction { calls, proofs, signatures: vec![], access_list: None };
         let sigs = tx.create_sigs(&mut OsRng, &[dao_kp.secret])?;
         tx.signatures = vec![sigs];
         tx_action_benchmark.creation_times.push(timer.elapsed());
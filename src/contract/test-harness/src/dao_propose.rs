@@ -21,7 +21,7 @@ use std::time::Instant;
 use darkfi::{tx::Transaction, Result};
 use darkfi_dao_contract::{
     client::{DaoInfo, DaoProposalInfo, DaoProposeCall, DaoProposeStakeInput},
-    model::{DaoBulla, DaoProposeParams},
+    model::{DaoBulla, DaoProposalAction, DaoProposeParams},
     DaoFunction, DAO_CONTRACT_ZKAS_DAO_PROPOSE_BURN_NS, DAO_CONTRACT_ZKAS_DAO_PROPOSE_MAIN_NS,
 };
 use darkfi_money_contract::client::OwnCoin;
@@ -78,6 +78,7 @@ impl TestHarness {
             dest: self.holders.get(recipient).unwrap().keypair.public,
             amount,
             token_id: tx_token_id,
+            action: DaoProposalAction::TreasuryTransfer,
             blind: pallas::Base::random(&mut OsRng),
         };
 
@@ -104,7 +105,7 @@ impl TestHarness {
         params.encode(&mut data)?;
         let calls = vec![ContractCall { contract_id: *DAO_CONTRACT_ID, data }];
         let proofs = vec![proofs];
-        let mut tx = Transaction { calls, proofs, signatures: vec![] };
+        let mut tx = Transaction { calls, proofs, signatures: vec![], access_list: None };
         let sigs = tx.create_sigs(&mut OsRng, &[signature_secret])?;
         tx.signatures = vec![sigs];
         tx_action_benchmark.creation_times.push(timer.elapsed());
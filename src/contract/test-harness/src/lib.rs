@@ -16,16 +16,16 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::{collections::HashMap, io::Cursor, time::Instant};
+use std::{collections::HashMap, io::Cursor, sync::Arc, time::Instant};
 
 use darkfi::{
-    blockchain::BlockInfo,
+    blockchain::{BlockInfo, MemoryBackend, StorageBackend},
     tx::Transaction,
     util::{
         pcg::Pcg32,
         time::{TimeKeeper, Timestamp},
     },
-    validator::{Validator, ValidatorConfig, ValidatorPtr},
+    validator::{policy::AllowAll, Validator, ValidatorConfig, ValidatorPtr},
     wallet::{WalletDb, WalletPtr},
     zk::{empty_witnesses, ProvingKey, ZkCircuit},
     zkas::ZkBinary,
@@ -53,8 +53,10 @@ use benchmarks::TxActionBenchmarks;
 pub mod vks;
 use vks::{read_or_gen_vks_and_pks, Vks};
 
+mod auth_capability;
 mod consensus_genesis_stake;
 mod consensus_proposal;
+mod consensus_slash;
 mod consensus_stake;
 mod consensus_unstake;
 mod consensus_unstake_request;
@@ -63,6 +65,7 @@ mod dao_mint;
 mod dao_propose;
 mod dao_vote;
 mod money_airdrop;
+mod money_fee;
 mod money_genesis_mint;
 mod money_otc_swap;
 mod money_token;
@@ -108,15 +111,19 @@ pub enum TxAction {
     MoneyGenesisMint,
     MoneyTransfer,
     MoneyOtcSwap,
+    MoneyFee,
     ConsensusGenesisStake,
     ConsensusStake,
     ConsensusProposal,
     ConsensusUnstakeRequest,
     ConsensusUnstake,
+    ConsensusSlash,
     DaoMint,
     DaoPropose,
     DaoVote,
     DaoExec,
+    AuthIssue,
+    AuthExercise,
 }
 
 pub struct Wallet {
@@ -144,7 +151,7 @@ impl Wallet {
         vks: &Vks,
     ) -> Result<Self> {
         let wallet = WalletDb::new(None, None)?;
-        let sled_db = sled::Config::new().temporary(true).open()?;
+        let sled_db = MemoryBackend.open()?;
 
         // Use pregenerated vks and get pregenerated pks
         vks::inject(&sled_db, vks)?;
@@ -159,6 +166,11 @@ impl Wallet {
             0,
             faucet_pubkeys.to_vec(),
             false,
+            false,
+            None,
+            None,
+            Arc::new(AllowAll),
+            false,
         );
         let validator = Validator::new(&sled_db, config).await?;
 
@@ -255,16 +267,20 @@ impl TestHarness {
         tx_action_benchmarks.insert(TxAction::MoneyGenesisMint, TxActionBenchmarks::default());
         tx_action_benchmarks.insert(TxAction::MoneyOtcSwap, TxActionBenchmarks::default());
         tx_action_benchmarks.insert(TxAction::MoneyTransfer, TxActionBenchmarks::default());
+        tx_action_benchmarks.insert(TxAction::MoneyFee, TxActionBenchmarks::default());
         tx_action_benchmarks.insert(TxAction::ConsensusGenesisStake, TxActionBenchmarks::default());
         tx_action_benchmarks.insert(TxAction::ConsensusStake, TxActionBenchmarks::default());
         tx_action_benchmarks.insert(TxAction::ConsensusProposal, TxActionBenchmarks::default());
         tx_action_benchmarks
             .insert(TxAction::ConsensusUnstakeRequest, TxActionBenchmarks::default());
         tx_action_benchmarks.insert(TxAction::ConsensusUnstake, TxActionBenchmarks::default());
+        tx_action_benchmarks.insert(TxAction::ConsensusSlash, TxActionBenchmarks::default());
         tx_action_benchmarks.insert(TxAction::DaoMint, TxActionBenchmarks::default());
         tx_action_benchmarks.insert(TxAction::DaoPropose, TxActionBenchmarks::default());
         tx_action_benchmarks.insert(TxAction::DaoVote, TxActionBenchmarks::default());
         tx_action_benchmarks.insert(TxAction::DaoExec, TxActionBenchmarks::default());
+        tx_action_benchmarks.insert(TxAction::AuthIssue, TxActionBenchmarks::default());
+        tx_action_benchmarks.insert(TxAction::AuthExercise, TxActionBenchmarks::default());
 
         Ok(Self {
             holders,
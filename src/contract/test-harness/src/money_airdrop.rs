@@ -67,9 +67,11 @@ impl TestHarness {
             rcpt_spend_hook: rcpt_spend_hook.unwrap_or(pallas::Base::ZERO),
             rcpt_user_data: rcpt_user_data.unwrap_or(pallas::Base::ZERO),
             rcpt_user_data_blind: pallas::Base::random(&mut OsRng),
+            rcpt_view_public: None,
             change_spend_hook: change_spend_hook.unwrap_or(pallas::Base::ZERO),
             change_user_data: change_user_data.unwrap_or(pallas::Base::ZERO),
             change_user_data_blind: pallas::Base::random(&mut OsRng),
+            change_view_public: None,
             coins: vec![],
             tree: faucet.money_merkle_tree.clone(),
             mint_zkbin: mint_zkbin.clone(),
@@ -85,7 +87,7 @@ impl TestHarness {
         debris.params.encode(&mut data)?;
         let calls = vec![ContractCall { contract_id: *MONEY_CONTRACT_ID, data }];
         let proofs = vec![debris.proofs];
-        let mut tx = Transaction { calls, proofs, signatures: vec![] };
+        let mut tx = Transaction { calls, proofs, signatures: vec![], access_list: None };
         let sigs = tx.create_sigs(&mut OsRng, &debris.signature_secrets)?;
         tx.signatures = vec![sigs];
         tx_action_benchmark.creation_times.push(timer.elapsed());
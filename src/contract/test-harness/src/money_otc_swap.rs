@@ -138,6 +138,7 @@ impl TestHarness {
             calls: vec![ContractCall { contract_id: *MONEY_CONTRACT_ID, data }],
             proofs: vec![swap_full_proofs],
             signatures: vec![],
+            access_list: None,
         };
         let sigs = tx.create_sigs(&mut OsRng, &[debris1.signature_secret])?;
         tx.signatures = vec![sigs];
@@ -42,6 +42,18 @@ impl TestHarness {
         recipient: &Holder,
         spend_hook: Option<pallas::Base>,
         user_data: Option<pallas::Base>,
+    ) -> Result<(Transaction, MoneyTokenMintParamsV1)> {
+        self.token_mint_capped(amount, holder, recipient, spend_hook, user_data, None)
+    }
+
+    pub fn token_mint_capped(
+        &mut self,
+        amount: u64,
+        holder: &Holder,
+        recipient: &Holder,
+        spend_hook: Option<pallas::Base>,
+        user_data: Option<pallas::Base>,
+        max_supply: Option<u64>,
     ) -> Result<(Transaction, MoneyTokenMintParamsV1)> {
         let wallet = self.holders.get(holder).unwrap();
         let mint_authority = wallet.token_mint_authority;
@@ -60,6 +72,7 @@ impl TestHarness {
             mint_authority,
             recipient: rcpt,
             amount,
+            max_supply,
             spend_hook: spend_hook.unwrap_or(pallas::Base::ZERO),
             user_data: user_data.unwrap_or(pallas::Base::ZERO),
             token_mint_zkbin: mint_zkbin.clone(),
@@ -72,7 +85,7 @@ impl TestHarness {
         debris.params.encode(&mut data)?;
         let calls = vec![ContractCall { contract_id: *MONEY_CONTRACT_ID, data }];
         let proofs = vec![debris.proofs];
-        let mut tx = Transaction { calls, proofs, signatures: vec![] };
+        let mut tx = Transaction { calls, proofs, signatures: vec![], access_list: None };
         let sigs = tx.create_sigs(&mut OsRng, &[mint_authority.secret])?;
         tx.signatures = vec![sigs];
         tx_action_benchmark.creation_times.push(timer.elapsed());
@@ -134,7 +147,7 @@ impl TestHarness {
         debris.params.encode(&mut data)?;
         let calls = vec![ContractCall { contract_id: *MONEY_CONTRACT_ID, data }];
         let proofs = vec![debris.proofs];
-        let mut tx = Transaction { calls, proofs, signatures: vec![] };
+        let mut tx = Transaction { calls, proofs, signatures: vec![], access_list: None };
         let sigs = tx.create_sigs(&mut OsRng, &[mint_authority.secret])?;
         tx.signatures = vec![sigs];
         tx_action_benchmark.creation_times.push(timer.elapsed());
@@ -36,10 +36,13 @@ use darkfi_dao_contract::{
 };
 use darkfi_deployooor_contract::DEPLOY_CONTRACT_ZKAS_DERIVE_NS_V1;
 use darkfi_money_contract::{
-    CONSENSUS_CONTRACT_ZKAS_BURN_NS_V1, CONSENSUS_CONTRACT_ZKAS_MINT_NS_V1,
-    CONSENSUS_CONTRACT_ZKAS_PROPOSAL_NS_V1, MONEY_CONTRACT_ZKAS_BURN_NS_V1,
-    MONEY_CONTRACT_ZKAS_MINT_NS_V1, MONEY_CONTRACT_ZKAS_TOKEN_FRZ_NS_V1,
-    MONEY_CONTRACT_ZKAS_TOKEN_MINT_NS_V1,
+    CONSENSUS_CONTRACT_ZKAS_BURN_NS_V1, CONSENSUS_CONTRACT_ZKAS_BURN_PI_V1,
+    CONSENSUS_CONTRACT_ZKAS_MINT_NS_V1, CONSENSUS_CONTRACT_ZKAS_MINT_PI_V1,
+    CONSENSUS_CONTRACT_ZKAS_PROPOSAL_NS_V1, CONSENSUS_CONTRACT_ZKAS_PROPOSAL_PI_V1,
+    MONEY_CONTRACT_ZKAS_BURN_NS_V1, MONEY_CONTRACT_ZKAS_BURN_PI_V1, MONEY_CONTRACT_ZKAS_MINT_NS_V1,
+    MONEY_CONTRACT_ZKAS_MINT_PI_V1, MONEY_CONTRACT_ZKAS_TOKEN_FRZ_NS_V1,
+    MONEY_CONTRACT_ZKAS_TOKEN_FRZ_PI_V1, MONEY_CONTRACT_ZKAS_TOKEN_MINT_NS_V1,
+    MONEY_CONTRACT_ZKAS_TOKEN_MINT_PI_V1,
 };
 use darkfi_sdk::crypto::{
     contract_id::DEPLOYOOOR_CONTRACT_ID, CONSENSUS_CONTRACT_ID, DAO_CONTRACT_ID, MONEY_CONTRACT_ID,
@@ -171,6 +174,21 @@ pub fn read_or_gen_vks_and_pks() -> Result<(Pks, Vks)> {
     Ok((pks, vks))
 }
 
+/// Number of public inputs a contract declares for the given circuit on
+/// deployment, for the contracts that ship such declarations.
+fn declared_public_inputs(namespace: &str) -> Option<u32> {
+    match namespace {
+        MONEY_CONTRACT_ZKAS_MINT_NS_V1 => Some(MONEY_CONTRACT_ZKAS_MINT_PI_V1),
+        MONEY_CONTRACT_ZKAS_BURN_NS_V1 => Some(MONEY_CONTRACT_ZKAS_BURN_PI_V1),
+        MONEY_CONTRACT_ZKAS_TOKEN_MINT_NS_V1 => Some(MONEY_CONTRACT_ZKAS_TOKEN_MINT_PI_V1),
+        MONEY_CONTRACT_ZKAS_TOKEN_FRZ_NS_V1 => Some(MONEY_CONTRACT_ZKAS_TOKEN_FRZ_PI_V1),
+        CONSENSUS_CONTRACT_ZKAS_MINT_NS_V1 => Some(CONSENSUS_CONTRACT_ZKAS_MINT_PI_V1),
+        CONSENSUS_CONTRACT_ZKAS_BURN_NS_V1 => Some(CONSENSUS_CONTRACT_ZKAS_BURN_PI_V1),
+        CONSENSUS_CONTRACT_ZKAS_PROPOSAL_NS_V1 => Some(CONSENSUS_CONTRACT_ZKAS_PROPOSAL_PI_V1),
+        _ => None,
+    }
+}
+
 pub fn inject(sled_db: &sled::Db, vks: &Vks) -> Result<()> {
     // Inject vks into the db
     let money_zkas_tree_ptr = MONEY_CONTRACT_ID.hash_state_id(SMART_CONTRACT_ZKAS_DB_NAME);
@@ -187,6 +205,19 @@ pub fn inject(sled_db: &sled::Db, vks: &Vks) -> Result<()> {
     let deployooor_zkas_tree = sled_db.open_tree(deployooor_zkas_tree_ptr)?;
 
     for (bincode, namespace, vk) in vks.iter() {
+        // Injecting skips deployment, so do the same public input check
+        // `zkas_db_set` would have done there.
+        if let Some(declared) = declared_public_inputs(namespace) {
+            let found = ZkBinary::decode(bincode)?.public_inputs_count();
+            assert!(
+                found == declared as usize,
+                "{} zkas circuit has {} public inputs, but its contract declares {}",
+                namespace,
+                found,
+                declared
+            );
+        }
+
         match namespace.as_str() {
             // Money circuits
             MONEY_CONTRACT_ZKAS_MINT_NS_V1 |
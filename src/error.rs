@@ -149,6 +149,9 @@ pub enum Error {
     #[error("Accept a new incoming connection from the listener {0} failed")]
     AcceptConnectionFailed(String),
 
+    #[error("File descriptors exhausted")]
+    FdExhausted,
+
     #[error("Accept a new tls connection from the listener {0} failed")]
     AcceptTlsConnectionFailed(String),
 
@@ -162,6 +165,9 @@ pub enum Error {
     #[error("Malformed packet")]
     MalformedPacket,
 
+    #[error("Packet payload too large for command {0}: {1} bytes")]
+    PacketTooLarge(String, u64),
+
     #[error("Socks proxy error: {0}")]
     SocksError(String),
 
@@ -184,6 +190,12 @@ pub enum Error {
     #[error("P2P network stopped")]
     P2PNetworkStopped,
 
+    #[error("Invalid P2P identity: {0}")]
+    InvalidIdentity(String),
+
+    #[error("Invalid P2P identity rotation")]
+    InvalidIdentityRotation,
+
     // =============
     // Crypto errors
     // =============
@@ -294,6 +306,9 @@ pub enum Error {
     #[error("Proposal contains more transactions than configured cap")]
     ProposalTxsExceedCapError,
 
+    #[error("Invalid block template: {0}")]
+    InvalidBlockTemplate(String),
+
     #[error("Unable to verify transfer transaction")]
     TransferTxVerification,
 
@@ -309,6 +324,9 @@ pub enum Error {
     #[error("Proposal task stopped")]
     ProposalTaskStopped,
 
+    #[error("Invalid checkpoint: {0}")]
+    CheckpointInvalid(String),
+
     // ===============
     // Database errors
     // ===============
@@ -348,6 +366,12 @@ pub enum Error {
     #[error("Block with order number {0} not found in database")]
     BlockNumberNotFound(u64),
 
+    #[error("MMR node {0} not found in database")]
+    MmrNodeNotFound(u64),
+
+    #[error("Leaf {0} is out of range for an MMR of {1} leaves")]
+    MmrLeafOutOfRange(u64, u64),
+
     #[error("Verifying slot missmatch")]
     VerifyingSlotMissmatch(),
 
@@ -372,6 +396,12 @@ pub enum Error {
     #[error("zkas bincode not found in sled database")]
     ZkasBincodeNotFound,
 
+    #[error("Invalid blockchain snapshot: {0}")]
+    SnapshotInvalid(String),
+
+    #[error("Invalid state set export: {0}")]
+    SetExportInvalid(String),
+
     // =============
     // Wallet errors
     // =============
@@ -384,6 +414,12 @@ pub enum Error {
     #[error("Wallet insufficient balance")]
     WalletInsufficientBalance,
 
+    #[error("Wallet migration failed: {0}")]
+    WalletMigrationFailed(String),
+
+    #[error("Invalid wallet SQL identifier: {0}")]
+    WalletInvalidIdentifier(String),
+
     // ===================
     // wasm runtime errors
     // ===================
@@ -412,8 +448,8 @@ pub enum Error {
     WasmerOomError(String),
 
     #[cfg(feature = "darkfi-sdk")]
-    #[error("Contract execution failed")]
-    ContractError(darkfi_sdk::error::ContractError),
+    #[error("Contract execution failed: {0}")]
+    ContractFailure(darkfi_sdk::error::ContractErrorReport),
 
     #[cfg(feature = "wasm-runtime")]
     #[error("contract wasm bincode not found")]
@@ -427,6 +463,18 @@ pub enum Error {
     #[error("contract execution error")]
     ContractExecError(u64),
 
+    #[cfg(feature = "wasm-runtime")]
+    #[error("contract accessed state outside of the transaction's access list")]
+    UndeclaredStateAccess,
+
+    #[cfg(feature = "wasm-runtime")]
+    #[error("Contract build metadata error: {0}")]
+    ContractBuildMetadata(String),
+
+    #[cfg(feature = "wasm-runtime")]
+    #[error("Contract build mismatch: {0}")]
+    ContractBuildMismatch(String),
+
     // ====================
     // Miscellaneous errors
     // ====================
@@ -546,11 +594,76 @@ pub enum TxVerifyFailed {
     #[error("Missing Money::Fee call in transaction")]
     MissingFee,
 
+    #[error("Transaction paid {0} in fees, but requires {1}")]
+    InsufficientFee(u64, u64),
+
     #[error("Invalid ZK proof in transaction")]
     InvalidZkProof,
 
     #[error("Erroneous transactions found")]
     ErroneousTxs(Vec<crate::tx::Transaction>),
+
+    #[error("Transaction rejected by policy: {0}")]
+    PolicyRejected(String),
+
+    #[error("Transaction {0} conflicts with pending transactions")]
+    PendingConflict(String),
+
+    #[error("Invalid contract deployment: {0}")]
+    InvalidDeployment(String),
+
+    #[cfg(feature = "wasm-runtime")]
+    #[error("Contract call {0} to {1} failed in {2}: {3}")]
+    ContractCallFailed(usize, String, CallPhase, darkfi_sdk::error::ContractErrorReport),
+
+    #[error("Contract call {0} to {1} failed in {2}: {3}")]
+    CallFailed(usize, String, CallPhase, String),
+}
+
+#[cfg(feature = "tx")]
+impl TxVerifyFailed {
+    /// Returns the index, contract ID and phase of the contract call that
+    /// failed, if the error can be pinned to a single call.
+    pub fn call_context(&self) -> Option<(usize, &str, CallPhase)> {
+        match self {
+            #[cfg(feature = "wasm-runtime")]
+            Self::ContractCallFailed(idx, contract_id, phase, _) => {
+                Some((*idx, contract_id, *phase))
+            }
+            Self::CallFailed(idx, contract_id, phase, _) => Some((*idx, contract_id, *phase)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "tx")]
+/// Phase of a contract call's verification in which it failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallPhase {
+    /// Executing the contract's `metadata` entrypoint
+    Metadata,
+    /// Executing the contract's `exec` entrypoint
+    Exec,
+    /// Executing the contract's `apply` entrypoint
+    Apply,
+    /// Verifying the call's signatures
+    Signature,
+    /// Verifying the call's ZK proofs
+    ZkProof,
+}
+
+#[cfg(feature = "tx")]
+impl std::fmt::Display for CallPhase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Metadata => "metadata",
+            Self::Exec => "exec",
+            Self::Apply => "apply",
+            Self::Signature => "sig",
+            Self::ZkProof => "zk",
+        };
+        write!(f, "{}", s)
+    }
 }
 
 /// Client module errors
@@ -576,6 +689,15 @@ pub enum ClientFailed {
 
     #[error("Verify error: {0}")]
     VerifyError(String),
+
+    #[error("Coin spent more than once: {0}")]
+    DuplicateCoin(String),
+
+    #[error("Amount overflow")]
+    AmountOverflow,
+
+    #[error("Merkle root still stale after {0} rebuilds")]
+    StaleMerkleRoot(usize),
 }
 
 #[cfg(feature = "rpc")]
@@ -778,6 +900,6 @@ impl From<wasmer::MemoryError> for Error {
 #[cfg(feature = "darkfi-sdk")]
 impl From<darkfi_sdk::error::ContractError> for Error {
     fn from(err: darkfi_sdk::error::ContractError) -> Self {
-        Self::ContractError(err)
+        Self::ContractFailure(darkfi_sdk::error::ContractErrorReport::from(&err))
     }
 }
@@ -16,14 +16,22 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::sync::Arc;
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
-use log::error;
-use smol::{lock::Mutex, Executor};
+use log::{error, warn};
+use smol::{lock::Mutex, Executor, Timer};
 use url::Url;
 
 use super::{
     channel::{Channel, ChannelPtr},
+    dnet::{self, DnetEvent},
+    p2p::P2pPtr,
     session::SessionWeakPtr,
     transport::{Listener, PtListener},
 };
@@ -32,6 +40,13 @@ use crate::{
     Error, Result,
 };
 
+/// Initial pause in accepting connections after running out of file descriptors
+const FD_BACKOFF_MIN: Duration = Duration::from_millis(100);
+/// Maximum pause in accepting connections after running out of file descriptors
+const FD_BACKOFF_MAX: Duration = Duration::from_secs(10);
+/// Number of inbound peers to shed each time we run out of file descriptors
+const FD_SHED_PEERS: usize = 4;
+
 /// Atomic pointer to Acceptor
 pub type AcceptorPtr = Arc<Acceptor>;
 
@@ -39,23 +54,65 @@ pub type AcceptorPtr = Arc<Acceptor>;
 pub struct Acceptor {
     channel_subscriber: SubscriberPtr<Result<ChannelPtr>>,
     task: StoppableTaskPtr,
+    /// Number of times accepting failed because we ran out of file descriptors
+    fd_exhaustions: AtomicU64,
     pub session: Mutex<Option<SessionWeakPtr>>,
 }
 
+/// File descriptor usage of the process
+#[derive(Clone, Debug)]
+pub struct FdUsage {
+    /// Number of open file descriptors
+    pub open: u64,
+    /// Soft limit of open file descriptors
+    pub limit: u64,
+}
+
+/// Returns the process' file descriptor usage.
+#[cfg(unix)]
+pub fn fd_usage() -> Option<FdUsage> {
+    let mut rlim = libc::rlimit { rlim_cur: 0, rlim_max: 0 };
+    // SAFETY: getrlimit only writes into the given struct
+    if unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut rlim) } != 0 {
+        return None
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    let fd_dir = "/proc/self/fd";
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    let fd_dir = "/dev/fd";
+
+    // Reading the directory holds one descriptor open itself
+    let open = std::fs::read_dir(fd_dir).ok()?.count().saturating_sub(1) as u64;
+
+    // rlim_t isn't u64 on every platform
+    #[allow(clippy::unnecessary_cast)]
+    let limit = rlim.rlim_cur as u64;
+
+    Some(FdUsage { open, limit })
+}
+
+/// Returns the process' file descriptor usage. Not available on this platform.
+#[cfg(not(unix))]
+pub fn fd_usage() -> Option<FdUsage> {
+    None
+}
+
 impl Acceptor {
     /// Create new Acceptor object.
     pub fn new(session: Mutex<Option<SessionWeakPtr>>) -> AcceptorPtr {
         Arc::new(Self {
             channel_subscriber: Subscriber::new(),
             task: StoppableTask::new(),
+            fd_exhaustions: AtomicU64::new(0),
             session,
         })
     }
 
     /// Start accepting inbound socket connections
     pub async fn start(self: Arc<Self>, endpoint: Url, ex: Arc<Executor<'_>>) -> Result<()> {
-        let listener = Listener::new(endpoint).await?.listen().await?;
-        self.accept(listener, ex);
+        let listener = Listener::new(endpoint.clone()).await?.listen().await?;
+        self.accept(endpoint, listener, ex);
         Ok(())
     }
 
@@ -70,27 +127,85 @@ impl Acceptor {
         self.channel_subscriber.clone().subscribe().await
     }
 
+    /// Returns the number of times accepting failed because we ran out of
+    /// file descriptors.
+    pub fn fd_exhaustions(&self) -> u64 {
+        self.fd_exhaustions.load(Ordering::Relaxed)
+    }
+
     /// Run the accept loop in a new thread and error if a connection problem occurs
-    fn accept(self: Arc<Self>, listener: Box<dyn PtListener>, ex: Arc<Executor<'_>>) {
+    fn accept(
+        self: Arc<Self>,
+        endpoint: Url,
+        listener: Box<dyn PtListener>,
+        ex: Arc<Executor<'_>>,
+    ) {
         let self_ = self.clone();
         self.task.clone().start(
-            self.run_accept_loop(listener),
+            self.run_accept_loop(endpoint, listener),
             |result| self_.handle_stop(result),
             Error::NetworkServiceStopped,
             ex,
         );
     }
 
-    /// Run the accept loop.
-    async fn run_accept_loop(self: Arc<Self>, listener: Box<dyn PtListener>) -> Result<()> {
+    /// Run the accept loop. When we run out of file descriptors, accepting
+    /// is paused with an exponential backoff and inbound peers get shed to
+    /// free some up, instead of spinning on the failing listener.
+    async fn run_accept_loop(
+        self: Arc<Self>,
+        endpoint: Url,
+        listener: Box<dyn PtListener>,
+    ) -> Result<()> {
+        let mut backoff = FD_BACKOFF_MIN;
+
         loop {
             match listener.next().await {
                 Ok((stream, url)) => {
+                    backoff = FD_BACKOFF_MIN;
                     let channel =
                         Channel::new(stream, url, self.session.lock().await.clone().unwrap()).await;
                     self.channel_subscriber.notify(Ok(channel)).await;
                 }
 
+                Err(Error::FdExhausted) => {
+                    let exhaustions = self.fd_exhaustions.fetch_add(1, Ordering::Relaxed) + 1;
+                    let fd_usage = fd_usage();
+                    warn!(
+                        target: "net::acceptor::run_accept_loop()",
+                        "[P2P] Acceptor on {} ran out of file descriptors ({:?}), pausing for {:?}",
+                        endpoint, fd_usage, backoff,
+                    );
+
+                    let shed = match self.p2p().await {
+                        Some(p2p) => {
+                            let shed = p2p.shed_inbound(FD_SHED_PEERS).await;
+                            if *p2p.dnet_enabled.lock().await {
+                                let event = dnet::AcceptorFdExhausted {
+                                    addr: endpoint.clone(),
+                                    fd_usage,
+                                    backoff,
+                                    shed,
+                                    exhaustions,
+                                };
+                                p2p.dnet_notify(DnetEvent::AcceptorFdExhausted(event)).await;
+                            }
+                            shed
+                        }
+                        None => 0,
+                    };
+
+                    if shed == 0 {
+                        warn!(
+                            target: "net::acceptor::run_accept_loop()",
+                            "[P2P] No inbound peers to shed, waiting for descriptors to free up",
+                        );
+                    }
+
+                    Timer::after(backoff).await;
+                    backoff = (backoff * 2).min(FD_BACKOFF_MAX);
+                }
+
                 Err(e) => {
                     error!(
                         target: "net::acceptor::run_accept_loop()",
@@ -101,6 +216,13 @@ impl Acceptor {
         }
     }
 
+    /// Returns the P2P instance owning this acceptor's session, if it's still alive.
+    async fn p2p(&self) -> Option<P2pPtr> {
+        let session = self.session.lock().await.clone()?;
+        let session = session.upgrade()?;
+        Some(session.p2p())
+    }
+
     /// Handles network errors. Panics if errors pass silently, otherwise broadcasts it
     /// to all channel subscribers.
     async fn handle_stop(self: Arc<Self>, result: Result<()>) {
@@ -16,10 +16,13 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::sync::Arc;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
 
 use darkfi_serial::{async_trait, serialize, SerialDecodable, SerialEncodable};
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 use rand::{rngs::OsRng, Rng};
 use smol::{
     io::{self, ReadHalf, WriteHalf},
@@ -34,6 +37,7 @@ use super::{
     message::Packet,
     message_subscriber::{MessageSubscription, MessageSubsystem},
     p2p::P2pPtr,
+    reputation::Violation,
     session::{Session, SessionBitFlag, SessionWeakPtr},
     transport::PtStream,
 };
@@ -75,6 +79,10 @@ pub struct Channel {
     stopped: Mutex<bool>,
     /// Weak pointer to respective session
     session: SessionWeakPtr,
+    /// Number of messages received over this channel
+    received: AtomicU64,
+    /// Names of the protocols attached to this channel
+    protocols: Mutex<Vec<&'static str>>,
     /// Channel debug info
     pub info: ChannelInfo,
 }
@@ -94,7 +102,12 @@ impl Channel {
         let reader = Mutex::new(reader);
         let writer = Mutex::new(writer);
 
-        let message_subsystem = MessageSubsystem::new();
+        let settings = session.upgrade().unwrap().p2p().settings();
+        let message_subsystem = MessageSubsystem::with_queues(
+            settings.message_queue_size,
+            settings.message_queue_sizes.clone(),
+            settings.message_queue_policy,
+        );
         Self::setup_dispatchers(&message_subsystem).await;
 
         let info = ChannelInfo::new(addr.clone());
@@ -107,6 +120,8 @@ impl Channel {
             receive_task: StoppableTask::new(),
             stopped: Mutex::new(false),
             session,
+            received: AtomicU64::new(0),
+            protocols: Mutex::new(vec![]),
             info,
         })
     }
@@ -119,6 +134,9 @@ impl Channel {
         subsystem.add_dispatch::<message::PongMessage>().await;
         subsystem.add_dispatch::<message::GetAddrsMessage>().await;
         subsystem.add_dispatch::<message::AddrsMessage>().await;
+        subsystem.add_dispatch::<message::IdentityChallengeMessage>().await;
+        subsystem.add_dispatch::<message::IdentityMessage>().await;
+        subsystem.add_dispatch::<message::IdentityRotationMessage>().await;
     }
 
     /// Starts the channel. Runs a receive loop to start receiving messages
@@ -211,7 +229,7 @@ impl Channel {
         dnetev!(self, SendMessage, {
             chan: self.info.clone(),
             cmd: packet.command.clone(),
-            time: NanoTimestamp::current_time(),
+            time: NanoTimestamp::monotonic(),
         });
 
         let stream = &mut *self.writer.lock().await;
@@ -238,7 +256,8 @@ impl Channel {
     }
 
     /// Handle network errors. Panic if error passes silently, otherwise
-    /// broadcast the error.
+    /// broadcast the error and stop the channel, so a dropped connection
+    /// tears down its protocols too.
     async fn handle_stop(self: Arc<Self>, result: Result<()>) {
         debug!(target: "net::channel::handle_stop()", "[START] address={}", self.address());
 
@@ -248,6 +267,8 @@ impl Channel {
             Err(e) => self.message_subsystem.trigger_error(e).await,
         }
 
+        self.stop().await;
+
         debug!(target: "net::channel::handle_stop()", "[END] address={}", self.address());
     }
 
@@ -260,7 +281,7 @@ impl Channel {
 
         // Run loop
         loop {
-            let packet = match message::read_packet(reader).await {
+            let packet = match message::read_packet(reader, &self.message_subsystem).await {
                 Ok(packet) => packet,
                 Err(err) => {
                     if Self::is_eof_error(&err) {
@@ -269,6 +290,12 @@ impl Channel {
                             "[P2P] Channel inbound connection {} disconnected",
                             self.address(),
                         );
+                    } else if let Error::PacketTooLarge(command, payload_len) = &err {
+                        warn!(
+                            target: "net::channel::main_receive_loop()",
+                            "[P2P] Channel {} sent a {} packet of {} bytes, over its limit",
+                            self.address(), command, payload_len,
+                        );
                     } else {
                         error!(
                             target: "net::channel::main_receive_loop()",
@@ -286,22 +313,48 @@ impl Channel {
                 }
             };
 
+            self.received.fetch_add(1, Ordering::Relaxed);
+
             dnetev!(self, RecvMessage, {
                 chan: self.info.clone(),
                 cmd: packet.command.clone(),
-                time: NanoTimestamp::current_time(),
+                time: NanoTimestamp::monotonic(),
             });
 
             // Send result to our subscribers
-            self.message_subsystem.notify(&packet.command, &packet.payload).await;
+            if !self.message_subsystem.notify(&packet.command, &packet.payload).await {
+                self.report_violation(Violation::InvalidMessage).await;
+            }
         }
     }
 
+    /// Report misbehaviour of the peer on this channel. Its penalty adds
+    /// to the peer's score, and the channel gets stopped if that bans it.
+    pub async fn report_violation(&self, violation: Violation) {
+        self.p2p().report_violation(self.address(), violation).await;
+    }
+
     /// Returns the local socket address
     pub fn address(&self) -> &Url {
         &self.info.addr
     }
 
+    /// Returns the number of messages received over this channel, used as
+    /// a measure of how useful the peer has been to us.
+    pub fn received_messages(&self) -> u64 {
+        self.received.load(Ordering::Relaxed)
+    }
+
+    /// Record the names of the protocols attached to this channel
+    pub(super) async fn set_protocols(&self, protocols: Vec<&'static str>) {
+        *self.protocols.lock().await = protocols;
+    }
+
+    /// Returns the names of the protocols attached to this channel
+    pub async fn protocols(&self) -> Vec<&'static str> {
+        self.protocols.lock().await.clone()
+    }
+
     /// Returns the inner [`MessageSubsystem`] reference
     pub fn message_subsystem(&self) -> &MessageSubsystem {
         &self.message_subsystem
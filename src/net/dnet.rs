@@ -16,9 +16,19 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use std::{
+    collections::{HashMap, VecDeque},
+    time::Duration,
+};
+
 use url::Url;
 
-use super::channel::ChannelInfo;
+use super::{
+    acceptor::FdUsage,
+    channel::ChannelInfo,
+    message_subscriber::MessageQueueStats,
+    session::{SessionBitFlag, SESSION_INBOUND, SESSION_MANUAL, SESSION_OUTBOUND, SESSION_SEED},
+};
 use crate::util::time::NanoTimestamp;
 
 macro_rules! dnetev {
@@ -63,6 +73,52 @@ pub struct OutboundDisconnected {
     pub err: String,
 }
 
+#[derive(Clone, Debug)]
+pub struct OutboundTransports {
+    /// Number of outbound slots using each transport
+    pub usage: HashMap<String, usize>,
+}
+
+#[derive(Clone, Debug)]
+pub struct AcceptorFdExhausted {
+    /// Address the acceptor listens on
+    pub addr: Url,
+    /// File descriptor usage of the process, if known on this platform
+    pub fd_usage: Option<FdUsage>,
+    /// How long accepting is paused for
+    pub backoff: Duration,
+    /// Number of inbound peers shed to free descriptors
+    pub shed: usize,
+    /// Number of times this acceptor ran out of descriptors so far
+    pub exhaustions: u64,
+}
+
+/// Protocol registration state of a single channel, as returned
+/// by [`P2p::dnet_protocols()`](super::p2p::P2p::dnet_protocols)
+#[derive(Clone, Debug)]
+pub struct ChannelProtocols {
+    pub chan: ChannelInfo,
+    /// Name of the session the channel belongs to
+    pub session: &'static str,
+    /// Message commands with a dispatcher on the channel
+    pub dispatchers: Vec<&'static str>,
+    /// Protocols attached to the channel
+    pub protocols: Vec<&'static str>,
+    /// Subscription queue metrics of the channel's dispatchers
+    pub queues: Vec<MessageQueueStats>,
+}
+
+/// Human readable name of a session type
+pub(super) fn session_name(session: SessionBitFlag) -> &'static str {
+    match session {
+        SESSION_INBOUND => "inbound",
+        SESSION_OUTBOUND => "outbound",
+        SESSION_MANUAL => "manual",
+        SESSION_SEED => "seed",
+        _ => "unknown",
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum DnetEvent {
     SendMessage(MessageInfo),
@@ -70,4 +126,108 @@ pub enum DnetEvent {
     OutboundConnecting(OutboundConnecting),
     OutboundConnected(OutboundConnected),
     OutboundDisconnected(OutboundDisconnected),
+    OutboundTransports(OutboundTransports),
+    AcceptorFdExhausted(AcceptorFdExhausted),
+}
+
+/// A dnet event recorded in the P2P event history
+#[derive(Clone, Debug)]
+pub struct DnetRecord {
+    /// Sequence number of the event, increasing with every record
+    pub seq: u64,
+    /// Time the event was recorded
+    pub time: NanoTimestamp,
+    /// The recorded event
+    pub event: DnetEvent,
+}
+
+/// Bounded ring buffer of recent dnet events, so debugging tools can
+/// retrieve the connection and message history from before they attached.
+pub struct DnetHistory {
+    /// Recorded events, oldest first
+    records: VecDeque<DnetRecord>,
+    /// Maximum number of kept events
+    capacity: usize,
+    /// Sequence number of the next recorded event
+    next_seq: u64,
+}
+
+impl DnetHistory {
+    /// Create a new history keeping at most `capacity` events.
+    /// Nothing is kept if `capacity` is 0.
+    pub fn new(capacity: usize) -> Self {
+        Self { records: VecDeque::new(), capacity, next_seq: 1 }
+    }
+
+    /// Record an event, evicting the oldest one if the history is full
+    pub fn push(&mut self, event: DnetEvent) {
+        if self.capacity == 0 {
+            return
+        }
+
+        if self.records.len() == self.capacity {
+            self.records.pop_front();
+        }
+
+        let record = DnetRecord { seq: self.next_seq, time: NanoTimestamp::monotonic(), event };
+        self.records.push_back(record);
+        self.next_seq += 1;
+    }
+
+    /// Retrieve at most `limit` events recorded after the sequence number
+    /// `since`, oldest first. Passing 0 starts from the oldest kept event.
+    pub fn since(&self, since: u64, limit: usize) -> Vec<DnetRecord> {
+        let Some(first) = self.records.front() else { return vec![] };
+
+        // Sequence numbers of kept events are contiguous
+        let skip = since.saturating_sub(first.seq - 1) as usize;
+        self.records.iter().skip(skip).take(limit).cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(slot: u32) -> DnetEvent {
+        DnetEvent::OutboundConnecting(OutboundConnecting {
+            slot,
+            addr: Url::parse("tcp://127.0.0.1:26661").unwrap(),
+        })
+    }
+
+    fn slots(records: &[DnetRecord]) -> Vec<u32> {
+        records
+            .iter()
+            .map(|r| match &r.event {
+                DnetEvent::OutboundConnecting(info) => info.slot,
+                _ => unreachable!(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn dnet_history_ring_buffer() {
+        let mut history = DnetHistory::new(3);
+        assert!(history.since(0, 10).is_empty());
+
+        for slot in 0..5 {
+            history.push(event(slot));
+        }
+
+        // Only the most recent events are kept
+        let records = history.since(0, 10);
+        assert_eq!(slots(&records), vec![2, 3, 4]);
+        assert_eq!(records.iter().map(|r| r.seq).collect::<Vec<_>>(), vec![3, 4, 5]);
+
+        // Resume after the last seen event
+        assert_eq!(slots(&history.since(3, 10)), vec![3, 4]);
+        assert_eq!(slots(&history.since(3, 1)), vec![3]);
+        assert!(history.since(5, 10).is_empty());
+
+        // A disabled history keeps nothing
+        let mut history = DnetHistory::new(0);
+        history.push(event(0));
+        assert!(history.since(0, 10).is_empty());
+    }
 }
@@ -18,11 +18,16 @@
 
 use std::{
     collections::{HashMap, HashSet},
+    net::IpAddr,
     sync::Arc,
 };
 
 use log::debug;
-use rand::{prelude::IteratorRandom, rngs::OsRng};
+use rand::{
+    prelude::{IteratorRandom, SliceRandom},
+    rngs::OsRng,
+    Rng,
+};
 use smol::lock::RwLock;
 use url::Url;
 
@@ -31,6 +36,108 @@ use super::settings::SettingsPtr;
 /// Atomic pointer to hosts object
 pub type HostsPtr = Arc<Hosts>;
 
+/// Network bucket an address belongs to. Outbound slots are spread across
+/// buckets, so an adversary controlling many addresses of a single network
+/// can't take over all of our outbound connections.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum NetBucket {
+    /// IPv4 /16 subnet
+    Ipv4([u8; 2]),
+    /// IPv6 /32 subnet
+    Ipv6([u8; 4]),
+    /// Onion address space, bucketed by the first character of the service ID
+    Onion(char),
+    /// Domain name, bucketed by its last two labels
+    Domain(String),
+}
+
+impl NetBucket {
+    /// Find the network bucket of the given address, if it has a host.
+    pub fn from_url(addr: &Url) -> Option<Self> {
+        let host = addr.host_str()?.to_lowercase();
+
+        let ip = host.trim_start_matches('[').trim_end_matches(']');
+        if let Ok(ip) = ip.parse::<IpAddr>() {
+            let ip = match ip {
+                IpAddr::V6(ip) => ip.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(IpAddr::V6(ip)),
+                ip => ip,
+            };
+
+            return match ip {
+                IpAddr::V4(ip) => {
+                    let octets = ip.octets();
+                    Some(Self::Ipv4([octets[0], octets[1]]))
+                }
+                IpAddr::V6(ip) => {
+                    let octets = ip.octets();
+                    Some(Self::Ipv6([octets[0], octets[1], octets[2], octets[3]]))
+                }
+            }
+        }
+
+        if let Some(service_id) = host.strip_suffix(".onion") {
+            return service_id.chars().next().map(Self::Onion)
+        }
+
+        let labels: Vec<&str> = host.rsplitn(3, '.').collect();
+        if labels.len() < 2 {
+            return Some(Self::Domain(host))
+        }
+
+        Some(Self::Domain(format!("{}.{}", labels[1], labels[0])))
+    }
+}
+
+/// Order `hosts` for outbound selection. Buckets are visited in random
+/// order, taking one random host of each per round, so flooding the hosts
+/// set with addresses of a single bucket doesn't make that bucket more
+/// likely to be picked. Hosts of buckets already used by `limit` outbound
+/// slots in `usage` are left out. A `limit` of 0 disables bucketing and
+/// just shuffles the hosts.
+pub fn diversify_hosts(
+    hosts: Vec<Url>,
+    usage: &HashMap<NetBucket, usize>,
+    limit: usize,
+    rng: &mut impl Rng,
+) -> Vec<Url> {
+    if limit == 0 {
+        let mut hosts = hosts;
+        hosts.shuffle(rng);
+        return hosts
+    }
+
+    let mut buckets: HashMap<Option<NetBucket>, Vec<Url>> = HashMap::new();
+    for host in hosts {
+        buckets.entry(NetBucket::from_url(&host)).or_default().push(host);
+    }
+
+    let mut buckets: Vec<Vec<Url>> = buckets
+        .into_iter()
+        .filter(|(bucket, _)| match bucket {
+            Some(bucket) => usage.get(bucket).copied().unwrap_or(0) < limit,
+            None => true,
+        })
+        .map(|(_, mut hosts)| {
+            hosts.shuffle(rng);
+            hosts
+        })
+        .collect();
+    buckets.shuffle(rng);
+
+    let mut ret = vec![];
+    let mut round = 0;
+    loop {
+        let picked: Vec<&Url> = buckets.iter().filter_map(|hosts| hosts.get(round)).collect();
+        if picked.is_empty() {
+            break
+        }
+        ret.extend(picked.into_iter().cloned());
+        round += 1;
+    }
+
+    ret
+}
+
 /// Manages a store of network addresses
 pub struct Hosts {
     /// Set of stored addresses
@@ -245,6 +352,8 @@ impl Hosts {
 
 #[cfg(test)]
 mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
     use super::{super::settings::Settings, *};
 
     #[test]
@@ -328,4 +437,60 @@ mod tests {
             assert!(!hosts.contains(&remote_hosts[2]).await);
         });
     }
+
+    #[test]
+    fn test_net_bucket() {
+        let bucket = |addr: &str| NetBucket::from_url(&Url::parse(addr).unwrap()).unwrap();
+
+        assert_eq!(bucket("tcp://1.2.3.4:80"), NetBucket::Ipv4([1, 2]));
+        assert_eq!(bucket("tcp+tls://1.2.200.1:80"), bucket("tcp://1.2.3.4:81"));
+        assert_ne!(bucket("tcp://1.2.3.4:80"), bucket("tcp://1.3.3.4:80"));
+        assert_eq!(bucket("tcp://[::ffff:1.2.3.4]:80"), NetBucket::Ipv4([1, 2]));
+        assert_eq!(
+            bucket("tcp://[2001:db8:aaaa::1]:80"),
+            NetBucket::Ipv6([0x20, 0x01, 0x0d, 0xb8])
+        );
+        assert_eq!(bucket("tor://abcdefgh.onion:80"), NetBucket::Onion('a'));
+        assert_eq!(bucket("tcp://node.dark.fi:80"), NetBucket::Domain("dark.fi".to_string()));
+        assert_eq!(bucket("tcp://dark.fi:80"), bucket("tcp://other.dark.fi:80"));
+    }
+
+    #[test]
+    fn test_address_flooding() {
+        let mut rng = StdRng::seed_from_u64(42);
+
+        // An adversary floods us with addresses from a single /16
+        let mut hosts: Vec<Url> = (0..1000)
+            .map(|i| Url::parse(&format!("tcp://6.6.{}.{}:8340", i / 250, i % 250)).unwrap())
+            .collect();
+        let honest: Vec<Url> = (1..=8)
+            .map(|i| Url::parse(&format!("tcp://{}.{}.1.1:8340", 10 + i, i)).unwrap())
+            .collect();
+        hosts.extend(honest.iter().cloned());
+        let flooded = NetBucket::Ipv4([6, 6]);
+
+        // Without bucketing, the flooded subnet takes nearly every slot
+        let order = diversify_hosts(hosts.clone(), &HashMap::new(), 0, &mut rng);
+        assert_eq!(order.len(), hosts.len());
+        let flooded_picks =
+            order[..8].iter().filter(|h| NetBucket::from_url(h) == Some(flooded.clone())).count();
+        assert!(flooded_picks > 6);
+
+        // Fill 8 outbound slots, respecting a limit of 2 slots per bucket
+        let mut usage = HashMap::new();
+        for _ in 0..8 {
+            let order = diversify_hosts(hosts.clone(), &usage, 2, &mut rng);
+            let bucket = NetBucket::from_url(&order[0]).unwrap();
+            *usage.entry(bucket).or_insert(0) += 1;
+        }
+        assert!(usage.get(&flooded).copied().unwrap_or(0) <= 2);
+        assert!(usage.values().all(|count| *count <= 2));
+        assert!(usage.len() >= 4);
+
+        // Once the flooded bucket is at its limit, none of its hosts are offered
+        let usage = HashMap::from([(flooded.clone(), 2)]);
+        let order = diversify_hosts(hosts, &usage, 2, &mut rng);
+        assert_eq!(order.len(), honest.len());
+        assert!(order.iter().all(|h| honest.contains(h)));
+    }
 }
@@ -0,0 +1,441 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use darkfi_serial::{deserialize, serialize, SerialDecodable, SerialEncodable};
+use ed25519_compact::{KeyPair, PublicKey, Signature};
+use url::Url;
+
+use crate::{util::time::Timestamp, Error, Result};
+
+/// Raw ed25519 public key identifying a node
+pub type IdentityKey = [u8; 32];
+
+/// Random per-channel challenge a peer has to sign with its identity key
+pub type IdentityChallenge = [u8; 32];
+
+/// Domain separator for identity challenge signatures
+const CHALLENGE_DOMAIN: &[u8] = b"darkfi:p2p:identity:challenge";
+
+/// Message signed in response to the given challenge
+fn challenge_message(challenge: &IdentityChallenge) -> Vec<u8> {
+    let mut message = CHALLENGE_DOMAIN.to_vec();
+    message.extend_from_slice(challenge);
+    message
+}
+
+/// Verify a peer's signature of the challenge we sent it, proving it
+/// holds the private key of the identity it announces.
+pub fn verify_challenge(
+    key: &IdentityKey,
+    challenge: &IdentityChallenge,
+    signature: &[u8],
+) -> bool {
+    let Ok(key) = PublicKey::from_slice(key) else { return false };
+    let Ok(signature) = Signature::from_slice(signature) else { return false };
+    key.verify(challenge_message(challenge), &signature).is_ok()
+}
+
+/// Signed transition from an old identity key to a new one. The old key
+/// vouches for the new one, and the new key proves it is held by the same
+/// node. The old key remains valid until `grace_until`, so peers can keep
+/// recognizing the node while the transition propagates.
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct IdentityRotation {
+    /// Identity key being retired
+    pub old_key: IdentityKey,
+    /// Identity key replacing it
+    pub new_key: IdentityKey,
+    /// Timestamp of the rotation
+    pub timestamp: u64,
+    /// Timestamp until which the old key remains valid
+    pub grace_until: u64,
+    /// Signature of the transition by the old key
+    pub old_signature: Vec<u8>,
+    /// Signature of the transition by the new key
+    pub new_signature: Vec<u8>,
+}
+
+impl IdentityRotation {
+    /// Message signed by both keys
+    fn message(&self) -> Vec<u8> {
+        serialize(&(self.old_key, self.new_key, self.timestamp, self.grace_until))
+    }
+
+    /// Verify both signatures of the transition.
+    pub fn verify(&self) -> bool {
+        let message = self.message();
+        for (key, signature) in
+            [(&self.old_key, &self.old_signature), (&self.new_key, &self.new_signature)]
+        {
+            let Ok(key) = PublicKey::from_slice(key) else { return false };
+            let Ok(signature) = Signature::from_slice(signature) else { return false };
+            if key.verify(&message, &signature).is_err() {
+                return false
+            }
+        }
+
+        true
+    }
+}
+
+/// P2P identity of this node, along with the history of its rotations.
+#[derive(Clone, SerialEncodable, SerialDecodable)]
+pub struct Identity {
+    /// Current ed25519 keypair
+    keypair: [u8; 64],
+    /// Past rotations, oldest first
+    pub history: Vec<IdentityRotation>,
+}
+
+impl Identity {
+    /// Generate a fresh identity.
+    pub fn generate() -> Self {
+        Self { keypair: *KeyPair::generate(), history: vec![] }
+    }
+
+    /// Load the identity stored at the given path, or generate and store
+    /// a new one if the file doesn't exist.
+    pub fn load_or_generate(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            let identity = Self::generate();
+            identity.save(path)?;
+            return Ok(identity)
+        }
+
+        let identity: Self = deserialize(&fs::read(path)?)?;
+        identity.keys()?;
+        Ok(identity)
+    }
+
+    /// Persist the identity, along with its rotation history. The file
+    /// holds the private key, so it is only readable by its owner. It is
+    /// written to a temporary file first and then moved in place, so a
+    /// failed write never leaves a truncated identity behind.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut tmp_path = path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_path);
+
+        // Start from a fresh file, as the mode is only applied on creation
+        if tmp_path.exists() {
+            fs::remove_file(&tmp_path)?;
+        }
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+
+        let mut file = options.open(&tmp_path)?;
+        file.write_all(&serialize(self))?;
+        file.sync_all()?;
+        drop(file);
+
+        fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+
+    /// Current keypair, erroring if it got corrupted
+    fn keys(&self) -> Result<KeyPair> {
+        let Ok(keys) = KeyPair::from_slice(&self.keypair) else {
+            return Err(Error::InvalidIdentity("Invalid identity keypair".to_string()))
+        };
+
+        // The public key is recomputed, so this also catches a corrupted secret key
+        if keys.sk.validate_public_key(&keys.pk).is_err() {
+            return Err(Error::InvalidIdentity("Identity keypair mismatch".to_string()))
+        }
+
+        Ok(keys)
+    }
+
+    /// Current identity key
+    pub fn key(&self) -> Result<IdentityKey> {
+        Ok(*self.keys()?.pk)
+    }
+
+    /// Sign a challenge received from a peer with the current key.
+    pub fn sign_challenge(&self, challenge: &IdentityChallenge) -> Result<Vec<u8>> {
+        Ok(self.keys()?.sk.sign(challenge_message(challenge), None).to_vec())
+    }
+
+    /// Last rotation, if the retired key is still within its grace period
+    pub fn pending_rotation(&self) -> Option<&IdentityRotation> {
+        let rotation = self.history.last()?;
+        (rotation.grace_until > Timestamp::current_time().0).then_some(rotation)
+    }
+
+    /// Keys currently valid for this node: the current one, and the
+    /// previous one while it is within its grace period.
+    pub fn valid_keys(&self) -> Result<Vec<IdentityKey>> {
+        let mut keys = vec![self.key()?];
+        if let Some(rotation) = self.pending_rotation() {
+            keys.push(rotation.old_key);
+        }
+        Ok(keys)
+    }
+
+    /// Replace the identity key with a freshly generated one, keeping the
+    /// old one valid for `grace_period` seconds. Returns the signed
+    /// transition to be announced to peers.
+    pub fn rotate(&mut self, grace_period: u64) -> Result<IdentityRotation> {
+        let old = self.keys()?;
+        let new = KeyPair::generate();
+
+        let timestamp = Timestamp::current_time().0;
+        let mut rotation = IdentityRotation {
+            old_key: *old.pk,
+            new_key: *new.pk,
+            timestamp,
+            grace_until: timestamp + grace_period,
+            old_signature: vec![],
+            new_signature: vec![],
+        };
+        let message = rotation.message();
+        rotation.old_signature = old.sk.sign(&message, None).to_vec();
+        rotation.new_signature = new.sk.sign(&message, None).to_vec();
+
+        self.keypair = *new;
+        self.history.push(rotation.clone());
+
+        Ok(rotation)
+    }
+}
+
+/// Identity known for a connected peer
+#[derive(Clone, Debug)]
+pub struct PeerIdentity {
+    /// Current identity key of the peer
+    pub key: IdentityKey,
+    /// Previous identity key, along with the timestamp it is valid until
+    pub previous: Option<(IdentityKey, u64)>,
+}
+
+impl PeerIdentity {
+    /// Check if the given key is valid for the peer, i.e. it is the current
+    /// key, or the previous one within its grace period.
+    pub fn is_valid(&self, key: &IdentityKey) -> bool {
+        if &self.key == key {
+            return true
+        }
+
+        match self.previous {
+            Some((previous, grace_until)) => {
+                &previous == key && grace_until > Timestamp::current_time().0
+            }
+            None => false,
+        }
+    }
+}
+
+/// Identities of connected peers, by channel address
+#[derive(Default)]
+pub struct PeerIdentities {
+    peers: HashMap<Url, PeerIdentity>,
+}
+
+impl PeerIdentities {
+    pub fn get(&self, addr: &Url) -> Option<&PeerIdentity> {
+        self.peers.get(addr)
+    }
+
+    /// Record the identity announced by a peer, which must have signed
+    /// the challenge we sent on this channel with the announced key. Only
+    /// one identity is accepted per channel, later changes have to go
+    /// through rotations. If the identity comes along with a rotation
+    /// still within its grace period, the old key is also retained.
+    pub fn insert(
+        &mut self,
+        addr: Url,
+        key: IdentityKey,
+        rotation: Option<&IdentityRotation>,
+        challenge: &IdentityChallenge,
+        signature: &[u8],
+    ) -> Result<()> {
+        if self.peers.contains_key(&addr) {
+            return Err(Error::InvalidIdentity("Identity already announced".to_string()))
+        }
+
+        if !verify_challenge(&key, challenge, signature) {
+            return Err(Error::InvalidIdentity("Challenge signature mismatch".to_string()))
+        }
+
+        let previous = match rotation {
+            Some(rotation) => {
+                if rotation.new_key != key || !rotation.verify() {
+                    return Err(Error::InvalidIdentityRotation)
+                }
+                Some((rotation.old_key, rotation.grace_until))
+            }
+            None => None,
+        };
+
+        self.peers.insert(addr, PeerIdentity { key, previous });
+        Ok(())
+    }
+
+    /// Apply a rotation announced by a peer. The rotation must be signed
+    /// by both keys, and the old key must be the peer's current one.
+    pub fn rotate(&mut self, addr: &Url, rotation: &IdentityRotation) -> Result<()> {
+        let Some(peer) = self.peers.get_mut(addr) else {
+            return Err(Error::InvalidIdentityRotation)
+        };
+
+        if peer.key != rotation.old_key || !rotation.verify() {
+            return Err(Error::InvalidIdentityRotation)
+        }
+
+        peer.previous = Some((rotation.old_key, rotation.grace_until));
+        peer.key = rotation.new_key;
+        Ok(())
+    }
+
+    pub fn remove(&mut self, addr: &Url) {
+        self.peers.remove(addr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identity_rotation() {
+        let mut path = std::env::temp_dir();
+        path.push("darkfi_test_identity");
+        let _ = fs::remove_file(&path);
+
+        let mut identity = Identity::load_or_generate(&path).unwrap();
+        let old_key = identity.key().unwrap();
+        let addr = Url::parse("tcp://127.0.0.1:1234").unwrap();
+
+        // The identity file holds the private key
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let mut peers = PeerIdentities::default();
+        let challenge = [7u8; 32];
+        let signature = identity.sign_challenge(&challenge).unwrap();
+        peers.insert(addr.clone(), old_key, None, &challenge, &signature).unwrap();
+
+        let rotation = identity.rotate(3600).unwrap();
+        assert!(rotation.verify());
+        assert_eq!(identity.valid_keys().unwrap(), vec![rotation.new_key, old_key]);
+        identity.save(&path).unwrap();
+
+        // The rotation history is persisted
+        let loaded = Identity::load_or_generate(&path).unwrap();
+        assert_eq!(loaded.key().unwrap(), rotation.new_key);
+        assert_eq!(loaded.history.len(), 1);
+
+        // Saving replaces the file, still only readable by its owner
+        loaded.save(&path).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        assert_eq!(Identity::load_or_generate(&path).unwrap().key().unwrap(), rotation.new_key);
+
+        // A corrupted keypair is an error rather than a panic
+        let mut corrupted = loaded.clone();
+        corrupted.keypair = [0u8; 64];
+        assert!(corrupted.key().is_err());
+        assert!(corrupted.sign_challenge(&challenge).is_err());
+        corrupted.save(&path).unwrap();
+        assert!(Identity::load_or_generate(&path).is_err());
+        loaded.save(&path).unwrap();
+
+        // Tampered rotations are rejected
+        let mut forged = rotation.clone();
+        forged.grace_until += 1;
+        assert!(!forged.verify());
+        assert!(peers.rotate(&addr, &forged).is_err());
+
+        // Both keys are valid during the grace period
+        peers.rotate(&addr, &rotation).unwrap();
+        let peer = peers.get(&addr).unwrap();
+        assert!(peer.is_valid(&rotation.new_key));
+        assert!(peer.is_valid(&old_key));
+
+        // A rotation must start from the peer's current key
+        assert!(peers.rotate(&addr, &rotation).is_err());
+
+        // Once the grace period is over, only the new key is valid
+        let mut identity = loaded;
+        let expired = identity.rotate(0).unwrap();
+        assert!(identity.pending_rotation().is_none());
+        peers.rotate(&addr, &expired).unwrap();
+        let peer = peers.get(&addr).unwrap();
+        assert!(peer.is_valid(&expired.new_key));
+        assert!(!peer.is_valid(&expired.old_key));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_identity_challenge() {
+        let identity = Identity::generate();
+        let other = Identity::generate();
+        let addr = Url::parse("tcp://127.0.0.1:1234").unwrap();
+        let challenge = [1u8; 32];
+        let mut peers = PeerIdentities::default();
+
+        // Claiming a key without holding it is rejected
+        let signature = other.sign_challenge(&challenge).unwrap();
+        assert!(peers
+            .insert(addr.clone(), identity.key().unwrap(), None, &challenge, &signature)
+            .is_err());
+
+        // A signature of another channel's challenge is rejected
+        let signature = identity.sign_challenge(&[2u8; 32]).unwrap();
+        assert!(peers
+            .insert(addr.clone(), identity.key().unwrap(), None, &challenge, &signature)
+            .is_err());
+
+        let signature = identity.sign_challenge(&challenge).unwrap();
+        assert!(verify_challenge(&identity.key().unwrap(), &challenge, &signature));
+        peers.insert(addr.clone(), identity.key().unwrap(), None, &challenge, &signature).unwrap();
+
+        // Only one identity is accepted per channel
+        let signature = other.sign_challenge(&challenge).unwrap();
+        assert!(peers
+            .insert(addr.clone(), other.key().unwrap(), None, &challenge, &signature)
+            .is_err());
+        assert_eq!(peers.get(&addr).unwrap().key, identity.key().unwrap());
+    }
+}
@@ -24,13 +24,27 @@ use log::trace;
 use smol::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use url::Url;
 
+use super::{
+    identity::{IdentityChallenge, IdentityKey, IdentityRotation},
+    message_subscriber::MessageSubsystem,
+};
 use crate::{Error, Result};
 
 const MAGIC_BYTES: [u8; 4] = [0xd9, 0xef, 0xb6, 0x7d];
 
+/// Maximum length of a packet command, in bytes
+const MAX_COMMAND_LENGTH: u64 = 64;
+
+/// Default maximum payload size of a message, in bytes. Also applies to
+/// packets whose command has no registered dispatcher.
+pub const DEFAULT_MAX_PAYLOAD_SIZE: u64 = 8 * 1024 * 1024;
+
 /// Generic message template.
 pub trait Message: 'static + Send + Sync + Encodable + Decodable {
     const NAME: &'static str;
+    /// Maximum payload size accepted for this message, in bytes. Packets
+    /// announcing a larger payload are rejected before it is read.
+    const MAX_PAYLOAD_SIZE: u64 = DEFAULT_MAX_PAYLOAD_SIZE;
 }
 
 #[macro_export]
@@ -40,6 +54,12 @@ macro_rules! impl_p2p_message {
             const NAME: &'static str = $nm;
         }
     };
+    ($st:ty, $nm:expr, $max:expr) => {
+        impl Message for $st {
+            const NAME: &'static str = $nm;
+            const MAX_PAYLOAD_SIZE: u64 = $max;
+        }
+    };
 }
 
 /// Outbound keepalive message.
@@ -47,14 +67,14 @@ macro_rules! impl_p2p_message {
 pub struct PingMessage {
     pub nonce: u16,
 }
-impl_p2p_message!(PingMessage, "ping");
+impl_p2p_message!(PingMessage, "ping", 16);
 
 /// Inbound keepalive message.
 #[derive(Debug, Copy, Clone, SerialEncodable, SerialDecodable)]
 pub struct PongMessage {
     pub nonce: u16,
 }
-impl_p2p_message!(PongMessage, "pong");
+impl_p2p_message!(PongMessage, "pong", 16);
 
 /// Requests address of outbound connecction.
 #[derive(Debug, Copy, Clone, SerialEncodable, SerialDecodable)]
@@ -62,7 +82,7 @@ pub struct GetAddrsMessage {
     /// Maximum number of addresses to receive
     pub max: u32,
 }
-impl_p2p_message!(GetAddrsMessage, "getaddr");
+impl_p2p_message!(GetAddrsMessage, "getaddr", 16);
 
 /// Sends address information to inbound connection.
 /// Response to `GetAddrsMessage`.
@@ -70,7 +90,7 @@ impl_p2p_message!(GetAddrsMessage, "getaddr");
 pub struct AddrsMessage {
     pub addrs: Vec<Url>,
 }
-impl_p2p_message!(AddrsMessage, "addr");
+impl_p2p_message!(AddrsMessage, "addr", 1024 * 1024);
 
 /// Requests version information of outbound connection.
 #[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
@@ -78,7 +98,7 @@ pub struct VersionMessage {
     /// Only used for debugging. Compromises privacy when set.
     pub node_id: String,
 }
-impl_p2p_message!(VersionMessage, "version");
+impl_p2p_message!(VersionMessage, "version", 1024);
 
 /// Sends version information to inbound connection.
 /// Response to `VersionMessage`.
@@ -87,7 +107,32 @@ pub struct VerackMessage {
     /// App version
     pub app_version: semver::Version,
 }
-impl_p2p_message!(VerackMessage, "verack");
+impl_p2p_message!(VerackMessage, "verack", 1024);
+
+/// Random challenge sent on connection, which the peer signs with its
+/// identity key to prove it holds it.
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct IdentityChallengeMessage {
+    pub challenge: IdentityChallenge,
+}
+impl_p2p_message!(IdentityChallengeMessage, "identitychallenge", 64);
+
+/// Announces the node's P2P identity key in response to the peer's
+/// challenge, along with its last rotation while the previous key is
+/// still within its grace period.
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct IdentityMessage {
+    pub key: IdentityKey,
+    pub rotation: Option<IdentityRotation>,
+    /// Signature of the peer's challenge by `key`
+    pub signature: Vec<u8>,
+}
+impl_p2p_message!(IdentityMessage, "identity", 1024);
+
+/// Announces a signed transition to a new P2P identity key.
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct IdentityRotationMessage(pub IdentityRotation);
+impl_p2p_message!(IdentityRotationMessage, "identityrotation", 1024);
 
 /// Packets are the base type read from the network.
 /// Converted to messages and passed to event loop.
@@ -98,8 +143,13 @@ pub struct Packet {
 }
 
 /// Reads and decodes an inbound payload from the given async stream.
+/// The announced payload length is checked against the maximum payload
+/// size registered for the command in `subsystem` before reading it.
 /// Returns decoded [`Packet`].
-pub async fn read_packet<R: AsyncRead + Unpin + Send + Sized>(stream: &mut R) -> Result<Packet> {
+pub async fn read_packet<R: AsyncRead + Unpin + Send + Sized>(
+    stream: &mut R,
+    subsystem: &MessageSubsystem,
+) -> Result<Packet> {
     // Packets should have a 4 byte header of magic digits.
     // This is used for network debugging.
     let mut magic = [0u8; 4];
@@ -113,15 +163,28 @@ pub async fn read_packet<R: AsyncRead + Unpin + Send + Sized>(stream: &mut R) ->
     }
 
     // The type of the message.
-    let command_len = VarInt::decode_async(stream).await?.0 as usize;
-    let mut cmd = vec![0u8; command_len];
+    let command_len = VarInt::decode_async(stream).await?.0;
+    if command_len > MAX_COMMAND_LENGTH {
+        trace!(target: "net::message", "Error: Command length {} too large", command_len);
+        return Err(Error::MalformedPacket)
+    }
+    let mut cmd = vec![0u8; command_len as usize];
     stream.read_exact(&mut cmd).await?;
     let command = String::from_utf8(cmd)?;
     trace!(target: "net::message", "Read command: {}", command);
 
     // The message-dependent data (see message types)
-    let payload_len = VarInt::decode_async(stream).await?.0 as usize;
-    let mut payload = vec![0u8; payload_len];
+    let payload_len = VarInt::decode_async(stream).await?.0;
+    let max_payload_size = subsystem.max_payload_size(&command).await;
+    if payload_len > max_payload_size {
+        trace!(
+            target: "net::message",
+            "Error: Payload of {} bytes exceeds the {} bytes limit",
+            payload_len, max_payload_size,
+        );
+        return Err(Error::PacketTooLarge(command, payload_len))
+    }
+    let mut payload = vec![0u8; payload_len as usize];
     stream.read_exact(&mut payload).await?;
     trace!(target: "net::message", "Read payload {} bytes", payload_len);
 
@@ -162,3 +225,62 @@ pub async fn send_packet<W: AsyncWrite + Unpin + Send + Sized>(
 
     Ok(written)
 }
+
+#[cfg(test)]
+mod tests {
+    use darkfi_serial::serialize;
+    use smol::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn test_read_packet_limits() {
+        smol::block_on(async {
+            let subsystem = MessageSubsystem::new();
+            subsystem.add_dispatch::<PingMessage>().await;
+            subsystem.add_dispatch_with_limit::<AddrsMessage>(32).await;
+
+            // Packets within their limit are read
+            let ping = serialize(&PingMessage { nonce: 42 });
+            let mut stream = Cursor::new(vec![]);
+            let packet = Packet { command: PingMessage::NAME.to_string(), payload: ping.clone() };
+            send_packet(&mut stream, packet).await.unwrap();
+            stream.set_position(0);
+            let packet = read_packet(&mut stream, &subsystem).await.unwrap();
+            assert_eq!(packet.command, PingMessage::NAME);
+            assert_eq!(packet.payload, ping);
+
+            // Limits configured on registration are enforced
+            let addrs = AddrsMessage { addrs: vec![Url::parse("tcp://dark.fi:8342").unwrap(); 4] };
+            let mut stream = Cursor::new(vec![]);
+            let packet =
+                Packet { command: AddrsMessage::NAME.to_string(), payload: serialize(&addrs) };
+            send_packet(&mut stream, packet).await.unwrap();
+            stream.set_position(0);
+            let res = read_packet(&mut stream, &subsystem).await;
+            assert!(matches!(res, Err(Error::PacketTooLarge(cmd, _)) if cmd == AddrsMessage::NAME));
+
+            // Absurd length prefixes are rejected before reading the payload
+            let mut data = MAGIC_BYTES.to_vec();
+            VarInt(PingMessage::NAME.len() as u64).encode(&mut data).unwrap();
+            data.extend_from_slice(PingMessage::NAME.as_bytes());
+            VarInt(u64::MAX).encode(&mut data).unwrap();
+            let res = read_packet(&mut Cursor::new(data), &subsystem).await;
+            assert!(matches!(res, Err(Error::PacketTooLarge(_, u64::MAX))));
+
+            // Unknown commands fall back to the default limit
+            let mut data = MAGIC_BYTES.to_vec();
+            VarInt(7).encode(&mut data).unwrap();
+            data.extend_from_slice(b"unknown");
+            VarInt(DEFAULT_MAX_PAYLOAD_SIZE + 1).encode(&mut data).unwrap();
+            let res = read_packet(&mut Cursor::new(data), &subsystem).await;
+            assert!(matches!(res, Err(Error::PacketTooLarge(_, _))));
+
+            // And so do absurd command lengths
+            let mut data = MAGIC_BYTES.to_vec();
+            VarInt(u64::MAX).encode(&mut data).unwrap();
+            let res = read_packet(&mut Cursor::new(data), &subsystem).await;
+            assert!(matches!(res, Err(Error::MalformedPacket)));
+        });
+    }
+}
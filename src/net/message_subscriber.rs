@@ -16,7 +16,16 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::{any::Any, collections::HashMap, io::Cursor, sync::Arc};
+use std::{
+    any::Any,
+    collections::HashMap,
+    io::Cursor,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
 
 use async_trait::async_trait;
 use futures::stream::{FuturesUnordered, StreamExt};
@@ -24,13 +33,52 @@ use log::{debug, warn};
 use rand::{rngs::OsRng, Rng};
 use smol::lock::Mutex;
 
-use super::message::Message;
+use super::message::{Message, DEFAULT_MAX_PAYLOAD_SIZE};
 use crate::{Error, Result};
 
 /// 64-bit identifier for message subscription.
 pub type MessageSubscriptionId = u64;
 type MessageResult<M> = Result<Arc<M>>;
 
+/// What a dispatcher does with a message when a subscription queue is full.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageQueuePolicy {
+    /// Drop the message for the subscribers whose queue is full
+    #[default]
+    Drop,
+    /// Wait for the subscribers to make room, which stalls the channel's
+    /// receive loop and in turn throttles the sender
+    Block,
+}
+
+impl FromStr for MessageQueuePolicy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "drop" => Ok(Self::Drop),
+            "block" => Ok(Self::Block),
+            _ => Err(Error::ParseFailed("Invalid message queue policy")),
+        }
+    }
+}
+
+/// Queue metrics of a message dispatcher
+#[derive(Clone, Debug)]
+pub struct MessageQueueStats {
+    /// Message command
+    pub command: &'static str,
+    /// Number of subscriptions to the message
+    pub subscribers: usize,
+    /// Number of messages waiting in the fullest subscription queue
+    pub depth: usize,
+    /// Capacity of each subscription queue, 0 for unbounded
+    pub capacity: usize,
+    /// Number of messages dropped because a subscription queue was full
+    pub dropped: u64,
+}
+
 /// A dispatcher that is unique to every [`Message`].
 /// Maintains a list of subscribers that are subscribed to that
 /// unique Message type and handles sending messages across these
@@ -38,12 +86,26 @@ type MessageResult<M> = Result<Arc<M>>;
 #[derive(Debug)]
 struct MessageDispatcher<M: Message> {
     subs: Mutex<HashMap<MessageSubscriptionId, smol::channel::Sender<MessageResult<M>>>>,
+    /// Maximum payload size accepted for the message, in bytes
+    max_payload_size: u64,
+    /// Capacity of each subscription queue, 0 for unbounded
+    queue_size: usize,
+    /// What to do with a message when a subscription queue is full
+    queue_policy: MessageQueuePolicy,
+    /// Number of messages dropped because a subscription queue was full
+    dropped: AtomicU64,
 }
 
 impl<M: Message> MessageDispatcher<M> {
     /// Create a new message dispatcher
-    fn new() -> Self {
-        Self { subs: Mutex::new(HashMap::new()) }
+    fn new(max_payload_size: u64, queue_size: usize, queue_policy: MessageQueuePolicy) -> Self {
+        Self {
+            subs: Mutex::new(HashMap::new()),
+            max_payload_size,
+            queue_size,
+            queue_policy,
+            dropped: AtomicU64::new(0),
+        }
     }
 
     /// Create a random ID.
@@ -55,7 +117,10 @@ impl<M: Message> MessageDispatcher<M> {
     /// Subscribe to a channel.
     /// Assigns a new ID and adds it to the list of subscribers.
     pub async fn subscribe(self: Arc<Self>) -> MessageSubscription<M> {
-        let (sender, recv_queue) = smol::channel::unbounded();
+        let (sender, recv_queue) = match self.queue_size {
+            0 => smol::channel::unbounded(),
+            n => smol::channel::bounded(n),
+        };
         // Guard against overwriting
         let mut id = Self::random_id();
         let mut subs = self.subs.lock().await;
@@ -81,8 +146,13 @@ impl<M: Message> MessageDispatcher<M> {
 
     /// Private function to concurrently transmit a message to all subscriber channels.
     /// Automatically clear all inactive channels. Strictly used internally.
+    /// Errors are terminal, so all subscribers are dropped after receiving one.
+    /// Full subscription queues are handled according to the queue policy.
     async fn _trigger_all(&self, message: MessageResult<M>) {
-        let mut subs = self.subs.lock().await;
+        // The lock isn't held while sending, so subscribers blocking the
+        // dispatch are still able to unsubscribe.
+        let subs: Vec<_> =
+            self.subs.lock().await.iter().map(|(id, sub)| (*id, sub.clone())).collect();
 
         debug!(
             target: "net::message_subscriber::_trigger_all()", "START msg={}({}), subs={}",
@@ -94,30 +164,45 @@ impl<M: Message> MessageDispatcher<M> {
         let mut garbage_ids = vec![];
 
         // Prep the futures for concurrent execution
-        for (sub_id, sub) in &*subs {
-            let sub_id = *sub_id;
-            let sub = sub.clone();
+        for (sub_id, sub) in subs {
             let message = message.clone();
+            let policy = self.queue_policy;
             futures.push(async move {
-                match sub.send(message).await {
-                    Ok(res) => Ok((sub_id, res)),
-                    Err(err) => Err((sub_id, err)),
-                }
+                let res = match policy {
+                    MessageQueuePolicy::Block => sub.send(message).await.map_err(|_| true),
+                    MessageQueuePolicy::Drop => sub.try_send(message).map_err(|e| e.is_closed()),
+                };
+                res.map_err(|closed| (sub_id, closed))
             });
         }
 
         // Start polling
         while let Some(r) = futures.next().await {
-            if let Err((sub_id, _err)) = r {
-                garbage_ids.push(sub_id);
+            match r {
+                Ok(()) => {}
+                Err((sub_id, true)) => garbage_ids.push(sub_id),
+                Err((sub_id, false)) => {
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                    debug!(
+                        target: "net::message_subscriber::_trigger_all()",
+                        "Queue of subscription {} is full, dropped msg={}", sub_id, M::NAME,
+                    );
+                }
             }
         }
 
         // Garbage cleanup
+        let mut subs = self.subs.lock().await;
         for sub_id in garbage_ids {
             subs.remove(&sub_id);
         }
 
+        // Closing the queues makes every later `receive()` fail too,
+        // so subscription loops exit instead of waiting forever.
+        if message.is_err() {
+            subs.clear();
+        }
+
         debug!(
             target: "net::message_subscriber::_trigger_all()", "END msg={}({}), subs={}",
             if message.is_ok() { "Ok" } else { "Err" },
@@ -136,11 +221,12 @@ pub struct MessageSubscription<M: Message> {
 }
 
 impl<M: Message> MessageSubscription<M> {
-    /// Start receiving messages.
+    /// Start receiving messages. Returns [`Error::ChannelStopped`] once the
+    /// dispatcher dropped this subscription after an error.
     pub async fn receive(&self) -> MessageResult<M> {
         match self.recv_queue.recv().await {
             Ok(message) => message,
-            Err(e) => panic!("MessageSubscription::receive(): recv_queue failed! {}", e),
+            Err(_) => Err(Error::ChannelStopped),
         }
     }
 
@@ -153,10 +239,14 @@ impl<M: Message> MessageSubscription<M> {
 /// Generic interface for the message dispatcher.
 #[async_trait]
 trait MessageDispatcherInterface: Send + Sync {
-    async fn trigger(&self, payload: &[u8]);
+    async fn trigger(&self, payload: &[u8]) -> bool;
 
     async fn trigger_error(&self, err: Error);
 
+    fn max_payload_size(&self) -> u64;
+
+    async fn queue_stats(&self) -> MessageQueueStats;
+
     fn as_any(self: Arc<Self>) -> Arc<dyn Any + Send + Sync>;
 }
 
@@ -164,14 +254,16 @@ trait MessageDispatcherInterface: Send + Sync {
 #[async_trait]
 impl<M: Message> MessageDispatcherInterface for MessageDispatcher<M> {
     /// Internal function to deserialize data into a message type
-    /// and dispatch it across subscriber channels.
-    async fn trigger(&self, payload: &[u8]) {
+    /// and dispatch it across subscriber channels. Returns `false` if
+    /// the data doesn't decode.
+    async fn trigger(&self, payload: &[u8]) -> bool {
         // Deserialize data into type, send down the pipes.
         let cursor = Cursor::new(payload);
         match M::decode(cursor) {
             Ok(message) => {
                 let message = Ok(Arc::new(message));
-                self._trigger_all(message).await
+                self._trigger_all(message).await;
+                true
             }
 
             Err(err) => {
@@ -180,6 +272,7 @@ impl<M: Message> MessageDispatcherInterface for MessageDispatcher<M> {
                     "Unable to decode data. Dropping...: {}",
                     err,
                 );
+                false
             }
         }
     }
@@ -189,6 +282,23 @@ impl<M: Message> MessageDispatcherInterface for MessageDispatcher<M> {
         self._trigger_all(Err(err)).await;
     }
 
+    /// Maximum payload size accepted for the message, in bytes.
+    fn max_payload_size(&self) -> u64 {
+        self.max_payload_size
+    }
+
+    /// Gather the queue metrics of the dispatcher.
+    async fn queue_stats(&self) -> MessageQueueStats {
+        let subs = self.subs.lock().await;
+        MessageQueueStats {
+            command: M::NAME,
+            subscribers: subs.len(),
+            depth: subs.values().map(|sub| sub.len()).max().unwrap_or(0),
+            capacity: self.queue_size,
+            dropped: self.dropped.load(Ordering::Relaxed),
+        }
+    }
+
     /// Converts to `Any` trait. Enables the dynamic modification of static types.
     fn as_any(self: Arc<Self>) -> Arc<dyn Any + Send + Sync> {
         self
@@ -201,17 +311,72 @@ impl<M: Message> MessageDispatcherInterface for MessageDispatcher<M> {
 #[derive(Default)]
 pub struct MessageSubsystem {
     dispatchers: Mutex<HashMap<&'static str, Arc<dyn MessageDispatcherInterface>>>,
+    /// Capacity of subscription queues, 0 for unbounded
+    queue_size: usize,
+    /// Per-command capacity of subscription queues, overriding `queue_size`
+    queue_sizes: HashMap<String, usize>,
+    /// What to do with a message when a subscription queue is full
+    queue_policy: MessageQueuePolicy,
 }
 
 impl MessageSubsystem {
-    /// Create a new message subsystem.
+    /// Create a new message subsystem with unbounded subscription queues.
     pub fn new() -> Self {
-        Self { dispatchers: Mutex::new(HashMap::new()) }
+        Self::default()
+    }
+
+    /// Create a new message subsystem whose subscription queues hold at
+    /// most `queue_size` messages, or the size given in `queue_sizes` for
+    /// the message command. A size of 0 leaves the queue unbounded.
+    pub fn with_queues(
+        queue_size: usize,
+        queue_sizes: HashMap<String, usize>,
+        queue_policy: MessageQueuePolicy,
+    ) -> Self {
+        Self { dispatchers: Mutex::new(HashMap::new()), queue_size, queue_sizes, queue_policy }
     }
 
     /// Add a new dispatcher for specified [`Message`].
     pub async fn add_dispatch<M: Message>(&self) {
-        self.dispatchers.lock().await.insert(M::NAME, Arc::new(MessageDispatcher::<M>::new()));
+        self.add_dispatch_with_limit::<M>(M::MAX_PAYLOAD_SIZE).await
+    }
+
+    /// Add a new dispatcher for specified [`Message`], accepting payloads
+    /// of at most `max_payload_size` bytes instead of `M::MAX_PAYLOAD_SIZE`.
+    pub async fn add_dispatch_with_limit<M: Message>(&self, max_payload_size: u64) {
+        let queue_size = self.queue_sizes.get(M::NAME).copied().unwrap_or(self.queue_size);
+        let dispatcher =
+            Arc::new(MessageDispatcher::<M>::new(max_payload_size, queue_size, self.queue_policy));
+        self.dispatchers.lock().await.insert(M::NAME, dispatcher);
+    }
+
+    /// Returns the maximum payload size accepted for the given command.
+    /// Commands without a dispatcher use [`DEFAULT_MAX_PAYLOAD_SIZE`].
+    pub async fn max_payload_size(&self, command: &str) -> u64 {
+        match self.dispatchers.lock().await.get(command) {
+            Some(dispatcher) => dispatcher.max_payload_size(),
+            None => DEFAULT_MAX_PAYLOAD_SIZE,
+        }
+    }
+
+    /// Returns the commands that have a dispatcher, in sorted order.
+    pub async fn dispatched_commands(&self) -> Vec<&'static str> {
+        let mut commands: Vec<_> = self.dispatchers.lock().await.keys().copied().collect();
+        commands.sort_unstable();
+        commands
+    }
+
+    /// Returns the queue metrics of every dispatcher, sorted by command.
+    pub async fn queue_stats(&self) -> Vec<MessageQueueStats> {
+        let dispatchers: Vec<_> = self.dispatchers.lock().await.values().cloned().collect();
+
+        let mut stats = Vec::with_capacity(dispatchers.len());
+        for dispatcher in dispatchers {
+            stats.push(dispatcher.queue_stats().await);
+        }
+
+        stats.sort_unstable_by_key(|s| s.command);
+        stats
     }
 
     /// Subscribes to a [`Message`]. Using the Message name, the method
@@ -240,18 +405,18 @@ impl MessageSubsystem {
     }
 
     /// Transmits a payload to a dispatcher.
-    /// Returns an error if the payload fails to transmit.
-    pub async fn notify(&self, command: &str, payload: &[u8]) {
+    /// Returns `false` if the payload doesn't decode as the command's message.
+    pub async fn notify(&self, command: &str, payload: &[u8]) -> bool {
         let Some(dispatcher) = self.dispatchers.lock().await.get(command).cloned() else {
             warn!(
                 target: "net::message_subscriber::notify",
                 "message_subscriber::notify: Command '{}' did not find a dispatcher",
                 command,
             );
-            return
+            return true
         };
 
-        dispatcher.trigger(payload).await;
+        dispatcher.trigger(payload).await
     }
 
     /// Concurrently transmits an error message across dispatchers.
@@ -311,6 +476,63 @@ mod tests {
             let msg2 = sub.receive().await;
             assert!(msg2.is_err());
 
+            // Errors are terminal, later receives fail instead of hanging
+            let msg2 = sub.receive().await;
+            assert!(matches!(msg2, Err(Error::ChannelStopped)));
+
+            sub.unsubscribe().await;
+        });
+    }
+
+    #[test]
+    fn dispatched_commands_test() {
+        #[derive(SerialEncodable, SerialDecodable)]
+        struct MyPingMessage(pub u32);
+        crate::impl_p2p_message!(MyPingMessage, "myping");
+
+        #[derive(SerialEncodable, SerialDecodable)]
+        struct MyAddrMessage(pub u32);
+        crate::impl_p2p_message!(MyAddrMessage, "myaddr");
+
+        smol::block_on(async {
+            let subsystem = MessageSubsystem::new();
+            assert!(subsystem.dispatched_commands().await.is_empty());
+
+            subsystem.add_dispatch::<MyPingMessage>().await;
+            subsystem.add_dispatch::<MyAddrMessage>().await;
+            assert_eq!(subsystem.dispatched_commands().await, vec!["myaddr", "myping"]);
+        });
+    }
+
+    #[test]
+    fn bounded_queue_test() {
+        #[derive(SerialEncodable, SerialDecodable)]
+        struct MyInsertMessage(pub u32);
+        crate::impl_p2p_message!(MyInsertMessage, "myinsert");
+
+        smol::block_on(async {
+            let queue_sizes = HashMap::from([("myinsert".to_string(), 2)]);
+            let subsystem = MessageSubsystem::with_queues(8, queue_sizes, MessageQueuePolicy::Drop);
+            subsystem.add_dispatch::<MyInsertMessage>().await;
+            let sub = subsystem.subscribe::<MyInsertMessage>().await.unwrap();
+
+            // Messages beyond the queue size are dropped
+            for i in 0..5 {
+                subsystem.notify("myinsert", &serialize(&MyInsertMessage(i))).await;
+            }
+
+            let stats = subsystem.queue_stats().await;
+            assert_eq!(stats.len(), 1);
+            assert_eq!(stats[0].subscribers, 1);
+            assert_eq!(stats[0].depth, 2);
+            assert_eq!(stats[0].capacity, 2);
+            assert_eq!(stats[0].dropped, 3);
+
+            // The oldest messages are the ones kept
+            assert_eq!(sub.receive().await.unwrap().0, 0);
+            assert_eq!(sub.receive().await.unwrap().0, 1);
+            assert_eq!(subsystem.queue_stats().await[0].depth, 0);
+
             sub.unsubscribe().await;
         });
     }
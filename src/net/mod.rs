@@ -112,6 +112,17 @@ pub mod acceptor;
 /// Used to establish an outbound connection.
 pub mod connector;
 
+/// Long-lived P2P identity of the node, and of its peers. Identity keys
+/// can be rotated, in which case the transition is signed by both the old
+/// and the new key and announced to peers, which keep accepting the old
+/// key for a grace period.
+pub mod identity;
+
+/// Penalty scores of misbehaving peers, and the list of banned ones.
+/// Protocols report violations of the peers on their channel, which get
+/// disconnected and banned for a while once their score gets too high.
+pub mod reputation;
+
 /// Network configuration settings. This holds the configured P2P instance
 /// behaviour and is controlled by clients of this API.
 pub mod settings;
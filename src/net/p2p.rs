@@ -29,18 +29,21 @@ use url::Url;
 
 use super::{
     channel::ChannelPtr,
-    dnet::DnetEvent,
+    dnet::{session_name, ChannelProtocols, DnetEvent, DnetHistory, DnetRecord},
     hosts::{Hosts, HostsPtr},
-    message::Message,
+    identity::{Identity, IdentityKey, IdentityRotation, PeerIdentities},
+    message::{IdentityRotationMessage, Message},
     protocol::{protocol_registry::ProtocolRegistry, register_default_protocols},
+    reputation::{Reputation, Violation},
     session::{
         InboundSession, InboundSessionPtr, ManualSession, ManualSessionPtr, OutboundSession,
-        OutboundSessionPtr, SeedSyncSession,
+        OutboundSessionPtr, SeedSyncSession, SESSION_INBOUND,
     },
     settings::{Settings, SettingsPtr},
 };
 use crate::{
     system::{Subscriber, SubscriberPtr, Subscription},
+    util::path::expand_path,
     Result,
 };
 
@@ -69,6 +72,10 @@ pub struct P2p {
     settings: SettingsPtr,
     /// Boolean lock marking if peer discovery is active
     pub peer_discovery_running: Mutex<bool>,
+    /// P2P identity of this node
+    identity: Arc<Mutex<Identity>>,
+    /// Identities announced by connected peers
+    peer_identities: Arc<Mutex<PeerIdentities>>,
 
     /// Reference to configured [`ManualSession`]
     session_manual: Mutex<Option<Arc<ManualSession>>>,
@@ -81,6 +88,11 @@ pub struct P2p {
     pub dnet_enabled: Mutex<bool>,
     /// The subscriber for which we can give dnet info over
     dnet_subscriber: SubscriberPtr<DnetEvent>,
+    /// Recent dnet events, kept for history queries
+    dnet_history: Mutex<DnetHistory>,
+
+    /// Penalty scores of misbehaving peers, and the banned ones
+    reputation: Reputation,
 }
 
 impl P2p {
@@ -95,6 +107,23 @@ impl P2p {
     pub async fn new(settings: Settings, executor: Arc<Executor<'static>>) -> P2pPtr {
         let settings = Arc::new(settings);
 
+        let identity = match &settings.identity_path {
+            Some(path) => match expand_path(path).and_then(|p| Identity::load_or_generate(&p)) {
+                Ok(identity) => identity,
+                Err(e) => {
+                    error!(
+                        target: "net::p2p::new()",
+                        "[P2P] Failed loading identity, using an ephemeral one: {}", e,
+                    );
+                    Identity::generate()
+                }
+            },
+            None => Identity::generate(),
+        };
+
+        let dnet_history = Mutex::new(DnetHistory::new(settings.dnet_history_size));
+        let reputation = Reputation::new(settings.ban_threshold, settings.ban_duration);
+
         let self_ = Arc::new(Self {
             executor,
             pending: Mutex::new(HashSet::new()),
@@ -104,6 +133,8 @@ impl P2p {
             protocol_registry: ProtocolRegistry::new(),
             settings,
             peer_discovery_running: Mutex::new(false),
+            identity: Arc::new(Mutex::new(identity)),
+            peer_identities: Arc::new(Mutex::new(PeerIdentities::default())),
 
             session_manual: Mutex::new(None),
             session_inbound: Mutex::new(None),
@@ -111,6 +142,9 @@ impl P2p {
 
             dnet_enabled: Mutex::new(false),
             dnet_subscriber: Subscriber::new(),
+            dnet_history,
+
+            reputation,
         });
 
         let parent = Arc::downgrade(&self_);
@@ -227,6 +261,42 @@ impl P2p {
     /// Remove a channel from the set of connected channels
     pub(super) async fn remove(&self, channel: ChannelPtr) {
         self.channels.lock().await.remove(channel.address());
+        self.peer_identities.lock().await.remove(channel.address());
+    }
+
+    /// Return a reference to the node's P2P identity
+    pub fn identity(&self) -> Arc<Mutex<Identity>> {
+        self.identity.clone()
+    }
+
+    /// Return a reference to the identities announced by connected peers
+    pub fn peer_identities(&self) -> Arc<Mutex<PeerIdentities>> {
+        self.peer_identities.clone()
+    }
+
+    /// Current identity key of the node
+    pub async fn identity_key(&self) -> Result<IdentityKey> {
+        self.identity.lock().await.key()
+    }
+
+    /// Rotate the node's identity key, persisting the rotation history if
+    /// the identity is stored on disk, and announce the signed transition
+    /// to connected peers. The old key remains valid for the configured
+    /// grace period.
+    pub async fn rotate_identity(&self) -> Result<IdentityRotation> {
+        let mut identity = self.identity.lock().await;
+        let mut rotated = identity.clone();
+        let rotation = rotated.rotate(self.settings.identity_grace_period)?;
+        if let Some(path) = &self.settings.identity_path {
+            rotated.save(&expand_path(path)?)?;
+        }
+        *identity = rotated;
+        drop(identity);
+
+        info!(target: "net::p2p::rotate_identity()", "[P2P] Rotated P2P identity");
+        self.broadcast(&IdentityRotationMessage(rotation.clone())).await;
+
+        Ok(rotation)
     }
 
     /// Add an address to the list of pending channels.
@@ -244,6 +314,34 @@ impl P2p {
         &self.channels
     }
 
+    /// Stop up to `n` inbound channels, starting with the peers that sent
+    /// us the fewest messages. Used to free file descriptors when we run
+    /// out of them. Returns the number of stopped channels.
+    pub async fn shed_inbound(&self, n: usize) -> usize {
+        let mut inbound: Vec<ChannelPtr> = self
+            .channels
+            .lock()
+            .await
+            .values()
+            .filter(|c| c.session_type_id() & SESSION_INBOUND != 0)
+            .cloned()
+            .collect();
+
+        inbound.sort_by_key(|c| c.received_messages());
+        inbound.truncate(n);
+
+        for channel in &inbound {
+            warn!(
+                target: "net::p2p::shed_inbound()",
+                "[P2P] Shedding inbound peer {} ({} messages received)",
+                channel.address(), channel.received_messages(),
+            );
+            channel.stop().await;
+        }
+
+        inbound.len()
+    }
+
     /// Retrieve a random connected channel from the
     pub async fn random_channel(&self) -> Option<ChannelPtr> {
         let channels = self.channels().lock().await;
@@ -285,6 +383,26 @@ impl P2p {
         self.session_outbound.lock().await.as_ref().unwrap().clone()
     }
 
+    /// Reference to the [`Reputation`] of peers
+    pub fn reputation(&self) -> &Reputation {
+        &self.reputation
+    }
+
+    /// Report misbehaviour of the peer at `addr`. Once its penalty score
+    /// gets it banned, all channels to it are stopped.
+    pub async fn report_violation(&self, addr: &Url, violation: Violation) {
+        if !self.reputation.report(addr, violation).await {
+            return
+        }
+
+        let channels: Vec<ChannelPtr> = self.channels.lock().await.values().cloned().collect();
+        for channel in channels {
+            if Reputation::same_peer(channel.address(), addr) {
+                channel.stop().await;
+            }
+        }
+    }
+
     /// Enable network debugging
     pub async fn dnet_enable(&self) {
         *self.dnet_enabled.lock().await = true;
@@ -302,8 +420,35 @@ impl P2p {
         self.dnet_subscriber.clone().subscribe().await
     }
 
-    /// Send a dnet notification over the subscriber
+    /// List, per connected channel, the message types that have a
+    /// dispatcher and the protocols that are attached
+    pub async fn dnet_protocols(&self) -> Vec<ChannelProtocols> {
+        let channels: Vec<ChannelPtr> = self.channels.lock().await.values().cloned().collect();
+
+        let mut ret = Vec::with_capacity(channels.len());
+        for channel in channels {
+            ret.push(ChannelProtocols {
+                chan: channel.info.clone(),
+                session: session_name(channel.session_type_id()),
+                dispatchers: channel.message_subsystem().dispatched_commands().await,
+                protocols: channel.protocols().await,
+                queues: channel.message_subsystem().queue_stats().await,
+            });
+        }
+
+        ret
+    }
+
+    /// Retrieve at most `limit` recent dnet events recorded after the
+    /// sequence number `since`, oldest first. Events are only recorded
+    /// while network debugging is enabled.
+    pub async fn dnet_events(&self, since: u64, limit: usize) -> Vec<DnetRecord> {
+        self.dnet_history.lock().await.since(since, limit)
+    }
+
+    /// Record a dnet event and send a notification over the subscriber
     pub async fn dnet_notify(&self, event: DnetEvent) {
+        self.dnet_history.lock().await.push(event.clone());
         self.dnet_subscriber.notify(event).await;
     }
 }
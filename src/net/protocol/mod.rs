@@ -67,6 +67,9 @@ pub use protocol_address::ProtocolAddress;
 pub mod protocol_seed;
 pub use protocol_seed::ProtocolSeed;
 
+pub mod protocol_identity;
+pub use protocol_identity::ProtocolIdentity;
+
 /// Base trait for implementing P2P protocols
 pub mod protocol_base;
 /// Interface for registering arbitrary P2P protocols
@@ -78,4 +81,5 @@ pub async fn register_default_protocols(p2p: P2pPtr) {
     registry.register(SESSION_ALL, ProtocolPing::init).await;
     registry.register(!SESSION_SEED, ProtocolAddress::init).await;
     registry.register(SESSION_SEED, ProtocolSeed::init).await;
+    registry.register(!SESSION_SEED, ProtocolIdentity::init).await;
 }
@@ -0,0 +1,189 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use log::{debug, warn};
+use rand::{rngs::OsRng, Rng};
+use smol::{lock::Mutex, Executor};
+
+use super::{
+    super::{
+        channel::ChannelPtr,
+        identity::{Identity, IdentityChallenge, PeerIdentities},
+        message::{IdentityChallengeMessage, IdentityMessage, IdentityRotationMessage},
+        message_subscriber::MessageSubscription,
+        p2p::P2pPtr,
+    },
+    protocol_base::{ProtocolBase, ProtocolBasePtr},
+    protocol_jobs_manager::{ProtocolJobsManager, ProtocolJobsManagerPtr},
+};
+use crate::Result;
+
+/// Defines identity announcement and identity rotation messages. Each
+/// side sends a random challenge, which the other side has to sign with
+/// the identity key it announces.
+pub struct ProtocolIdentity {
+    channel: ChannelPtr,
+    challenge_sub: MessageSubscription<IdentityChallengeMessage>,
+    identity_sub: MessageSubscription<IdentityMessage>,
+    rotation_sub: MessageSubscription<IdentityRotationMessage>,
+    /// Challenge we sent to the peer on this channel
+    challenge: IdentityChallenge,
+    identity: Arc<Mutex<Identity>>,
+    peer_identities: Arc<Mutex<PeerIdentities>>,
+    jobsman: ProtocolJobsManagerPtr,
+}
+
+const PROTO_NAME: &str = "ProtocolIdentity";
+
+impl ProtocolIdentity {
+    /// Create a new identity protocol.
+    pub async fn init(channel: ChannelPtr, p2p: P2pPtr) -> ProtocolBasePtr {
+        // Creates a subscription to identity challenge message
+        let challenge_sub = channel
+            .subscribe_msg::<IdentityChallengeMessage>()
+            .await
+            .expect("Missing identity challenge dispatcher!");
+
+        // Creates a subscription to identity message
+        let identity_sub =
+            channel.subscribe_msg::<IdentityMessage>().await.expect("Missing identity dispatcher!");
+
+        // Creates a subscription to identity rotation message
+        let rotation_sub = channel
+            .subscribe_msg::<IdentityRotationMessage>()
+            .await
+            .expect("Missing identity rotation dispatcher!");
+
+        Arc::new(Self {
+            channel: channel.clone(),
+            challenge_sub,
+            identity_sub,
+            rotation_sub,
+            challenge: OsRng.gen(),
+            identity: p2p.identity(),
+            peer_identities: p2p.peer_identities(),
+            jobsman: ProtocolJobsManager::new(PROTO_NAME, channel),
+        })
+    }
+
+    /// Waits for the peer's challenge and answers it with our identity.
+    /// Only the first challenge is answered.
+    async fn handle_challenge(self: Arc<Self>) -> Result<()> {
+        debug!(
+            target: "net::protocol_identity::handle_challenge()",
+            "START => address={}", self.channel.address(),
+        );
+
+        let msg = self.challenge_sub.receive().await?;
+
+        let identity = self.identity.lock().await;
+        let reply = IdentityMessage {
+            key: identity.key()?,
+            rotation: identity.pending_rotation().cloned(),
+            signature: identity.sign_challenge(&msg.challenge)?,
+        };
+        drop(identity);
+        self.channel.send(&reply).await?;
+
+        loop {
+            let _ = self.challenge_sub.receive().await?;
+            warn!(
+                target: "net::protocol_identity::handle_challenge()",
+                "[P2P] Ignoring repeated identity challenge from {}", self.channel.address(),
+            );
+        }
+    }
+
+    /// Waits for the peer's identity announcement and records it, once it
+    /// proves possession of the key by signing our challenge.
+    async fn handle_identity(self: Arc<Self>) -> Result<()> {
+        debug!(
+            target: "net::protocol_identity::handle_identity()",
+            "START => address={}", self.channel.address(),
+        );
+
+        loop {
+            let msg = self.identity_sub.receive().await?;
+
+            let mut peer_identities = self.peer_identities.lock().await;
+            if let Err(e) = peer_identities.insert(
+                self.channel.address().clone(),
+                msg.key,
+                msg.rotation.as_ref(),
+                &self.challenge,
+                &msg.signature,
+            ) {
+                warn!(
+                    target: "net::protocol_identity::handle_identity()",
+                    "[P2P] Invalid identity from {}: {}", self.channel.address(), e,
+                );
+            }
+        }
+    }
+
+    /// Waits for identity rotations announced by the peer and applies them.
+    async fn handle_rotation(self: Arc<Self>) -> Result<()> {
+        debug!(
+            target: "net::protocol_identity::handle_rotation()",
+            "START => address={}", self.channel.address(),
+        );
+
+        loop {
+            let msg = self.rotation_sub.receive().await?;
+
+            let mut peer_identities = self.peer_identities.lock().await;
+            if let Err(e) = peer_identities.rotate(self.channel.address(), &msg.0) {
+                warn!(
+                    target: "net::protocol_identity::handle_rotation()",
+                    "[P2P] Rejected identity rotation from {}: {}", self.channel.address(), e,
+                );
+                continue
+            }
+
+            debug!(
+                target: "net::protocol_identity::handle_rotation()",
+                "Peer {} rotated its identity", self.channel.address(),
+            );
+        }
+    }
+}
+
+#[async_trait]
+impl ProtocolBase for ProtocolIdentity {
+    /// Sends our challenge to the peer, and starts answering its challenge
+    /// and listening for its identity announcements and rotations.
+    async fn start(self: Arc<Self>, ex: Arc<Executor<'_>>) -> Result<()> {
+        debug!(target: "net::protocol_identity::start()", "START => address={}", self.channel.address());
+        self.jobsman.clone().start(ex.clone());
+        self.jobsman.clone().spawn(self.clone().handle_challenge(), ex.clone()).await;
+        self.jobsman.clone().spawn(self.clone().handle_identity(), ex.clone()).await;
+        self.jobsman.clone().spawn(self.clone().handle_rotation(), ex).await;
+
+        self.channel.send(&IdentityChallengeMessage { challenge: self.challenge }).await?;
+
+        debug!(target: "net::protocol_identity::start()", "END => address={}", self.channel.address());
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        PROTO_NAME
+    }
+}
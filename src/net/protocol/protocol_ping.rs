@@ -78,13 +78,18 @@ impl ProtocolPing {
     /// Runs the ping-pong protocol. Creates a subscription to pong, then
     /// starts a loop. Loop sleeps for the duration of the channel heartbeat,
     /// then sends a ping message with a random nonce. Loop starts a timer,
-    /// waits for the pong reply and ensures the nonce is the same.
+    /// waits for the pong reply and ensures the nonce is the same. A ping
+    /// that isn't answered in time is retried right away, and the channel
+    /// is stopped once `channel_missed_pongs` pings in a row went unanswered.
     async fn run_ping_pong(self: Arc<Self>) -> Result<()> {
         debug!(
             target: "net::protocol_ping::run_ping_pong()",
             "START => address={}", self.channel.address(),
         );
 
+        // Nonces of the pings that timed out since the last pong
+        let mut missed = vec![];
+
         loop {
             // Create a random nonce.
             let nonce = Self::random_nonce();
@@ -97,37 +102,40 @@ impl ProtocolPing {
             let timer = Instant::now();
 
             // Wait for pong, check nonce matches.
-            let pong_msg = match timeout(
-                Duration::from_secs(self.settings.outbound_connect_timeout),
-                self.pong_sub.receive(),
+            match timeout(
+                Duration::from_secs(self.settings.channel_pong_timeout),
+                self.wait_pong(nonce, &missed),
             )
             .await
             {
-                Ok(msg) => {
-                    // msg will be an error when the channel is stopped
+                Ok(res) => {
+                    // res will be an error when the channel is stopped
                     // so just yield out of this function.
-                    msg?
+                    res?;
+                    missed.clear();
                 }
                 Err(_e) => {
-                    // Pong timeout. We didn't receive any message back
-                    // so close the connection.
+                    // Pong timeout. We didn't receive any message back.
+                    missed.push(nonce);
                     warn!(
                         target: "net::protocol_ping::run_ping_pong()",
-                        "[P2P] Ping-Pong protocol timed out for {}", self.channel.address(),
+                        "[P2P] Missed pong {}/{} from {}",
+                        missed.len(), self.settings.channel_missed_pongs, self.channel.address(),
                     );
-                    self.channel.stop().await;
-                    return Err(Error::ChannelStopped)
-                }
-            };
 
-            if pong_msg.nonce != nonce {
-                error!(
-                    target: "net::protocol_ping::run_ping_pong()",
-                    "[P2P] Wrong nonce in pingpong, disconnecting {}",
-                    self.channel.address(),
-                );
-                self.channel.stop().await;
-                return Err(Error::ChannelStopped)
+                    // Too many in a row, so consider the peer dead and
+                    // close the connection.
+                    if missed.len() >= self.settings.channel_missed_pongs {
+                        warn!(
+                            target: "net::protocol_ping::run_ping_pong()",
+                            "[P2P] Ping-Pong protocol timed out for {}", self.channel.address(),
+                        );
+                        self.channel.stop().await;
+                        return Err(Error::ChannelStopped)
+                    }
+
+                    continue
+                }
             }
 
             debug!(
@@ -142,6 +150,27 @@ impl ProtocolPing {
         }
     }
 
+    /// Waits for the pong matching `nonce`. Late pongs replying to pings
+    /// that already timed out are skipped, any other nonce stops the channel.
+    async fn wait_pong(&self, nonce: u16, missed: &[u16]) -> Result<()> {
+        loop {
+            let pong_msg = self.pong_sub.receive().await?;
+            if pong_msg.nonce == nonce {
+                return Ok(())
+            }
+
+            if !missed.contains(&pong_msg.nonce) {
+                error!(
+                    target: "net::protocol_ping::wait_pong()",
+                    "[P2P] Wrong nonce in pingpong, disconnecting {}",
+                    self.channel.address(),
+                );
+                self.channel.stop().await;
+                return Err(Error::ChannelStopped)
+            }
+        }
+    }
+
     /// Waits for ping, then replies with pong.
     /// Copies ping's nonce into the pong reply.
     async fn reply_to_ping(self: Arc<Self>) -> Result<()> {
@@ -0,0 +1,280 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{collections::HashMap, net::IpAddr};
+
+use log::{debug, warn};
+use smol::lock::Mutex;
+use url::Url;
+
+use crate::util::time::Timestamp;
+
+/// Misbehaviour of a peer, reported by the protocols running on its channel
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Violation {
+    /// A message failed to decode
+    InvalidMessage,
+    /// A request got no reply, or an unusable one
+    FailedReply,
+    /// A message broke the rules of the protocol it belongs to
+    ProtocolViolation,
+}
+
+impl Violation {
+    /// Penalty score the violation adds to the peer's score
+    pub fn penalty(&self) -> u32 {
+        match self {
+            Self::InvalidMessage => 20,
+            Self::FailedReply => 10,
+            Self::ProtocolViolation => 50,
+        }
+    }
+}
+
+/// Seconds it takes for a peer's penalty score to go down by one point
+pub const SCORE_DECAY_INTERVAL: u64 = 60;
+
+/// Penalty scores of misbehaving peers, and the list of peers banned for
+/// reaching the ban threshold. Scores decay over time, so only sustained
+/// misbehaviour gets a peer banned.
+///
+/// Peers are identified by host, so a banned peer can't come back by
+/// connecting from another port. Loopback peers are identified by their
+/// full address instead, since every peer relayed by a local proxy (e.g.
+/// Tor inbound) shares the loopback host. Peers without a host, like unix
+/// socket ones, can't be told apart at all and are never scored.
+pub struct Reputation {
+    /// Accumulated penalty scores, along with the UNIX timestamp of their
+    /// last update, per peer key
+    scores: Mutex<HashMap<String, (u32, u64)>>,
+    /// Banned peer keys, and the UNIX timestamp their ban expires at
+    bans: Mutex<HashMap<String, u64>>,
+    /// Score at which a peer gets banned, 0 to never ban
+    threshold: u32,
+    /// Ban duration, in seconds
+    ban_duration: u64,
+}
+
+impl Reputation {
+    /// Create a new reputation subsystem, banning peers reaching the
+    /// `threshold` score for `ban_duration` seconds.
+    pub fn new(threshold: u32, ban_duration: u64) -> Self {
+        Self {
+            scores: Mutex::new(HashMap::new()),
+            bans: Mutex::new(HashMap::new()),
+            threshold,
+            ban_duration,
+        }
+    }
+
+    /// Add the penalty of a violation to the score of the peer at `addr`.
+    /// Returns `true` if the peer got banned as a result.
+    pub async fn report(&self, addr: &Url, violation: Violation) -> bool {
+        let Some(host) = peer_key(addr) else {
+            debug!(
+                target: "net::reputation::report()",
+                "Peer {} reported for {:?}, not scoring hostless peer", addr, violation,
+            );
+            return false
+        };
+
+        let now = Timestamp::current_time().0;
+        let mut scores = self.scores.lock().await;
+        // Forget peers whose score fully decayed
+        scores.retain(|_, (score, updated)| decayed(*score, *updated, now) > 0);
+        let (score, updated) = scores.entry(host.clone()).or_insert((0, now));
+        *score = decayed(*score, *updated, now).saturating_add(violation.penalty());
+        *updated = now;
+        debug!(
+            target: "net::reputation::report()",
+            "Peer {} reported for {:?}, score {}", host, violation, score,
+        );
+
+        if self.threshold == 0 || *score < self.threshold {
+            return false
+        }
+
+        // The ban wipes the slate clean for when it expires
+        scores.remove(&host);
+        drop(scores);
+
+        warn!(
+            target: "net::reputation::report()",
+            "[P2P] Banning peer {} for {} seconds", host, self.ban_duration,
+        );
+        let expiry = Timestamp::current_time().0.saturating_add(self.ban_duration);
+        self.bans.lock().await.insert(host, expiry);
+        true
+    }
+
+    /// Check if the peer at `addr` is banned. Expired bans are lifted.
+    pub async fn is_banned(&self, addr: &Url) -> bool {
+        let Some(host) = peer_key(addr) else { return false };
+
+        let mut bans = self.bans.lock().await;
+        let Some(expiry) = bans.get(&host) else { return false };
+        if *expiry > Timestamp::current_time().0 {
+            return true
+        }
+
+        bans.remove(&host);
+        false
+    }
+
+    /// Current penalty score of the peer at `addr`
+    pub async fn score(&self, addr: &Url) -> u32 {
+        let Some(host) = peer_key(addr) else { return 0 };
+        let Some((score, updated)) = self.scores.lock().await.get(&host).copied() else { return 0 };
+
+        decayed(score, updated, Timestamp::current_time().0)
+    }
+
+    /// Lift the ban of the peer at `addr`, if any
+    pub async fn unban(&self, addr: &Url) {
+        if let Some(host) = peer_key(addr) {
+            self.bans.lock().await.remove(&host);
+        }
+    }
+
+    /// Check if `a` and `b` are addresses of the same peer, as far as
+    /// scores and bans are concerned
+    pub fn same_peer(a: &Url, b: &Url) -> bool {
+        match (peer_key(a), peer_key(b)) {
+            (Some(a), Some(b)) => a == b,
+            _ => false,
+        }
+    }
+
+    /// List the banned peers, along with the UNIX timestamp their ban expires at
+    pub async fn bans(&self) -> Vec<(String, u64)> {
+        let now = Timestamp::current_time().0;
+        let mut bans = self.bans.lock().await;
+        bans.retain(|_, expiry| *expiry > now);
+        bans.iter().map(|(host, expiry)| (host.clone(), *expiry)).collect()
+    }
+}
+
+/// Key identifying the peer at `addr`: its host, or its full address for
+/// loopback hosts. Returns `None` for addresses without a host.
+fn peer_key(addr: &Url) -> Option<String> {
+    let host = addr.host_str()?;
+
+    let loopback = match addr.host()? {
+        url::Host::Domain(domain) => domain.eq_ignore_ascii_case("localhost"),
+        url::Host::Ipv4(ip) => IpAddr::V4(ip).is_loopback(),
+        url::Host::Ipv6(ip) => IpAddr::V6(ip).is_loopback(),
+    };
+
+    if loopback {
+        return Some(addr.to_string())
+    }
+
+    Some(host.to_string())
+}
+
+/// Penalty `score` last updated at `updated`, decayed up to `now`
+fn decayed(score: u32, updated: u64, now: u64) -> u32 {
+    let decay = now.saturating_sub(updated) / SCORE_DECAY_INTERVAL;
+    score.saturating_sub(decay.try_into().unwrap_or(u32::MAX))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reputation_bans_at_threshold() {
+        smol::block_on(async {
+            let reputation = Reputation::new(100, 3600);
+            let peer = Url::parse("tcp://198.51.100.7:26661").unwrap();
+            let same_host = Url::parse("tcp://198.51.100.7:41234").unwrap();
+
+            assert!(!reputation.report(&peer, Violation::ProtocolViolation).await);
+            assert!(!reputation.report(&same_host, Violation::InvalidMessage).await);
+            assert_eq!(reputation.score(&peer).await, 70);
+            assert!(!reputation.is_banned(&peer).await);
+
+            // Reaching the threshold bans every port of the host
+            assert!(reputation.report(&peer, Violation::ProtocolViolation).await);
+            assert!(reputation.is_banned(&peer).await);
+            assert!(reputation.is_banned(&same_host).await);
+            assert_eq!(reputation.score(&peer).await, 0);
+            assert_eq!(reputation.bans().await.len(), 1);
+
+            reputation.unban(&peer).await;
+            assert!(!reputation.is_banned(&peer).await);
+
+            // Bans expire
+            let reputation = Reputation::new(10, 0);
+            assert!(reputation.report(&peer, Violation::FailedReply).await);
+            assert!(!reputation.is_banned(&peer).await);
+            assert!(reputation.bans().await.is_empty());
+
+            // A zero threshold never bans
+            let reputation = Reputation::new(0, 3600);
+            for _ in 0..10 {
+                assert!(!reputation.report(&peer, Violation::ProtocolViolation).await);
+            }
+            assert!(!reputation.is_banned(&peer).await);
+        });
+    }
+
+    #[test]
+    fn reputation_peer_keys() {
+        smol::block_on(async {
+            let reputation = Reputation::new(50, 3600);
+
+            // Loopback peers are told apart by port
+            let local = Url::parse("tcp://127.0.0.1:41234").unwrap();
+            let other_local = Url::parse("tcp://127.0.0.1:41235").unwrap();
+            assert!(reputation.report(&local, Violation::ProtocolViolation).await);
+            assert!(reputation.is_banned(&local).await);
+            assert!(!reputation.is_banned(&other_local).await);
+            assert!(!Reputation::same_peer(&local, &other_local));
+
+            let local6 = Url::parse("tcp://[::1]:41234").unwrap();
+            let localhost = Url::parse("tcp://localhost:41234").unwrap();
+            assert!(!Reputation::same_peer(&local6, &Url::parse("tcp://[::1]:1").unwrap()));
+            assert!(!Reputation::same_peer(&localhost, &Url::parse("tcp://localhost:1").unwrap()));
+
+            // Unix socket peers are never scored
+            let unix = Url::parse("unix:///tmp/darkfi.sock").unwrap();
+            assert!(!reputation.report(&unix, Violation::ProtocolViolation).await);
+            assert!(!reputation.is_banned(&unix).await);
+            assert_eq!(reputation.score(&unix).await, 0);
+            assert!(!Reputation::same_peer(&unix, &unix));
+
+            // Remote peers are told apart by host only
+            let remote = Url::parse("tcp://198.51.100.7:26661").unwrap();
+            assert!(Reputation::same_peer(&remote, &Url::parse("tcp://198.51.100.7:1").unwrap()));
+        });
+    }
+
+    #[test]
+    fn reputation_score_decay() {
+        let interval = SCORE_DECAY_INTERVAL;
+        assert_eq!(decayed(50, 1000, 1000), 50);
+        assert_eq!(decayed(50, 1000, 1000 + interval - 1), 50);
+        assert_eq!(decayed(50, 1000, 1000 + interval), 49);
+        assert_eq!(decayed(50, 1000, 1000 + 10 * interval), 40);
+        assert_eq!(decayed(50, 1000, 1000 + 100 * interval), 0);
+        assert_eq!(decayed(50, 1000, u64::MAX), 0);
+        // A clock going backwards doesn't raise the score
+        assert_eq!(decayed(50, 1000, 0), 50);
+    }
+}
@@ -23,7 +23,7 @@ use log::debug;
 use smol::Executor;
 
 use super::{channel::ChannelPtr, p2p::P2pPtr, protocol::ProtocolVersion};
-use crate::Result;
+use crate::{Error, Result};
 
 pub mod inbound_session;
 pub use inbound_session::{InboundSession, InboundSessionPtr};
@@ -86,13 +86,24 @@ pub trait Session: Sync {
     ) -> Result<()> {
         debug!(target: "net::session::register_channel()", "[START]");
 
+        // Refuse peers banned for misbehaving, whichever side connected
+        let p2p = self.p2p();
+        if p2p.reputation().is_banned(channel.address()).await {
+            debug!(
+                target: "net::session::register_channel()",
+                "Refusing banned peer {}", channel.address(),
+            );
+            channel.stop().await;
+            return Err(Error::ChannelStopped)
+        }
+
         // Protocols should all be initialized but not started.
         // We do this so that the protocols can begin receiving and buffering
         // messages while the handshake protocol is ongoing. They are currently
         // in sleep mode.
-        let p2p = self.p2p();
         let protocols =
             p2p.protocol_registry().attach(self.type_id(), channel.clone(), p2p.clone()).await;
+        channel.set_protocols(protocols.iter().map(|p| p.name()).collect()).await;
 
         // Perform the handshake protocol
         let protocol_version =
@@ -27,12 +27,13 @@
 //! same time.
 
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     sync::{Arc, Weak},
 };
 
 use async_trait::async_trait;
 use log::{debug, error, info, warn};
+use rand::{rngs::OsRng, Rng};
 use smol::lock::Mutex;
 use url::Url;
 
@@ -41,8 +42,10 @@ use super::{
         channel::ChannelPtr,
         connector::Connector,
         dnet::{self, dnetev, DnetEvent},
+        hosts::{diversify_hosts, NetBucket},
         message::GetAddrsMessage,
         p2p::{P2p, P2pPtr},
+        settings::Settings,
     },
     Session, SessionBitFlag, SESSION_OUTBOUND,
 };
@@ -66,6 +69,10 @@ pub struct OutboundSession {
 
     /// Outbound connection slots
     slots: Mutex<Vec<Arc<Slot>>>,
+    /// Number of slots using each transport, either connecting or connected
+    transport_usage: Mutex<HashMap<String, usize>>,
+    /// Number of slots using each network bucket, either connecting or connected
+    bucket_usage: Mutex<HashMap<NetBucket, usize>>,
 }
 
 impl OutboundSession {
@@ -77,6 +84,8 @@ impl OutboundSession {
             channel_subscriber: Subscriber::new(),
             notify: Mutex::new(false),
             slots: Mutex::new(Vec::new()),
+            transport_usage: Mutex::new(HashMap::new()),
+            bucket_usage: Mutex::new(HashMap::new()),
         })
     }
 
@@ -104,6 +113,87 @@ impl OutboundSession {
             slot.stop().await;
         }
     }
+
+    /// Returns the number of outbound slots currently using each transport,
+    /// either connecting or connected.
+    pub async fn transport_usage(&self) -> HashMap<String, usize> {
+        self.transport_usage.lock().await.clone()
+    }
+
+    /// Returns the number of outbound slots currently using each network
+    /// bucket, either connecting or connected.
+    pub async fn bucket_usage(&self) -> HashMap<NetBucket, usize> {
+        self.bucket_usage.lock().await.clone()
+    }
+
+    /// Marks a slot as no longer using the given address, releasing its
+    /// transport and network bucket.
+    async fn release_address(&self, addr: &Url) {
+        let mut usage = self.transport_usage.lock().await;
+        if let Some(count) = usage.get_mut(addr.scheme()) {
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                usage.remove(addr.scheme());
+            }
+        }
+        drop(usage);
+
+        let Some(bucket) = NetBucket::from_url(addr) else { return };
+        let mut usage = self.bucket_usage.lock().await;
+        if let Some(count) = usage.get_mut(&bucket) {
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                usage.remove(&bucket);
+            }
+        }
+    }
+}
+
+/// Picks the transport an outbound slot should dial next, out of the
+/// `candidates` we have hosts for. Transports at their configured limit
+/// are skipped, and the rest are picked randomly in proportion to their
+/// configured weights. Transports weighted 0 are only picked when no
+/// other transport is available. A transport is never picked to fill the
+/// last free outbound slot while other transports are available.
+fn select_transport(
+    settings: &Settings,
+    candidates: &[String],
+    usage: &HashMap<String, usize>,
+    rng: &mut impl Rng,
+) -> Option<String> {
+    let available: Vec<(&String, u64)> = candidates
+        .iter()
+        .filter(|t| match settings.transport_limits.get(*t) {
+            Some(limit) => usage.get(*t).copied().unwrap_or(0) < *limit,
+            None => true,
+        })
+        .map(|t| (t, settings.transport_weights.get(t).copied().unwrap_or(1) as u64))
+        .collect();
+
+    // Don't let a single transport take every outbound slot
+    let n_slots = settings.outbound_connections;
+    let diverse: Vec<(&String, u64)> = available
+        .iter()
+        .filter(|(t, _)| n_slots < 2 || usage.get(*t).copied().unwrap_or(0) + 1 < n_slots)
+        .copied()
+        .collect();
+    let available = if diverse.is_empty() { available } else { diverse };
+
+    let total: u64 = available.iter().map(|(_, weight)| weight).sum();
+    if total == 0 {
+        // Only fallback transports are left, if any
+        return available.first().map(|(t, _)| t.to_string())
+    }
+
+    let mut pick = rng.gen_range(0..total);
+    for (transport, weight) in available {
+        if pick < weight {
+            return Some(transport.clone())
+        }
+        pick -= weight;
+    }
+
+    unreachable!()
 }
 
 #[async_trait]
@@ -243,6 +333,7 @@ impl Slot {
                         "[P2P] Outbound slot #{} connection failed: {}",
                         self.slot, err,
                     );
+                    self.release_address(&addr).await;
 
                     dnetev!(self, OutboundDisconnected, {
                         slot: self.slot,
@@ -260,6 +351,7 @@ impl Slot {
                     "[P2P] Outbound slot #{} disconnected: {}",
                     self.slot, err
                 );
+                self.release_address(&addr).await;
 
                 dnetev!(self, OutboundDisconnected, {
                     slot: self.slot,
@@ -269,6 +361,7 @@ impl Slot {
             }
             // Wait for channel to close
             stop_sub.receive().await;
+            self.release_address(&addr).await;
         }
     }
 
@@ -342,10 +435,14 @@ impl Slot {
     }
 
     /// Loops through host addresses to find an outbound address that we can
-    /// connect to. Check whether the address is valid by making sure it isn't
+    /// connect to. The transport is picked first, using the configured
+    /// transport weights and limits, and then the hosts using it are checked,
+    /// spread across network buckets and skipping buckets at their limit.
+    /// Check whether the address is valid by making sure it isn't
     /// our own inbound address, then checks whether it is already connected
     /// (exists) or connecting (pending).
-    /// Lastly adds matching address to the pending list.
+    /// Lastly adds matching address to the pending list and counts the slot
+    /// against the transport's limit.
     /// TODO: this method should go in hosts
     async fn fetch_address_with_lock(&self, transports: &[String]) -> Option<Url> {
         let p2p = self.p2p();
@@ -390,30 +487,64 @@ impl Slot {
             hosts.insert(addr);
         }
 
-        // TODO: randomize hosts list. Do not try to connect in a deterministic order.
-        // This is healthier for multiple slots to not compete for the same addrs.
+        // Group the hosts by transport, so we first pick the transport to use
+        // according to the configured weights and limits.
+        let mut transport_hosts: HashMap<String, Vec<Url>> = HashMap::new();
+        for host in hosts {
+            transport_hosts.entry(host.scheme().to_string()).or_default().push(host);
+        }
 
-        // Try to find an unused host in the set.
-        for host in &hosts {
-            // Check if we already have this connection established
-            if p2p.exists(host).await {
-                continue
-            }
+        let session = self.session();
+        let settings = p2p.settings();
+        let mut usage = session.transport_usage.lock().await;
+        let mut bucket_usage = session.bucket_usage.lock().await;
+        let bucket_limit = if settings.localnet { 0 } else { settings.outbound_bucket_limit };
 
-            // Check if we already have this configured as a manual peer
-            if p2p.settings().peers.contains(host) {
-                continue
-            }
+        loop {
+            let candidates: Vec<String> = transport_hosts.keys().cloned().collect();
+            let transport = select_transport(&settings, &candidates, &usage, &mut OsRng)?;
+            let hosts = transport_hosts.remove(&transport).unwrap();
+            let hosts = diversify_hosts(hosts, &bucket_usage, bucket_limit, &mut OsRng);
+
+            // Try to find an unused host of this transport.
+            for host in hosts {
+                // Check if we already have this connection established
+                if p2p.exists(&host).await {
+                    continue
+                }
 
-            // Obtain a lock on this address to prevent duplicate connection
-            if !p2p.add_pending(host).await {
-                continue
-            }
+                // Check if we already have this configured as a manual peer
+                if settings.peers.contains(&host) {
+                    continue
+                }
+
+                // Don't bother with peers banned for misbehaving
+                if p2p.reputation().is_banned(&host).await {
+                    continue
+                }
+
+                // Obtain a lock on this address to prevent duplicate connection
+                if !p2p.add_pending(&host).await {
+                    continue
+                }
 
-            return Some(host.clone())
+                *usage.entry(transport).or_insert(0) += 1;
+                if let Some(bucket) = NetBucket::from_url(&host) {
+                    *bucket_usage.entry(bucket).or_insert(0) += 1;
+                }
+                let snapshot = usage.clone();
+                dnetev!(self, OutboundTransports, { usage: snapshot });
+
+                return Some(host)
+            }
         }
+    }
 
-        None
+    /// Marks this slot as no longer using the given address.
+    async fn release_address(&self, addr: &Url) {
+        self.session().release_address(addr).await;
+        let usage = self.session().transport_usage().await;
+        dnetev!(self, OutboundTransports, { usage });
     }
 
     /// Activate peer discovery if not active already. This will loop through all
@@ -490,3 +621,69 @@ impl Slot {
         self.session().p2p()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    #[test]
+    fn test_select_transport() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let candidates = vec!["tor".to_string(), "tcp+tls".to_string()];
+        let mut settings = Settings {
+            transport_weights: HashMap::from([("tor".to_string(), 9), ("tcp+tls".to_string(), 1)]),
+            ..Default::default()
+        };
+
+        // Weighted picks roughly follow the configured ratio
+        let mut usage = HashMap::new();
+        for _ in 0..1000 {
+            let transport = select_transport(&settings, &candidates, &usage, &mut rng).unwrap();
+            *usage.entry(transport).or_insert(0) += 1;
+        }
+        assert!(usage["tor"] > 800);
+        assert!(usage["tcp+tls"] > 50);
+
+        // Transports at their limit are skipped
+        settings.transport_limits = HashMap::from([("tor".to_string(), 2)]);
+        let usage = HashMap::from([("tor".to_string(), 2)]);
+        for _ in 0..10 {
+            let transport = select_transport(&settings, &candidates, &usage, &mut rng);
+            assert_eq!(transport.as_deref(), Some("tcp+tls"));
+        }
+
+        // Zero-weighted transports are only used as a fallback
+        settings.transport_limits = HashMap::new();
+        settings.transport_weights.insert("tcp+tls".to_string(), 0);
+        let usage = HashMap::new();
+        for _ in 0..10 {
+            let transport = select_transport(&settings, &candidates, &usage, &mut rng);
+            assert_eq!(transport.as_deref(), Some("tor"));
+        }
+        let fallback = ["tcp+tls".to_string()];
+        let transport = select_transport(&settings, &fallback, &usage, &mut rng);
+        assert_eq!(transport.as_deref(), Some("tcp+tls"));
+
+        // Nothing is picked once every transport is at its limit
+        settings.transport_limits = HashMap::from([("tcp+tls".to_string(), 1)]);
+        let usage = HashMap::from([("tcp+tls".to_string(), 1)]);
+        assert!(select_transport(&settings, &fallback, &usage, &mut rng).is_none());
+
+        // A transport can't fill the last free slot while others are available
+        let mut settings = Settings { outbound_connections: 4, ..Default::default() };
+        let usage = HashMap::from([("tor".to_string(), 3)]);
+        for _ in 0..10 {
+            let transport = select_transport(&settings, &candidates, &usage, &mut rng);
+            assert_eq!(transport.as_deref(), Some("tcp+tls"));
+        }
+        let only_tor = ["tor".to_string()];
+        let transport = select_transport(&settings, &only_tor, &usage, &mut rng);
+        assert_eq!(transport.as_deref(), Some("tor"));
+        settings.outbound_connections = 1;
+        let usage = HashMap::new();
+        let transport = select_transport(&settings, &only_tor, &usage, &mut rng);
+        assert_eq!(transport.as_deref(), Some("tor"));
+    }
+}
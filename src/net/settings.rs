@@ -16,11 +16,13 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use structopt::StructOpt;
 use url::Url;
 
+use super::message_subscriber::MessageQueuePolicy;
+
 /// Atomic pointer to network settings
 pub type SettingsPtr = Arc<Settings>;
 
@@ -46,6 +48,18 @@ pub struct Settings {
     pub allowed_transports: Vec<String>,
     /// Allow transport mixing (e.g. Tor would be allowed to connect to `tcp://`)
     pub transport_mixing: bool,
+    /// Preference weights for outbound transports. Outbound slots pick the
+    /// transport to dial randomly, in proportion to these weights. Transports
+    /// not listed get a weight of 1, and transports weighted 0 are only used
+    /// as a fallback when no other transport has hosts available.
+    pub transport_weights: HashMap<String, u32>,
+    /// Maximum number of outbound slots using a transport at the same time,
+    /// either connecting or connected. Transports not listed are unlimited.
+    pub transport_limits: HashMap<String, usize>,
+    /// Maximum number of outbound slots using hosts of the same network
+    /// bucket (IPv4 /16, IPv6 /32 or onion-space bucket) at the same time,
+    /// 0 for unlimited. Ignored on localnet.
+    pub outbound_bucket_limit: usize,
     /// Outbound connection slots number, this many connections will be
     /// attempted. (This does not include manual connections)
     pub outbound_connections: usize,
@@ -57,10 +71,33 @@ pub struct Settings {
     pub channel_handshake_timeout: u64,
     /// Ping-pong exchange execution interval (in seconds)
     pub channel_heartbeat_interval: u64,
+    /// Time to wait for a pong reply to a ping (in seconds)
+    pub channel_pong_timeout: u64,
+    /// Number of consecutive missed pongs after which the channel is
+    /// considered dead and torn down
+    pub channel_missed_pongs: usize,
+    /// Capacity of each channel's message subscription queues, 0 for unbounded
+    pub message_queue_size: usize,
+    /// Per-command capacity of message subscription queues, overriding
+    /// `message_queue_size`
+    pub message_queue_sizes: HashMap<String, usize>,
+    /// What to do with a received message when a subscription queue is full
+    pub message_queue_policy: MessageQueuePolicy,
+    /// Number of recent dnet events kept for history queries, 0 to disable
+    pub dnet_history_size: usize,
     /// Allow localnet hosts
     pub localnet: bool,
     /// Delete a peer from hosts if they've been quarantined N times
     pub hosts_quarantine_limit: usize,
+    /// Penalty score at which a misbehaving peer gets banned, 0 to never ban
+    pub ban_threshold: u32,
+    /// Time a misbehaving peer stays banned (in seconds)
+    pub ban_duration: u64,
+    /// Path to the file holding the node's P2P identity and its rotation
+    /// history. The identity is ephemeral if unset.
+    pub identity_path: Option<String>,
+    /// Time an identity key remains valid after being rotated (in seconds)
+    pub identity_grace_period: u64,
 }
 
 impl Default for Settings {
@@ -77,13 +114,26 @@ impl Default for Settings {
             app_version,
             allowed_transports: vec![],
             transport_mixing: true,
+            transport_weights: HashMap::new(),
+            transport_limits: HashMap::new(),
+            outbound_bucket_limit: 2,
             outbound_connections: 0,
             manual_attempt_limit: 0,
             outbound_connect_timeout: 15,
             channel_handshake_timeout: 10,
             channel_heartbeat_interval: 10,
+            channel_pong_timeout: 15,
+            channel_missed_pongs: 3,
+            message_queue_size: 1024,
+            message_queue_sizes: HashMap::new(),
+            message_queue_policy: MessageQueuePolicy::Drop,
+            dnet_history_size: 4096,
             localnet: false,
             hosts_quarantine_limit: 50,
+            ban_threshold: 100,
+            ban_duration: 3600,
+            identity_path: None,
+            identity_grace_period: 86400,
         }
     }
 }
@@ -138,6 +188,31 @@ pub struct SettingsOpt {
     #[structopt(skip)]
     pub channel_heartbeat_interval: Option<u64>,
 
+    /// Time to wait for a pong reply in seconds
+    #[structopt(skip)]
+    pub channel_pong_timeout: Option<u64>,
+
+    /// Consecutive missed pongs before a channel is torn down
+    #[structopt(skip)]
+    pub channel_missed_pongs: Option<usize>,
+
+    /// Capacity of message subscription queues, 0 for unbounded
+    #[structopt(skip)]
+    pub message_queue_size: Option<usize>,
+
+    /// Per-command capacity of message subscription queues
+    #[serde(default)]
+    #[structopt(skip)]
+    pub message_queue_sizes: HashMap<String, usize>,
+
+    /// What to do with a message when a subscription queue is full (drop or block)
+    #[structopt(long)]
+    pub message_queue_policy: Option<MessageQueuePolicy>,
+
+    /// Number of recent dnet events kept for history queries, 0 to disable
+    #[structopt(skip)]
+    pub dnet_history_size: Option<usize>,
+
     /// Only used for debugging. Compromises privacy when set.
     #[serde(default)]
     #[structopt(skip)]
@@ -151,6 +226,21 @@ pub struct SettingsOpt {
     #[structopt(long)]
     pub transport_mixing: Option<bool>,
 
+    /// Preference weights for outbound transports
+    #[serde(default)]
+    #[structopt(skip)]
+    pub transport_weights: HashMap<String, u32>,
+
+    /// Maximum number of outbound slots per transport
+    #[serde(default)]
+    #[structopt(skip)]
+    pub transport_limits: HashMap<String, usize>,
+
+    /// Maximum number of outbound slots per network bucket, 0 for unlimited
+    #[serde(default)]
+    #[structopt(long)]
+    pub outbound_bucket_limit: Option<usize>,
+
     /// Allow localnet hosts
     #[serde(default)]
     #[structopt(long)]
@@ -158,6 +248,22 @@ pub struct SettingsOpt {
 
     #[structopt(skip)]
     pub hosts_quarantine_limit: Option<usize>,
+
+    /// Penalty score at which a misbehaving peer gets banned, 0 to never ban
+    #[structopt(skip)]
+    pub ban_threshold: Option<u32>,
+
+    /// Time a misbehaving peer stays banned (in seconds)
+    #[structopt(skip)]
+    pub ban_duration: Option<u64>,
+
+    /// Path to the P2P identity file, ephemeral identity if unset
+    #[structopt(long)]
+    pub identity_path: Option<String>,
+
+    /// Time an identity key remains valid after being rotated (in seconds)
+    #[structopt(skip)]
+    pub identity_grace_period: Option<u64>,
 }
 
 impl From<SettingsOpt> for Settings {
@@ -174,13 +280,26 @@ impl From<SettingsOpt> for Settings {
             app_version,
             allowed_transports: opt.allowed_transports,
             transport_mixing: opt.transport_mixing.unwrap_or(false),
+            transport_weights: opt.transport_weights,
+            transport_limits: opt.transport_limits,
+            outbound_bucket_limit: opt.outbound_bucket_limit.unwrap_or(2),
             outbound_connections: opt.outbound_connections.unwrap_or(0),
             manual_attempt_limit: opt.manual_attempt_limit.unwrap_or(0),
             outbound_connect_timeout: opt.outbound_connect_timeout.unwrap_or(15),
             channel_handshake_timeout: opt.channel_handshake_timeout.unwrap_or(10),
             channel_heartbeat_interval: opt.channel_heartbeat_interval.unwrap_or(10),
+            channel_pong_timeout: opt.channel_pong_timeout.unwrap_or(15),
+            channel_missed_pongs: opt.channel_missed_pongs.unwrap_or(3),
+            message_queue_size: opt.message_queue_size.unwrap_or(1024),
+            message_queue_sizes: opt.message_queue_sizes,
+            message_queue_policy: opt.message_queue_policy.unwrap_or_default(),
+            dnet_history_size: opt.dnet_history_size.unwrap_or(4096),
             localnet: opt.localnet,
             hosts_quarantine_limit: opt.hosts_quarantine_limit.unwrap_or(15),
+            ban_threshold: opt.ban_threshold.unwrap_or(100),
+            ban_duration: opt.ban_duration.unwrap_or(3600),
+            identity_path: opt.identity_path,
+            identity_grace_period: opt.identity_grace_period.unwrap_or(86400),
         }
     }
 }
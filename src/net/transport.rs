@@ -353,3 +353,58 @@ impl PtStream for smol::net::unix::UnixStream {}
 pub trait PtListener: Send + Sync + Unpin {
     async fn next(&self) -> Result<(Box<dyn PtStream>, Url)>;
 }
+
+/// Convert an error returned by a listener's `accept()` into our error
+/// type, marking file descriptor exhaustion so the acceptor can back off
+/// instead of spinning on it.
+pub(crate) fn accept_error(err: std::io::Error) -> Error {
+    if is_fd_exhaustion(&err) {
+        return Error::FdExhausted
+    }
+
+    err.into()
+}
+
+/// Returns `true` if the error means the process or the system ran out
+/// of file descriptors (`EMFILE`/`ENFILE`).
+#[cfg(unix)]
+fn is_fd_exhaustion(err: &std::io::Error) -> bool {
+    matches!(err.raw_os_error(), Some(libc::EMFILE) | Some(libc::ENFILE))
+}
+
+/// Returns `true` if the error means the process ran out of sockets
+/// (`WSAEMFILE`).
+#[cfg(windows)]
+fn is_fd_exhaustion(err: &std::io::Error) -> bool {
+    err.raw_os_error() == Some(10024)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn is_fd_exhaustion(_err: &std::io::Error) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_accept_error_fd_exhaustion() {
+        for errno in [libc::EMFILE, libc::ENFILE] {
+            let err = std::io::Error::from_raw_os_error(errno);
+            assert!(matches!(accept_error(err), Error::FdExhausted));
+        }
+
+        let err = std::io::Error::from_raw_os_error(libc::ECONNABORTED);
+        assert!(matches!(accept_error(err), Error::Io(_)));
+    }
+
+    #[test]
+    fn test_fd_usage() {
+        if let Some(usage) = super::super::acceptor::fd_usage() {
+            assert!(usage.open > 0);
+            assert!(usage.open <= usage.limit);
+        }
+    }
+}
@@ -24,7 +24,7 @@ use log::debug;
 use smol::net::{SocketAddr, TcpListener as SmolTcpListener, TcpStream};
 use url::Url;
 
-use super::{PtListener, PtStream};
+use super::{accept_error, PtListener, PtStream};
 use crate::{system::io_timeout, Result};
 
 /// TCP Dialer implementation
@@ -85,7 +85,7 @@ impl PtListener for SmolTcpListener {
     async fn next(&self) -> Result<(Box<dyn PtStream>, Url)> {
         let (stream, peer_addr) = match self.accept().await {
             Ok((s, a)) => (s, a),
-            Err(e) => return Err(e.into()),
+            Err(e) => return Err(accept_error(e)),
         };
 
         let url = Url::parse(&format!("tcp://{}", peer_addr))?;
@@ -98,7 +98,7 @@ impl PtListener for (TlsAcceptor, SmolTcpListener) {
     async fn next(&self) -> Result<(Box<dyn PtStream>, Url)> {
         let (stream, peer_addr) = match self.1.accept().await {
             Ok((s, a)) => (s, a),
-            Err(e) => return Err(e.into()),
+            Err(e) => return Err(accept_error(e)),
         };
 
         let stream = self.0.accept(stream).await;
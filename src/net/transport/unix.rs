@@ -26,7 +26,7 @@ use smol::{
 };
 use url::Url;
 
-use super::{PtListener, PtStream};
+use super::{accept_error, PtListener, PtStream};
 use crate::Result;
 
 /// Unix Dialer implementation
@@ -74,7 +74,7 @@ impl PtListener for SmolUnixListener {
     async fn next(&self) -> Result<(Box<dyn PtStream>, Url)> {
         let (stream, _peer_addr) = match self.accept().await {
             Ok((s, a)) => (s, a),
-            Err(e) => return Err(e.into()),
+            Err(e) => return Err(accept_error(e)),
         };
 
         let addr = self.local_addr().unwrap();
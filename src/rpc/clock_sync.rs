@@ -22,7 +22,10 @@ use std::{net::UdpSocket, time::Duration};
 use log::debug;
 use url::Url;
 
-use crate::{util::time::Timestamp, Error, Result};
+use crate::{
+    util::time::{correct_monotonic_clock, Timestamp},
+    Error, Result,
+};
 
 /// Clock sync parameters
 const RETRIES: u8 = 10;
@@ -109,6 +112,9 @@ async fn clock_check(_peers: &[Url]) -> Result<()> {
     debug!(target: "rpc::clock_sync", "ntp_time: {:#?}", ntp_time);
     debug!(target: "rpc::clock_sync", "system_time: {:#?}", system_time);
 
+    // Anchor the monotonic clock to NTP corrected time
+    correct_monotonic_clock((ntp_time.0 as i128 - system_time.0 as i128) * 1_000_000_000);
+
     // We verify that system time is equal to peer (if exists) and ntp times
     let check = match peer_time {
         Some(p) => (system_time == p) && (system_time == ntp_time),
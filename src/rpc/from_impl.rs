@@ -72,6 +72,73 @@ impl From<net::dnet::OutboundDisconnected> for JsonValue {
     }
 }
 
+#[cfg(feature = "net")]
+impl From<net::dnet::OutboundTransports> for JsonValue {
+    fn from(info: net::dnet::OutboundTransports) -> JsonValue {
+        let usage = info.usage.into_iter().map(|(k, v)| (k, JsonNum(v as f64))).collect();
+        json_map([("usage", JsonObj(usage))])
+    }
+}
+
+#[cfg(feature = "net")]
+impl From<net::dnet::AcceptorFdExhausted> for JsonValue {
+    fn from(info: net::dnet::AcceptorFdExhausted) -> JsonValue {
+        let fd_usage = match info.fd_usage {
+            Some(usage) => json_map([
+                ("open", JsonNum(usage.open as f64)),
+                ("limit", JsonNum(usage.limit as f64)),
+            ]),
+            None => JsonValue::Null,
+        };
+
+        json_map([
+            ("addr", JsonStr(info.addr.to_string())),
+            ("fd_usage", fd_usage),
+            ("backoff_ms", JsonNum(info.backoff.as_millis() as f64)),
+            ("shed", JsonNum(info.shed as f64)),
+            ("exhaustions", JsonNum(info.exhaustions as f64)),
+        ])
+    }
+}
+
+#[cfg(feature = "net")]
+impl From<net::dnet::ChannelProtocols> for JsonValue {
+    fn from(info: net::dnet::ChannelProtocols) -> JsonValue {
+        let strs = |v: Vec<&str>| JsonValue::Array(v.into_iter().map(json_str).collect());
+        json_map([
+            ("chan", info.chan.into()),
+            ("session", json_str(info.session)),
+            ("dispatchers", strs(info.dispatchers)),
+            ("protocols", strs(info.protocols)),
+            ("queues", JsonValue::Array(info.queues.into_iter().map(JsonValue::from).collect())),
+        ])
+    }
+}
+
+#[cfg(feature = "net")]
+impl From<net::message_subscriber::MessageQueueStats> for JsonValue {
+    fn from(stats: net::message_subscriber::MessageQueueStats) -> JsonValue {
+        json_map([
+            ("command", json_str(stats.command)),
+            ("subscribers", JsonNum(stats.subscribers as f64)),
+            ("depth", JsonNum(stats.depth as f64)),
+            ("capacity", JsonNum(stats.capacity as f64)),
+            ("dropped", JsonNum(stats.dropped as f64)),
+        ])
+    }
+}
+
+#[cfg(feature = "net")]
+impl From<net::dnet::DnetRecord> for JsonValue {
+    fn from(record: net::dnet::DnetRecord) -> JsonValue {
+        json_map([
+            ("seq", JsonNum(record.seq as f64)),
+            ("time", JsonStr(record.time.0.to_string())),
+            ("event", record.event.into()),
+        ])
+    }
+}
+
 #[cfg(feature = "net")]
 impl From<net::dnet::DnetEvent> for JsonValue {
     fn from(event: net::dnet::DnetEvent) -> JsonValue {
@@ -91,6 +158,12 @@ impl From<net::dnet::DnetEvent> for JsonValue {
             net::dnet::DnetEvent::OutboundDisconnected(info) => {
                 json_map([("event", json_str("outbound_disconnected")), ("info", info.into())])
             }
+            net::dnet::DnetEvent::OutboundTransports(info) => {
+                json_map([("event", json_str("outbound_transports")), ("info", info.into())])
+            }
+            net::dnet::DnetEvent::AcceptorFdExhausted(info) => {
+                json_map([("event", json_str("acceptor_fd_exhausted")), ("info", info.into())])
+            }
         }
     }
 }
@@ -47,6 +47,8 @@ pub enum ErrorCode {
     IdMismatch,
     /// Invalid/Unexpected reply
     InvalidReply,
+    /// Request rejected because the client exceeded its rate limit
+    RateLimited,
     /// Reserved for implementation-defined server-errors.
     ServerError(i32),
 }
@@ -61,6 +63,7 @@ impl ErrorCode {
             Self::InternalError => -32603,
             Self::IdMismatch => -32360,
             Self::InvalidReply => -32361,
+            Self::RateLimited => -32005,
             Self::ServerError(c) => c,
         }
     }
@@ -74,6 +77,7 @@ impl ErrorCode {
             Self::InternalError => "internal error".to_string(),
             Self::IdMismatch => "id mismatch".to_string(),
             Self::InvalidReply => "invalid reply".to_string(),
+            Self::RateLimited => "rate limited".to_string(),
             Self::ServerError(_) => "server error".to_string(),
         }
     }
@@ -381,13 +385,15 @@ pub struct JsonError {
     pub error: JsonErrorVal,
 }
 
-/// A JSON-RPC error value (code and message)
+/// A JSON-RPC error value (code, message and optional data)
 #[derive(Clone, Debug)]
 pub struct JsonErrorVal {
     /// Error code
     pub code: i32,
     /// Error message
     pub message: String,
+    /// Additional information about the error
+    pub data: Option<JsonValue>,
 }
 
 impl JsonError {
@@ -395,10 +401,18 @@ impl JsonError {
     /// message, and a response ID.
     /// Creating a `JsonError` implies that the method call was unsuccessful.
     pub fn new(c: ErrorCode, message: Option<String>, id: u16) -> Self {
-        let error = JsonErrorVal { code: c.code(), message: message.unwrap_or(c.message()) };
+        let error =
+            JsonErrorVal { code: c.code(), message: message.unwrap_or(c.message()), data: None };
         Self { jsonrpc: "2.0", id, error }
     }
 
+    /// Attach additional information about the error, sent in the
+    /// error object's `data` field.
+    pub fn with_data(mut self, data: JsonValue) -> Self {
+        self.error.data = Some(data);
+        self
+    }
+
     /// Convert the object into a JSON string
     pub fn stringify(&self) -> Result<String> {
         let v: JsonValue = self.into();
@@ -408,10 +422,14 @@ impl JsonError {
 
 impl From<&JsonError> for JsonValue {
     fn from(err: &JsonError) -> JsonValue {
-        let errmap = JsonValue::Object(HashMap::from([
+        let mut errmap = HashMap::from([
             ("code".to_string(), JsonValue::Number(err.error.code.into())),
             ("message".to_string(), JsonValue::String(err.error.message.clone())),
-        ]));
+        ]);
+        if let Some(data) = &err.error.data {
+            errmap.insert("data".to_string(), data.clone());
+        }
+        let errmap = JsonValue::Object(errmap);
 
         JsonValue::Object(HashMap::from([
             ("jsonrpc".to_string(), JsonValue::String(err.jsonrpc.to_string())),
@@ -470,6 +488,11 @@ impl TryFrom<&JsonValue> for JsonError {
             error: JsonErrorVal {
                 code: *map["error"]["code"].get::<f64>().unwrap() as i32,
                 message: map["error"]["message"].get::<String>().unwrap().to_string(),
+                data: map["error"]
+                    .get::<HashMap<String, JsonValue>>()
+                    .unwrap()
+                    .get("data")
+                    .cloned(),
             },
         })
     }
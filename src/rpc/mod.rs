@@ -28,6 +28,9 @@ pub mod client;
 /// Server-side JSON-RPC implementation
 pub mod server;
 
+/// Rate limiting for JSON-RPC servers
+pub mod ratelimit;
+
 /// Clock sync utility module
 pub mod clock_sync;
 
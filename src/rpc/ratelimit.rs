@@ -0,0 +1,164 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Token bucket rate limiting for JSON-RPC servers
+use std::{collections::HashMap, time::Instant};
+
+/// Rate limit parameters of a single token bucket
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RateLimit {
+    /// Maximum number of requests that can be made in a burst
+    pub burst: u32,
+    /// Number of requests the bucket is refilled with every second
+    pub per_second: f64,
+}
+
+impl RateLimit {
+    pub fn new(burst: u32, per_second: f64) -> Self {
+        Self { burst, per_second }
+    }
+}
+
+/// Rate limiting configuration for a JSON-RPC server.
+/// Every accepted connection gets its own set of buckets, so one client
+/// can't starve the others.
+#[derive(Clone, Debug, Default)]
+pub struct RateLimitConfig {
+    /// Limit applied to all requests made over a connection
+    pub connection: Option<RateLimit>,
+    /// Shared limit for methods not marked as expensive
+    pub cheap: Option<RateLimit>,
+    /// Shared limit for methods marked as expensive
+    pub expensive: Option<RateLimit>,
+    /// Methods considered expensive, e.g. simulation or proof operations
+    pub expensive_methods: Vec<String>,
+    /// Limits for specific methods, overriding the class limit
+    pub methods: HashMap<String, RateLimit>,
+}
+
+impl RateLimitConfig {
+    /// Returns `true` if the configuration doesn't limit anything.
+    pub fn is_unlimited(&self) -> bool {
+        self.connection.is_none() &&
+            self.cheap.is_none() &&
+            self.expensive.is_none() &&
+            self.methods.is_empty()
+    }
+
+    fn is_expensive(&self, method: &str) -> bool {
+        self.expensive_methods.iter().any(|m| m == method)
+    }
+}
+
+/// Token bucket refilled continuously at a fixed rate
+#[derive(Debug)]
+pub struct TokenBucket {
+    limit: RateLimit,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// Create a new bucket, starting out full
+    pub fn new(limit: RateLimit) -> Self {
+        Self { limit, tokens: limit.burst as f64, last_refill: Instant::now() }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.limit.per_second).min(self.limit.burst as f64);
+        self.last_refill = now;
+    }
+
+    /// Returns `true` if a token is available at `now`, without taking it.
+    pub fn check(&mut self, now: Instant) -> bool {
+        self.refill(now);
+        self.tokens >= 1.0
+    }
+
+    /// Take a token, if one is available at `now`.
+    pub fn try_take(&mut self, now: Instant) -> bool {
+        if !self.check(now) {
+            return false
+        }
+
+        self.tokens -= 1.0;
+        true
+    }
+}
+
+/// Bucket class a request is charged against, besides the connection bucket
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum BucketKey {
+    Method(String),
+    Cheap,
+    Expensive,
+}
+
+/// Rate limiter state of a single connection
+#[derive(Debug)]
+pub struct ConnectionLimiter {
+    config: RateLimitConfig,
+    connection: Option<TokenBucket>,
+    buckets: HashMap<BucketKey, TokenBucket>,
+}
+
+impl ConnectionLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        let connection = config.connection.map(TokenBucket::new);
+        Self { config, connection, buckets: HashMap::new() }
+    }
+
+    /// Check if a call to `method` is allowed right now and charge it to
+    /// its buckets. A rejected request doesn't consume any tokens.
+    pub fn allow(&mut self, method: &str) -> bool {
+        self.allow_at(method, Instant::now())
+    }
+
+    /// Same as [`ConnectionLimiter::allow`], at the given point in time.
+    pub fn allow_at(&mut self, method: &str, now: Instant) -> bool {
+        let (key, limit) = if let Some(limit) = self.config.methods.get(method) {
+            (BucketKey::Method(method.to_string()), Some(*limit))
+        } else if self.config.is_expensive(method) {
+            (BucketKey::Expensive, self.config.expensive)
+        } else {
+            (BucketKey::Cheap, self.config.cheap)
+        };
+
+        let mut bucket =
+            limit.map(|l| self.buckets.entry(key).or_insert_with(|| TokenBucket::new(l)));
+
+        if let Some(bucket) = bucket.as_mut() {
+            if !bucket.check(now) {
+                return false
+            }
+        }
+
+        if let Some(ref mut connection) = self.connection {
+            if !connection.try_take(now) {
+                return false
+            }
+        }
+
+        if let Some(bucket) = bucket {
+            bucket.try_take(now);
+        }
+
+        true
+    }
+}
@@ -26,6 +26,7 @@ use url::Url;
 use super::{
     common::{read_from_stream, write_to_stream, INIT_BUF_SIZE},
     jsonrpc::*,
+    ratelimit::{ConnectionLimiter, RateLimitConfig},
 };
 use crate::{
     net::transport::{Listener, PtListener, PtStream},
@@ -45,10 +46,24 @@ pub trait RequestHandler: Sync + Send {
 /// Accept function that should run inside a loop for accepting incoming
 /// JSON-RPC requests and passing them to the [`RequestHandler`].
 pub async fn accept(
+    stream: Box<dyn PtStream>,
+    addr: Url,
+    rh: Arc<impl RequestHandler + 'static>,
+) -> Result<()> {
+    accept_with_limits(stream, addr, rh, None).await
+}
+
+/// Same as [`accept()`], but requests exceeding the given rate limits
+/// are answered with an [`ErrorCode::RateLimited`] error instead of
+/// being passed to the [`RequestHandler`].
+pub async fn accept_with_limits(
     mut stream: Box<dyn PtStream>,
     addr: Url,
     rh: Arc<impl RequestHandler + 'static>,
+    limits: Option<RateLimitConfig>,
 ) -> Result<()> {
+    let mut limiter = limits.filter(|l| !l.is_unlimited()).map(ConnectionLimiter::new);
+
     loop {
         let mut buf = Vec::with_capacity(INIT_BUF_SIZE);
         let _ = read_from_stream(&mut stream, &mut buf, false).await?;
@@ -57,6 +72,15 @@ pub async fn accept(
 
         debug!(target: "rpc::server", "{} --> {}", addr, val.stringify()?);
 
+        if let Some(ref mut limiter) = limiter {
+            if !limiter.allow(&req.method) {
+                debug!(target: "rpc::server", "{} rate limited on {}", addr, req.method);
+                let rep: JsonResult = JsonError::new(ErrorCode::RateLimited, None, req.id).into();
+                write_to_stream(&mut stream, &rep).await?;
+                continue
+            }
+        }
+
         let rep = rh.handle_request(req).await;
 
         match rep {
@@ -99,14 +123,16 @@ pub async fn accept(
 async fn run_accept_loop(
     listener: Box<dyn PtListener>,
     rh: Arc<impl RequestHandler + 'static>,
+    limits: Option<RateLimitConfig>,
     ex: Arc<smol::Executor<'_>>,
 ) -> Result<()> {
     while let Ok((stream, peer_addr)) = listener.next().await {
         info!(target: "rpc::server", "[RPC] Server accepted conn from {}", peer_addr);
         // Detaching requests handling
         let rh_ = rh.clone();
+        let limits_ = limits.clone();
         ex.spawn(async move {
-            if let Err(e) = accept(stream, peer_addr.clone(), rh_).await {
+            if let Err(e) = accept_with_limits(stream, peer_addr.clone(), rh_, limits_).await {
                 if e.to_string().as_str() == "Connection closed: Connection closed cleanly" {
                     info!(
                         target: "rpc::server",
@@ -135,7 +161,18 @@ pub async fn listen_and_serve(
     accept_url: Url,
     rh: Arc<impl RequestHandler + 'static>,
     ex: Arc<smol::Executor<'_>>,
+) -> Result<()> {
+    listen_and_serve_with_limits(accept_url, rh, None, ex).await
+}
+
+/// Start a JSON-RPC server like [`listen_and_serve()`], enforcing the
+/// given rate limits on every accepted connection.
+pub async fn listen_and_serve_with_limits(
+    accept_url: Url,
+    rh: Arc<impl RequestHandler + 'static>,
+    limits: Option<RateLimitConfig>,
+    ex: Arc<smol::Executor<'_>>,
 ) -> Result<()> {
     let listener = Listener::new(accept_url).await?.listen().await?;
-    run_accept_loop(listener, rh, ex.clone()).await
+    run_accept_loop(listener, rh, limits, ex.clone()).await
 }
@@ -0,0 +1,238 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::fmt;
+
+use wasmer::wasmparser::{BinaryReaderError, Operator, Parser, Payload, TypeRef};
+
+use crate::{Error, Result};
+
+/// Host functions the runtime provides to contracts, all imported from
+/// the `env` module. This has to match the imports of [`super::vm_runtime::Runtime`].
+pub const HOST_FUNCTIONS: [&str; 29] = [
+    "drk_log_",
+    "drk_trace_",
+    "set_return_data_",
+    "set_error_report_",
+    "set_fee_paid_",
+    "db_init_",
+    "db_lookup_",
+    "db_get_",
+    "db_contains_key_",
+    "db_set_",
+    "db_del_",
+    "db_savepoint_",
+    "db_rollback_",
+    "db_release_",
+    "zkas_db_set_",
+    "put_object_bytes_",
+    "get_object_bytes_",
+    "get_object_size_",
+    "merkle_add_",
+    "merkle_frontier_add_",
+    "get_current_epoch_",
+    "get_current_slot_",
+    "get_verifying_slot_",
+    "get_verifying_slot_epoch_",
+    "get_slot_",
+    "get_blockchain_time_",
+    "is_feature_active_",
+    "poseidon_hash_",
+    "pedersen_commitment_u64_",
+];
+
+/// Name fragments of host functions whose results differ between nodes,
+/// like wall clock or randomness access
+const NONDETERMINISTIC_IMPORTS: [&str; 4] = ["clock", "system_time", "wall_time", "random"];
+
+/// A pattern in a contract's wasm bincode likely to break determinism or safety
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ContractFinding {
+    /// Import of a host function whose result differs between nodes
+    NondeterministicImport { module: String, name: String },
+    /// Import outside the host functions provided by the runtime
+    UnsanctionedImport { module: String, name: String },
+    /// Floating point arithmetic, whose NaN results may differ between platforms
+    FloatArithmetic { function: u32 },
+    /// Loop that can't be left, running until the gas limit is hit
+    UnboundedLoop { function: u32 },
+}
+
+impl fmt::Display for ContractFinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NondeterministicImport { module, name } => {
+                write!(f, "Import {}::{} is not deterministic", module, name)
+            }
+            Self::UnsanctionedImport { module, name } => {
+                write!(f, "Import {}::{} is not provided by the runtime", module, name)
+            }
+            Self::FloatArithmetic { function } => {
+                write!(f, "Function {} uses floating point arithmetic", function)
+            }
+            Self::UnboundedLoop { function } => {
+                write!(f, "Function {} has a loop without an exit", function)
+            }
+        }
+    }
+}
+
+/// Analyze a contract's wasm bincode for patterns likely to break
+/// determinism or safety. The analysis is only advisory: its findings
+/// don't make the bincode invalid, so callers decide whether to warn
+/// about them or refuse the contract.
+pub fn analyze_contract(wasm: &[u8]) -> Result<Vec<ContractFinding>> {
+    let mut findings = vec![];
+
+    // Function indices count imported functions first
+    let mut function = 0_u32;
+
+    for payload in Parser::new(0).parse_all(wasm) {
+        let payload = payload.map_err(parse_error)?;
+
+        match payload {
+            Payload::ImportSection(reader) => {
+                for import in reader {
+                    let import = import.map_err(parse_error)?;
+                    if let TypeRef::Func(_) = import.ty {
+                        function += 1;
+                    }
+
+                    let (module, name) = (import.module.to_string(), import.name.to_string());
+                    if NONDETERMINISTIC_IMPORTS.iter().any(|p| import.name.contains(p)) {
+                        findings.push(ContractFinding::NondeterministicImport { module, name });
+                        continue
+                    }
+
+                    let sanctioned = import.module == "env" &&
+                        matches!(import.ty, TypeRef::Func(_)) &&
+                        HOST_FUNCTIONS.contains(&import.name);
+                    if !sanctioned {
+                        findings.push(ContractFinding::UnsanctionedImport { module, name });
+                    }
+                }
+            }
+
+            Payload::CodeSectionEntry(body) => {
+                let mut reader = body.get_operators_reader().map_err(parse_error)?;
+                let mut operators = vec![];
+                while !reader.eof() {
+                    operators.push(reader.read().map_err(parse_error)?);
+                }
+
+                if operators.iter().any(is_float_arithmetic) {
+                    findings.push(ContractFinding::FloatArithmetic { function });
+                }
+
+                if has_unbounded_loop(&operators) {
+                    findings.push(ContractFinding::UnboundedLoop { function });
+                }
+
+                function += 1;
+            }
+
+            _ => {}
+        }
+    }
+
+    Ok(findings)
+}
+
+fn parse_error(e: BinaryReaderError) -> Error {
+    Error::WasmerCompileError(e.to_string())
+}
+
+/// Check if an operator is floating point arithmetic, which may produce
+/// NaNs with platform dependent bits. Loads, stores, constants and
+/// reinterpretations only move bits around and are deterministic.
+fn is_float_arithmetic(operator: &Operator) -> bool {
+    matches!(
+        operator,
+        Operator::F32Add |
+            Operator::F32Sub |
+            Operator::F32Mul |
+            Operator::F32Div |
+            Operator::F32Min |
+            Operator::F32Max |
+            Operator::F32Sqrt |
+            Operator::F32Ceil |
+            Operator::F32Floor |
+            Operator::F32Trunc |
+            Operator::F32Nearest |
+            Operator::F32DemoteF64 |
+            Operator::F64Add |
+            Operator::F64Sub |
+            Operator::F64Mul |
+            Operator::F64Div |
+            Operator::F64Min |
+            Operator::F64Max |
+            Operator::F64Sqrt |
+            Operator::F64Ceil |
+            Operator::F64Floor |
+            Operator::F64Trunc |
+            Operator::F64Nearest |
+            Operator::F64PromoteF32
+    )
+}
+
+/// Check if a function body has a loop that can't be left: its body ends
+/// by branching back to its start, and nothing in it branches outside of
+/// it, returns, traps or calls another function. Loops are metered like
+/// any other code, so such a loop only ever ends by exhausting the gas.
+fn has_unbounded_loop(operators: &[Operator]) -> bool {
+    // Open blocks, and whether each one is a loop that can be left.
+    // The function body itself is the outermost block.
+    let mut blocks: Vec<(bool, bool)> = vec![(false, true)];
+    let mut repeats = false;
+
+    for operator in operators {
+        match operator {
+            Operator::Block { .. } | Operator::If { .. } => blocks.push((false, false)),
+            Operator::Loop { .. } => blocks.push((true, false)),
+            Operator::End => {
+                if let Some((true, false)) = blocks.pop() {
+                    if repeats {
+                        return true
+                    }
+                }
+            }
+            Operator::Br { relative_depth } => {
+                // Branching to a block leaves every block nested in it
+                let target = blocks.len().saturating_sub(1 + *relative_depth as usize);
+                for block in blocks.iter_mut().skip(target + 1) {
+                    block.1 = true;
+                }
+            }
+            Operator::BrIf { .. } |
+            Operator::BrTable { .. } |
+            Operator::Return |
+            Operator::Unreachable |
+            Operator::Call { .. } |
+            Operator::CallIndirect { .. } => {
+                for block in blocks.iter_mut() {
+                    block.1 = true;
+                }
+            }
+            _ => {}
+        }
+
+        repeats = matches!(operator, Operator::Br { relative_depth: 0 });
+    }
+
+    false
+}
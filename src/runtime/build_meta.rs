@@ -0,0 +1,288 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    fs,
+    ops::Range,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use darkfi_serial::{async_trait, deserialize, serialize, SerialDecodable, SerialEncodable};
+use log::{debug, info};
+
+use crate::{Error, Result};
+
+/// Name of the wasm custom section holding a contract's build metadata
+pub const BUILD_METADATA_SECTION: &str = "darkfi.build";
+
+/// Extensions of the source files covered by the source hash
+const SOURCE_EXTENSIONS: [&str; 3] = ["rs", "toml", "zk"];
+
+/// wasm magic number followed by the binary format version
+const WASM_HEADER: [u8; 8] = [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+
+/// Build metadata embedded in a contract's wasm bincode, describing
+/// what it was compiled from.
+#[derive(Clone, Debug, PartialEq, Eq, SerialEncodable, SerialDecodable)]
+pub struct BuildMetadata {
+    /// Hash of the sources the contract was compiled from
+    pub source_hash: [u8; 32],
+    /// Version string of the compiler that produced the bincode
+    pub compiler_version: String,
+}
+
+impl BuildMetadata {
+    /// Create the build metadata of the given source roots, using the
+    /// local compiler's version.
+    pub fn current(sources: &[PathBuf]) -> Result<Self> {
+        Ok(Self { source_hash: hash_sources(sources)?, compiler_version: compiler_version()? })
+    }
+}
+
+/// Version string of the local `rustc`. When invoked through a rustup
+/// toolchain override (e.g. `cargo +nightly`), this is the overriding
+/// toolchain's version.
+pub fn compiler_version() -> Result<String> {
+    let output = Command::new("rustc").arg("--version").output()?;
+    if !output.status.success() {
+        return Err(Error::ContractBuildMetadata("Unable to query rustc version".to_string()))
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Hash the source files found under the given roots. Files are visited
+/// in sorted order and hashed together with their path relative to their
+/// root, so the result doesn't depend on where the sources live on disk.
+pub fn hash_sources(roots: &[PathBuf]) -> Result<[u8; 32]> {
+    let mut hasher = blake3::Hasher::new();
+
+    for root in roots {
+        let mut files = vec![];
+        collect_sources(root, &mut files)?;
+        files.sort();
+
+        for file in files {
+            let relative = file.strip_prefix(root).unwrap_or(&file);
+            let relative: Vec<_> =
+                relative.components().map(|c| c.as_os_str().to_string_lossy()).collect();
+            let relative = relative.join("/");
+            let contents = fs::read(&file)?;
+
+            hasher.update(&(relative.len() as u64).to_le_bytes());
+            hasher.update(relative.as_bytes());
+            hasher.update(&(contents.len() as u64).to_le_bytes());
+            hasher.update(&contents);
+        }
+    }
+
+    Ok(*hasher.finalize().as_bytes())
+}
+
+/// Recursively collect the source files under `dir`, skipping hidden
+/// entries and build output directories.
+fn collect_sources(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        if name.starts_with('.') || name == "target" {
+            continue
+        }
+
+        if path.is_dir() {
+            collect_sources(&path, files)?;
+            continue
+        }
+
+        let extension = path.extension().map(|e| e.to_string_lossy().to_string());
+        if SOURCE_EXTENSIONS.iter().any(|ext| extension.as_deref() == Some(*ext)) {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Read an unsigned LEB128 encoded `u32`, returning it along with the
+/// number of bytes consumed.
+fn read_leb128(buf: &[u8]) -> Result<(u32, usize)> {
+    let mut value = 0u32;
+    for (i, byte) in buf.iter().take(5).enumerate() {
+        value |= ((byte & 0x7f) as u32) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Ok((value, i + 1))
+        }
+    }
+
+    Err(Error::ContractBuildMetadata("Malformed LEB128 integer".to_string()))
+}
+
+/// Append an unsigned LEB128 encoded `u32` to `buf`.
+fn write_leb128(mut value: u32, buf: &mut Vec<u8>) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            buf.push(byte);
+            return
+        }
+        buf.push(byte | 0x80);
+    }
+}
+
+/// Split a wasm bincode into its sections, returning each section's id,
+/// its full byte range and the byte range of its payload.
+fn wasm_sections(wasm: &[u8]) -> Result<Vec<(u8, Range<usize>, Range<usize>)>> {
+    if wasm.len() < WASM_HEADER.len() || wasm[..WASM_HEADER.len()] != WASM_HEADER {
+        return Err(Error::ContractBuildMetadata("Invalid wasm header".to_string()))
+    }
+
+    let mut sections = vec![];
+    let mut pos = WASM_HEADER.len();
+    while pos < wasm.len() {
+        let start = pos;
+        let id = wasm[pos];
+        let (size, read) = read_leb128(&wasm[pos + 1..])?;
+        let payload = pos + 1 + read;
+        let Some(end) = payload.checked_add(size as usize).filter(|end| *end <= wasm.len()) else {
+            return Err(Error::ContractBuildMetadata("Truncated wasm section".to_string()))
+        };
+
+        sections.push((id, start..end, payload..end));
+        pos = end;
+    }
+
+    Ok(sections)
+}
+
+/// Split a custom section payload into its name and data.
+fn custom_section(payload: &[u8]) -> Result<(&[u8], &[u8])> {
+    let (len, read) = read_leb128(payload)?;
+    let Some(end) = read.checked_add(len as usize).filter(|end| *end <= payload.len()) else {
+        return Err(Error::ContractBuildMetadata("Truncated custom section name".to_string()))
+    };
+
+    Ok((&payload[read..end], &payload[end..]))
+}
+
+/// Remove the build metadata section from a wasm bincode, leaving the
+/// code the metadata describes.
+pub fn strip_build_metadata(wasm: &[u8]) -> Result<Vec<u8>> {
+    let mut stripped = WASM_HEADER.to_vec();
+    for (id, section, payload) in wasm_sections(wasm)? {
+        if id == 0 && custom_section(&wasm[payload])?.0 == BUILD_METADATA_SECTION.as_bytes() {
+            continue
+        }
+        stripped.extend_from_slice(&wasm[section]);
+    }
+
+    Ok(stripped)
+}
+
+/// Embed the given build metadata into a wasm bincode, replacing any
+/// metadata it already carries.
+pub fn embed_build_metadata(wasm: &[u8], metadata: &BuildMetadata) -> Result<Vec<u8>> {
+    let name = BUILD_METADATA_SECTION.as_bytes();
+    let mut payload = vec![];
+    write_leb128(name.len() as u32, &mut payload);
+    payload.extend_from_slice(name);
+    payload.extend_from_slice(&serialize(metadata));
+
+    let mut embedded = strip_build_metadata(wasm)?;
+    embedded.push(0);
+    write_leb128(payload.len() as u32, &mut embedded);
+    embedded.extend_from_slice(&payload);
+
+    Ok(embedded)
+}
+
+/// Read the build metadata embedded in a wasm bincode, if any.
+pub fn read_build_metadata(wasm: &[u8]) -> Result<Option<BuildMetadata>> {
+    for (id, _, payload) in wasm_sections(wasm)? {
+        if id != 0 {
+            continue
+        }
+
+        let (name, data) = custom_section(&wasm[payload])?;
+        if name == BUILD_METADATA_SECTION.as_bytes() {
+            let metadata =
+                deserialize(data).map_err(|e| Error::ContractBuildMetadata(e.to_string()))?;
+            return Ok(Some(metadata))
+        }
+    }
+
+    Ok(None)
+}
+
+/// Hash of a contract's code, excluding its build metadata.
+pub fn code_hash(wasm: &[u8]) -> Result<blake3::Hash> {
+    Ok(blake3::hash(&strip_build_metadata(wasm)?))
+}
+
+/// Verify a contract bincode against the sources it claims to be built
+/// from. The embedded source hash and compiler version must match the
+/// given source roots and the local toolchain, and recompiling `package`
+/// inside `workspace` must reproduce the same code.
+pub fn verify_contract_build(
+    wasm: &[u8],
+    package: &str,
+    workspace: &Path,
+    sources: &[PathBuf],
+) -> Result<()> {
+    let Some(metadata) = read_build_metadata(wasm)? else {
+        return Err(Error::ContractBuildMismatch("No build metadata embedded".to_string()))
+    };
+
+    let local = BuildMetadata::current(sources)?;
+    if metadata.source_hash != local.source_hash {
+        return Err(Error::ContractBuildMismatch("Source hash differs".to_string()))
+    }
+
+    if metadata.compiler_version != local.compiler_version {
+        return Err(Error::ContractBuildMismatch(format!(
+            "Built with {}, local compiler is {}",
+            metadata.compiler_version, local.compiler_version
+        )))
+    }
+
+    info!(target: "runtime::build_meta", "Recompiling {} for verification", package);
+    let output = Command::new("cargo")
+        .current_dir(workspace)
+        .args(["build", "--release", "--target", "wasm32-unknown-unknown", "--package", package])
+        .output()?;
+
+    if !output.status.success() {
+        debug!(target: "runtime::build_meta", "{}", String::from_utf8_lossy(&output.stderr));
+        return Err(Error::ContractBuildMetadata(format!("Failed to recompile {}", package)))
+    }
+
+    let artifact = workspace
+        .join("target/wasm32-unknown-unknown/release")
+        .join(format!("{}.wasm", package.replace('-', "_")));
+    let rebuilt = fs::read(artifact)?;
+
+    if code_hash(wasm)? != code_hash(&rebuilt)? {
+        return Err(Error::ContractBuildMismatch(format!(
+            "Recompiled {} doesn't reproduce the given bincode",
+            package
+        )))
+    }
+
+    Ok(())
+}
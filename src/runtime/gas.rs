@@ -0,0 +1,174 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Versioned gas schedules. The cost of executing a contract is given by
+//! the gas schedule in force at the height of the block being verified.
+//! Costs are tuned by adding a new schedule version activated through the
+//! feature framework, so historical blocks replay with the costs they were
+//! originally verified with.
+
+use darkfi_sdk::activation::{ActivationSchedule, Feature};
+use wasmer::wasmparser::Operator;
+
+/// Cost of wasm opcodes, by opcode class
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct OpcodeCosts {
+    /// Constants, locals, globals, numeric and other simple opcodes
+    pub simple: u64,
+    /// Linear memory loads, stores and bulk operations
+    pub memory: u64,
+    /// Blocks, branches and other control flow
+    pub control: u64,
+    /// Direct and indirect function calls
+    pub call: u64,
+    /// Linear memory growth, per `memory.grow` opcode
+    pub memory_grow: u64,
+}
+
+impl OpcodeCosts {
+    /// Cost of executing the given wasm opcode
+    pub fn cost(&self, operator: &Operator) -> u64 {
+        match operator {
+            Operator::Call { .. } | Operator::CallIndirect { .. } => self.call,
+
+            Operator::MemoryGrow { .. } => self.memory_grow,
+
+            Operator::Unreachable { .. } |
+            Operator::Block { .. } |
+            Operator::Loop { .. } |
+            Operator::If { .. } |
+            Operator::Else { .. } |
+            Operator::End { .. } |
+            Operator::Br { .. } |
+            Operator::BrIf { .. } |
+            Operator::BrTable { .. } |
+            Operator::Return { .. } => self.control,
+
+            Operator::I32Load { .. } |
+            Operator::I64Load { .. } |
+            Operator::F32Load { .. } |
+            Operator::F64Load { .. } |
+            Operator::I32Load8S { .. } |
+            Operator::I32Load8U { .. } |
+            Operator::I32Load16S { .. } |
+            Operator::I32Load16U { .. } |
+            Operator::I64Load8S { .. } |
+            Operator::I64Load8U { .. } |
+            Operator::I64Load16S { .. } |
+            Operator::I64Load16U { .. } |
+            Operator::I64Load32S { .. } |
+            Operator::I64Load32U { .. } |
+            Operator::I32Store { .. } |
+            Operator::I64Store { .. } |
+            Operator::F32Store { .. } |
+            Operator::F64Store { .. } |
+            Operator::I32Store8 { .. } |
+            Operator::I32Store16 { .. } |
+            Operator::I64Store8 { .. } |
+            Operator::I64Store16 { .. } |
+            Operator::I64Store32 { .. } |
+            Operator::MemorySize { .. } |
+            Operator::MemoryCopy { .. } |
+            Operator::MemoryFill { .. } |
+            Operator::MemoryInit { .. } => self.memory,
+
+            _ => self.simple,
+        }
+    }
+}
+
+/// Cost of the host functions exposed to contracts
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct HostCosts {
+    /// Per Poseidon permutation. The sponge absorbs two messages per
+    /// permutation.
+    pub poseidon_permutation: u64,
+    /// Per Pedersen commitment, dominated by its two scalar multiplications
+    pub pedersen_commitment: u64,
+}
+
+/// Cost of contract state access, per byte of key and value
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct StateIoCosts {
+    /// Per byte read through `db_get`
+    pub read_byte: u64,
+    /// Per byte written through `db_set`
+    pub write_byte: u64,
+}
+
+/// Gas costs of contract execution
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct GasSchedule {
+    /// Version of the schedule
+    pub version: u32,
+    /// Cost of wasm opcodes
+    pub opcodes: OpcodeCosts,
+    /// Cost of host functions
+    pub host: HostCosts,
+    /// Cost of contract state access
+    pub state_io: StateIoCosts,
+}
+
+impl GasSchedule {
+    /// Gas charged for reading `bytes` bytes of contract state
+    pub fn state_read(&self, bytes: usize) -> u64 {
+        self.state_io.read_byte.saturating_mul(bytes as u64)
+    }
+
+    /// Gas charged for writing `bytes` bytes of contract state
+    pub fn state_write(&self, bytes: usize) -> u64 {
+        self.state_io.write_byte.saturating_mul(bytes as u64)
+    }
+}
+
+/// Gas schedule in force since genesis. Every wasm opcode costs the same
+/// and state access is free.
+pub const GAS_SCHEDULE_V1: GasSchedule = GasSchedule {
+    version: 1,
+    opcodes: OpcodeCosts { simple: 1, memory: 1, control: 1, call: 1, memory_grow: 1 },
+    host: HostCosts { poseidon_permutation: 20_000, pedersen_commitment: 400_000 },
+    state_io: StateIoCosts { read_byte: 0, write_byte: 0 },
+};
+
+/// Gas schedule pricing memory access, calls and contract state access
+/// closer to their native cost. Activated by [`Feature::GasScheduleV2`].
+pub const GAS_SCHEDULE_V2: GasSchedule = GasSchedule {
+    version: 2,
+    opcodes: OpcodeCosts { simple: 1, memory: 2, control: 2, call: 10, memory_grow: 10_000 },
+    host: HostCosts { poseidon_permutation: 20_000, pedersen_commitment: 400_000 },
+    state_io: StateIoCosts { read_byte: 20, write_byte: 100 },
+};
+
+/// Gas schedules in activation order, along with the feature activating
+/// them. The first one applies from genesis.
+const GAS_SCHEDULES: [(Option<Feature>, &GasSchedule); 2] =
+    [(None, &GAS_SCHEDULE_V1), (Some(Feature::GasScheduleV2), &GAS_SCHEDULE_V2)];
+
+/// Gas schedule in force for the block at the given height: the latest one
+/// whose feature is active in the given activation schedule.
+pub fn gas_schedule(activations: &ActivationSchedule, height: u64) -> &'static GasSchedule {
+    GAS_SCHEDULES
+        .iter()
+        .rev()
+        .find(|(feature, _)| match feature {
+            Some(feature) => activations.is_active(*feature, height),
+            None => true,
+        })
+        .map(|(_, schedule)| *schedule)
+        .unwrap()
+}
@@ -16,21 +16,27 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::io::Cursor;
+use std::{collections::BTreeMap, io::Cursor};
 
 use darkfi_sdk::{
     crypto::ContractId,
     db::{
-        CALLER_ACCESS_DENIED, DB_CONTAINS_KEY_FAILED, DB_DEL_FAILED, DB_GET_FAILED, DB_INIT_FAILED,
-        DB_LOOKUP_FAILED, DB_SET_FAILED, DB_SUCCESS,
+        CALLER_ACCESS_DENIED, DB_CONTAINS_KEY_FAILED, DB_DEL_FAILED, DB_GAS_EXHAUSTED,
+        DB_GET_FAILED, DB_INIT_FAILED, DB_LOOKUP_FAILED, DB_SAVEPOINT_FAILED, DB_SET_FAILED,
+        DB_SUCCESS, MAX_SAVEPOINTS, ZKAS_NAMESPACE_IMMUTABLE, ZKAS_PUBLIC_INPUTS_MISMATCH,
     },
+    deploy::{zkas_ns_version, zkas_versioned_ns},
 };
 use darkfi_serial::{deserialize, serialize, Decodable};
 use log::{debug, error, info};
+use sled_overlay::SledDbOverlay;
 use wasmer::{FunctionEnvMut, WasmPtr};
 
 use crate::{
-    runtime::vm_runtime::{ContractSection, Env, SMART_CONTRACT_ZKAS_DB_NAME},
+    runtime::{
+        state_access::StateKey,
+        vm_runtime::{ContractSection, Env, SMART_CONTRACT_ZKAS_DB_NAME},
+    },
     zk::{empty_witnesses, VerifyingKey, ZkCircuit},
     zkas::ZkBinary,
 };
@@ -47,6 +53,33 @@ impl DbHandle {
     }
 }
 
+/// Savepoint taken by `db_savepoint`, holding the state writes recorded
+/// when it was taken, and the value each key held before it was first
+/// written after it, so the contract can roll back to it.
+pub struct Savepoint {
+    writes: BTreeMap<StateKey, Option<Vec<u8>>>,
+    undo: BTreeMap<StateKey, Option<Vec<u8>>>,
+}
+
+/// Before a key gets written, remember the value it holds in every open
+/// savepoint that hasn't seen it written yet, so rolling back can restore
+/// it. Returns `false` if the current value couldn't be read.
+pub(crate) fn save_undo(env: &Env, overlay: &SledDbOverlay, tree: &[u8; 32], key: &[u8]) -> bool {
+    let mut savepoints = env.savepoints.borrow_mut();
+    let state_key = (*tree, key.to_vec());
+    if savepoints.iter().all(|s| s.undo.contains_key(&state_key)) {
+        return true
+    }
+
+    let Ok(value) = overlay.get(tree, key) else { return false };
+    let value = value.map(|v| v.to_vec());
+    for savepoint in savepoints.iter_mut() {
+        savepoint.undo.entry(state_key.clone()).or_insert_with(|| value.clone());
+    }
+
+    true
+}
+
 /// Only deploy() can call this. Creates a new database instance for this contract.
 pub(crate) fn db_init(ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, len: u32) -> i32 {
     let env = ctx.data();
@@ -114,6 +147,7 @@ pub(crate) fn db_init(ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, len: u32) -> i
             return DB_INIT_FAILED
         }
     };
+    env.state_access.borrow_mut().structural = true;
 
     // TODO: Make sure we don't duplicate the DbHandle in the vec.
     //       It should behave like an ordered set.
@@ -270,20 +304,21 @@ pub(crate) fn db_set(ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, len: u32) -> i3
         return CALLER_ACCESS_DENIED
     }
 
-    if env
-        .blockchain
-        .lock()
-        .unwrap()
-        .overlay
-        .lock()
-        .unwrap()
-        .insert(&db_handle.tree, &key, &value)
-        .is_err()
+    if !env.charge_host_gas(env.gas_schedule.state_write(key.len() + value.len())) {
+        return DB_GAS_EXHAUSTED
+    }
+
+    let lock = env.blockchain.lock().unwrap();
+    let mut overlay = lock.overlay.lock().unwrap();
+    if !save_undo(env, &overlay, &db_handle.tree, &key) ||
+        overlay.insert(&db_handle.tree, &key, &value).is_err()
     {
         error!(target: "runtime::db::db_set()", "Couldn't insert to db_handle tree");
         return DB_SET_FAILED
     }
 
+    env.state_access.borrow_mut().write(&db_handle.tree, &key, &value);
+
     DB_SUCCESS
 }
 
@@ -353,12 +388,116 @@ pub(crate) fn db_del(ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, len: u32) -> i3
         return CALLER_ACCESS_DENIED
     }
 
-    if env.blockchain.lock().unwrap().overlay.lock().unwrap().remove(&db_handle.tree, &key).is_err()
+    let lock = env.blockchain.lock().unwrap();
+    let mut overlay = lock.overlay.lock().unwrap();
+    if !save_undo(env, &overlay, &db_handle.tree, &key) ||
+        overlay.remove(&db_handle.tree, &key).is_err()
     {
         error!(target: "runtime::db::db_del()", "Couldn't remove key from db_handle tree");
         return DB_DEL_FAILED
     }
 
+    env.state_access.borrow_mut().remove(&db_handle.tree, &key);
+
+    DB_SUCCESS
+}
+
+/// Only update() can call this. Takes a savepoint of the state written so
+/// far in this section, returning the savepoint's index.
+pub(crate) fn db_savepoint(ctx: FunctionEnvMut<Env>) -> i64 {
+    let env = ctx.data();
+
+    if env.contract_section != ContractSection::Update {
+        error!(target: "runtime::db::db_savepoint()", "db_savepoint called in unauthorized section");
+        return CALLER_ACCESS_DENIED as i64
+    }
+
+    let mut savepoints = env.savepoints.borrow_mut();
+    if savepoints.len() >= MAX_SAVEPOINTS {
+        error!(target: "runtime::db::db_savepoint()", "Too many open savepoints");
+        return DB_SAVEPOINT_FAILED as i64
+    }
+
+    // Keeping the recorded writes costs as much as writing them out. The
+    // values overwritten later are paid for when rolling back to them.
+    let writes = env.state_access.borrow().writes.clone();
+    let written: usize = writes
+        .iter()
+        .map(|((_, key), value)| key.len() + value.as_ref().map_or(0, |v| v.len()))
+        .sum();
+    if !env.charge_host_gas(env.gas_schedule.state_write(written)) {
+        return DB_GAS_EXHAUSTED as i64
+    }
+
+    savepoints.push(Savepoint { writes, undo: BTreeMap::new() });
+
+    (savepoints.len() - 1) as i64
+}
+
+/// Only update() can call this. Discards everything written since the given
+/// savepoint was taken. The savepoint stays open, while any taken after it
+/// are released.
+pub(crate) fn db_rollback(ctx: FunctionEnvMut<Env>, savepoint: u32) -> i32 {
+    let env = ctx.data();
+
+    if env.contract_section != ContractSection::Update {
+        error!(target: "runtime::db::db_rollback()", "db_rollback called in unauthorized section");
+        return CALLER_ACCESS_DENIED
+    }
+
+    let mut savepoints = env.savepoints.borrow_mut();
+    let Some(snapshot) = savepoints.get_mut(savepoint as usize) else {
+        error!(target: "runtime::db::db_rollback()", "Requested savepoint that is out of bounds");
+        return DB_SAVEPOINT_FAILED
+    };
+
+    // Restoring costs as much as writing the overwritten values back
+    let restored: usize = snapshot
+        .undo
+        .iter()
+        .map(|((_, key), value)| key.len() + value.as_ref().map_or(0, |v| v.len()))
+        .sum();
+    if !env.charge_host_gas(env.gas_schedule.state_write(restored)) {
+        return DB_GAS_EXHAUSTED
+    }
+
+    let lock = env.blockchain.lock().unwrap();
+    let mut overlay = lock.overlay.lock().unwrap();
+    for ((tree, key), value) in std::mem::take(&mut snapshot.undo) {
+        let res = match value {
+            Some(value) => overlay.insert(&tree, &key, &value),
+            None => overlay.remove(&tree, &key),
+        };
+        if res.is_err() {
+            error!(target: "runtime::db::db_rollback()", "Couldn't restore key in tree");
+            return DB_SAVEPOINT_FAILED
+        }
+    }
+
+    env.state_access.borrow_mut().rollback(snapshot.writes.clone());
+    savepoints.truncate(savepoint as usize + 1);
+
+    DB_SUCCESS
+}
+
+/// Only update() can call this. Releases the given savepoint and any taken
+/// after it, keeping what was written since.
+pub(crate) fn db_release(ctx: FunctionEnvMut<Env>, savepoint: u32) -> i32 {
+    let env = ctx.data();
+
+    if env.contract_section != ContractSection::Update {
+        error!(target: "runtime::db::db_release()", "db_release called in unauthorized section");
+        return CALLER_ACCESS_DENIED
+    }
+
+    let mut savepoints = env.savepoints.borrow_mut();
+    if savepoints.len() <= savepoint as usize {
+        error!(target: "runtime::db::db_release()", "Requested savepoint that is out of bounds");
+        return DB_SAVEPOINT_FAILED
+    }
+
+    savepoints.truncate(savepoint as usize);
+
     DB_SUCCESS
 }
 
@@ -433,6 +572,13 @@ pub(crate) fn db_get(ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, len: u32) -> i6
             }
         };
 
+    env.state_access.borrow_mut().read(&db_handle.tree, &key);
+
+    let read = key.len() + ret.as_ref().map_or(0, |v| v.len());
+    if !env.charge_host_gas(env.gas_schedule.state_read(read)) {
+        return DB_GAS_EXHAUSTED.into()
+    }
+
     let Some(return_data) = ret else {
         debug!(target: "runtime::db::db_get()", "returned empty vec");
         return -127
@@ -507,6 +653,8 @@ pub(crate) fn db_contains_key(ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, len: u
     let handle_idx = db_handle;
     let db_handle = &db_handles[handle_idx];
 
+    env.state_access.borrow_mut().read(&db_handle.tree, &key);
+
     match env.blockchain.lock().unwrap().overlay.lock().unwrap().contains_key(&db_handle.tree, &key)
     {
         Ok(v) => i32::from(v), // <- 0=false, 1=true
@@ -518,7 +666,8 @@ pub(crate) fn db_contains_key(ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, len: u
 }
 
 /// Only `deploy()` can call this. Given a zkas circuit, create a VerifyingKey and insert
-/// them both into the db.
+/// them both into the db. The circuit may be followed by the number of public inputs
+/// the contract declares for it, in which case the circuit is refused on mismatch.
 pub(crate) fn zkas_db_set(ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, len: u32) -> i32 {
     let env = ctx.data();
 
@@ -557,6 +706,27 @@ pub(crate) fn zkas_db_set(ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, len: u32)
         return DB_SET_FAILED
     };
 
+    // If the contract shipped a public input declaration, hold the circuit to it.
+    if (buf_reader.position() as usize) < buf_reader.get_ref().len() {
+        let declared: u32 = match Decodable::decode(&mut buf_reader) {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "runtime::db::zkas_db_set()", "Failed to decode public input declaration: {}", e);
+                return DB_SET_FAILED
+            }
+        };
+
+        let found = zkbin.public_inputs_count();
+        if found != declared as usize {
+            error!(
+                target: "runtime::db::zkas_db_set()",
+                "{} zkas circuit has {} public inputs, but contract {} declares {}",
+                zkbin.namespace, found, contract_id, declared,
+            );
+            return ZKAS_PUBLIC_INPUTS_MISMATCH
+        }
+    }
+
     // Because of `Runtime::Deploy`, we should be sure that the zkas db is index zero.
     let db_handles = env.db_handles.borrow();
     let db_handle = &db_handles[0];
@@ -568,7 +738,9 @@ pub(crate) fn zkas_db_set(ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, len: u32)
 
     // Check if there is existing bincode and compare it. Return DB_SUCCESS if
     // they're the same. The assumption should be that VerifyingKey was generated
-    // already so we can skip things after this guard.
+    // already so we can skip things after this guard. Namespaces are immutable,
+    // since historical proofs were made against the existing circuit, so a
+    // different bincode gets refused.
     match env
         .blockchain
         .lock()
@@ -588,6 +760,14 @@ pub(crate) fn zkas_db_set(ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, len: u32)
                     debug!(target: "runtime::db::zkas_db_set()", "Existing zkas bincode is the same. Skipping.");
                     return DB_SUCCESS
                 }
+
+                let (base, version) = zkas_ns_version(&zkbin.namespace);
+                error!(
+                    target: "runtime::db::zkas_db_set()",
+                    "{} zkas circuit of contract {} already exists, changed circuits must use a new namespace, e.g. {}",
+                    zkbin.namespace, contract_id, zkas_versioned_ns(base, version.saturating_add(1)),
+                );
+                return ZKAS_NAMESPACE_IMMUTABLE
             }
         }
         Err(e) => {
@@ -631,5 +811,7 @@ pub(crate) fn zkas_db_set(ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, len: u32)
         return DB_SET_FAILED
     }
 
+    env.state_access.borrow_mut().structural = true;
+
     DB_SUCCESS
 }
@@ -0,0 +1,126 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::io::Cursor;
+
+use darkfi_sdk::{
+    crypto::{pedersen_commitment_u64 as pedersen, poseidon_hash as poseidon},
+    error::{HOST_GAS_EXHAUSTED, INTERNAL_ERROR},
+    hash::POSEIDON_MAX_MESSAGES,
+    pasta::pallas,
+};
+use darkfi_serial::{serialize, Decodable};
+use log::error;
+use wasmer::{FunctionEnvMut, WasmPtr};
+
+use crate::runtime::vm_runtime::Env;
+
+/// Read the serialized arguments of a host function from the VM's memory.
+fn read_args(ctx: &FunctionEnvMut<Env>, ptr: WasmPtr<u8>, len: u32) -> Option<Vec<u8>> {
+    let env = ctx.data();
+    let memory_view = env.memory_view(ctx);
+
+    let Ok(mem_slice) = ptr.slice(&memory_view, len) else {
+        error!(target: "runtime::hash", "Failed to make slice from ptr");
+        return None
+    };
+
+    let mut buf = vec![0u8; len as usize];
+    if let Err(e) = mem_slice.read_slice(&mut buf) {
+        error!(target: "runtime::hash", "Failed to read from memory slice: {}", e);
+        return None
+    }
+
+    Some(buf)
+}
+
+/// Copy a host function's result to the VM, returning its object index.
+fn put_object(env: &Env, data: Vec<u8>) -> i64 {
+    let mut objects = env.objects.borrow_mut();
+    objects.push(data);
+    (objects.len() - 1) as i64
+}
+
+/// Poseidon hash of the `Vec<pallas::Base>` messages serialized at `ptr`.
+/// The hash is returned as an object.
+pub(crate) fn poseidon_hash(ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, len: u32) -> i64 {
+    let Some(buf) = read_args(&ctx, ptr, len) else { return INTERNAL_ERROR };
+
+    let messages: Vec<pallas::Base> = match Decodable::decode(&mut Cursor::new(buf)) {
+        Ok(v) => v,
+        Err(e) => {
+            error!(target: "runtime::hash", "Failed to decode poseidon messages: {}", e);
+            return INTERNAL_ERROR
+        }
+    };
+
+    if messages.is_empty() || messages.len() > POSEIDON_MAX_MESSAGES {
+        error!(target: "runtime::hash", "Unsupported poseidon message count: {}", messages.len());
+        return INTERNAL_ERROR
+    }
+
+    // The sponge absorbs two messages per permutation
+    let permutations = (messages.len() as u64 + 1) / 2;
+    let env = ctx.data();
+    if !env.charge_host_gas(permutations * env.gas_schedule.host.poseidon_permutation) {
+        return HOST_GAS_EXHAUSTED
+    }
+
+    // The hash is generic over the message count, so dispatch on it
+    macro_rules! hash {
+        ($($n:literal),+) => {
+            match messages.len() {
+                $($n => poseidon::<$n>(messages[..].try_into().unwrap()),)+
+                _ => unreachable!(),
+            }
+        };
+    }
+
+    let hash = hash!(1, 2, 3, 4, 5, 6, 7, 8);
+    put_object(env, serialize(&hash))
+}
+
+/// Pedersen commitment to the `(u64, pallas::Scalar)` value and blind
+/// serialized at `ptr`. The commitment is returned as an object.
+pub(crate) fn pedersen_commitment_u64(ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, len: u32) -> i64 {
+    let Some(buf) = read_args(&ctx, ptr, len) else { return INTERNAL_ERROR };
+
+    let mut buf_reader = Cursor::new(buf);
+    let value: u64 = match Decodable::decode(&mut buf_reader) {
+        Ok(v) => v,
+        Err(e) => {
+            error!(target: "runtime::hash", "Failed to decode pedersen commitment value: {}", e);
+            return INTERNAL_ERROR
+        }
+    };
+
+    let blind: pallas::Scalar = match Decodable::decode(&mut buf_reader) {
+        Ok(v) => v,
+        Err(e) => {
+            error!(target: "runtime::hash", "Failed to decode pedersen commitment blind: {}", e);
+            return INTERNAL_ERROR
+        }
+    };
+
+    let env = ctx.data();
+    if !env.charge_host_gas(env.gas_schedule.host.pedersen_commitment) {
+        return HOST_GAS_EXHAUSTED
+    }
+
+    put_object(env, serialize(&pedersen(value, blind)))
+}
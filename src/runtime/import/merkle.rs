@@ -18,12 +18,15 @@
 
 use std::io::Cursor;
 
-use darkfi_sdk::crypto::{MerkleNode, MerkleTree};
-use darkfi_serial::{serialize, Decodable, Encodable, WriteExt};
+use darkfi_sdk::crypto::{MerkleFrontier, MerkleNode, MerkleTree};
+use darkfi_serial::{deserialize, serialize, Decodable, Encodable, WriteExt};
 use log::{debug, error};
 use wasmer::{FunctionEnvMut, WasmPtr};
 
-use crate::runtime::vm_runtime::{ContractSection, Env};
+use crate::runtime::{
+    import::db::save_undo,
+    vm_runtime::{ContractSection, Env},
+};
 
 pub(crate) fn merkle_add(ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, len: u32) -> i32 {
     let env = ctx.data();
@@ -191,10 +194,15 @@ pub(crate) fn merkle_add(ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, len: u32) -
             // Apply changes to overlay
             let lock = env.blockchain.lock().unwrap();
             let mut overlay = lock.overlay.lock().unwrap();
-            if overlay.insert(&db_info.tree, &tree_key, &tree_data).is_err() {
+            let mut state_access = env.state_access.borrow_mut();
+            state_access.read(&db_info.tree, &tree_key);
+            if !save_undo(env, &overlay, &db_info.tree, &tree_key) ||
+                overlay.insert(&db_info.tree, &tree_key, &tree_data).is_err()
+            {
                 error!(target: "runtime::merkle", "Couldn't insert to db_info tree");
                 return -2
             }
+            state_access.write(&db_info.tree, &tree_key, &tree_data);
 
             // Here we add the Merkle root to our set of roots
             // TODO: We should probably make sure that this root isn't in the set
@@ -206,21 +214,192 @@ pub(crate) fn merkle_add(ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, len: u32) -
                 let root_value: Vec<u8> = serialize(root);
                 // FIXME: This assert can be used to DoS nodes from contracts
                 assert_eq!(root_value.len(), 32);
-                if overlay.insert(&db_roots.tree, &root_value, &[]).is_err() {
+                if !save_undo(env, &overlay, &db_roots.tree, &root_value) ||
+                    overlay.insert(&db_roots.tree, &root_value, &[]).is_err()
+                {
                     error!(target: "runtime::merkle", "Couldn't insert to db_roots tree");
                     return -2
                 }
+                state_access.write(&db_roots.tree, &root_value, &[]);
             }
 
             // Write a pointer to the latest known root
             if !new_roots.is_empty() {
                 debug!(target: "runtime::merkle", "Replacing latest Merkle root pointer");
                 let latest_root = serialize(new_roots.last().unwrap());
-                if overlay.insert(&db_info.tree, &root_key, &latest_root).is_err() {
+                if !save_undo(env, &overlay, &db_info.tree, &root_key) ||
+                    overlay.insert(&db_info.tree, &root_key, &latest_root).is_err()
+                {
                     error!(target: "runtime::merkle", "Couldn't insert latest root to db_info tree");
                     return -2
                 }
+                state_access.write(&db_info.tree, &root_key, &latest_root);
+            }
+
+            0
+        }
+        _ => -1,
+    }
+}
+
+/// Batched variant of [`merkle_add`] operating on a persisted [`MerkleFrontier`].
+/// All the coins are appended before a single root is computed, so the hash
+/// work and the overlay writes are done once per call rather than once per coin.
+pub(crate) fn merkle_frontier_add(ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, len: u32) -> i32 {
+    let env = ctx.data();
+    match env.contract_section {
+        ContractSection::Update => {
+            let memory_view = env.memory_view(&ctx);
+
+            let Ok(mem_slice) = ptr.slice(&memory_view, len) else {
+                error!(target: "runtime::merkle", "Failed to make slice from ptr");
+                return -2
+            };
+
+            let mut buf = vec![0_u8; len as usize];
+            if let Err(e) = mem_slice.read_slice(&mut buf) {
+                error!(target: "runtime::merkle", "Failed to read from memory slice: {}", e);
+                return -2
+            };
+
+            // The buffer should deserialize into:
+            // - db_info
+            // - db_roots
+            // - root_key (as Vec<u8>) (key being the name of the sled key in info_db where the latest root is)
+            // - frontier_key (as Vec<u8>) (key being the name of the sled key in info_db where the Merkle frontier is)
+            // - coins (as Vec<MerkleNode>) (the coins being added into the Merkle tree)
+            let mut buf_reader = Cursor::new(buf);
+            let db_info: u32 = match Decodable::decode(&mut buf_reader) {
+                Ok(v) => v,
+                Err(e) => {
+                    error!(target: "runtime::merkle", "Failed to decode db_info DbHandle: {}", e);
+                    return -2
+                }
+            };
+
+            let db_roots: u32 = match Decodable::decode(&mut buf_reader) {
+                Ok(v) => v,
+                Err(e) => {
+                    error!(target: "runtime::merkle", "Failed to decode db_roots DbHandle: {}", e);
+                    return -2
+                }
+            };
+
+            let db_info = db_info as usize;
+            let db_roots = db_roots as usize;
+            let db_handles = env.db_handles.borrow();
+            let n_dbs = db_handles.len();
+
+            if n_dbs <= db_info || n_dbs <= db_roots {
+                error!(target: "runtime::merkle", "Requested DbHandle that is out of bounds");
+                return -2
+            }
+
+            let db_info = &db_handles[db_info];
+            let db_roots = &db_handles[db_roots];
+
+            if db_info.contract_id != env.contract_id || db_roots.contract_id != env.contract_id {
+                error!(target: "runtime::merkle", "Unauthorized to write to DbHandle");
+                return -2
+            }
+
+            let root_key: Vec<u8> = match Decodable::decode(&mut buf_reader) {
+                Ok(v) => v,
+                Err(e) => {
+                    error!(target: "runtime::merkle", "Failed to decode key vec: {}", e);
+                    return -2
+                }
+            };
+
+            let frontier_key: Vec<u8> = match Decodable::decode(&mut buf_reader) {
+                Ok(v) => v,
+                Err(e) => {
+                    error!(target: "runtime::merkle", "Failed to decode key vec: {}", e);
+                    return -2
+                }
+            };
+
+            let coins: Vec<MerkleNode> = match Decodable::decode(&mut buf_reader) {
+                Ok(v) => v,
+                Err(e) => {
+                    error!(target: "runtime::merkle", "Failed to decode MerkleNode: {}", e);
+                    return -2
+                }
+            };
+
+            if buf_reader.position() != len as u64 {
+                error!(target: "runtime::merkle", "Trailing bytes in argument buffer");
+                return -2
+            }
+
+            // Nothing to do, and we don't want to record the same root twice
+            if coins.is_empty() {
+                return 0
+            }
+
+            let lock = env.blockchain.lock().unwrap();
+            let mut overlay = lock.overlay.lock().unwrap();
+            let mut state_access = env.state_access.borrow_mut();
+
+            // Read the current frontier
+            let frontier_data = match overlay.get(&db_info.tree, &frontier_key) {
+                Ok(Some(v)) => v,
+                Ok(None) => {
+                    error!(target: "runtime::merkle", "Merkle frontier not found in db");
+                    return -2
+                }
+                Err(e) => {
+                    error!(target: "runtime::merkle", "Internal error getting from tree: {}", e);
+                    return -2
+                }
+            };
+            state_access.read(&db_info.tree, &frontier_key);
+
+            let mut frontier: MerkleFrontier = match deserialize(&frontier_data) {
+                Ok(v) => v,
+                Err(e) => {
+                    error!(target: "runtime::merkle", "Unable to deserialize frontier: {}", e);
+                    return -2
+                }
+            };
+
+            // Append the whole batch, and only then compute the new root
+            for coin in coins {
+                if !frontier.append(coin) {
+                    error!(target: "runtime::merkle", "Merkle tree is full");
+                    return -2
+                }
+            }
+            let root = frontier.root();
+
+            // Apply changes to overlay
+            let frontier_data = serialize(&frontier);
+            if !save_undo(env, &overlay, &db_info.tree, &frontier_key) ||
+                overlay.insert(&db_info.tree, &frontier_key, &frontier_data).is_err()
+            {
+                error!(target: "runtime::merkle", "Couldn't insert to db_info tree");
+                return -2
+            }
+            state_access.write(&db_info.tree, &frontier_key, &frontier_data);
+
+            debug!(target: "runtime::merkle", "Appending Merkle root to db: {:?}", root);
+            let root_value = serialize(&root);
+            if !save_undo(env, &overlay, &db_roots.tree, &root_value) ||
+                overlay.insert(&db_roots.tree, &root_value, &[]).is_err()
+            {
+                error!(target: "runtime::merkle", "Couldn't insert to db_roots tree");
+                return -2
+            }
+            state_access.write(&db_roots.tree, &root_value, &[]);
+
+            debug!(target: "runtime::merkle", "Replacing latest Merkle root pointer");
+            if !save_undo(env, &overlay, &db_info.tree, &root_key) ||
+                overlay.insert(&db_info.tree, &root_key, &root_value).is_err()
+            {
+                error!(target: "runtime::merkle", "Couldn't insert latest root to db_info tree");
+                return -2
             }
+            state_access.write(&db_info.tree, &root_key, &root_value);
 
             0
         }
@@ -19,6 +19,9 @@
 /// Host functions for interacting with db backend
 pub(crate) mod db;
 
+/// Host functions for hashing
+pub(crate) mod hash;
+
 /// Host functions for merkle tree functions
 pub(crate) mod merkle;
 
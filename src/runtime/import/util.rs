@@ -16,7 +16,13 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use darkfi_sdk::db::{CALLER_ACCESS_DENIED, DB_GET_FAILED};
+use darkfi_sdk::{
+    activation::{Feature, ACTIVATIONS},
+    crypto::MONEY_CONTRACT_ID,
+    db::{CALLER_ACCESS_DENIED, DB_GET_FAILED},
+    log::ContractTrace,
+};
+use darkfi_serial::deserialize;
 use log::error;
 use wasmer::{FunctionEnvMut, WasmPtr};
 
@@ -40,6 +46,31 @@ pub(crate) fn drk_log(ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, len: u32) {
     }
 }
 
+/// Host function for emitting structured debug traces.
+/// This is a no-op unless tracing was enabled on the runtime.
+pub(crate) fn drk_trace(ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, len: u32) {
+    let env = ctx.data();
+    if !env.tracing {
+        return
+    }
+
+    let memory_view = env.memory_view(&ctx);
+    let Ok(slice) = ptr.slice(&memory_view, len) else {
+        error!(target: "runtime::util", "Failed to make slice from ptr");
+        return
+    };
+
+    let Ok(trace) = slice.read_to_vec() else {
+        error!(target: "runtime::util", "Failed to read trace from VM memory");
+        return
+    };
+
+    match deserialize::<ContractTrace>(&trace) {
+        Ok(trace) => env.traces.borrow_mut().push(trace),
+        Err(_) => error!(target: "runtime::util", "Failed to deserialize contract trace"),
+    }
+}
+
 pub(crate) fn set_return_data(ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, len: u32) -> i64 {
     let env = ctx.data();
     match env.contract_section {
@@ -65,6 +96,35 @@ pub(crate) fn set_return_data(ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, len: u
     }
 }
 
+/// Host function for a contract to describe why the current call is failing.
+/// Only the first report of a call is kept.
+pub(crate) fn set_error_report(ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, len: u32) -> i64 {
+    let env = ctx.data();
+    let memory_view = env.memory_view(&ctx);
+
+    let Ok(slice) = ptr.slice(&memory_view, len) else { return darkfi_sdk::error::INTERNAL_ERROR };
+
+    let Ok(report) = slice.read_to_vec() else { return darkfi_sdk::error::INTERNAL_ERROR };
+
+    let previous = env.contract_error_report.take();
+    env.contract_error_report.set(previous.or(Some(report)));
+    0
+}
+
+/// Host function for the native money contract to report the fee collected
+/// by the current call. Fees reported multiple times during a call add up.
+pub(crate) fn set_fee_paid(ctx: FunctionEnvMut<Env>, fee: u64) -> i64 {
+    let env = ctx.data();
+
+    if env.contract_id != *MONEY_CONTRACT_ID || env.contract_section != ContractSection::Exec {
+        error!(target: "runtime::util", "set_fee_paid called by unauthorized contract or section");
+        return darkfi_sdk::error::CALLER_ACCESS_DENIED
+    }
+
+    env.fee_paid.set(env.fee_paid.get().saturating_add(fee));
+    0
+}
+
 pub(crate) fn put_object_bytes(ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, len: u32) -> i64 {
     let env = ctx.data();
     let memory_view = env.memory_view(&ctx);
@@ -200,3 +260,14 @@ pub(crate) fn get_slot(ctx: FunctionEnvMut<Env>, slot: u64) -> i64 {
 pub(crate) fn get_blockchain_time(ctx: FunctionEnvMut<Env>) -> u64 {
     ctx.data().time_keeper.blockchain_timestamp()
 }
+
+/// Will return 1 if the given consensus feature is active for the block
+/// being verified, 0 otherwise.
+pub(crate) fn is_feature_active(ctx: FunctionEnvMut<Env>, feature: u32) -> i64 {
+    let Ok(feature) = Feature::try_from(feature) else {
+        error!(target: "runtime::util", "is_feature_active called with unknown feature {}", feature);
+        return darkfi_sdk::error::UNKNOWN_FEATURE
+    };
+
+    ACTIVATIONS.is_active(feature, ctx.data().time_keeper.verifying_slot) as i64
+}
@@ -19,6 +19,18 @@
 /// Main wasm vm runtime implementation
 pub mod vm_runtime;
 
+/// Versioned gas schedules for contract execution
+pub mod gas;
+
+/// Tracking of contract state accessed during execution
+pub mod state_access;
+
+/// Contract build metadata embedding and verification
+pub mod build_meta;
+
+/// Static analysis of contract wasm bincodes
+pub mod analysis;
+
 /// VM memory access (read/write)
 pub(crate) mod memory;
 
@@ -0,0 +1,100 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use darkfi_sdk::tx::AccessList;
+
+use crate::{blockchain::BlockchainOverlayPtr, Result};
+
+/// A key within a contract state tree, identified by the tree's handle
+pub type StateKey = ([u8; 32], Vec<u8>);
+
+/// Record of the contract state touched while executing transactions.
+///
+/// Reads are tracked as a set of keys, while writes keep the final value
+/// written (`None` for removals) so they can be replayed onto another
+/// overlay with [`StateAccess::replay`].
+#[derive(Clone, Debug, Default)]
+pub struct StateAccess {
+    /// Keys read through `db_get`, `db_contains_key` and the `merkle_*` functions
+    pub reads: BTreeSet<StateKey>,
+    /// Keys written through `db_set`, `db_del` and the `merkle_*` functions
+    pub writes: BTreeMap<StateKey, Option<Vec<u8>>>,
+    /// Set when the execution created trees, deployed code or otherwise
+    /// changed state outside of plain key writes. Such executions can't
+    /// be replayed and must run sequentially.
+    pub structural: bool,
+}
+
+impl StateAccess {
+    pub fn read(&mut self, tree: &[u8; 32], key: &[u8]) {
+        self.reads.insert((*tree, key.to_vec()));
+    }
+
+    pub fn write(&mut self, tree: &[u8; 32], key: &[u8], value: &[u8]) {
+        self.writes.insert((*tree, key.to_vec()), Some(value.to_vec()));
+    }
+
+    pub fn remove(&mut self, tree: &[u8; 32], key: &[u8]) {
+        self.writes.insert((*tree, key.to_vec()), None);
+    }
+
+    /// Discard the writes made since `writes` was taken, restoring it as the
+    /// recorded writes. The discarded keys are kept as reads, since whether
+    /// they were written depended on the state they held.
+    pub fn rollback(&mut self, writes: BTreeMap<StateKey, Option<Vec<u8>>>) {
+        let discarded = std::mem::replace(&mut self.writes, writes);
+        self.reads.extend(discarded.into_keys());
+    }
+
+    /// Merge the accesses of a subsequent execution into this one.
+    pub fn extend(&mut self, other: StateAccess) {
+        self.reads.extend(other.reads);
+        self.writes.extend(other.writes);
+        self.structural |= other.structural;
+    }
+
+    /// Returns `true` if anything this execution touched was written by `other`.
+    pub fn depends_on(&self, other: &StateAccess) -> bool {
+        self.reads.iter().chain(self.writes.keys()).any(|key| other.writes.contains_key(key))
+    }
+
+    /// Returns `true` if everything this execution touched was declared in
+    /// the given access list.
+    pub fn within(&self, access_list: &AccessList) -> bool {
+        self.reads.iter().all(|(tree, key)| access_list.may_read(tree, key)) &&
+            self.writes.keys().all(|(tree, key)| access_list.may_write(tree, key))
+    }
+
+    /// Apply the recorded writes onto the given overlay.
+    pub fn replay(&self, overlay: &BlockchainOverlayPtr) -> Result<()> {
+        let lock = overlay.lock().unwrap();
+        let mut overlay = lock.overlay.lock().unwrap();
+
+        for ((tree, key), value) in &self.writes {
+            overlay.open_tree(tree)?;
+            match value {
+                Some(value) => overlay.insert(tree, key, value)?,
+                None => overlay.remove(tree, key)?,
+            };
+        }
+
+        Ok(())
+    }
+}
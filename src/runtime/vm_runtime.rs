@@ -21,8 +21,15 @@ use std::{
     sync::Arc,
 };
 
-use darkfi_sdk::{crypto::ContractId, entrypoint};
-use darkfi_serial::serialize;
+use darkfi_sdk::{
+    activation::ACTIVATIONS,
+    crypto::ContractId,
+    entrypoint,
+    error::{ContractError, ContractErrorReport},
+    log::ContractTrace,
+    tx::AccessList,
+};
+use darkfi_serial::{deserialize, serialize};
 use log::{debug, error, info};
 use wasmer::{
     imports, wasmparser::Operator, AsStoreRef, CompilerConfig, Function, FunctionEnv, Instance,
@@ -34,14 +41,20 @@ use wasmer_middlewares::{
     Metering,
 };
 
-use super::{import, import::db::DbHandle, memory::MemoryManipulation};
+use super::{
+    gas::{gas_schedule, GasSchedule},
+    import,
+    import::db::{DbHandle, Savepoint},
+    memory::MemoryManipulation,
+    state_access::StateAccess,
+};
 use crate::{blockchain::BlockchainOverlayPtr, util::time::TimeKeeper, Error, Result};
 
 /// Name of the wasm linear memory in our guest module
 const MEMORY: &str = "memory";
 
 /// Gas limit for a contract
-const GAS_LIMIT: u64 = 400_000_000;
+pub(crate) const GAS_LIMIT: u64 = 400_000_000;
 
 /// The hardcoded db name for the zkas circuits database tree
 pub const SMART_CONTRACT_ZKAS_DB_NAME: &str = "_zkas";
@@ -86,14 +99,32 @@ pub struct Env {
     pub contract_section: ContractSection,
     /// State update produced by a smart contract function call
     pub contract_return_data: Cell<Option<Vec<u8>>>,
+    /// Serialized error report of a failing smart contract function call
+    pub contract_error_report: Cell<Option<Vec<u8>>>,
+    /// Fee collected by the smart contract function call
+    pub fee_paid: Cell<u64>,
+    /// Gas charged by host functions, which the metering doesn't see
+    pub host_gas: Cell<u64>,
+    /// Gas schedule in force for the block being verified
+    pub gas_schedule: &'static GasSchedule,
     /// Logs produced by the contract
     pub logs: RefCell<Vec<String>>,
+    /// Whether structured debug traces emitted by the contract are collected
+    pub tracing: bool,
+    /// Structured debug traces emitted by the contract
+    pub traces: RefCell<Vec<ContractTrace>>,
     /// Direct memory access to the VM
     pub memory: Option<Memory>,
     /// Object store for transferring memory from the host to VM
     pub objects: RefCell<Vec<Vec<u8>>>,
     /// Helper structure to calculate time related operations
     pub time_keeper: TimeKeeper,
+    /// Contract state read and written by this runtime
+    pub state_access: RefCell<StateAccess>,
+    /// Contract state the executed transaction declared it touches
+    pub access_list: Option<AccessList>,
+    /// Savepoints taken by the contract in the section being executed
+    pub savepoints: RefCell<Vec<Savepoint>>,
 }
 
 impl Env {
@@ -112,6 +143,22 @@ impl Env {
     pub fn memory(&self) -> &Memory {
         self.memory.as_ref().unwrap()
     }
+
+    /// Charge the given amount of gas for a host function, failing if the
+    /// runtime's host function gas would exceed the limit.
+    pub(crate) fn charge_host_gas(&self, gas: u64) -> bool {
+        let host_gas = self.host_gas.get().saturating_add(gas);
+        if host_gas > GAS_LIMIT {
+            error!(
+                target: "runtime::vm_runtime",
+                "Host function gas exhausted: {}/{}", host_gas, GAS_LIMIT,
+            );
+            return false
+        }
+
+        self.host_gas.set(host_gas);
+        true
+    }
 }
 
 pub struct Runtime {
@@ -129,12 +176,17 @@ impl Runtime {
         time_keeper: TimeKeeper,
     ) -> Result<Self> {
         info!(target: "runtime::vm_runtime", "Instantiating a new runtime");
+        // Costs are given by the gas schedule in force for the block being
+        // verified, so historical blocks replay with their original costs.
+        let gas_schedule = gas_schedule(&ACTIVATIONS, time_keeper.verifying_slot);
+        debug!(target: "runtime::vm_runtime", "Using gas schedule v{}", gas_schedule.version);
+
         // This function will be called for each `Operator` encountered during
         // the wasm module execution. It should return the cost of the operator
-        // that it received as its first argument. For now, every wasm opcode
-        // has a cost of `1`.
+        // that it received as its first argument.
         // https://docs.rs/wasmparser/latest/wasmparser/enum.Operator.html
-        let cost_function = |_operator: &Operator| -> u64 { 1 };
+        let cost_function =
+            move |operator: &Operator| -> u64 { gas_schedule.opcodes.cost(operator) };
 
         // `Metering` needs to be conigured with a limit and a cost function.
         // For each `Operator`, the metering middleware will call the cost
@@ -164,10 +216,19 @@ impl Runtime {
                 contract_bincode: wasm_bytes.to_vec(),
                 contract_section: ContractSection::Null,
                 contract_return_data: Cell::new(None),
+                contract_error_report: Cell::new(None),
+                fee_paid: Cell::new(0),
+                host_gas: Cell::new(0),
+                gas_schedule,
                 logs,
+                tracing: false,
+                traces: RefCell::new(vec![]),
                 memory: None,
                 objects: RefCell::new(vec![]),
                 time_keeper,
+                state_access: RefCell::new(StateAccess::default()),
+                access_list: None,
+                savepoints: RefCell::new(vec![]),
             },
         );
 
@@ -179,12 +240,30 @@ impl Runtime {
                     import::util::drk_log,
                 ),
 
+                "drk_trace_" => Function::new_typed_with_env(
+                    &mut store,
+                    &ctx,
+                    import::util::drk_trace,
+                ),
+
                 "set_return_data_" => Function::new_typed_with_env(
                     &mut store,
                     &ctx,
                     import::util::set_return_data,
                 ),
 
+                "set_error_report_" => Function::new_typed_with_env(
+                    &mut store,
+                    &ctx,
+                    import::util::set_error_report,
+                ),
+
+                "set_fee_paid_" => Function::new_typed_with_env(
+                    &mut store,
+                    &ctx,
+                    import::util::set_fee_paid,
+                ),
+
                 "db_init_" => Function::new_typed_with_env(
                     &mut store,
                     &ctx,
@@ -221,6 +300,24 @@ impl Runtime {
                     import::db::db_del,
                 ),
 
+                "db_savepoint_" => Function::new_typed_with_env(
+                    &mut store,
+                    &ctx,
+                    import::db::db_savepoint,
+                ),
+
+                "db_rollback_" => Function::new_typed_with_env(
+                    &mut store,
+                    &ctx,
+                    import::db::db_rollback,
+                ),
+
+                "db_release_" => Function::new_typed_with_env(
+                    &mut store,
+                    &ctx,
+                    import::db::db_release,
+                ),
+
                 "zkas_db_set_" => Function::new_typed_with_env(
                     &mut store,
                     &ctx,
@@ -251,6 +348,12 @@ impl Runtime {
                     import::merkle::merkle_add,
                 ),
 
+                "merkle_frontier_add_" => Function::new_typed_with_env(
+                    &mut store,
+                    &ctx,
+                    import::merkle::merkle_frontier_add,
+                ),
+
                 "get_current_epoch_" => Function::new_typed_with_env(
                     &mut store,
                     &ctx,
@@ -286,6 +389,24 @@ impl Runtime {
                     &ctx,
                     import::util::get_blockchain_time,
                 ),
+
+                "is_feature_active_" => Function::new_typed_with_env(
+                    &mut store,
+                    &ctx,
+                    import::util::is_feature_active,
+                ),
+
+                "poseidon_hash_" => Function::new_typed_with_env(
+                    &mut store,
+                    &ctx,
+                    import::hash::poseidon_hash,
+                ),
+
+                "pedersen_commitment_u64_" => Function::new_typed_with_env(
+                    &mut store,
+                    &ctx,
+                    import::hash::pedersen_commitment_u64,
+                ),
             }
         };
 
@@ -318,8 +439,11 @@ impl Runtime {
         env_mut.contract_section = section;
         assert!(env_mut.contract_return_data.take().is_none());
         env_mut.contract_return_data.set(None);
+        env_mut.contract_error_report.set(None);
         // Clear the logs
         let _ = env_mut.logs.take();
+        // Savepoints don't outlive the section they were taken in
+        let _ = env_mut.savepoints.take();
 
         // Serialize the payload for the format the wasm runtime is expecting.
         let payload = Self::serialize_payload(&env_mut.contract_id, payload);
@@ -363,15 +487,28 @@ impl Runtime {
             _ => unreachable!("Got unexpected result from ret: {:?}", ret),
         };
 
-        match retval {
-            entrypoint::SUCCESS => Ok(retdata),
-            // FIXME: we should be able to see the error returned from the contract
-            // We can put sdk::Error inside of this.
-            _ => {
-                let err = darkfi_sdk::error::ContractError::from(retval);
-                Err(Error::ContractError(err))
+        if let Some(access_list) = &env_mut.access_list {
+            if !env_mut.state_access.borrow().within(access_list) {
+                error!(target: "runtime::vm_runtime", "Contract accessed state outside of the declared access list");
+                return Err(Error::UndeclaredStateAccess)
             }
         }
+
+        if retval == entrypoint::SUCCESS {
+            return Ok(retdata)
+        }
+
+        // Prefer the contract's own description of the failure, falling back
+        // to one derived from the returned error code.
+        let report = env_mut
+            .contract_error_report
+            .take()
+            .and_then(|report| deserialize::<ContractErrorReport>(&report).ok())
+            .filter(|report| report.code == retval)
+            .unwrap_or_else(|| ContractErrorReport::from(&ContractError::from(retval)));
+
+        error!(target: "runtime::vm_runtime", "Contract {} failed: {}", env_mut.contract_id, report);
+        Err(Error::ContractFailure(report))
     }
 
     /// This function runs when a smart contract is initially deployed, or re-deployed.
@@ -415,6 +552,7 @@ impl Runtime {
 
         // Update the wasm bincode in the WasmStore
         let env_mut = self.ctx.as_mut(&mut self.store);
+        env_mut.state_access.borrow_mut().structural = true;
         env_mut
             .blockchain
             .lock()
@@ -454,6 +592,29 @@ impl Runtime {
         self.call(ContractSection::Metadata, payload)
     }
 
+    /// Restrict the contract state this runtime may access to the given
+    /// declaration. Calls touching anything else will fail.
+    pub fn set_access_list(&mut self, access_list: Option<AccessList>) {
+        self.ctx.as_mut(&mut self.store).access_list = access_list;
+    }
+
+    /// Take the record of contract state accessed by this runtime so far.
+    pub fn take_state_access(&mut self) -> StateAccess {
+        self.ctx.as_mut(&mut self.store).state_access.take()
+    }
+
+    /// Enable or disable collecting structured debug traces emitted by the
+    /// contract. This is disabled by default, so consensus verification
+    /// ignores any traces.
+    pub fn set_tracing(&mut self, tracing: bool) {
+        self.ctx.as_mut(&mut self.store).tracing = tracing;
+    }
+
+    /// Take the structured debug traces the contract emitted so far.
+    pub fn take_traces(&mut self) -> Vec<ContractTrace> {
+        self.ctx.as_mut(&mut self.store).traces.take()
+    }
+
     fn print_logs(&self) {
         let logs = self.ctx.as_ref(&self.store).logs.borrow();
         for msg in logs.iter() {
@@ -461,13 +622,23 @@ impl Runtime {
         }
     }
 
-    fn gas_used(&mut self) -> u64 {
+    /// Fee collected by the contract call executed on this runtime.
+    pub fn fee_paid(&self) -> u64 {
+        self.ctx.as_ref(&self.store).fee_paid.get()
+    }
+
+    /// Gas used by all the sections executed on this runtime so far,
+    /// including the gas charged by host functions.
+    pub fn gas_used(&mut self) -> u64 {
         let remaining_points = get_remaining_points(&mut self.store, &self.instance);
+        let host_gas = self.ctx.as_ref(&self.store).host_gas.get();
 
-        match remaining_points {
+        let wasm_gas = match remaining_points {
             MeteringPoints::Remaining(rem) => GAS_LIMIT - rem,
             MeteringPoints::Exhausted => GAS_LIMIT + 1,
-        }
+        };
+
+        wasm_gas.saturating_add(host_gas)
     }
 
     fn gas_info(&mut self) -> String {
@@ -0,0 +1,73 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use darkfi_sdk::{
+    crypto::smt::{MemoryStorageFp, SparseMerkleTreeFp},
+    pasta::{group::ff::Field, pallas},
+};
+use rand::rngs::OsRng;
+
+fn random_leaves(n: usize) -> Vec<(pallas::Base, pallas::Base)> {
+    (0..n).map(|_| (pallas::Base::random(&mut OsRng), pallas::Base::random(&mut OsRng))).collect()
+}
+
+fn smt_insert(c: &mut Criterion) {
+    let mut group = c.benchmark_group("smt_insert");
+    group.sample_size(10);
+
+    for n in [1, 10, 100] {
+        group.bench_with_input(BenchmarkId::new("sequential", n), &n, |b, &n| {
+            b.iter_batched(
+                || (SparseMerkleTreeFp::new(MemoryStorageFp::new()), random_leaves(n)),
+                |(mut smt, leaves)| {
+                    for (key, value) in &leaves {
+                        smt.insert(key, *value).unwrap();
+                    }
+                },
+                BatchSize::SmallInput,
+            )
+        });
+
+        group.bench_with_input(BenchmarkId::new("batch", n), &n, |b, &n| {
+            b.iter_batched(
+                || (SparseMerkleTreeFp::new(MemoryStorageFp::new()), random_leaves(n)),
+                |(mut smt, leaves)| smt.insert_batch(&leaves).unwrap(),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+fn smt_prove_verify(c: &mut Criterion) {
+    let leaves = random_leaves(100);
+    let mut smt = SparseMerkleTreeFp::new(MemoryStorageFp::new());
+    smt.insert_batch(&leaves).unwrap();
+    let root = smt.root().unwrap();
+    let (key, value) = leaves[0];
+
+    c.bench_function("smt_prove", |b| b.iter(|| smt.prove(&key).unwrap()));
+
+    let path = smt.prove(&key).unwrap();
+    c.bench_function("smt_verify", |b| b.iter(|| assert!(path.verify(&root, &key, &value))));
+}
+
+criterion_group!(benches, smt_insert, smt_prove_verify);
+criterion_main!(benches);
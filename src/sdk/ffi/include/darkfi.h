@@ -0,0 +1,89 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+/*
+ * C API for the DarkFi wallet and proof functionality.
+ *
+ * All functions return a DrkError code. Outputs are written through
+ * pointer arguments and left untouched on failure. Buffers returned
+ * by the library are owned by the caller and must be released with
+ * drk_buffer_free(). Input buffers are only borrowed for the call.
+ */
+
+#ifndef DARKFI_H
+#define DARKFI_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef enum {
+    DRK_OK = 0,
+    DRK_ERR_NULL_POINTER = 1,
+    DRK_ERR_INVALID_KEY = 2,
+    DRK_ERR_INVALID_FIELD = 3,
+    DRK_ERR_DECODE_FAILED = 4,
+    DRK_ERR_DECRYPT_FAILED = 5,
+    DRK_ERR_ZKAS_FAILED = 6,
+    DRK_ERR_PROOF_FAILED = 7,
+    DRK_ERR_SIGN_FAILED = 8,
+} DrkError;
+
+typedef uint8_t DrkBytes32[32];
+
+typedef struct {
+    uint8_t *data;
+    size_t len;
+} DrkBuffer;
+
+typedef struct DrkProvingKey DrkProvingKey;
+
+/* Memory */
+void drk_buffer_free(DrkBuffer *buf);
+
+/* Keys */
+DrkError drk_keypair_random(DrkBytes32 *secret_out, DrkBytes32 *public_out);
+DrkError drk_public_from_secret(const DrkBytes32 *secret, DrkBytes32 *public_out);
+DrkError drk_public_validate(const DrkBytes32 *public_key);
+
+/* Coin scanning */
+DrkError drk_money_output_scan(const DrkBytes32 *secret, const uint8_t *output,
+                               size_t output_len, DrkBuffer *note_out);
+
+/* Proofs */
+DrkError drk_proving_key_build(const uint8_t *zkbin, size_t zkbin_len,
+                               DrkProvingKey **pk_out);
+void drk_proving_key_free(DrkProvingKey *pk);
+DrkError drk_money_mint_proof(const DrkProvingKey *pk, uint64_t value,
+                              const DrkBytes32 *token_id, const DrkBytes32 *public_key,
+                              DrkBuffer *proof_out, DrkBuffer *public_inputs_out,
+                              DrkBuffer *output_out, DrkBuffer *note_out);
+
+/* Transactions */
+DrkError drk_tx_hash(const uint8_t *tx, size_t tx_len, DrkBytes32 *hash_out);
+DrkError drk_tx_sign(const uint8_t *tx, size_t tx_len, const DrkBytes32 *secrets,
+                     size_t n_secrets, DrkBuffer *tx_out);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* DARKFI_H */
@@ -0,0 +1,61 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+/// A heap-allocated byte buffer handed to the caller.
+///
+/// The caller owns the buffer and must release it with
+/// [`drk_buffer_free`]. It must not be freed with `free(3)`.
+#[repr(C)]
+#[derive(Debug)]
+pub struct DrkBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+impl DrkBuffer {
+    /// An empty buffer, safe to pass to [`drk_buffer_free`]
+    pub const fn empty() -> Self {
+        Self { data: std::ptr::null_mut(), len: 0 }
+    }
+}
+
+impl From<Vec<u8>> for DrkBuffer {
+    fn from(v: Vec<u8>) -> Self {
+        let boxed = v.into_boxed_slice();
+        let len = boxed.len();
+        let data = Box::into_raw(boxed) as *mut u8;
+        Self { data, len }
+    }
+}
+
+/// Release a buffer previously returned by this library.
+/// The buffer is reset to empty so a double free is harmless.
+///
+/// # Safety
+/// `buf` must be NULL or point to a `DrkBuffer` returned by this library.
+#[no_mangle]
+pub unsafe extern "C" fn drk_buffer_free(buf: *mut DrkBuffer) {
+    let Some(buf) = buf.as_mut() else { return };
+
+    if !buf.data.is_null() {
+        let slice = std::ptr::slice_from_raw_parts_mut(buf.data, buf.len);
+        drop(Box::from_raw(slice));
+    }
+
+    *buf = DrkBuffer::empty();
+}
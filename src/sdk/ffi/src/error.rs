@@ -0,0 +1,44 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+/// Error codes returned by all exported functions.
+///
+/// These values are part of the stable ABI. New variants may be
+/// appended, but existing ones must never be renumbered.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DrkError {
+    /// The call succeeded
+    Ok = 0,
+    /// A required pointer argument was NULL
+    NullPointer = 1,
+    /// A secret or public key was not a canonical encoding
+    InvalidKey = 2,
+    /// A field element or token ID was not a canonical encoding
+    InvalidField = 3,
+    /// Failed decoding a serialized structure
+    DecodeFailed = 4,
+    /// The note could not be decrypted with the given secret key
+    DecryptFailed = 5,
+    /// Failed decoding the zkas binary
+    ZkasFailed = 6,
+    /// Failed creating a ZK proof
+    ProofFailed = 7,
+    /// Failed creating a signature
+    SignFailed = 8,
+}
@@ -0,0 +1,77 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_sdk::crypto::{pasta_prelude::*, Keypair, PublicKey, SecretKey};
+use rand::rngs::OsRng;
+
+use crate::{DrkBytes32, DrkError};
+
+/// Generate a new random keypair.
+///
+/// # Safety
+/// `secret_out` and `public_out` must be valid for writes of 32 bytes.
+#[no_mangle]
+pub unsafe extern "C" fn drk_keypair_random(
+    secret_out: *mut DrkBytes32,
+    public_out: *mut DrkBytes32,
+) -> DrkError {
+    if secret_out.is_null() || public_out.is_null() {
+        return DrkError::NullPointer
+    }
+
+    let keypair = Keypair::random(&mut OsRng);
+    *secret_out = keypair.secret.inner().to_repr();
+    *public_out = keypair.public.to_bytes();
+
+    DrkError::Ok
+}
+
+/// Derive the public key corresponding to the given secret key.
+///
+/// # Safety
+/// `secret` must be valid for reads and `public_out` for writes of 32 bytes.
+#[no_mangle]
+pub unsafe extern "C" fn drk_public_from_secret(
+    secret: *const DrkBytes32,
+    public_out: *mut DrkBytes32,
+) -> DrkError {
+    if secret.is_null() || public_out.is_null() {
+        return DrkError::NullPointer
+    }
+
+    let Ok(secret) = SecretKey::from_bytes(*secret) else { return DrkError::InvalidKey };
+    *public_out = PublicKey::from_secret(secret).to_bytes();
+
+    DrkError::Ok
+}
+
+/// Check that the given bytes are a valid public key.
+///
+/// # Safety
+/// `public` must be valid for reads of 32 bytes.
+#[no_mangle]
+pub unsafe extern "C" fn drk_public_validate(public: *const DrkBytes32) -> DrkError {
+    if public.is_null() {
+        return DrkError::NullPointer
+    }
+
+    match PublicKey::from_bytes(*public) {
+        Ok(_) => DrkError::Ok,
+        Err(_) => DrkError::InvalidKey,
+    }
+}
@@ -0,0 +1,61 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! C ABI bindings for DarkFi wallets.
+//!
+//! See `include/darkfi.h` for the C declarations and `README.md` for
+//! the memory ownership conventions.
+
+/// Error codes returned across the FFI boundary
+mod error;
+pub use error::DrkError;
+
+/// Caller-owned byte buffers
+mod buffer;
+pub use buffer::{drk_buffer_free, DrkBuffer};
+
+/// Key management
+mod keys;
+
+/// Coin scanning
+mod note;
+
+/// Proof creation
+mod proof;
+
+/// Transaction hashing and signing
+mod tx;
+
+/// A 32-byte canonical encoding of a key or field element
+pub type DrkBytes32 = [u8; 32];
+
+/// Borrow a caller-provided `(ptr, len)` pair as a slice.
+///
+/// # Safety
+/// `ptr` must be valid for reads of `len` bytes, or `len` must be 0.
+pub(crate) unsafe fn slice_from_raw<'a>(ptr: *const u8, len: usize) -> Option<&'a [u8]> {
+    if len == 0 {
+        return Some(&[])
+    }
+
+    if ptr.is_null() {
+        return None
+    }
+
+    Some(std::slice::from_raw_parts(ptr, len))
+}
@@ -0,0 +1,77 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_money_contract::{client::MoneyNote, model::Output};
+use darkfi_sdk::{
+    crypto::{poseidon_hash, PublicKey, SecretKey},
+    pasta::pallas,
+};
+use darkfi_serial::{deserialize, serialize};
+
+use crate::{slice_from_raw, DrkBuffer, DrkBytes32, DrkError};
+
+/// Try to decrypt a serialized `Money::Transfer` output with the given
+/// secret key. This is the building block for coin scanning: wallets
+/// call it for every output in a block and keep the ones that succeed.
+///
+/// On success, the serialized `MoneyNote` is written to `note_out`.
+/// Returns `DRK_ERR_DECRYPT_FAILED` if the output doesn't belong to us,
+/// which is the expected result for most outputs.
+///
+/// # Safety
+/// `secret` must be valid for reads of 32 bytes, `output` for reads of
+/// `output_len` bytes, and `note_out` must point to a `DrkBuffer`.
+#[no_mangle]
+pub unsafe extern "C" fn drk_money_output_scan(
+    secret: *const DrkBytes32,
+    output: *const u8,
+    output_len: usize,
+    note_out: *mut DrkBuffer,
+) -> DrkError {
+    if secret.is_null() || note_out.is_null() {
+        return DrkError::NullPointer
+    }
+
+    let Some(output) = slice_from_raw(output, output_len) else { return DrkError::NullPointer };
+    let Ok(secret) = SecretKey::from_bytes(*secret) else { return DrkError::InvalidKey };
+    let Ok(output) = deserialize::<Output>(output) else { return DrkError::DecodeFailed };
+
+    let Ok(note) = output.note.decrypt::<MoneyNote>(&secret) else {
+        return DrkError::DecryptFailed
+    };
+
+    // Make sure the note actually opens the coin, otherwise a malicious
+    // sender could make us believe we received something we can't spend.
+    let (pub_x, pub_y) = PublicKey::from_secret(secret).xy();
+    let coin = poseidon_hash([
+        pub_x,
+        pub_y,
+        pallas::Base::from(note.value),
+        note.token_id.inner(),
+        note.serial,
+        note.spend_hook,
+        note.user_data,
+    ]);
+
+    if coin != output.coin.inner() {
+        return DrkError::DecryptFailed
+    }
+
+    *note_out = serialize(&note).into();
+    DrkError::Ok
+}
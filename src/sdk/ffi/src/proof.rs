@@ -0,0 +1,171 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{
+    zk::{empty_witnesses, ProvingKey, ZkCircuit},
+    zkas::ZkBinary,
+};
+use darkfi_money_contract::{
+    client::{
+        transfer_v1::{create_transfer_mint_proof, TransactionBuilderOutputInfo},
+        MoneyNote,
+    },
+    model::Output,
+};
+use darkfi_sdk::{
+    crypto::{note::AeadEncryptedNote, pasta_prelude::*, PublicKey, TokenId},
+    pasta::pallas,
+};
+use darkfi_serial::serialize;
+use rand::rngs::OsRng;
+
+use crate::{slice_from_raw, DrkBuffer, DrkBytes32, DrkError};
+
+/// Opaque handle to a compiled circuit and its proving key.
+/// Building one is expensive, so callers should reuse it across proofs.
+pub struct DrkProvingKey {
+    zkbin: ZkBinary,
+    pk: ProvingKey,
+}
+
+/// Build a proving key for the given zkas binary.
+/// The returned handle must be released with `drk_proving_key_free()`.
+///
+/// # Safety
+/// `zkbin` must be valid for reads of `zkbin_len` bytes, and `pk_out`
+/// must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn drk_proving_key_build(
+    zkbin: *const u8,
+    zkbin_len: usize,
+    pk_out: *mut *mut DrkProvingKey,
+) -> DrkError {
+    if pk_out.is_null() {
+        return DrkError::NullPointer
+    }
+
+    let Some(zkbin) = slice_from_raw(zkbin, zkbin_len) else { return DrkError::NullPointer };
+    let Ok(zkbin) = ZkBinary::decode(zkbin) else { return DrkError::ZkasFailed };
+    let Ok(witnesses) = empty_witnesses(&zkbin) else { return DrkError::ZkasFailed };
+
+    let circuit = ZkCircuit::new(witnesses, &zkbin);
+    let pk = ProvingKey::build(zkbin.k, &circuit);
+
+    *pk_out = Box::into_raw(Box::new(DrkProvingKey { zkbin, pk }));
+    DrkError::Ok
+}
+
+/// Release a proving key handle.
+///
+/// # Safety
+/// `pk` must be NULL or a handle returned by `drk_proving_key_build()`
+/// which hasn't been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn drk_proving_key_free(pk: *mut DrkProvingKey) {
+    if !pk.is_null() {
+        drop(Box::from_raw(pk));
+    }
+}
+
+/// Create a `Money::Transfer` mint proof for an output of `value` units
+/// of `token_id` paid to `public`. `pk` must have been built from the
+/// Money `Mint_V1` circuit.
+///
+/// On success, the serialized `Proof` is written to `proof_out`, the
+/// serialized public inputs (`Vec<pallas::Base>`) to `public_inputs_out`,
+/// and the serialized `Output`, carrying the note encrypted to `public`,
+/// to `output_out`. The serialized `MoneyNote`, holding the random value
+/// and token blinds and coin serial, is written to `note_out`, since the
+/// caller needs the blinds to balance the transaction.
+///
+/// # Safety
+/// `pk` must be a live handle, `token_id` and `public` must be valid
+/// for reads of 32 bytes, and the output pointers must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn drk_money_mint_proof(
+    pk: *const DrkProvingKey,
+    value: u64,
+    token_id: *const DrkBytes32,
+    public: *const DrkBytes32,
+    proof_out: *mut DrkBuffer,
+    public_inputs_out: *mut DrkBuffer,
+    output_out: *mut DrkBuffer,
+    note_out: *mut DrkBuffer,
+) -> DrkError {
+    if pk.is_null() ||
+        token_id.is_null() ||
+        public.is_null() ||
+        proof_out.is_null() ||
+        public_inputs_out.is_null() ||
+        output_out.is_null() ||
+        note_out.is_null()
+    {
+        return DrkError::NullPointer
+    }
+
+    let pk = &*pk;
+    let Ok(token_id) = TokenId::from_bytes(*token_id) else { return DrkError::InvalidField };
+    let Ok(public_key) = PublicKey::from_bytes(*public) else { return DrkError::InvalidKey };
+
+    let output = TransactionBuilderOutputInfo { value, token_id, public_key, view_public: None };
+
+    let value_blind = pallas::Scalar::random(&mut OsRng);
+    let token_blind = pallas::Base::random(&mut OsRng);
+    let serial = pallas::Base::random(&mut OsRng);
+
+    let Ok((proof, revealed)) = create_transfer_mint_proof(
+        &pk.zkbin,
+        &pk.pk,
+        &output,
+        value_blind,
+        token_blind,
+        serial,
+        pallas::Base::ZERO,
+        pallas::Base::ZERO,
+    ) else {
+        return DrkError::ProofFailed
+    };
+
+    let note = MoneyNote {
+        serial,
+        value,
+        token_id,
+        spend_hook: pallas::Base::ZERO,
+        user_data: pallas::Base::ZERO,
+        value_blind,
+        token_blind,
+        memo: vec![],
+    };
+
+    let Ok(encrypted_note) = AeadEncryptedNote::encrypt(&note, &public_key, &mut OsRng) else {
+        return DrkError::ProofFailed
+    };
+
+    let money_output = Output {
+        value_commit: revealed.value_commit,
+        token_commit: revealed.token_commit,
+        coin: revealed.coin,
+        note: encrypted_note,
+    };
+
+    *proof_out = serialize(&proof).into();
+    *public_inputs_out = serialize(&revealed.to_vec()).into();
+    *output_out = serialize(&money_output).into();
+    *note_out = serialize(&note).into();
+    DrkError::Ok
+}
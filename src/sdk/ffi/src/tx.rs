@@ -0,0 +1,86 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::tx::Transaction;
+use darkfi_sdk::crypto::SecretKey;
+use darkfi_serial::{deserialize, serialize};
+use rand::rngs::OsRng;
+
+use crate::{slice_from_raw, DrkBuffer, DrkBytes32, DrkError};
+
+/// Compute the hash of a serialized transaction.
+///
+/// # Safety
+/// `tx` must be valid for reads of `tx_len` bytes and `hash_out` for
+/// writes of 32 bytes.
+#[no_mangle]
+pub unsafe extern "C" fn drk_tx_hash(
+    tx: *const u8,
+    tx_len: usize,
+    hash_out: *mut DrkBytes32,
+) -> DrkError {
+    if hash_out.is_null() {
+        return DrkError::NullPointer
+    }
+
+    let Some(tx) = slice_from_raw(tx, tx_len) else { return DrkError::NullPointer };
+    let Ok(tx) = deserialize::<Transaction>(tx) else { return DrkError::DecodeFailed };
+
+    *hash_out = *tx.hash().as_bytes();
+    DrkError::Ok
+}
+
+/// Sign a serialized transaction with `n_secrets` secret keys and append
+/// the resulting signatures as a new entry in the transaction's
+/// signature set. The signed transaction is written to `tx_out`.
+///
+/// Callers building multi-call transactions invoke this once per call,
+/// in call order, with that call's signing keys.
+///
+/// # Safety
+/// `tx` must be valid for reads of `tx_len` bytes, `secrets` must point
+/// to `n_secrets` 32-byte keys, and `tx_out` must point to a `DrkBuffer`.
+#[no_mangle]
+pub unsafe extern "C" fn drk_tx_sign(
+    tx: *const u8,
+    tx_len: usize,
+    secrets: *const DrkBytes32,
+    n_secrets: usize,
+    tx_out: *mut DrkBuffer,
+) -> DrkError {
+    if tx_out.is_null() || (secrets.is_null() && n_secrets > 0) {
+        return DrkError::NullPointer
+    }
+
+    let Some(tx) = slice_from_raw(tx, tx_len) else { return DrkError::NullPointer };
+    let Ok(mut tx) = deserialize::<Transaction>(tx) else { return DrkError::DecodeFailed };
+
+    let mut secret_keys = Vec::with_capacity(n_secrets);
+    if n_secrets > 0 {
+        for bytes in std::slice::from_raw_parts(secrets, n_secrets) {
+            let Ok(secret) = SecretKey::from_bytes(*bytes) else { return DrkError::InvalidKey };
+            secret_keys.push(secret);
+        }
+    }
+
+    let Ok(sigs) = tx.create_sigs(&mut OsRng, &secret_keys) else { return DrkError::SignFailed };
+    tx.signatures.push(sigs);
+
+    *tx_out = serialize(&tx).into();
+    DrkError::Ok
+}
@@ -0,0 +1,155 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+/*
+ * Minimal C harness exercising the darkfi-ffi API.
+ * Run with `make test`.
+ */
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "darkfi.h"
+
+/* Money Mint_V1 circuit, built with `make -C ../../contract/money` */
+#define MINT_ZKBIN "../../contract/money/proof/mint_v1.zk.bin"
+
+static int failures = 0;
+
+#define CHECK(cond)                                                    \
+    do {                                                               \
+        if (!(cond)) {                                                 \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__,     \
+                    __LINE__, #cond);                                  \
+            failures++;                                                \
+        }                                                              \
+    } while (0)
+
+static void test_keys(void) {
+    DrkBytes32 secret, public_key, derived;
+
+    CHECK(drk_keypair_random(&secret, &public_key) == DRK_OK);
+    CHECK(drk_public_from_secret(&secret, &derived) == DRK_OK);
+    CHECK(memcmp(public_key, derived, 32) == 0);
+    CHECK(drk_public_validate(&public_key) == DRK_OK);
+
+    /* Noncanonical field element */
+    memset(secret, 0xff, 32);
+    CHECK(drk_public_from_secret(&secret, &derived) == DRK_ERR_INVALID_KEY);
+
+    CHECK(drk_keypair_random(NULL, &public_key) == DRK_ERR_NULL_POINTER);
+    CHECK(drk_public_validate(NULL) == DRK_ERR_NULL_POINTER);
+}
+
+static void test_buffers(void) {
+    DrkBuffer buf = {NULL, 0};
+
+    /* Freeing empty and NULL buffers is a no-op */
+    drk_buffer_free(&buf);
+    drk_buffer_free(NULL);
+}
+
+static void test_tx(void) {
+    /* An empty transaction: no calls, no proofs, no signatures */
+    const uint8_t tx[] = {0x00, 0x00, 0x00};
+    const uint8_t garbage[] = {0x05};
+    DrkBytes32 hash, hash_signed, secret, public_key;
+    DrkBuffer signed_tx = {NULL, 0};
+
+    CHECK(drk_tx_hash(tx, sizeof(tx), &hash) == DRK_OK);
+    CHECK(drk_tx_hash(garbage, sizeof(garbage), &hash) == DRK_ERR_DECODE_FAILED);
+
+    CHECK(drk_keypair_random(&secret, &public_key) == DRK_OK);
+    CHECK(drk_tx_sign(tx, sizeof(tx), &secret, 1, &signed_tx) == DRK_OK);
+    CHECK(signed_tx.data != NULL && signed_tx.len > sizeof(tx));
+
+    CHECK(drk_tx_hash(signed_tx.data, signed_tx.len, &hash_signed) == DRK_OK);
+    CHECK(memcmp(hash, hash_signed, 32) != 0);
+
+    drk_buffer_free(&signed_tx);
+    CHECK(signed_tx.data == NULL && signed_tx.len == 0);
+}
+
+static void test_scan(void) {
+    DrkBytes32 secret, public_key;
+    DrkBuffer note = {NULL, 0};
+    const uint8_t garbage[] = {0x01, 0x02, 0x03};
+
+    CHECK(drk_keypair_random(&secret, &public_key) == DRK_OK);
+    CHECK(drk_money_output_scan(&secret, garbage, sizeof(garbage), &note) ==
+          DRK_ERR_DECODE_FAILED);
+    CHECK(note.data == NULL);
+}
+
+static void test_proof(void) {
+    FILE *f = fopen(MINT_ZKBIN, "rb");
+    if (f == NULL) {
+        fprintf(stderr, "skipping proof test: %s not found\n", MINT_ZKBIN);
+        return;
+    }
+
+    fseek(f, 0, SEEK_END);
+    long len = ftell(f);
+    fseek(f, 0, SEEK_SET);
+    uint8_t *zkbin = malloc(len);
+    CHECK(fread(zkbin, 1, len, f) == (size_t)len);
+    fclose(f);
+
+    DrkProvingKey *pk = NULL;
+    CHECK(drk_proving_key_build(zkbin, len, &pk) == DRK_OK);
+    CHECK(drk_proving_key_build(zkbin, 1, &pk) == DRK_ERR_ZKAS_FAILED);
+    free(zkbin);
+
+    DrkBytes32 secret, public_key;
+    DrkBytes32 token_id = {0x2a};
+    DrkBuffer proof = {NULL, 0}, public_inputs = {NULL, 0};
+    DrkBuffer output = {NULL, 0}, note = {NULL, 0}, scanned = {NULL, 0};
+
+    CHECK(drk_keypair_random(&secret, &public_key) == DRK_OK);
+    CHECK(drk_money_mint_proof(pk, 42, &token_id, &public_key, &proof, &public_inputs,
+                               &output, &note) == DRK_OK);
+    CHECK(proof.len > 0 && public_inputs.len > 0);
+
+    /* The recipient finds the same note, blinds and serial in the output */
+    CHECK(drk_money_output_scan(&secret, output.data, output.len, &scanned) == DRK_OK);
+    CHECK(scanned.len == note.len && memcmp(scanned.data, note.data, note.len) == 0);
+
+    drk_buffer_free(&proof);
+    drk_buffer_free(&public_inputs);
+    drk_buffer_free(&output);
+    drk_buffer_free(&note);
+    drk_buffer_free(&scanned);
+    drk_proving_key_free(pk);
+}
+
+int main(void) {
+    test_keys();
+    test_buffers();
+    test_tx();
+    test_scan();
+    test_proof();
+
+    if (failures > 0) {
+        fprintf(stderr, "%d check(s) failed\n", failures);
+        return 1;
+    }
+
+    printf("All checks passed\n");
+    return 0;
+}
@@ -0,0 +1,67 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Coordinated activation of consensus rule changes. Every node switches
+//! to the new rules at the same block height, given by the activation
+//! schedule of the network. Since blocks are ordered by their slot, block
+//! heights are expressed in slots.
+
+use super::error::ContractError;
+
+/// Named consensus rule changes, gated behind an activation height
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[repr(u32)]
+pub enum Feature {
+    /// Transactions must pay a fee covering their verification cost,
+    /// on nodes enforcing fees
+    TxFees = 0x00,
+    /// Contract execution is charged with the second gas schedule
+    GasScheduleV2 = 0x01,
+}
+
+impl TryFrom<u32> for Feature {
+    type Error = ContractError;
+
+    fn try_from(x: u32) -> Result<Self, Self::Error> {
+        match x {
+            0x00 => Ok(Self::TxFees),
+            0x01 => Ok(Self::GasScheduleV2),
+            _ => Err(ContractError::UnknownFeature),
+        }
+    }
+}
+
+/// Activation heights of the network's consensus rule changes
+#[derive(Copy, Clone, Debug)]
+pub struct ActivationSchedule(pub &'static [(Feature, u64)]);
+
+impl ActivationSchedule {
+    /// Height at which the given feature activates, if it's scheduled
+    pub fn activation_height(&self, feature: Feature) -> Option<u64> {
+        self.0.iter().find(|(f, _)| *f == feature).map(|(_, height)| *height)
+    }
+
+    /// Check if the given feature is active for the block at the given height.
+    /// Features that are not scheduled are never active.
+    pub fn is_active(&self, feature: Feature, height: u64) -> bool {
+        self.activation_height(feature).is_some_and(|activation| height >= activation)
+    }
+}
+
+/// Activation schedule of the DarkFi network
+pub const ACTIVATIONS: ActivationSchedule = ActivationSchedule(&[(Feature::TxFees, 0)]);
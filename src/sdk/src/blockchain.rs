@@ -18,12 +18,12 @@
 
 #[cfg(feature = "async")]
 use darkfi_serial::async_trait;
-use darkfi_serial::{SerialDecodable, SerialEncodable};
+use darkfi_serial::{SerialDecodable, SerialEncodable, SerialSchema};
 use pasta_curves::{group::ff::Field, pallas};
 
 /// Auxiliary structure used to keep track of slots' previous slot
 /// relevant validation parameters.
-#[derive(Debug, Clone, PartialEq, SerialEncodable, SerialDecodable)]
+#[derive(Debug, Clone, PartialEq, SerialEncodable, SerialDecodable, SerialSchema)]
 pub struct PreviousSlot {
     /// Block producers count
     pub producers: u64,
@@ -56,7 +56,7 @@ impl Default for PreviousSlot {
 }
 
 /// Auxiliary structure used to keep track of slot PID output.
-#[derive(Debug, Clone, PartialEq, SerialEncodable, SerialDecodable)]
+#[derive(Debug, Clone, PartialEq, SerialEncodable, SerialDecodable, SerialSchema)]
 pub struct PidOutput {
     /// Inverse probability `f` of becoming a block producer
     pub f: f64,
@@ -82,7 +82,7 @@ impl Default for PidOutput {
 }
 
 /// Auxiliary structure used to keep track of slot validation parameters.
-#[derive(Debug, Clone, PartialEq, SerialEncodable, SerialDecodable)]
+#[derive(Debug, Clone, PartialEq, SerialEncodable, SerialDecodable, SerialSchema)]
 pub struct Slot {
     /// Slot UID
     pub id: u64,
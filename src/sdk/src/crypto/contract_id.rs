@@ -49,6 +49,10 @@ lazy_static! {
     /// Contract ID for the native Deployooor contract
     pub static ref DEPLOYOOOR_CONTRACT_ID: ContractId =
         ContractId::from(poseidon_hash([*CONTRACT_ID_PREFIX, pallas::Base::zero(), pallas::Base::from(3)]));
+
+    /// Contract ID for the native Auth contract
+    pub static ref AUTH_CONTRACT_ID: ContractId =
+        ContractId::from(poseidon_hash([*CONTRACT_ID_PREFIX, pallas::Base::zero(), pallas::Base::from(4)]));
 }
 
 /// ContractId represents an on-chain identifier for a certain smart contract.
@@ -102,6 +106,12 @@ impl ContractId {
     }
 }
 
+impl core::hash::Hash for ContractId {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.to_bytes().hash(state);
+    }
+}
+
 use core::str::FromStr;
 crate::fp_from_bs58!(ContractId);
 crate::fp_to_bs58!(ContractId);
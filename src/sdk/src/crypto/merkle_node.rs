@@ -19,7 +19,7 @@
 use core::{fmt, str::FromStr};
 use std::{io, iter};
 
-use bridgetree::{BridgeTree, Hashable, Level};
+use bridgetree::{BridgeTree, Frontier, Hashable, Level};
 use darkfi_serial::{SerialDecodable, SerialEncodable};
 use halo2_gadgets::sinsemilla::primitives::HashDomain;
 use lazy_static::lazy_static;
@@ -39,6 +39,10 @@ use crate::crypto::constants::{
 
 pub type MerkleTree = BridgeTree<MerkleNode, usize, { MERKLE_DEPTH }>;
 
+/// The rightmost path of a [`MerkleTree`]. This is enough to append new
+/// leaves and compute the root, without keeping any witness data around.
+pub type MerkleFrontier = Frontier<MerkleNode, { MERKLE_DEPTH }>;
+
 lazy_static! {
     static ref UNCOMMITTED_ORCHARD: pallas::Base = pallas::Base::from(2);
     static ref EMPTY_ROOTS: Vec<MerkleNode> = {
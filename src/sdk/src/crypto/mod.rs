@@ -32,7 +32,9 @@ pub use keypair::{Keypair, PublicKey, SecretKey};
 
 /// Contract ID definitions and methods
 pub mod contract_id;
-pub use contract_id::{ContractId, CONSENSUS_CONTRACT_ID, DAO_CONTRACT_ID, MONEY_CONTRACT_ID};
+pub use contract_id::{
+    ContractId, AUTH_CONTRACT_ID, CONSENSUS_CONTRACT_ID, DAO_CONTRACT_ID, MONEY_CONTRACT_ID,
+};
 
 /// Token ID definitions and methods
 pub mod token_id;
@@ -40,7 +42,7 @@ pub use token_id::{TokenId, DARK_TOKEN_ID};
 
 /// Merkle node definitions
 pub mod merkle_node;
-pub use merkle_node::{MerkleNode, MerkleTree};
+pub use merkle_node::{MerkleFrontier, MerkleNode, MerkleTree};
 
 /// Note encryption
 pub mod note;
@@ -59,6 +59,12 @@ use pasta_curves::group::ff::{FromUniformBytes, WithSmallOrderMulGroup};
 
 use crate::error::{ContractError, GenericResult};
 
+/// Key-value sparse Merkle tree with pluggable node storage
+mod kv;
+pub use kv::{
+    MemoryStorageFp, PathFp, SparseMerkleTreeFp, StorageAdapter, EMPTY_LEAF_FP, SMT_FP_DEPTH,
+};
+
 pub trait FieldHasher<F: WithSmallOrderMulGroup<3> + Ord, const L: usize> {
     fn hash(&self, inputs: [F; L]) -> GenericResult<F>;
     fn hasher() -> Self;
@@ -0,0 +1,324 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Key-value sparse Merkle tree over `pallas::Base` with pluggable node
+//! storage.
+//!
+//! Every `pallas::Base` key maps to a fixed leaf position in a tree of
+//! height [`SMT_FP_DEPTH`], so a key that was never inserted holds
+//! [`EMPTY_LEAF_FP`] and non-membership is proven the same way as
+//! membership: by opening the path at that position.
+//!
+//! Nodes are addressed by their index in the implicit binary heap layout
+//! (the root is `0` and the children of `i` are `2i + 1` and `2i + 2`),
+//! and only nodes differing from the empty subtree at their level are
+//! kept in the [`StorageAdapter`]. Parents are hashed with Poseidon.
+
+use std::collections::{BTreeSet, HashMap};
+
+use darkfi_serial::{SerialDecodable, SerialEncodable};
+use num_bigint::BigUint;
+use pasta_curves::{
+    group::ff::{Field, PrimeField},
+    pallas,
+};
+
+use crate::{crypto::util::poseidon_hash, error::GenericResult};
+
+/// Height of the tree, enough to give every `pallas::Base` its own leaf
+pub const SMT_FP_DEPTH: usize = 255;
+
+/// Value of a leaf that holds nothing
+pub const EMPTY_LEAF_FP: pallas::Base = pallas::Base::ZERO;
+
+/// Backend holding the non-empty nodes of a [`SparseMerkleTreeFp`].
+pub trait StorageAdapter {
+    /// Store the node at heap index `key`
+    fn put(&mut self, key: &BigUint, value: pallas::Base) -> GenericResult<()>;
+    /// Fetch the node at heap index `key`, if it is stored
+    fn get(&self, key: &BigUint) -> GenericResult<Option<pallas::Base>>;
+    /// Remove the node at heap index `key`
+    fn del(&mut self, key: &BigUint) -> GenericResult<()>;
+}
+
+/// In-memory [`StorageAdapter`]
+#[derive(Clone, Debug, Default)]
+pub struct MemoryStorageFp {
+    pub tree: HashMap<BigUint, pallas::Base>,
+}
+
+impl MemoryStorageFp {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl StorageAdapter for MemoryStorageFp {
+    fn put(&mut self, key: &BigUint, value: pallas::Base) -> GenericResult<()> {
+        self.tree.insert(key.clone(), value);
+        Ok(())
+    }
+
+    fn get(&self, key: &BigUint) -> GenericResult<Option<pallas::Base>> {
+        Ok(self.tree.get(key).copied())
+    }
+
+    fn del(&mut self, key: &BigUint) -> GenericResult<()> {
+        self.tree.remove(key);
+        Ok(())
+    }
+}
+
+/// Sibling nodes on the way from a leaf up to the root.
+#[derive(Clone, Debug, Eq, PartialEq, SerialEncodable, SerialDecodable)]
+pub struct PathFp {
+    /// Siblings ordered from the leaf level upwards
+    pub siblings: Vec<pallas::Base>,
+}
+
+impl PathFp {
+    /// Compute the root of a tree holding `value` under `key` and
+    /// this path's siblings everywhere else.
+    pub fn calculate_root(&self, key: &pallas::Base, value: &pallas::Base) -> pallas::Base {
+        let mut index = leaf_index(key);
+        let mut current = *value;
+
+        for sibling in &self.siblings {
+            current = if is_left_child(&index) {
+                poseidon_hash([current, *sibling])
+            } else {
+                poseidon_hash([*sibling, current])
+            };
+            index = parent(&index);
+        }
+
+        current
+    }
+
+    /// Check that `key` holds `value` in the tree with the given `root`.
+    /// Passing [`EMPTY_LEAF_FP`] as `value` checks non-membership.
+    pub fn verify(&self, root: &pallas::Base, key: &pallas::Base, value: &pallas::Base) -> bool {
+        self.siblings.len() == SMT_FP_DEPTH && self.calculate_root(key, value) == *root
+    }
+}
+
+/// Sparse Merkle tree mapping `pallas::Base` keys to `pallas::Base` values.
+pub struct SparseMerkleTreeFp<S: StorageAdapter> {
+    /// Node storage backend
+    store: S,
+    /// Root of an empty subtree at each level, where `0` is the root
+    /// level and [`SMT_FP_DEPTH`] is the leaf level.
+    empty_nodes: Vec<pallas::Base>,
+}
+
+impl<S: StorageAdapter> SparseMerkleTreeFp<S> {
+    /// Open a tree over the given storage. The storage may already hold
+    /// the nodes of an existing tree.
+    pub fn new(store: S) -> Self {
+        let mut empty_nodes = vec![EMPTY_LEAF_FP; SMT_FP_DEPTH + 1];
+        for level in (0..SMT_FP_DEPTH).rev() {
+            let child = empty_nodes[level + 1];
+            empty_nodes[level] = poseidon_hash([child, child]);
+        }
+
+        Self { store, empty_nodes }
+    }
+
+    /// Returns the Merkle root.
+    pub fn root(&self) -> GenericResult<pallas::Base> {
+        self.get_node(&BigUint::from(0_u8), 0)
+    }
+
+    /// Returns the value stored under `key`, or [`EMPTY_LEAF_FP`].
+    pub fn get(&self, key: &pallas::Base) -> GenericResult<pallas::Base> {
+        self.get_node(&leaf_index(key), SMT_FP_DEPTH)
+    }
+
+    /// Set `key` to `value` and update the root.
+    pub fn insert(&mut self, key: &pallas::Base, value: pallas::Base) -> GenericResult<()> {
+        self.insert_batch(&[(*key, value)])
+    }
+
+    /// Remove `key` from the tree by resetting it to [`EMPTY_LEAF_FP`].
+    pub fn remove(&mut self, key: &pallas::Base) -> GenericResult<()> {
+        self.insert_batch(&[(*key, EMPTY_LEAF_FP)])
+    }
+
+    /// Set all the given keys, hashing each affected inner node once.
+    /// If a key appears more than once, the last value wins.
+    pub fn insert_batch(&mut self, leaves: &[(pallas::Base, pallas::Base)]) -> GenericResult<()> {
+        let mut dirty = BTreeSet::new();
+        for (key, value) in leaves {
+            let index = leaf_index(key);
+            self.set_node(&index, SMT_FP_DEPTH, *value)?;
+            dirty.insert(parent(&index));
+        }
+
+        // Walk up one level at a time, so shared ancestors are only
+        // rehashed once their children are final.
+        for level in (0..SMT_FP_DEPTH).rev() {
+            let mut parents = BTreeSet::new();
+            for index in dirty {
+                let left = self.get_node(&left_child(&index), level + 1)?;
+                let right = self.get_node(&right_child(&index), level + 1)?;
+                self.set_node(&index, level, poseidon_hash([left, right]))?;
+
+                if level > 0 {
+                    parents.insert(parent(&index));
+                }
+            }
+            dirty = parents;
+        }
+
+        Ok(())
+    }
+
+    /// Build the path for `key`. It proves membership of the stored
+    /// value, or non-membership if the key is not in the tree.
+    pub fn prove(&self, key: &pallas::Base) -> GenericResult<PathFp> {
+        let mut siblings = Vec::with_capacity(SMT_FP_DEPTH);
+        let mut index = leaf_index(key);
+
+        for level in (1..=SMT_FP_DEPTH).rev() {
+            siblings.push(self.get_node(&sibling(&index), level)?);
+            index = parent(&index);
+        }
+
+        Ok(PathFp { siblings })
+    }
+
+    /// Returns a reference to the underlying storage.
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    fn get_node(&self, index: &BigUint, level: usize) -> GenericResult<pallas::Base> {
+        Ok(self.store.get(index)?.unwrap_or(self.empty_nodes[level]))
+    }
+
+    fn set_node(
+        &mut self,
+        index: &BigUint,
+        level: usize,
+        value: pallas::Base,
+    ) -> GenericResult<()> {
+        // Empty subtrees are implicit, so keep the storage sparse
+        if value == self.empty_nodes[level] {
+            self.store.del(index)
+        } else {
+            self.store.put(index, value)
+        }
+    }
+}
+
+/// Returns the heap index of the leaf holding `key`.
+fn leaf_index(key: &pallas::Base) -> BigUint {
+    let first_leaf = (BigUint::from(1_u8) << SMT_FP_DEPTH) - 1_u8;
+    first_leaf + BigUint::from_bytes_le(key.to_repr().as_ref())
+}
+
+#[inline]
+fn left_child(index: &BigUint) -> BigUint {
+    (index << 1) + 1_u8
+}
+
+#[inline]
+fn right_child(index: &BigUint) -> BigUint {
+    (index << 1) + 2_u8
+}
+
+#[inline]
+fn is_left_child(index: &BigUint) -> bool {
+    index.bit(0)
+}
+
+#[inline]
+fn parent(index: &BigUint) -> BigUint {
+    (index - 1_u8) >> 1
+}
+
+#[inline]
+fn sibling(index: &BigUint) -> BigUint {
+    if is_left_child(index) {
+        index + 1_u8
+    } else {
+        index - 1_u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::OsRng;
+
+    #[test]
+    fn smt_fp_membership() {
+        let mut smt = SparseMerkleTreeFp::new(MemoryStorageFp::new());
+        let empty_root = smt.root().unwrap();
+
+        let key = pallas::Base::random(&mut OsRng);
+        let value = pallas::Base::random(&mut OsRng);
+        let absent = pallas::Base::random(&mut OsRng);
+
+        // Everything is absent in the empty tree
+        let path = smt.prove(&key).unwrap();
+        assert!(path.verify(&empty_root, &key, &EMPTY_LEAF_FP));
+
+        smt.insert(&key, value).unwrap();
+        let root = smt.root().unwrap();
+        assert_ne!(root, empty_root);
+        assert_eq!(smt.get(&key).unwrap(), value);
+
+        let path = smt.prove(&key).unwrap();
+        assert!(path.verify(&root, &key, &value));
+        assert!(!path.verify(&root, &key, &EMPTY_LEAF_FP));
+        assert!(!path.verify(&empty_root, &key, &value));
+
+        let path = smt.prove(&absent).unwrap();
+        assert!(path.verify(&root, &absent, &EMPTY_LEAF_FP));
+        assert!(!path.verify(&root, &absent, &value));
+
+        // Removing the only key prunes every stored node
+        smt.remove(&key).unwrap();
+        assert_eq!(smt.root().unwrap(), empty_root);
+        assert!(smt.store().tree.is_empty());
+    }
+
+    #[test]
+    fn smt_fp_batch_matches_sequential() {
+        let leaves: Vec<_> = (0..10)
+            .map(|_| (pallas::Base::random(&mut OsRng), pallas::Base::random(&mut OsRng)))
+            .collect();
+
+        let mut sequential = SparseMerkleTreeFp::new(MemoryStorageFp::new());
+        for (key, value) in &leaves {
+            sequential.insert(key, *value).unwrap();
+        }
+
+        let mut batched = SparseMerkleTreeFp::new(MemoryStorageFp::new());
+        batched.insert_batch(&leaves).unwrap();
+
+        assert_eq!(sequential.root().unwrap(), batched.root().unwrap());
+        assert_eq!(sequential.store().tree, batched.store().tree);
+
+        for (key, value) in &leaves {
+            let path = batched.prove(key).unwrap();
+            assert!(path.verify(&batched.root().unwrap(), key, value));
+        }
+    }
+}
@@ -34,6 +34,9 @@ lazy_static! {
     /// Derivation prefix for `TokenId`
     pub static ref TOKEN_ID_PREFIX: pallas::Base = pallas::Base::from(69);
 
+    /// Derivation prefix for standard token mint authorities
+    pub static ref TOKEN_AUTHORITY_PREFIX: pallas::Base = pallas::Base::from(70);
+
     /// Native DARK token ID
     pub static ref DARK_TOKEN_ID: TokenId =
         TokenId::from(poseidon_hash([*TOKEN_ID_PREFIX, pallas::Base::zero(), pallas::Base::from(42)]));
@@ -59,6 +62,24 @@ impl TokenId {
         Self(hash)
     }
 
+    /// Derives the mint authority of a standard token from its issuer's
+    /// `SecretKey` and a per-symbol nonce. This lets an issuer recover all
+    /// of its tokens from a single key, while the on-chain metadata registry
+    /// publicly ties the issuer and nonce to the resulting `TokenId`.
+    pub fn derive_mint_authority(issuer: SecretKey, symbol_nonce: u64) -> SecretKey {
+        SecretKey::from(poseidon_hash([
+            *TOKEN_AUTHORITY_PREFIX,
+            issuer.inner(),
+            pallas::Base::from(symbol_nonce),
+        ]))
+    }
+
+    /// Derives the `TokenId` of a standard token from its issuer's
+    /// `SecretKey` and a per-symbol nonce.
+    pub fn derive_standard(issuer: SecretKey, symbol_nonce: u64) -> Self {
+        Self::derive(Self::derive_mint_authority(issuer, symbol_nonce))
+    }
+
     /// Get the inner `pallas::Base` element.
     pub fn inner(&self) -> pallas::Base {
         self.0
@@ -16,15 +16,16 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use darkfi_serial::Encodable;
+use darkfi_serial::{deserialize, serialize, Encodable, SerialDecodable, SerialEncodable};
 
 use super::{
-    crypto::ContractId,
+    crypto::{ContractId, Nullifier},
     error::{ContractError, GenericResult},
     util::parse_ret,
 };
 
 pub type DbHandle = u32;
+pub type SavepointHandle = u32;
 
 pub const DB_SUCCESS: i32 = 0;
 pub const CALLER_ACCESS_DENIED: i32 = -1;
@@ -34,6 +35,13 @@ pub const DB_GET_FAILED: i32 = -4;
 pub const DB_CONTAINS_KEY_FAILED: i32 = -5;
 pub const DB_SET_FAILED: i32 = -6;
 pub const DB_DEL_FAILED: i32 = -7;
+pub const ZKAS_PUBLIC_INPUTS_MISMATCH: i32 = -8;
+pub const ZKAS_NAMESPACE_IMMUTABLE: i32 = -9;
+pub const DB_GAS_EXHAUSTED: i32 = -10;
+pub const DB_SAVEPOINT_FAILED: i32 = -11;
+
+/// Maximum number of savepoints a contract can hold open at once
+pub const MAX_SAVEPOINTS: usize = 16;
 
 /// Only deploy() can call this. Creates a new database instance for this contract.
 ///
@@ -140,6 +148,7 @@ pub fn db_set(db_handle: DbHandle, key: &[u8], value: &[u8]) -> GenericResult<()
         match db_set_(buf.as_ptr(), len as u32) {
             CALLER_ACCESS_DENIED => Err(ContractError::CallerAccessDenied),
             DB_SET_FAILED => Err(ContractError::DbSetFailed),
+            DB_GAS_EXHAUSTED => Err(ContractError::HostGasExhausted),
             DB_SUCCESS => Ok(()),
             _ => unreachable!(),
         }
@@ -168,7 +177,62 @@ pub fn db_del(db_handle: DbHandle, key: &[u8]) -> GenericResult<()> {
     }
 }
 
-/// Only deploy() can call this.
+/// Only update() can call this. Takes a savepoint of the state written so
+/// far, which [`db_rollback`] can later return to. This lets a call made of
+/// several steps undo a step that failed in a recoverable way, instead of
+/// failing as a whole. Savepoints are released when the function returns.
+///
+/// ```
+/// let savepoint = db_savepoint()?;
+/// if step(&db_handle).is_err() {
+///     db_rollback(savepoint)?;
+/// }
+/// db_release(savepoint)?;
+/// ```
+pub fn db_savepoint() -> GenericResult<SavepointHandle> {
+    let ret = unsafe { db_savepoint_() };
+
+    if ret < 0 {
+        match ret as i32 {
+            CALLER_ACCESS_DENIED => return Err(ContractError::CallerAccessDenied),
+            DB_SAVEPOINT_FAILED => return Err(ContractError::DbSavepointFailed),
+            DB_GAS_EXHAUSTED => return Err(ContractError::HostGasExhausted),
+            _ => unimplemented!(),
+        }
+    }
+
+    Ok(ret as u32)
+}
+
+/// Only update() can call this. Discards everything written since the given
+/// savepoint was taken. The savepoint stays usable, while any taken after it
+/// are released.
+pub fn db_rollback(savepoint: SavepointHandle) -> GenericResult<()> {
+    match unsafe { db_rollback_(savepoint) } {
+        CALLER_ACCESS_DENIED => Err(ContractError::CallerAccessDenied),
+        DB_SAVEPOINT_FAILED => Err(ContractError::DbSavepointFailed),
+        DB_SUCCESS => Ok(()),
+        _ => unreachable!(),
+    }
+}
+
+/// Only update() can call this. Releases the given savepoint and any taken
+/// after it, keeping everything written since.
+pub fn db_release(savepoint: SavepointHandle) -> GenericResult<()> {
+    match unsafe { db_release_(savepoint) } {
+        CALLER_ACCESS_DENIED => Err(ContractError::CallerAccessDenied),
+        DB_SAVEPOINT_FAILED => Err(ContractError::DbSavepointFailed),
+        DB_SUCCESS => Ok(()),
+        _ => unreachable!(),
+    }
+}
+
+/// Only deploy() can call this. Stores a zkas circuit under its namespace,
+/// along with its `VerifyingKey`. Namespaces are immutable: setting the same
+/// circuit again is a no-op, but a different circuit under an existing
+/// namespace is refused, so proofs made against the old one stay verifiable.
+/// Changed circuits have to go under a new namespace, see
+/// [`crate::deploy::zkas_versioned_ns`].
 pub fn zkas_db_set(bincode: &[u8]) -> GenericResult<()> {
     unsafe {
         let mut len = 0;
@@ -178,12 +242,115 @@ pub fn zkas_db_set(bincode: &[u8]) -> GenericResult<()> {
         match zkas_db_set_(buf.as_ptr(), len as u32) {
             CALLER_ACCESS_DENIED => Err(ContractError::CallerAccessDenied),
             DB_SET_FAILED => Err(ContractError::DbSetFailed),
+            ZKAS_NAMESPACE_IMMUTABLE => Err(ContractError::ZkasNamespaceImmutable),
+            DB_SUCCESS => Ok(()),
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Only deploy() can call this. Works like [`zkas_db_set`], but also ships
+/// the number of public inputs the contract builds for this circuit. The
+/// host refuses the circuit if its `constrain_instance` count differs, so a
+/// contract and its circuits going out of sync fails at deploy time instead
+/// of on the first proof verification.
+pub fn zkas_db_set_declared(bincode: &[u8], public_inputs: u32) -> GenericResult<()> {
+    unsafe {
+        let mut len = 0;
+        let mut buf = vec![];
+        len += bincode.to_vec().encode(&mut buf)?;
+        len += public_inputs.encode(&mut buf)?;
+
+        match zkas_db_set_(buf.as_ptr(), len as u32) {
+            CALLER_ACCESS_DENIED => Err(ContractError::CallerAccessDenied),
+            DB_SET_FAILED => Err(ContractError::DbSetFailed),
+            ZKAS_PUBLIC_INPUTS_MISMATCH => Err(ContractError::ZkasPublicInputsMismatch),
+            ZKAS_NAMESPACE_IMMUTABLE => Err(ContractError::ZkasNamespaceImmutable),
             DB_SUCCESS => Ok(()),
             _ => unreachable!(),
         }
     }
 }
 
+/// The contract call that revealed a nullifier, recorded alongside it in a
+/// [`NullifierSet`]. Contracts checking another contract's set can demand a
+/// specific domain instead of trusting any nullifier found there.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, SerialEncodable, SerialDecodable)]
+pub struct NullifierDomain {
+    /// Contract the revealing call belongs to
+    pub contract_id: ContractId,
+    /// Function code of the revealing call
+    pub function: u8,
+}
+
+impl NullifierDomain {
+    pub fn new(contract_id: ContractId, function: u8) -> Self {
+        Self { contract_id, function }
+    }
+}
+
+/// A set of revealed nullifiers kept in a contract's db tree.
+///
+/// ```
+/// let nullifiers = NullifierSet::lookup(cid, "nullifiers")?;
+/// if nullifiers.contains(&nullifier)? {
+///     return Err(DuplicateNullifier)
+/// }
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct NullifierSet {
+    /// Contract owning the db tree
+    owner: ContractId,
+    /// Handle to the db tree
+    db: DbHandle,
+}
+
+impl NullifierSet {
+    /// Everyone can call this. Open the nullifier set stored in the given
+    /// db tree of the `owner` contract.
+    pub fn lookup(owner: ContractId, db_name: &str) -> GenericResult<Self> {
+        Ok(Self { owner, db: db_lookup(owner, db_name)? })
+    }
+
+    /// Contract owning this set.
+    pub fn owner(&self) -> ContractId {
+        self.owner
+    }
+
+    /// Everyone can call this. Check if the nullifier has been revealed.
+    pub fn contains(&self, nullifier: &Nullifier) -> GenericResult<bool> {
+        db_contains_key(self.db, &serialize(nullifier))
+    }
+
+    /// Everyone can call this. Retrieve the domain that revealed the nullifier,
+    /// or `None` if it hasn't been revealed.
+    pub fn revealed_by(&self, nullifier: &Nullifier) -> GenericResult<Option<NullifierDomain>> {
+        match db_get(self.db, &serialize(nullifier))? {
+            Some(data) => Ok(Some(deserialize(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Everyone can call this. Check that the nullifier was revealed by
+    /// exactly the given domain. This is what a contract should use when
+    /// it relies on a nullifier revealed by a previous call in the same
+    /// transaction, so nullifiers revealed by any other call can't be
+    /// replayed into it.
+    pub fn was_revealed_by(
+        &self,
+        nullifier: &Nullifier,
+        domain: &NullifierDomain,
+    ) -> GenericResult<bool> {
+        Ok(self.revealed_by(nullifier)?.as_ref() == Some(domain))
+    }
+
+    /// Only update() of the owner contract can call this. Record the
+    /// nullifier as revealed by the given domain.
+    pub fn insert(&self, nullifier: &Nullifier, domain: &NullifierDomain) -> GenericResult<()> {
+        db_set(self.db, &serialize(nullifier), &serialize(domain))
+    }
+}
+
 extern "C" {
     fn db_init_(ptr: *const u8, len: u32) -> i32;
     fn db_lookup_(ptr: *const u8, len: u32) -> i32;
@@ -191,6 +358,9 @@ extern "C" {
     fn db_contains_key_(ptr: *const u8, len: u32) -> i32;
     fn db_set_(ptr: *const u8, len: u32) -> i32;
     fn db_del_(ptr: *const u8, len: u32) -> i32;
+    fn db_savepoint_() -> i64;
+    fn db_rollback_(savepoint: u32) -> i32;
+    fn db_release_(savepoint: u32) -> i32;
 
     fn zkas_db_set_(ptr: *const u8, len: u32) -> i32;
 }
@@ -0,0 +1,92 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Definitions shared between the Deployooor contract, which handles the
+//! deployment of non-native contracts, and the host deploying them.
+
+#[cfg(feature = "async")]
+use darkfi_serial::async_trait;
+use darkfi_serial::{SerialDecodable, SerialEncodable};
+
+use crate::crypto::PublicKey;
+
+/// Function code of `Deploy::DeployV1`. The host recognizes calls to it
+/// and performs the actual deployment once the contract accepted them.
+pub const DEPLOY_FUNCTION_DEPLOY_V1: u8 = 0x00;
+
+/// Maximum size of a deployed contract's wasm bincode, in bytes
+pub const MAX_WASM_BINCODE_SIZE: usize = 2 * 1024 * 1024;
+
+/// Maximum size of the payload given to a deployed contract's deploy
+/// function, in bytes
+pub const MAX_DEPLOY_IX_SIZE: usize = 64 * 1024;
+
+/// Parameters for `Deploy::Deploy`
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct DeployParamsV1 {
+    /// Webassembly bincode of the smart contract
+    pub wasm_bincode: Vec<u8>,
+    /// Public key used to sign the transaction and derive the `ContractId`
+    pub public_key: PublicKey,
+    /// Payload given to the contract's deploy function
+    pub ix: Vec<u8>,
+}
+
+/// Namespace of the given version of a zkas circuit, e.g. `Mint_V2`.
+/// Deployed namespaces are immutable, so a contract upgrade changing a
+/// circuit has to ship it under the next version, while the previous one
+/// stays around to verify historical proofs.
+pub fn zkas_versioned_ns(ns: &str, version: u32) -> String {
+    format!("{}_V{}", ns, version)
+}
+
+/// Split a zkas namespace into its base namespace and version, as built
+/// by [`zkas_versioned_ns`]. Namespaces without a version suffix, e.g.
+/// `DeriveContractID`, are version 1.
+pub fn zkas_ns_version(ns: &str) -> (&str, u32) {
+    if let Some((base, suffix)) = ns.rsplit_once("_V") {
+        // Only accept the canonical form, so each namespace has one version
+        if let Ok(version) = suffix.parse::<u32>() {
+            if !base.is_empty() && version > 0 && version.to_string() == suffix {
+                return (base, version)
+            }
+        }
+    }
+
+    (ns, 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zkas_namespace_versions() {
+        assert_eq!(zkas_versioned_ns("Mint", 2), "Mint_V2");
+        assert_eq!(zkas_ns_version("Mint_V1"), ("Mint", 1));
+        assert_eq!(zkas_ns_version("Mint_V2"), ("Mint", 2));
+        assert_eq!(zkas_ns_version("Token_Mint_V13"), ("Token_Mint", 13));
+        assert_eq!(zkas_ns_version("DeriveContractID"), ("DeriveContractID", 1));
+
+        // Non-canonical suffixes are part of the base namespace
+        assert_eq!(zkas_ns_version("Mint_V0"), ("Mint_V0", 1));
+        assert_eq!(zkas_ns_version("Mint_V02"), ("Mint_V02", 1));
+        assert_eq!(zkas_ns_version("_V2"), ("_V2", 1));
+        assert_eq!(zkas_ns_version("Mint_Vx"), ("Mint_Vx", 1));
+    }
+}
@@ -18,7 +18,11 @@
 
 use core::{mem::size_of, slice::from_raw_parts};
 
-use crate::crypto::ContractId;
+use crate::{
+    crypto::ContractId,
+    error::{ContractError, ContractErrorReport},
+    util::set_error_report,
+};
 
 /// Success exit code for a contract
 pub const SUCCESS: i64 = 0;
@@ -38,7 +42,7 @@ macro_rules! define_contract {
 
             match $init_func(contract_id, &instruction_data) {
                 Ok(()) => $crate::entrypoint::SUCCESS,
-                Err(e) => e.into(),
+                Err(e) => $crate::entrypoint::report_error(e),
             }
         }
         #[no_mangle]
@@ -47,7 +51,7 @@ macro_rules! define_contract {
 
             match $exec_func(contract_id, &instruction_data) {
                 Ok(()) => $crate::entrypoint::SUCCESS,
-                Err(e) => e.into(),
+                Err(e) => $crate::entrypoint::report_error(e),
             }
         }
         #[no_mangle]
@@ -56,7 +60,7 @@ macro_rules! define_contract {
 
             match $apply_func(contract_id, &update_data) {
                 Ok(()) => $crate::entrypoint::SUCCESS,
-                Err(e) => e.into(),
+                Err(e) => $crate::entrypoint::report_error(e),
             }
         }
         #[no_mangle]
@@ -65,12 +69,20 @@ macro_rules! define_contract {
 
             match $metadata_func(contract_id, &instruction_data) {
                 Ok(()) => $crate::entrypoint::SUCCESS,
-                Err(e) => e.into(),
+                Err(e) => $crate::entrypoint::report_error(e),
             }
         }
     };
 }
 
+/// Report the given error to the host and return its error code.
+/// Used by `define_contract!` when an entrypoint fails.
+pub fn report_error(err: ContractError) -> i64 {
+    // A failure to report is not fatal, the host still gets the code
+    let _ = set_error_report(&ContractErrorReport::from(&err));
+    err.into()
+}
+
 /// Deserialize a given payload in `entrypoint`
 /// The return values from this are the input values for the above defined functions.
 /// # Safety
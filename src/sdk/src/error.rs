@@ -18,6 +18,10 @@
 
 use std::result::Result as ResultGeneric;
 
+#[cfg(feature = "async")]
+use darkfi_serial::async_trait;
+use darkfi_serial::{SerialDecodable, SerialEncodable};
+
 pub type GenericResult<T> = ResultGeneric<T, ContractError>;
 pub type ContractResult = ResultGeneric<(), ContractError>;
 
@@ -72,6 +76,9 @@ pub enum ContractError {
     #[error("Db contains_key failed")]
     DbContainsKeyFailed,
 
+    #[error("Db savepoint failed")]
+    DbSavepointFailed,
+
     #[error("Invalid function call")]
     InvalidFunction,
 
@@ -83,6 +90,42 @@ pub enum ContractError {
 
     #[error("Error retrieving system time")]
     GetSystemTimeFailed,
+
+    #[error("Unknown consensus feature")]
+    UnknownFeature,
+
+    #[error("zkas circuit public input count does not match its declaration")]
+    ZkasPublicInputsMismatch,
+
+    #[error("zkas circuit namespace is already taken by a different circuit")]
+    ZkasNamespaceImmutable,
+
+    #[error("Gas limit for host functions exhausted")]
+    HostGasExhausted,
+}
+
+/// Structured description of a contract failure, passed to the host
+/// alongside the entrypoint's error code.
+#[derive(Debug, Clone, Eq, PartialEq, SerialEncodable, SerialDecodable)]
+pub struct ContractErrorReport {
+    /// Error code, as returned from the entrypoint
+    pub code: i64,
+    /// Human readable description of the error
+    pub message: String,
+    /// Optional contract-specific data describing the error
+    pub data: Vec<u8>,
+}
+
+impl From<&ContractError> for ContractErrorReport {
+    fn from(err: &ContractError) -> Self {
+        Self { code: err.clone().into(), message: err.to_string(), data: vec![] }
+    }
+}
+
+impl std::fmt::Display for ContractErrorReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (code {:#x})", self.message, self.code)
+    }
 }
 
 /// Builtin return values occupy the upper 32 bits
@@ -111,6 +154,11 @@ pub const DB_DEL_FAILED: i64 = to_builtin!(16);
 pub const SMT_INVALID_LEAF: i64 = to_builtin!(17);
 pub const SMT_INVALID_PATH_NODES: i64 = to_builtin!(18);
 pub const GET_SYSTEM_TIME_FAILED: i64 = to_builtin!(19);
+pub const UNKNOWN_FEATURE: i64 = to_builtin!(20);
+pub const ZKAS_PUBLIC_INPUTS_MISMATCH: i64 = to_builtin!(21);
+pub const ZKAS_NAMESPACE_IMMUTABLE: i64 = to_builtin!(22);
+pub const HOST_GAS_EXHAUSTED: i64 = to_builtin!(23);
+pub const DB_SAVEPOINT_FAILED: i64 = to_builtin!(24);
 
 impl From<ContractError> for i64 {
     fn from(err: ContractError) -> Self {
@@ -133,6 +181,11 @@ impl From<ContractError> for i64 {
             ContractError::SmtInvalidLeaf => SMT_INVALID_LEAF,
             ContractError::SmtInvalidPathNodes => SMT_INVALID_PATH_NODES,
             ContractError::GetSystemTimeFailed => GET_SYSTEM_TIME_FAILED,
+            ContractError::UnknownFeature => UNKNOWN_FEATURE,
+            ContractError::ZkasPublicInputsMismatch => ZKAS_PUBLIC_INPUTS_MISMATCH,
+            ContractError::ZkasNamespaceImmutable => ZKAS_NAMESPACE_IMMUTABLE,
+            ContractError::HostGasExhausted => HOST_GAS_EXHAUSTED,
+            ContractError::DbSavepointFailed => DB_SAVEPOINT_FAILED,
             ContractError::Custom(error) => {
                 if error == 0 {
                     CUSTOM_ZERO
@@ -166,6 +219,11 @@ impl From<i64> for ContractError {
             SMT_INVALID_LEAF => Self::SmtInvalidLeaf,
             SMT_INVALID_PATH_NODES => Self::SmtInvalidPathNodes,
             GET_SYSTEM_TIME_FAILED => Self::GetSystemTimeFailed,
+            UNKNOWN_FEATURE => Self::UnknownFeature,
+            ZKAS_PUBLIC_INPUTS_MISMATCH => Self::ZkasPublicInputsMismatch,
+            ZKAS_NAMESPACE_IMMUTABLE => Self::ZkasNamespaceImmutable,
+            HOST_GAS_EXHAUSTED => Self::HostGasExhausted,
+            DB_SAVEPOINT_FAILED => Self::DbSavepointFailed,
             _ => Self::Custom(error as u32),
         }
     }
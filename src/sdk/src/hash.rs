@@ -0,0 +1,82 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Hashing functions computed natively by the host. Contracts should prefer
+//! these over the equivalent functions in [`crate::crypto`], which cost a lot
+//! more gas when executed in wasm.
+
+use darkfi_serial::{deserialize, Encodable};
+use pasta_curves::pallas;
+
+use super::{
+    error::{ContractError, GenericResult},
+    util::{get_object_bytes, get_object_size},
+};
+
+/// Maximum number of messages [`poseidon_hash`] accepts
+pub const POSEIDON_MAX_MESSAGES: usize = 8;
+
+/// Everyone can call this. Poseidon hash of the given messages, computed by
+/// the host. Equivalent to [`crate::crypto::poseidon_hash`] with the same
+/// messages, up to [`POSEIDON_MAX_MESSAGES`] of them.
+///
+/// ```
+/// hash = poseidon_hash(&[a, b])?;
+/// ```
+pub fn poseidon_hash(messages: &[pallas::Base]) -> GenericResult<pallas::Base> {
+    let mut buf = vec![];
+    let len = messages.to_vec().encode(&mut buf)?;
+
+    let ret = unsafe { poseidon_hash_(buf.as_ptr(), len as u32) };
+    Ok(deserialize(&read_object(ret)?)?)
+}
+
+/// Everyone can call this. Pedersen commitment to a 64-bit value, computed
+/// by the host. Equivalent to [`crate::crypto::pedersen_commitment_u64`].
+///
+/// ```
+/// commitment = pedersen_commitment_u64(value, blind)?;
+/// ```
+pub fn pedersen_commitment_u64(value: u64, blind: pallas::Scalar) -> GenericResult<pallas::Point> {
+    let mut buf = vec![];
+    let mut len = 0;
+    len += value.encode(&mut buf)?;
+    len += blind.encode(&mut buf)?;
+
+    let ret = unsafe { pedersen_commitment_u64_(buf.as_ptr(), len as u32) };
+    Ok(deserialize(&read_object(ret)?)?)
+}
+
+/// Read the object holding a host function's result, given its index or
+/// an error code.
+fn read_object(ret: i64) -> GenericResult<Vec<u8>> {
+    if ret < 0 {
+        return Err(ContractError::from(ret))
+    }
+
+    let obj = ret as u32;
+    let mut buf = vec![0u8; get_object_size(obj) as usize];
+    get_object_bytes(&mut buf, obj);
+
+    Ok(buf)
+}
+
+extern "C" {
+    fn poseidon_hash_(ptr: *const u8, len: u32) -> i64;
+    fn pedersen_commitment_u64_(ptr: *const u8, len: u32) -> i64;
+}
@@ -21,18 +21,27 @@ pub use num_bigint;
 pub use num_traits;
 pub use pasta_curves as pasta;
 
+/// Consensus rule changes activation
+pub mod activation;
+
 /// Blockchain structures
 pub mod blockchain;
 
 /// Database functions
 pub mod db;
 
+/// Non-native contract deployment definitions
+pub mod deploy;
+
 /// Entrypoint used for the wasm binaries
 pub mod entrypoint;
 
 /// Error handling
 pub mod error;
 
+/// Host-computed hashing functions
+pub mod hash;
+
 /// Logging infrastructure
 pub mod log;
 
@@ -41,7 +50,7 @@ pub mod crypto;
 
 /// Merkle
 pub mod merkle;
-pub use merkle::merkle_add;
+pub use merkle::{merkle_add, merkle_frontier_add};
 
 /// Transaction structure
 pub mod tx;
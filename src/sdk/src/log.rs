@@ -16,6 +16,8 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use darkfi_serial::{SerialDecodable, SerialEncodable};
+
 /// Print a message to the log
 #[macro_export]
 macro_rules! msg {
@@ -36,7 +38,57 @@ pub fn drk_log(message: &str) {
     println!("{}", message);
 }
 
+/// Structured debug trace record emitted by a contract call.
+#[derive(Debug, Clone, Eq, PartialEq, SerialEncodable, SerialDecodable)]
+pub struct ContractTrace {
+    /// What is being traced
+    pub key: String,
+    /// Debug representations of the traced values
+    pub values: Vec<String>,
+    /// The branch the contract took, if any
+    pub branch: Option<String>,
+}
+
+/// Emit a structured debug trace record. The host only collects traces
+/// when simulating transactions in development mode, otherwise they are
+/// discarded, so these should not be relied upon for anything but debugging.
+///
+/// ```ignore
+/// trace!("coin"; coin, value);
+/// trace!("fee", "paid"; fee);
+/// ```
+#[macro_export]
+macro_rules! trace {
+    ($key:expr, $branch:expr; $($value:expr),* $(,)?) => {
+        $crate::log::drk_trace(&$crate::log::ContractTrace {
+            key: $key.to_string(),
+            values: vec![$(format!("{:?}", $value)),*],
+            branch: Some($branch.to_string()),
+        })
+    };
+    ($key:expr; $($value:expr),* $(,)?) => {
+        $crate::log::drk_trace(&$crate::log::ContractTrace {
+            key: $key.to_string(),
+            values: vec![$(format!("{:?}", $value)),*],
+            branch: None,
+        })
+    };
+}
+
+#[inline]
+pub fn drk_trace(trace: &ContractTrace) {
+    #[cfg(target_arch = "wasm32")]
+    unsafe {
+        let data = darkfi_serial::serialize(trace);
+        drk_trace_(data.as_ptr(), data.len());
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    println!("{:?}", trace);
+}
+
 #[cfg(target_arch = "wasm32")]
 extern "C" {
     fn drk_log_(ptr: *const u8, len: usize);
+    fn drk_trace_(ptr: *const u8, len: usize);
 }
@@ -53,6 +53,41 @@ pub fn merkle_add(
     }
 }
 
+/// Add given elements into a Merkle tree persisted as a [`MerkleFrontier`].
+/// Unlike [`merkle_add`], only the root after appending the whole batch is
+/// computed and stored, and the frontier stays small regardless of how
+/// many leaves the tree holds.
+/// * `db_info` is a handle for a database where the Merkle frontier is stored.
+/// * `db_roots` is a handle for a database where the new Merkle root is stored.
+/// * `root_key` is the serialized key pointing to the latest Merkle root in `db_info`
+/// * `frontier_key` is the serialized key pointing to the Merkle frontier in `db_info`.
+/// * `elements` are the items we want to add to the Merkle tree.
+///
+/// [`MerkleFrontier`]: super::crypto::MerkleFrontier
+pub fn merkle_frontier_add(
+    db_info: DbHandle,
+    db_roots: DbHandle,
+    root_key: &[u8],
+    frontier_key: &[u8],
+    elements: &[MerkleNode],
+) -> GenericResult<()> {
+    let mut buf = vec![];
+    let mut len = 0;
+    len += db_info.encode(&mut buf)?;
+    len += db_roots.encode(&mut buf)?;
+    len += root_key.to_vec().encode(&mut buf)?;
+    len += frontier_key.to_vec().encode(&mut buf)?;
+    len += elements.to_vec().encode(&mut buf)?;
+
+    match unsafe { merkle_frontier_add_(buf.as_ptr(), len as u32) } {
+        0 => Ok(()),
+        -1 => Err(ContractError::CallerAccessDenied),
+        -2 => Err(ContractError::DbSetFailed),
+        _ => unreachable!(),
+    }
+}
+
 extern "C" {
     fn merkle_add_(ptr: *const u8, len: u32) -> i32;
+    fn merkle_frontier_add_(ptr: *const u8, len: u32) -> i32;
 }
@@ -18,14 +18,14 @@
 
 #[cfg(feature = "async")]
 use darkfi_serial::async_trait;
-use darkfi_serial::{SerialDecodable, SerialEncodable};
+use darkfi_serial::{SerialDecodable, SerialEncodable, SerialSchema};
 
 use super::crypto::ContractId;
 
 // ANCHOR: contractcall
 /// A ContractCall is the part of a transaction that executes a certain
 /// `contract_id` with `data` as the call's payload.
-#[derive(Debug, Clone, Eq, PartialEq, SerialEncodable, SerialDecodable)]
+#[derive(Debug, Clone, Eq, PartialEq, SerialEncodable, SerialDecodable, SerialSchema)]
 pub struct ContractCall {
     /// ID of the contract invoked
     pub contract_id: ContractId,
@@ -33,3 +33,62 @@ pub struct ContractCall {
     pub data: Vec<u8>,
 }
 // ANCHOR_END: contractcall
+
+/// A contract state tree, or a single key within it
+#[derive(Debug, Clone, Eq, PartialEq, SerialEncodable, SerialDecodable, SerialSchema)]
+pub struct StateScope {
+    /// State tree, as given by [`ContractId::hash_state_id`]
+    pub tree: [u8; 32],
+    /// Key within the tree, or `None` for the entire tree
+    pub key: Option<Vec<u8>>,
+}
+
+impl StateScope {
+    /// Returns `true` if the given key falls within this scope.
+    pub fn contains(&self, tree: &[u8; 32], key: &[u8]) -> bool {
+        &self.tree == tree && self.key.as_ref().map_or(true, |k| k == key)
+    }
+
+    /// Returns `true` if the two scopes share any key.
+    pub fn overlaps(&self, other: &StateScope) -> bool {
+        self.tree == other.tree &&
+            match (&self.key, &other.key) {
+                (Some(a), Some(b)) => a == b,
+                _ => true,
+            }
+    }
+}
+
+/// Optional declaration of the contract state a transaction may touch.
+/// Execution touching anything outside of it is aborted, and validators
+/// use it to schedule non-conflicting transactions in parallel.
+#[derive(Debug, Clone, Default, Eq, PartialEq, SerialEncodable, SerialDecodable, SerialSchema)]
+pub struct AccessList {
+    /// State the transaction may read
+    pub reads: Vec<StateScope>,
+    /// State the transaction may read and write
+    pub writes: Vec<StateScope>,
+}
+
+impl AccessList {
+    /// Returns `true` if the given key was declared as readable.
+    pub fn may_read(&self, tree: &[u8; 32], key: &[u8]) -> bool {
+        self.reads.iter().chain(&self.writes).any(|s| s.contains(tree, key))
+    }
+
+    /// Returns `true` if the given key was declared as writable.
+    pub fn may_write(&self, tree: &[u8; 32], key: &[u8]) -> bool {
+        self.writes.iter().any(|s| s.contains(tree, key))
+    }
+
+    /// Returns `true` if either declaration writes state the other one touches,
+    /// meaning the two transactions can't be executed independently.
+    pub fn conflicts_with(&self, other: &AccessList) -> bool {
+        let touches = |list: &AccessList, scope: &StateScope| {
+            list.reads.iter().chain(&list.writes).any(|s| s.overlaps(scope))
+        };
+
+        self.writes.iter().any(|s| touches(other, s)) ||
+            other.writes.iter().any(|s| touches(self, s))
+    }
+}
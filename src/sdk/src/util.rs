@@ -16,9 +16,12 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use darkfi_serial::serialize;
+
 use super::{
-    db::{CALLER_ACCESS_DENIED, DB_GET_FAILED},
-    error::{ContractError, GenericResult},
+    activation::Feature,
+    db::{CALLER_ACCESS_DENIED, DB_GAS_EXHAUSTED, DB_GET_FAILED},
+    error::{ContractError, ContractErrorReport, GenericResult},
 };
 
 pub fn set_return_data(data: &[u8]) -> Result<(), ContractError> {
@@ -30,6 +33,33 @@ pub fn set_return_data(data: &[u8]) -> Result<(), ContractError> {
     }
 }
 
+/// Describe the reason the current call is failing to the host. Only the
+/// first report of a call is kept, so contracts may report a more detailed
+/// error before returning, which takes precedence over the generic one
+/// `define_contract!` reports from the returned [`ContractError`]. The
+/// report's code must match the code the entrypoint returns.
+pub fn set_error_report(report: &ContractErrorReport) -> Result<(), ContractError> {
+    let data = serialize(report);
+    unsafe {
+        match set_error_report_(data.as_ptr(), data.len() as u32) {
+            0 => Ok(()),
+            errcode => Err(ContractError::from(errcode)),
+        }
+    }
+}
+
+/// Report the fee collected by the current call to the host, which checks
+/// it against the cost of verifying the transaction. Only the native money
+/// contract is allowed to do this, and only from its `exec` section.
+pub fn set_fee_paid(fee: u64) -> Result<(), ContractError> {
+    unsafe {
+        match set_fee_paid_(fee) {
+            0 => Ok(()),
+            errcode => Err(ContractError::from(errcode)),
+        }
+    }
+}
+
 pub fn put_object_bytes(data: &[u8]) -> i64 {
     unsafe { put_object_bytes_(data.as_ptr(), data.len() as u32) }
 }
@@ -48,6 +78,7 @@ pub(crate) fn parse_ret(ret: i64) -> GenericResult<Option<Vec<u8>>> {
         match ret as i32 {
             CALLER_ACCESS_DENIED => return Err(ContractError::CallerAccessDenied),
             DB_GET_FAILED => return Err(ContractError::DbGetFailed),
+            DB_GAS_EXHAUSTED => return Err(ContractError::HostGasExhausted),
             -127 => return Ok(None),
             _ => unimplemented!(),
         }
@@ -118,8 +149,24 @@ pub fn get_blockchain_time() -> u64 {
     unsafe { get_blockchain_time_() }
 }
 
+/// Everyone can call this. Will return whether the given consensus feature
+/// is active for the block being verified.
+///
+/// ```
+/// active = is_feature_active(Feature::TxFees)?;
+/// ```
+pub fn is_feature_active(feature: Feature) -> GenericResult<bool> {
+    match unsafe { is_feature_active_(feature as u32) } {
+        0 => Ok(false),
+        1 => Ok(true),
+        errcode => Err(ContractError::from(errcode)),
+    }
+}
+
 extern "C" {
     fn set_return_data_(ptr: *const u8, len: u32) -> i64;
+    fn set_error_report_(ptr: *const u8, len: u32) -> i64;
+    fn set_fee_paid_(fee: u64) -> i64;
     fn put_object_bytes_(ptr: *const u8, len: u32) -> i64;
     fn get_object_bytes_(ptr: *const u8, len: u32) -> i64;
     fn get_object_size_(len: u32) -> i64;
@@ -130,4 +177,5 @@ extern "C" {
     fn get_verifying_slot_epoch_() -> u64;
     fn get_slot_(slot: u64) -> i64;
     fn get_blockchain_time_() -> u64;
+    fn is_feature_active_(feature: u32) -> i64;
 }
@@ -26,6 +26,9 @@ use syn::{punctuated::Punctuated, token::Comma, Attribute, Path, Variant, WhereP
 mod sync_derive;
 pub use sync_derive::{enum_de, enum_ser, struct_de, struct_ser};
 
+mod schema_derive;
+pub use schema_derive::{enum_schema, struct_schema};
+
 #[cfg(feature = "async")]
 mod async_derive;
 #[cfg(feature = "async")]
@@ -0,0 +1,114 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Derive serialization schemas for enums and structs, see src/serial/derive
+use proc_macro2::{Ident, TokenStream};
+use quote::quote;
+use syn::{Fields, ItemEnum, ItemStruct, Type};
+
+use super::{contains_skip, discriminant_map};
+
+/// Render a type the way it was written, minus the token spacing.
+fn type_str(ty: &Type) -> String {
+    quote!(#ty).to_string().replace(' ', "")
+}
+
+/// Build a `Vec<SchemaField>` expression for the encoded fields.
+/// `skip_unnamed` mirrors the encoders: tuple structs encode every
+/// field, while tuple enum variants honour `skip_serialize`.
+fn fields_schema(cratename: &Ident, fields: &Fields, skip_unnamed: bool) -> TokenStream {
+    let mut body = TokenStream::new();
+
+    match fields {
+        Fields::Named(fields) => {
+            for field in &fields.named {
+                if contains_skip(&field.attrs) {
+                    continue
+                }
+
+                let name = field.ident.as_ref().unwrap().to_string();
+                let ty = type_str(&field.ty);
+                body.extend(quote! {
+                    #cratename::SchemaField { name: #name.to_string(), ty: #ty },
+                });
+            }
+        }
+        Fields::Unnamed(fields) => {
+            for (field_idx, field) in fields.unnamed.iter().enumerate() {
+                if skip_unnamed && contains_skip(&field.attrs) {
+                    continue
+                }
+
+                let name = field_idx.to_string();
+                let ty = type_str(&field.ty);
+                body.extend(quote! {
+                    #cratename::SchemaField { name: #name.to_string(), ty: #ty },
+                });
+            }
+        }
+        Fields::Unit => {}
+    }
+
+    quote! { vec![#body] }
+}
+
+pub fn struct_schema(input: &ItemStruct, cratename: Ident) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let name_str = name.to_string();
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let fields = fields_schema(&cratename, &input.fields, false);
+
+    Ok(quote! {
+        impl #impl_generics #cratename::SerialSchema for #name #ty_generics #where_clause {
+            fn schema() -> #cratename::Schema {
+                #cratename::Schema::Struct { name: #name_str, fields: #fields }
+            }
+        }
+    })
+}
+
+pub fn enum_schema(input: &ItemEnum, cratename: Ident) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let name_str = name.to_string();
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let discriminants = discriminant_map(&input.variants);
+
+    let mut variants = TokenStream::new();
+    for variant in input.variants.iter() {
+        let variant_ident = &variant.ident;
+        let variant_str = variant_ident.to_string();
+        let discriminant = discriminants.get(variant_ident).unwrap();
+        let fields = fields_schema(&cratename, &variant.fields, true);
+
+        variants.extend(quote! {
+            #cratename::SchemaVariant {
+                name: #variant_str,
+                tag: (#discriminant) as u8,
+                fields: #fields,
+            },
+        });
+    }
+
+    Ok(quote! {
+        impl #impl_generics #cratename::SerialSchema for #name #ty_generics #where_clause {
+            fn schema() -> #cratename::Schema {
+                #cratename::Schema::Enum { name: #name_str, variants: vec![#variants] }
+            }
+        }
+    })
+}
@@ -26,7 +26,9 @@ use syn::{Ident, ItemEnum, ItemStruct, ItemUnion};
 #[cfg(feature = "async")]
 use darkfi_derive_internal::{async_enum_de, async_enum_ser, async_struct_de, async_struct_ser};
 
-use darkfi_derive_internal::{enum_de, enum_ser, struct_de, struct_ser};
+use darkfi_derive_internal::{
+    enum_de, enum_schema, enum_ser, struct_de, struct_schema, struct_ser,
+};
 
 #[proc_macro_derive(SerialEncodable, attributes(skip_serialize))]
 pub fn darkfi_serialize(input: TokenStream) -> TokenStream {
@@ -121,3 +123,32 @@ pub fn darkfi_deserialize(input: TokenStream) -> TokenStream {
         Err(err) => err.to_compile_error(),
     })
 }
+
+#[proc_macro_derive(SerialSchema, attributes(skip_serialize))]
+pub fn darkfi_schema(input: TokenStream) -> TokenStream {
+    let found_crate = crate_name("darkfi-serial").expect("darkfi-serial is found in Cargo.toml");
+
+    let found_crate = match found_crate {
+        FoundCrate::Name(name) => name,
+        FoundCrate::Itself => "crate".to_string(),
+    };
+
+    let cratename = Ident::new(&found_crate, Span::call_site());
+
+    let res: syn::Result<TokenStream2> = if let Ok(input) = syn::parse::<ItemStruct>(input.clone())
+    {
+        struct_schema(&input, cratename)
+    } else if let Ok(input) = syn::parse::<ItemEnum>(input.clone()) {
+        enum_schema(&input, cratename)
+    } else if let Ok(_input) = syn::parse::<ItemUnion>(input) {
+        todo!()
+    } else {
+        // Derive macros can only be defined on structs, enums, and unions.
+        unreachable!()
+    };
+
+    TokenStream::from(match res {
+        Ok(res) => res,
+        Err(err) => err.to_compile_error(),
+    })
+}
@@ -22,7 +22,7 @@ use std::{
 };
 
 #[cfg(feature = "derive")]
-pub use darkfi_derive::{SerialDecodable, SerialEncodable};
+pub use darkfi_derive::{SerialDecodable, SerialEncodable, SerialSchema};
 
 #[cfg(feature = "async")]
 mod async_lib;
@@ -35,6 +35,9 @@ pub use async_lib::{
 mod endian;
 mod types;
 
+mod schema;
+pub use schema::{Schema, SchemaField, SchemaVariant, SerialSchema};
+
 /// Data which can be encoded in a consensus-consistent way.
 pub trait Encodable {
     /// Encode an object with a well-defined format.
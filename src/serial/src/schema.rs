@@ -0,0 +1,96 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Machine-readable descriptions of serialized layouts.
+//!
+//! Types deriving [`SerialSchema`](darkfi_derive::SerialSchema) describe
+//! the fields they encode, in encoding order, so that the wire format of
+//! consensus-critical types can be documented and checked for accidental
+//! changes by other implementations.
+
+use std::fmt;
+
+/// A single encoded field. Tuple struct fields are named by their index.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SchemaField {
+    pub name: String,
+    pub ty: &'static str,
+}
+
+/// An enum variant along with the `u8` tag prepended to its fields.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SchemaVariant {
+    pub name: &'static str,
+    pub tag: u8,
+    pub fields: Vec<SchemaField>,
+}
+
+/// The serialized layout of a type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Schema {
+    Struct { name: &'static str, fields: Vec<SchemaField> },
+    Enum { name: &'static str, variants: Vec<SchemaVariant> },
+}
+
+/// Types which can describe their serialized layout.
+pub trait SerialSchema {
+    fn schema() -> Schema;
+}
+
+fn fmt_fields(f: &mut fmt::Formatter<'_>, fields: &[SchemaField], indent: &str) -> fmt::Result {
+    for field in fields {
+        writeln!(f, "{}{}: {},", indent, field.name, field.ty)?;
+    }
+    Ok(())
+}
+
+/// The textual form is stable and line-oriented, one field per line:
+///
+/// ```text
+/// struct Foo {
+///     a: u64,
+///     b: Vec<u8>,
+/// }
+/// enum Bar {
+///     0 A {
+///         0: u32,
+///     }
+///     1 B {
+///     }
+/// }
+/// ```
+impl fmt::Display for Schema {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Schema::Struct { name, fields } => {
+                writeln!(f, "struct {} {{", name)?;
+                fmt_fields(f, fields, "    ")?;
+                writeln!(f, "}}")
+            }
+            Schema::Enum { name, variants } => {
+                writeln!(f, "enum {} {{", name)?;
+                for variant in variants {
+                    writeln!(f, "    {} {} {{", variant.tag, variant.name)?;
+                    fmt_fields(f, &variant.fields, "        ")?;
+                    writeln!(f, "    }}")?;
+                }
+                writeln!(f, "}}")
+            }
+        }
+    }
+}
@@ -148,6 +148,55 @@ impl<H: AsyncDecodable + Send + Ord + Clone> AsyncDecodable for bridgetree::NonE
     }
 }
 
+impl<H: Encodable + Ord + Clone, const DEPTH: u8> Encodable for bridgetree::Frontier<H, DEPTH> {
+    fn encode<S: Write>(&self, mut s: S) -> Result<usize> {
+        self.value().cloned().encode(&mut s)
+    }
+}
+
+#[cfg(feature = "async")]
+#[async_trait]
+impl<H: AsyncEncodable + Sync + Send + Ord + Clone, const DEPTH: u8> AsyncEncodable
+    for bridgetree::Frontier<H, DEPTH>
+{
+    async fn encode_async<S: AsyncWrite + Unpin + Send>(&self, s: &mut S) -> Result<usize> {
+        self.value().cloned().encode_async(s).await
+    }
+}
+
+impl<H: Decodable + Ord + Clone, const DEPTH: u8> Decodable for bridgetree::Frontier<H, DEPTH> {
+    fn decode<D: Read>(mut d: D) -> Result<Self> {
+        let frontier: Option<bridgetree::NonEmptyFrontier<H>> = Decodable::decode(&mut d)?;
+        let Some(frontier) = frontier else { return Ok(Self::empty()) };
+
+        let (position, leaf, ommers) =
+            (frontier.position(), frontier.leaf().clone(), frontier.ommers().to_vec());
+        match Self::from_parts(position, leaf, ommers) {
+            Ok(v) => Ok(v),
+            Err(_) => Err(Error::new(ErrorKind::Other, "FrontierError")),
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+#[async_trait]
+impl<H: AsyncDecodable + Send + Ord + Clone, const DEPTH: u8> AsyncDecodable
+    for bridgetree::Frontier<H, DEPTH>
+{
+    async fn decode_async<D: AsyncRead + Unpin + Send>(d: &mut D) -> Result<Self> {
+        let frontier: Option<bridgetree::NonEmptyFrontier<H>> =
+            AsyncDecodable::decode_async(d).await?;
+        let Some(frontier) = frontier else { return Ok(Self::empty()) };
+
+        let (position, leaf, ommers) =
+            (frontier.position(), frontier.leaf().clone(), frontier.ommers().to_vec());
+        match Self::from_parts(position, leaf, ommers) {
+            Ok(v) => Ok(v),
+            Err(_) => Err(Error::new(ErrorKind::Other, "FrontierError")),
+        }
+    }
+}
+
 impl<H: Encodable + Ord + Clone> Encodable for bridgetree::MerkleBridge<H> {
     fn encode<S: Write>(&self, mut s: S) -> Result<usize> {
         let mut len = 0;
@@ -316,7 +365,7 @@ impl<
 #[cfg(test)]
 mod tests {
     use crate::{deserialize, serialize, SerialDecodable, SerialEncodable};
-    use bridgetree::{BridgeTree, Hashable, Level};
+    use bridgetree::{BridgeTree, Frontier, Hashable, Level};
 
     #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, SerialEncodable, SerialDecodable)]
     struct Node(String);
@@ -364,4 +413,27 @@ mod tests {
         assert!(tree2 == deserial_tree2);
         assert!(tree3 == deserial_tree3);
     }
+
+    #[test]
+    fn serialize_deserialize_frontier() {
+        const DEPTH: u8 = 8;
+
+        // Empty frontier
+        let frontier: Frontier<Node, DEPTH> = Frontier::empty();
+        let deserial_frontier: Frontier<Node, DEPTH> = deserialize(&serialize(&frontier)).unwrap();
+        assert!(frontier == deserial_frontier);
+
+        // The frontier must track the same root as a full tree
+        let mut tree: BridgeTree<Node, usize, DEPTH> = BridgeTree::new(100);
+        let mut frontier: Frontier<Node, DEPTH> = Frontier::empty();
+        for i in 0..100 {
+            tree.append(Node(format!("test{}", i)));
+            assert!(frontier.append(Node(format!("test{}", i))));
+
+            let deserial_frontier: Frontier<Node, DEPTH> =
+                deserialize(&serialize(&frontier)).unwrap();
+            assert!(frontier == deserial_frontier);
+            assert_eq!(tree.root(0).unwrap(), deserial_frontier.root());
+        }
+    }
 }
@@ -0,0 +1,106 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Compatibility layer for running the smol-based stack under a
+//! host-provided async runtime (e.g. tokio).
+//!
+//! The `net` and `rpc` subsystems spawn their tasks on a
+//! [`smol::Executor`], and their I/O and timers are driven by the
+//! `async-io` reactor which runs on its own thread. This means they
+//! do not depend on any particular runtime, as long as *something*
+//! polls the executor. [`HostExecutor`] owns such an executor and
+//! hands out driver futures which the host can spawn on its own
+//! runtime:
+//!
+//! ```rust,ignore
+//!     let host_ex = HostExecutor::new();
+//!     for _ in 0..4 {
+//!         tokio::spawn(host_ex.driver());
+//!     }
+//!
+//!     let p2p = P2p::new(settings, host_ex.executor()).await;
+//!     p2p.clone().start(host_ex.executor()).await?;
+//!     ...
+//!     host_ex.stop();
+//! ```
+
+use std::sync::Arc;
+
+use smol::{channel, future::Future, Executor};
+
+pub type ExecutorPtr = Arc<Executor<'static>>;
+
+/// A [`smol::Executor`] that is driven by futures spawned on a foreign
+/// runtime rather than by dedicated threads.
+#[derive(Clone)]
+pub struct HostExecutor {
+    /// The executor handed to the `net` and `rpc` APIs
+    ex: ExecutorPtr,
+    /// Closing this channel stops all drivers
+    stop_send: channel::Sender<()>,
+    stop_recv: channel::Receiver<()>,
+}
+
+impl HostExecutor {
+    pub fn new() -> Self {
+        Self::with_executor(Arc::new(Executor::new()))
+    }
+
+    /// Wrap an existing executor so it can be driven by the host.
+    pub fn with_executor(ex: ExecutorPtr) -> Self {
+        let (stop_send, stop_recv) = channel::bounded(1);
+        Self { ex, stop_send, stop_recv }
+    }
+
+    /// Returns a pointer to the wrapped executor.
+    pub fn executor(&self) -> ExecutorPtr {
+        self.ex.clone()
+    }
+
+    /// Returns a future that runs the executor until [`HostExecutor::stop`]
+    /// is called. The future is `Send + 'static`, so it can be handed to
+    /// any multi-threaded runtime's spawn function. Spawning several
+    /// drivers lets the host run the executor's tasks in parallel.
+    pub fn driver(&self) -> impl Future<Output = ()> + Send + 'static {
+        let ex = self.ex.clone();
+        let stop_recv = self.stop_recv.clone();
+        async move {
+            // An error means the channel got closed, which is our stop signal.
+            let _ = ex.run(stop_recv.recv()).await;
+        }
+    }
+
+    /// Signal all drivers to return. Stopping is permanent: drivers
+    /// obtained afterwards return right away. Tasks still queued on the
+    /// executor are not dropped, and only run again if something else
+    /// drives [`HostExecutor::executor`].
+    pub fn stop(&self) {
+        self.stop_send.close();
+    }
+
+    /// Returns `true` if [`HostExecutor::stop`] has been called.
+    pub fn is_stopped(&self) -> bool {
+        self.stop_send.is_closed()
+    }
+}
+
+impl Default for HostExecutor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -33,6 +33,12 @@ pub use subscriber::{Subscriber, SubscriberPtr, Subscription};
 pub mod timeout;
 pub use timeout::io_timeout;
 
+/// Compatibility layer for driving the executor from a host runtime
+#[cfg(feature = "host-executor")]
+pub mod host_executor;
+#[cfg(feature = "host-executor")]
+pub use host_executor::{ExecutorPtr, HostExecutor};
+
 /// Sleep for any number of seconds.
 pub async fn sleep(seconds: u64) {
     Timer::after(Duration::from_secs(seconds)).await;
@@ -24,15 +24,17 @@ use darkfi_sdk::{
         PublicKey, SecretKey,
     },
     pasta::pallas,
-    tx::ContractCall,
+    tx::{AccessList, ContractCall},
+};
+use darkfi_serial::{
+    async_trait, serialize, Encodable, SerialDecodable, SerialEncodable, SerialSchema,
 };
-use darkfi_serial::{async_trait, serialize, Encodable, SerialDecodable, SerialEncodable};
 use log::{debug, error};
 use rand::{CryptoRng, RngCore};
 
 use crate::{
-    error::TxVerifyFailed,
-    zk::{proof::VerifyingKey, Proof},
+    error::{CallPhase, TxVerifyFailed},
+    zk::{proof::VerifyingKey, BatchVerifier, Proof},
     Error, Result,
 };
 
@@ -46,7 +48,7 @@ macro_rules! zip {
 // ANCHOR: transaction
 /// A Transaction contains an arbitrary number of `ContractCall` objects,
 /// along with corresponding ZK proofs and Schnorr signatures.
-#[derive(Debug, Clone, Default, Eq, PartialEq, SerialEncodable, SerialDecodable)]
+#[derive(Debug, Clone, Default, Eq, PartialEq, SerialEncodable, SerialDecodable, SerialSchema)]
 pub struct Transaction {
     /// Calls executed in this transaction
     pub calls: Vec<ContractCall>,
@@ -54,6 +56,8 @@ pub struct Transaction {
     pub proofs: Vec<Vec<Proof>>,
     /// Attached Schnorr signatures
     pub signatures: Vec<Vec<Signature>>,
+    /// Optional declaration of the contract state touched by the calls
+    pub access_list: Option<AccessList>,
 }
 // ANCHOR_END: transaction
 
@@ -68,25 +72,30 @@ impl Transaction {
         assert_eq!(self.calls.len(), self.proofs.len());
         assert_eq!(self.calls.len(), zkp_table.len());
 
-        for (call, (proofs, pubvals)) in zip!(self.calls, self.proofs, zkp_table) {
-            assert_eq!(proofs.len(), pubvals.len());
+        for (idx, (call, (proofs, pubvals))) in zip!(self.calls, self.proofs, zkp_table).enumerate()
+        {
+            let Some(pairs) = pair_proofs(proofs, pubvals) else {
+                error!("Call {} proofs don't match its public inputs", call.contract_id);
+                let e = "Proofs don't match the public inputs".to_string();
+                return Err(zk_failed(idx, call, e))
+            };
 
             let Some(contract_map) = verifying_keys.get(&call.contract_id.to_bytes()) else {
                 error!("Verifying keys not found for contract {}", call.contract_id);
-                return Err(TxVerifyFailed::InvalidZkProof.into())
+                return Err(zk_failed(idx, call, "Verifying keys not found".to_string()))
             };
 
-            for (proof, (zk_ns, public_vals)) in proofs.iter().zip(pubvals.iter()) {
+            for (proof, zk_ns, instances) in pairs {
                 if let Some(vk) = contract_map.get(zk_ns) {
                     // We have a verifying key for this
-                    debug!("public inputs: {:#?}", public_vals);
-                    if let Err(e) = proof.verify(vk, public_vals) {
+                    debug!("public inputs: {:#?}", instances);
+                    if let Err(e) = proof.verify_batch(vk, &instances) {
                         error!(
                             target: "",
                             "Failed verifying {}::{} ZK proof: {:#?}",
                             call.contract_id, zk_ns, e
                         );
-                        return Err(TxVerifyFailed::InvalidZkProof.into())
+                        return Err(zk_failed(idx, call, format!("Invalid {} ZK proof", zk_ns)))
                     }
                     debug!("Successfully verified {}::{} ZK proof", call.contract_id, zk_ns);
                     continue
@@ -94,7 +103,61 @@ impl Transaction {
 
                 let e = format!("{}:{} circuit VK nonexistent", call.contract_id, zk_ns);
                 error!("{}", e);
-                return Err(TxVerifyFailed::InvalidZkProof.into())
+                return Err(zk_failed(idx, call, format!("{} circuit VK nonexistent", zk_ns)))
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Add the transaction's ZK proofs to the given accumulator instead of
+    /// verifying them right away. Only cheap structural checks happen here,
+    /// the actual verification is done by [`ZkpAccumulator::verify`].
+    pub fn accumulate_zkps(
+        &self,
+        verifying_keys: &HashMap<[u8; 32], HashMap<String, VerifyingKey>>,
+        zkp_table: Vec<Vec<(String, Vec<pallas::Base>)>>,
+        accumulator: &mut ZkpAccumulator,
+    ) -> Result<()> {
+        if self.calls.len() != self.proofs.len() || self.calls.len() != zkp_table.len() {
+            error!("Transaction proofs don't match its calls");
+            return Err(TxVerifyFailed::InvalidZkProof.into())
+        }
+
+        // Make sure everything is in place before touching the accumulator,
+        // so a rejected transaction never leaves proofs behind.
+        let mut checked = Vec::with_capacity(self.calls.len());
+        for (idx, (call, (proofs, pubvals))) in zip!(self.calls, self.proofs, zkp_table).enumerate()
+        {
+            let Some(pairs) = pair_proofs(proofs, pubvals) else {
+                error!("Call {} proofs don't match its public inputs", call.contract_id);
+                let e = "Proofs don't match the public inputs".to_string();
+                return Err(zk_failed(idx, call, e))
+            };
+
+            let Some(contract_map) = verifying_keys.get(&call.contract_id.to_bytes()) else {
+                error!("Verifying keys not found for contract {}", call.contract_id);
+                return Err(zk_failed(idx, call, "Verifying keys not found".to_string()))
+            };
+
+            for (proof, zk_ns, instances) in &pairs {
+                let Some(vk) = contract_map.get(*zk_ns) else {
+                    error!("{}:{} circuit VK nonexistent", call.contract_id, zk_ns);
+                    return Err(zk_failed(idx, call, format!("{} circuit VK nonexistent", zk_ns)))
+                };
+
+                if !proof.is_well_formed_batch(vk, instances.len()) {
+                    error!("{}:{} ZK proof is malformed", call.contract_id, zk_ns);
+                    return Err(zk_failed(idx, call, format!("Malformed {} ZK proof", zk_ns)))
+                }
+            }
+
+            checked.push((call, pairs));
+        }
+
+        for (call, pairs) in checked {
+            for (proof, zk_ns, instances) in pairs {
+                accumulator.add(call.contract_id.to_bytes(), zk_ns, proof, instances);
             }
         }
 
@@ -104,9 +167,7 @@ impl Transaction {
     /// Verify Schnorr signatures for the entire transaction.
     pub fn verify_sigs(&self, pub_table: Vec<Vec<PublicKey>>) -> Result<()> {
         let tx_data = self.encode_without_sigs()?;
-        let mut hasher = blake3::Hasher::new();
-        hasher.update_rayon(&tx_data);
-        let data_hash = hasher.finalize();
+        let data_hash = blake3_hash(&tx_data);
         debug!("tx.verify_sigs: data_hash: {:?}", data_hash.as_bytes());
 
         assert!(pub_table.len() == self.signatures.len());
@@ -116,7 +177,13 @@ impl Transaction {
                 debug!("Verifying signature with public key: {}", pubkey);
                 if !pubkey.verify(&data_hash.as_bytes()[..], signature) {
                     error!("tx::verify_sigs[{}] failed to verify", i);
-                    return Err(Error::InvalidSignature)
+                    return Err(TxVerifyFailed::CallFailed(
+                        i,
+                        self.calls[i].contract_id.to_string(),
+                        CallPhase::Signature,
+                        format!("Invalid signature for public key {}", pubkey),
+                    )
+                    .into())
                 }
             }
             debug!("tx::verify_sigs[{}] passed", i);
@@ -125,6 +192,28 @@ impl Transaction {
         Ok(())
     }
 
+    /// Instead of verifying the Schnorr signatures for the entire transaction,
+    /// add them to the given accumulator so they can be checked in a batch.
+    pub fn accumulate_sigs(
+        &self,
+        pub_table: Vec<Vec<PublicKey>>,
+        accumulator: &mut ZkpAccumulator,
+    ) -> Result<()> {
+        let tx_data = self.encode_without_sigs()?;
+        let data_hash = blake3_hash(&tx_data);
+        debug!("tx.accumulate_sigs: data_hash: {:?}", data_hash.as_bytes());
+
+        assert!(pub_table.len() == self.signatures.len());
+
+        for (sigs, pubkeys) in self.signatures.iter().zip(pub_table) {
+            for (pubkey, signature) in pubkeys.into_iter().zip(sigs) {
+                accumulator.signatures.push((data_hash, pubkey, *signature));
+            }
+        }
+
+        Ok(())
+    }
+
     /// Create Schnorr signatures for the entire transaction.
     pub fn create_sigs(
         &self,
@@ -132,9 +221,7 @@ impl Transaction {
         secret_keys: &[SecretKey],
     ) -> Result<Vec<Signature>> {
         let tx_data = self.encode_without_sigs()?;
-        let mut hasher = blake3::Hasher::new();
-        hasher.update_rayon(&tx_data);
-        let data_hash = hasher.finalize();
+        let data_hash = blake3_hash(&tx_data);
         debug!("tx.create_sigs: data_hash: {:?}", data_hash.as_bytes());
 
         let mut sigs = vec![];
@@ -152,13 +239,174 @@ impl Transaction {
         let mut buf = vec![];
         self.calls.encode(&mut buf)?;
         self.proofs.encode(&mut buf)?;
+        self.access_list.encode(&mut buf)?;
         Ok(buf)
     }
 
     /// Get the transaction hash
     pub fn hash(&self) -> blake3::Hash {
-        let mut hasher = blake3::Hasher::new();
-        hasher.update_rayon(&serialize(self));
-        hasher.finalize()
+        blake3_hash(&serialize(self))
+    }
+}
+
+/// Accumulates ZK proofs across a set of transactions, grouped by the
+/// verifying key (contract ID and zkas namespace) they are checked with,
+/// along with the transactions' signatures.
+#[derive(Default)]
+pub struct ZkpAccumulator {
+    /// Proofs along with the public inputs of each circuit they cover
+    batches: HashMap<([u8; 32], String), Vec<(Proof, Vec<Vec<pallas::Base>>)>>,
+    signatures: Vec<(blake3::Hash, PublicKey, Signature)>,
+}
+
+impl ZkpAccumulator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn add(
+        &mut self,
+        contract_id: [u8; 32],
+        zk_ns: &str,
+        proof: &Proof,
+        instances: Vec<Vec<pallas::Base>>,
+    ) {
+        self.batches
+            .entry((contract_id, zk_ns.to_string()))
+            .or_default()
+            .push((proof.clone(), instances));
     }
+
+    /// Move all proofs and signatures accumulated in `other` into this accumulator.
+    pub fn merge(&mut self, other: ZkpAccumulator) {
+        for (key, proofs) in other.batches {
+            self.batches.entry(key).or_default().extend(proofs);
+        }
+        self.signatures.extend(other.signatures);
+    }
+
+    /// Split the accumulated proofs and signatures into at most `parts`
+    /// accumulators of similar size, so they can be verified concurrently.
+    /// Large batches for a single verifying key are spread over all parts.
+    pub fn split(self, parts: usize) -> Vec<ZkpAccumulator> {
+        let parts = parts.max(1);
+        let mut split: Vec<ZkpAccumulator> = (0..parts).map(|_| Self::default()).collect();
+
+        let mut next = 0;
+        for (key, proofs) in self.batches {
+            for proof in proofs {
+                split[next].batches.entry(key.clone()).or_default().push(proof);
+                next = (next + 1) % parts;
+            }
+        }
+
+        for signature in self.signatures {
+            split[next].signatures.push(signature);
+            next = (next + 1) % parts;
+        }
+
+        split.retain(|a| !a.is_empty());
+        split
+    }
+
+    /// Returns the total number of accumulated proofs.
+    pub fn len(&self) -> usize {
+        self.batches.values().map(|b| b.len()).sum()
+    }
+
+    /// Returns `true` if neither proofs nor signatures have been accumulated.
+    pub fn is_empty(&self) -> bool {
+        self.batches.is_empty() && self.signatures.is_empty()
+    }
+
+    /// Verify all accumulated signatures, then perform one final check per
+    /// verifying key over all accumulated proofs.
+    pub fn verify(
+        self,
+        verifying_keys: &HashMap<[u8; 32], HashMap<String, VerifyingKey>>,
+    ) -> Result<()> {
+        for (data_hash, pubkey, signature) in &self.signatures {
+            if !pubkey.verify(&data_hash.as_bytes()[..], signature) {
+                error!("Accumulated signature with public key {} failed to verify", pubkey);
+                return Err(TxVerifyFailed::InvalidSignature.into())
+            }
+        }
+        debug!("Successfully verified {} accumulated signatures", self.signatures.len());
+
+        for ((contract_id, zk_ns), proofs) in self.batches {
+            let Some(vk) = verifying_keys.get(&contract_id).and_then(|m| m.get(&zk_ns)) else {
+                error!("{} circuit VK nonexistent", zk_ns);
+                return Err(TxVerifyFailed::InvalidZkProof.into())
+            };
+
+            let mut batch = BatchVerifier::new();
+            for (proof, instances) in &proofs {
+                batch.add_batch(proof, instances);
+            }
+
+            if !batch.finalize(vk) {
+                error!("Batched verification of {} {} ZK proofs failed", proofs.len(), zk_ns);
+                return Err(TxVerifyFailed::InvalidZkProof.into())
+            }
+            debug!("Successfully verified {} batched {} ZK proofs", proofs.len(), zk_ns);
+        }
+
+        Ok(())
+    }
+}
+
+/// Pair a call's proofs with the public inputs of the circuits they cover.
+/// Either every public input has its own proof, or each run of consecutive
+/// public inputs for the same circuit is covered by a single aggregated
+/// proof, as created by [`Proof::create_batch`]. Returns `None` if the
+/// proofs match neither layout.
+fn pair_proofs<'a>(
+    proofs: &'a [Proof],
+    pubvals: &'a [(String, Vec<pallas::Base>)],
+) -> Option<Vec<(&'a Proof, &'a str, Vec<Vec<pallas::Base>>)>> {
+    if proofs.len() == pubvals.len() {
+        let pairs = proofs.iter().zip(pubvals);
+        return Some(
+            pairs
+                .map(|(proof, (zk_ns, public))| (proof, &zk_ns[..], vec![public.clone()]))
+                .collect(),
+        )
+    }
+
+    let mut runs: Vec<(&str, Vec<Vec<pallas::Base>>)> = vec![];
+    for (zk_ns, public) in pubvals {
+        match runs.last_mut() {
+            Some((ns, instances)) if *ns == zk_ns.as_str() => instances.push(public.clone()),
+            _ => runs.push((zk_ns.as_str(), vec![public.clone()])),
+        }
+    }
+
+    if runs.len() != proofs.len() {
+        return None
+    }
+
+    Some(
+        proofs
+            .iter()
+            .zip(runs)
+            .map(|(proof, (zk_ns, instances))| (proof, zk_ns, instances))
+            .collect(),
+    )
+}
+
+/// Build the error for a ZK proof failure in the call at the given index.
+fn zk_failed(idx: usize, call: &ContractCall, reason: String) -> Error {
+    TxVerifyFailed::CallFailed(idx, call.contract_id.to_string(), CallPhase::ZkProof, reason).into()
+}
+
+/// Hash the given data with blake3. Multithreaded hashing is used where
+/// available, but `wasm32` targets (e.g. browser wallets) have no threads
+/// so we fall back to the single-threaded implementation there.
+fn blake3_hash(data: &[u8]) -> blake3::Hash {
+    let mut hasher = blake3::Hasher::new();
+    #[cfg(not(target_arch = "wasm32"))]
+    hasher.update_rayon(data);
+    #[cfg(target_arch = "wasm32")]
+    hasher.update(data);
+    hasher.finalize()
 }
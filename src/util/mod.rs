@@ -40,3 +40,6 @@ pub mod ringbuffer;
 /// Permuted Congruential Generator (PCG)
 /// This is an insecure PRNG used for simulations and tests.
 pub mod pcg;
+
+/// Log of operations exceeding a duration threshold
+pub mod slow_log;
@@ -0,0 +1,179 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use log::warn;
+
+use super::time::Timestamp;
+
+pub type SlowLogPtr = Arc<SlowLog>;
+
+/// Default threshold above which an operation is considered slow, in milliseconds
+pub const DEFAULT_SLOW_THRESHOLD_MS: u64 = 200;
+
+/// Number of slowest operations kept by a [`SlowLog`]
+pub const SLOW_LOG_CAPACITY: usize = 32;
+
+/// Maximum length of a recorded operation label
+const MAX_LABEL_LEN: usize = 256;
+
+/// A recorded operation that exceeded the [`SlowLog`] threshold
+#[derive(Clone, Debug)]
+pub struct SlowOp {
+    /// Short description of the operation, e.g. the SQL statement
+    pub label: String,
+    /// How long the operation took
+    pub duration: Duration,
+    /// When the operation finished
+    pub timestamp: Timestamp,
+}
+
+/// Counters of a [`SlowLog`]
+#[derive(Clone, Debug, Default)]
+pub struct SlowLogMetrics {
+    /// Operations timed
+    pub total: u64,
+    /// Operations exceeding the threshold
+    pub slow: u64,
+}
+
+#[derive(Default)]
+struct SlowLogInner {
+    metrics: SlowLogMetrics,
+    /// Slowest operations, sorted by descending duration
+    worst: Vec<SlowOp>,
+}
+
+/// Log of operations exceeding a configurable duration threshold.
+/// Counts every timed operation, and keeps the slowest ones around
+/// so they can be inspected later, e.g. over RPC.
+pub struct SlowLog {
+    /// Name of the log, used in its messages
+    name: &'static str,
+    /// Threshold in microseconds
+    threshold: AtomicU64,
+    inner: Mutex<SlowLogInner>,
+}
+
+impl SlowLog {
+    /// Create a new [`SlowLog`] with the default threshold.
+    pub fn new(name: &'static str) -> SlowLogPtr {
+        Arc::new(Self {
+            name,
+            threshold: AtomicU64::new(DEFAULT_SLOW_THRESHOLD_MS * 1000),
+            inner: Mutex::new(SlowLogInner::default()),
+        })
+    }
+
+    /// Retrieve the current threshold.
+    pub fn threshold(&self) -> Duration {
+        Duration::from_micros(self.threshold.load(Ordering::Relaxed))
+    }
+
+    /// Set the threshold above which operations get recorded.
+    pub fn set_threshold(&self, threshold: Duration) {
+        self.threshold.store(threshold.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    /// Run `f`, recording it under `label` if it takes longer than the threshold.
+    pub fn time<T>(&self, label: &str, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let ret = f();
+        self.record(label, start.elapsed());
+        ret
+    }
+
+    /// Account for an operation that took `duration`.
+    pub fn record(&self, label: &str, duration: Duration) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.metrics.total += 1;
+        if duration <= self.threshold() {
+            return
+        }
+
+        inner.metrics.slow += 1;
+        warn!(
+            target: "slow_log",
+            "[{}] Slow operation ({} ms): {}", self.name, duration.as_millis(), label,
+        );
+
+        if inner.worst.len() == SLOW_LOG_CAPACITY &&
+            inner.worst.last().unwrap().duration >= duration
+        {
+            return
+        }
+
+        let label = label.chars().take(MAX_LABEL_LEN).collect();
+        let op = SlowOp { label, duration, timestamp: Timestamp::current_time() };
+        let pos = inner.worst.partition_point(|x| x.duration >= duration);
+        inner.worst.insert(pos, op);
+        inner.worst.truncate(SLOW_LOG_CAPACITY);
+    }
+
+    /// Retrieve the log counters.
+    pub fn metrics(&self) -> SlowLogMetrics {
+        self.inner.lock().unwrap().metrics.clone()
+    }
+
+    /// Retrieve up to `n` of the slowest recorded operations, slowest first.
+    pub fn worst(&self, n: usize) -> Vec<SlowOp> {
+        self.inner.lock().unwrap().worst.iter().take(n).cloned().collect()
+    }
+
+    /// Clear the counters and the recorded operations.
+    pub fn reset(&self) {
+        *self.inner.lock().unwrap() = SlowLogInner::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slow_log_keeps_worst() {
+        let log = SlowLog::new("test");
+        log.set_threshold(Duration::from_millis(10));
+
+        log.record("fast", Duration::from_millis(5));
+        for i in 0..SLOW_LOG_CAPACITY as u64 + 8 {
+            log.record(&format!("op{}", i), Duration::from_millis(11 + i));
+        }
+
+        let metrics = log.metrics();
+        assert_eq!(metrics.total, SLOW_LOG_CAPACITY as u64 + 9);
+        assert_eq!(metrics.slow, SLOW_LOG_CAPACITY as u64 + 8);
+
+        let worst = log.worst(usize::MAX);
+        assert_eq!(worst.len(), SLOW_LOG_CAPACITY);
+        assert_eq!(worst[0].label, format!("op{}", SLOW_LOG_CAPACITY + 7));
+        assert!(worst.windows(2).all(|w| w[0].duration >= w[1].duration));
+        assert_eq!(log.worst(3).len(), 3);
+
+        log.reset();
+        assert_eq!(log.metrics().total, 0);
+        assert!(log.worst(1).is_empty());
+    }
+}
@@ -16,7 +16,11 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::{fmt, time::UNIX_EPOCH};
+use std::{
+    fmt,
+    sync::{Mutex, OnceLock},
+    time::{Instant, UNIX_EPOCH},
+};
 
 use darkfi_serial::{async_trait, SerialDecodable, SerialEncodable};
 
@@ -162,7 +166,57 @@ impl NanoTimestamp {
     pub fn current_time() -> Self {
         Self(UNIX_EPOCH.elapsed().unwrap().as_nanos())
     }
+
+    /// Generate a `NanoTimestamp` from the process wide monotonic clock.
+    /// Readings never go backwards and are strictly increasing, so they
+    /// can be used to order events, while still being close to wall time.
+    pub fn monotonic() -> Self {
+        monotonic_clock().lock().unwrap().now()
+    }
+
+    /// Calculates elapsed nanoseconds of a `NanoTimestamp` taken from
+    /// the monotonic clock.
+    pub fn elapsed(&self) -> u128 {
+        Self::monotonic().0.saturating_sub(self.0)
+    }
+}
+
+/// Hybrid clock anchored to the wall clock at startup, advancing with a
+/// steady counter, so its readings are not affected by system time jumps.
+struct MonotonicClock {
+    /// Steady instant the clock was anchored at
+    instant: Instant,
+    /// Wall time, in nanoseconds, corresponding to the anchor instant
+    wall: u128,
+    /// Last reading handed out
+    last: u128,
+}
+
+impl MonotonicClock {
+    fn new() -> Self {
+        Self { instant: Instant::now(), wall: UNIX_EPOCH.elapsed().unwrap().as_nanos(), last: 0 }
+    }
+
+    fn now(&mut self) -> NanoTimestamp {
+        let reading = self.wall + self.instant.elapsed().as_nanos();
+        self.last = reading.max(self.last + 1);
+        NanoTimestamp(self.last)
+    }
+}
+
+fn monotonic_clock() -> &'static Mutex<MonotonicClock> {
+    static CLOCK: OnceLock<Mutex<MonotonicClock>> = OnceLock::new();
+    CLOCK.get_or_init(|| Mutex::new(MonotonicClock::new()))
+}
+
+/// Correct the monotonic clock anchor by the given offset, in nanoseconds,
+/// e.g. the difference between NTP and system time. Readings keep increasing
+/// when the correction is negative, until the corrected time catches up.
+pub fn correct_monotonic_clock(offset: i128) {
+    let mut clock = monotonic_clock().lock().unwrap();
+    clock.wall = clock.wall.saturating_add_signed(offset);
 }
+
 impl std::fmt::Display for NanoTimestamp {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         let date = timestamp_to_date(self.0.try_into().unwrap(), DateFormat::Nanos);
@@ -0,0 +1,127 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_serial::{deserialize, serialize, SerialDecodable, SerialEncodable};
+use log::error;
+
+use crate::{util::time::Timestamp, Result};
+
+/// Name of the sled tree holding the audit log
+pub const SLED_AUDIT_LOG_TREE: &[u8] = b"_validator_audit_log";
+
+/// A validator decision recorded in the audit log
+#[derive(Clone, Debug, PartialEq, Eq, SerialEncodable, SerialDecodable)]
+pub enum AuditEvent {
+    /// A block was verified and applied to the canonical chain
+    BlockAccepted { hash: blake3::Hash, slot: u64 },
+    /// A block failed verification
+    BlockRejected { hash: blake3::Hash, slot: u64, reason: String },
+    /// A proposal was verified and appended to a fork
+    ProposalAccepted { hash: blake3::Hash, slot: u64 },
+    /// A proposal was rejected
+    ProposalRejected { hash: blake3::Hash, slot: u64, reason: String },
+    /// A transaction was rejected from the mempool
+    TxRejected { hash: blake3::Hash, reason: String },
+    /// The best fork switched to one not extending the previous best tip
+    ForkSwitch { from: blake3::Hash, to: blake3::Hash },
+    /// A fork was selected for finalization
+    ForkFinalized { tip: blake3::Hash, blocks: u64 },
+}
+
+impl AuditEvent {
+    /// Short name of the event kind
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::BlockAccepted { .. } => "block_accepted",
+            Self::BlockRejected { .. } => "block_rejected",
+            Self::ProposalAccepted { .. } => "proposal_accepted",
+            Self::ProposalRejected { .. } => "proposal_rejected",
+            Self::TxRejected { .. } => "tx_rejected",
+            Self::ForkSwitch { .. } => "fork_switch",
+            Self::ForkFinalized { .. } => "fork_finalized",
+        }
+    }
+}
+
+/// An audit log entry, an event along with the time it was recorded
+#[derive(Clone, Debug, PartialEq, Eq, SerialEncodable, SerialDecodable)]
+pub struct AuditEntry {
+    /// Sequence number of the entry, increasing with every record
+    pub id: u64,
+    /// Time the event was recorded
+    pub timestamp: Timestamp,
+    /// The recorded event
+    pub event: AuditEvent,
+}
+
+/// Optional append-only log of validator decisions, kept in its own
+/// sled tree for post-incident analysis. Entries are never rewritten
+/// or removed by the validator.
+#[derive(Clone, Default)]
+pub struct AuditLog {
+    inner: Option<(sled::Db, sled::Tree)>,
+}
+
+impl AuditLog {
+    /// Open the audit log in the given database. Nothing is recorded if
+    /// `enabled` is false.
+    pub fn new(db: &sled::Db, enabled: bool) -> Result<Self> {
+        if !enabled {
+            return Ok(Self::default())
+        }
+
+        let tree = db.open_tree(SLED_AUDIT_LOG_TREE)?;
+        Ok(Self { inner: Some((db.clone(), tree)) })
+    }
+
+    /// Check if the audit log records anything
+    pub fn is_enabled(&self) -> bool {
+        self.inner.is_some()
+    }
+
+    /// Append an event to the log. Failures are only logged, since the
+    /// decision being recorded has already been made.
+    pub fn record(&self, event: AuditEvent) {
+        let Some((db, tree)) = &self.inner else { return };
+
+        if let Err(e) = Self::append(db, tree, event) {
+            error!(target: "validator::audit::record", "Failed appending to audit log: {}", e);
+        }
+    }
+
+    fn append(db: &sled::Db, tree: &sled::Tree, event: AuditEvent) -> Result<()> {
+        // Big-endian keys keep the tree iteration in record order
+        let id = db.generate_id()?;
+        let entry = AuditEntry { id, timestamp: Timestamp::current_time(), event };
+        tree.insert(id.to_be_bytes(), serialize(&entry))?;
+        Ok(())
+    }
+
+    /// Retrieve at most `limit` of the most recent entries, newest first.
+    pub fn recent(&self, limit: usize) -> Result<Vec<AuditEntry>> {
+        let Some((_, tree)) = &self.inner else { return Ok(vec![]) };
+
+        let mut entries = Vec::with_capacity(limit.min(tree.len()));
+        for record in tree.iter().rev().take(limit) {
+            let (_, value) = record?;
+            entries.push(deserialize(&value)?);
+        }
+
+        Ok(entries)
+    }
+}
@@ -0,0 +1,107 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_sdk::crypto::{
+    schnorr::{SchnorrPublic, SchnorrSecret, Signature},
+    PublicKey, SecretKey,
+};
+use darkfi_serial::{async_trait, serialize, SerialDecodable, SerialEncodable};
+use rand::rngs::OsRng;
+
+use crate::{Error, Result};
+
+/// Configuration of the authority set whose checkpoints the node accepts
+#[derive(Clone, Debug)]
+pub struct CheckpointConfig {
+    /// Public keys of the checkpoint authorities
+    pub authorities: Vec<PublicKey>,
+    /// Number of distinct authority signatures a checkpoint requires
+    pub threshold: usize,
+    /// Use accepted checkpoints as hints when finalizing forks
+    pub finality_hints: bool,
+}
+
+/// A block checkpoint signed by members of the configured authority set.
+/// Checkpoints are hints for fledgling networks, and never override
+/// blocks the node has already finalized on its own.
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct Checkpoint {
+    /// Slot of the checkpointed block
+    pub slot: u64,
+    /// Hash of the checkpointed block
+    pub block_hash: blake3::Hash,
+    /// Authority signatures over the slot and block hash
+    pub signatures: Vec<(PublicKey, Signature)>,
+}
+
+impl Checkpoint {
+    pub fn new(slot: u64, block_hash: blake3::Hash) -> Self {
+        Self { slot, block_hash, signatures: vec![] }
+    }
+
+    /// Message the authorities sign
+    fn message(&self) -> Vec<u8> {
+        serialize(&(self.slot, self.block_hash))
+    }
+
+    /// Add a signature by the given authority secret key. Signing
+    /// again with the same key replaces its previous signature.
+    pub fn sign(&mut self, secret_key: &SecretKey) {
+        let public_key = PublicKey::from_secret(*secret_key);
+        let signature = secret_key.sign(&mut OsRng, &self.message());
+        self.signatures.retain(|(signer, _)| *signer != public_key);
+        self.signatures.push((public_key, signature));
+    }
+
+    /// Returns the public keys that signed the checkpoint.
+    pub fn signers(&self) -> Vec<PublicKey> {
+        self.signatures.iter().map(|(signer, _)| *signer).collect()
+    }
+
+    /// Verify the checkpoint carries valid signatures by at least
+    /// `threshold` distinct members of the configured authority set.
+    pub fn verify(&self, config: &CheckpointConfig) -> Result<()> {
+        let message = self.message();
+        let mut signers: Vec<PublicKey> = vec![];
+        for (signer, signature) in &self.signatures {
+            if !config.authorities.contains(signer) {
+                return Err(Error::CheckpointInvalid(format!("{} is not an authority", signer)))
+            }
+
+            if signers.contains(signer) {
+                return Err(Error::CheckpointInvalid(format!("Duplicate signature by {}", signer)))
+            }
+
+            if !signer.verify(&message, signature) {
+                return Err(Error::CheckpointInvalid(format!("Invalid signature by {}", signer)))
+            }
+
+            signers.push(*signer);
+        }
+
+        if signers.len() < config.threshold {
+            return Err(Error::CheckpointInvalid(format!(
+                "{} signatures, {} required",
+                signers.len(),
+                config.threshold
+            )))
+        }
+
+        Ok(())
+    }
+}
@@ -30,8 +30,13 @@ use crate::{
         BlockInfo, BlockProducer, Blockchain, BlockchainOverlay, BlockchainOverlayPtr, Header,
     },
     tx::Transaction,
-    util::time::{TimeKeeper, Timestamp},
-    validator::{consensus::pid::slot_pid_output, verify_block, verify_transactions},
+    util::time::{NanoTimestamp, TimeKeeper, Timestamp},
+    validator::{
+        audit::{AuditEvent, AuditLog},
+        checkpoint::Checkpoint,
+        consensus::pid::slot_pid_output,
+        verify_block, verify_transactions,
+    },
     Error, Result,
 };
 
@@ -58,11 +63,22 @@ pub struct Consensus {
     pub forks: Vec<Fork>,
     /// Flag to enable testing mode
     pub testing_mode: bool,
+    /// Flag to enforce that transactions pay for their verification cost
+    pub verify_fees: bool,
+    /// Last authority checkpoint, used to break ties between forks
+    pub checkpoint_hint: Option<Checkpoint>,
+    /// Log of consensus decisions, disabled unless set by the validator
+    pub audit_log: AuditLog,
 }
 
 impl Consensus {
     /// Generate a new Consensus state.
-    pub fn new(blockchain: Blockchain, time_keeper: TimeKeeper, testing_mode: bool) -> Self {
+    pub fn new(
+        blockchain: Blockchain,
+        time_keeper: TimeKeeper,
+        testing_mode: bool,
+        verify_fees: bool,
+    ) -> Self {
         Self {
             blockchain,
             time_keeper,
@@ -70,6 +86,9 @@ impl Consensus {
             checked_finalization: 0,
             forks: vec![],
             testing_mode,
+            verify_fees,
+            checkpoint_hint: None,
+            audit_log: AuditLog::default(),
         }
     }
 
@@ -126,6 +145,78 @@ impl Consensus {
         let time_keeper = self.time_keeper.current();
 
         // Retrieve longest known fork
+        let fork = &self.forks[self.longest_fork_index()];
+
+        // Grab forks' unproposed transactions
+        let unproposed_txs =
+            fork.unproposed_txs(&self.blockchain, &time_keeper, self.verify_fees).await?;
+
+        self.build_proposal(fork, &time_keeper, secret_key, proposal_tx, unproposed_txs)
+    }
+
+    /// Generate a block proposal for the current hot/live(last) slot, out of
+    /// a template assembled by an external block producer. Proposal extends
+    /// the longest fork chain the node is holding, and is signed using provided
+    /// secret key, same as [`Consensus::generate_proposal`]. Template transactions
+    /// must all be valid on top of that fork, and not already proposed in it.
+    /// Caller should still pass the resulting proposal through
+    /// [`Consensus::append_proposal`] to enforce all consensus rules.
+    pub async fn generate_proposal_from_template(
+        &self,
+        secret_key: SecretKey,
+        template: BlockTemplate,
+    ) -> Result<Proposal> {
+        // Generate a time keeper for current slot
+        let time_keeper = self.time_keeper.current();
+
+        if self.forks.is_empty() {
+            return Err(Error::InvalidBlockTemplate("No hot/live slot generated".to_string()))
+        }
+
+        // Check that template transactions don't exceed limit
+        if template.txs.len() > TXS_CAP {
+            return Err(Error::InvalidBlockTemplate(format!(
+                "Template transactions exceed configured cap: {} - {}",
+                template.txs.len(),
+                TXS_CAP
+            )))
+        }
+
+        // Retrieve longest known fork
+        let fork = &self.forks[self.longest_fork_index()];
+
+        // Check that template transactions haven't been proposed already
+        let proposals = fork.overlay.lock().unwrap().get_blocks_by_hash(&fork.proposals)?;
+        for proposal in proposals {
+            if let Some(tx) = template.txs.iter().find(|tx| proposal.txs.contains(tx)) {
+                return Err(Error::InvalidBlockTemplate(format!(
+                    "Transaction {} is already proposed",
+                    tx.hash()
+                )))
+            }
+        }
+
+        // Verify transactions against a clone of the forks' overlay
+        let overlay = fork.overlay.lock().unwrap().full_clone()?;
+        let erroneous_txs =
+            verify_transactions(&overlay, &time_keeper, &template.txs, self.verify_fees).await?;
+        if !erroneous_txs.is_empty() {
+            return Err(Error::InvalidBlockTemplate(format!(
+                "Template contains {} erroneous transactions",
+                erroneous_txs.len()
+            )))
+        }
+
+        self.build_proposal(fork, &time_keeper, secret_key, template.proposal_tx, template.txs)
+    }
+
+    /// Auxiliary function to retrieve the tip of the longest known fork.
+    fn longest_fork_tip(&self) -> Option<blake3::Hash> {
+        self.forks.get(self.longest_fork_index())?.proposals.last().copied()
+    }
+
+    /// Auxiliary function to retrieve the index of the longest known fork.
+    fn longest_fork_index(&self) -> usize {
         let mut fork_index = 0;
         let mut max_fork_length = 0;
         for (index, fork) in self.forks.iter().enumerate() {
@@ -134,13 +225,24 @@ impl Consensus {
                 max_fork_length = fork.proposals.len();
             }
         }
-        let fork = &self.forks[fork_index];
 
-        // Grab forks' unproposed transactions and their root
-        let unproposed_txs = fork.unproposed_txs(&self.blockchain, &time_keeper).await?;
+        fork_index
+    }
+
+    /// Auxiliary function to build and sign a block proposal containing
+    /// given transactions, extending provided fork.
+    fn build_proposal(
+        &self,
+        fork: &Fork,
+        time_keeper: &TimeKeeper,
+        secret_key: SecretKey,
+        proposal_tx: Transaction,
+        txs: Vec<Transaction>,
+    ) -> Result<Proposal> {
+        // Generate the transactions root
         let mut tree = MerkleTree::new(100);
         // The following is pretty weird, so something better should be done.
-        for tx in &unproposed_txs {
+        for tx in &txs {
             let mut hash = [0_u8; 32];
             hash[0..31].copy_from_slice(&blake3::hash(&serialize(tx)).as_bytes()[0..31]);
             tree.append(MerkleNode::from(pallas::Base::from_repr(hash).unwrap()));
@@ -169,7 +271,7 @@ impl Consensus {
         let block_producer = BlockProducer::new(signature, proposal_tx, slot.last_eta);
 
         // Generate the block and its proposal
-        let block = BlockInfo::new(header, unproposed_txs, block_producer, fork.slots.clone());
+        let block = BlockInfo::new(header, txs, block_producer, fork.slots.clone());
         let proposal = Proposal::new(block);
 
         Ok(proposal)
@@ -186,7 +288,32 @@ impl Consensus {
     ///        correspond to the fork hot/live ones
     ///     6. Block is valid
     /// Additional validity rules can be applied.
+    /// The decision is recorded in the audit log, along with the switch of
+    /// the longest fork to one not extending its previous tip.
     pub async fn append_proposal(&mut self, proposal: &Proposal) -> Result<()> {
+        let previous_tip = self.longest_fork_tip();
+        let slot = proposal.block.header.slot;
+
+        if let Err(e) = self.try_append_proposal(proposal).await {
+            let reason = e.to_string();
+            let event = AuditEvent::ProposalRejected { hash: proposal.hash, slot, reason };
+            self.audit_log.record(event);
+            return Err(e)
+        }
+        self.audit_log.record(AuditEvent::ProposalAccepted { hash: proposal.hash, slot });
+
+        if let (Some(from), Some(to)) = (previous_tip, self.longest_fork_tip()) {
+            let longest = &self.forks[self.longest_fork_index()];
+            if !longest.proposals.contains(&from) {
+                info!(target: "validator::consensus::append_proposal", "Longest fork switched from {} to {}", from, to);
+                self.audit_log.record(AuditEvent::ForkSwitch { from, to });
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn try_append_proposal(&mut self, proposal: &Proposal) -> Result<()> {
         // Generate a time keeper for current slot
         let time_keeper = self.time_keeper.current();
 
@@ -257,6 +384,7 @@ impl Consensus {
             &previous,
             expected_reward,
             self.testing_mode,
+            self.verify_fees,
         )
         .await
         .is_err()
@@ -353,6 +481,7 @@ impl Consensus {
                 previous,
                 expected_reward,
                 self.testing_mode,
+                self.verify_fees,
             )
             .await
             .is_err()
@@ -373,11 +502,14 @@ impl Consensus {
     /// Consensus finalization logic:
     /// - If the node has observed the creation of a fork and no other forks exists at same or greater height,
     ///   all proposals in that fork can be finalized (append to canonical blockchain).
+    /// - If other forks exist at same height, but only one of them contains the block of
+    ///   the last authority checkpoint hint, that fork can be finalized.
     /// When a fork can be finalized, blocks(proposals) should be appended to canonical,
     /// and forks should be removed.
     pub async fn forks_finalization(&mut self) -> Result<Vec<BlockInfo>> {
         let slot = self.time_keeper.current_slot();
-        info!(target: "validator::consensus::forks_finalization", "Started finalization check for slot: {}", slot);
+        let started = NanoTimestamp::monotonic();
+        info!(target: "validator::consensus::forks_finalization", "Started finalization check for slot {} at {}", slot, started);
         // Set last slot finalization check occured to current slot
         self.checked_finalization = slot;
 
@@ -404,10 +536,16 @@ impl Consensus {
 
         // Check if we found any fork to finalize
         match fork_index {
-            -2 => {
-                info!(target: "validator::consensus::forks_finalization", "Eligible forks with same height exist, nothing to finalize.");
-                return Ok(vec![])
-            }
+            -2 => match self.checkpointed_fork(max_length) {
+                Some(index) => {
+                    info!(target: "validator::consensus::forks_finalization", "Eligible forks with same height exist, fork {} is checkpointed and can be finalized!", index);
+                    fork_index = index as i64;
+                }
+                None => {
+                    info!(target: "validator::consensus::forks_finalization", "Eligible forks with same height exist, nothing to finalize.");
+                    return Ok(vec![])
+                }
+            },
             -1 => {
                 info!(target: "validator::consensus::forks_finalization", "Nothing to finalize.");
             }
@@ -423,10 +561,101 @@ impl Consensus {
         // Starting finalization
         let fork = &self.forks[fork_index as usize];
         let finalized = fork.overlay.lock().unwrap().get_blocks_by_hash(&fork.proposals)?;
-        info!(target: "validator::consensus::forks_finalization", "Finalized blocks: {}", finalized.len());
+        info!(target: "validator::consensus::forks_finalization", "Finalized blocks: {} (took {}ns)", finalized.len(), started.elapsed());
+        if let Some(tip) = fork.proposals.last() {
+            let event = AuditEvent::ForkFinalized { tip: *tip, blocks: finalized.len() as u64 };
+            self.audit_log.record(event);
+        }
 
         Ok(finalized)
     }
+
+    /// Summarize the current fork tree, along with the proposals blocks of
+    /// each fork, so its shape can be monitored.
+    pub fn forks_summary(&self) -> Result<Vec<ForkSummary>> {
+        // All forks extend the canonical tip, so genesis is at height 0
+        let canonical_height = self.blockchain.len().saturating_sub(1) as u64;
+
+        let proposals: Vec<&[blake3::Hash]> =
+            self.forks.iter().map(|fork| fork.proposals.as_slice()).collect();
+        let shared = forks_shared_proposals(&proposals);
+
+        let mut summaries = Vec::with_capacity(self.forks.len());
+        for (fork, shared) in self.forks.iter().zip(shared) {
+            let Some(tip) = fork.proposals.last() else { continue };
+            let blocks = fork.overlay.lock().unwrap().get_blocks_by_hash(&fork.proposals)?;
+            let tip_slot = blocks.last().unwrap().header.slot;
+
+            summaries.push(ForkSummary {
+                tip: *tip,
+                tip_slot,
+                height: canonical_height + fork.proposals.len() as u64,
+                common_ancestor: canonical_height + shared as u64,
+                blocks,
+            });
+        }
+
+        Ok(summaries)
+    }
+
+    /// Find the only fork of given length containing the block of the
+    /// checkpoint hint, if any.
+    fn checkpointed_fork(&self, length: usize) -> Option<usize> {
+        let checkpoint = self.checkpoint_hint.as_ref()?;
+        let mut checkpointed = self.forks.iter().enumerate().filter(|(_, fork)| {
+            fork.proposals.len() == length && fork.proposals.contains(&checkpoint.block_hash)
+        });
+
+        let (index, _) = checkpointed.next()?;
+        if checkpointed.next().is_some() {
+            return None
+        }
+
+        Some(index)
+    }
+}
+
+/// Fork position in the fork tree, as exported for monitoring.
+pub struct ForkSummary {
+    /// Hash of the fork's last proposal
+    pub tip: blake3::Hash,
+    /// Slot of the fork's last proposal
+    pub tip_slot: u64,
+    /// Height of the fork's last proposal
+    pub height: u64,
+    /// Height of the last block this fork shares with any other fork,
+    /// which is the canonical tip if it shares none of its proposals
+    pub common_ancestor: u64,
+    /// Fork proposals blocks, in order
+    pub blocks: Vec<BlockInfo>,
+}
+
+/// For each fork proposals sequence, find the length of the longest
+/// prefix it shares with any other fork.
+pub fn forks_shared_proposals(forks: &[&[blake3::Hash]]) -> Vec<usize> {
+    forks
+        .iter()
+        .enumerate()
+        .map(|(i, fork)| {
+            forks
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| i != *j)
+                .map(|(_, other)| fork.iter().zip(other.iter()).take_while(|(a, b)| a == b).count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect()
+}
+
+/// Block contents assembled by an external block producer (sequencer),
+/// which the node turns into a signed proposal after validating them.
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct BlockTemplate {
+    /// Block transactions, in order of execution
+    pub txs: Vec<Transaction>,
+    /// Block producer transaction, signed with the node's block signing key
+    pub proposal_tx: Transaction,
 }
 
 /// This struct represents a block proposal, used for consensus.
@@ -510,6 +739,7 @@ impl Fork {
         &self,
         blockchain: &Blockchain,
         time_keeper: &TimeKeeper,
+        verify_fees: bool,
     ) -> Result<Vec<Transaction>> {
         // Retrieve all mempool transactions
         let mut unproposed_txs: Vec<Transaction> = blockchain
@@ -537,7 +767,8 @@ impl Fork {
         let overlay = self.overlay.lock().unwrap().full_clone()?;
 
         // Verify transactions
-        let erroneous_txs = verify_transactions(&overlay, time_keeper, &unproposed_txs).await?;
+        let erroneous_txs =
+            verify_transactions(&overlay, time_keeper, &unproposed_txs, verify_fees).await?;
         if !erroneous_txs.is_empty() {
             unproposed_txs.retain(|x| !erroneous_txs.contains(x));
         }
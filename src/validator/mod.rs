@@ -16,18 +16,24 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::sync::Arc;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
-use darkfi_sdk::{blockchain::Slot, crypto::PublicKey};
+use darkfi_sdk::{blockchain::Slot, crypto::PublicKey, log::ContractTrace};
 use darkfi_serial::serialize;
 use log::{debug, error, info, warn};
 use smol::lock::RwLock;
 
 use crate::{
     blockchain::{BlockInfo, Blockchain, BlockchainOverlay},
+    consensus::fees::{FeeModel, GasData, FEE_WINDOW},
     error::TxVerifyFailed,
+    system::{Subscriber, SubscriberPtr},
     tx::Transaction,
     util::time::TimeKeeper,
+    zk::VerifyingKey,
     Error, Result,
 };
 
@@ -37,7 +43,22 @@ use consensus::{next_block_reward, Consensus};
 
 /// Verification functions
 pub mod verification;
-use verification::{verify_block, verify_genesis_block, verify_transactions};
+use verification::{
+    estimate_transaction_gas, execute_transaction, set_proof_verification_limit, trace_transaction,
+    verify_block, verify_genesis_block, verify_transaction, verify_transactions, TxExecution,
+};
+
+/// Authority signed checkpoints
+pub mod checkpoint;
+use checkpoint::{Checkpoint, CheckpointConfig};
+
+/// Mempool admission policies
+pub mod policy;
+use policy::{ConflictPolicy, MempoolConflict, MempoolMetrics, TxPolicy};
+
+/// Append-only log of validator decisions
+pub mod audit;
+use audit::{AuditEvent, AuditLog};
 
 /// Helper utilities
 pub mod utils;
@@ -56,17 +77,44 @@ pub struct ValidatorConfig {
     pub faucet_pubkeys: Vec<PublicKey>,
     /// Flag to enable testing mode
     pub testing_mode: bool,
+    /// Flag to enforce that transactions pay for their verification cost
+    pub verify_fees: bool,
+    /// Maximum number of ZK proof verifications running at once
+    pub proof_verification_limit: Option<usize>,
+    /// Optional authority set whose signed checkpoints are accepted
+    pub checkpoint_config: Option<CheckpointConfig>,
+    /// Admission policy for transactions entering the mempool
+    pub tx_policy: Arc<dyn TxPolicy>,
+    /// Flag to record validator decisions in the audit log
+    pub audit_log: bool,
 }
 
 impl ValidatorConfig {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         time_keeper: TimeKeeper,
         genesis_block: BlockInfo,
         genesis_txs_total: u64,
         faucet_pubkeys: Vec<PublicKey>,
         testing_mode: bool,
+        verify_fees: bool,
+        proof_verification_limit: Option<usize>,
+        checkpoint_config: Option<CheckpointConfig>,
+        tx_policy: Arc<dyn TxPolicy>,
+        audit_log: bool,
     ) -> Self {
-        Self { time_keeper, genesis_block, genesis_txs_total, faucet_pubkeys, testing_mode }
+        Self {
+            time_keeper,
+            genesis_block,
+            genesis_txs_total,
+            faucet_pubkeys,
+            testing_mode,
+            verify_fees,
+            proof_verification_limit,
+            checkpoint_config,
+            tx_policy,
+            audit_log,
+        }
     }
 }
 
@@ -83,12 +131,40 @@ pub struct Validator {
     pub synced: bool,
     /// Flag to enable testing mode
     pub testing_mode: bool,
+    /// Flag to enforce that transactions pay for their verification cost
+    pub verify_fees: bool,
+    /// Optional authority set whose signed checkpoints are accepted
+    pub checkpoint_config: Option<CheckpointConfig>,
+    /// Last checkpoint received from the authority set
+    pub last_checkpoint: Option<Checkpoint>,
+    /// Admission policy for transactions entering the mempool
+    pub tx_policy: Arc<dyn TxPolicy>,
+    /// Counters of the mempool conflicts handling
+    pub mempool_metrics: MempoolMetrics,
+    /// Notifications of transactions conflicting with pending ones
+    pub conflict_subscriber: SubscriberPtr<MempoolConflict>,
+    /// Execution of each pending transaction on canonical state, used to
+    /// find the ones incoming transactions conflict with from the contract
+    /// state they touched, and the fee they paid
+    pending_access: HashMap<blake3::Hash, TxExecution>,
+    /// Canonical block `pending_access` was recorded on
+    pending_access_tip: Option<blake3::Hash>,
+    /// Notifications of transactions appended to the pending txs store
+    pub pending_tx_subscriber: SubscriberPtr<Transaction>,
+    /// Log of validator decisions, shared with [`Consensus`]
+    pub audit_log: AuditLog,
 }
 
 impl Validator {
     pub async fn new(db: &sled::Db, config: ValidatorConfig) -> Result<ValidatorPtr> {
         info!(target: "validator::new", "Initializing Validator");
         let testing_mode = config.testing_mode;
+        let verify_fees = config.verify_fees;
+
+        if let Some(limit) = config.proof_verification_limit {
+            info!(target: "validator::new", "Limiting concurrent proof verifications to {}", limit);
+            set_proof_verification_limit(limit);
+        }
 
         info!(target: "validator::new", "Initializing Blockchain");
         let blockchain = Blockchain::new(db)?;
@@ -114,20 +190,52 @@ impl Validator {
         // Write the changes to the actual chain db
         overlay.lock().unwrap().overlay.lock().unwrap().apply()?;
 
+        let audit_log = AuditLog::new(db, config.audit_log)?;
+
         info!(target: "validator::new", "Initializing Consensus");
-        let consensus = Consensus::new(blockchain.clone(), config.time_keeper, testing_mode);
+        let mut consensus =
+            Consensus::new(blockchain.clone(), config.time_keeper, testing_mode, verify_fees);
+        consensus.audit_log = audit_log.clone();
 
         // Create the actual state
-        let state =
-            Arc::new(RwLock::new(Self { blockchain, consensus, synced: false, testing_mode }));
+        let state = Arc::new(RwLock::new(Self {
+            blockchain,
+            consensus,
+            synced: false,
+            testing_mode,
+            verify_fees,
+            checkpoint_config: config.checkpoint_config,
+            last_checkpoint: None,
+            tx_policy: config.tx_policy,
+            mempool_metrics: MempoolMetrics::default(),
+            conflict_subscriber: Subscriber::new(),
+            pending_access: HashMap::new(),
+            pending_access_tip: None,
+            pending_tx_subscriber: Subscriber::new(),
+            audit_log,
+        }));
         info!(target: "validator::new", "Finished initializing validator");
 
         Ok(state)
     }
 
     /// The node retrieves a transaction, validates its state transition,
-    /// and appends it to the pending txs store.
+    /// and appends it to the pending txs store. Rejections are recorded
+    /// in the audit log, except for transactions we've already seen.
     pub async fn append_tx(&mut self, tx: &Transaction) -> Result<()> {
+        let result = self.try_append_tx(tx).await;
+
+        if let Err(e) = &result {
+            if !matches!(e, Error::TxVerifyFailed(TxVerifyFailed::AlreadySeenTx(_))) {
+                let event = AuditEvent::TxRejected { hash: tx.hash(), reason: e.to_string() };
+                self.audit_log.record(event);
+            }
+        }
+
+        result
+    }
+
+    async fn try_append_tx(&mut self, tx: &Transaction) -> Result<()> {
         let tx_hash = blake3::hash(&serialize(tx));
 
         // Check if we have already seen this tx
@@ -139,14 +247,31 @@ impl Validator {
             return Err(TxVerifyFailed::AlreadySeenTx(tx_hash.to_string()).into())
         }
 
+        // Check the transaction against our admission policy
+        if let Err(e) = self.tx_policy.check(tx) {
+            info!(target: "validator::append_tx", "Transaction {} rejected: {}", tx_hash, e);
+            return Err(e)
+        }
+
+        // Generate a time keeper for current slot
+        let time_keeper = self.consensus.time_keeper.current();
+
+        // Check the fee the transaction paid against our admission policy,
+        // before verifying anything expensive. Transactions that can't be
+        // executed are left to the state transition validation to reject.
+        if let Some(execution) = self.execute_pending_candidate(tx, &time_keeper).await? {
+            let min_fee = self.fee_model()?.min_fee(&execution.gas);
+            if let Err(e) = self.tx_policy.check_fee(execution.fee_paid, min_fee) {
+                info!(target: "validator::append_tx", "Transaction {} rejected: {}", tx_hash, e);
+                return Err(e)
+            }
+        }
+
         // Verify state transition
         info!(target: "validator::append_tx", "Starting state transition validation");
         let tx_vec = [tx.clone()];
         let mut valid = false;
 
-        // Generate a time keeper for current slot
-        let time_keeper = self.consensus.time_keeper.current();
-
         // If node participates in consensus and holds any forks, iterate over them
         // to verify transaction validity in their overlays
         for fork in self.consensus.forks.iter_mut() {
@@ -154,7 +279,8 @@ impl Validator {
             let overlay = fork.overlay.lock().unwrap().full_clone()?;
 
             // Verify transaction
-            let erroneous_txs = verify_transactions(&overlay, &time_keeper, &tx_vec).await?;
+            let erroneous_txs =
+                verify_transactions(&overlay, &time_keeper, &tx_vec, self.verify_fees).await?;
             if !erroneous_txs.is_empty() {
                 continue
             }
@@ -166,8 +292,10 @@ impl Validator {
 
         // Verify transaction against canonical state
         let overlay = BlockchainOverlay::new(&self.blockchain)?;
-        let erroneous_txs = verify_transactions(&overlay, &time_keeper, &tx_vec).await?;
-        if erroneous_txs.is_empty() {
+        let erroneous_txs =
+            verify_transactions(&overlay, &time_keeper, &tx_vec, self.verify_fees).await?;
+        let canonical_valid = erroneous_txs.is_empty();
+        if canonical_valid {
             valid = true
         }
 
@@ -176,9 +304,160 @@ impl Validator {
             return Err(TxVerifyFailed::ErroneousTxs(erroneous_txs).into())
         }
 
+        // Check if the transaction conflicts with pending ones, e.g. a double
+        // spend racing for the mempool. Pending transactions are verified
+        // against canonical state, so that's where we look for conflicts.
+        // Unless our conflict policy lets the transaction replace them by
+        // paying a higher fee, the pending ones were seen first, so they
+        // are kept.
+        if canonical_valid {
+            let (conflicts, fee_paid) = self.pending_conflicts(tx, &time_keeper).await?;
+            if !conflicts.is_empty() {
+                info!(target: "validator::append_tx", "Transaction {} conflicts with {} pending transactions", tx_hash, conflicts.len());
+
+                let replaced = self.tx_policy.conflict_policy() == ConflictPolicy::FeeReplacement &&
+                    conflicts.iter().all(|(_, fee)| fee_paid > *fee);
+                let conflicts: Vec<Transaction> =
+                    conflicts.into_iter().map(|(conflict, _)| conflict).collect();
+                let conflict_hashes: Vec<blake3::Hash> =
+                    conflicts.iter().map(|c| c.hash()).collect();
+
+                if !replaced {
+                    self.mempool_metrics.conflicts += 1;
+                    let event =
+                        MempoolConflict { tx_hash, conflicts: conflict_hashes, replaced: false };
+                    self.conflict_subscriber.notify(event).await;
+
+                    for fork in self.consensus.forks.iter_mut() {
+                        fork.mempool.retain(|x| *x != tx_hash);
+                    }
+                    return Err(TxVerifyFailed::PendingConflict(tx_hash.to_string()).into())
+                }
+
+                info!(target: "validator::append_tx", "Transaction {} paid {} in fees, replacing the pending transactions it conflicts with", tx_hash, fee_paid);
+                self.blockchain.remove_pending_txs(&conflicts)?;
+                for conflict in &conflict_hashes {
+                    self.pending_access.remove(conflict);
+                }
+                for fork in self.consensus.forks.iter_mut() {
+                    fork.mempool.retain(|x| !conflict_hashes.contains(x));
+                }
+
+                self.mempool_metrics.replacements += 1;
+                let event = MempoolConflict { tx_hash, conflicts: conflict_hashes, replaced: true };
+                self.conflict_subscriber.notify(event).await;
+            }
+        }
+
         // Add transaction to pending txs store
         self.blockchain.add_pending_txs(&tx_vec)?;
         info!(target: "validator::append_tx", "Appended tx to pending txs store");
+        self.pending_tx_subscriber.notify(tx.clone()).await;
+
+        Ok(())
+    }
+
+    /// Execute the given incoming transaction on canonical state, or on the
+    /// forks' state if it can't be executed there, without verifying its
+    /// signatures and ZK proofs. Returns `None` if it can't be executed on
+    /// any of them, in which case it isn't valid either.
+    async fn execute_pending_candidate(
+        &self,
+        tx: &Transaction,
+        time_keeper: &TimeKeeper,
+    ) -> Result<Option<TxExecution>> {
+        let overlay = BlockchainOverlay::new(&self.blockchain)?;
+        let result = execute_transaction(&overlay, time_keeper, tx).await;
+        overlay.lock().unwrap().overlay.lock().unwrap().purge_new_trees()?;
+        if let Ok(execution) = result {
+            return Ok(Some(execution))
+        }
+
+        for fork in &self.consensus.forks {
+            let overlay = fork.overlay.lock().unwrap().full_clone()?;
+            if let Ok(execution) = execute_transaction(&overlay, time_keeper, tx).await {
+                return Ok(Some(execution))
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Find the pending transactions the given transaction conflicts with,
+    /// meaning it's valid on its own against canonical state, but not once
+    /// they have been applied. Only pending transactions that wrote state the
+    /// given one touches can conflict with it, and each of them is checked by
+    /// replaying its writes and executing the given transaction on top, so
+    /// no signatures or ZK proofs get verified again. The conflicting ones are
+    /// returned along with the fee each of them paid, and the fee the given
+    /// transaction paid.
+    async fn pending_conflicts(
+        &mut self,
+        tx: &Transaction,
+        time_keeper: &TimeKeeper,
+    ) -> Result<(Vec<(Transaction, u64)>, u64)> {
+        let pending_txs = self.blockchain.get_pending_txs()?;
+        if pending_txs.is_empty() {
+            return Ok((vec![], 0))
+        }
+        self.index_pending_txs(&pending_txs, time_keeper).await?;
+
+        let overlay = BlockchainOverlay::new(&self.blockchain)?;
+        let result = execute_transaction(&overlay, time_keeper, tx).await;
+        overlay.lock().unwrap().overlay.lock().unwrap().purge_new_trees()?;
+        let execution = result?;
+
+        let mut conflicts = vec![];
+        for pending_tx in pending_txs {
+            let Some(pending) = self.pending_access.get(&pending_tx.hash()) else { continue };
+            if !execution.access.depends_on(&pending.access) {
+                continue
+            }
+
+            let overlay = BlockchainOverlay::new(&self.blockchain)?;
+            pending.access.replay(&overlay)?;
+            let result = execute_transaction(&overlay, time_keeper, tx).await;
+            overlay.lock().unwrap().overlay.lock().unwrap().purge_new_trees()?;
+            if result.is_err() {
+                conflicts.push((pending_tx, pending.fee_paid));
+            }
+        }
+
+        Ok((conflicts, execution.fee_paid))
+    }
+
+    /// Record the contract state each of the given pending transactions
+    /// touches on canonical state, and the fee it paid. Records are kept
+    /// until the canonical chain moves, so each pending transaction is
+    /// executed once per block. Transactions no longer valid on canonical
+    /// state touch nothing.
+    async fn index_pending_txs(
+        &mut self,
+        pending_txs: &[Transaction],
+        time_keeper: &TimeKeeper,
+    ) -> Result<()> {
+        let (_, tip) = self.blockchain.last()?;
+        if self.pending_access_tip != Some(tip) {
+            self.pending_access.clear();
+            self.pending_access_tip = Some(tip);
+        }
+
+        let pending_hashes: HashSet<blake3::Hash> =
+            pending_txs.iter().map(|tx| tx.hash()).collect();
+        self.pending_access.retain(|tx_hash, _| pending_hashes.contains(tx_hash));
+
+        for tx in pending_txs {
+            let tx_hash = tx.hash();
+            if self.pending_access.contains_key(&tx_hash) {
+                continue
+            }
+
+            let overlay = BlockchainOverlay::new(&self.blockchain)?;
+            let execution =
+                execute_transaction(&overlay, time_keeper, tx).await.unwrap_or_default();
+            overlay.lock().unwrap().overlay.lock().unwrap().purge_new_trees()?;
+            self.pending_access.insert(tx_hash, execution);
+        }
 
         Ok(())
     }
@@ -210,7 +489,8 @@ impl Validator {
                 let overlay = fork.overlay.lock().unwrap().full_clone()?;
 
                 // Verify transaction
-                let erroneous_txs = verify_transactions(&overlay, &time_keeper, &tx_vec).await?;
+                let erroneous_txs =
+                    verify_transactions(&overlay, &time_keeper, &tx_vec, self.verify_fees).await?;
                 if erroneous_txs.is_empty() {
                     valid = true;
                     continue
@@ -222,7 +502,8 @@ impl Validator {
 
             // Verify transaction against canonical state
             let overlay = BlockchainOverlay::new(&self.blockchain)?;
-            let erroneous_txs = verify_transactions(&overlay, &time_keeper, &tx_vec).await?;
+            let erroneous_txs =
+                verify_transactions(&overlay, &time_keeper, &tx_vec, self.verify_fees).await?;
             if erroneous_txs.is_empty() {
                 valid = true
             }
@@ -259,6 +540,45 @@ impl Validator {
         Ok(())
     }
 
+    /// The node retrieves a checkpoint signed by the configured authority set,
+    /// and keeps it if it's newer than the last one. Checkpoints contradicting
+    /// blocks the node has already finalized are rejected, since they never
+    /// override local finality. Returns `true` if the checkpoint was kept, so
+    /// it can be relayed further.
+    pub fn receive_checkpoint(&mut self, checkpoint: &Checkpoint) -> Result<bool> {
+        let Some(config) = &self.checkpoint_config else {
+            return Err(Error::CheckpointInvalid("Checkpoints are not enabled".to_string()))
+        };
+
+        // Ignore checkpoints we've already seen, or older ones
+        if let Some(last) = &self.last_checkpoint {
+            if checkpoint.slot <= last.slot {
+                debug!(target: "validator::receive_checkpoint", "Checkpoint for slot {} is not newer than the last one", checkpoint.slot);
+                return Ok(false)
+            }
+        }
+
+        checkpoint.verify(config)?;
+
+        // Check the checkpoint agrees with our finalized blocks
+        let (last_slot, _) = self.blockchain.last()?;
+        if checkpoint.slot <= last_slot {
+            let finalized = self.blockchain.order.get(&[checkpoint.slot], false)?[0];
+            if finalized != Some(checkpoint.block_hash) {
+                warn!(target: "validator::receive_checkpoint", "Checkpoint for slot {} conflicts with our finalized chain", checkpoint.slot);
+                return Err(Error::CheckpointInvalid("Conflicts with finalized chain".to_string()))
+            }
+        }
+
+        info!(target: "validator::receive_checkpoint", "Received checkpoint for block {} at slot {}", checkpoint.block_hash, checkpoint.slot);
+        if config.finality_hints {
+            self.consensus.checkpoint_hint = Some(checkpoint.clone());
+        }
+        self.last_checkpoint = Some(checkpoint.clone());
+
+        Ok(true)
+    }
+
     // ==========================
     // State transition functions
     // ==========================
@@ -294,19 +614,24 @@ impl Validator {
             let expected_reward = next_block_reward();
 
             // Verify block
-            if verify_block(
+            if let Err(e) = verify_block(
                 &overlay,
                 &time_keeper,
                 block,
                 previous,
                 expected_reward,
                 self.testing_mode,
+                self.verify_fees,
             )
             .await
-            .is_err()
             {
                 error!(target: "validator::add_blocks", "Erroneous block found in set");
                 overlay.lock().unwrap().overlay.lock().unwrap().purge_new_trees()?;
+                self.audit_log.record(AuditEvent::BlockRejected {
+                    hash: block.blockhash(),
+                    slot: block.header.slot,
+                    reason: e.to_string(),
+                });
                 return Err(Error::BlockIsInvalid(block.blockhash().to_string()))
             };
 
@@ -322,6 +647,12 @@ impl Validator {
         debug!(target: "validator::add_blocks", "Applying overlay changes");
         overlay.lock().unwrap().overlay.lock().unwrap().apply()?;
 
+        for block in blocks {
+            let event =
+                AuditEvent::BlockAccepted { hash: block.blockhash(), slot: block.header.slot };
+            self.audit_log.record(event);
+        }
+
         // Purge pending erroneous txs since canonical state has been changed
         self.blockchain.remove_pending_txs(&removed_txs)?;
         self.purge_pending_txs().await?;
@@ -351,7 +682,8 @@ impl Validator {
         );
 
         // Verify all transactions and get erroneous ones
-        let erroneous_txs = verify_transactions(&overlay, &time_keeper, txs).await?;
+        let erroneous_txs =
+            verify_transactions(&overlay, &time_keeper, txs, self.verify_fees).await?;
 
         let lock = overlay.lock().unwrap();
         let mut overlay = lock.overlay.lock().unwrap();
@@ -372,6 +704,94 @@ impl Validator {
         Ok(())
     }
 
+    /// Generate the time keeper for the given verifying slot, along with an
+    /// empty verifying keys map for the transaction's contracts, to simulate
+    /// a single transaction against canonical state.
+    fn simulation_setup(
+        &self,
+        tx: &Transaction,
+        verifying_slot: u64,
+    ) -> (TimeKeeper, HashMap<[u8; 32], HashMap<String, VerifyingKey>>) {
+        let time_keeper = TimeKeeper::new(
+            self.consensus.time_keeper.genesis_ts,
+            self.consensus.time_keeper.epoch_length,
+            self.consensus.time_keeper.slot_time,
+            verifying_slot,
+        );
+
+        let mut vks = HashMap::new();
+        for call in &tx.calls {
+            vks.insert(call.contract_id.to_bytes(), HashMap::new());
+        }
+
+        (time_keeper, vks)
+    }
+
+    /// Validate a single transaction against canonical state without applying
+    /// it. Unlike [`Validator::add_transactions`], the returned error describes
+    /// why the transaction failed verification, e.g. the contract's report.
+    pub async fn simulate_transaction(&self, tx: &Transaction, verifying_slot: u64) -> Result<()> {
+        debug!(target: "validator::simulate_transaction", "Instantiating BlockchainOverlay");
+        let overlay = BlockchainOverlay::new(&self.blockchain)?;
+
+        let (time_keeper, mut vks) = self.simulation_setup(tx, verifying_slot);
+
+        let result =
+            verify_transaction(&overlay, &time_keeper, tx, &mut vks, self.verify_fees).await;
+        overlay.lock().unwrap().overlay.lock().unwrap().purge_new_trees()?;
+        result
+    }
+
+    /// Same as [`Validator::simulate_transaction`], but the transaction doesn't
+    /// have to pay any fee yet. Returns the gas it used, from which wallets can
+    /// compute the fee to pay before building the fee call.
+    pub async fn estimate_transaction_gas(
+        &self,
+        tx: &Transaction,
+        verifying_slot: u64,
+    ) -> Result<GasData> {
+        debug!(target: "validator::estimate_transaction_gas", "Instantiating BlockchainOverlay");
+        let overlay = BlockchainOverlay::new(&self.blockchain)?;
+
+        let (time_keeper, mut vks) = self.simulation_setup(tx, verifying_slot);
+
+        let result = estimate_transaction_gas(&overlay, &time_keeper, tx, &mut vks).await;
+        overlay.lock().unwrap().overlay.lock().unwrap().purge_new_trees()?;
+        result
+    }
+
+    /// Retrieve the fee model transactions are currently verified against,
+    /// derived from the load of the last [`FEE_WINDOW`] canonical blocks.
+    pub fn fee_model(&self) -> Result<FeeModel> {
+        let overlay = BlockchainOverlay::new(&self.blockchain)?;
+        let block_txs = overlay.lock().unwrap().recent_block_txs(FEE_WINDOW)?;
+        Ok(FeeModel::from_block_txs(&block_txs))
+    }
+
+    /// Same as [`Validator::simulate_transaction`], but also returns the
+    /// structured debug traces emitted by the transaction's contract calls,
+    /// paired with the index of the emitting call. Tracing is a development
+    /// aid, so it is only available in testing mode.
+    pub async fn simulate_transaction_traced(
+        &self,
+        tx: &Transaction,
+        verifying_slot: u64,
+    ) -> Result<(Result<()>, Vec<(usize, ContractTrace)>)> {
+        if !self.testing_mode {
+            return Err(Error::Custom(String::from("Contract tracing requires testing mode")))
+        }
+
+        debug!(target: "validator::simulate_transaction_traced", "Instantiating BlockchainOverlay");
+        let overlay = BlockchainOverlay::new(&self.blockchain)?;
+
+        let (time_keeper, mut vks) = self.simulation_setup(tx, verifying_slot);
+
+        let result =
+            trace_transaction(&overlay, &time_keeper, tx, &mut vks, self.verify_fees).await;
+        overlay.lock().unwrap().overlay.lock().unwrap().purge_new_trees()?;
+        Ok(result)
+    }
+
     /// Append to canonical state received slot.
     /// This should be only used for test purposes.
     pub async fn receive_test_slot(&mut self, slot: &Slot) -> Result<()> {
@@ -397,8 +817,7 @@ impl Validator {
         }
 
         // Create an in memory blockchain overlay
-        let sled_db = sled::Config::new().temporary(true).open()?;
-        let blockchain = Blockchain::new(&sled_db)?;
+        let blockchain = Blockchain::in_memory()?;
         let overlay = BlockchainOverlay::new(&blockchain)?;
 
         // Set previous
@@ -429,6 +848,7 @@ impl Validator {
                 previous,
                 expected_reward,
                 self.testing_mode,
+                self.verify_fees,
             )
             .await
             .is_err()
@@ -0,0 +1,246 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
+
+use darkfi_sdk::{
+    crypto::{contract_id::DEPLOYOOOR_CONTRACT_ID, ContractId},
+    deploy::{DeployParamsV1, DEPLOY_FUNCTION_DEPLOY_V1},
+};
+use darkfi_serial::{deserialize, serialize};
+
+use crate::{
+    error::TxVerifyFailed, runtime::analysis::analyze_contract, tx::Transaction, Error, Result,
+};
+
+/// Admission policy applied to transactions before they enter the mempool.
+/// Policies only decide what a node is willing to relay and keep pending,
+/// they have no effect on the validity of transactions in blocks.
+pub trait TxPolicy: Send + Sync {
+    /// Check whether the given transaction is admitted, returning
+    /// [`TxVerifyFailed::PolicyRejected`] with the reason if it isn't.
+    fn check(&self, tx: &Transaction) -> Result<()>;
+
+    /// Check whether the fee a transaction paid is admitted, given the
+    /// minimum fee it has to pay for its verification under the current
+    /// fee model, returning [`TxVerifyFailed::PolicyRejected`] if it isn't.
+    fn check_fee(&self, _fee_paid: u64, _min_fee: u64) -> Result<()> {
+        Ok(())
+    }
+
+    /// How transactions conflicting with pending ones are handled
+    fn conflict_policy(&self) -> ConflictPolicy {
+        ConflictPolicy::FirstSeen
+    }
+}
+
+/// How the mempool handles an incoming transaction conflicting with pending
+/// ones, i.e. a double spend racing for the mempool.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// Keep the pending transactions, which were seen first, and reject
+    /// the incoming one
+    #[default]
+    FirstSeen,
+    /// Replace the pending transactions if the incoming one paid a strictly
+    /// higher fee than each of them through `Money::Fee`. Transactions not
+    /// paying fees never replace anything, so this behaves like
+    /// [`ConflictPolicy::FirstSeen`] until fees are paid.
+    FeeReplacement,
+}
+
+impl FromStr for ConflictPolicy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "first-seen" => Ok(Self::FirstSeen),
+            "fee-replacement" => Ok(Self::FeeReplacement),
+            _ => Err(Error::ParseFailed("Invalid mempool conflict policy")),
+        }
+    }
+}
+
+/// Notification of a transaction conflicting with pending ones, i.e. a
+/// double spend racing for the mempool. Depending on the [`ConflictPolicy`],
+/// either the incoming transaction got rejected, or it replaced the pending
+/// ones.
+#[derive(Clone, Debug)]
+pub struct MempoolConflict {
+    /// Hash of the incoming transaction
+    pub tx_hash: blake3::Hash,
+    /// Hashes of the pending transactions it conflicts with
+    pub conflicts: Vec<blake3::Hash>,
+    /// Whether the incoming transaction replaced the pending ones
+    pub replaced: bool,
+}
+
+/// Counters of the mempool conflicts handling
+#[derive(Clone, Debug, Default)]
+pub struct MempoolMetrics {
+    /// Incoming transactions rejected for conflicting with pending ones
+    pub conflicts: u64,
+    /// Incoming transactions that replaced the pending ones they conflict with
+    pub replacements: u64,
+}
+
+/// Default policy admitting every transaction
+#[derive(Clone, Debug, Default)]
+pub struct AllowAll;
+
+impl TxPolicy for AllowAll {
+    fn check(&self, _tx: &Transaction) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Rules enforced by a [`RulePolicy`]
+#[derive(Clone, Debug, Default)]
+pub struct PolicyRules {
+    /// Contracts whose calls are refused
+    pub blocked_contracts: Vec<ContractId>,
+    /// Maximum serialized transaction size, in bytes
+    pub max_tx_size: Option<usize>,
+    /// Maximum number of contract calls in a transaction
+    pub max_calls: Option<usize>,
+    /// Maximum number of calls to a given contract in a transaction
+    pub contract_call_limits: Vec<(ContractId, usize)>,
+    /// Refuse contract deployments whose wasm bincode has static analysis findings
+    pub strict_contract_analysis: bool,
+    /// How transactions conflicting with pending ones are handled
+    pub conflict_policy: ConflictPolicy,
+    /// Minimum fee a transaction has to pay, regardless of its gas use
+    pub min_fee: Option<u64>,
+    /// Minimum fee a transaction has to pay, as a percentage of the fee
+    /// model's minimum fee for its gas use, e.g. 150 for 1.5 times that
+    pub min_fee_multiplier: Option<u64>,
+}
+
+/// Config-driven policy checking transactions against a set of [`PolicyRules`]
+#[derive(Clone, Debug)]
+pub struct RulePolicy {
+    blocked_contracts: HashSet<[u8; 32]>,
+    max_tx_size: Option<usize>,
+    max_calls: Option<usize>,
+    contract_call_limits: HashMap<[u8; 32], usize>,
+    strict_contract_analysis: bool,
+    conflict_policy: ConflictPolicy,
+    min_fee: Option<u64>,
+    min_fee_multiplier: Option<u64>,
+}
+
+impl RulePolicy {
+    pub fn new(rules: PolicyRules) -> Self {
+        Self {
+            blocked_contracts: rules.blocked_contracts.iter().map(|c| c.to_bytes()).collect(),
+            max_tx_size: rules.max_tx_size,
+            max_calls: rules.max_calls,
+            contract_call_limits: rules
+                .contract_call_limits
+                .iter()
+                .map(|(c, limit)| (c.to_bytes(), *limit))
+                .collect(),
+            strict_contract_analysis: rules.strict_contract_analysis,
+            conflict_policy: rules.conflict_policy,
+            min_fee: rules.min_fee,
+            min_fee_multiplier: rules.min_fee_multiplier,
+        }
+    }
+}
+
+impl TxPolicy for RulePolicy {
+    fn check(&self, tx: &Transaction) -> Result<()> {
+        let reject = |reason: String| Err(TxVerifyFailed::PolicyRejected(reason).into());
+
+        if let Some(max_calls) = self.max_calls {
+            if tx.calls.len() > max_calls {
+                return reject(format!("{} calls exceed the limit of {}", tx.calls.len(), max_calls))
+            }
+        }
+
+        let mut calls_per_contract: HashMap<[u8; 32], usize> = HashMap::new();
+        for call in &tx.calls {
+            let contract_id = call.contract_id.to_bytes();
+            if self.blocked_contracts.contains(&contract_id) {
+                return reject(format!("Calls to contract {} are not accepted", call.contract_id))
+            }
+
+            let calls = calls_per_contract.entry(contract_id).or_default();
+            *calls += 1;
+            if let Some(limit) = self.contract_call_limits.get(&contract_id) {
+                if *calls > *limit {
+                    return reject(format!(
+                        "Calls to contract {} exceed the limit of {}",
+                        call.contract_id, limit
+                    ))
+                }
+            }
+        }
+
+        if let Some(max_tx_size) = self.max_tx_size {
+            let tx_size = serialize(tx).len();
+            if tx_size > max_tx_size {
+                return reject(format!(
+                    "Size of {} bytes exceeds the limit of {}",
+                    tx_size, max_tx_size
+                ))
+            }
+        }
+
+        if self.strict_contract_analysis {
+            for call in &tx.calls {
+                if call.contract_id != *DEPLOYOOOR_CONTRACT_ID ||
+                    call.data.first() != Some(&DEPLOY_FUNCTION_DEPLOY_V1)
+                {
+                    continue
+                }
+
+                // Malformed deployments are left to transaction verification
+                let Ok(params) = deserialize::<DeployParamsV1>(&call.data[1..]) else { continue };
+                let Ok(findings) = analyze_contract(&params.wasm_bincode) else { continue };
+                if let Some(finding) = findings.first() {
+                    return reject(format!("Contract deployment refused: {}", finding))
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_fee(&self, fee_paid: u64, min_fee: u64) -> Result<()> {
+        let mut floor = self.min_fee.unwrap_or_default();
+        if let Some(multiplier) = self.min_fee_multiplier {
+            let scaled = (min_fee as u128 * multiplier as u128).div_ceil(100);
+            floor = floor.max(scaled.min(u64::MAX as u128) as u64);
+        }
+
+        if fee_paid < floor {
+            let reason = format!("Fee of {} is below the floor of {}", fee_paid, floor);
+            return Err(TxVerifyFailed::PolicyRejected(reason).into())
+        }
+
+        Ok(())
+    }
+
+    fn conflict_policy(&self) -> ConflictPolicy {
+        self.conflict_policy
+    }
+}
@@ -16,14 +16,97 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use darkfi_sdk::crypto::{PublicKey, CONSENSUS_CONTRACT_ID, DAO_CONTRACT_ID, MONEY_CONTRACT_ID};
+use std::collections::HashMap;
+
+use darkfi_sdk::crypto::{
+    contract_id::DEPLOYOOOR_CONTRACT_ID, ContractId, PublicKey, AUTH_CONTRACT_ID,
+    CONSENSUS_CONTRACT_ID, DAO_CONTRACT_ID, MONEY_CONTRACT_ID,
+};
 use darkfi_serial::serialize;
-use log::info;
+use log::{error, info, warn};
 
 use crate::{
-    blockchain::BlockchainOverlayPtr, runtime::vm_runtime::Runtime, util::time::TimeKeeper, Result,
+    blockchain::BlockchainOverlayPtr,
+    runtime::{
+        build_meta::{code_hash, read_build_metadata},
+        vm_runtime::Runtime,
+    },
+    util::time::TimeKeeper,
+    Error, Result,
 };
 
+/// Native wasm contracts embedded in the node, as (name, ContractID, bincode).
+pub fn native_contract_bincodes() -> [(&'static str, ContractId, &'static [u8]); 5] {
+    [
+        (
+            "Money Contract",
+            *MONEY_CONTRACT_ID,
+            include_bytes!("../contract/money/money_contract.wasm"),
+        ),
+        ("DAO Contract", *DAO_CONTRACT_ID, include_bytes!("../contract/dao/dao_contract.wasm")),
+        (
+            "Consensus Contract",
+            *CONSENSUS_CONTRACT_ID,
+            include_bytes!("../contract/consensus/consensus_contract.wasm"),
+        ),
+        ("Auth Contract", *AUTH_CONTRACT_ID, include_bytes!("../contract/auth/auth_contract.wasm")),
+        (
+            "Deployooor Contract",
+            *DEPLOYOOOR_CONTRACT_ID,
+            include_bytes!("../contract/deployooor/deployooor_contract.wasm"),
+        ),
+    ]
+}
+
+/// Check the embedded native wasm contracts against their expected code
+/// hashes, keyed by ContractID bytes. A contract whose code hash differs from the expected one, or
+/// which carries no build metadata, is reported. In strict mode such a
+/// contract, or one without an expected hash, is an error.
+pub fn check_native_contracts(
+    expected_hashes: &HashMap<[u8; 32], blake3::Hash>,
+    strict: bool,
+) -> Result<()> {
+    let mut failed = false;
+
+    for (name, contract_id, bincode) in native_contract_bincodes() {
+        let hash = code_hash(bincode)?;
+
+        match read_build_metadata(bincode)? {
+            Some(metadata) => info!(
+                target: "validator::utils::check_native_contracts",
+                "{} code hash {}, built from sources {} with {}",
+                name, hash, blake3::Hash::from(metadata.source_hash), metadata.compiler_version,
+            ),
+            None => {
+                warn!(target: "validator::utils::check_native_contracts", "{} has no build metadata", name);
+                failed = true;
+            }
+        }
+
+        match expected_hashes.get(&contract_id.to_bytes()) {
+            Some(expected) if *expected != hash => {
+                error!(
+                    target: "validator::utils::check_native_contracts",
+                    "{} code hash {} doesn't match expected {}", name, hash, expected,
+                );
+                failed = true;
+            }
+            Some(_) => {}
+            None if strict => {
+                warn!(target: "validator::utils::check_native_contracts", "No expected code hash for {}", name);
+                failed = true;
+            }
+            None => {}
+        }
+    }
+
+    if strict && failed {
+        return Err(Error::ContractBuildMismatch("Native contracts failed verification".to_string()))
+    }
+
+    Ok(())
+}
+
 /// Deploy DarkFi native wasm contracts to provided blockchain overlay.
 /// If overlay already contains the contracts, it will just open the
 /// necessary db and trees, and give back what it has. This means that
@@ -51,35 +134,30 @@ pub fn deploy_native_contracts(
     // The Consensus contract uses an empty payload to deploy itself.
     let consensus_contract_deploy_payload = vec![];
 
-    let native_contracts = vec![
-        (
-            "Money Contract",
-            *MONEY_CONTRACT_ID,
-            include_bytes!("../contract/money/money_contract.wasm").to_vec(),
-            money_contract_deploy_payload,
-        ),
-        (
-            "DAO Contract",
-            *DAO_CONTRACT_ID,
-            include_bytes!("../contract/dao/dao_contract.wasm").to_vec(),
-            dao_contract_deploy_payload,
-        ),
-        (
-            "Consensus Contract",
-            *CONSENSUS_CONTRACT_ID,
-            include_bytes!("../contract/consensus/consensus_contract.wasm").to_vec(),
-            consensus_contract_deploy_payload,
-        ),
+    // The Auth contract uses an empty payload to deploy itself.
+    let auth_contract_deploy_payload = vec![];
+
+    // The Deployooor contract uses an empty payload to deploy itself.
+    let deployooor_contract_deploy_payload = vec![];
+
+    let payloads = [
+        money_contract_deploy_payload,
+        dao_contract_deploy_payload,
+        consensus_contract_deploy_payload,
+        auth_contract_deploy_payload,
+        deployooor_contract_deploy_payload,
     ];
 
-    for nc in native_contracts {
-        info!(target: "validator::utils::deploy_native_contracts", "Deploying {} with ContractID {}", nc.0, nc.1);
+    for ((name, contract_id, bincode), payload) in
+        native_contract_bincodes().into_iter().zip(payloads)
+    {
+        info!(target: "validator::utils::deploy_native_contracts", "Deploying {} with ContractID {}", name, contract_id);
 
-        let mut runtime = Runtime::new(&nc.2[..], overlay.clone(), nc.1, time_keeper.clone())?;
+        let mut runtime = Runtime::new(bincode, overlay.clone(), contract_id, time_keeper.clone())?;
 
-        runtime.deploy(&nc.3)?;
+        runtime.deploy(&payload)?;
 
-        info!(target: "validator::utils::deploy_native_contracts", "Successfully deployed {}", nc.0);
+        info!(target: "validator::utils::deploy_native_contracts", "Successfully deployed {}", name);
     }
 
     info!(target: "validator::utils::deploy_native_contracts", "Finished deployment of native WASM contracts");
@@ -16,25 +16,56 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::{collections::HashMap, io::Cursor};
+use std::{collections::HashMap, io::Cursor, sync::OnceLock};
 
 use darkfi_sdk::{
-    crypto::{PublicKey, CONSENSUS_CONTRACT_ID},
+    activation::{Feature, ACTIVATIONS},
+    crypto::{contract_id::DEPLOYOOOR_CONTRACT_ID, ContractId, PublicKey, CONSENSUS_CONTRACT_ID},
+    deploy::{DeployParamsV1, DEPLOY_FUNCTION_DEPLOY_V1},
+    log::ContractTrace,
     pasta::pallas,
+    tx::{AccessList, ContractCall},
 };
-use darkfi_serial::{Decodable, Encodable, WriteExt};
+use darkfi_serial::{deserialize, Decodable, Encodable, WriteExt};
 use log::{debug, error, warn};
+use smol::lock::{Semaphore, SemaphoreGuard};
 
 use crate::{
-    blockchain::{BlockInfo, BlockchainOverlayPtr},
-    error::TxVerifyFailed,
-    runtime::vm_runtime::Runtime,
-    tx::Transaction,
+    blockchain::{BlockInfo, BlockchainOverlayPtr, DeploymentRecord},
+    consensus::fees::{
+        circuit_gas_use, compute_fee, signatures_gas_use, storage_gas_use, FeeModel, GasData,
+        CIRCUIT_GAS_MULTIPLIER, FEE_WINDOW,
+    },
+    error::{CallPhase, TxVerifyFailed},
+    runtime::{analysis::analyze_contract, state_access::StateAccess, vm_runtime::Runtime},
+    tx::{Transaction, ZkpAccumulator},
     util::time::TimeKeeper,
     zk::VerifyingKey,
     Error, Result,
 };
 
+/// Bounds the number of ZK proof verifications running at once across the
+/// node, since each of them can take a lot of memory.
+static PROOF_VERIFICATION_PERMITS: OnceLock<Semaphore> = OnceLock::new();
+
+/// Set the maximum number of ZK proof verifications allowed to run at once,
+/// trading verification latency for bounded peak memory use. Only the first
+/// call has an effect, and until it's made verification is unbounded.
+pub fn set_proof_verification_limit(limit: usize) {
+    let limit = limit.max(1);
+    if PROOF_VERIFICATION_PERMITS.set(Semaphore::new(limit)).is_err() {
+        warn!(target: "validator::verification", "Proof verification limit is already set");
+    }
+}
+
+/// Wait for a ZK proof verification slot, if verification is bounded.
+async fn acquire_proof_permit() -> Option<SemaphoreGuard<'static>> {
+    match PROOF_VERIFICATION_PERMITS.get() {
+        Some(permits) => Some(permits.acquire().await),
+        None => None,
+    }
+}
+
 /// Validate given genesis [`BlockInfo`], and apply it to the provided overlay
 pub async fn verify_genesis_block(
     overlay: &BlockchainOverlayPtr,
@@ -80,8 +111,8 @@ pub async fn verify_genesis_block(
         return Err(TxVerifyFailed::ErroneousTxs(vec![block.producer.proposal.clone()]).into())
     }
 
-    // Verify transactions
-    let erroneous_txs = verify_transactions(overlay, time_keeper, &block.txs).await?;
+    // Verify transactions, genesis ones don't pay fees
+    let erroneous_txs = verify_transactions(overlay, time_keeper, &block.txs, false).await?;
     if !erroneous_txs.is_empty() {
         warn!(target: "validator::verification::verify_genesis_block", "Erroneous transactions found in set");
         overlay.lock().unwrap().overlay.lock().unwrap().purge_new_trees()?;
@@ -103,6 +134,7 @@ pub async fn verify_block(
     previous: &BlockInfo,
     expected_reward: u64,
     testing_mode: bool,
+    verify_fees: bool,
 ) -> Result<()> {
     let block_hash = block.blockhash().to_string();
     debug!(target: "validator::verification::verify_block", "Validating block {}", block_hash);
@@ -127,7 +159,7 @@ pub async fn verify_block(
     }
 
     // Verify transactions
-    let erroneous_txs = verify_transactions(overlay, time_keeper, &block.txs).await?;
+    let erroneous_txs = verify_transactions(overlay, time_keeper, &block.txs, verify_fees).await?;
     if !erroneous_txs.is_empty() {
         warn!(target: "validator::verification::verify_block", "Erroneous transactions found in set");
         overlay.lock().unwrap().overlay.lock().unwrap().purge_new_trees()?;
@@ -175,9 +207,8 @@ pub async fn verify_proposal_transaction(
     // Initialize the map
     vks.insert(tx.calls[0].contract_id.to_bytes(), HashMap::new());
 
-    // TODO: when fee is implemented, differentiate here since this transaction
-    // won't have fee
-    verify_transaction(overlay, time_keeper, tx, &mut vks).await?;
+    // Proposal transactions don't pay fees
+    verify_transaction(overlay, time_keeper, tx, &mut vks, false).await?;
 
     debug!(target: "validator::verification::verify_proposal_transaction", "Proposal transaction {} verified successfully", tx_hash);
 
@@ -185,12 +216,128 @@ pub async fn verify_proposal_transaction(
 }
 
 /// Validate WASM execution, signatures, and ZK proofs for a given [`Transaction`],
-/// and apply it to the provided overlay.
+/// and apply it to the provided overlay. If `verify_fee` is set, the fee the
+/// transaction paid through `Money::Fee` must also cover the cost of verifying it,
+/// at the gas price derived from the last [`FEE_WINDOW`] blocks of the overlay.
 pub async fn verify_transaction(
     overlay: &BlockchainOverlayPtr,
     time_keeper: &TimeKeeper,
     tx: &Transaction,
     verifying_keys: &mut HashMap<[u8; 32], HashMap<String, VerifyingKey>>,
+    verify_fee: bool,
+) -> Result<()> {
+    let mut collectors = VerifyCollectors::default();
+    verify_transaction_inner(overlay, time_keeper, tx, verifying_keys, verify_fee, &mut collectors)
+        .await
+}
+
+/// Same as [`verify_transaction`], but doesn't require the transaction to
+/// pay any fee, and returns the gas it used instead, so the fee it has to
+/// pay can be computed before building its fee call.
+pub async fn estimate_transaction_gas(
+    overlay: &BlockchainOverlayPtr,
+    time_keeper: &TimeKeeper,
+    tx: &Transaction,
+    verifying_keys: &mut HashMap<[u8; 32], HashMap<String, VerifyingKey>>,
+) -> Result<GasData> {
+    let mut gas = GasData::default();
+    let mut collectors = VerifyCollectors { gas_used: Some(&mut gas), ..Default::default() };
+    verify_transaction_inner(overlay, time_keeper, tx, verifying_keys, false, &mut collectors)
+        .await?;
+    Ok(gas)
+}
+
+/// Outcome of executing a [`Transaction`] with [`execute_transaction`]
+#[derive(Debug, Default)]
+pub struct TxExecution {
+    /// Contract state touched by the transaction's calls
+    pub access: StateAccess,
+    /// Gas used by the transaction
+    pub gas: GasData,
+    /// Fee paid by the transaction's calls
+    pub fee_paid: u64,
+}
+
+/// Execute the contract calls of a given [`Transaction`] on the provided
+/// overlay and return the contract state they touched, along with the gas
+/// they used and the fee they paid. Its signatures and ZK proofs are not
+/// verified, so this is only meant for transactions that have already been
+/// verified, e.g. pending ones, or to check them before verification.
+pub async fn execute_transaction(
+    overlay: &BlockchainOverlayPtr,
+    time_keeper: &TimeKeeper,
+    tx: &Transaction,
+) -> Result<TxExecution> {
+    let mut vks = HashMap::new();
+    for call in &tx.calls {
+        vks.insert(call.contract_id.to_bytes(), HashMap::new());
+    }
+
+    // The accumulated signatures and proofs are simply dropped
+    let mut accumulator = ZkpAccumulator::new();
+    let mut execution = TxExecution::default();
+    let mut collectors = VerifyCollectors {
+        accumulator: Some(&mut accumulator),
+        access: Some(&mut execution.access),
+        gas_used: Some(&mut execution.gas),
+        fee_paid: Some(&mut execution.fee_paid),
+        ..Default::default()
+    };
+    verify_transaction_inner(overlay, time_keeper, tx, &mut vks, false, &mut collectors).await?;
+    Ok(execution)
+}
+
+/// Same as [`verify_transaction`], but also collects the structured debug
+/// traces emitted by the transaction's contract calls, along with the index
+/// of the call that emitted them. Traces are returned even if verification
+/// failed, to aid in debugging the failure.
+pub async fn trace_transaction(
+    overlay: &BlockchainOverlayPtr,
+    time_keeper: &TimeKeeper,
+    tx: &Transaction,
+    verifying_keys: &mut HashMap<[u8; 32], HashMap<String, VerifyingKey>>,
+    verify_fee: bool,
+) -> (Result<()>, Vec<(usize, ContractTrace)>) {
+    let mut traces = vec![];
+    let mut collectors = VerifyCollectors { traces: Some(&mut traces), ..Default::default() };
+    let result = verify_transaction_inner(
+        overlay,
+        time_keeper,
+        tx,
+        verifying_keys,
+        verify_fee,
+        &mut collectors,
+    )
+    .await;
+    (result, traces)
+}
+
+/// Optional outputs of [`verify_transaction_inner`], each of them only
+/// gathered when set.
+#[derive(Default)]
+struct VerifyCollectors<'a> {
+    /// Accumulator the signatures and ZK proofs are added to, instead of
+    /// being verified immediately
+    accumulator: Option<&'a mut ZkpAccumulator>,
+    /// Contract state touched by the transaction's calls
+    access: Option<&'a mut StateAccess>,
+    /// Structured debug traces emitted by the calls, along with the index
+    /// of the emitting call. Contract tracing is only enabled when set.
+    traces: Option<&'a mut Vec<(usize, ContractTrace)>>,
+    /// Gas used by the transaction
+    gas_used: Option<&'a mut GasData>,
+    /// Fee paid by the transaction's calls
+    fee_paid: Option<&'a mut u64>,
+}
+
+/// Same as [`verify_transaction`], but also fills in the given collectors.
+async fn verify_transaction_inner(
+    overlay: &BlockchainOverlayPtr,
+    time_keeper: &TimeKeeper,
+    tx: &Transaction,
+    verifying_keys: &mut HashMap<[u8; 32], HashMap<String, VerifyingKey>>,
+    verify_fee: bool,
+    collectors: &mut VerifyCollectors<'_>,
 ) -> Result<()> {
     let tx_hash = tx.hash();
     debug!(target: "validator::verification::verify_transaction", "Validating transaction {}", tx_hash);
@@ -199,6 +346,11 @@ pub async fn verify_transaction(
     let mut zkp_table = vec![];
     // Table of public keys used for signature verification
     let mut sig_table = vec![];
+    // Gas used executing the calls, and the fee they collected
+    let mut wasm_gas = 0_u64;
+    let mut fee_paid = 0_u64;
+    // Size of the contract bincodes the transaction deployed
+    let mut deployed_bytes = 0_usize;
 
     // Iterate over all calls to get the metadata
     for (idx, call) in tx.calls.iter().enumerate() {
@@ -214,53 +366,44 @@ pub async fn verify_transaction(
 
         let mut runtime =
             Runtime::new(&wasm, overlay.clone(), call.contract_id, time_keeper.clone())?;
-
-        debug!(target: "validator::verification::verify_transaction", "Executing \"metadata\" call");
-        let metadata = runtime.metadata(&payload)?;
-
-        // Decode the metadata retrieved from the execution
-        let mut decoder = Cursor::new(&metadata);
-
-        // The tuple is (zkasa_ns, public_inputs)
-        let zkp_pub: Vec<(String, Vec<pallas::Base>)> = Decodable::decode(&mut decoder)?;
-        let sig_pub: Vec<PublicKey> = Decodable::decode(&mut decoder)?;
-        // TODO: Make sure we've read all the bytes above.
-        debug!(target: "validator::verification::verify_transaction", "Successfully executed \"metadata\" call");
-
-        // Here we'll look up verifying keys and insert them into the per-contract map.
-        debug!(target: "validator::verification::verify_transaction", "Performing VerifyingKey lookups from the sled db");
-        for (zkas_ns, _) in &zkp_pub {
-            let inner_vk_map = verifying_keys.get_mut(&call.contract_id.to_bytes()).unwrap();
-
-            // TODO: This will be a problem in case of ::deploy, unless we force a different
-            // namespace and disable updating existing circuit. Might be a smart idea to do
-            // so in order to have to care less about being able to verify historical txs.
-            if inner_vk_map.contains_key(zkas_ns.as_str()) {
-                continue
-            }
-
-            let (_, vk) = overlay.lock().unwrap().contracts.get_zkas(&call.contract_id, zkas_ns)?;
-
-            inner_vk_map.insert(zkas_ns.to_string(), vk);
+        runtime.set_access_list(tx.access_list.clone());
+        runtime.set_tracing(collectors.traces.is_some());
+
+        // The state access is taken out of the runtime even if the call failed,
+        // since a failure can be caused by state another transaction wrote.
+        let res = execute_call(&mut runtime, overlay, idx, call, &payload, verifying_keys);
+        if let Some(access) = collectors.access.as_deref_mut() {
+            access.extend(runtime.take_state_access());
+        }
+        if let Some(traces) = collectors.traces.as_deref_mut() {
+            traces.extend(runtime.take_traces().into_iter().map(|trace| (idx, trace)));
         }
+        wasm_gas = wasm_gas.saturating_add(runtime.gas_used());
+        fee_paid = fee_paid.saturating_add(runtime.fee_paid());
+        let (zkp_pub, sig_pub) = res?;
 
         zkp_table.push(zkp_pub);
         sig_table.push(sig_pub);
 
-        // After getting the metadata, we run the "exec" function with the same runtime
-        // and the same payload.
-        debug!(target: "validator::verification::verify_transaction", "Executing \"exec\" call");
-        let state_update = runtime.exec(&payload)?;
-        debug!(target: "validator::verification::verify_transaction", "Successfully executed \"exec\" call");
-
-        // If that was successful, we apply the state update in the ephemeral overlay.
-        debug!(target: "validator::verification::verify_transaction", "Executing \"apply\" call");
-        runtime.apply(&state_update)?;
-        debug!(target: "validator::verification::verify_transaction", "Successfully executed \"apply\" call");
+        // The Deployooor contract only registers deployments, since contracts
+        // can't store wasm bincodes, so once it accepted the call we perform
+        // the actual deployment here.
+        if call.contract_id == *DEPLOYOOOR_CONTRACT_ID &&
+            call.data.first() == Some(&DEPLOY_FUNCTION_DEPLOY_V1)
+        {
+            let (gas, bytes) =
+                deploy_contract(overlay, time_keeper, call, collectors.access.as_deref_mut())?;
+            wasm_gas = wasm_gas.saturating_add(gas);
+            deployed_bytes = deployed_bytes.saturating_add(bytes);
+        }
 
         // At this point we're done with the call and move on to the next one.
     }
 
+    if let Some(paid) = collectors.fee_paid.as_deref_mut() {
+        *paid = fee_paid;
+    }
+
     // When we're done looping and executing over the tx's contract calls, we now
     // move on with verification. First we verify the signatures as that's cheaper,
     // and then finally we verify the ZK proofs.
@@ -270,19 +413,75 @@ pub async fn verify_transaction(
         return Err(TxVerifyFailed::MissingSignatures.into())
     }
 
-    // TODO: Go through the ZK circuits that have to be verified and account for the opcodes.
+    // Check the paid fee covers the transaction's cost before verifying
+    // anything expensive, once fees are activated.
+    let check_fee =
+        verify_fee && ACTIVATIONS.is_active(Feature::TxFees, time_keeper.verifying_slot);
+    if check_fee || collectors.gas_used.is_some() {
+        let mut gas = GasData {
+            wasm: wasm_gas,
+            signatures: signatures_gas_use(tx.signatures.iter().map(|s| s.len()).sum()),
+            zk_circuits: 0,
+            storage: storage_gas_use(deployed_bytes),
+        };
+        for (call, zkp_pub) in tx.calls.iter().zip(&zkp_table) {
+            for (zkas_ns, _) in zkp_pub {
+                let zkbin =
+                    overlay.lock().unwrap().contracts.get_zkbin(&call.contract_id, zkas_ns)?;
+                let circuit_gas = circuit_gas_use(&zkbin).saturating_mul(CIRCUIT_GAS_MULTIPLIER);
+                gas.zk_circuits = gas.zk_circuits.saturating_add(circuit_gas);
+            }
+        }
+
+        // Enforced fees follow the recent load of the chain, while estimates
+        // are given at the base gas price, leaving the pricing to the wallet.
+        let required = if check_fee {
+            let block_txs = overlay.lock().unwrap().recent_block_txs(FEE_WINDOW)?;
+            FeeModel::from_block_txs(&block_txs).min_fee(&gas)
+        } else {
+            compute_fee(&gas)
+        };
+        debug!(target: "validator::verification::verify_transaction", "Transaction {} used {:?}, requiring a fee of {}", tx_hash, gas, required);
+        if check_fee && fee_paid < required {
+            error!(target: "validator::verification::verify_transaction", "Transaction {} paid {} in fees, but requires {}", tx_hash, fee_paid, required);
+            return Err(TxVerifyFailed::InsufficientFee(fee_paid, required).into())
+        }
+
+        if let Some(gas_used) = collectors.gas_used.as_deref_mut() {
+            *gas_used = gas;
+        }
+    }
+
+    if let Some(accumulator) = collectors.accumulator.as_deref_mut() {
+        debug!(target: "validator::verification::verify_transaction", "Accumulating signatures and ZK proofs for transaction {}", tx_hash);
+        if let Err(e) = tx.accumulate_sigs(sig_table, accumulator) {
+            error!(target: "validator::verification::verify_transaction", "Signature accumulation for tx {} failed: {}", tx_hash, e);
+            return Err(TxVerifyFailed::InvalidSignature.into())
+        }
+
+        if let Err(e) = tx.accumulate_zkps(verifying_keys, zkp_table, accumulator) {
+            error!(target: "validator::verification::verify_transaction", "ZK proof accumulation for tx {} failed: {}", tx_hash, e);
+            return Err(with_call_context(e, TxVerifyFailed::InvalidZkProof))
+        }
+
+        debug!(target: "validator::verification::verify_transaction", "Transaction {} verified successfully, pending signatures and ZK proofs", tx_hash);
+        return Ok(())
+    }
 
     if let Err(e) = tx.verify_sigs(sig_table) {
         error!(target: "validator::verification::verify_transaction", "Signature verification for tx {} failed: {}", tx_hash, e);
-        return Err(TxVerifyFailed::InvalidSignature.into())
+        return Err(with_call_context(e, TxVerifyFailed::InvalidSignature))
     }
 
     debug!(target: "validator::verification::verify_transaction", "Signature verification successful");
 
     debug!(target: "validator::verification::verify_transaction", "Verifying ZK proofs for transaction {}", tx_hash);
-    if let Err(e) = tx.verify_zkps(verifying_keys, zkp_table).await {
+    let permit = acquire_proof_permit().await;
+    let result = tx.verify_zkps(verifying_keys, zkp_table).await;
+    drop(permit);
+    if let Err(e) = result {
         error!(target: "validator::verification::verify_transaction", "ZK proof verification for tx {} failed: {}", tx_hash, e);
-        return Err(TxVerifyFailed::InvalidZkProof.into())
+        return Err(with_call_context(e, TxVerifyFailed::InvalidZkProof))
     }
 
     debug!(target: "validator::verification::verify_transaction", "ZK proof verification successful");
@@ -291,14 +490,336 @@ pub async fn verify_transaction(
     Ok(())
 }
 
-/// Validate a set of [`Transaction`] in sequence and apply them if all are valid.
+/// Keep a verification error if it can be pinned to a single contract call,
+/// otherwise replace it with the given coarse one.
+fn with_call_context(e: Error, fallback: TxVerifyFailed) -> Error {
+    match e {
+        Error::TxVerifyFailed(e) if e.call_context().is_some() => e.into(),
+        _ => fallback.into(),
+    }
+}
+
+/// Attach the call index, contract ID and phase to an error raised while
+/// executing a contract call, keeping the contract's report if it gave one.
+fn call_failed(e: Error, idx: usize, call: &ContractCall, phase: CallPhase) -> Error {
+    let contract_id = call.contract_id.to_string();
+    match e {
+        Error::ContractFailure(report) => {
+            TxVerifyFailed::ContractCallFailed(idx, contract_id, phase, report).into()
+        }
+        e => TxVerifyFailed::CallFailed(idx, contract_id, phase, e.to_string()).into(),
+    }
+}
+
+/// Run the "metadata", "exec" and "apply" sections of a single contract call
+/// on the given runtime, looking up any verifying keys the call needs.
+/// Returns the call's ZK proof public inputs and signature public keys.
+/// Failures are reported along with the call index and the failing phase.
+fn execute_call(
+    runtime: &mut Runtime,
+    overlay: &BlockchainOverlayPtr,
+    idx: usize,
+    call: &ContractCall,
+    payload: &[u8],
+    verifying_keys: &mut HashMap<[u8; 32], HashMap<String, VerifyingKey>>,
+) -> Result<(Vec<(String, Vec<pallas::Base>)>, Vec<PublicKey>)> {
+    debug!(target: "validator::verification::verify_transaction", "Executing \"metadata\" call");
+    let metadata =
+        runtime.metadata(payload).map_err(|e| call_failed(e, idx, call, CallPhase::Metadata))?;
+
+    // Decode the metadata retrieved from the execution
+    let mut decoder = Cursor::new(&metadata);
+
+    // The tuple is (zkasa_ns, public_inputs)
+    let zkp_pub: Vec<(String, Vec<pallas::Base>)> = Decodable::decode(&mut decoder)
+        .map_err(|e| call_failed(Error::from(e), idx, call, CallPhase::Metadata))?;
+    let sig_pub: Vec<PublicKey> = Decodable::decode(&mut decoder)
+        .map_err(|e| call_failed(Error::from(e), idx, call, CallPhase::Metadata))?;
+    // TODO: Make sure we've read all the bytes above.
+    debug!(target: "validator::verification::verify_transaction", "Successfully executed \"metadata\" call");
+
+    // Here we'll look up verifying keys and insert them into the per-contract map.
+    debug!(target: "validator::verification::verify_transaction", "Performing VerifyingKey lookups from the sled db");
+    for (zkas_ns, _) in &zkp_pub {
+        let inner_vk_map = verifying_keys.get_mut(&call.contract_id.to_bytes()).unwrap();
+
+        // Deployed zkas namespaces are immutable, so a cached key can't go stale
+        // even if the contract gets upgraded.
+        if inner_vk_map.contains_key(zkas_ns.as_str()) {
+            continue
+        }
+
+        let (_, vk) = overlay
+            .lock()
+            .unwrap()
+            .contracts
+            .get_zkas(&call.contract_id, zkas_ns)
+            .map_err(|e| call_failed(e, idx, call, CallPhase::ZkProof))?;
+
+        inner_vk_map.insert(zkas_ns.to_string(), vk);
+    }
+
+    // After getting the metadata, we run the "exec" function with the same runtime
+    // and the same payload.
+    debug!(target: "validator::verification::verify_transaction", "Executing \"exec\" call");
+    let state_update =
+        runtime.exec(payload).map_err(|e| call_failed(e, idx, call, CallPhase::Exec))?;
+    debug!(target: "validator::verification::verify_transaction", "Successfully executed \"exec\" call");
+
+    // If that was successful, we apply the state update in the ephemeral overlay.
+    debug!(target: "validator::verification::verify_transaction", "Executing \"apply\" call");
+    runtime.apply(&state_update).map_err(|e| call_failed(e, idx, call, CallPhase::Apply))?;
+    debug!(target: "validator::verification::verify_transaction", "Successfully executed \"apply\" call");
+
+    Ok((zkp_pub, sig_pub))
+}
+
+/// Deploy the non-native contract carried by a `Deployooor::DeployV1` call:
+/// verify its wasm bincode exports everything a contract needs, then run its
+/// deploy function, which stores the bincode on-chain. Redeploying an existing
+/// contract is an upgrade. Since the contract ID is derived from the deploy
+/// key, whose signature the Deployooor contract already required, only the
+/// key's holder can upgrade it.
+/// Returns the gas used by the deploy function, along with the size of the
+/// stored bincode.
+fn deploy_contract(
+    overlay: &BlockchainOverlayPtr,
+    time_keeper: &TimeKeeper,
+    call: &ContractCall,
+    access: Option<&mut StateAccess>,
+) -> Result<(u64, usize)> {
+    let params: DeployParamsV1 = deserialize(&call.data[1..])?;
+    let contract_id = ContractId::derive_public(params.public_key);
+    debug!(target: "validator::verification::verify_transaction", "Deploying contract {}", contract_id);
+
+    // Findings are only advisory here, since whether a block is valid can't
+    // depend on node settings. Nodes refusing such deployments do so in
+    // their mempool admission policy.
+    if let Ok(findings) = analyze_contract(&params.wasm_bincode) {
+        for finding in findings {
+            warn!(target: "validator::verification::verify_transaction", "Contract {}: {}", contract_id, finding);
+        }
+    }
+
+    let version = match overlay.lock().unwrap().contracts.get_deployment(&contract_id)? {
+        Some(record) => {
+            let Some(version) = record.version.checked_add(1) else {
+                let e = format!("Contract {} can't be upgraded anymore", contract_id);
+                return Err(TxVerifyFailed::InvalidDeployment(e).into())
+            };
+
+            debug!(target: "validator::verification::verify_transaction", "Upgrading contract {} to version {}", contract_id, version);
+            version
+        }
+        None => 1,
+    };
+
+    let mut runtime = match Runtime::new(
+        &params.wasm_bincode,
+        overlay.clone(),
+        contract_id,
+        time_keeper.clone(),
+    ) {
+        Ok(v) => v,
+        Err(e) => {
+            error!(target: "validator::verification::verify_transaction", "Failed compiling wasm bincode of contract {}: {}", contract_id, e);
+            return Err(TxVerifyFailed::InvalidDeployment(e.to_string()).into())
+        }
+    };
+
+    if let Err(e) = runtime.sanity_check() {
+        error!(target: "validator::verification::verify_transaction", "Contract {} is missing entrypoints: {}", contract_id, e);
+        return Err(TxVerifyFailed::InvalidDeployment(e.to_string()).into())
+    }
+
+    let res = runtime.deploy(&params.ix);
+    if let Some(access) = access {
+        access.extend(runtime.take_state_access());
+    }
+    if let Err(e) = res {
+        error!(target: "validator::verification::verify_transaction", "Deploying contract {} failed: {}", contract_id, e);
+        return Err(TxVerifyFailed::InvalidDeployment(e.to_string()).into())
+    }
+
+    let record =
+        DeploymentRecord { version, code_hash: *blake3::hash(&params.wasm_bincode).as_bytes() };
+    overlay.lock().unwrap().contracts.insert_deployment(&contract_id, &record)?;
+
+    Ok((runtime.gas_used(), params.wasm_bincode.len()))
+}
+
+/// Result of optimistically executing a single [`Transaction`]
+struct ParallelOutcome {
+    /// Index of the chunk the transaction was executed in
+    chunk: usize,
+    /// Verification result
+    result: Result<()>,
+    /// Contract state touched by the transaction's calls
+    access: StateAccess,
+    /// Signatures and ZK proofs pending verification
+    accumulator: ZkpAccumulator,
+}
+
+/// Optimistically verify the given transactions in parallel, recording the
+/// state each of them touched. The set is split in one contiguous chunk per
+/// available thread, and each worker executes its chunk in order against its
+/// own clone of the overlay, so there's at most one clone per thread alive at
+/// once. The workers run off the async executor, and the verifying keys they
+/// looked up are added to the given map.
+async fn verify_transactions_parallel(
+    overlay: &BlockchainOverlayPtr,
+    time_keeper: &TimeKeeper,
+    txs: &[Transaction],
+    vks: &mut HashMap<[u8; 32], HashMap<String, VerifyingKey>>,
+    verify_fees: bool,
+) -> Result<Vec<ParallelOutcome>> {
+    if txs.is_empty() {
+        return Ok(vec![])
+    }
+
+    let n_threads = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    let chunk_size = txs.len().div_ceil(n_threads);
+
+    let overlay = overlay.clone();
+    let time_keeper = time_keeper.clone();
+    let txs = txs.to_vec();
+    let base_vks = vks.clone();
+    let chunks = smol::unblock(move || {
+        std::thread::scope(|s| {
+            let handles: Vec<_> = txs
+                .chunks(chunk_size)
+                .enumerate()
+                .map(|(chunk, txs)| {
+                    let (overlay, time_keeper) = (&overlay, &time_keeper);
+                    let mut vks = base_vks.clone();
+                    s.spawn(move || -> Result<_> {
+                        let fork = overlay.lock().unwrap().full_clone()?;
+                        let mut outcomes = Vec::with_capacity(txs.len());
+                        for tx in txs {
+                            let mut accumulator = ZkpAccumulator::new();
+                            let mut access = StateAccess::default();
+                            let mut collectors = VerifyCollectors {
+                                accumulator: Some(&mut accumulator),
+                                access: Some(&mut access),
+                                ..Default::default()
+                            };
+                            fork.lock().unwrap().checkpoint();
+                            let result = smol::block_on(verify_transaction_inner(
+                                &fork,
+                                time_keeper,
+                                tx,
+                                &mut vks,
+                                verify_fees,
+                                &mut collectors,
+                            ));
+                            if result.is_err() {
+                                fork.lock().unwrap().revert_to_checkpoint()?;
+                            }
+                            outcomes.push(ParallelOutcome { chunk, result, access, accumulator });
+                        }
+                        Ok((outcomes, vks))
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|h| h.join().unwrap_or_else(|_| Err(worker_panicked())))
+                .collect::<Result<Vec<_>>>()
+        })
+    })
+    .await?;
+
+    let mut outcomes = vec![];
+    for (chunk_outcomes, chunk_vks) in chunks {
+        for (contract_id, contract_vks) in chunk_vks {
+            vks.entry(contract_id).or_default().extend(contract_vks);
+        }
+        outcomes.extend(chunk_outcomes);
+    }
+
+    Ok(outcomes)
+}
+
+/// Verify the signatures and ZK proofs gathered in the given accumulator,
+/// splitting them over all available threads, off the async executor. Each
+/// thread holds a proof verification permit while it works, so the configured
+/// limit still applies. The verifying keys are handed back once done.
+async fn verify_accumulator_parallel(
+    accumulator: ZkpAccumulator,
+    vks: HashMap<[u8; 32], HashMap<String, VerifyingKey>>,
+) -> (Result<()>, HashMap<[u8; 32], HashMap<String, VerifyingKey>>) {
+    smol::unblock(move || {
+        let n_threads = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+
+        let result = std::thread::scope(|s| {
+            let vks = &vks;
+            let handles: Vec<_> = accumulator
+                .split(n_threads)
+                .into_iter()
+                .map(|part| {
+                    s.spawn(move || {
+                        let _permit = smol::block_on(acquire_proof_permit());
+                        part.verify(vks)
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .try_for_each(|h| h.join().unwrap_or_else(|_| Err(worker_panicked())))
+        });
+
+        (result, vks)
+    })
+    .await
+}
+
+/// Error returned in place of a verification worker thread's panic
+fn worker_panicked() -> Error {
+    Error::Custom(String::from("Transaction verification worker panicked"))
+}
+
+/// Returns the number of leading transactions whose declared access lists
+/// don't conflict with one another. Transactions without a declaration are
+/// executed optimistically and their conflicts are detected afterwards.
+fn declared_independent(txs: &[Transaction]) -> usize {
+    let mut declared: Vec<&AccessList> = vec![];
+    for (idx, tx) in txs.iter().enumerate() {
+        let Some(access_list) = &tx.access_list else { continue };
+        if declared.iter().any(|d| d.conflicts_with(access_list)) {
+            return idx
+        }
+        declared.push(access_list);
+    }
+
+    txs.len()
+}
+
+/// Validate a set of [`Transaction`] and apply them if all are valid.
 /// In case any of the transactions fail, they will be returned to the caller.
-/// The function takes a boolean called `write` which tells it to actually write
-/// the state transitions to the database.
+///
+/// Transactions are first executed optimistically in parallel, in one chunk
+/// per thread, each chunk in order against its own copy of the overlay. Their
+/// writes are then committed in order for as long as none of them touched
+/// state written by a transaction of an earlier chunk.
+/// From the first conflicting (or structural, e.g. deploying) transaction
+/// onwards, the set is executed sequentially on the overlay itself. When
+/// transactions declare access lists, the ones known to conflict are not
+/// executed optimistically at all.
+///
+/// Signatures and ZK proofs are accumulated across the whole set and checked
+/// once at the end, split over all available threads. If that final check
+/// fails, the overlay is rolled back and the set is verified again transaction
+/// by transaction, to single out the offending txs.
+///
+/// If `verify_fees` is set, each transaction's paid fee must also cover the
+/// cost of verifying it.
 pub async fn verify_transactions(
     overlay: &BlockchainOverlayPtr,
     time_keeper: &TimeKeeper,
     txs: &[Transaction],
+    verify_fees: bool,
 ) -> Result<Vec<Transaction>> {
     debug!(target: "validator::verification::verify_transactions", "Verifying {} transactions", txs.len());
 
@@ -315,10 +836,65 @@ pub async fn verify_transactions(
         }
     }
 
-    // Iterate over transactions and attempt to verify them
-    for tx in txs {
+    // Keep the overlay state so we can roll back if the batched check fails
+    let backup = overlay.lock().unwrap().overlay.lock().unwrap().clone();
+    let mut accumulator = ZkpAccumulator::new();
+
+    // Commit the parallel execution results in order, until the first conflict.
+    // Transactions were executed in order within their chunk, so they only
+    // conflict with the ones committed from earlier chunks.
+    let n_parallel = declared_independent(txs);
+    let outcomes = verify_transactions_parallel(
+        overlay,
+        time_keeper,
+        &txs[..n_parallel],
+        &mut vks,
+        verify_fees,
+    )
+    .await?;
+    let mut committed = StateAccess::default();
+    let mut committed_chunk = StateAccess::default();
+    let mut chunk = 0;
+    let mut sequential_from = n_parallel;
+    for (idx, (tx, outcome)) in txs.iter().zip(outcomes).enumerate() {
+        if outcome.chunk != chunk {
+            committed.extend(std::mem::take(&mut committed_chunk));
+            chunk = outcome.chunk;
+        }
+
+        if outcome.access.structural || outcome.access.depends_on(&committed) {
+            debug!(target: "validator::verification::verify_transactions", "Transaction {} conflicts with the ones before it, continuing sequentially", tx.hash());
+            sequential_from = idx;
+            break
+        }
+
+        if let Err(e) = outcome.result {
+            warn!(target: "validator::verification::verify_transactions", "Transaction verification failed: {}", e);
+            erroneous_txs.push(tx.clone());
+            continue
+        }
+
+        outcome.access.replay(overlay)?;
+        accumulator.merge(outcome.accumulator);
+        committed_chunk.extend(outcome.access);
+    }
+    debug!(target: "validator::verification::verify_transactions", "Executed {} transactions in parallel", sequential_from);
+
+    // Iterate over the remaining transactions and attempt to verify them
+    for tx in &txs[sequential_from..] {
         overlay.lock().unwrap().checkpoint();
-        if let Err(e) = verify_transaction(overlay, time_keeper, tx, &mut vks).await {
+        let mut collectors =
+            VerifyCollectors { accumulator: Some(&mut accumulator), ..Default::default() };
+        if let Err(e) = verify_transaction_inner(
+            overlay,
+            time_keeper,
+            tx,
+            &mut vks,
+            verify_fees,
+            &mut collectors,
+        )
+        .await
+        {
             warn!(target: "validator::verification::verify_transactions", "Transaction verification failed: {}", e);
             erroneous_txs.push(tx.clone());
             // TODO: verify this works as expected
@@ -326,5 +902,25 @@ pub async fn verify_transactions(
         }
     }
 
+    let n_proofs = accumulator.len();
+    let (result, mut vks) = verify_accumulator_parallel(accumulator, vks).await;
+    if result.is_ok() {
+        debug!(target: "validator::verification::verify_transactions", "Batch verified {} ZK proofs", n_proofs);
+        return Ok(erroneous_txs)
+    }
+
+    warn!(target: "validator::verification::verify_transactions", "Batched verification failed, retrying transaction by transaction");
+    *overlay.lock().unwrap().overlay.lock().unwrap() = backup;
+    erroneous_txs.clear();
+
+    for tx in txs {
+        overlay.lock().unwrap().checkpoint();
+        if let Err(e) = verify_transaction(overlay, time_keeper, tx, &mut vks, verify_fees).await {
+            warn!(target: "validator::verification::verify_transactions", "Transaction verification failed: {}", e);
+            erroneous_txs.push(tx.clone());
+            overlay.lock().unwrap().revert_to_checkpoint()?;
+        }
+    }
+
     Ok(erroneous_txs)
 }
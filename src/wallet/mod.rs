@@ -18,4 +18,4 @@
 
 /// Main wallet primitives, extendable by traits.
 pub mod walletdb;
-pub use walletdb::{WalletDb, WalletPtr};
+pub use walletdb::{WalletDb, WalletMigration, WalletPtr};
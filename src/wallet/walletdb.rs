@@ -16,13 +16,19 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::{any::Any, path::PathBuf, sync::Arc};
+use std::{any::Any, path::PathBuf, sync::Arc, time::Instant};
 
 use log::{debug, info};
 use rusqlite::Connection;
 use smol::lock::Mutex;
 
-use crate::Result;
+use crate::{
+    util::{
+        slow_log::{SlowLog, SlowLogPtr},
+        time::Timestamp,
+    },
+    Error, Result,
+};
 
 pub type WalletPtr = Arc<WalletDb>;
 
@@ -38,8 +44,12 @@ pub enum QueryType {
     OptionBlob = 0x03,
     /// Text gets decoded into `String`
     Text = 0x04,
+    /// OptionText gets decoded into `Option<String>`
+    OptionText = 0x05,
+    /// Float gets decoded into `f64`
+    Float = 0x06,
     /// Last type, increment this when you add new types.
-    Last = 0x05,
+    Last = 0x07,
 }
 
 impl From<u8> for QueryType {
@@ -50,6 +60,8 @@ impl From<u8> for QueryType {
             0x02 => Self::OptionInteger,
             0x03 => Self::OptionBlob,
             0x04 => Self::Text,
+            0x05 => Self::OptionText,
+            0x06 => Self::Float,
             _ => unimplemented!(),
         }
     }
@@ -58,15 +70,29 @@ impl From<u8> for QueryType {
 #[derive(Debug)]
 pub enum SqlType {
     Integer(i64),
+    Real(f64),
     Text(String),
     Blob(Vec<u8>),
     Null,
 }
 
+impl From<SqlType> for rusqlite::types::ToSqlOutput<'_> {
+    fn from(value: SqlType) -> Self {
+        match value {
+            SqlType::Integer(i) => Self::from(i),
+            SqlType::Real(r) => Self::from(r),
+            SqlType::Text(t) => Self::from(t),
+            SqlType::Blob(b) => Self::from(b),
+            SqlType::Null => Self::from(rusqlite::types::Null),
+        }
+    }
+}
+
 impl SqlType {
     pub fn inner<T: 'static>(&self) -> Option<&T> {
         match self {
             SqlType::Integer(v) => (v as &dyn Any).downcast_ref::<T>(),
+            SqlType::Real(v) => (v as &dyn Any).downcast_ref::<T>(),
             SqlType::Text(v) => (v as &dyn Any).downcast_ref::<T>(),
             SqlType::Blob(v) => (v as &dyn Any).downcast_ref::<T>(),
             SqlType::Null => None,
@@ -74,10 +100,28 @@ impl SqlType {
     }
 }
 
+/// A versioned wallet schema upgrade. Migrations are applied in order,
+/// each one exactly once, starting from version 1.
+pub struct WalletMigration {
+    /// Schema version the wallet is at after applying this migration
+    pub version: u32,
+    /// Short human-readable description of the migration
+    pub description: &'static str,
+    /// SQL statements performing the migration
+    pub sql: &'static str,
+}
+
+/// Table keeping track of the migrations applied to the wallet
+const SCHEMA_VERSION_TABLE: &str = "schema_version";
+
 /// Structure representing base wallet operations.
 /// Additional operations can be implemented by trait extensions.
 pub struct WalletDb {
     pub conn: Mutex<Connection>,
+    /// Path of the wallet file, `None` if it lives in memory
+    path: Option<PathBuf>,
+    /// Log of SQL statements exceeding the configured duration threshold
+    pub slow_log: SlowLogPtr,
 }
 
 impl WalletDb {
@@ -94,7 +138,7 @@ impl WalletDb {
         conn.pragma_update(None, "foreign_keys", "ON")?;
 
         info!(target: "wallet::walletdb", "[WalletDb] Opened Sqlite connection at \"{:?}\"", path);
-        Ok(Arc::new(Self { conn: Mutex::new(conn) }))
+        Ok(Arc::new(Self { conn: Mutex::new(conn), path, slow_log: SlowLog::new("wallet") }))
     }
 
     /// This function executes a given SQL query, but isn't able to return anything.
@@ -102,7 +146,145 @@ impl WalletDb {
     pub async fn exec_sql(&self, query: &str) -> Result<()> {
         info!(target: "wallet::walletdb", "[WalletDb] Executing SQL query");
         debug!(target: "wallet::walletdb", "[WalletDb] Query:\n{}", query);
-        let _ = self.conn.lock().await.execute(query, ())?;
+        let conn = self.conn.lock().await;
+        let _ = self.slow_log.time(query, || conn.execute(query, ()))?;
+        Ok(())
+    }
+
+    /// Drop every table in the wallet, leaving it empty so it can be
+    /// rebuilt from scratch, e.g. when recovering it from a seed.
+    pub async fn reset(&self) -> Result<()> {
+        info!(target: "wallet::walletdb", "[WalletDb] Dropping all wallet tables");
+        let conn = self.conn.lock().await;
+
+        let tables: Vec<String> = {
+            let mut stmt = conn.prepare(
+                "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'",
+            )?;
+            let rows = stmt.query_map((), |row| row.get(0))?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+
+        // Tables referencing each other can't be dropped in an arbitrary
+        // order with foreign key enforcement on.
+        conn.pragma_update(None, "foreign_keys", "OFF")?;
+        for table in tables {
+            debug!(target: "wallet::walletdb", "[WalletDb] Dropping table {}", table);
+            conn.execute(&format!("DROP TABLE IF EXISTS \"{}\";", table), ())?;
+        }
+        conn.pragma_update(None, "foreign_keys", "ON")?;
+
+        Ok(())
+    }
+
+    /// Retrieve the wallet schema version, i.e. the version of the last
+    /// applied migration. A wallet without any migrations is at version 0.
+    pub async fn schema_version(&self) -> Result<u32> {
+        let conn = self.conn.lock().await;
+        Self::read_schema_version(&conn)
+    }
+
+    fn read_schema_version(conn: &Connection) -> Result<u32> {
+        let query = format!(
+            "CREATE TABLE IF NOT EXISTS {} (
+                version INTEGER PRIMARY KEY NOT NULL,
+                description TEXT NOT NULL,
+                applied_at INTEGER NOT NULL
+            );",
+            SCHEMA_VERSION_TABLE
+        );
+        conn.execute(&query, ())?;
+
+        let query = format!("SELECT COALESCE(MAX(version), 0) FROM {}", SCHEMA_VERSION_TABLE);
+        Ok(conn.query_row(&query, (), |row| row.get(0))?)
+    }
+
+    /// Apply any pending migrations, in order, and return the resulting
+    /// schema version. Each migration runs in its own transaction, so a
+    /// failing one leaves the wallet at the previous version. Wallets stored
+    /// on disk are backed up next to the wallet file before migrating.
+    pub async fn migrate(&self, migrations: &[WalletMigration]) -> Result<u32> {
+        for (i, migration) in migrations.iter().enumerate() {
+            if migration.version != i as u32 + 1 {
+                return Err(Error::WalletMigrationFailed(format!(
+                    "Migration {} is out of order",
+                    migration.version
+                )))
+            }
+        }
+
+        let mut conn = self.conn.lock().await;
+        let mut version = Self::read_schema_version(&conn)?;
+        if version as usize > migrations.len() {
+            return Err(Error::WalletMigrationFailed(format!(
+                "Wallet schema version {} is newer than the supported {}",
+                version,
+                migrations.len()
+            )))
+        }
+
+        let pending = &migrations[version as usize..];
+        if pending.is_empty() {
+            return Ok(version)
+        }
+
+        if let Some(path) = &self.path {
+            let mut backup = path.clone().into_os_string();
+            backup.push(format!(".v{}.bak", version));
+            info!(target: "wallet::walletdb", "[WalletDb] Backing up wallet to {:?}", backup);
+            std::fs::copy(path, &backup)?;
+        }
+
+        for migration in pending {
+            info!(
+                target: "wallet::walletdb",
+                "[WalletDb] Applying migration {}: {}", migration.version, migration.description,
+            );
+            let tx = conn.transaction()?;
+            tx.execute_batch(migration.sql)?;
+            tx.execute(
+                &format!(
+                    "INSERT INTO {} (version, description, applied_at) VALUES (?1, ?2, ?3);",
+                    SCHEMA_VERSION_TABLE
+                ),
+                rusqlite::params![
+                    migration.version,
+                    migration.description,
+                    Timestamp::current_time().0
+                ],
+            )?;
+            tx.commit()?;
+            version = migration.version;
+        }
+
+        Ok(version)
+    }
+
+    /// Insert a row into the given table. Values are bound as parameters
+    /// of a prepared statement, so only identifiers end up in the query.
+    pub async fn insert(&self, table: &str, values: Vec<(&str, SqlType)>) -> Result<()> {
+        check_identifier(table)?;
+        for (col, _) in &values {
+            check_identifier(col)?;
+        }
+
+        let cols: Vec<&str> = values.iter().map(|(k, _)| *k).collect();
+        let placeholders: Vec<String> = (1..=values.len()).map(|i| format!("?{}", i)).collect();
+        let query = format!(
+            "INSERT INTO {} ({}) VALUES ({});",
+            table,
+            cols.join(", "),
+            placeholders.join(", ")
+        );
+
+        let params: Vec<rusqlite::types::ToSqlOutput> =
+            values.into_iter().map(|(_, v)| v.into()).collect();
+        let params_as_slice: Vec<&dyn rusqlite::ToSql> =
+            params.iter().map(|x| x as &dyn rusqlite::ToSql).collect();
+
+        let wallet_conn = self.conn.lock().await;
+        self.slow_log.time(&query, || wallet_conn.execute(&query, params_as_slice.as_slice()))?;
+
         Ok(())
     }
 
@@ -112,6 +294,32 @@ impl WalletDb {
         col_names: Vec<&str>,
         where_queries: Option<Vec<(&str, SqlType)>>,
     ) -> Result<Vec<SqlType>> {
+        let mut rows = self.query(table, col_names, where_queries, Some(1)).await?;
+        Ok(rows.pop().unwrap_or_default())
+    }
+
+    /// Query all the rows of the given table matching `where_queries`.
+    pub async fn query_multiple(
+        &self,
+        table: &str,
+        col_names: Vec<&str>,
+        where_queries: Option<Vec<(&str, SqlType)>>,
+    ) -> Result<Vec<Vec<SqlType>>> {
+        self.query(table, col_names, where_queries, None).await
+    }
+
+    async fn query(
+        &self,
+        table: &str,
+        col_names: Vec<&str>,
+        where_queries: Option<Vec<(&str, SqlType)>>,
+        limit: Option<u32>,
+    ) -> Result<Vec<Vec<SqlType>>> {
+        check_identifier(table)?;
+        for col in col_names.iter().chain(where_queries.iter().flatten().map(|(k, _)| k)) {
+            check_identifier(col)?;
+        }
+
         let mut query = format!("SELECT {} FROM {}", col_names.join(", "), table);
 
         if let Some(wq) = where_queries.as_ref() {
@@ -119,45 +327,57 @@ impl WalletDb {
             query.push_str(&format!(" WHERE {}", where_str.join(" AND ")));
         }
 
-        let params: Vec<rusqlite::types::ToSqlOutput> = where_queries.map_or(Vec::new(), |wq| {
-            wq.into_iter()
-                .map(|(_, v)| match v {
-                    SqlType::Integer(i) => rusqlite::types::ToSqlOutput::from(i),
-                    SqlType::Text(t) => rusqlite::types::ToSqlOutput::from(t),
-                    SqlType::Blob(b) => rusqlite::types::ToSqlOutput::from(b),
-                    SqlType::Null => rusqlite::types::ToSqlOutput::from(rusqlite::types::Null),
-                })
-                .collect::<Vec<_>>()
-        });
+        if let Some(limit) = limit {
+            query.push_str(&format!(" LIMIT {}", limit));
+        }
+
+        let params: Vec<rusqlite::types::ToSqlOutput> = where_queries
+            .map_or(Vec::new(), |wq| wq.into_iter().map(|(_, v)| v.into()).collect::<Vec<_>>());
 
         let wallet_conn = self.conn.lock().await;
+        let start = Instant::now();
         let mut stmt = wallet_conn.prepare(&query)?;
         let params_as_slice: Vec<&dyn rusqlite::ToSql> =
             params.iter().map(|x| x as &dyn rusqlite::ToSql).collect();
         let mut rows = stmt.query(params_as_slice.as_slice())?;
 
-        let row = match rows.next()? {
-            Some(row_result) => row_result,
-            None => return Ok(vec![]),
-        };
-
         let mut result = vec![];
-        for (idx, _) in col_names.iter().enumerate() {
-            let value: SqlType = match row.get_ref(idx)?.data_type() {
-                rusqlite::types::Type::Integer => SqlType::Integer(row.get(idx)?),
-                rusqlite::types::Type::Text => SqlType::Text(row.get(idx)?),
-                rusqlite::types::Type::Blob => SqlType::Blob(row.get(idx)?),
-                rusqlite::types::Type::Null => SqlType::Null,
-                _ => unimplemented!(),
-            };
-
-            result.push(value);
+        while let Some(row) = rows.next()? {
+            let mut values = vec![];
+            for (idx, _) in col_names.iter().enumerate() {
+                let value: SqlType = match row.get_ref(idx)?.data_type() {
+                    rusqlite::types::Type::Integer => SqlType::Integer(row.get(idx)?),
+                    rusqlite::types::Type::Real => SqlType::Real(row.get(idx)?),
+                    rusqlite::types::Type::Text => SqlType::Text(row.get(idx)?),
+                    rusqlite::types::Type::Blob => SqlType::Blob(row.get(idx)?),
+                    rusqlite::types::Type::Null => SqlType::Null,
+                };
+
+                values.push(value);
+            }
+
+            result.push(values);
         }
+        self.slow_log.record(&query, start.elapsed());
 
         Ok(result)
     }
 }
 
+/// Check that `name` is a plain SQL identifier, so it can be safely
+/// interpolated into a query.
+fn check_identifier(name: &str) -> Result<()> {
+    let mut chars = name.chars();
+    let valid = matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_') &&
+        chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+    if !valid {
+        return Err(Error::WalletInvalidIdentifier(name.to_string()))
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -209,4 +429,139 @@ mod tests {
             assert!(ret[3].inner::<Vec<u8>>().unwrap() == &gae);
         });
     }
+
+    #[test]
+    fn test_insert_query_multiple() {
+        smol::block_on(async {
+            let wallet = WalletDb::new(None, None).unwrap();
+            wallet.exec_sql("CREATE TABLE mista ( why INTEGER, are TEXT );").await.unwrap();
+
+            for (why, are) in [(42, "are"), (69, "you"), (42, "gae")] {
+                let values =
+                    vec![("why", SqlType::Integer(why)), ("are", SqlType::Text(are.to_string()))];
+                wallet.insert("mista", values).await.unwrap();
+            }
+
+            let rows = wallet
+                .query_multiple("mista", vec!["are"], Some(vec![("why", SqlType::Integer(42))]))
+                .await
+                .unwrap();
+            assert_eq!(rows.len(), 2);
+            assert!(rows[0][0].inner::<String>().unwrap() == "are");
+            assert!(rows[1][0].inner::<String>().unwrap() == "gae");
+
+            // Values are bound as parameters, so they can't alter the query
+            let values = vec![("are", SqlType::Text("x'); DROP TABLE mista; --".to_string()))];
+            wallet.insert("mista", values).await.unwrap();
+            assert_eq!(wallet.query_multiple("mista", vec!["why"], None).await.unwrap().len(), 4);
+
+            // While identifiers are rejected unless they're plain names
+            assert!(wallet
+                .query_multiple("mista; DROP TABLE mista", vec!["why"], None)
+                .await
+                .is_err());
+            assert!(wallet.insert("mista", vec![("why)", SqlType::Null)]).await.is_err());
+        });
+    }
+
+    #[test]
+    fn test_text_and_real_columns() {
+        smol::block_on(async {
+            let wallet = WalletDb::new(None, None).unwrap();
+            wallet.exec_sql("CREATE TABLE mista ( label TEXT, ratio REAL );").await.unwrap();
+
+            let values = vec![
+                ("label", SqlType::Text("savings".to_string())),
+                ("ratio", SqlType::Real(0.5)),
+            ];
+            wallet.insert("mista", values).await.unwrap();
+            wallet.insert("mista", vec![("label", SqlType::Null)]).await.unwrap();
+
+            let rows = wallet.query_multiple("mista", vec!["label", "ratio"], None).await.unwrap();
+            assert!(rows[0][0].inner::<String>().unwrap() == "savings");
+            assert!(*rows[0][1].inner::<f64>().unwrap() == 0.5);
+            assert!(matches!(rows[1][0], SqlType::Null));
+            assert!(matches!(rows[1][1], SqlType::Null));
+        });
+    }
+
+    #[test]
+    fn test_slow_log() {
+        smol::block_on(async {
+            let wallet = WalletDb::new(None, None).unwrap();
+            wallet.exec_sql("CREATE TABLE mista ( why INTEGER );").await.unwrap();
+            assert_eq!(wallet.slow_log.metrics().slow, 0);
+
+            // With a zero threshold every statement is slow
+            wallet.slow_log.set_threshold(std::time::Duration::ZERO);
+            wallet.insert("mista", vec![("why", SqlType::Integer(42))]).await.unwrap();
+            wallet.query_multiple("mista", vec!["why"], None).await.unwrap();
+
+            let metrics = wallet.slow_log.metrics();
+            assert_eq!(metrics.total, 3);
+            assert_eq!(metrics.slow, 2);
+            let worst = wallet.slow_log.worst(10);
+            assert!(worst.iter().any(|op| op.label.starts_with("INSERT INTO mista")));
+            assert!(worst.iter().any(|op| op.label.starts_with("SELECT why FROM mista")));
+        });
+    }
+
+    #[test]
+    fn test_reset_wallet() {
+        smol::block_on(async {
+            let wallet = WalletDb::new(None, None).unwrap();
+            wallet.exec_sql("CREATE TABLE parent ( id INTEGER PRIMARY KEY );").await.unwrap();
+            wallet
+                .exec_sql("CREATE TABLE child ( parent_id INTEGER REFERENCES parent(id) );")
+                .await
+                .unwrap();
+            wallet.exec_sql("INSERT INTO parent ( id ) VALUES ( 1 );").await.unwrap();
+            wallet.exec_sql("INSERT INTO child ( parent_id ) VALUES ( 1 );").await.unwrap();
+
+            wallet.reset().await.unwrap();
+
+            let conn = wallet.conn.lock().await;
+            let mut stmt = conn.prepare("SELECT COUNT(*) FROM sqlite_master").unwrap();
+            let count: u64 = stmt.query_row((), |row| row.get(0)).unwrap();
+            assert_eq!(count, 0);
+        });
+    }
+
+    #[test]
+    fn test_migrations() {
+        smol::block_on(async {
+            let mut migrations = vec![WalletMigration {
+                version: 1,
+                description: "Create mista",
+                sql: "CREATE TABLE mista ( numba INTEGER );",
+            }];
+
+            let wallet = WalletDb::new(None, None).unwrap();
+            assert_eq!(wallet.schema_version().await.unwrap(), 0);
+            assert_eq!(wallet.migrate(&migrations).await.unwrap(), 1);
+            // Applied migrations are not applied again
+            assert_eq!(wallet.migrate(&migrations).await.unwrap(), 1);
+
+            // A failing migration is rolled back entirely
+            migrations.push(WalletMigration {
+                version: 2,
+                description: "Broken",
+                sql:
+                    "ALTER TABLE mista ADD COLUMN gae BLOB; ALTER TABLE nope ADD COLUMN x INTEGER;",
+            });
+            assert!(wallet.migrate(&migrations).await.is_err());
+            assert_eq!(wallet.schema_version().await.unwrap(), 1);
+
+            migrations[1].sql = "ALTER TABLE mista ADD COLUMN gae BLOB;";
+            assert_eq!(wallet.migrate(&migrations).await.unwrap(), 2);
+            wallet
+                .exec_sql("INSERT INTO mista ( numba, gae ) VALUES ( 42, x'00' );")
+                .await
+                .unwrap();
+
+            // Wallets newer than the known migrations are refused
+            migrations.pop();
+            assert!(wallet.migrate(&migrations).await.is_err());
+        });
+    }
 }
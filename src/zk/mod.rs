@@ -29,7 +29,11 @@ pub mod gadget;
 
 /// Proof creation API
 pub mod proof;
-pub use proof::{Proof, ProvingKey, VerifyingKey};
+pub use proof::{shared_params, BatchVerifier, Proof, ProvingKey, VerifyingKey};
+
+/// MockProver harness for unit-testing zkas circuits
+pub mod testing;
+pub use testing::{CircuitFailure, CircuitTester};
 
 /// Trace computation of intermediate values in circuit
 mod tracer;
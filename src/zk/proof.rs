@@ -15,30 +15,78 @@
  * You should have received a copy of the GNU Affero General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
-use std::{io, io::Cursor};
+use std::{
+    collections::HashMap,
+    io,
+    io::Cursor,
+    sync::{Mutex, OnceLock},
+};
 
 use darkfi_sdk::pasta::{pallas, vesta};
 use darkfi_serial::{async_trait, SerialDecodable, SerialEncodable};
 use halo2_proofs::{
+    dev::CircuitCost,
     helpers::SerdeFormat,
     plonk,
-    plonk::{Circuit, SingleVerifier},
+    plonk::{BatchVerifier as Halo2BatchVerifier, Circuit, SingleVerifier},
     poly::commitment::Params,
     transcript::{Blake2bRead, Blake2bWrite},
 };
 use rand::RngCore;
 
+/// Public parameters generated so far, keyed by their circuit size `k`
+static PARAMS_CACHE: OnceLock<Mutex<HashMap<u32, Params<vesta::Affine>>>> = OnceLock::new();
+
+/// Returns the public parameters for circuits of size `k`. Generating them is
+/// expensive and their value only depends on `k`, so they are generated once
+/// and shared by all proving and verifying keys built in this process.
+pub fn shared_params(k: u32) -> Params<vesta::Affine> {
+    let cache = PARAMS_CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+    if let Some(params) = cache.lock().unwrap().get(&k) {
+        return params.clone()
+    }
+
+    // Generate without holding the lock, so keys of other sizes aren't blocked
+    let params = Params::new(k);
+    cache.lock().unwrap().entry(k).or_insert(params).clone()
+}
+
 #[derive(Clone, Debug)]
 pub struct VerifyingKey {
     pub params: Params<vesta::Affine>,
     pub vk: plonk::VerifyingKey<vesta::Affine>,
+    /// Size in bytes of every valid proof for this circuit
+    proof_size: usize,
+    /// Size in bytes each additional circuit adds to a batched proof
+    batch_proof_size: usize,
+}
+
+/// Compute the exact size of a single-instance proof of the given circuit,
+/// along with the size each additional instance adds to a batched proof.
+fn expected_proof_sizes<C: Circuit<pallas::Base>>(k: u32, c: &C) -> (usize, usize) {
+    let cost = CircuitCost::<vesta::Point, C>::measure(k, c);
+    let single = usize::from(cost.proof_size(1));
+    let double = usize::from(cost.proof_size(2));
+    (single, double - single)
 }
 
 impl VerifyingKey {
     pub fn build(k: u32, c: &impl Circuit<pallas::Base>) -> Self {
-        let params = Params::new(k);
+        let params = shared_params(k);
         let vk = plonk::keygen_vk(&params, c).unwrap();
-        VerifyingKey { params, vk }
+        let (proof_size, batch_proof_size) = expected_proof_sizes(k, c);
+        VerifyingKey { params, vk, proof_size, batch_proof_size }
+    }
+
+    /// Returns the size in bytes every valid proof for this key must have.
+    pub fn proof_size(&self) -> usize {
+        self.proof_size
+    }
+
+    /// Returns the size in bytes a valid proof covering `n` circuits,
+    /// as created by [`Proof::create_batch`], must have.
+    pub fn batch_proof_size(&self, n: usize) -> usize {
+        self.proof_size + self.batch_proof_size * n.saturating_sub(1)
     }
 
     pub fn write<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
@@ -83,6 +131,7 @@ impl VerifyingKey {
 
         let mut params_c = Cursor::new(params_buf);
         let params: Params<vesta::Affine> = Params::read(&mut params_c)?;
+        let (proof_size, batch_proof_size) = expected_proof_sizes(params.k(), &circuit);
 
         let mut vk_c = Cursor::new(vk_buf);
         let vk: plonk::VerifyingKey<vesta::Affine> =
@@ -92,7 +141,7 @@ impl VerifyingKey {
                 circuit.params(),
             )?;
 
-        Ok(Self { params, vk })
+        Ok(Self { params, vk, proof_size, batch_proof_size })
     }
 }
 
@@ -104,7 +153,7 @@ pub struct ProvingKey {
 
 impl ProvingKey {
     pub fn build(k: u32, c: &impl Circuit<pallas::Base>) -> Self {
-        let params = Params::new(k);
+        let params = shared_params(k);
         let vk = plonk::keygen_vk(&params, c).unwrap();
         let pk = plonk::keygen_pk(&params, vk, c).unwrap();
         ProvingKey { params, pk }
@@ -202,13 +251,169 @@ impl Proof {
         vk: &VerifyingKey,
         instances: &[pallas::Base],
     ) -> std::result::Result<(), plonk::Error> {
+        // Reject malformed encodings before they reach the verifier, so
+        // the same proof can't be carried by different byte strings.
+        if !self.is_well_formed(vk) {
+            return Err(plonk::Error::Transcript(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Proof length does not match verifying key",
+            )))
+        }
+
         let strategy = SingleVerifier::new(&vk.params);
-        let mut transcript = Blake2bRead::init(&self.0[..]);
+        let mut reader = &self.0[..];
+        let mut transcript = Blake2bRead::init(&mut reader);
+
+        plonk::verify_proof(&vk.params, &vk.vk, strategy, &[&[instances]], &mut transcript)?;
+        drop(transcript);
+
+        // The verifier must have consumed the proof entirely
+        if !reader.is_empty() {
+            return Err(plonk::Error::Transcript(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Trailing data after proof",
+            )))
+        }
+
+        Ok(())
+    }
+
+    /// Create a single proof for several circuits sharing the same proving
+    /// key, each with its own public inputs. The commitment and evaluation
+    /// phases are done once for the whole batch, making this considerably
+    /// cheaper than creating a proof per circuit. The resulting proof is
+    /// checked with [`Proof::verify_batch`], given the public inputs in the
+    /// same order as the circuits.
+    pub fn create_batch(
+        pk: &ProvingKey,
+        circuits: &[impl Circuit<pallas::Base>],
+        instances: &[Vec<pallas::Base>],
+        mut rng: impl RngCore,
+    ) -> std::result::Result<Self, plonk::Error> {
+        if circuits.is_empty() || circuits.len() != instances.len() {
+            return Err(plonk::Error::InvalidInstances)
+        }
+
+        let instances: Vec<[&[pallas::Base]; 1]> = instances.iter().map(|i| [&i[..]]).collect();
+        let instances: Vec<&[&[pallas::Base]]> = instances.iter().map(|i| &i[..]).collect();
 
-        plonk::verify_proof(&vk.params, &vk.vk, strategy, &[&[instances]], &mut transcript)
+        let mut transcript = Blake2bWrite::<_, vesta::Affine, _>::init(vec![]);
+        plonk::create_proof(&pk.params, &pk.pk, circuits, &instances, &mut rng, &mut transcript)?;
+
+        Ok(Proof(transcript.finalize()))
+    }
+
+    /// Verify a proof created with [`Proof::create_batch`], given the public
+    /// inputs of each of its circuits.
+    pub fn verify_batch(
+        &self,
+        vk: &VerifyingKey,
+        instances: &[Vec<pallas::Base>],
+    ) -> std::result::Result<(), plonk::Error> {
+        if instances.is_empty() {
+            return Err(plonk::Error::InvalidInstances)
+        }
+
+        if !self.is_well_formed_batch(vk, instances.len()) {
+            return Err(plonk::Error::Transcript(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Proof length does not match verifying key",
+            )))
+        }
+
+        let instances: Vec<[&[pallas::Base]; 1]> = instances.iter().map(|i| [&i[..]]).collect();
+        let instances: Vec<&[&[pallas::Base]]> = instances.iter().map(|i| &i[..]).collect();
+
+        let strategy = SingleVerifier::new(&vk.params);
+        let mut reader = &self.0[..];
+        let mut transcript = Blake2bRead::init(&mut reader);
+
+        plonk::verify_proof(&vk.params, &vk.vk, strategy, &instances, &mut transcript)?;
+        drop(transcript);
+
+        // The verifier must have consumed the proof entirely
+        if !reader.is_empty() {
+            return Err(plonk::Error::Transcript(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Trailing data after proof",
+            )))
+        }
+
+        Ok(())
+    }
+
+    /// Cheap structural check that the proof has the exact size
+    /// proofs created with the given key's circuit have.
+    pub fn is_well_formed(&self, vk: &VerifyingKey) -> bool {
+        self.0.len() == vk.proof_size()
+    }
+
+    /// Same as [`Proof::is_well_formed`], for a proof created with
+    /// [`Proof::create_batch`] over `n` circuits.
+    pub fn is_well_formed_batch(&self, vk: &VerifyingKey, n: usize) -> bool {
+        n > 0 && self.0.len() == vk.batch_proof_size(n)
     }
 
     pub fn new(bytes: Vec<u8>) -> Self {
         Proof(bytes)
     }
 }
+
+/// Accumulates proofs created under the same [`VerifyingKey`] so they
+/// can be checked together. The final check is a single multiscalar
+/// multiplication, which is considerably cheaper than verifying each
+/// proof on its own. A failed check doesn't tell which proof was bad,
+/// so callers should fall back to [`Proof::verify`] to find out.
+pub struct BatchVerifier {
+    inner: Halo2BatchVerifier<vesta::Affine>,
+    /// Size of each added proof, along with the number of circuits it covers
+    proof_sizes: Vec<(usize, usize)>,
+}
+
+impl BatchVerifier {
+    pub fn new() -> Self {
+        Self { inner: Halo2BatchVerifier::new(), proof_sizes: vec![] }
+    }
+
+    /// Add a proof along with its public inputs to the batch.
+    pub fn add(&mut self, proof: &Proof, instances: &[pallas::Base]) {
+        self.inner.add_proof(vec![vec![instances.to_vec()]], proof.0.clone());
+        self.proof_sizes.push((proof.0.len(), 1));
+    }
+
+    /// Add a proof created with [`Proof::create_batch`] to the batch,
+    /// along with the public inputs of each of its circuits.
+    pub fn add_batch(&mut self, proof: &Proof, instances: &[Vec<pallas::Base>]) {
+        self.proof_sizes.push((proof.0.len(), instances.len()));
+        let instances = instances.iter().map(|i| vec![i.clone()]).collect();
+        self.inner.add_proof(instances, proof.0.clone());
+    }
+
+    /// Returns the number of proofs in the batch.
+    pub fn len(&self) -> usize {
+        self.proof_sizes.len()
+    }
+
+    /// Returns `true` if no proofs have been added.
+    pub fn is_empty(&self) -> bool {
+        self.proof_sizes.is_empty()
+    }
+
+    /// Verify all the accumulated proofs against `vk`.
+    /// Returns `true` only if every proof in the batch is valid.
+    pub fn finalize(self, vk: &VerifyingKey) -> bool {
+        // The batch verifier doesn't notice trailing data, so proof
+        // sizes are checked upfront.
+        if self.proof_sizes.iter().any(|(size, n)| *n == 0 || *size != vk.batch_proof_size(*n)) {
+            return false
+        }
+
+        self.inner.finalize(&vk.params, &vk.vk)
+    }
+}
+
+impl Default for BatchVerifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,215 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Harness for unit-testing zkas circuits with halo2's [`MockProver`],
+//! which checks every constraint without needing proving keys, and
+//! reports the ones that failed along with the opcode they belong to.
+
+use std::fmt;
+
+use darkfi_sdk::pasta::pallas;
+use halo2_proofs::dev::{metadata, FailureLocation, MockProver, VerifyFailure};
+
+use super::{vm::ZkCircuit, Witness};
+use crate::{
+    zkas::{Analyzer, Compiler, Lexer, Opcode, Parser, ZkBinary},
+    Result,
+};
+
+/// A constraint the circuit failed to satisfy
+#[derive(Debug)]
+pub struct CircuitFailure {
+    /// Index of the opcode that assigned the failing cells,
+    /// or `None` if they were assigned outside of the opcodes
+    pub opcode: Option<usize>,
+    /// The opcode itself
+    pub op: Option<Opcode>,
+    /// Line of the zkas source the opcode was compiled from, if known
+    pub line: Option<usize>,
+    /// The failure as reported by the [`MockProver`]
+    pub failure: VerifyFailure,
+}
+
+impl fmt::Display for CircuitFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.line, self.opcode, self.op) {
+            (Some(line), Some(idx), Some(op)) => {
+                write!(f, "line {} (opcode {} `{}`): ", line, idx, op.name())?
+            }
+            (None, Some(idx), Some(op)) => write!(f, "opcode {} `{}`: ", idx, op.name())?,
+            _ => write!(f, "outside opcodes: ")?,
+        }
+        write!(f, "{}", self.failure)
+    }
+}
+
+/// Runs zkas circuits through the [`MockProver`], mapping the constraints
+/// they fail back to the opcodes, and zkas source lines, they come from.
+pub struct CircuitTester {
+    zkbin: ZkBinary,
+    /// Source line of each opcode, if the circuit was compiled from source
+    lines: Option<Vec<usize>>,
+}
+
+impl CircuitTester {
+    /// Create a tester for an already compiled circuit. Failures can
+    /// only be mapped back to opcodes, since the bincode has no lines.
+    pub fn new(zkbin: ZkBinary) -> Self {
+        Self { zkbin, lines: None }
+    }
+
+    /// Compile the given zkas source, keeping track of the line each
+    /// opcode was compiled from.
+    pub fn from_source(filename: &str, source: &str) -> Result<Self> {
+        let source = source.replace('\t', "    ").replace("\r\n", "\n");
+
+        let lexer = Lexer::new(filename, source.chars());
+        let tokens = lexer.lex()?;
+
+        let parser = Parser::new(filename, source.chars(), tokens);
+        let (namespace, k, constants, witnesses, statements) = parser.parse()?;
+
+        let mut analyzer =
+            Analyzer::new(filename, source.chars(), constants, witnesses, statements);
+        analyzer.analyze_types()?;
+
+        // Every statement is compiled into a single opcode
+        let lines = analyzer.statements.iter().map(|s| s.line).collect();
+
+        let compiler = Compiler::new(
+            filename,
+            source.chars(),
+            namespace,
+            k,
+            analyzer.constants,
+            analyzer.witnesses,
+            analyzer.statements,
+            analyzer.literals,
+            false,
+        );
+        let zkbin = ZkBinary::decode(&compiler.compile()?)?;
+
+        Ok(Self { zkbin, lines: Some(lines) })
+    }
+
+    /// Returns the circuit under test.
+    pub fn zkbin(&self) -> &ZkBinary {
+        &self.zkbin
+    }
+
+    /// Run the circuit with the given witnesses, checking it against the
+    /// expected public inputs. Returns the constraints that failed, so
+    /// an empty vector means the circuit is satisfied.
+    pub fn run(
+        &self,
+        witnesses: Vec<Witness>,
+        public_inputs: &[pallas::Base],
+    ) -> Result<Vec<CircuitFailure>> {
+        let circuit = ZkCircuit::new(witnesses, &self.zkbin);
+        let prover = MockProver::run(self.zkbin.k, &circuit, vec![public_inputs.to_vec()])?;
+
+        let Err(failures) = prover.verify() else { return Ok(vec![]) };
+
+        let opcode_regions = circuit.opcode_regions.borrow();
+        let failures = failures
+            .into_iter()
+            .map(|failure| {
+                let opcode = failure_region(&failure)
+                    .and_then(region_index)
+                    .and_then(|region| opcode_of_region(&opcode_regions, region));
+                let op = opcode.map(|idx| self.zkbin.opcodes[idx].0);
+                let line = opcode.zip(self.lines.as_ref()).map(|(idx, lines)| lines[idx]);
+                CircuitFailure { opcode, op, line, failure }
+            })
+            .collect();
+
+        Ok(failures)
+    }
+
+    /// Same as [`CircuitTester::run`], but panics with a report of
+    /// the failing constraints if the circuit isn't satisfied.
+    pub fn assert_satisfied(&self, witnesses: Vec<Witness>, public_inputs: &[pallas::Base]) {
+        let failures = match self.run(witnesses, public_inputs) {
+            Ok(v) => v,
+            Err(e) => panic!("Failed running {} circuit: {}", self.zkbin.namespace, e),
+        };
+
+        if failures.is_empty() {
+            return
+        }
+
+        let report: Vec<String> = failures.iter().map(|f| format!("  {}", f)).collect();
+        panic!("{} circuit is not satisfied:\n{}", self.zkbin.namespace, report.join("\n"));
+    }
+}
+
+/// Returns the region the cells of the given failure are in, if any.
+fn failure_region(failure: &VerifyFailure) -> Option<&metadata::Region> {
+    match failure {
+        VerifyFailure::CellNotAssigned { region, .. } => Some(region),
+        VerifyFailure::ConstraintNotSatisfied { location, .. } |
+        VerifyFailure::Lookup { location, .. } |
+        VerifyFailure::Permutation { location, .. } => match location {
+            FailureLocation::InRegion { region, .. } => Some(region),
+            FailureLocation::OutsideRegion { .. } => None,
+        },
+        _ => None,
+    }
+}
+
+/// Returns the index of the given region. halo2 doesn't expose it,
+/// so it's taken from the region's debug representation.
+fn region_index(region: &metadata::Region) -> Option<usize> {
+    let debug = format!("{:?}", region);
+    let start = debug.find("index: ")? + "index: ".len();
+    let digits: String = debug[start..].chars().take_while(|c| c.is_ascii_digit()).collect();
+    digits.parse().ok()
+}
+
+/// Find the opcode that assigned the given region, given the number of
+/// regions assigned before each opcode, followed by the total number.
+fn opcode_of_region(opcode_regions: &[usize], region: usize) -> Option<usize> {
+    let (total, starts) = opcode_regions.split_last()?;
+    if region >= *total {
+        return None
+    }
+
+    // Regions assigned before the first opcode belong to the setup
+    starts.iter().rposition(|start| *start <= region)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn region_to_opcode() {
+        // Setup took 2 regions, the opcodes 1, 0 and 3
+        let opcode_regions = [2, 3, 3, 6];
+        assert_eq!(opcode_of_region(&opcode_regions, 0), None);
+        assert_eq!(opcode_of_region(&opcode_regions, 1), None);
+        assert_eq!(opcode_of_region(&opcode_regions, 2), Some(0));
+        assert_eq!(opcode_of_region(&opcode_regions, 3), Some(2));
+        assert_eq!(opcode_of_region(&opcode_regions, 5), Some(2));
+        assert_eq!(opcode_of_region(&opcode_regions, 6), None);
+        assert_eq!(opcode_of_region(&[], 0), None);
+
+        let region = metadata::Region::from((7, "test".to_string()));
+        assert_eq!(region_index(&region), Some(7));
+    }
+}
@@ -16,7 +16,7 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::collections::HashSet;
+use std::{cell::RefCell, collections::HashSet};
 
 use darkfi_sdk::crypto::constants::{
     sinsemilla::{OrchardCommitDomains, OrchardHashDomains},
@@ -44,7 +44,7 @@ use halo2_gadgets::{
 };
 use halo2_proofs::{
     arithmetic::Field,
-    circuit::{floor_planner, AssignedCell, Layouter, Value},
+    circuit::{floor_planner, AssignedCell, Cell, Layouter, Region, Table, Value},
     pasta::{group::Curve, pallas, Fp},
     plonk,
     plonk::{Advice, Circuit, Column, ConstraintSystem, Instance as InstanceColumn},
@@ -267,6 +267,9 @@ pub struct ZkCircuit {
     literals: Vec<(LitType, String)>,
     opcodes: Vec<(Opcode, Vec<(HeapType, usize)>)>,
     pub tracer: ZkTracer,
+    /// Number of regions assigned before each opcode, followed by the total
+    /// number of regions assigned, as recorded by the last synthesis
+    pub(crate) opcode_regions: RefCell<Vec<usize>>,
 }
 
 impl ZkCircuit {
@@ -279,6 +282,7 @@ impl ZkCircuit {
             literals,
             opcodes: circuit_code.opcodes.clone(),
             tracer: ZkTracer::new(true),
+            opcode_regions: RefCell::new(vec![]),
         }
     }
 
@@ -299,6 +303,7 @@ impl Circuit<pallas::Base> for ZkCircuit {
             literals: self.literals.clone(),
             opcodes: self.opcodes.clone(),
             tracer: ZkTracer::new(false),
+            opcode_regions: RefCell::new(vec![]),
         }
     }
 
@@ -527,10 +532,14 @@ impl Circuit<pallas::Base> for ZkCircuit {
     fn synthesize(
         &self,
         config: Self::Config,
-        mut layouter: impl Layouter<pallas::Base>,
+        layouter: impl Layouter<pallas::Base>,
     ) -> std::result::Result<(), plonk::Error> {
         trace!(target: "zk::vm", "Entering synthesize()");
 
+        // Count the assigned regions, so failures can be traced to opcodes
+        let mut layouter = RegionCounter { inner: layouter, regions: 0 };
+        let mut opcode_regions = Vec::with_capacity(self.opcodes.len() + 1);
+
         // ===================
         // VM Setup
         //====================
@@ -751,6 +760,7 @@ impl Circuit<pallas::Base> for ZkCircuit {
         // TODO: Copy constraints
         // ANCHOR: opcode_begin
         for opcode in &self.opcodes {
+            opcode_regions.push(layouter.regions);
             match opcode.0 {
                 Opcode::EcAdd => {
                     trace!(target: "zk::vm", "Executing `EcAdd{:?}` opcode", opcode.1);
@@ -1212,8 +1222,73 @@ impl Circuit<pallas::Base> for ZkCircuit {
             }
         }
         self.tracer.assert_correct(self.opcodes.len());
+        opcode_regions.push(layouter.regions);
+        *self.opcode_regions.borrow_mut() = opcode_regions;
 
         trace!(target: "zk::vm", "Exiting synthesize() successfully");
         Ok(())
     }
 }
+
+/// [`Layouter`] wrapper counting the regions assigned through it,
+/// including the ones assigned by gadgets within namespaces.
+struct RegionCounter<L> {
+    inner: L,
+    regions: usize,
+}
+
+impl<L: Layouter<pallas::Base>> Layouter<pallas::Base> for RegionCounter<L> {
+    type Root = Self;
+
+    fn assign_region<A, AR, N, NR>(
+        &mut self,
+        name: N,
+        assignment: A,
+    ) -> std::result::Result<AR, plonk::Error>
+    where
+        A: FnMut(Region<'_, pallas::Base>) -> std::result::Result<AR, plonk::Error>,
+        N: Fn() -> NR,
+        NR: Into<String>,
+    {
+        self.regions += 1;
+        self.inner.assign_region(name, assignment)
+    }
+
+    fn assign_table<A, N, NR>(
+        &mut self,
+        name: N,
+        assignment: A,
+    ) -> std::result::Result<(), plonk::Error>
+    where
+        A: FnMut(Table<'_, pallas::Base>) -> std::result::Result<(), plonk::Error>,
+        N: Fn() -> NR,
+        NR: Into<String>,
+    {
+        self.inner.assign_table(name, assignment)
+    }
+
+    fn constrain_instance(
+        &mut self,
+        cell: Cell,
+        column: Column<InstanceColumn>,
+        row: usize,
+    ) -> std::result::Result<(), plonk::Error> {
+        self.inner.constrain_instance(cell, column, row)
+    }
+
+    fn get_root(&mut self) -> &mut Self::Root {
+        self
+    }
+
+    fn push_namespace<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.inner.get_root().push_namespace(name_fn)
+    }
+
+    fn pop_namespace(&mut self, gadget_name: Option<String>) {
+        self.inner.get_root().pop_namespace(gadget_name)
+    }
+}
@@ -0,0 +1,134 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Compatibility checks between compiled zkas circuits, used to catch
+//! changes that would make proofs created with a locally compiled circuit
+//! incompatible with the version of it that is deployed on-chain.
+
+use std::fmt;
+
+use super::{types::HeapType, Opcode, ZkBinary};
+
+/// A difference between a deployed and a locally compiled zkas circuit,
+/// breaking compatibility of proofs and verifying keys between them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Incompatibility {
+    /// The circuit namespace changed
+    Namespace(String, String),
+    /// The circuit's `k` parameter changed
+    K(u32, u32),
+    /// The constant table changed, starting at the given index
+    Constants(usize),
+    /// The literal table changed, starting at the given index
+    Literals(usize),
+    /// The witness layout changed, starting at the given index
+    Witnesses(usize),
+    /// The opcode stream changed, starting at the given index
+    Opcodes(usize),
+    /// The number of public inputs changed
+    PublicInputCount(usize, usize),
+    /// The public input at the given index is constrained to a different value
+    PublicInput(usize),
+}
+
+impl fmt::Display for Incompatibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Namespace(d, l) => write!(f, "namespace changed from \"{}\" to \"{}\"", d, l),
+            Self::K(d, l) => write!(f, "k changed from {} to {}", d, l),
+            Self::Constants(i) => write!(f, "constant table differs from index {}", i),
+            Self::Literals(i) => write!(f, "literal table differs from index {}", i),
+            Self::Witnesses(i) => write!(f, "witness layout differs from index {}", i),
+            Self::Opcodes(i) => write!(f, "opcode stream differs from index {}", i),
+            Self::PublicInputCount(d, l) => {
+                write!(f, "number of public inputs changed from {} to {}", d, l)
+            }
+            Self::PublicInput(i) => write!(f, "public input {} is constrained differently", i),
+        }
+    }
+}
+
+/// Returns the index of the first element differing between the two slices,
+/// including elements missing from the shorter one.
+fn first_difference<T: PartialEq>(a: &[T], b: &[T]) -> Option<usize> {
+    match a.iter().zip(b).position(|(a, b)| a != b) {
+        Some(idx) => Some(idx),
+        None if a.len() != b.len() => Some(a.len().min(b.len())),
+        None => None,
+    }
+}
+
+/// Compare a locally compiled circuit against its deployed version.
+/// Returns every breaking difference found, so an empty vector means proofs
+/// made with either circuit verify against the other's verifying key.
+pub fn check_compatibility(deployed: &ZkBinary, local: &ZkBinary) -> Vec<Incompatibility> {
+    let mut incompatibilities = vec![];
+
+    if deployed.namespace != local.namespace {
+        incompatibilities
+            .push(Incompatibility::Namespace(deployed.namespace.clone(), local.namespace.clone()));
+    }
+
+    if deployed.k != local.k {
+        incompatibilities.push(Incompatibility::K(deployed.k, local.k));
+    }
+
+    if let Some(idx) = first_difference(&deployed.constants, &local.constants) {
+        incompatibilities.push(Incompatibility::Constants(idx));
+    }
+
+    if let Some(idx) = first_difference(&deployed.literals, &local.literals) {
+        incompatibilities.push(Incompatibility::Literals(idx));
+    }
+
+    if let Some(idx) = first_difference(&deployed.witnesses, &local.witnesses) {
+        incompatibilities.push(Incompatibility::Witnesses(idx));
+    }
+
+    if let Some(idx) = first_difference(&deployed.opcodes, &local.opcodes) {
+        incompatibilities.push(Incompatibility::Opcodes(idx));
+    }
+
+    // The public input layout is what contracts build their metadata
+    // against, so it's reported on its own.
+    let deployed_inputs = public_inputs(deployed);
+    let local_inputs = public_inputs(local);
+    if deployed_inputs.len() != local_inputs.len() {
+        incompatibilities
+            .push(Incompatibility::PublicInputCount(deployed_inputs.len(), local_inputs.len()));
+    }
+
+    for (idx, (d, l)) in deployed_inputs.iter().zip(&local_inputs).enumerate() {
+        if d != l {
+            incompatibilities.push(Incompatibility::PublicInput(idx));
+        }
+    }
+
+    incompatibilities
+}
+
+/// Returns the arguments of the circuit's `constrain_instance` calls,
+/// in the order the public inputs are expected.
+fn public_inputs(zkbin: &ZkBinary) -> Vec<&[(HeapType, usize)]> {
+    zkbin
+        .opcodes
+        .iter()
+        .filter(|(opcode, _)| *opcode == Opcode::ConstrainInstance)
+        .map(|(_, args)| args.as_slice())
+        .collect()
+}
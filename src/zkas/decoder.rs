@@ -44,6 +44,12 @@ fn find_subslice(haystack: &[u8], needle: &[u8]) -> Option<usize> {
 }
 
 impl ZkBinary {
+    /// Returns the number of public inputs the circuit expects, which is
+    /// the number of `constrain_instance` calls it makes.
+    pub fn public_inputs_count(&self) -> usize {
+        self.opcodes.iter().filter(|(opcode, _)| *opcode == Opcode::ConstrainInstance).count()
+    }
+
     pub fn decode(bytes: &[u8]) -> Result<Self> {
         let magic_bytes = &bytes[0..4];
         if magic_bytes != MAGIC_BYTES {
@@ -56,3 +56,6 @@ pub use compiler::Compiler;
 /// Decoder module
 pub mod decoder;
 pub use decoder::ZkBinary;
+
+/// Compatibility checks between compiled binaries
+pub mod compat;
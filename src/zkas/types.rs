@@ -17,7 +17,7 @@
  */
 
 /// Heap types in bincode & vm
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 #[repr(u8)]
 pub enum HeapType {
     Var = 0x00,
@@ -17,14 +17,17 @@
  */
 
 use darkfi::{
-    blockchain::{BlockInfo, Blockchain, BlockchainOverlay, Header},
+    blockchain::{set_digest, BlockInfo, Blockchain, BlockchainOverlay, Header},
+    tx::Transaction,
     validator::consensus::{next_block_reward, pid::slot_pid_output},
     Error, Result,
 };
 use darkfi_sdk::{
     blockchain::{PidOutput, PreviousSlot, Slot},
+    crypto::{ContractId, SecretKey},
     pasta::{group::ff::Field, pallas},
 };
+use darkfi_serial::serialize;
 
 struct Harness {
     pub alice: Blockchain,
@@ -33,8 +36,8 @@ struct Harness {
 
 impl Harness {
     fn new() -> Result<Self> {
-        let alice = Blockchain::new(&sled::Config::new().temporary(true).open()?)?;
-        let bob = Blockchain::new(&sled::Config::new().temporary(true).open()?)?;
+        let alice = Blockchain::in_memory()?;
+        let bob = Blockchain::in_memory()?;
         Ok(Self { alice, bob })
     }
 
@@ -157,7 +160,104 @@ fn blockchain_add_blocks() -> Result<()> {
         // Validate chains
         th.validate_chains()?;
 
+        // Blocks can be found by their height
+        for (height, block) in blocks.iter().enumerate() {
+            let found = th.alice.get_block_by_height(height as u64)?.unwrap();
+            assert_eq!(found.blockhash(), block.blockhash());
+            assert_eq!(th.alice.block_height(block), height as u64);
+        }
+        assert!(th.alice.get_block_by_height(blocks.len() as u64)?.is_none());
+
         // Thanks for reading
         Ok(())
     })
 }
+
+#[test]
+fn blockchain_snapshot() -> Result<()> {
+    // Initialize harness
+    let th = Harness::new()?;
+
+    // Alice builds a chain and some contract state
+    let genesis_block = BlockInfo::default();
+    let block = th.generate_next_block(&genesis_block);
+    let blocks = vec![genesis_block, block.clone(), th.generate_next_block(&block)];
+    th.add_blocks_to_chain(&th.alice, &blocks)?;
+
+    let state = th.alice.sled_db.open_tree("contract_state")?;
+    state.insert(b"key", b"value")?;
+
+    // Pending transactions aren't chain state
+    th.alice.add_pending_txs(&[Transaction::default()])?;
+
+    let path = std::env::temp_dir().join(format!("darkfi-snapshot-{}", std::process::id()));
+    let exported = th.alice.export_snapshot(&path)?;
+    assert_eq!(exported.height, 2);
+    assert_eq!(exported.block, blocks[2].blockhash());
+
+    // Bob bootstraps from the snapshot
+    let imported = th.bob.import_snapshot(&path)?;
+    assert_eq!(imported, exported);
+    th.validate_chains()?;
+    assert_eq!(th.bob.last()?, th.alice.last()?);
+    let state = th.bob.sled_db.open_tree("contract_state")?;
+    assert_eq!(state.get(b"key")?.unwrap(), b"value");
+    assert!(th.bob.get_pending_txs()?.is_empty());
+
+    // Importing over existing state is refused
+    assert!(th.bob.import_snapshot(&path).is_err());
+
+    // Tampered archives are refused
+    let mut archive = std::fs::read(&path)?;
+    *archive.last_mut().unwrap() ^= 1;
+    std::fs::write(&path, archive)?;
+    let carol = Blockchain::in_memory()?;
+    assert!(matches!(carol.import_snapshot(&path), Err(Error::SnapshotInvalid(_))));
+    assert!(carol.is_empty());
+
+    std::fs::remove_file(&path)?;
+
+    // Thanks for reading
+    Ok(())
+}
+
+#[test]
+fn blockchain_set_export() -> Result<()> {
+    // Initialize harness
+    let th = Harness::new()?;
+
+    // Alice builds a chain and a contract nullifier set
+    let genesis_block = BlockInfo::default();
+    let block = th.generate_next_block(&genesis_block);
+    th.add_blocks_to_chain(&th.alice, &[genesis_block, block.clone()])?;
+
+    let contract_id = ContractId::derive(SecretKey::from(pallas::Base::from(42)));
+    let overlay = BlockchainOverlay::new(&th.alice)?;
+    overlay.lock().unwrap().contracts.init(&contract_id, "nullifiers")?;
+    overlay.lock().unwrap().overlay.lock().unwrap().apply()?;
+
+    let nullifiers = th.alice.contracts.lookup(&th.alice.sled_db, &contract_id, "nullifiers")?;
+    for i in [3u64, 1, 2] {
+        nullifiers.insert(serialize(&pallas::Base::from(i)), vec![])?;
+    }
+
+    let export = th.alice.export_set(&contract_id, "nullifiers")?;
+    assert_eq!(export.height, 1);
+    assert_eq!(export.block, block.blockhash());
+    assert_eq!(
+        export.elements,
+        [1u64, 2, 3].into_iter().map(pallas::Base::from).collect::<Vec<_>>()
+    );
+    assert_eq!(export.digest, set_digest(&export.elements)?);
+
+    // The digest commits to the elements
+    let mut tampered = export.elements.clone();
+    tampered.pop();
+    assert_ne!(export.digest, set_digest(&tampered)?);
+
+    // Unknown states can't be exported
+    assert!(th.alice.export_set(&contract_id, "coins").is_err());
+
+    // Thanks for reading
+    Ok(())
+}
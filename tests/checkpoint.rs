@@ -0,0 +1,57 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{
+    validator::checkpoint::{Checkpoint, CheckpointConfig},
+    Result,
+};
+use darkfi_sdk::crypto::{PublicKey, SecretKey};
+use rand::rngs::OsRng;
+
+#[test]
+fn checkpoint_multisig() -> Result<()> {
+    let secrets: Vec<SecretKey> = (0..3).map(|_| SecretKey::random(&mut OsRng)).collect();
+    let authorities = secrets.iter().map(|s| PublicKey::from_secret(*s)).collect();
+    let config = CheckpointConfig { authorities, threshold: 2, finality_hints: true };
+
+    let mut checkpoint = Checkpoint::new(42, blake3::hash(b"Let there be dark!"));
+
+    // A single signature doesn't reach the threshold
+    checkpoint.sign(&secrets[0]);
+    assert!(checkpoint.verify(&config).is_err());
+
+    // Signing twice with the same key doesn't count twice
+    checkpoint.sign(&secrets[0]);
+    assert_eq!(checkpoint.signatures.len(), 1);
+    assert!(checkpoint.verify(&config).is_err());
+
+    checkpoint.sign(&secrets[1]);
+    assert!(checkpoint.verify(&config).is_ok());
+
+    // Signatures by keys outside the authority set are rejected
+    let mut outsider = checkpoint.clone();
+    outsider.sign(&SecretKey::random(&mut OsRng));
+    assert!(outsider.verify(&config).is_err());
+
+    // Signatures don't carry over to a different block
+    let mut forged = checkpoint.clone();
+    forged.block_hash = blake3::hash(b"Never skip brain day.");
+    assert!(forged.verify(&config).is_err());
+
+    Ok(())
+}
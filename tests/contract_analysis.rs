@@ -0,0 +1,74 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::runtime::analysis::{analyze_contract, ContractFinding};
+use wasmer::wat2wasm;
+
+#[test]
+fn sanctioned_contract_has_no_findings() {
+    let wasm = wat2wasm(
+        br#"(module
+            (import "env" "db_get_" (func $db_get (param i32 i64) (result i64)))
+            (func (export "process_instruction") (param i32) (result i64)
+                (local $i i32)
+                (loop $continue
+                    (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                    (br_if $continue (i32.lt_u (local.get $i) (i32.const 10))))
+                (i64.const 0)))"#,
+    )
+    .unwrap();
+
+    assert!(analyze_contract(&wasm).unwrap().is_empty());
+}
+
+#[test]
+fn forbidden_patterns_are_flagged() {
+    let wasm = wat2wasm(
+        br#"(module
+            (import "env" "db_get_" (func $db_get (param i32 i64) (result i64)))
+            (import "env" "get_system_clock_" (func $clock (result i64)))
+            (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+            (func $spin
+                (loop $forever
+                    (br $forever)))
+            (func $float (param f64) (result f64)
+                (f64.div (local.get 0) (f64.const 3))))"#,
+    )
+    .unwrap();
+
+    let findings = analyze_contract(&wasm).unwrap();
+    assert_eq!(
+        findings,
+        vec![
+            ContractFinding::NondeterministicImport {
+                module: "env".to_string(),
+                name: "get_system_clock_".to_string()
+            },
+            ContractFinding::UnsanctionedImport {
+                module: "wasi_snapshot_preview1".to_string(),
+                name: "fd_write".to_string()
+            },
+            // Imported functions come first in the function index space
+            ContractFinding::UnboundedLoop { function: 3 },
+            ContractFinding::FloatArithmetic { function: 4 },
+        ]
+    );
+
+    // Garbage can't be analyzed
+    assert!(analyze_contract(b"not wasm").is_err());
+}
@@ -0,0 +1,81 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{fs, path::PathBuf};
+
+use darkfi::{
+    runtime::build_meta::{
+        code_hash, embed_build_metadata, hash_sources, read_build_metadata, strip_build_metadata,
+        BuildMetadata,
+    },
+    Result,
+};
+
+// Header followed by an empty type section
+const WASM: [u8; 11] = [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x01, 0x00];
+
+#[test]
+fn build_metadata_roundtrip() -> Result<()> {
+    assert!(read_build_metadata(&WASM)?.is_none());
+
+    let metadata =
+        BuildMetadata { source_hash: [7u8; 32], compiler_version: "rustc 1.0.0".to_string() };
+    let embedded = embed_build_metadata(&WASM, &metadata)?;
+    assert_eq!(read_build_metadata(&embedded)?, Some(metadata.clone()));
+
+    // The code hash doesn't cover the metadata
+    assert_eq!(strip_build_metadata(&embedded)?, WASM.to_vec());
+    assert_eq!(code_hash(&embedded)?, code_hash(&WASM)?);
+
+    // Embedding again replaces the previous metadata
+    let updated = BuildMetadata { source_hash: [8u8; 32], ..metadata };
+    let reembedded = embed_build_metadata(&embedded, &updated)?;
+    assert_eq!(reembedded.len(), embedded.len());
+    assert_eq!(read_build_metadata(&reembedded)?, Some(updated));
+
+    // Garbage is rejected
+    assert!(read_build_metadata(b"not wasm").is_err());
+    assert!(read_build_metadata(&embedded[..embedded.len() - 1]).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn source_hash_is_location_independent() -> Result<()> {
+    let base = std::env::temp_dir().join(format!("darkfi-build-meta-{}", std::process::id()));
+    let first = base.join("first");
+    let second = base.join("second");
+
+    for root in [&first, &second] {
+        fs::create_dir_all(root.join("src"))?;
+        fs::write(root.join("src/lib.rs"), "pub fn f() {}")?;
+        fs::write(root.join("Cargo.toml"), "[package]")?;
+        // Files outside the covered extensions are ignored
+        fs::write(root.join("notes.txt"), root.to_string_lossy().as_bytes())?;
+    }
+
+    let first_hash = hash_sources(&[first.clone()])?;
+    assert_eq!(first_hash, hash_sources(&[second.clone()])?);
+
+    fs::write(second.join("src/lib.rs"), "pub fn g() {}")?;
+    assert_ne!(first_hash, hash_sources(&[second])?);
+    assert_ne!(first_hash, hash_sources(&[PathBuf::from(&base)])?);
+
+    fs::remove_dir_all(base)?;
+    Ok(())
+}
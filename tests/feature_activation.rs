@@ -0,0 +1,54 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_sdk::{
+    activation::{ActivationSchedule, Feature, ACTIVATIONS},
+    error::{ContractError, UNKNOWN_FEATURE},
+};
+
+#[test]
+fn feature_activation_boundaries() {
+    let schedule = ActivationSchedule(&[(Feature::TxFees, 100)]);
+    assert_eq!(schedule.activation_height(Feature::TxFees), Some(100));
+
+    // Features activate exactly at their activation height
+    assert!(!schedule.is_active(Feature::TxFees, 0));
+    assert!(!schedule.is_active(Feature::TxFees, 99));
+    assert!(schedule.is_active(Feature::TxFees, 100));
+    assert!(schedule.is_active(Feature::TxFees, 101));
+    assert!(schedule.is_active(Feature::TxFees, u64::MAX));
+
+    // Unscheduled features never activate
+    let schedule = ActivationSchedule(&[]);
+    assert_eq!(schedule.activation_height(Feature::TxFees), None);
+    assert!(!schedule.is_active(Feature::TxFees, u64::MAX));
+
+    // Fees are enforced since genesis on the network
+    assert!(ACTIVATIONS.is_active(Feature::TxFees, 0));
+}
+
+#[test]
+fn feature_host_encoding() {
+    // Features cross the host boundary as their numeric ids
+    assert_eq!(Feature::try_from(Feature::TxFees as u32).unwrap(), Feature::TxFees);
+    assert!(matches!(Feature::try_from(u32::MAX), Err(ContractError::UnknownFeature)));
+
+    let code: i64 = ContractError::UnknownFeature.into();
+    assert_eq!(code, UNKNOWN_FEATURE);
+    assert!(matches!(ContractError::from(code), ContractError::UnknownFeature));
+}
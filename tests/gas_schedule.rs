@@ -0,0 +1,58 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::runtime::gas::{gas_schedule, GAS_SCHEDULE_V1, GAS_SCHEDULE_V2};
+use darkfi_sdk::activation::{ActivationSchedule, Feature, ACTIVATIONS};
+use wasmer::wasmparser::Operator;
+
+#[test]
+fn gas_schedule_activation() {
+    // Without the feature scheduled, the genesis schedule stays in force
+    let schedule = ActivationSchedule(&[]);
+    assert_eq!(gas_schedule(&schedule, 0), &GAS_SCHEDULE_V1);
+    assert_eq!(gas_schedule(&schedule, u64::MAX), &GAS_SCHEDULE_V1);
+
+    // Blocks before the activation height replay with the old costs
+    let schedule = ActivationSchedule(&[(Feature::GasScheduleV2, 100)]);
+    assert_eq!(gas_schedule(&schedule, 99), &GAS_SCHEDULE_V1);
+    assert_eq!(gas_schedule(&schedule, 100), &GAS_SCHEDULE_V2);
+    assert_eq!(gas_schedule(&schedule, u64::MAX), &GAS_SCHEDULE_V2);
+
+    // The network hasn't scheduled the second schedule yet
+    assert_eq!(gas_schedule(&ACTIVATIONS, 0), &GAS_SCHEDULE_V1);
+    assert_eq!(Feature::try_from(Feature::GasScheduleV2 as u32).unwrap(), Feature::GasScheduleV2);
+}
+
+#[test]
+fn gas_schedule_costs() {
+    // The genesis schedule charges every opcode the same and state access is free
+    for op in [Operator::Nop, Operator::Return, Operator::Call { function_index: 0 }] {
+        assert_eq!(GAS_SCHEDULE_V1.opcodes.cost(&op), 1);
+    }
+    assert_eq!(GAS_SCHEDULE_V1.state_read(1024), 0);
+    assert_eq!(GAS_SCHEDULE_V1.state_write(1024), 0);
+
+    // Later schedules price opcodes by class and state access by byte
+    let costs = GAS_SCHEDULE_V2.opcodes;
+    assert_eq!(costs.cost(&Operator::I32Add), costs.simple);
+    assert_eq!(costs.cost(&Operator::Return), costs.control);
+    assert_eq!(costs.cost(&Operator::Call { function_index: 0 }), costs.call);
+    assert_eq!(GAS_SCHEDULE_V2.state_read(10), 10 * GAS_SCHEDULE_V2.state_io.read_byte);
+    assert_eq!(GAS_SCHEDULE_V2.state_write(10), 10 * GAS_SCHEDULE_V2.state_io.write_byte);
+    assert_eq!(GAS_SCHEDULE_V2.state_write(usize::MAX), u64::MAX);
+}
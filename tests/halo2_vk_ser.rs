@@ -27,13 +27,14 @@ use halo2_gadgets::poseidon::{
 use halo2_proofs::{
     arithmetic::{CurveAffine, Field},
     circuit::Value,
-    pasta::{group::Curve, pallas},
+    pasta::{group::Curve, pallas, vesta},
+    poly::commitment::Params,
 };
 use rand::rngs::OsRng;
 
 use darkfi::{
     zk::{
-        proof::{ProvingKey, VerifyingKey},
+        proof::{shared_params, ProvingKey, VerifyingKey},
         vm::ZkCircuit,
         vm_heap::{empty_witnesses, Witness},
         Proof,
@@ -172,3 +173,20 @@ fn halo2_vk_ser() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn halo2_shared_params() -> Result<()> {
+    let k = 11;
+
+    // Cached parameters must be identical to freshly generated ones
+    let mut fresh = vec![];
+    Params::<vesta::Affine>::new(k).write(&mut fresh)?;
+
+    for _ in 0..2 {
+        let mut cached = vec![];
+        shared_params(k).write(&mut cached)?;
+        assert_eq!(cached, fresh);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,77 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{
+    blockchain::{Blockchain, BlockchainOverlay},
+    Error, Result,
+};
+
+fn headerhashes(n: u64) -> Vec<blake3::Hash> {
+    (0..n).map(|i| blake3::hash(&i.to_le_bytes())).collect()
+}
+
+#[test]
+fn header_mmr_proofs() -> Result<()> {
+    let blockchain = Blockchain::in_memory()?;
+    let hashes = headerhashes(23);
+
+    // Append one by one, remembering the root at each size
+    let mut roots = vec![blockchain.mmr.get_root(0)?];
+    for hash in &hashes {
+        blockchain.mmr.insert(&[*hash])?;
+        roots.push(blockchain.mmr.get_root(blockchain.mmr.size()?)?);
+    }
+    assert_eq!(blockchain.mmr.size()?, hashes.len() as u64);
+
+    // Past roots never change, and every leaf is provable at every size
+    for size in 1..=hashes.len() as u64 {
+        assert_eq!(blockchain.mmr.get_root(size)?, roots[size as usize]);
+        for leaf_index in 0..size {
+            let proof = blockchain.mmr.get_proof(leaf_index, size)?;
+            assert!(proof.verify(&roots[size as usize], &hashes[leaf_index as usize]));
+            assert!(!proof.verify(&roots[size as usize], &blake3::hash(b"forged")));
+            assert!(!proof.verify(&roots[size as usize - 1], &hashes[leaf_index as usize]));
+        }
+    }
+
+    // Out of range requests are refused
+    assert!(matches!(blockchain.mmr.get_proof(23, 23), Err(Error::MmrLeafOutOfRange(23, 23))));
+    assert!(matches!(blockchain.mmr.get_root(24), Err(Error::MmrLeafOutOfRange(24, 23))));
+
+    Ok(())
+}
+
+#[test]
+fn header_mmr_overlay() -> Result<()> {
+    let blockchain = Blockchain::in_memory()?;
+    let hashes = headerhashes(10);
+    blockchain.mmr.insert(&hashes[..3])?;
+
+    // Appending through an overlay and applying it matches direct appends
+    let overlay = BlockchainOverlay::new(&blockchain)?;
+    overlay.lock().unwrap().mmr.insert(&hashes[3..])?;
+    overlay.lock().unwrap().overlay.lock().unwrap().apply()?;
+
+    let expected = Blockchain::in_memory()?;
+    expected.mmr.insert(&hashes)?;
+
+    assert_eq!(blockchain.mmr.size()?, 10);
+    assert_eq!(blockchain.mmr.get_root(10)?, expected.mmr.get_root(10)?);
+
+    Ok(())
+}
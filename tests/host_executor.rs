@@ -0,0 +1,62 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{sync::Arc, thread, time::Duration};
+
+use darkfi::system::HostExecutor;
+use smol::{channel, future, Executor, Timer};
+
+/// Wait for `fut`, failing the test if it takes longer than a few seconds
+async fn within_timeout<T>(fut: impl future::Future<Output = T>) -> T {
+    future::or(fut, async {
+        Timer::after(Duration::from_secs(5)).await;
+        panic!("Timed out");
+    })
+    .await
+}
+
+#[test]
+fn host_executor_driver() {
+    // The host runtime, running on its own thread
+    let host_rt = Arc::new(Executor::new());
+    let (shutdown_send, shutdown_recv) = channel::unbounded::<()>();
+    let host_thread = {
+        let host_rt = host_rt.clone();
+        thread::spawn(move || smol::block_on(host_rt.run(shutdown_recv.recv())))
+    };
+
+    // Drive the wrapped executor from the host runtime
+    let host_ex = HostExecutor::new();
+    let driver = host_rt.spawn(host_ex.driver());
+
+    // Tasks spawned on the wrapped executor get run by the driver
+    let task = host_ex.executor().spawn(async { 42 });
+    assert_eq!(smol::block_on(within_timeout(task)), 42);
+
+    // Stopping makes the driver return
+    assert!(!host_ex.is_stopped());
+    host_ex.stop();
+    assert!(host_ex.is_stopped());
+    smol::block_on(within_timeout(driver));
+
+    // Stopping is permanent, so new drivers return right away
+    smol::block_on(within_timeout(host_rt.spawn(host_ex.driver())));
+
+    drop(shutdown_send);
+    let _ = host_thread.join().unwrap();
+}
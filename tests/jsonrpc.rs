@@ -16,7 +16,10 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::sync::Arc;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use async_trait::async_trait;
 use smol::{
@@ -32,9 +35,10 @@ use darkfi::{
     rpc::{
         client::RpcClient,
         jsonrpc::*,
-        server::{accept, RequestHandler},
+        ratelimit::{ConnectionLimiter, RateLimit, RateLimitConfig},
+        server::{accept, accept_with_limits, RequestHandler},
     },
-    Result,
+    Error, Result,
 };
 
 struct RpcSrv {
@@ -109,3 +113,102 @@ fn jsonrpc_reqrep() -> Result<()> {
         Ok(())
     }))
 }
+
+#[test]
+fn jsonrpc_rate_limit() -> Result<()> {
+    let executor = Arc::new(Executor::new());
+    let executor_ = executor.clone();
+
+    smol::block_on(executor.run(async {
+        // Find an available port
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let sockaddr = listener.local_addr()?;
+        let endpoint = Url::parse(&format!("tcp://127.0.0.1:{}", sockaddr.port()))?;
+        drop(listener);
+
+        let rpcsrv = Arc::new(RpcSrv { stop_sub: smol::channel::unbounded() });
+        let listener = Listener::new(endpoint.clone()).await?.listen().await?;
+        let limits = RateLimitConfig {
+            cheap: Some(RateLimit::new(2, 0.001)),
+            expensive: Some(RateLimit::new(1, 0.001)),
+            expensive_methods: vec!["kill".to_string()],
+            ..Default::default()
+        };
+
+        executor
+            .spawn(async move {
+                while let Ok((stream, peer_addr)) = listener.next().await {
+                    let _rh = rpcsrv.clone();
+                    let _limits = Some(limits.clone());
+                    executor_
+                        .spawn(async move {
+                            let _ = accept_with_limits(stream, peer_addr, _rh, _limits).await;
+                        })
+                        .detach();
+                }
+            })
+            .detach();
+
+        let client = RpcClient::new(endpoint, executor.clone()).await?;
+        for _ in 0..2 {
+            let rep = client.request(JsonRequest::new("ping", vec![])).await?;
+            assert_eq!(&String::try_from(rep).unwrap(), "pong");
+        }
+
+        // Cheap bucket is now empty
+        match client.request(JsonRequest::new("ping", vec![])).await {
+            Err(Error::JsonRpcError((code, _))) => assert_eq!(code, ErrorCode::RateLimited.code()),
+            _ => panic!("ping should have been rate limited"),
+        }
+
+        // Expensive methods are charged against their own bucket
+        let rep = client.request(JsonRequest::new("kill", vec![])).await?;
+        assert_eq!(&String::try_from(rep).unwrap(), "bye");
+
+        Ok(())
+    }))
+}
+
+#[test]
+fn rate_limit_buckets() {
+    let config = RateLimitConfig {
+        connection: Some(RateLimit::new(3, 1.0)),
+        cheap: Some(RateLimit::new(10, 10.0)),
+        expensive: Some(RateLimit::new(1, 1.0)),
+        expensive_methods: vec!["tx.simulate".to_string()],
+        ..Default::default()
+    };
+    let mut limiter = ConnectionLimiter::new(config);
+    let start = Instant::now();
+
+    assert!(limiter.allow_at("tx.simulate", start));
+    assert!(!limiter.allow_at("tx.simulate", start));
+    // A rejected expensive call doesn't drain the connection bucket
+    assert!(limiter.allow_at("ping", start));
+    assert!(limiter.allow_at("ping", start));
+    assert!(!limiter.allow_at("ping", start));
+
+    // Buckets refill over time
+    let later = start + Duration::from_secs(1);
+    assert!(limiter.allow_at("tx.simulate", later));
+    assert!(!limiter.allow_at("ping", later));
+}
+
+#[test]
+fn jsonrpc_error_data() {
+    let data =
+        JsonValue::Object([("call".to_string(), JsonValue::Number(1.0))].into_iter().collect());
+    let err = JsonError::new(ErrorCode::ServerError(-32110), Some("failed".to_string()), 7)
+        .with_data(data.clone());
+
+    let value: JsonValue = (&err).into();
+    let parsed = JsonError::try_from(&value).unwrap();
+    assert_eq!(parsed.id, 7);
+    assert_eq!(parsed.error.code, -32110);
+    assert_eq!(parsed.error.message, "failed");
+    assert_eq!(parsed.error.data, Some(data));
+
+    // The data field is optional
+    let value: JsonValue = (&JsonError::new(ErrorCode::InternalError, None, 7)).into();
+    assert!(JsonError::try_from(&value).unwrap().error.data.is_none());
+}
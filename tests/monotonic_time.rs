@@ -0,0 +1,38 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::util::time::{correct_monotonic_clock, NanoTimestamp};
+
+#[test]
+fn monotonic_time() {
+    let mut last = NanoTimestamp::monotonic();
+    for _ in 0..1000 {
+        let now = NanoTimestamp::monotonic();
+        assert!(now > last);
+        last = now;
+    }
+
+    // Readings stay close to wall time
+    let wall = NanoTimestamp::current_time();
+    assert!(last.0.abs_diff(wall.0) < 1_000_000_000);
+
+    // A negative correction never makes the clock go backwards
+    correct_monotonic_clock(-10_000_000_000);
+    assert!(NanoTimestamp::monotonic() > last);
+    correct_monotonic_clock(10_000_000_000);
+}
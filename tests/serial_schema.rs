@@ -0,0 +1,159 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Guards the wire format of consensus-critical types.
+//!
+//! The schema of every type below is compared against the snapshot
+//! stored for the current `SERIAL_FORMAT_VERSION`. Changing a layout
+//! without bumping the version (and adding a new snapshot) fails here.
+
+use std::path::PathBuf;
+
+use darkfi::{
+    blockchain::{
+        Block, BlockInfo, BlockProducer, DeploymentRecord, Header, SERIAL_FORMAT_VERSION,
+    },
+    tx::Transaction,
+};
+use darkfi_consensus_contract::model::{
+    ConsensusGenesisStakeParamsV1, ConsensusProposalParamsV1, ConsensusProposalUpdateV1,
+    ConsensusSlashEvidenceV1, ConsensusSlashHeaderV1, ConsensusSlashParamsV1,
+    ConsensusSlashUpdateV1, ConsensusUnstakeRequestParamsV1, ConsensusUnstakeRequestUpdateV1,
+};
+use darkfi_dao_contract::model::{
+    DaoBlindAggregateVote, DaoBulla, DaoConsensusParam, DaoExecParams, DaoExecUpdate,
+    DaoMintParams, DaoMintUpdate, DaoProposalAction, DaoProposalBulla, DaoProposalMetadata,
+    DaoProposeParams, DaoProposeParamsInput, DaoProposeUpdate, DaoVoteParams, DaoVoteParamsInput,
+    DaoVoteUpdate,
+};
+use darkfi_money_contract::model::{
+    ClearInput, Coin, ConsensusInput, ConsensusOutput, ConsensusStakeParamsV1,
+    ConsensusStakeUpdateV1, ConsensusUnstakeParamsV1, ConsensusUnstakeReqParamsV1,
+    ConsensusUnstakeUpdateV1, Input, MoneyFeeParamsV1, MoneyStakeParamsV1, MoneyStakeUpdateV1,
+    MoneyTokenFreezeParamsV1, MoneyTokenFreezeUpdateV1, MoneyTokenMetadataParamsV1,
+    MoneyTokenMetadataUpdateV1, MoneyTokenMintParamsV1, MoneyTokenMintUpdateV1,
+    MoneyTransferParamsV1, MoneyTransferUpdateV1, MoneyUnstakeParamsV1, MoneyUnstakeUpdateV1,
+    Output, SupplyOpening, TokenMetadata, TokenMintInfo, TokenSupply,
+};
+use darkfi_sdk::{
+    blockchain::{PidOutput, PreviousSlot, Slot},
+    tx::{AccessList, ContractCall, StateScope},
+};
+use darkfi_serial::SerialSchema;
+
+/// Emit the schema of all consensus-critical types, including the native
+/// contracts' call parameters, state updates and state records.
+fn dump_schemas() -> String {
+    let schemas = [
+        ContractCall::schema(),
+        Transaction::schema(),
+        AccessList::schema(),
+        StateScope::schema(),
+        Header::schema(),
+        Block::schema(),
+        BlockInfo::schema(),
+        BlockProducer::schema(),
+        Slot::schema(),
+        PreviousSlot::schema(),
+        PidOutput::schema(),
+        DeploymentRecord::schema(),
+        // Contract call parameters, state updates and state records
+        Coin::schema(),
+        ClearInput::schema(),
+        Input::schema(),
+        ConsensusInput::schema(),
+        Output::schema(),
+        ConsensusOutput::schema(),
+        MoneyTransferParamsV1::schema(),
+        MoneyTransferUpdateV1::schema(),
+        MoneyFeeParamsV1::schema(),
+        MoneyTokenMintParamsV1::schema(),
+        TokenMintInfo::schema(),
+        SupplyOpening::schema(),
+        TokenSupply::schema(),
+        MoneyTokenMintUpdateV1::schema(),
+        MoneyTokenFreezeParamsV1::schema(),
+        MoneyTokenFreezeUpdateV1::schema(),
+        TokenMetadata::schema(),
+        MoneyTokenMetadataParamsV1::schema(),
+        MoneyTokenMetadataUpdateV1::schema(),
+        MoneyStakeParamsV1::schema(),
+        MoneyStakeUpdateV1::schema(),
+        MoneyUnstakeParamsV1::schema(),
+        MoneyUnstakeUpdateV1::schema(),
+        ConsensusStakeParamsV1::schema(),
+        ConsensusStakeUpdateV1::schema(),
+        ConsensusUnstakeReqParamsV1::schema(),
+        ConsensusUnstakeParamsV1::schema(),
+        ConsensusUnstakeUpdateV1::schema(),
+        DaoBulla::schema(),
+        DaoProposalBulla::schema(),
+        DaoConsensusParam::schema(),
+        DaoProposalAction::schema(),
+        DaoMintParams::schema(),
+        DaoMintUpdate::schema(),
+        DaoProposeParams::schema(),
+        DaoProposeParamsInput::schema(),
+        DaoProposeUpdate::schema(),
+        DaoProposalMetadata::schema(),
+        DaoVoteParams::schema(),
+        DaoVoteParamsInput::schema(),
+        DaoVoteUpdate::schema(),
+        DaoBlindAggregateVote::schema(),
+        DaoExecParams::schema(),
+        DaoExecUpdate::schema(),
+        ConsensusGenesisStakeParamsV1::schema(),
+        ConsensusProposalParamsV1::schema(),
+        ConsensusProposalUpdateV1::schema(),
+        ConsensusUnstakeRequestParamsV1::schema(),
+        ConsensusUnstakeRequestUpdateV1::schema(),
+        ConsensusSlashHeaderV1::schema(),
+        ConsensusSlashEvidenceV1::schema(),
+        ConsensusSlashParamsV1::schema(),
+        ConsensusSlashUpdateV1::schema(),
+    ];
+
+    schemas.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn serial_schema_unchanged() {
+    let path: PathBuf = [
+        env!("CARGO_MANIFEST_DIR"),
+        "tests",
+        "serial_schema",
+        &format!("v{}.txt", SERIAL_FORMAT_VERSION),
+    ]
+    .iter()
+    .collect();
+
+    let current = dump_schemas();
+
+    let Ok(snapshot) = std::fs::read_to_string(&path) else {
+        panic!(
+            "No schema snapshot for format version {}, create {:?} with:\n{}",
+            SERIAL_FORMAT_VERSION, path, current
+        );
+    };
+
+    assert!(
+        snapshot == current,
+        "Serialization schema changed, bump SERIAL_FORMAT_VERSION and add a new snapshot:\n{}",
+        current
+    );
+}
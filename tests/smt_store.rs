@@ -0,0 +1,57 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{blockchain::SmtStore, Result};
+use darkfi_sdk::{
+    crypto::smt::{MemoryStorageFp, SparseMerkleTreeFp, EMPTY_LEAF_FP},
+    pasta::{group::ff::Field, pallas},
+};
+use rand::rngs::OsRng;
+
+#[test]
+fn smt_sled_storage() -> Result<()> {
+    let sled_db = sled::Config::new().temporary(true).open()?;
+    let leaves: Vec<_> = (0..5)
+        .map(|_| (pallas::Base::random(&mut OsRng), pallas::Base::random(&mut OsRng)))
+        .collect();
+
+    let mut memory_smt = SparseMerkleTreeFp::new(MemoryStorageFp::new());
+    memory_smt.insert_batch(&leaves).unwrap();
+
+    let mut sled_smt = SparseMerkleTreeFp::new(SmtStore::new(&sled_db, b"_test_smt")?);
+    sled_smt.insert_batch(&leaves).unwrap();
+    assert_eq!(sled_smt.root().unwrap(), memory_smt.root().unwrap());
+    assert_eq!(sled_smt.store().0.len(), memory_smt.store().tree.len());
+
+    // Reopening the sled tree gives back the same state
+    let root = sled_smt.root().unwrap();
+    drop(sled_smt);
+    let mut sled_smt = SparseMerkleTreeFp::new(SmtStore::new(&sled_db, b"_test_smt")?);
+    assert_eq!(sled_smt.root().unwrap(), root);
+
+    let (key, value) = leaves[0];
+    let path = sled_smt.prove(&key).unwrap();
+    assert!(path.verify(&root, &key, &value));
+
+    sled_smt.remove(&key).unwrap();
+    let root = sled_smt.root().unwrap();
+    let path = sled_smt.prove(&key).unwrap();
+    assert!(path.verify(&root, &key, &EMPTY_LEAF_FP));
+
+    Ok(())
+}
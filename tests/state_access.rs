@@ -0,0 +1,126 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{
+    blockchain::{Blockchain, BlockchainOverlay},
+    runtime::state_access::StateAccess,
+    Result,
+};
+use darkfi_sdk::tx::{AccessList, StateScope};
+
+#[test]
+fn state_access_conflicts() {
+    let tree = [1u8; 32];
+    let other_tree = [2u8; 32];
+
+    let mut first = StateAccess::default();
+    first.read(&tree, b"a");
+    first.write(&tree, b"b", b"value");
+
+    // Reading what the first execution only read is not a conflict
+    let mut second = StateAccess::default();
+    second.read(&tree, b"a");
+    second.write(&other_tree, b"b", b"value");
+    assert!(!second.depends_on(&first));
+
+    // Reading what it wrote is
+    let mut third = StateAccess::default();
+    third.read(&tree, b"b");
+    assert!(third.depends_on(&first));
+
+    // So is writing the same key again
+    let mut fourth = StateAccess::default();
+    fourth.remove(&tree, b"b");
+    assert!(fourth.depends_on(&first));
+
+    first.extend(second);
+    assert!(first.writes.contains_key(&(other_tree, b"b".to_vec())));
+    assert!(!first.structural);
+}
+
+#[test]
+fn state_access_replay() -> Result<()> {
+    let blockchain = Blockchain::in_memory()?;
+    let overlay = BlockchainOverlay::new(&blockchain)?;
+    let tree = [3u8; 32];
+
+    let mut access = StateAccess::default();
+    access.write(&tree, b"kept", b"1");
+    access.write(&tree, b"removed", b"2");
+    access.remove(&tree, b"removed");
+    access.replay(&overlay)?;
+
+    let lock = overlay.lock().unwrap();
+    let db = lock.overlay.lock().unwrap();
+    assert_eq!(db.get(&tree, b"kept")?.unwrap(), b"1");
+    assert!(!db.contains_key(&tree, b"removed")?);
+
+    Ok(())
+}
+
+#[test]
+fn state_access_rollback() {
+    let tree = [5u8; 32];
+
+    let mut access = StateAccess::default();
+    access.write(&tree, b"kept", b"1");
+    let savepoint = access.writes.clone();
+
+    access.write(&tree, b"kept", b"2");
+    access.write(&tree, b"discarded", b"3");
+    access.rollback(savepoint);
+
+    // Writes made after the savepoint are gone, but still count as reads
+    assert_eq!(access.writes.get(&(tree, b"kept".to_vec())), Some(&Some(b"1".to_vec())));
+    assert!(!access.writes.contains_key(&(tree, b"discarded".to_vec())));
+    assert!(access.reads.contains(&(tree, b"discarded".to_vec())));
+
+    let mut other = StateAccess::default();
+    other.write(&tree, b"discarded", b"4");
+    assert!(access.depends_on(&other));
+}
+
+#[test]
+fn access_list_declarations() {
+    let tree = [4u8; 32];
+    let whole_tree = StateScope { tree, key: None };
+    let key_a = StateScope { tree, key: Some(b"a".to_vec()) };
+    let key_b = StateScope { tree, key: Some(b"b".to_vec()) };
+
+    let declared = AccessList { reads: vec![key_a.clone()], writes: vec![key_b.clone()] };
+
+    let mut access = StateAccess::default();
+    access.read(&tree, b"a");
+    access.write(&tree, b"b", b"value");
+    assert!(access.within(&declared));
+
+    // Writing a key that was only declared as readable exceeds the declaration
+    access.write(&tree, b"a", b"value");
+    assert!(!access.within(&declared));
+
+    // Readers of the same key don't conflict, writers do
+    let reader = AccessList { reads: vec![key_a.clone()], writes: vec![] };
+    assert!(!reader.conflicts_with(&declared));
+    let writer = AccessList { reads: vec![], writes: vec![key_a] };
+    assert!(writer.conflicts_with(&declared));
+
+    // Declaring a whole tree overlaps with every key in it
+    let tree_reader = AccessList { reads: vec![whole_tree], writes: vec![] };
+    assert!(tree_reader.conflicts_with(&declared));
+    assert!(!tree_reader.conflicts_with(&reader));
+}
@@ -0,0 +1,114 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::str::FromStr;
+
+use darkfi::{
+    tx::Transaction,
+    validator::policy::{AllowAll, ConflictPolicy, PolicyRules, RulePolicy, TxPolicy},
+};
+use darkfi_sdk::{
+    crypto::{DAO_CONTRACT_ID, MONEY_CONTRACT_ID},
+    tx::ContractCall,
+};
+
+fn tx_with_calls(calls: &[ContractCall]) -> Transaction {
+    Transaction { calls: calls.to_vec(), ..Default::default() }
+}
+
+#[test]
+fn tx_policy_rules() {
+    let money_call = ContractCall { contract_id: *MONEY_CONTRACT_ID, data: vec![0; 64] };
+    let dao_call = ContractCall { contract_id: *DAO_CONTRACT_ID, data: vec![0; 64] };
+
+    let tx = tx_with_calls(&[money_call.clone(), money_call.clone(), dao_call.clone()]);
+    assert!(AllowAll.check(&tx).is_ok());
+    assert!(RulePolicy::new(PolicyRules::default()).check(&tx).is_ok());
+
+    let blocked = RulePolicy::new(PolicyRules {
+        blocked_contracts: vec![*DAO_CONTRACT_ID],
+        ..Default::default()
+    });
+    assert!(blocked.check(&tx).is_err());
+    assert!(blocked.check(&tx_with_calls(&[money_call.clone()])).is_ok());
+
+    let max_calls = RulePolicy::new(PolicyRules { max_calls: Some(2), ..Default::default() });
+    assert!(max_calls.check(&tx).is_err());
+    assert!(max_calls.check(&tx_with_calls(&[money_call.clone(), dao_call])).is_ok());
+
+    let call_limits = RulePolicy::new(PolicyRules {
+        contract_call_limits: vec![(*MONEY_CONTRACT_ID, 1)],
+        ..Default::default()
+    });
+    assert!(call_limits.check(&tx).is_err());
+    assert!(call_limits.check(&tx_with_calls(&[money_call.clone()])).is_ok());
+
+    let max_tx_size = RulePolicy::new(PolicyRules { max_tx_size: Some(100), ..Default::default() });
+    assert!(max_tx_size.check(&tx).is_err());
+    assert!(max_tx_size.check(&Transaction::default()).is_ok());
+}
+
+#[test]
+fn tx_conflict_policy() {
+    assert_eq!(AllowAll.conflict_policy(), ConflictPolicy::FirstSeen);
+    assert_eq!(
+        RulePolicy::new(PolicyRules::default()).conflict_policy(),
+        ConflictPolicy::FirstSeen
+    );
+
+    let replacement = RulePolicy::new(PolicyRules {
+        conflict_policy: ConflictPolicy::FeeReplacement,
+        ..Default::default()
+    });
+    assert_eq!(replacement.conflict_policy(), ConflictPolicy::FeeReplacement);
+
+    assert_eq!(ConflictPolicy::from_str("first-seen").unwrap(), ConflictPolicy::FirstSeen);
+    assert_eq!(
+        ConflictPolicy::from_str("fee-replacement").unwrap(),
+        ConflictPolicy::FeeReplacement
+    );
+    assert!(ConflictPolicy::from_str("replace").is_err());
+}
+
+#[test]
+fn tx_fee_policy() {
+    assert!(AllowAll.check_fee(0, 100).is_ok());
+    assert!(RulePolicy::new(PolicyRules::default()).check_fee(0, 100).is_ok());
+
+    let min_fee = RulePolicy::new(PolicyRules { min_fee: Some(50), ..Default::default() });
+    assert!(min_fee.check_fee(49, 0).is_err());
+    assert!(min_fee.check_fee(50, 0).is_ok());
+
+    let multiplier =
+        RulePolicy::new(PolicyRules { min_fee_multiplier: Some(150), ..Default::default() });
+    assert!(multiplier.check_fee(149, 100).is_err());
+    assert!(multiplier.check_fee(150, 100).is_ok());
+    // The scaled floor is rounded up
+    assert!(multiplier.check_fee(1, 1).is_err());
+    assert!(multiplier.check_fee(2, 1).is_ok());
+
+    let both = RulePolicy::new(PolicyRules {
+        min_fee: Some(200),
+        min_fee_multiplier: Some(150),
+        ..Default::default()
+    });
+    assert!(both.check_fee(199, 100).is_err());
+    assert!(both.check_fee(200, 100).is_ok());
+    assert!(both.check_fee(200, 200).is_err());
+    assert!(both.check_fee(300, 200).is_ok());
+}
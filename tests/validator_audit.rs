@@ -0,0 +1,68 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{
+    validator::audit::{AuditEvent, AuditLog},
+    Result,
+};
+
+#[test]
+fn audit_log_recent_entries() -> Result<()> {
+    let sled_db = sled::Config::new().temporary(true).open()?;
+    let log = AuditLog::new(&sled_db, true)?;
+    assert!(log.is_enabled());
+
+    let events: Vec<_> = (0..5u64)
+        .map(|slot| AuditEvent::BlockAccepted { hash: blake3::hash(&slot.to_le_bytes()), slot })
+        .collect();
+    for event in &events {
+        log.record(event.clone());
+    }
+    log.record(AuditEvent::TxRejected {
+        hash: blake3::hash(b"tx"),
+        reason: "Insufficient fee paid".to_string(),
+    });
+
+    // Newest entries come first
+    let recent = log.recent(3)?;
+    assert_eq!(recent.len(), 3);
+    assert_eq!(recent[0].event.kind(), "tx_rejected");
+    assert_eq!(recent[1].event, events[4]);
+    assert_eq!(recent[2].event, events[3]);
+    assert!(recent[0].id > recent[1].id && recent[1].id > recent[2].id);
+
+    assert_eq!(log.recent(100)?.len(), 6);
+
+    // Entries persist across reopening the log
+    let log = AuditLog::new(&sled_db, true)?;
+    assert_eq!(log.recent(100)?.len(), 6);
+
+    Ok(())
+}
+
+#[test]
+fn audit_log_disabled() -> Result<()> {
+    let sled_db = sled::Config::new().temporary(true).open()?;
+    let log = AuditLog::new(&sled_db, false)?;
+    assert!(!log.is_enabled());
+
+    log.record(AuditEvent::ForkSwitch { from: blake3::hash(b"a"), to: blake3::hash(b"b") });
+    assert!(log.recent(10)?.is_empty());
+
+    Ok(())
+}
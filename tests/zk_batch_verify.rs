@@ -0,0 +1,237 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::collections::HashMap;
+
+use darkfi::{
+    tx::{Transaction, ZkpAccumulator},
+    zk::{
+        proof::{BatchVerifier, ProvingKey, VerifyingKey},
+        vm::ZkCircuit,
+        vm_heap::{empty_witnesses, Witness},
+        Proof,
+    },
+    zkas::ZkBinary,
+    Result,
+};
+use darkfi_sdk::{
+    crypto::{ContractId, PublicKey, SecretKey},
+    tx::ContractCall,
+};
+use halo2_proofs::{arithmetic::Field, circuit::Value, pasta::pallas};
+use rand::rngs::OsRng;
+
+/// Create an `Arith` proof over random inputs, returning it
+/// along with its public inputs.
+fn arith_proof(zkbin: &ZkBinary, pk: &ProvingKey) -> Result<(Proof, Vec<pallas::Base>)> {
+    let a = pallas::Base::random(&mut OsRng);
+    let b = pallas::Base::random(&mut OsRng);
+
+    let witnesses = vec![Witness::Base(Value::known(a)), Witness::Base(Value::known(b))];
+    let public_inputs = vec![a + b, a * b, a - b];
+
+    let circuit = ZkCircuit::new(witnesses, zkbin);
+    let proof = Proof::create(pk, &[circuit], &public_inputs, &mut OsRng)?;
+
+    Ok((proof, public_inputs))
+}
+
+#[test]
+fn zk_batch_verify() -> Result<()> {
+    let bincode = include_bytes!("../proof/arithmetic.zk.bin");
+    let zkbin = ZkBinary::decode(bincode)?;
+
+    let verifier_witnesses = empty_witnesses(&zkbin)?;
+    let circuit = ZkCircuit::new(verifier_witnesses, &zkbin);
+    let pk = ProvingKey::build(zkbin.k, &circuit);
+    let vk = VerifyingKey::build(zkbin.k, &circuit);
+
+    let mut proofs = vec![];
+    for _ in 0..4 {
+        proofs.push(arith_proof(&zkbin, &pk)?);
+    }
+
+    // Every proof is valid on its own, and so is the batch
+    let mut batch = BatchVerifier::new();
+    assert!(batch.is_empty());
+    for (proof, public_inputs) in &proofs {
+        assert!(proof.verify(&vk, public_inputs).is_ok());
+        batch.add(proof, public_inputs);
+    }
+    assert_eq!(batch.len(), proofs.len());
+    assert!(batch.finalize(&vk));
+
+    // Tamper with a single proof's public inputs. Per-proof verification
+    // catches it and the batch must agree.
+    proofs[2].1[0] += pallas::Base::ONE;
+
+    let mut batch = BatchVerifier::new();
+    for (i, (proof, public_inputs)) in proofs.iter().enumerate() {
+        assert_eq!(proof.verify(&vk, public_inputs).is_ok(), i != 2);
+        batch.add(proof, public_inputs);
+    }
+    assert!(!batch.finalize(&vk));
+
+    // An empty batch trivially verifies
+    assert!(BatchVerifier::new().finalize(&vk));
+
+    Ok(())
+}
+
+#[test]
+fn zk_batch_prove() -> Result<()> {
+    let bincode = include_bytes!("../proof/arithmetic.zk.bin");
+    let zkbin = ZkBinary::decode(bincode)?;
+
+    let verifier_witnesses = empty_witnesses(&zkbin)?;
+    let circuit = ZkCircuit::new(verifier_witnesses, &zkbin);
+    let pk = ProvingKey::build(zkbin.k, &circuit);
+    let vk = VerifyingKey::build(zkbin.k, &circuit);
+
+    let mut circuits = vec![];
+    let mut instances = vec![];
+    for _ in 0..3 {
+        let a = pallas::Base::random(&mut OsRng);
+        let b = pallas::Base::random(&mut OsRng);
+        let witnesses = vec![Witness::Base(Value::known(a)), Witness::Base(Value::known(b))];
+        circuits.push(ZkCircuit::new(witnesses, &zkbin));
+        instances.push(vec![a + b, a * b, a - b]);
+    }
+
+    // A single proof covers all circuits, and is smaller than separate ones
+    let proof = Proof::create_batch(&pk, &circuits, &instances, &mut OsRng)?;
+    assert_eq!(proof.as_ref().len(), vk.batch_proof_size(circuits.len()));
+    assert!(proof.as_ref().len() < vk.proof_size() * circuits.len());
+    assert!(proof.verify_batch(&vk, &instances).is_ok());
+
+    // A batch of one is an ordinary proof
+    let single = Proof::create_batch(&pk, &circuits[..1], &instances[..1], &mut OsRng)?;
+    assert!(single.verify(&vk, &instances[0]).is_ok());
+
+    // Public inputs must be given for every circuit, in order
+    assert!(proof.verify_batch(&vk, &instances[..2]).is_err());
+    let mut swapped = instances.clone();
+    swapped.swap(0, 1);
+    assert!(proof.verify_batch(&vk, &swapped).is_err());
+
+    // Mismatched circuits and public inputs are refused
+    assert!(Proof::create_batch(&pk, &circuits, &instances[..2], &mut OsRng).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn zk_aggregated_tx_proofs() -> Result<()> {
+    let bincode = include_bytes!("../proof/arithmetic.zk.bin");
+    let zkbin = ZkBinary::decode(bincode)?;
+
+    let verifier_witnesses = empty_witnesses(&zkbin)?;
+    let circuit = ZkCircuit::new(verifier_witnesses, &zkbin);
+    let pk = ProvingKey::build(zkbin.k, &circuit);
+    let vk = VerifyingKey::build(zkbin.k, &circuit);
+
+    let mut circuits = vec![];
+    let mut instances = vec![];
+    for _ in 0..3 {
+        let a = pallas::Base::random(&mut OsRng);
+        let b = pallas::Base::random(&mut OsRng);
+        let witnesses = vec![Witness::Base(Value::known(a)), Witness::Base(Value::known(b))];
+        circuits.push(ZkCircuit::new(witnesses, &zkbin));
+        instances.push(vec![a + b, a * b, a - b]);
+    }
+    let aggregated = Proof::create_batch(&pk, &circuits, &instances, &mut OsRng)?;
+
+    let contract_id = ContractId::from(pallas::Base::from(42));
+    let vks = HashMap::from([(
+        contract_id.to_bytes(),
+        HashMap::from([(zkbin.namespace.clone(), vk.clone())]),
+    )]);
+    let zkp_table: Vec<_> =
+        instances.iter().map(|i| (zkbin.namespace.clone(), i.clone())).collect();
+
+    // A single proof covers all the call's instances of the circuit
+    let call = ContractCall { contract_id, data: vec![] };
+    let tx = Transaction {
+        calls: vec![call],
+        proofs: vec![vec![aggregated.clone()]],
+        signatures: vec![vec![]],
+        access_list: None,
+    };
+    assert!(smol::block_on(tx.verify_zkps(&vks, vec![zkp_table.clone()])).is_ok());
+
+    let mut accumulator = ZkpAccumulator::new();
+    tx.accumulate_zkps(&vks, vec![zkp_table.clone()], &mut accumulator)?;
+    assert_eq!(accumulator.len(), 1);
+    assert!(accumulator.verify(&vks).is_ok());
+
+    // The aggregated proof batches along with ordinary ones
+    let (single, single_instances) = arith_proof(&zkbin, &pk)?;
+    let mut batch = BatchVerifier::new();
+    batch.add_batch(&aggregated, &instances);
+    batch.add(&single, &single_instances);
+    assert!(batch.finalize(&vk));
+
+    // It must cover exactly the instances it was created for
+    let mut tampered = zkp_table.clone();
+    tampered[1].1[0] += pallas::Base::ONE;
+    assert!(smol::block_on(tx.verify_zkps(&vks, vec![tampered.clone()])).is_err());
+    let mut accumulator = ZkpAccumulator::new();
+    tx.accumulate_zkps(&vks, vec![tampered], &mut accumulator)?;
+    assert!(accumulator.verify(&vks).is_err());
+
+    let mut accumulator = ZkpAccumulator::new();
+    let extra = [zkp_table.clone(), vec![(zkbin.namespace.clone(), single_instances)]].concat();
+    assert!(tx.accumulate_zkps(&vks, vec![extra.clone()], &mut accumulator).is_err());
+    assert!(smol::block_on(tx.verify_zkps(&vks, vec![extra])).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn zk_accumulator_split_signatures() -> Result<()> {
+    let secrets: Vec<SecretKey> = (0..5).map(|_| SecretKey::random(&mut OsRng)).collect();
+    let pubkeys: Vec<PublicKey> = secrets.iter().map(|s| PublicKey::from_secret(*s)).collect();
+
+    let mut tx =
+        Transaction { calls: vec![], proofs: vec![], signatures: vec![], access_list: None };
+    tx.signatures = vec![tx.create_sigs(&mut OsRng, &secrets)?];
+
+    // Signatures verify when accumulated, however the accumulator is split
+    let vks = HashMap::new();
+    for parts in [1, 2, 8] {
+        let mut accumulator = ZkpAccumulator::new();
+        tx.accumulate_sigs(vec![pubkeys.clone()], &mut accumulator)?;
+        assert!(!accumulator.is_empty());
+
+        let split = accumulator.split(parts);
+        assert_eq!(split.len(), parts.min(secrets.len()));
+        for part in split {
+            assert!(part.verify(&vks).is_ok());
+        }
+    }
+
+    // Swapping two public keys must make exactly the affected parts fail
+    let mut swapped = pubkeys.clone();
+    swapped.swap(0, 1);
+    let mut accumulator = ZkpAccumulator::new();
+    tx.accumulate_sigs(vec![swapped], &mut accumulator)?;
+    let failed = accumulator.split(5).into_iter().filter(|p| p.verify(&vks).is_err()).count();
+    assert_eq!(failed, 2);
+
+    Ok(())
+}
@@ -0,0 +1,73 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{
+    zk::{CircuitTester, Witness},
+    zkas::{Opcode, ZkBinary},
+    Result,
+};
+use halo2_proofs::{arithmetic::Field, circuit::Value, pasta::pallas};
+use rand::rngs::OsRng;
+
+/// Witnesses and public inputs of the `Arith` circuit for random values
+fn arith_inputs() -> (Vec<Witness>, Vec<pallas::Base>) {
+    let a = pallas::Base::random(&mut OsRng);
+    let b = pallas::Base::random(&mut OsRng);
+    let witnesses = vec![Witness::Base(Value::known(a)), Witness::Base(Value::known(b))];
+    (witnesses, vec![a + b, a * b, a - b])
+}
+
+#[test]
+fn zk_circuit_tester() -> Result<()> {
+    let source = include_str!("../proof/arithmetic.zk");
+    let tester = CircuitTester::from_source("arithmetic.zk", source)?;
+    assert_eq!(tester.zkbin().namespace, "Arith");
+
+    let (witnesses, public_inputs) = arith_inputs();
+    assert!(tester.run(witnesses.clone(), &public_inputs)?.is_empty());
+    tester.assert_satisfied(witnesses.clone(), &public_inputs);
+
+    // A wrong product is traced back to the statement computing it
+    let mut wrong = public_inputs.clone();
+    wrong[1] += pallas::Base::ONE;
+    let failures = tester.run(witnesses.clone(), &wrong)?;
+    assert!(!failures.is_empty());
+    let product_line = source.lines().position(|l| l.contains("base_mul")).unwrap() + 1;
+    assert!(failures.iter().any(|f| f.line == Some(product_line) && f.op == Some(Opcode::BaseMul)));
+    assert!(failures.iter().all(|f| f.line.is_none() || f.line == Some(product_line)));
+
+    // Without the source, failures still point at the opcode
+    let bincode = include_bytes!("../proof/arithmetic.zk.bin");
+    let tester = CircuitTester::new(ZkBinary::decode(bincode)?);
+    let failures = tester.run(witnesses, &wrong)?;
+    assert!(failures.iter().all(|f| f.line.is_none()));
+    assert!(failures.iter().any(|f| f.opcode == Some(2) && f.op == Some(Opcode::BaseMul)));
+
+    Ok(())
+}
+
+#[test]
+#[should_panic(expected = "Arith circuit is not satisfied")]
+fn zk_circuit_tester_panics() {
+    let bincode = include_bytes!("../proof/arithmetic.zk.bin");
+    let tester = CircuitTester::new(ZkBinary::decode(bincode).unwrap());
+
+    let (witnesses, mut public_inputs) = arith_inputs();
+    public_inputs[0] = pallas::Base::ZERO;
+    tester.assert_satisfied(witnesses, &public_inputs);
+}
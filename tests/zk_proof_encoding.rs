@@ -0,0 +1,106 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{
+    tx::Transaction,
+    zk::{
+        proof::{BatchVerifier, ProvingKey, VerifyingKey},
+        vm::ZkCircuit,
+        vm_heap::{empty_witnesses, Witness},
+        Proof,
+    },
+    zkas::ZkBinary,
+    Result,
+};
+use darkfi_sdk::{crypto::MONEY_CONTRACT_ID, tx::ContractCall};
+use darkfi_serial::{deserialize, serialize};
+use halo2_proofs::{arithmetic::Field, circuit::Value, pasta::pallas};
+use rand::rngs::OsRng;
+
+#[test]
+fn zk_proof_encoding() -> Result<()> {
+    let bincode = include_bytes!("../proof/arithmetic.zk.bin");
+    let zkbin = ZkBinary::decode(bincode)?;
+
+    let verifier_witnesses = empty_witnesses(&zkbin)?;
+    let circuit = ZkCircuit::new(verifier_witnesses, &zkbin);
+    let pk = ProvingKey::build(zkbin.k, &circuit);
+    let vk = VerifyingKey::build(zkbin.k, &circuit);
+
+    let a = pallas::Base::random(&mut OsRng);
+    let b = pallas::Base::random(&mut OsRng);
+    let witnesses = vec![Witness::Base(Value::known(a)), Witness::Base(Value::known(b))];
+    let public_inputs = vec![a + b, a * b, a - b];
+    let circuit = ZkCircuit::new(witnesses, &zkbin);
+    let proof = Proof::create(&pk, &[circuit], &public_inputs, &mut OsRng)?;
+
+    // Valid proofs have exactly the size the verifying key expects
+    assert_eq!(proof.as_ref().len(), vk.proof_size());
+    assert!(proof.is_well_formed(&vk));
+    assert!(proof.verify(&vk, &public_inputs).is_ok());
+
+    // Serialization round-trips to the very same bytes
+    let encoded = serialize(&proof);
+    let decoded: Proof = deserialize(&encoded)?;
+    assert_eq!(decoded, proof);
+    assert_eq!(serialize(&decoded), encoded);
+
+    // Trailing data after an encoded proof is rejected
+    let mut trailing = encoded.clone();
+    trailing.push(0);
+    assert!(deserialize::<Proof>(&trailing).is_err());
+
+    // Proofs carrying extra bytes, or missing some, are rejected both
+    // on their own and when batched, even though the verifier would
+    // otherwise only read the bytes it needs.
+    let mut extended = proof.as_ref().to_vec();
+    extended.push(0);
+    let extended = Proof::new(extended);
+    let truncated = Proof::new(proof.as_ref()[..vk.proof_size() - 1].to_vec());
+
+    for bad in [&extended, &truncated, &Proof::new(vec![])] {
+        assert!(!bad.is_well_formed(&vk));
+        assert!(bad.verify(&vk, &public_inputs).is_err());
+
+        let mut batch = BatchVerifier::new();
+        batch.add(&proof, &public_inputs);
+        batch.add(bad, &public_inputs);
+        assert!(!batch.finalize(&vk));
+    }
+
+    // A transaction carrying the proof hashes deterministically across
+    // encoding round-trips, and can't be decoded with trailing data.
+    let call = ContractCall { contract_id: *MONEY_CONTRACT_ID, data: vec![0] };
+    let tx = Transaction {
+        calls: vec![call],
+        proofs: vec![vec![proof]],
+        signatures: vec![vec![]],
+        access_list: None,
+    };
+
+    let encoded = serialize(&tx);
+    let decoded: Transaction = deserialize(&encoded)?;
+    assert_eq!(decoded.hash(), tx.hash());
+    assert_eq!(serialize(&decoded), encoded);
+
+    let mut trailing = encoded;
+    trailing.push(0);
+    assert!(deserialize::<Transaction>(&trailing).is_err());
+
+    Ok(())
+}
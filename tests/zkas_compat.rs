@@ -0,0 +1,71 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2023 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{
+    zkas::{
+        compat::{check_compatibility, Incompatibility},
+        types::HeapType,
+        Opcode, VarType, ZkBinary,
+    },
+    Result,
+};
+
+#[test]
+fn zkas_compat() -> Result<()> {
+    let bincode = include_bytes!("../proof/opcodes.zk.bin");
+    let deployed = ZkBinary::decode(bincode)?;
+
+    // A recompilation of the same circuit is compatible
+    let local = ZkBinary::decode(bincode)?;
+    assert!(check_compatibility(&deployed, &local).is_empty());
+
+    let mut local = deployed.clone();
+    local.k += 1;
+    local.witnesses.push(VarType::Base);
+    assert_eq!(
+        check_compatibility(&deployed, &local),
+        vec![
+            Incompatibility::K(deployed.k, deployed.k + 1),
+            Incompatibility::Witnesses(deployed.witnesses.len()),
+        ]
+    );
+
+    // Dropping the last public input changes both the opcode stream
+    // and the public input layout
+    let mut local = deployed.clone();
+    let idx = local.opcodes.iter().rposition(|(op, _)| *op == Opcode::ConstrainInstance).unwrap();
+    local.opcodes.remove(idx);
+    let n_inputs =
+        deployed.opcodes.iter().filter(|(op, _)| *op == Opcode::ConstrainInstance).count();
+    assert_eq!(
+        check_compatibility(&deployed, &local),
+        vec![
+            Incompatibility::Opcodes(idx),
+            Incompatibility::PublicInputCount(n_inputs, n_inputs - 1),
+        ]
+    );
+
+    // Constraining a public input to another value
+    let mut local = deployed.clone();
+    let idx = local.opcodes.iter().position(|(op, _)| *op == Opcode::ConstrainInstance).unwrap();
+    local.opcodes[idx].1 = vec![(HeapType::Var, 0)];
+    let incompatibilities = check_compatibility(&deployed, &local);
+    assert!(incompatibilities.contains(&Incompatibility::PublicInput(0)));
+
+    Ok(())
+}